@@ -0,0 +1,299 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[path = "src/glsl.rs"]
+mod glsl;
+
+use glsl::{strip_comments, ShaderStage};
+
+// Every shader the app embeds with `include_str!` lives under here
+const SHADER_DIR: &str = "shaders";
+
+/**
+ * Checks the GLSL sources, so mistakes in a shader file fail the build with
+ * the file and line instead of showing up as a console error when the
+ * program is linked: first the ES-specific rules naga doesn't know about
+ * (the version line, a fragment shader's default precision), then a full
+ * parse and validation by naga of every permutation of the file's defines.
+ * Then embeds them all in the generated module `src/shaders.rs` includes
+ */
+fn main() {
+    println!("cargo:rerun-if-changed={}", SHADER_DIR);
+    println!("cargo:rerun-if-changed=src/glsl.rs");
+
+    let mut files = Vec::new();
+    collect(Path::new(SHADER_DIR), &mut files);
+    files.sort();
+
+    let mut errors = Vec::new();
+    let mut sources = Vec::new();
+    for path in &files {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(err) => {
+                errors.push(format!("{}: {}", path.display(), err));
+                continue;
+            }
+        };
+        let mut problems = check(path, &source);
+        if problems.is_empty() {
+            problems = validate(path, &source);
+        }
+        errors.extend(
+            problems
+                .into_iter()
+                .map(|err| format!("{}:{}", path.display(), err)),
+        );
+        sources.push((path, source));
+    }
+    if !errors.is_empty() {
+        for err in &errors {
+            eprintln!("error: {}", err);
+        }
+        eprintln!("{} shader error(s)", errors.len());
+        std::process::exit(1);
+    }
+
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR is set for build scripts");
+    let module = generate(&sources);
+    fs::write(Path::new(&out_dir).join("shaders.rs"), module).expect("Unable to write shaders.rs");
+}
+
+/**
+ * A constant per file, named after its path (`shaders/oit/resolve.frag.glsl`
+ * becomes `OIT_RESOLVE_FRAG`), and a `ShaderProgram` for each vertex and
+ * fragment pair sharing a name, with the defines either stage tests for
+ */
+fn generate(sources: &[(&PathBuf, String)]) -> String {
+    let mut module = String::from("// Generated by build.rs from the files in shaders/\n\n");
+    // Program name to (vertex constant, fragment constant, defines)
+    let mut programs = BTreeMap::<String, (Option<String>, Option<String>, Vec<String>)>::new();
+    for (path, source) in sources {
+        let relative = path.strip_prefix(SHADER_DIR).unwrap_or(path);
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        let stem = relative.trim_end_matches(".glsl");
+        let constant = stem.replace(['/', '.'], "_").to_uppercase();
+        module.push_str(&format!(
+            "pub const {}: &str = include_str!(concat!(env!(\"CARGO_MANIFEST_DIR\"), \"/{}/{}\"));\n",
+            constant, SHADER_DIR, relative
+        ));
+
+        let (name, stage) = stem.rsplit_once('.').unwrap_or((stem, ""));
+        let program = programs.entry(name.to_string()).or_default();
+        match stage {
+            "vert" => program.0 = Some(constant),
+            _ => program.1 = Some(constant),
+        }
+        for define in tested_defines(source) {
+            if !program.2.contains(&define) {
+                program.2.push(define);
+            }
+        }
+    }
+
+    module.push_str("\npub const PROGRAMS: &[ShaderProgram] = &[\n");
+    for (name, (vertex, fragment, defines)) in &programs {
+        let (Some(vertex), Some(fragment)) = (vertex, fragment) else {
+            continue;
+        };
+        let defines: Vec<String> = defines
+            .iter()
+            .map(|define| format!("{:?}", define))
+            .collect();
+        module.push_str(&format!(
+            "    ShaderProgram {{ name: {:?}, vertex: {}, fragment: {}, defines: &[{}] }},\n",
+            name,
+            vertex,
+            fragment,
+            defines.join(", ")
+        ));
+    }
+    module.push_str("];\n");
+    module
+}
+
+/**
+ * Names the source tests with `#ifdef`, `#ifndef` or `#if defined(...)`, in
+ * order of first use
+ */
+fn tested_defines(source: &str) -> Vec<String> {
+    let mut defines = Vec::new();
+    for line in strip_comments(source).lines() {
+        let Some(directive) = line.trim().strip_prefix('#') else {
+            continue;
+        };
+        let directive = directive.trim_start();
+        let names: Vec<&str> = if let Some(name) = directive
+            .strip_prefix("ifdef")
+            .or_else(|| directive.strip_prefix("ifndef"))
+        {
+            vec![name.trim()]
+        } else if directive.starts_with("if") || directive.starts_with("elif") {
+            directive
+                .split("defined")
+                .skip(1)
+                .filter_map(|rest| {
+                    let rest = rest.trim_start().trim_start_matches('(').trim_start();
+                    rest.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                        .next()
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        for name in names {
+            if !name.is_empty() && !defines.iter().any(|define| define == name) {
+                defines.push(name.to_string());
+            }
+        }
+    }
+    defines
+}
+
+fn collect(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "glsl") {
+            files.push(path);
+        }
+    }
+}
+
+fn stage(path: &Path) -> Option<ShaderStage> {
+    let name = path.file_name()?.to_str()?;
+    if name.ends_with(".vert.glsl") {
+        Some(ShaderStage::Vertex)
+    } else if name.ends_with(".frag.glsl") {
+        Some(ShaderStage::Fragment)
+    } else {
+        None
+    }
+}
+
+/**
+ * Problems in one shader that naga would miss or report less clearly, each
+ * as "line: message"
+ */
+fn check(path: &Path, source: &str) -> Vec<String> {
+    let mut errors = Vec::new();
+    let Some(stage) = stage(path) else {
+        errors.push("1: shader files must end in .vert.glsl or .frag.glsl".to_string());
+        return errors;
+    };
+
+    if source.lines().next().map(str::trim) != Some("#version 300 es") {
+        errors.push("1: the first line must be `#version 300 es`".to_string());
+    }
+
+    let code = strip_comments(source);
+    // Preprocessor conditionals still open: line each one started on
+    let mut conditionals = Vec::new();
+    // Brackets still open: (bracket, line)
+    let mut brackets = Vec::<(char, usize)>::new();
+    let mut has_main = false;
+    let mut has_precision = false;
+    for (index, line) in code.lines().enumerate() {
+        let number = index + 1;
+        let trimmed = line.trim();
+        if let Some(directive) = trimmed.strip_prefix('#') {
+            let keyword = directive.split_whitespace().next().unwrap_or("");
+            match keyword {
+                "if" | "ifdef" | "ifndef" => conditionals.push(number),
+                "else" | "elif" if conditionals.is_empty() => {
+                    errors.push(format!("{}: #{} without #if", number, keyword));
+                }
+                "endif" => {
+                    let closed = conditionals.pop();
+                    if closed.is_none() {
+                        errors.push(format!("{}: #endif without #if", number));
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+        has_main |= trimmed.starts_with("void main(");
+        has_precision |= trimmed.starts_with("precision ");
+        for c in line.chars() {
+            match c {
+                '(' | '[' | '{' => brackets.push((c, number)),
+                ')' | ']' | '}' => {
+                    let opening = match c {
+                        ')' => '(',
+                        ']' => '[',
+                        _ => '{',
+                    };
+                    match brackets.pop() {
+                        Some((open, _)) if open == opening => {}
+                        Some((open, line)) => errors.push(format!(
+                            "{}: `{}` closes the `{}` from line {}",
+                            number, c, open, line
+                        )),
+                        None => errors.push(format!("{}: unmatched `{}`", number, c)),
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    for line in conditionals {
+        errors.push(format!("{}: #if without #endif", line));
+    }
+    for (open, line) in brackets {
+        errors.push(format!("{}: unclosed `{}`", line, open));
+    }
+    if !has_main {
+        errors.push("1: no `void main()`".to_string());
+    }
+    if stage == ShaderStage::Fragment && !has_precision {
+        errors.push("1: fragment shaders need a default float precision".to_string());
+    }
+    errors
+}
+
+/**
+ * naga's errors for the file by itself with each combination of the defines
+ * it tests, each as "line: message" and naming the defines it needs
+ */
+fn validate(path: &Path, source: &str) -> Vec<String> {
+    let Some(stage) = stage(path) else {
+        return Vec::new();
+    };
+    let interface = glsl::Interface::new(&[(stage, source)]);
+    let defines = tested_defines(source);
+    // Each problem with the first set of defines it showed up with
+    let mut errors = Vec::<(String, Vec<&str>)>::new();
+    for mask in 0..1usize << defines.len() {
+        let set: Vec<&str> = defines
+            .iter()
+            .enumerate()
+            .filter(|(bit, _)| mask & (1 << bit) != 0)
+            .map(|(_, define)| define.as_str())
+            .collect();
+        let Err(problems) = glsl::parse(source, stage, &interface, &set) else {
+            continue;
+        };
+        for problem in problems {
+            if !errors.iter().any(|(known, _)| *known == problem) {
+                errors.push((problem, set.clone()));
+            }
+        }
+    }
+    errors
+        .into_iter()
+        .map(|(problem, set)| {
+            if set.is_empty() {
+                problem
+            } else {
+                format!("{} (with {})", problem, set.join(", "))
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,11 @@
+#version 300 es
+precision mediump float;
+in vec3 vColor;
+uniform bool roundPoints;
+out vec4 fragColor;
+void main() {
+    if (roundPoints && length(gl_PointCoord - 0.5) > 0.5) {
+        discard;
+    }
+    fragColor = vec4(vColor, 1.0);
+}
@@ -0,0 +1,11 @@
+#version 300 es
+// Flat shading from screen-space derivatives, so the cube needs no normals
+precision mediump float;
+in vec3 vWorld;
+in vec3 vColor;
+out vec4 fragColor;
+void main() {
+    vec3 normal = normalize(cross(dFdx(vWorld), dFdy(vWorld)));
+    float light = 0.45 + 0.55 * abs(dot(normal, normalize(vec3(0.4, 0.8, 0.5))));
+    fragColor = vec4(vColor * light, 1.0);
+}
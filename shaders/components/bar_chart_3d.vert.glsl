@@ -0,0 +1,15 @@
+#version 300 es
+// Unit cube stretched per instance into a bar standing on y = 0
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec2 bar; // x offset, height
+layout(location = 2) in vec3 color;
+uniform mat4 viewProjection;
+uniform float barWidth;
+out vec3 vWorld;
+out vec3 vColor;
+void main() {
+    vec3 p = vec3(position.x * barWidth + bar.x, (position.y + 0.5) * bar.y, position.z * barWidth);
+    vWorld = p;
+    vColor = color;
+    gl_Position = viewProjection * vec4(p, 1.0);
+}
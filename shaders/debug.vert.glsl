@@ -0,0 +1,10 @@
+#version 300 es
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec3 color;
+uniform mat4 viewProjection;
+out vec3 vColor;
+void main() {
+    gl_Position = viewProjection * vec4(position, 1.0);
+    gl_PointSize = 6.0;
+    vColor = color;
+}
@@ -0,0 +1,11 @@
+#version 300 es
+precision mediump float;
+in vec3 vNormal;
+in vec2 vUv;
+uniform vec3 lightDirection;
+uniform sampler2D checker;
+out vec4 fragColor;
+void main() {
+    float light = 0.3 + 0.7 * max(dot(normalize(vNormal), lightDirection), 0.0);
+    fragColor = vec4(texture(checker, vUv).rgb * light, 1.0);
+}
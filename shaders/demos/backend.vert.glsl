@@ -0,0 +1,14 @@
+#version 300 es
+// Drawn through src/backend, so it also has to translate to WGSL
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec3 normal;
+layout(location = 2) in vec2 uv;
+uniform mat4 modelViewProjection;
+uniform mat4 model;
+out vec3 vNormal;
+out vec2 vUv;
+void main() {
+    gl_Position = modelViewProjection * vec4(position, 1.0);
+    vNormal = (model * vec4(normal, 0.0)).xyz;
+    vUv = uv;
+}
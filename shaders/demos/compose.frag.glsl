@@ -0,0 +1,11 @@
+#version 300 es
+// Flat shading from screen-space derivatives, so the cube needs no normals
+precision mediump float;
+in vec3 vWorld;
+uniform vec3 color;
+out vec4 fragColor;
+void main() {
+    vec3 normal = normalize(cross(dFdx(vWorld), dFdy(vWorld)));
+    float light = 0.4 + 0.6 * max(dot(normal, normalize(vec3(0.4, 0.8, 0.5))), 0.0);
+    fragColor = vec4(color * light, 1.0);
+}
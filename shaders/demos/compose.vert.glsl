@@ -0,0 +1,9 @@
+#version 300 es
+layout(location = 0) in vec3 position;
+uniform mat4 modelViewProjection;
+uniform mat4 model;
+out vec3 vWorld;
+void main() {
+    vWorld = (model * vec4(position, 1.0)).xyz;
+    gl_Position = modelViewProjection * vec4(position, 1.0);
+}
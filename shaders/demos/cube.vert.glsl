@@ -0,0 +1,9 @@
+#version 300 es
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec3 color;
+uniform mat4 modelViewMatrix;
+out vec3 vColor;
+void main() {
+    gl_Position = modelViewMatrix * vec4(position, 1.0);
+    vColor = color;
+}
@@ -0,0 +1,13 @@
+#version 300 es
+precision mediump float;
+in vec2 vUv;
+in vec3 vClip;
+uniform sampler2D faceTexture;
+out vec4 fragColor;
+void main() {
+    // Faces turned away from the viewer are shaded darker (an orthographic
+    // projection, so clip space is only scaled view space)
+    vec3 normal = normalize(cross(dFdx(vClip), dFdy(vClip)));
+    float light = 0.55 + 0.45 * abs(normal.z);
+    fragColor = vec4(texture(faceTexture, vUv).rgb * light, 1.0);
+}
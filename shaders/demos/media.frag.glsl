@@ -0,0 +1,8 @@
+#version 300 es
+precision mediump float;
+in vec2 vUv;
+uniform sampler2D image;
+out vec4 fragColor;
+void main() {
+    fragColor = vec4(texture(image, vUv).rgb, 1.0);
+}
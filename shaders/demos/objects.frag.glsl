@@ -0,0 +1,17 @@
+#version 300 es
+precision mediump float;
+in vec3 vColor;
+in vec3 vWorld;
+uniform vec4 tint;
+uniform vec3 lightDirection;
+// 1 for the selected object, which is drawn lighter
+uniform float highlight;
+out vec4 fragColor;
+void main() {
+    // Flat shading from the world-space face normal
+    vec3 normal = normalize(cross(dFdx(vWorld), dFdy(vWorld)));
+    float light = 0.35 + 0.65 * max(dot(normal, lightDirection), 0.0);
+    // Mostly the tint, with a hint of the vertex colors so faces stay distinguishable
+    vec3 color = tint.rgb * mix(vec3(1.0), vColor, 0.25) * light;
+    fragColor = vec4(mix(color, vec3(1.0), highlight * 0.35), tint.a);
+}
@@ -0,0 +1,13 @@
+#version 300 es
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec3 color;
+uniform mat4 model;
+uniform mat4 viewProjection;
+out vec3 vColor;
+out vec3 vWorld;
+void main() {
+    vec4 world = model * vec4(position, 1.0);
+    gl_Position = viewProjection * world;
+    vColor = color;
+    vWorld = world.xyz;
+}
@@ -0,0 +1,25 @@
+#version 300 es
+precision mediump float;
+in vec3 vColor;
+in float vFade;
+flat in float vFrame;
+uniform vec4 flipbook;
+uniform bool useFlipbook;
+uniform sampler2D sheet;
+out vec4 fragColor;
+void main() {
+    if (useFlipbook) {
+        // gl_PointCoord starts at the top-left, like the sheet's rows
+        vec2 cell = vec2(mod(vFrame, flipbook.x), floor(vFrame / flipbook.x));
+        vec4 texel = texture(sheet, (cell + gl_PointCoord) / flipbook.xy);
+        fragColor = vec4(vColor * texel.rgb * texel.a * vFade * 0.5, 1.0);
+        return;
+    }
+    // Round, soft-edged points
+    vec2 c = gl_PointCoord * 2.0 - 1.0;
+    float falloff = 1.0 - dot(c, c);
+    if (falloff <= 0.0) {
+        discard;
+    }
+    fragColor = vec4(vColor * falloff * vFade, 1.0);
+}
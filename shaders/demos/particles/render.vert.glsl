@@ -0,0 +1,18 @@
+#version 300 es
+layout(location = 0) in vec4 position;
+layout(location = 1) in vec4 velocity;
+uniform mat4 viewProjection;
+uniform vec4 flipbook; // columns, rows, frame count, frames per second
+uniform bool useFlipbook;
+out vec3 vColor;
+out float vFade;
+flat out float vFrame;
+void main() {
+    gl_Position = viewProjection * vec4(position.xyz, 1.0);
+    float size = useFlipbook ? 24.0 : 6.0;
+    gl_PointSize = position.w < 0.0 ? 0.0 : clamp(size / gl_Position.w, 1.0, size + 2.0);
+    vFrame = min(floor(max(position.w, 0.0) * flipbook.w), flipbook.z - 1.0);
+    float speed = length(velocity.xyz);
+    vColor = mix(vec3(0.2, 0.4, 1.0), vec3(1.0, 0.6, 0.2), clamp(speed / 3.5, 0.0, 1.0));
+    vFade = clamp(1.0 - position.w / velocity.w, 0.0, 1.0);
+}
@@ -0,0 +1,7 @@
+#version 300 es
+// Transform feedback needs a fragment stage even though rasterization is discarded
+precision mediump float;
+out vec4 fragColor;
+void main() {
+    fragColor = vec4(0.0);
+}
@@ -0,0 +1,48 @@
+#version 300 es
+// Simulation shader: integrates one particle and writes it back through transform feedback
+layout(location = 0) in vec4 position;
+layout(location = 1) in vec4 velocity;
+uniform float deltaTime;
+uniform float time;
+out vec4 outPosition;
+out vec4 outVelocity;
+
+float hash(float n) {
+    return fract(sin(n) * 43758.5453123);
+}
+
+void main() {
+    vec3 p = position.xyz;
+    vec3 v = velocity.xyz;
+    float age = position.w + deltaTime;
+    float lifespan = velocity.w;
+
+    // Negative age means the particle has not been emitted yet
+    if (age < 0.0) {
+        outPosition = vec4(p, age);
+        outVelocity = velocity;
+        return;
+    }
+
+    if (position.w < 0.0 || age >= lifespan) {
+        // Respawn at the fountain head with a fresh random velocity
+        float seed = float(gl_VertexID) * 0.001 + time;
+        float angle = hash(seed) * 6.2831853;
+        float spread = 0.3 + hash(seed + 1.7) * 0.5;
+        p = vec3(0.0, -0.5, 0.0);
+        v = vec3(cos(angle) * spread, 2.5 + hash(seed + 3.1) * 1.0, sin(angle) * spread);
+        age = 0.0;
+    } else {
+        v += vec3(0.0, -3.0, 0.0) * deltaTime;
+        p += v * deltaTime;
+        // Bounce off the floor with damping
+        if (p.y < -1.0) {
+            p.y = -1.0;
+            v.y = -v.y * 0.5;
+            v.xz *= 0.8;
+        }
+    }
+
+    outPosition = vec4(p, age);
+    outVelocity = vec4(v, lifespan);
+}
@@ -0,0 +1,23 @@
+#version 300 es
+precision highp float;
+in vec3 vColor;
+in vec3 vWorld;
+in vec4 vClip;
+in vec4 vPreviousClip;
+uniform vec3 tint;
+uniform vec3 lightDirection;
+// Light given off in the tint's color, on top of the lit surface
+uniform float emission;
+layout(location = 0) out vec4 fragColor;
+// Screen-space motion since the last frame, in texture coordinates
+layout(location = 1) out vec4 velocity;
+void main() {
+    // Flat shading from the world-space face normal
+    vec3 normal = normalize(cross(dFdx(vWorld), dFdy(vWorld)));
+    float light = 0.35 + 0.65 * max(dot(normal, lightDirection), 0.0);
+    fragColor = vec4(vColor * tint * light + tint * emission, 1.0);
+    // Divided per pixel; interpolating after the divide would be wrong under perspective
+    vec2 current = vClip.xy / vClip.w;
+    vec2 previous = vPreviousClip.xy / vPreviousClip.w;
+    velocity = vec4((current - previous) * 0.5, 0.0, 0.0);
+}
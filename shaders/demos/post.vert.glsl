@@ -0,0 +1,22 @@
+#version 300 es
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec3 color;
+uniform mat4 modelViewProjection;
+// Where the object and camera were last frame
+uniform mat4 previousModelViewProjection;
+uniform mat4 model;
+// Subpixel offset for temporal antialiasing, in clip space
+uniform vec2 jitter;
+out vec3 vColor;
+out vec3 vWorld;
+out vec4 vClip;
+out vec4 vPreviousClip;
+void main() {
+    vec4 clip = modelViewProjection * vec4(position, 1.0);
+    gl_Position = clip + vec4(jitter * clip.w, 0.0, 0.0);
+    vColor = color;
+    vWorld = (model * vec4(position, 1.0)).xyz;
+    // Unjittered, so velocities only hold real motion
+    vClip = clip;
+    vPreviousClip = previousModelViewProjection * vec4(position, 1.0);
+}
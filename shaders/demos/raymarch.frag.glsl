@@ -0,0 +1,100 @@
+#version 300 es
+// Fragment shader: sphere-traced SDF scene
+precision highp float;
+in vec2 vNdc;
+uniform mat4 inverseViewProjection;
+uniform vec3 cameraPosition;
+uniform float time;
+out vec4 fragColor;
+
+const int MAX_STEPS = 128;
+const float MAX_DIST = 50.0;
+const float EPSILON = 0.001;
+
+float sdSphere(vec3 p, float r) {
+    return length(p) - r;
+}
+
+float sdBox(vec3 p, vec3 b) {
+    vec3 q = abs(p) - b;
+    return length(max(q, 0.0)) + min(max(q.x, max(q.y, q.z)), 0.0);
+}
+
+float smoothMin(float a, float b, float k) {
+    float h = clamp(0.5 + 0.5 * (b - a) / k, 0.0, 1.0);
+    return mix(b, a, h) - k * h * (1.0 - h);
+}
+
+// Returns distance in x and material id in y
+vec2 map(vec3 p) {
+    float c = cos(time * 0.5);
+    float s = sin(time * 0.5);
+    vec3 q = vec3(c * p.x + s * p.z, p.y, -s * p.x + c * p.z);
+    float box = sdBox(q, vec3(0.35)) - 0.05;
+    float sphere = sdSphere(p - vec3(sin(time) * 0.7, 0.15, 0.0), 0.35);
+    float blob = smoothMin(box, sphere, 0.25);
+    float ground = p.y + 0.6;
+    return blob < ground ? vec2(blob, 1.0) : vec2(ground, 2.0);
+}
+
+vec3 calcNormal(vec3 p) {
+    vec2 e = vec2(EPSILON, 0.0);
+    return normalize(vec3(
+        map(p + e.xyy).x - map(p - e.xyy).x,
+        map(p + e.yxy).x - map(p - e.yxy).x,
+        map(p + e.yyx).x - map(p - e.yyx).x
+    ));
+}
+
+float softShadow(vec3 ro, vec3 rd) {
+    float res = 1.0;
+    float t = 0.02;
+    for (int i = 0; i < 48 && t < 10.0; i++) {
+        float h = map(ro + rd * t).x;
+        if (h < EPSILON) {
+            return 0.0;
+        }
+        res = min(res, 8.0 * h / t);
+        t += h;
+    }
+    return clamp(res, 0.0, 1.0);
+}
+
+void main() {
+    // Unproject the pixel to a world-space ray through the orbit camera
+    vec4 nearPoint = inverseViewProjection * vec4(vNdc, -1.0, 1.0);
+    vec4 farPoint = inverseViewProjection * vec4(vNdc, 1.0, 1.0);
+    vec3 rd = normalize(farPoint.xyz / farPoint.w - nearPoint.xyz / nearPoint.w);
+    vec3 ro = cameraPosition;
+
+    vec3 background = vec3(0.1, 0.1, 0.1);
+    float t = 0.0;
+    vec2 hit = vec2(-1.0);
+    for (int i = 0; i < MAX_STEPS; i++) {
+        vec2 d = map(ro + rd * t);
+        if (d.x < EPSILON) {
+            hit = vec2(t, d.y);
+            break;
+        }
+        t += d.x;
+        if (t > MAX_DIST) {
+            break;
+        }
+    }
+
+    vec3 color = background;
+    if (hit.x > 0.0) {
+        vec3 p = ro + rd * hit.x;
+        vec3 n = calcNormal(p);
+        vec3 lightDir = normalize(vec3(0.6, 0.8, 0.4));
+        vec3 albedo = hit.y < 1.5
+            ? 0.5 + 0.5 * cos(vec3(0.0, 2.0, 4.0) + p.y * 3.0 + time)
+            : vec3(0.35 + 0.15 * mod(floor(p.x * 2.0) + floor(p.z * 2.0), 2.0));
+        float diffuse = max(dot(n, lightDir), 0.0) * softShadow(p + n * 0.01, lightDir);
+        float ambient = 0.15 + 0.1 * n.y;
+        color = albedo * (diffuse + ambient);
+        color = mix(color, background, 1.0 - exp(-0.02 * hit.x * hit.x));
+    }
+
+    fragColor = vec4(pow(color, vec3(1.0 / 2.2)), 1.0);
+}
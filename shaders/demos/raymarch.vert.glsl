@@ -0,0 +1,8 @@
+#version 300 es
+// Vertex shader: one oversized triangle covering the viewport, generated from gl_VertexID
+out vec2 vNdc;
+void main() {
+    vec2 pos = vec2(float((gl_VertexID << 1) & 2), float(gl_VertexID & 2)) * 2.0 - 1.0;
+    vNdc = pos;
+    gl_Position = vec4(pos, 0.0, 1.0);
+}
@@ -0,0 +1,9 @@
+#version 300 es
+precision mediump float;
+in vec3 vWorld;
+uniform vec4 baseColor;
+out vec4 fragColor;
+void main() {
+    vec3 normal = normalize(cross(dFdx(vWorld), dFdy(vWorld)));
+    fragColor = vec4(normal * 0.5 + 0.5, baseColor.a);
+}
@@ -0,0 +1,10 @@
+#version 300 es
+// Colors each face by its world-space normal, to check geometry and winding
+layout(location = 0) in vec3 position;
+uniform mat4 modelViewProjection;
+uniform mat4 model;
+out vec3 vWorld;
+void main() {
+    gl_Position = modelViewProjection * vec4(position, 1.0);
+    vWorld = (model * vec4(position, 1.0)).xyz;
+}
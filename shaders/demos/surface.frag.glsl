@@ -0,0 +1,17 @@
+#version 300 es
+// Height colormap (blue, cyan, green, yellow, red) over `heightRange`, with
+// flat shading from screen-space derivatives
+precision mediump float;
+in vec3 vWorld;
+uniform vec2 heightRange;
+out vec4 fragColor;
+vec3 colormap(float t) {
+    vec3 c = vec3(1.5 * t - 0.5, 1.0 - abs(2.0 * t - 1.0) * 0.8, 1.0 - 1.5 * t);
+    return clamp(c, 0.0, 1.0);
+}
+void main() {
+    float t = (vWorld.y - heightRange.x) / max(heightRange.y - heightRange.x, 1e-4);
+    vec3 normal = normalize(cross(dFdx(vWorld), dFdy(vWorld)));
+    float light = 0.4 + 0.6 * abs(dot(normal, normalize(vec3(0.3, 1.0, 0.4))));
+    fragColor = vec4(colormap(clamp(t, 0.0, 1.0)) * light, 1.0);
+}
@@ -0,0 +1,14 @@
+#version 300 es
+// Vertex shader: a quad generated from gl_VertexID (triangle strip), placed by `rect`
+uniform vec4 rect; // center xy and half size zw, in NDC
+uniform float angle;
+out vec2 vUv;
+void main() {
+    vec2 corner = vec2(float(gl_VertexID & 1), float(gl_VertexID >> 1));
+    vec2 local = (corner * 2.0 - 1.0) * rect.zw;
+    float c = cos(angle);
+    float s = sin(angle);
+    gl_Position = vec4(rect.xy + vec2(c * local.x - s * local.y, s * local.x + c * local.y), 0.0, 1.0);
+    // Image rows start at the top
+    vUv = vec2(corner.x, 1.0 - corner.y);
+}
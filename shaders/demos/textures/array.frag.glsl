@@ -0,0 +1,9 @@
+#version 300 es
+precision mediump float;
+precision mediump sampler2DArray;
+in vec3 vUv;
+uniform sampler2DArray images;
+out vec4 fragColor;
+void main() {
+    fragColor = texture(images, vUv);
+}
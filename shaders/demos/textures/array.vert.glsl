@@ -0,0 +1,21 @@
+#version 300 es
+// The same tiles drawn from one texture array; the rect, angle and layer come
+// from uniforms for one draw per tile, or from instance attributes for one
+// draw in total
+layout(location = 0) in vec4 instanceRect;
+layout(location = 1) in vec2 instanceAngleLayer;
+uniform bool perInstance;
+uniform vec4 rect;
+uniform float angle;
+uniform float layer;
+out vec3 vUv;
+void main() {
+    vec4 r = perInstance ? instanceRect : rect;
+    float a = perInstance ? instanceAngleLayer.x : angle;
+    vec2 corner = vec2(float(gl_VertexID & 1), float(gl_VertexID >> 1));
+    vec2 local = (corner * 2.0 - 1.0) * r.zw;
+    float c = cos(a);
+    float s = sin(a);
+    gl_Position = vec4(r.xy + vec2(c * local.x - s * local.y, s * local.x + c * local.y), 0.0, 1.0);
+    vUv = vec3(corner.x, 1.0 - corner.y, perInstance ? instanceAngleLayer.y : layer);
+}
@@ -0,0 +1,11 @@
+#version 300 es
+precision mediump float;
+in vec3 vColor;
+uniform vec4 color;
+// 0 draws the vertex colors opaque, 1 the uniform color shaded like the OIT surfaces
+uniform float tinted;
+out vec4 fragColor;
+void main() {
+    vec4 surface = vec4(color.rgb * (0.6 + 0.4 * vColor), color.a);
+    fragColor = mix(vec4(vColor, 1.0), surface, tinted);
+}
@@ -0,0 +1,10 @@
+#version 300 es
+// Opaque, vertex-colored
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec3 color;
+uniform mat4 modelViewProjection;
+out vec3 vColor;
+void main() {
+    gl_Position = modelViewProjection * vec4(position, 1.0);
+    vColor = color;
+}
@@ -0,0 +1,49 @@
+#version 300 es
+// Front-to-back raymarch through the volume from the eye, mapping each
+// density sample through the transfer function texture
+precision highp float;
+precision highp sampler3D;
+in vec3 vPosition;
+uniform vec3 eye;
+uniform sampler3D volume;
+uniform sampler2D transfer;
+// Density range stretched over the transfer function
+uniform vec2 window;
+uniform float opacity;
+out vec4 fragColor;
+
+const int STEPS = 160;
+
+vec2 intersectBox(vec3 origin, vec3 dir) {
+    vec3 inverse = 1.0 / dir;
+    vec3 t0 = (-0.5 - origin) * inverse;
+    vec3 t1 = (0.5 - origin) * inverse;
+    vec3 near = min(t0, t1);
+    vec3 far = max(t0, t1);
+    return vec2(max(max(near.x, near.y), near.z), min(min(far.x, far.y), far.z));
+}
+
+void main() {
+    vec3 dir = normalize(vPosition - eye);
+    vec2 hit = intersectBox(eye, dir);
+    float stepSize = 1.7320508 / float(STEPS);
+    // Per-pixel jitter of the start trades banding for fine noise
+    float jitter = fract(sin(dot(gl_FragCoord.xy, vec2(12.9898, 78.233))) * 43758.5453);
+    float t = max(hit.x, 0.0) + jitter * stepSize;
+
+    vec4 color = vec4(0.0);
+    for (int i = 0; i < STEPS; i++) {
+        if (t > hit.y || color.a > 0.97) {
+            break;
+        }
+        float density = texture(volume, eye + dir * t + 0.5).r;
+        float v = clamp((density - window.x) / max(window.y - window.x, 1e-3), 0.0, 1.0);
+        vec4 sampleColor = texture(transfer, vec2(v, 0.5));
+        float alpha = clamp(sampleColor.a * opacity * stepSize * 40.0, 0.0, 1.0);
+        color.rgb += (1.0 - color.a) * alpha * sampleColor.rgb;
+        color.a += (1.0 - color.a) * alpha;
+        t += stepSize;
+    }
+    // Premultiplied, composited over the clear color
+    fragColor = color;
+}
@@ -0,0 +1,9 @@
+#version 300 es
+// The volume fills the unit cube centered on the origin
+layout(location = 0) in vec3 position;
+uniform mat4 viewProjection;
+out vec3 vPosition;
+void main() {
+    vPosition = position;
+    gl_Position = viewProjection * vec4(position, 1.0);
+}
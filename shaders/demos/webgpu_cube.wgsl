@@ -0,0 +1,26 @@
+// The cube demo's shaders, written in WGSL for the raw WebGPU cube
+struct Uniforms {
+    modelViewMatrix: mat4x4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+}
+
+@vertex
+fn vs_main(@location(0) position: vec3<f32>, @location(1) color: vec3<f32>) -> VertexOutput {
+    var output: VertexOutput;
+    let clip = uniforms.modelViewMatrix * vec4<f32>(position, 1.0);
+    // WebGPU clips depth to 0..w rather than GL's -w..w
+    output.position = vec4<f32>(clip.xy, (clip.z + clip.w) * 0.5, clip.w);
+    output.color = color;
+    return output;
+}
+
+@fragment
+fn fs_main(@location(0) color: vec3<f32>) -> @location(0) vec4<f32> {
+    return vec4<f32>(color, 1.0);
+}
@@ -0,0 +1,26 @@
+#version 300 es
+// Upscaling takes one bilinear tap per pixel. Downsampling averages the
+// pixel's footprint with bilinear taps that each cover up to 2x2 texels
+precision highp float;
+uniform sampler2D source;
+// Rendered size within the target, and the canvas size, in pixels
+uniform vec2 sourceSize;
+uniform vec2 outputSize;
+out vec4 fragColor;
+void main() {
+    vec2 ratio = sourceSize / outputSize;
+    ivec2 taps = ivec2(ceil(ratio * 0.5));
+    vec2 start = (gl_FragCoord.xy - 0.5) * ratio;
+    vec2 spacing = ratio / vec2(taps);
+    vec2 size = vec2(textureSize(source, 0));
+    vec4 sum = vec4(0.0);
+    for (int y = 0; y < taps.y; y++) {
+        for (int x = 0; x < taps.x; x++) {
+            vec2 pixel = start + (vec2(x, y) + 0.5) * spacing;
+            // Filtering mustn't reach past the rendered part into stale pixels
+            pixel = clamp(pixel, vec2(0.5), sourceSize - 0.5);
+            sum += texture(source, pixel / size);
+        }
+    }
+    fragColor = sum / float(taps.x * taps.y);
+}
@@ -0,0 +1,41 @@
+#version 300 es
+// Intersects each view ray with the plane y = height and draws antialiased
+// lines there, writing the plane's depth so scene objects occlude it
+precision highp float;
+in vec3 vNear;
+in vec3 vFar;
+uniform mat4 viewProjection;
+uniform float height;
+uniform float fadeDistance;
+out vec4 fragColor;
+float lines(vec2 coord, float spacing) {
+    vec2 cell = coord / spacing;
+    vec2 width = fwidth(cell);
+    vec2 offset = abs(fract(cell - 0.5) - 0.5) / width;
+    return 1.0 - min(min(offset.x, offset.y), 1.0);
+}
+void main() {
+    float t = (height - vNear.y) / (vFar.y - vNear.y);
+    if (!(t > 0.0 && t < 1.0)) {
+        discard;
+    }
+    vec3 p = mix(vNear, vFar, t);
+    vec4 clip = viewProjection * vec4(p, 1.0);
+    gl_FragDepth = clip.z / clip.w * 0.5 + 0.5;
+
+    float minor = lines(p.xz, 0.25) * 0.25;
+    float major = lines(p.xz, 1.0) * 0.6;
+    vec4 color = vec4(vec3(0.6), max(minor, major));
+    // World X runs along z = 0 (red), world Z along x = 0 (blue)
+    vec2 axis = abs(p.zx) / fwidth(p.zx);
+    if (axis.x < 1.0) {
+        color = vec4(0.9, 0.25, 0.2, 1.0 - axis.x);
+    } else if (axis.y < 1.0) {
+        color = vec4(0.2, 0.4, 0.95, 1.0 - axis.y);
+    }
+    float fade = 1.0 - smoothstep(0.3, 1.0, distance(p, vNear) / fadeDistance);
+    fragColor = vec4(color.rgb, color.a * fade);
+    if (fragColor.a <= 0.0) {
+        discard;
+    }
+}
@@ -0,0 +1,16 @@
+#version 300 es
+// Full-screen triangle from gl_VertexID; each corner carries the world-space
+// points it unprojects to on the near and far planes
+uniform mat4 inverseViewProjection;
+out vec3 vNear;
+out vec3 vFar;
+vec3 unproject(vec2 ndc, float z) {
+    vec4 p = inverseViewProjection * vec4(ndc, z, 1.0);
+    return p.xyz / p.w;
+}
+void main() {
+    vec2 ndc = vec2(float((gl_VertexID << 1) & 2), float(gl_VertexID & 2)) * 2.0 - 1.0;
+    vNear = unproject(ndc, -1.0);
+    vFar = unproject(ndc, 1.0);
+    gl_Position = vec4(ndc, 0.0, 1.0);
+}
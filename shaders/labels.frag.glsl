@@ -0,0 +1,12 @@
+#version 300 es
+precision mediump float;
+in vec2 vUv;
+uniform sampler2D label;
+out vec4 fragColor;
+void main() {
+    vec4 color = texture(label, vUv);
+    if (color.a < 0.01) {
+        discard;
+    }
+    fragColor = color;
+}
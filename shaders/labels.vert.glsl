@@ -0,0 +1,18 @@
+#version 300 es
+// A camera-facing quad generated from gl_VertexID (triangle strip), its bottom
+// edge centered on `anchor` and `size` world units across
+uniform mat4 viewProjection;
+uniform vec3 anchor;
+uniform vec3 cameraRight;
+uniform vec3 cameraUp;
+uniform vec2 size;
+out vec2 vUv;
+void main() {
+    vec2 corner = vec2(float(gl_VertexID & 1), float(gl_VertexID >> 1));
+    // Text textures store their top row first
+    vUv = vec2(corner.x, 1.0 - corner.y);
+    vec3 world = anchor
+        + cameraRight * (corner.x - 0.5) * size.x
+        + cameraUp * corner.y * size.y;
+    gl_Position = viewProjection * vec4(world, 1.0);
+}
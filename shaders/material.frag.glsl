@@ -0,0 +1,37 @@
+#version 300 es
+precision mediump float;
+in vec3 vColor;
+in vec3 vLocal;
+in vec3 vWorld;
+uniform vec4 baseColor;
+// The object's own color multiplier
+uniform vec4 tint;
+uniform sampler2D baseTexture;
+uniform vec3 lightDirection;
+uniform float flash;
+// Lighting levels for toon shading
+uniform float toonBands;
+out vec4 fragColor;
+void main() {
+    vec4 color = baseColor * tint;
+#ifdef VERTEX_COLORS
+    color.rgb *= vColor;
+#endif
+#ifdef TEXTURE
+    // No UVs in the meshes: project the texture along the face's dominant object axis
+    vec3 n = abs(cross(dFdx(vLocal), dFdy(vLocal)));
+    vec2 uv = n.x > n.y && n.x > n.z ? vLocal.zy : (n.y > n.z ? vLocal.xz : vLocal.xy);
+    color *= texture(baseTexture, uv + 0.5);
+#endif
+#ifdef LIGHTING
+    // Flat shading from the world-space face normal
+    vec3 normal = normalize(cross(dFdx(vWorld), dFdy(vWorld)));
+    float diffuse = max(dot(normal, lightDirection), 0.0);
+#ifdef TOON
+    // A stepped ramp: every surface snaps to one of a few flat tones
+    diffuse = min(floor(diffuse * toonBands) / max(toonBands - 1.0, 1.0), 1.0);
+#endif
+    color.rgb *= 0.35 + 0.65 * diffuse;
+#endif
+    fragColor = vec4(mix(color.rgb, vec3(1.0, 0.15, 0.1), flash), color.a);
+}
@@ -0,0 +1,15 @@
+#version 300 es
+// Source of the standard shader; `ShaderFeatures` switches parts on with #defines
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec3 color;
+uniform mat4 modelViewProjection;
+uniform mat4 model;
+out vec3 vColor;
+out vec3 vLocal;
+out vec3 vWorld;
+void main() {
+    gl_Position = modelViewProjection * vec4(position, 1.0);
+    vColor = color;
+    vLocal = position;
+    vWorld = (model * vec4(position, 1.0)).xyz;
+}
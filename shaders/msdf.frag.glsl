@@ -0,0 +1,27 @@
+#version 300 es
+precision mediump float;
+in vec2 vUv;
+uniform sampler2D atlas;
+// The atlas's distance range over its size: how much of the field one unit
+// of UV spans
+uniform vec2 unitRange;
+uniform vec4 color;
+out vec4 fragColor;
+
+float median(float r, float g, float b) {
+    return max(min(r, g), min(max(r, g), b));
+}
+
+void main() {
+    vec3 distances = texture(atlas, vUv).rgb;
+    float signedDistance = median(distances.r, distances.g, distances.b) - 0.5;
+    // The distance range in screen pixels at this magnification, so edges
+    // stay about a pixel wide at any size
+    vec2 screenTexSize = vec2(1.0) / fwidth(vUv);
+    float screenPxRange = max(0.5 * dot(unitRange, screenTexSize), 1.0);
+    float opacity = clamp(screenPxRange * signedDistance + 0.5, 0.0, 1.0);
+    if (opacity < 0.01) {
+        discard;
+    }
+    fragColor = vec4(color.rgb, color.a * opacity);
+}
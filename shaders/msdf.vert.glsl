@@ -0,0 +1,9 @@
+#version 300 es
+layout(location = 0) in vec3 position;
+layout(location = 2) in vec2 uv;
+uniform mat4 modelViewProjection;
+out vec2 vUv;
+void main() {
+    vUv = uv;
+    gl_Position = modelViewProjection * vec4(position, 1.0);
+}
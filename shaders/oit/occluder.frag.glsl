@@ -0,0 +1,7 @@
+#version 300 es
+// Depth-only pass for opaque geometry that hides transparent surfaces behind it
+precision mediump float;
+out vec4 fragColor;
+void main() {
+    fragColor = vec4(0.0);
+}
@@ -0,0 +1,15 @@
+#version 300 es
+precision highp float;
+uniform sampler2D accumTexture;
+uniform sampler2D weightTexture;
+out vec4 fragColor;
+void main() {
+    ivec2 pixel = ivec2(gl_FragCoord.xy);
+    vec4 accum = texelFetch(accumTexture, pixel, 0);
+    float revealage = accum.a;
+    if (revealage >= 1.0) {
+        discard;
+    }
+    float weight = texelFetch(weightTexture, pixel, 0).r;
+    fragColor = vec4(accum.rgb / max(weight, 1e-5), 1.0 - revealage);
+}
@@ -0,0 +1,17 @@
+#version 300 es
+// Target 0: rgb sums weighted premultiplied color; alpha blends to the product
+// of (1 - alpha), the revealage. Target 1 sums the weights
+precision highp float;
+in vec3 vShade;
+uniform vec4 color;
+layout(location = 0) out vec4 accum;
+layout(location = 1) out float weight;
+void main() {
+    // Vertex colors only shade the surface a little, for some depth cues
+    vec3 rgb = color.rgb * (0.6 + 0.4 * vShade);
+    float a = color.a;
+    // Nearer and more opaque fragments count for more (McGuire and Bavoil 2013)
+    float w = clamp(pow(min(1.0, a * 10.0) + 0.01, 3.0) * 1e8 * pow(1.0 - gl_FragCoord.z * 0.9, 3.0), 1e-2, 3e3);
+    accum = vec4(rgb * a * w, a);
+    weight = a * w;
+}
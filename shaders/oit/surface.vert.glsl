@@ -0,0 +1,10 @@
+#version 300 es
+// Transparent surfaces, written to both accumulation targets
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec3 color;
+uniform mat4 modelViewProjection;
+out vec3 vShade;
+void main() {
+    gl_Position = modelViewProjection * vec4(position, 1.0);
+    vShade = color;
+}
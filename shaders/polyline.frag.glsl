@@ -0,0 +1,28 @@
+#version 300 es
+precision highp float;
+flat in vec4 vSegment;
+flat in vec2 vWidths;
+flat in vec4 vStartColor;
+flat in vec4 vEndColor;
+uniform bool roundJoins;
+out vec4 fragColor;
+void main() {
+    vec2 p = gl_FragCoord.xy;
+    vec2 a = vSegment.xy;
+    vec2 ab = vSegment.zw - a;
+    float t = clamp(dot(p - a, ab) / max(dot(ab, ab), 1e-6), 0.0, 1.0);
+    float distanceToLine;
+    if (roundJoins) {
+        distanceToLine = length(p - (a + ab * t));
+    } else {
+        vec2 dir = ab / max(length(ab), 1e-6);
+        distanceToLine = abs(dir.x * (p - a).y - dir.y * (p - a).x);
+    }
+    // Coverage of a one-pixel-wide edge
+    float alpha = clamp(mix(vWidths.x, vWidths.y, t) * 0.5 + 0.5 - distanceToLine, 0.0, 1.0);
+    if (alpha <= 0.0) {
+        discard;
+    }
+    vec4 color = mix(vStartColor, vEndColor, t);
+    fragColor = vec4(color.rgb, color.a * alpha);
+}
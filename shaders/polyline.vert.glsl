@@ -0,0 +1,83 @@
+#version 300 es
+// One instance per segment, expanded from gl_VertexID into a screen-space quad.
+// Each instance also sees the points either side of the segment to shape its joins
+layout(location = 0) in vec3 previousPosition;
+layout(location = 1) in vec3 startPosition;
+layout(location = 2) in float startWidth;
+layout(location = 3) in vec4 startColor;
+layout(location = 4) in vec3 endPosition;
+layout(location = 5) in float endWidth;
+layout(location = 6) in vec4 endColor;
+layout(location = 7) in vec3 nextPosition;
+uniform mat4 viewProjection;
+uniform vec2 viewport;
+uniform bool roundJoins;
+flat out vec4 vSegment;
+flat out vec2 vWidths;
+flat out vec4 vStartColor;
+flat out vec4 vEndColor;
+
+const float MITER_LIMIT = 4.0;
+
+vec2 toScreen(vec3 p, out float depth) {
+    vec4 clip = viewProjection * vec4(p, 1.0);
+    depth = clip.z / clip.w;
+    return (clip.xy / clip.w * 0.5 + 0.5) * viewport;
+}
+
+vec2 direction(vec2 from, vec2 to) {
+    vec2 d = to - from;
+    float len = length(d);
+    return len > 1e-4 ? d / len : vec2(0.0);
+}
+
+vec2 perpendicular(vec2 v) {
+    return vec2(-v.y, v.x);
+}
+
+void main() {
+    // x picks the segment end (0 start, 1 end), y the side of the line
+    vec2 corner = vec2[6](
+        vec2(0.0, -1.0), vec2(1.0, -1.0), vec2(1.0, 1.0),
+        vec2(0.0, -1.0), vec2(1.0, 1.0), vec2(0.0, 1.0)
+    )[gl_VertexID];
+    bool atEnd = corner.x > 0.5;
+
+    float startDepth;
+    float endDepth;
+    float unusedDepth;
+    vec2 a = toScreen(startPosition, startDepth);
+    vec2 b = toScreen(endPosition, endDepth);
+    vec2 dir = direction(a, b);
+    if (dir == vec2(0.0)) {
+        dir = vec2(1.0, 0.0);
+    }
+    vec2 normal = perpendicular(dir);
+    // One extra pixel all round for the antialiased edge
+    float halfWidth = (atEnd ? endWidth : startWidth) * 0.5 + 1.0;
+
+    vec2 offset;
+    if (roundJoins) {
+        // A capsule: the fragment shader rounds off the extended ends
+        offset = normal * corner.y * halfWidth + dir * (atEnd ? halfWidth : -halfWidth);
+    } else {
+        vec2 neighbour = atEnd
+            ? direction(b, toScreen(nextPosition, unusedDepth))
+            : direction(toScreen(previousPosition, unusedDepth), a);
+        // Polyline ends repeat their point, leaving no neighbour to join to
+        vec2 tangent = neighbour == vec2(0.0) ? dir : direction(vec2(0.0), dir + neighbour);
+        if (tangent == vec2(0.0)) {
+            tangent = dir;
+        }
+        vec2 miter = perpendicular(tangent);
+        float len = min(halfWidth / max(dot(miter, normal), 1e-4), halfWidth * MITER_LIMIT);
+        offset = miter * corner.y * len;
+    }
+
+    vec2 pixel = (atEnd ? b : a) + offset;
+    gl_Position = vec4(pixel / viewport * 2.0 - 1.0, atEnd ? endDepth : startDepth, 1.0);
+    vSegment = vec4(a, b);
+    vWidths = vec2(startWidth, endWidth);
+    vStartColor = startColor;
+    vEndColor = endColor;
+}
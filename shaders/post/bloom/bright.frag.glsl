@@ -0,0 +1,13 @@
+#version 300 es
+precision highp float;
+in vec2 vUv;
+uniform sampler2D source;
+uniform float threshold;
+out vec4 fragColor;
+void main() {
+    vec3 color = texture(source, vUv).rgb;
+    float brightness = max(color.r, max(color.g, color.b));
+    // Keep the part above the threshold, in the pixel's own hue
+    float excess = max(brightness - threshold, 0.0);
+    fragColor = vec4(color * excess / max(brightness, 1e-4), 1.0);
+}
@@ -0,0 +1,10 @@
+#version 300 es
+precision mediump float;
+in vec2 vUv;
+uniform sampler2D frame;
+uniform sampler2D glow;
+uniform float intensity;
+out vec4 fragColor;
+void main() {
+    fragColor = vec4(texture(frame, vUv).rgb + texture(glow, vUv).rgb * intensity, 1.0);
+}
@@ -0,0 +1,15 @@
+#version 300 es
+precision highp float;
+in vec2 vUv;
+uniform sampler2D source;
+// One texel of the larger level being read
+uniform vec2 texel;
+out vec4 fragColor;
+void main() {
+    // Four bilinear taps between texels average a 4x4 block around the pixel
+    vec3 sum = texture(source, vUv + texel * vec2(-1.0, -1.0)).rgb;
+    sum += texture(source, vUv + texel * vec2(1.0, -1.0)).rgb;
+    sum += texture(source, vUv + texel * vec2(-1.0, 1.0)).rgb;
+    sum += texture(source, vUv + texel * vec2(1.0, 1.0)).rgb;
+    fragColor = vec4(sum * 0.25, 1.0);
+}
@@ -0,0 +1,20 @@
+#version 300 es
+precision highp float;
+in vec2 vUv;
+uniform sampler2D source;
+// One texel of the smaller level being read
+uniform vec2 texel;
+out vec4 fragColor;
+void main() {
+    // 3x3 tent filter, so the enlarged level comes out smooth rather than blocky
+    vec3 sum = texture(source, vUv).rgb * 4.0;
+    sum += texture(source, vUv + texel * vec2(-1.0, 0.0)).rgb * 2.0;
+    sum += texture(source, vUv + texel * vec2(1.0, 0.0)).rgb * 2.0;
+    sum += texture(source, vUv + texel * vec2(0.0, -1.0)).rgb * 2.0;
+    sum += texture(source, vUv + texel * vec2(0.0, 1.0)).rgb * 2.0;
+    sum += texture(source, vUv + texel * vec2(-1.0, -1.0)).rgb;
+    sum += texture(source, vUv + texel * vec2(1.0, -1.0)).rgb;
+    sum += texture(source, vUv + texel * vec2(-1.0, 1.0)).rgb;
+    sum += texture(source, vUv + texel * vec2(1.0, 1.0)).rgb;
+    fragColor = vec4(sum / 16.0, 1.0);
+}
@@ -0,0 +1,26 @@
+#version 300 es
+precision highp float;
+in vec2 vUv;
+// Color with the circle of confusion in alpha
+uniform sampler2D source;
+// One full-resolution pixel along the blur axis, in texture coordinates
+uniform vec2 direction;
+// Blur radius in pixels at a circle of confusion of 1
+uniform float maxRadius;
+out vec4 fragColor;
+const int TAPS = 8;
+void main() {
+    vec4 center = texture(source, vUv);
+    float radius = center.a * maxRadius;
+    vec3 sum = vec3(0.0);
+    float total = 0.0;
+    for (int i = -TAPS; i <= TAPS; i++) {
+        float offset = float(i) / float(TAPS) * radius;
+        vec4 tap = texture(source, vUv + direction * offset);
+        // A sample only spreads as far as its own blur reaches, so sharp objects don't bleed into blurred ones
+        float weight = clamp(tap.a * maxRadius - abs(offset) + 1.0, 0.0, 1.0);
+        sum += tap.rgb * weight;
+        total += weight;
+    }
+    fragColor = vec4(sum / total, center.a);
+}
@@ -0,0 +1,21 @@
+#version 300 es
+precision highp float;
+in vec2 vUv;
+uniform sampler2D colorTexture;
+uniform sampler2D depthTexture;
+uniform float near;
+uniform float far;
+uniform float focusDistance;
+uniform float aperture;
+out vec4 fragColor;
+// Distance from the camera of a depth buffer value
+float linearDepth(float depth) {
+    float z = depth * 2.0 - 1.0;
+    return 2.0 * near * far / (far + near - z * (far - near));
+}
+void main() {
+    float distance = linearDepth(texture(depthTexture, vUv).r);
+    // Thin lens: the blur grows with the distance from the focal plane relative to the distance itself
+    float coc = clamp(aperture * abs(distance - focusDistance) / distance, 0.0, 1.0);
+    fragColor = vec4(texture(colorTexture, vUv).rgb, coc);
+}
@@ -0,0 +1,14 @@
+#version 300 es
+precision mediump float;
+in vec2 vUv;
+// Full resolution, circle of confusion in alpha
+uniform sampler2D sharpTexture;
+// Half resolution
+uniform sampler2D blurredTexture;
+out vec4 fragColor;
+void main() {
+    vec4 sharp = texture(sharpTexture, vUv);
+    vec3 blurred = texture(blurredTexture, vUv).rgb;
+    // The half-resolution blur would soften the focal plane too, so it only fades in with the circle
+    fragColor = vec4(mix(sharp.rgb, blurred, smoothstep(0.0, 0.2, sharp.a)), 1.0);
+}
@@ -0,0 +1,8 @@
+#version 300 es
+// Full-screen triangle for post passes, with texture coordinates over the viewport
+out vec2 vUv;
+void main() {
+    vec2 corner = vec2(float((gl_VertexID << 1) & 2), float(gl_VertexID & 2));
+    vUv = corner;
+    gl_Position = vec4(corner * 2.0 - 1.0, 0.0, 1.0);
+}
@@ -0,0 +1,39 @@
+#version 300 es
+precision highp float;
+in vec2 vUv;
+uniform sampler2D source;
+// Size of a pixel in texture coordinates
+uniform vec2 texel;
+out vec4 fragColor;
+// Longest edge search, in pixels
+const float SPAN_MAX = 8.0;
+const float REDUCE_MUL = 1.0 / 8.0;
+const float REDUCE_MIN = 1.0 / 128.0;
+// Edges are found in display brightness, so HDR values count as 1
+float luma(vec3 color) {
+    return dot(clamp(color, 0.0, 1.0), vec3(0.299, 0.587, 0.114));
+}
+vec3 fetch(vec2 uv) {
+    return texture(source, uv).rgb;
+}
+void main() {
+    float lumaNW = luma(fetch(vUv + vec2(-1.0, -1.0) * texel));
+    float lumaNE = luma(fetch(vUv + vec2(1.0, -1.0) * texel));
+    float lumaSW = luma(fetch(vUv + vec2(-1.0, 1.0) * texel));
+    float lumaSE = luma(fetch(vUv + vec2(1.0, 1.0) * texel));
+    float lumaM = luma(fetch(vUv));
+    float lumaMin = min(lumaM, min(min(lumaNW, lumaNE), min(lumaSW, lumaSE)));
+    float lumaMax = max(lumaM, max(max(lumaNW, lumaNE), max(lumaSW, lumaSE)));
+
+    // Along the edge, across the brightness gradient
+    vec2 direction = vec2(-((lumaNW + lumaNE) - (lumaSW + lumaSE)), (lumaNW + lumaSW) - (lumaNE + lumaSE));
+    float reduce = max((lumaNW + lumaNE + lumaSW + lumaSE) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    float scale = 1.0 / (min(abs(direction.x), abs(direction.y)) + reduce);
+    direction = clamp(direction * scale, -SPAN_MAX, SPAN_MAX) * texel;
+
+    vec3 near = 0.5 * (fetch(vUv + direction * (1.0 / 3.0 - 0.5)) + fetch(vUv + direction * (2.0 / 3.0 - 0.5)));
+    vec3 far = near * 0.5 + 0.25 * (fetch(vUv - direction * 0.5) + fetch(vUv + direction * 0.5));
+    // The wider average crossed another edge, so keep the narrow one
+    float lumaFar = luma(far);
+    fragColor = vec4(lumaFar < lumaMin || lumaFar > lumaMax ? near : far, 1.0);
+}
@@ -0,0 +1,23 @@
+#version 300 es
+precision highp float;
+precision highp sampler3D;
+in vec2 vUv;
+uniform sampler2D source;
+uniform sampler3D lut;
+// Entries per axis of the lookup table
+uniform float lutSize;
+uniform float saturation;
+uniform float vignette;
+out vec4 fragColor;
+void main() {
+    vec3 color = clamp(texture(source, vUv).rgb, 0.0, 1.0);
+    // Sample entry centers, so 0 and 1 land on the first and last entries rather than between them and the edge
+    vec3 coord = color * ((lutSize - 1.0) / lutSize) + 0.5 / lutSize;
+    color = texture(lut, coord).rgb;
+    float luma = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    color = max(mix(vec3(luma), color, saturation), 0.0);
+    // Darken smoothly towards the corners
+    vec2 offset = vUv - 0.5;
+    color *= 1.0 - vignette * smoothstep(0.2, 0.75, length(offset));
+    fragColor = vec4(color, 1.0);
+}
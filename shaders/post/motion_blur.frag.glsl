@@ -0,0 +1,25 @@
+#version 300 es
+precision highp float;
+in vec2 vUv;
+uniform sampler2D source;
+// Screen-space motion since the last frame, in texture coordinates
+uniform sampler2D velocityTexture;
+uniform float shutter;
+out vec4 fragColor;
+const int SAMPLES = 12;
+// Longest smear, as a fraction of the frame, so a teleporting object doesn't streak across it
+const float MAX_LENGTH = 0.08;
+void main() {
+    vec2 velocity = texture(velocityTexture, vUv).rg * shutter;
+    float speed = length(velocity);
+    if (speed > MAX_LENGTH) {
+        velocity *= MAX_LENGTH / speed;
+    }
+    vec3 sum = vec3(0.0);
+    // Centered on the pixel: half the motion before it, half after
+    for (int i = 0; i < SAMPLES; i++) {
+        float t = float(i) / float(SAMPLES - 1) - 0.5;
+        sum += texture(source, vUv - velocity * t).rgb;
+    }
+    fragColor = vec4(sum / float(SAMPLES), 1.0);
+}
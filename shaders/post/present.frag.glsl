@@ -0,0 +1,8 @@
+#version 300 es
+precision mediump float;
+in vec2 vUv;
+uniform sampler2D source;
+out vec4 fragColor;
+void main() {
+    fragColor = vec4(clamp(texture(source, vUv).rgb, 0.0, 1.0), 1.0);
+}
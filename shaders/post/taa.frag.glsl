@@ -0,0 +1,33 @@
+#version 300 es
+precision highp float;
+in vec2 vUv;
+// This frame, drawn with a subpixel offset
+uniform sampler2D source;
+// Blended previous frames
+uniform sampler2D history;
+// Screen-space motion since the last frame, in texture coordinates
+uniform sampler2D velocityTexture;
+// Weight of this frame; 1 ignores the history
+uniform float blend;
+out vec4 fragColor;
+void main() {
+    ivec2 pixel = ivec2(gl_FragCoord.xy);
+    ivec2 last = textureSize(source, 0) - 1;
+    vec3 current = texelFetch(source, pixel, 0).rgb;
+    // The history may only hold what this frame's neighborhood could blend
+    // to; anything outside is stale, like what a moving object just uncovered
+    vec3 low = current;
+    vec3 high = current;
+    for (int y = -1; y <= 1; y++) {
+        for (int x = -1; x <= 1; x++) {
+            vec3 neighbor = texelFetch(source, clamp(pixel + ivec2(x, y), ivec2(0), last), 0).rgb;
+            low = min(low, neighbor);
+            high = max(high, neighbor);
+        }
+    }
+    vec2 previousUv = vUv - texture(velocityTexture, vUv).rg;
+    vec3 previous = clamp(texture(history, previousUv).rgb, low, high);
+    // Off screen last frame, so there is nothing to blend with
+    bool outside = any(lessThan(previousUv, vec2(0.0))) || any(greaterThan(previousUv, vec2(1.0)));
+    fragColor = vec4(mix(previous, current, outside ? 1.0 : blend), 1.0);
+}
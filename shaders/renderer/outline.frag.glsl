@@ -0,0 +1,7 @@
+#version 300 es
+precision mediump float;
+uniform vec3 outlineColor;
+out vec4 fragColor;
+void main() {
+    fragColor = vec4(outlineColor, 1.0);
+}
@@ -0,0 +1,7 @@
+#version 300 es
+// Solid color, used for the hover and material outline hulls
+layout(location = 0) in vec3 position;
+uniform mat4 modelViewProjection;
+void main() {
+    gl_Position = modelViewProjection * vec4(position, 1.0);
+}
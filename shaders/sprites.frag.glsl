@@ -0,0 +1,9 @@
+#version 300 es
+precision mediump float;
+in vec2 vUv;
+in vec4 vColor;
+uniform sampler2D image;
+out vec4 fragColor;
+void main() {
+    fragColor = texture(image, vUv) * vColor;
+}
@@ -0,0 +1,12 @@
+#version 300 es
+layout(location = 0) in vec2 position;
+layout(location = 1) in vec2 uv;
+layout(location = 2) in vec4 color;
+uniform mat4 projection;
+out vec2 vUv;
+out vec4 vColor;
+void main() {
+    gl_Position = projection * vec4(position, 0.0, 1.0);
+    vUv = uv;
+    vColor = color;
+}
@@ -0,0 +1,153 @@
+use js_sys::{ArrayBuffer, Object, Promise, Reflect};
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IdbDatabase, IdbRequest, IdbTransactionMode, IdbVersionChangeEvent, XmlHttpRequest};
+
+use crate::assets::{fetch_bytes, Progress};
+
+const DB_NAME: &str = "asset-cache";
+const DB_VERSION: u32 = 1;
+const STORE: &str = "assets";
+
+/**
+ * Download `url` like `fetch_bytes`, but keep the bytes in IndexedDB keyed by
+ * URL. A HEAD request asks for the current ETag (or Last-Modified) first and
+ * the cached copy is served when it still matches, so large models and
+ * textures are only downloaded again after they change. Responses without a
+ * validator aren't cached; when the HEAD request fails, e.g. offline, any
+ * cached copy is used as is
+ */
+pub async fn fetch_cached(
+    url: &str,
+    mut on_progress: impl FnMut(Progress) + 'static,
+) -> Result<ArrayBuffer, String> {
+    // Private browsing and some embedded views have no IndexedDB
+    let Some(db) = open().await else {
+        return fetch_bytes(url, on_progress).await;
+    };
+    let cached = get(&db, url).await;
+    let validator = match head_validator(url).await {
+        Ok(validator) => validator,
+        Err(_) => {
+            if let Some((_, bytes)) = cached {
+                report_complete(&bytes, &mut on_progress);
+                return Ok(bytes);
+            }
+            None
+        }
+    };
+
+    if let (Some(validator), Some((cached_validator, bytes))) = (&validator, cached) {
+        if *validator == cached_validator {
+            report_complete(&bytes, &mut on_progress);
+            return Ok(bytes);
+        }
+    }
+
+    let bytes = fetch_bytes(url, on_progress).await?;
+    if let Some(validator) = validator {
+        if let Err(err) = put(&db, url, &validator, &bytes).await {
+            tracing::warn!(target: "assets", "Could not cache {}: {:?}", url, err);
+        }
+    }
+    Ok(bytes)
+}
+
+fn report_complete(bytes: &ArrayBuffer, on_progress: &mut impl FnMut(Progress)) {
+    let size = bytes.byte_length() as f64;
+    on_progress(Progress {
+        loaded: size,
+        total: Some(size),
+    });
+}
+
+/**
+ * The ETag, or failing that Last-Modified, the server reports for `url`
+ */
+async fn head_validator(url: &str) -> Result<Option<String>, JsValue> {
+    let xhr = XmlHttpRequest::new()?;
+    xhr.open("HEAD", url)?;
+    let done = Promise::new(&mut |resolve, reject| {
+        xhr.set_onload(Some(&resolve));
+        xhr.set_onerror(Some(&reject));
+    });
+    xhr.send()?;
+    JsFuture::from(done).await?;
+    if !(200..300).contains(&xhr.status()?) {
+        return Ok(None);
+    }
+    Ok(match xhr.get_response_header("ETag")? {
+        Some(etag) => Some(etag),
+        None => xhr
+            .get_response_header("Last-Modified")?
+            .map(|modified| format!("modified:{}", modified)),
+    })
+}
+
+async fn open() -> Option<IdbDatabase> {
+    let factory = web_sys::window()?.indexed_db().ok()??;
+    let request = factory.open_with_u32(DB_NAME, DB_VERSION).ok()?;
+
+    let upgrade_request = request.clone();
+    let on_upgrade = Closure::<dyn FnMut(IdbVersionChangeEvent)>::new(move |_| {
+        let Ok(db) = upgrade_request
+            .result()
+            .and_then(|db| db.dyn_into::<IdbDatabase>())
+        else {
+            return;
+        };
+        if !db.object_store_names().contains(STORE) {
+            if let Err(err) = db.create_object_store(STORE) {
+                tracing::warn!(target: "assets", "Could not create the asset cache: {:?}", err);
+            }
+        }
+    });
+    request.set_onupgradeneeded(Some(on_upgrade.as_ref().unchecked_ref()));
+    let result = request_result(&request).await;
+    // The upgrade handler runs before success, so it can go now
+    drop(on_upgrade);
+    result.ok()?.dyn_into::<IdbDatabase>().ok()
+}
+
+/**
+ * The cached validator and bytes for `url`, if any
+ */
+async fn get(db: &IdbDatabase, url: &str) -> Option<(String, ArrayBuffer)> {
+    let store = db
+        .transaction_with_str(STORE)
+        .and_then(|transaction| transaction.object_store(STORE))
+        .ok()?;
+    let record = request_result(&store.get(&url.into()).ok()?).await.ok()?;
+    if record.is_undefined() {
+        return None;
+    }
+    let etag = Reflect::get(&record, &"etag".into()).ok()?.as_string()?;
+    let bytes = Reflect::get(&record, &"bytes".into())
+        .ok()?
+        .dyn_into::<ArrayBuffer>()
+        .ok()?;
+    Some((etag, bytes))
+}
+
+async fn put(db: &IdbDatabase, url: &str, etag: &str, bytes: &ArrayBuffer) -> Result<(), JsValue> {
+    let record = Object::new();
+    Reflect::set(&record, &"etag".into(), &etag.into())?;
+    Reflect::set(&record, &"bytes".into(), bytes)?;
+    let store = db
+        .transaction_with_str_and_mode(STORE, IdbTransactionMode::Readwrite)?
+        .object_store(STORE)?;
+    request_result(&store.put_with_key(&record, &url.into())?).await?;
+    Ok(())
+}
+
+/**
+ * Wait for an IndexedDB request to finish and return its result
+ */
+async fn request_result(request: &IdbRequest) -> Result<JsValue, JsValue> {
+    let done = Promise::new(&mut |resolve, reject| {
+        request.set_onsuccess(Some(&resolve));
+        request.set_onerror(Some(&reject));
+    });
+    JsFuture::from(done).await?;
+    request.result()
+}
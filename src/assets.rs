@@ -0,0 +1,488 @@
+use dioxus::prelude::*;
+use js_sys::{Array, ArrayBuffer, Promise, Uint8Array};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use tracing::Instrument;
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    Blob, CanvasRenderingContext2d, HtmlCanvasElement, ImageBitmap, ProgressEvent,
+    WebGl2RenderingContext, XmlHttpRequest, XmlHttpRequestResponseType,
+};
+
+use crate::asset_cache;
+use crate::gltf;
+use crate::mesh::MeshData;
+use crate::obj;
+use crate::texture::Texture;
+
+/**
+ * Bytes received so far for one download; `total` is unknown when the server
+ * doesn't send a Content-Length
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Progress {
+    pub loaded: f64,
+    pub total: Option<f64>,
+}
+
+impl Progress {
+    pub fn fraction(&self) -> Option<f64> {
+        self.total
+            .filter(|&total| total > 0.0)
+            .map(|total| (self.loaded / total).clamp(0.0, 1.0))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LoadState {
+    Loading(Progress),
+    Ready,
+    Failed(String),
+}
+
+/**
+ * What the loading UI knows about one asset. Critical assets hold back the
+ * render loop; the rest render with placeholders until they arrive
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssetStatus {
+    pub name: String,
+    pub critical: bool,
+    pub state: LoadState,
+}
+
+/**
+ * Typed reference to an asset owned by `Assets`; cheap to copy and compare
+ */
+pub struct Handle<T> {
+    index: usize,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle({})", self.index)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum AssetKind {
+    Texture,
+    Mesh,
+}
+
+enum Decoded {
+    Texture(ImageBitmap),
+    Mesh(Rc<MeshData>),
+}
+
+struct Entry {
+    kind: AssetKind,
+    decoded: Option<Decoded>,
+    // Textures uploaded so far, one per GL context that asked for this asset
+    uploads: Vec<(WebGl2RenderingContext, Texture)>,
+}
+
+#[derive(Default)]
+struct Registry {
+    by_url: HashMap<String, usize>,
+    entries: Vec<Entry>,
+}
+
+/**
+ * App-wide asset manager, provided as context. Each URL is fetched and
+ * decoded once no matter how many components ask for it, and GPU textures
+ * are uploaded once per GL context
+ */
+#[derive(Clone)]
+pub struct Assets {
+    registry: Rc<RefCell<Registry>>,
+    // Indexed by handle; a signal so loading UI re-renders as downloads progress
+    states: Signal<Vec<LoadState>>,
+}
+
+impl Default for Assets {
+    fn default() -> Self {
+        Self {
+            registry: Rc::default(),
+            states: Signal::new(Vec::new()),
+        }
+    }
+}
+
+impl Assets {
+    pub fn load_texture(&self, url: &str) -> Handle<Texture> {
+        self.load(url, AssetKind::Texture)
+    }
+
+    /**
+     * Load an OBJ model, or glTF for URLs ending in `.gltf` or `.glb`
+     */
+    pub fn load_mesh(&self, url: &str) -> Handle<MeshData> {
+        self.load(url, AssetKind::Mesh)
+    }
+
+    /**
+     * Current load state; reading it subscribes the calling component or effect
+     */
+    pub fn state<T>(&self, handle: Handle<T>) -> LoadState {
+        self.states.read()[handle.index].clone()
+    }
+
+    /**
+     * The texture uploaded to `gl`, uploading it on first use. `None` until decoded
+     */
+    pub fn texture(&self, gl: &WebGl2RenderingContext, handle: Handle<Texture>) -> Option<Texture> {
+        let mut registry = self.registry.borrow_mut();
+        let entry = &mut registry.entries[handle.index];
+        if let Some((_, texture)) = entry.uploads.iter().find(|(context, _)| context == gl) {
+            return Some(texture.clone());
+        }
+        let Some(Decoded::Texture(image)) = &entry.decoded else {
+            return None;
+        };
+        let texture = Texture::from_image(gl, image)?;
+        entry.uploads.push((gl.clone(), texture.clone()));
+        Some(texture)
+    }
+
+    /**
+     * The decoded image, e.g. for packing into an atlas. `None` until decoded
+     */
+    pub fn image(&self, handle: Handle<Texture>) -> Option<ImageBitmap> {
+        match &self.registry.borrow().entries[handle.index].decoded {
+            Some(Decoded::Texture(image)) => Some(image.clone()),
+            _ => None,
+        }
+    }
+
+    /**
+     * The parsed mesh, or `None` while it is still loading
+     */
+    pub fn mesh(&self, handle: Handle<MeshData>) -> Option<Rc<MeshData>> {
+        match &self.registry.borrow().entries[handle.index].decoded {
+            Some(Decoded::Mesh(mesh)) => Some(mesh.clone()),
+            _ => None,
+        }
+    }
+
+    fn load<T>(&self, url: &str, kind: AssetKind) -> Handle<T> {
+        let mut registry = self.registry.borrow_mut();
+        // Requests for a URL already loading (or loaded) share its entry
+        if let Some(&index) = registry.by_url.get(url) {
+            debug_assert_eq!(registry.entries[index].kind, kind);
+            return Handle {
+                index,
+                marker: PhantomData,
+            };
+        }
+
+        let index = registry.entries.len();
+        registry.by_url.insert(url.to_string(), index);
+        registry.entries.push(Entry {
+            kind,
+            decoded: None,
+            uploads: Vec::new(),
+        });
+        drop(registry);
+
+        let mut states = self.states;
+        states.write().push(LoadState::Loading(Progress::default()));
+
+        let registry = self.registry.clone();
+        let url = url.to_string();
+        let span = tracing::info_span!(target: "assets", "load", url = %url);
+        // Not tied to the requesting component, so unmounting doesn't strand the entry
+        spawn_forever(
+            async move {
+                let on_progress = move |progress| {
+                    states.write()[index] = LoadState::Loading(progress);
+                };
+                let decoded = match asset_cache::fetch_cached(&url, on_progress).await {
+                    Ok(bytes) => match kind {
+                        AssetKind::Texture => decode_image(&bytes).await.map(Decoded::Texture),
+                        AssetKind::Mesh => {
+                            let bytes = Uint8Array::new(&bytes).to_vec();
+                            let path = url.split(['?', '#']).next().unwrap_or_default();
+                            if path.ends_with(".gltf") || path.ends_with(".glb") {
+                                gltf::import(&bytes)
+                            } else {
+                                String::from_utf8(bytes)
+                                    .map_err(|_| format!("{} is not valid UTF-8", url))
+                                    .and_then(|source| obj::parse(&source))
+                            }
+                            .map(|mesh| Decoded::Mesh(Rc::new(mesh)))
+                        }
+                    },
+                    Err(err) => Err(err),
+                };
+                states.write()[index] = match decoded {
+                    Ok(decoded) => {
+                        tracing::info!(target: "assets", "Loaded {}", url);
+                        registry.borrow_mut().entries[index].decoded = Some(decoded);
+                        LoadState::Ready
+                    }
+                    Err(err) => {
+                        tracing::error!(target: "assets", "Loading {} failed: {}", url, err);
+                        LoadState::Failed(err)
+                    }
+                };
+            }
+            .instrument(span),
+        );
+
+        Handle {
+            index,
+            marker: PhantomData,
+        }
+    }
+}
+
+/**
+ * Suspend the calling component until `handle` has loaded, so the nearest
+ * `SuspenseBoundary` shows its fallback in the meantime. A failed load aborts
+ * the render with its error, for an `ErrorBoundary` to show
+ */
+pub fn use_asset<T: 'static>(handle: Handle<T>) -> Result<(), RenderError> {
+    let assets = use_context::<Assets>();
+    let loaded = use_resource(move || {
+        // Reading the state restarts the resource whenever it changes, so
+        // only a finished load ever resolves
+        let state = assets.state(handle);
+        async move {
+            match state {
+                LoadState::Loading(_) => std::future::pending().await,
+                LoadState::Ready => Ok(()),
+                LoadState::Failed(err) => Err(err),
+            }
+        }
+    });
+    loaded
+        .suspend()?
+        .cloned()
+        .map_err(|err| RenderError::Aborted(dioxus::CapturedError::from_display(err)))
+}
+
+/**
+ * True once every critical asset has finished loading
+ */
+pub fn critical_ready(assets: &[AssetStatus]) -> bool {
+    assets
+        .iter()
+        .filter(|asset| asset.critical)
+        .all(|asset| asset.state == LoadState::Ready)
+}
+
+/**
+ * Download `url` as an ArrayBuffer, reporting progress events as they arrive.
+ * Uses XHR because fetch() has no progress events without streaming the body
+ */
+pub async fn fetch_bytes(
+    url: &str,
+    mut on_progress: impl FnMut(Progress) + 'static,
+) -> Result<ArrayBuffer, String> {
+    let xhr = XmlHttpRequest::new().map_err(js_error)?;
+    xhr.open("GET", url).map_err(js_error)?;
+    xhr.set_response_type(XmlHttpRequestResponseType::Arraybuffer);
+
+    let progress_handler = Closure::<dyn FnMut(ProgressEvent)>::new(move |evt: ProgressEvent| {
+        on_progress(Progress {
+            loaded: evt.loaded(),
+            total: evt.length_computable().then(|| evt.total()),
+        });
+    });
+    xhr.set_onprogress(Some(progress_handler.as_ref().unchecked_ref()));
+
+    let done = Promise::new(&mut |resolve, reject| {
+        xhr.set_onload(Some(&resolve));
+        xhr.set_onerror(Some(&reject));
+    });
+    xhr.send().map_err(js_error)?;
+    let result = JsFuture::from(done).await;
+    // The handler must outlive the request
+    drop(progress_handler);
+    result.map_err(|_| format!("Network error loading {}", url))?;
+
+    let status = xhr.status().map_err(js_error)?;
+    if !(200..300).contains(&status) {
+        return Err(format!("HTTP {} loading {}", status, url));
+    }
+    xhr.response()
+        .map_err(js_error)?
+        .dyn_into::<ArrayBuffer>()
+        .map_err(|_| format!("Unexpected response type for {}", url))
+}
+
+/**
+ * Decode encoded image bytes (PNG, JPEG, ...) off the main thread
+ */
+pub async fn decode_image(bytes: &ArrayBuffer) -> Result<ImageBitmap, String> {
+    let blob = Blob::new_with_buffer_source_sequence(&Array::of1(bytes)).map_err(js_error)?;
+    decode_blob(&blob).await
+}
+
+/**
+ * Decode an image held in a blob, such as a file the user picked
+ */
+pub async fn decode_blob(blob: &Blob) -> Result<ImageBitmap, String> {
+    let promise = web_sys::window()
+        .ok_or_else(|| "No window".to_string())?
+        .create_image_bitmap_with_blob(blob)
+        .map_err(js_error)?;
+    JsFuture::from(promise)
+        .await
+        .map_err(js_error)?
+        .dyn_into::<ImageBitmap>()
+        .map_err(|_| "createImageBitmap returned a non-bitmap".to_string())
+}
+
+/**
+ * A decoded image's RGBA pixels, top row first, drawn through a throwaway
+ * 2D canvas
+ */
+pub fn image_pixels(image: &ImageBitmap) -> Result<(u32, u32, Vec<u8>), String> {
+    let (width, height) = (image.width(), image.height());
+    let canvas = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.create_element("canvas").ok())
+        .and_then(|element| element.dyn_into::<HtmlCanvasElement>().ok())
+        .ok_or_else(|| "Unable to create 2D canvas".to_string())?;
+    canvas.set_width(width);
+    canvas.set_height(height);
+    let context = canvas
+        .get_context("2d")
+        .ok()
+        .flatten()
+        .and_then(|context| context.dyn_into::<CanvasRenderingContext2d>().ok())
+        .ok_or_else(|| "Unable to get 2D context".to_string())?;
+    context
+        .draw_image_with_image_bitmap(image, 0.0, 0.0)
+        .map_err(js_error)?;
+    let data = context
+        .get_image_data(0, 0, width as i32, height as i32)
+        .map_err(js_error)?;
+    Ok((width, height, data.data().0))
+}
+
+fn js_error(err: JsValue) -> String {
+    err.as_string().unwrap_or_else(|| format!("{:?}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::gl_util;
+
+    const CHECKER: &[u8] = include_bytes!("../assets/textures/checker.png");
+    const GEM: &str = include_str!("../assets/models/gem.obj");
+
+    /**
+     * An object URL serving `bytes`, standing in for the server
+     */
+    fn blob_url(bytes: &[u8]) -> String {
+        let parts = Array::of1(&Uint8Array::from(bytes));
+        let blob = Blob::new_with_u8_array_sequence(&parts).unwrap();
+        web_sys::Url::create_object_url_with_blob(&blob).unwrap()
+    }
+
+    /**
+     * `Assets` on the root scope of a VirtualDom that renders nothing, which
+     * runs its downloads as tasks
+     */
+    fn headless_assets() -> (VirtualDom, Assets) {
+        let mut dom = VirtualDom::new(VNode::empty);
+        dom.rebuild_in_place();
+        let assets = dom.in_runtime(|| ScopeId::ROOT.in_runtime(Assets::default));
+        (dom, assets)
+    }
+
+    /**
+     * Poll `dom`'s tasks until `handle` is no longer loading
+     */
+    async fn finish_loading<T>(
+        dom: &mut VirtualDom,
+        assets: &Assets,
+        handle: Handle<T>,
+    ) -> LoadState {
+        loop {
+            dom.process_events();
+            let state = dom.in_runtime(|| assets.state(handle));
+            if !matches!(state, LoadState::Loading(_)) {
+                return state;
+            }
+            gloo_timers::future::TimeoutFuture::new(10).await;
+        }
+    }
+
+    #[wasm_bindgen_test]
+    async fn loads_textures_and_meshes_once() {
+        let (mut dom, assets) = headless_assets();
+        let (texture_url, mesh_url) = (blob_url(CHECKER), blob_url(GEM.as_bytes()));
+        let (texture, mesh) = dom.in_runtime(|| {
+            let texture = assets.load_texture(&texture_url);
+            // Asking again for a URL shares the first request
+            assert_eq!(assets.load_texture(&texture_url), texture);
+            (texture, assets.load_mesh(&mesh_url))
+        });
+        assert_eq!(
+            finish_loading(&mut dom, &assets, texture).await,
+            LoadState::Ready
+        );
+        assert_eq!(
+            finish_loading(&mut dom, &assets, mesh).await,
+            LoadState::Ready
+        );
+
+        let loaded = assets.mesh(mesh).unwrap();
+        let expected = obj::parse(GEM).unwrap();
+        assert_eq!(loaded.positions, expected.positions);
+        assert_eq!(loaded.indices, expected.indices);
+
+        let image = assets.image(texture).unwrap();
+        assert_eq!((image.width(), image.height()), (128, 128));
+        let gl = gl_util::test_context();
+        assert!(assets.texture(&gl, texture).is_some());
+        assert_eq!(gl.get_error(), WebGl2RenderingContext::NO_ERROR);
+    }
+
+    #[wasm_bindgen_test]
+    async fn reports_loads_that_fail_to_decode() {
+        let (mut dom, assets) = headless_assets();
+        let (texture_url, mesh_url) = (blob_url(b"not an image"), blob_url(b"f 1 2 3"));
+        let (texture, mesh) = dom.in_runtime(|| {
+            (
+                assets.load_texture(&texture_url),
+                assets.load_mesh(&mesh_url),
+            )
+        });
+        let state = finish_loading(&mut dom, &assets, texture).await;
+        assert!(matches!(state, LoadState::Failed(_)), "{:?}", state);
+        assert_eq!(
+            finish_loading(&mut dom, &assets, mesh).await,
+            LoadState::Failed("OBJ line 1: face index out of range".to_string())
+        );
+        assert!(assets.mesh(mesh).is_none());
+    }
+}
@@ -0,0 +1,143 @@
+use web_sys::{ImageBitmap, WebGl2RenderingContext};
+
+use crate::texture::Texture;
+
+// Empty pixels around each packed image so linear filtering doesn't bleed
+// neighbors into each other
+const PADDING: u32 = 2;
+
+/**
+ * Sub-rectangle of a texture in UV space, `min` at the image's top-left
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UvRect {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+}
+
+impl UvRect {
+    /**
+     * Map a UV relative to this rect (0..1 across it) into the containing texture
+     */
+    pub fn remap(&self, uv: [f32; 2]) -> [f32; 2] {
+        [
+            self.min[0] + (self.max[0] - self.min[0]) * uv[0],
+            self.min[1] + (self.max[1] - self.min[1]) * uv[1],
+        ]
+    }
+
+    /**
+     * The part of this rect that `inner` covers, with `inner` relative to this rect
+     */
+    pub fn sub_rect(&self, inner: UvRect) -> UvRect {
+        UvRect {
+            min: self.remap(inner.min),
+            max: self.remap(inner.max),
+        }
+    }
+}
+
+/**
+ * Pixel placement of each packed image plus the atlas size
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct AtlasLayout {
+    pub width: u32,
+    pub height: u32,
+    // (x, y) of each image's top-left corner, in input order
+    pub positions: Vec<(u32, u32)>,
+}
+
+/**
+ * Shelf packing: images are placed tallest first in rows across a
+ * power-of-two-wide atlas that is about as wide as it is tall
+ */
+pub fn pack(sizes: &[(u32, u32)]) -> AtlasLayout {
+    let padded = |(w, h): (u32, u32)| (w + PADDING * 2, h + PADDING * 2);
+    let area: u32 = sizes
+        .iter()
+        .map(|&size| padded(size))
+        .map(|(w, h)| w * h)
+        .sum();
+    let widest = sizes.iter().map(|&size| padded(size).0).max().unwrap_or(1);
+    let width = widest
+        .max((area as f32).sqrt().ceil() as u32)
+        .next_power_of_two();
+
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(sizes[i].1));
+
+    let mut positions = vec![(0, 0); sizes.len()];
+    let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+    for i in order {
+        let (w, h) = padded(sizes[i]);
+        if x + w > width {
+            x = 0;
+            y += shelf_height;
+            shelf_height = 0;
+        }
+        positions[i] = (x + PADDING, y + PADDING);
+        x += w;
+        shelf_height = shelf_height.max(h);
+    }
+
+    AtlasLayout {
+        width,
+        height: (y + shelf_height).max(1),
+        positions,
+    }
+}
+
+/**
+ * Several images packed into one texture, so sprites using any of them can be
+ * drawn without rebinding
+ */
+pub struct TextureAtlas {
+    texture: Texture,
+    regions: Vec<UvRect>,
+}
+
+impl TextureAtlas {
+    pub fn build(gl: &WebGl2RenderingContext, images: &[ImageBitmap]) -> Option<Self> {
+        let sizes: Vec<(u32, u32)> = images
+            .iter()
+            .map(|image| (image.width(), image.height()))
+            .collect();
+        let layout = pack(&sizes);
+        let texture = Texture::with_size(gl, layout.width as i32, layout.height as i32)?;
+
+        let (atlas_width, atlas_height) = (layout.width as f32, layout.height as f32);
+        let regions = images
+            .iter()
+            .zip(&sizes)
+            .zip(&layout.positions)
+            .map(|((image, &(w, h)), &(x, y))| {
+                texture.upload_region(gl, x as i32, y as i32, image)?;
+                // Inset by half a texel so sampling stays inside the image
+                Some(UvRect {
+                    min: [
+                        (x as f32 + 0.5) / atlas_width,
+                        (y as f32 + 0.5) / atlas_height,
+                    ],
+                    max: [
+                        ((x + w) as f32 - 0.5) / atlas_width,
+                        ((y + h) as f32 - 0.5) / atlas_height,
+                    ],
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Self { texture, regions })
+    }
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /**
+     * UV rect of the `index`-th image passed to `build`
+     */
+    pub fn region(&self, index: usize) -> UvRect {
+        self.regions[index]
+    }
+}
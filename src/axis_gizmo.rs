@@ -0,0 +1,111 @@
+use web_sys::{
+    WebGl2RenderingContext, WebGlBuffer, WebGlProgram, WebGlUniformLocation, WebGlVertexArrayObject,
+};
+
+use crate::gl_util;
+use crate::math::{self, Mat4};
+use crate::shaders;
+
+const AXES: [([f32; 3], [f32; 3]); 3] = [
+    ([1.0, 0.0, 0.0], [0.9, 0.25, 0.2]),
+    ([0.0, 1.0, 0.0], [0.3, 0.8, 0.3]),
+    ([0.0, 0.0, 1.0], [0.2, 0.4, 0.95]),
+];
+// Viewport square in pixels, and its margin from the canvas corner
+const SIZE: i32 = 80;
+const MARGIN: i32 = 8;
+
+/**
+ * XYZ axes drawn in the bottom-left corner of the viewport, turned with the
+ * camera so they show which way the world axes point
+ */
+pub struct AxisGizmo {
+    program: WebGlProgram,
+    mvp_loc: Option<WebGlUniformLocation>,
+    round_points_loc: Option<WebGlUniformLocation>,
+    vao: WebGlVertexArrayObject,
+    // Kept alive alongside the VAO that references it
+    _buffer: WebGlBuffer,
+}
+
+impl AxisGizmo {
+    pub fn new(gl: &WebGl2RenderingContext) -> Result<Self, String> {
+        let program =
+            gl_util::create_program(gl, shaders::AXIS_GIZMO_VERT, shaders::AXIS_GIZMO_FRAG)?;
+        let vao = gl
+            .create_vertex_array()
+            .ok_or_else(|| "Unable to create gizmo VAO".to_string())?;
+        let buffer = gl
+            .create_buffer()
+            .ok_or_else(|| "Unable to create gizmo buffer".to_string())?;
+
+        // Position + color: three origin-to-tip line segments, then the three tips again as points
+        let mut vertices = Vec::with_capacity(9 * 6);
+        for (axis, color) in AXES {
+            vertices.extend([0.0; 3]);
+            vertices.extend(color);
+            vertices.extend(axis);
+            vertices.extend(color);
+        }
+        for (axis, color) in AXES {
+            vertices.extend(axis);
+            vertices.extend(color);
+        }
+
+        gl.bind_vertex_array(Some(&vao));
+        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&buffer));
+        unsafe {
+            let array = js_sys::Float32Array::view(&vertices);
+            gl.buffer_data_with_array_buffer_view(
+                WebGl2RenderingContext::ARRAY_BUFFER,
+                &array,
+                WebGl2RenderingContext::STATIC_DRAW,
+            );
+        }
+        let stride = 6 * 4;
+        gl.enable_vertex_attrib_array(0);
+        gl.vertex_attrib_pointer_with_i32(0, 3, WebGl2RenderingContext::FLOAT, false, stride, 0);
+        gl.enable_vertex_attrib_array(1);
+        gl.vertex_attrib_pointer_with_i32(1, 3, WebGl2RenderingContext::FLOAT, false, stride, 12);
+        gl.bind_vertex_array(None);
+
+        Ok(Self {
+            mvp_loc: gl.get_uniform_location(&program, "modelViewProjection"),
+            round_points_loc: gl.get_uniform_location(&program, "roundPoints"),
+            program,
+            vao,
+            _buffer: buffer,
+        })
+    }
+
+    /**
+     * Draw the axes using only the rotation part of `view`, then restore the
+     * full `width` x `height` viewport
+     */
+    pub fn draw(&self, gl: &WebGl2RenderingContext, view: &Mat4, width: i32, height: i32) {
+        let mut rotation = *view;
+        rotation[12] = 0.0;
+        rotation[13] = 0.0;
+        rotation[14] = 0.0;
+        let projection = math::orthographic(-1.3, 1.3, -1.3, 1.3, -2.0, 2.0);
+        let mvp = math::multiply(&projection, &rotation);
+
+        // Its own depth, so axes pointing away sort behind the others but not behind the scene
+        gl.enable(WebGl2RenderingContext::SCISSOR_TEST);
+        gl.scissor(MARGIN, MARGIN, SIZE, SIZE);
+        gl.clear(WebGl2RenderingContext::DEPTH_BUFFER_BIT);
+        gl.disable(WebGl2RenderingContext::SCISSOR_TEST);
+        gl.viewport(MARGIN, MARGIN, SIZE, SIZE);
+        gl.enable(WebGl2RenderingContext::DEPTH_TEST);
+
+        gl.use_program(Some(&self.program));
+        gl.uniform_matrix4fv_with_f32_array(self.mvp_loc.as_ref(), false, &mvp);
+        gl.bind_vertex_array(Some(&self.vao));
+        gl.uniform1i(self.round_points_loc.as_ref(), 0);
+        gl.draw_arrays(WebGl2RenderingContext::LINES, 0, 6);
+        gl.uniform1i(self.round_points_loc.as_ref(), 1);
+        gl.draw_arrays(WebGl2RenderingContext::POINTS, 6, 3);
+        gl.bind_vertex_array(None);
+        gl.viewport(0, 0, width, height);
+    }
+}
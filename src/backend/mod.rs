@@ -0,0 +1,306 @@
+mod webgl2;
+#[cfg(feature = "webgpu")]
+mod webgpu;
+
+use web_sys::HtmlCanvasElement;
+
+use crate::shaders::ShaderProgram;
+use crate::wgsl::UniformField;
+
+pub use webgl2::WebGl2Device;
+#[cfg(feature = "webgpu")]
+pub use webgpu::WebGpuDevice;
+
+/**
+ * The graphics APIs a `Device` can sit on
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackendKind {
+    WebGl2,
+    WebGpu,
+}
+
+impl BackendKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            BackendKind::WebGl2 => "WebGL2",
+            BackendKind::WebGpu => "WebGPU",
+        }
+    }
+
+    /**
+     * The backend asked for with `?backend=webgl2` or `?backend=webgpu`
+     */
+    pub fn from_query() -> Option<Self> {
+        let pairs = crate::query::current();
+        let (_, value) = pairs.iter().find(|(key, _)| key == "backend")?;
+        match value.to_ascii_lowercase().as_str() {
+            "webgl2" => Some(BackendKind::WebGl2),
+            "webgpu" => Some(BackendKind::WebGpu),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * What a buffer feeds. Index buffers hold `u16` indices
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BufferUsage {
+    Vertex,
+    Index,
+    // Laid out as `UniformBlock` describes, for the pipeline's uniforms
+    Uniform,
+}
+
+/**
+ * A float attribute in an interleaved vertex buffer, found in the shader by
+ * name
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VertexAttribute<'a> {
+    pub name: &'a str,
+    // 1 to 4 floats
+    pub components: u32,
+    pub offset: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VertexBufferLayout<'a> {
+    pub stride: u32,
+    pub attributes: &'a [VertexAttribute<'a>],
+}
+
+/**
+ * Everything fixed about how a draw is shaded: the shader program from
+ * `shaders/` with the defines to build it with, the vertex buffers it reads,
+ * and the depth and culling state
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct PipelineDesc<'a> {
+    pub program: &'a ShaderProgram,
+    pub defines: &'a [&'a str],
+    pub vertex_buffers: &'a [VertexBufferLayout<'a>],
+    pub depth_test: bool,
+    // Counter-clockwise triangles face the camera
+    pub cull_back_faces: bool,
+}
+
+/**
+ * One indexed draw in a `Device::render` pass
+ */
+pub struct Draw<'a, D: Device + ?Sized> {
+    pub pipeline: &'a D::Pipeline,
+    pub bindings: &'a D::Bindings,
+    // In the order of the pipeline's vertex buffer layouts
+    pub vertex_buffers: &'a [&'a D::Buffer],
+    pub index_buffer: &'a D::Buffer,
+    pub index_count: u32,
+}
+
+/**
+ * A GPU to draw on a canvas with, through handles that look the same
+ * whichever API is underneath, so drawing code can be written once against
+ * this trait. Shaders are the GLSL programs under `shaders/`, translated to
+ * WGSL where needed, and their uniforms are written as bytes into a uniform
+ * buffer in the layout the pipeline reports; the WebGL2 backend reads them
+ * back out into plain uniforms
+ */
+pub trait Device {
+    type Buffer;
+    type Texture;
+    type Pipeline;
+    type Bindings;
+
+    fn kind(&self) -> BackendKind;
+
+    /**
+     * Match the drawing buffer to the canvas's new size in pixels
+     */
+    fn resize(&mut self, width: u32, height: u32);
+
+    fn create_buffer(&self, usage: BufferUsage, bytes: &[u8]) -> Result<Self::Buffer, String>;
+
+    /**
+     * Replace a buffer's contents from the start; `bytes` must fit
+     */
+    fn write_buffer(&self, buffer: &Self::Buffer, bytes: &[u8]) -> Result<(), String>;
+
+    /**
+     * An RGBA8 texture, sampled linearly and repeating
+     */
+    fn create_texture(&self, size: (u32, u32), pixels: &[u8]) -> Result<Self::Texture, String>;
+
+    fn create_pipeline(&self, desc: &PipelineDesc) -> Result<Self::Pipeline, String>;
+
+    /**
+     * Where the pipeline expects each uniform in its uniform buffer
+     */
+    fn uniform_layout<'a>(&self, pipeline: &'a Self::Pipeline) -> &'a [UniformField];
+
+    /**
+     * The uniform buffer and textures a pipeline's draws read, the textures
+     * in the order the shaders declare their samplers
+     */
+    fn create_bindings(
+        &self,
+        pipeline: &Self::Pipeline,
+        uniforms: &Self::Buffer,
+        textures: &[&Self::Texture],
+    ) -> Result<Self::Bindings, String>;
+
+    /**
+     * Clear the canvas to `clear` and depth to the far plane, then run the
+     * draws in order
+     */
+    fn render(&self, clear: [f32; 4], draws: &[Draw<Self>]) -> Result<(), String>;
+}
+
+/**
+ * Uniform values packed where a pipeline's layout puts them, ready for
+ * `Device::write_buffer`
+ */
+pub struct UniformBlock<'a> {
+    layout: &'a [UniformField],
+    bytes: Vec<u8>,
+}
+
+impl<'a> UniformBlock<'a> {
+    pub fn new(layout: &'a [UniformField]) -> Self {
+        let size = layout
+            .iter()
+            .map(|field| field.offset + field.size)
+            .max()
+            .unwrap_or(0);
+        Self {
+            layout,
+            bytes: vec![0; size.next_multiple_of(16)],
+        }
+    }
+
+    /**
+     * Write `values` into the uniform called `name`, converted for integer
+     * uniforms: components in WGSL order, so a `mat4` is 16 column-major
+     * floats and a `mat3` is 9, padded here to the three 16-byte columns
+     * WGSL wants. Uniforms the shaders don't declare are skipped, like
+     * `glUniform` with a missing location
+     */
+    pub fn set(&mut self, name: &str, values: &[f32]) -> &mut Self {
+        let Some(field) = self.layout.iter().find(|field| field.name == name) else {
+            return self;
+        };
+        let mat3 = field.wgsl_type == "mat3x3<f32>";
+        for (index, value) in values.iter().enumerate() {
+            let offset = if mat3 {
+                // Columns of a mat3 start every 16 bytes
+                field.offset + index / 3 * 16 + index % 3 * 4
+            } else {
+                field.offset + index * 4
+            };
+            if offset + 4 <= field.offset + field.size {
+                let bytes = if field.wgsl_type.contains("i32") {
+                    (*value as i32).to_le_bytes()
+                } else if field.wgsl_type.contains("u32") {
+                    (*value as u32).to_le_bytes()
+                } else {
+                    value.to_le_bytes()
+                };
+                self.bytes[offset..offset + 4].copy_from_slice(&bytes);
+            }
+        }
+        self
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/**
+ * A device for `canvas` on the backend asked for, or else the best one this
+ * browser supports: WebGPU when the app is built with the `webgpu` feature
+ * and the browser hands out an adapter, WebGL2 otherwise. A canvas can only
+ * ever have one kind of context, so WebGPU is given up on before the canvas
+ * is touched if any earlier step fails
+ */
+pub async fn create(
+    canvas: &HtmlCanvasElement,
+    preferred: Option<BackendKind>,
+) -> Result<AnyDevice, String> {
+    #[cfg(feature = "webgpu")]
+    if preferred != Some(BackendKind::WebGl2) {
+        match WebGpuDevice::new(canvas).await {
+            Ok(device) => return Ok(AnyDevice::WebGpu(device)),
+            Err(err) => {
+                tracing::info!(target: "renderer", "WebGPU unavailable, using WebGL2: {}", err)
+            }
+        }
+    }
+    #[cfg(not(feature = "webgpu"))]
+    if preferred == Some(BackendKind::WebGpu) {
+        tracing::info!(target: "renderer", "Built without the webgpu feature, using WebGL2");
+    }
+    WebGl2Device::new(canvas).map(AnyDevice::WebGl2)
+}
+
+/**
+ * The device `create` picked. Code generic over `Device` is run with the
+ * variant's device, so it is written once and compiled per backend
+ */
+pub enum AnyDevice {
+    WebGl2(WebGl2Device),
+    #[cfg(feature = "webgpu")]
+    WebGpu(WebGpuDevice),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, offset: usize, size: usize, wgsl_type: &'static str) -> UniformField {
+        UniformField {
+            name: name.to_string(),
+            offset,
+            size,
+            wgsl_type,
+        }
+    }
+
+    fn floats(bytes: &[u8]) -> Vec<f32> {
+        bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn packs_uniforms_at_their_offsets() {
+        let layout = [
+            field("scale", 0, 4, "f32"),
+            field("tint", 16, 12, "vec3<f32>"),
+        ];
+        let mut block = UniformBlock::new(&layout);
+        block.set("tint", &[0.5, 0.25, 1.0]).set("scale", &[2.0]);
+        // Unknown names are ignored
+        block.set("missing", &[9.0]);
+        assert_eq!(block.bytes().len(), 32);
+        assert_eq!(
+            floats(block.bytes()),
+            [2.0, 0.0, 0.0, 0.0, 0.5, 0.25, 1.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn pads_mat3_columns() {
+        let layout = [field("normalMatrix", 0, 48, "mat3x3<f32>")];
+        let mut block = UniformBlock::new(&layout);
+        block.set(
+            "normalMatrix",
+            &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0],
+        );
+        assert_eq!(
+            floats(block.bytes()),
+            [1.0, 2.0, 3.0, 0.0, 4.0, 5.0, 6.0, 0.0, 7.0, 8.0, 9.0, 0.0]
+        );
+    }
+}
@@ -0,0 +1,490 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use web_sys::{
+    HtmlCanvasElement, WebGl2RenderingContext, WebGlBuffer, WebGlProgram, WebGlTexture,
+    WebGlUniformLocation, WebGlVertexArrayObject,
+};
+
+use super::{BackendKind, BufferUsage, Device, Draw, PipelineDesc};
+use crate::gl_errors;
+use crate::gl_util;
+use crate::glsl::{Interface, ShaderStage};
+use crate::wgsl::UniformField;
+
+/**
+ * `Device` on a WebGL2 context
+ */
+pub struct WebGl2Device {
+    gl: WebGl2RenderingContext,
+    size: (u32, u32),
+}
+
+impl WebGl2Device {
+    pub fn new(canvas: &HtmlCanvasElement) -> Result<Self, String> {
+        let gl = gl_util::webgl2_context(canvas)?;
+        Ok(Self {
+            gl,
+            size: (canvas.width(), canvas.height()),
+        })
+    }
+}
+
+/**
+ * A GL buffer, or for uniforms the bytes themselves: plain uniforms are set
+ * one by one at draw time, so there is nothing to upload
+ */
+pub enum Buffer {
+    Gl(WebGlBuffer, u32),
+    Uniforms(Rc<RefCell<Vec<u8>>>),
+}
+
+// Location, components and offset of an attribute
+type AttributePointer = (u32, i32, i32);
+
+pub struct Pipeline {
+    program: WebGlProgram,
+    vao: WebGlVertexArrayObject,
+    // Per vertex buffer: its stride and attributes
+    vertex_buffers: Vec<(i32, Vec<AttributePointer>)>,
+    uniforms: Vec<UniformField>,
+    locations: Vec<Option<WebGlUniformLocation>>,
+    samplers: Vec<Option<WebGlUniformLocation>>,
+    depth_test: bool,
+    cull_back_faces: bool,
+}
+
+pub struct Bindings {
+    uniforms: Rc<RefCell<Vec<u8>>>,
+    textures: Vec<WebGlTexture>,
+}
+
+impl Device for WebGl2Device {
+    type Buffer = Buffer;
+    type Texture = WebGlTexture;
+    type Pipeline = Pipeline;
+    type Bindings = Bindings;
+
+    fn kind(&self) -> BackendKind {
+        BackendKind::WebGl2
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        self.size = (width, height);
+    }
+
+    fn create_buffer(&self, usage: BufferUsage, bytes: &[u8]) -> Result<Buffer, String> {
+        let target = match usage {
+            BufferUsage::Vertex => WebGl2RenderingContext::ARRAY_BUFFER,
+            BufferUsage::Index => WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER,
+            BufferUsage::Uniform => {
+                return Ok(Buffer::Uniforms(Rc::new(RefCell::new(bytes.to_vec()))))
+            }
+        };
+        let buffer = self
+            .gl
+            .create_buffer()
+            .ok_or_else(|| "Unable to create buffer".to_string())?;
+        // No VAO may be bound, or the index buffer would end up in it
+        self.gl.bind_vertex_array(None);
+        self.gl.bind_buffer(target, Some(&buffer));
+        self.gl
+            .buffer_data_with_u8_array(target, bytes, WebGl2RenderingContext::STATIC_DRAW);
+        self.gl.bind_buffer(target, None);
+        Ok(Buffer::Gl(buffer, target))
+    }
+
+    fn write_buffer(&self, buffer: &Buffer, bytes: &[u8]) -> Result<(), String> {
+        match buffer {
+            Buffer::Gl(buffer, target) => {
+                self.gl.bind_vertex_array(None);
+                self.gl.bind_buffer(*target, Some(buffer));
+                self.gl
+                    .buffer_sub_data_with_i32_and_u8_array(*target, 0, bytes);
+                self.gl.bind_buffer(*target, None);
+            }
+            Buffer::Uniforms(contents) => {
+                let mut contents = contents.borrow_mut();
+                if bytes.len() > contents.len() {
+                    return Err(format!(
+                        "{} bytes don't fit a {} byte uniform buffer",
+                        bytes.len(),
+                        contents.len()
+                    ));
+                }
+                contents[..bytes.len()].copy_from_slice(bytes);
+            }
+        }
+        Ok(())
+    }
+
+    fn create_texture(
+        &self,
+        (width, height): (u32, u32),
+        pixels: &[u8],
+    ) -> Result<WebGlTexture, String> {
+        let gl = &self.gl;
+        let texture = gl
+            .create_texture()
+            .ok_or_else(|| "Unable to create texture".to_string())?;
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&texture));
+        gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            WebGl2RenderingContext::TEXTURE_2D,
+            0,
+            WebGl2RenderingContext::RGBA8 as i32,
+            width as i32,
+            height as i32,
+            0,
+            WebGl2RenderingContext::RGBA,
+            WebGl2RenderingContext::UNSIGNED_BYTE,
+            Some(pixels),
+        )
+        .map_err(|err| format!("Uploading the texture failed: {:?}", err))?;
+        for (parameter, value) in [
+            (
+                WebGl2RenderingContext::TEXTURE_MIN_FILTER,
+                WebGl2RenderingContext::LINEAR,
+            ),
+            (
+                WebGl2RenderingContext::TEXTURE_MAG_FILTER,
+                WebGl2RenderingContext::LINEAR,
+            ),
+            (
+                WebGl2RenderingContext::TEXTURE_WRAP_S,
+                WebGl2RenderingContext::REPEAT,
+            ),
+            (
+                WebGl2RenderingContext::TEXTURE_WRAP_T,
+                WebGl2RenderingContext::REPEAT,
+            ),
+        ] {
+            gl.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, parameter, value as i32);
+        }
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+        Ok(texture)
+    }
+
+    fn create_pipeline(&self, desc: &PipelineDesc) -> Result<Pipeline, String> {
+        let gl = &self.gl;
+        let defines: String = desc
+            .defines
+            .iter()
+            .map(|define| format!("#define {}\n", define))
+            .collect();
+        // Defines have to follow the #version line
+        let with_defines = |source: &str| {
+            let (version, body) = source.split_once('\n').unwrap_or((source, ""));
+            format!("{}\n{}{}", version, defines, body)
+        };
+        let program = gl_util::create_program(
+            gl,
+            &with_defines(desc.program.vertex),
+            &with_defines(desc.program.fragment),
+        )?;
+
+        let mut vertex_buffers = Vec::new();
+        for layout in desc.vertex_buffers {
+            let mut attributes = Vec::new();
+            for attribute in layout.attributes {
+                let location = gl.get_attrib_location(&program, attribute.name);
+                // Attributes the compiler dropped as unused are fine to skip
+                if location >= 0 {
+                    attributes.push((
+                        location as u32,
+                        attribute.components as i32,
+                        attribute.offset as i32,
+                    ));
+                }
+            }
+            vertex_buffers.push((layout.stride as i32, attributes));
+        }
+        let vao = gl
+            .create_vertex_array()
+            .ok_or_else(|| "Unable to create vertex array".to_string())?;
+
+        let uniforms = active_uniforms(gl, &program);
+        let locations = uniforms
+            .iter()
+            .map(|field| gl.get_uniform_location(&program, &field.name))
+            .collect();
+        // Textures are bound in declaration order, as on WebGPU, which GL's
+        // list of active uniforms doesn't keep
+        let interface = Interface::new(&[
+            (ShaderStage::Vertex, desc.program.vertex),
+            (ShaderStage::Fragment, desc.program.fragment),
+        ]);
+        let samplers = interface
+            .samplers
+            .iter()
+            .map(|sampler| gl.get_uniform_location(&program, &sampler.name))
+            .collect();
+        Ok(Pipeline {
+            program,
+            vao,
+            vertex_buffers,
+            uniforms,
+            locations,
+            samplers,
+            depth_test: desc.depth_test,
+            cull_back_faces: desc.cull_back_faces,
+        })
+    }
+
+    fn uniform_layout<'a>(&self, pipeline: &'a Pipeline) -> &'a [UniformField] {
+        &pipeline.uniforms
+    }
+
+    fn create_bindings(
+        &self,
+        pipeline: &Pipeline,
+        uniforms: &Buffer,
+        textures: &[&WebGlTexture],
+    ) -> Result<Bindings, String> {
+        let Buffer::Uniforms(uniforms) = uniforms else {
+            return Err("Bindings need a uniform buffer".to_string());
+        };
+        if textures.len() != pipeline.samplers.len() {
+            return Err(format!(
+                "The pipeline samples {} textures, {} given",
+                pipeline.samplers.len(),
+                textures.len()
+            ));
+        }
+        Ok(Bindings {
+            uniforms: uniforms.clone(),
+            textures: textures.iter().map(|texture| (*texture).clone()).collect(),
+        })
+    }
+
+    fn render(&self, clear: [f32; 4], draws: &[Draw<Self>]) -> Result<(), String> {
+        let gl = &self.gl;
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+        gl.viewport(0, 0, self.size.0 as i32, self.size.1 as i32);
+        gl.clear_color(clear[0], clear[1], clear[2], clear[3]);
+        gl.clear_depth(1.0);
+        gl.depth_mask(true);
+        gl.clear(
+            WebGl2RenderingContext::COLOR_BUFFER_BIT | WebGl2RenderingContext::DEPTH_BUFFER_BIT,
+        );
+
+        for draw in draws {
+            let pipeline = draw.pipeline;
+            gl.use_program(Some(&pipeline.program));
+            set_capability(gl, WebGl2RenderingContext::DEPTH_TEST, pipeline.depth_test);
+            set_capability(
+                gl,
+                WebGl2RenderingContext::CULL_FACE,
+                pipeline.cull_back_faces,
+            );
+
+            gl.bind_vertex_array(Some(&pipeline.vao));
+            for ((stride, attributes), buffer) in
+                pipeline.vertex_buffers.iter().zip(draw.vertex_buffers)
+            {
+                let Buffer::Gl(buffer, _) = buffer else {
+                    return Err("A vertex buffer slot holds a uniform buffer".to_string());
+                };
+                gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(buffer));
+                for &(location, components, offset) in attributes {
+                    gl.enable_vertex_attrib_array(location);
+                    gl.vertex_attrib_pointer_with_i32(
+                        location,
+                        components,
+                        WebGl2RenderingContext::FLOAT,
+                        false,
+                        *stride,
+                        offset,
+                    );
+                }
+            }
+            let Buffer::Gl(index_buffer, _) = draw.index_buffer else {
+                return Err("The index buffer is a uniform buffer".to_string());
+            };
+            gl.bind_buffer(
+                WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER,
+                Some(index_buffer),
+            );
+
+            let bytes = draw.bindings.uniforms.borrow();
+            for (field, location) in pipeline.uniforms.iter().zip(&pipeline.locations) {
+                if location.is_some() {
+                    set_uniform(gl, location.as_ref(), field, &bytes);
+                }
+            }
+            for (unit, (texture, location)) in draw
+                .bindings
+                .textures
+                .iter()
+                .zip(&pipeline.samplers)
+                .enumerate()
+            {
+                gl.active_texture(WebGl2RenderingContext::TEXTURE0 + unit as u32);
+                gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(texture));
+                gl.uniform1i(location.as_ref(), unit as i32);
+            }
+
+            gl.draw_elements_with_i32(
+                WebGl2RenderingContext::TRIANGLES,
+                draw.index_count as i32,
+                WebGl2RenderingContext::UNSIGNED_SHORT,
+                0,
+            );
+        }
+        gl.bind_vertex_array(None);
+        gl_errors::check(gl, "backend: draw");
+        Ok(())
+    }
+}
+
+fn set_capability(gl: &WebGl2RenderingContext, capability: u32, enabled: bool) {
+    if enabled {
+        gl.enable(capability);
+    } else {
+        gl.disable(capability);
+    }
+}
+
+/**
+ * The program's active non-sampler uniforms, laid out one after another
+ * with WGSL's alignment rules so `UniformBlock` packs them like it does for
+ * WebGPU. Uniforms the compiler dropped as unused aren't in the layout
+ */
+fn active_uniforms(gl: &WebGl2RenderingContext, program: &WebGlProgram) -> Vec<UniformField> {
+    let count = gl
+        .get_program_parameter(program, WebGl2RenderingContext::ACTIVE_UNIFORMS)
+        .as_f64()
+        .unwrap_or(0.0) as u32;
+    let active: Vec<(String, u32)> = (0..count)
+        .filter_map(|index| gl.get_active_uniform(program, index))
+        // Arrays would need a field per element
+        .filter(|info| info.size() == 1)
+        .map(|info| (info.name(), info.type_()))
+        .collect();
+    lay_out(&active)
+}
+
+/**
+ * Fields for uniforms of the given GL types, in order and aligned as WGSL
+ * would align them. Types with no plain-uniform equivalent, like samplers,
+ * are left out
+ */
+fn lay_out(uniforms: &[(String, u32)]) -> Vec<UniformField> {
+    type Gl = WebGl2RenderingContext;
+    let mut fields = Vec::new();
+    let mut offset = 0;
+    for (name, gl_type) in uniforms {
+        // WGSL type, size and alignment; bools are set like unsigned ints
+        let (wgsl_type, size, align) = match *gl_type {
+            Gl::FLOAT => ("f32", 4, 4),
+            Gl::FLOAT_VEC2 => ("vec2<f32>", 8, 8),
+            Gl::FLOAT_VEC3 => ("vec3<f32>", 12, 16),
+            Gl::FLOAT_VEC4 => ("vec4<f32>", 16, 16),
+            Gl::INT => ("i32", 4, 4),
+            Gl::INT_VEC2 => ("vec2<i32>", 8, 8),
+            Gl::INT_VEC3 => ("vec3<i32>", 12, 16),
+            Gl::INT_VEC4 => ("vec4<i32>", 16, 16),
+            Gl::UNSIGNED_INT | Gl::BOOL => ("u32", 4, 4),
+            Gl::UNSIGNED_INT_VEC2 | Gl::BOOL_VEC2 => ("vec2<u32>", 8, 8),
+            Gl::UNSIGNED_INT_VEC3 | Gl::BOOL_VEC3 => ("vec3<u32>", 12, 16),
+            Gl::UNSIGNED_INT_VEC4 | Gl::BOOL_VEC4 => ("vec4<u32>", 16, 16),
+            Gl::FLOAT_MAT2 => ("mat2x2<f32>", 16, 8),
+            Gl::FLOAT_MAT3 => ("mat3x3<f32>", 48, 16),
+            Gl::FLOAT_MAT4 => ("mat4x4<f32>", 64, 16),
+            _ => continue,
+        };
+        offset = usize::next_multiple_of(offset, align);
+        fields.push(UniformField {
+            name: name.clone(),
+            offset,
+            size,
+            wgsl_type,
+        });
+        offset += size;
+    }
+    fields
+}
+
+/**
+ * Set one uniform from its bytes in the WGSL-layout uniform buffer
+ */
+fn set_uniform(
+    gl: &WebGl2RenderingContext,
+    location: Option<&WebGlUniformLocation>,
+    field: &UniformField,
+    bytes: &[u8],
+) {
+    let words = |count: usize| bytes[field.offset..field.offset + count * 4].chunks_exact(4);
+    let floats = |count: usize| -> Vec<f32> {
+        words(count)
+            .map(|word| f32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect()
+    };
+    let ints = |count: usize| -> Vec<i32> {
+        words(count)
+            .map(|word| i32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect()
+    };
+    let uints = |count: usize| -> Vec<u32> {
+        words(count)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect()
+    };
+    match field.wgsl_type {
+        "f32" => gl.uniform1fv_with_f32_array(location, &floats(1)),
+        "vec2<f32>" => gl.uniform2fv_with_f32_array(location, &floats(2)),
+        "vec3<f32>" => gl.uniform3fv_with_f32_array(location, &floats(3)),
+        "vec4<f32>" => gl.uniform4fv_with_f32_array(location, &floats(4)),
+        "i32" => gl.uniform1iv_with_i32_array(location, &ints(1)),
+        "vec2<i32>" => gl.uniform2iv_with_i32_array(location, &ints(2)),
+        "vec3<i32>" => gl.uniform3iv_with_i32_array(location, &ints(3)),
+        "vec4<i32>" => gl.uniform4iv_with_i32_array(location, &ints(4)),
+        "u32" => gl.uniform1uiv_with_u32_array(location, &uints(1)),
+        "vec2<u32>" => gl.uniform2uiv_with_u32_array(location, &uints(2)),
+        "vec3<u32>" => gl.uniform3uiv_with_u32_array(location, &uints(3)),
+        "vec4<u32>" => gl.uniform4uiv_with_u32_array(location, &uints(4)),
+        "mat2x2<f32>" => gl.uniform_matrix2fv_with_f32_array(location, false, &floats(4)),
+        "mat3x3<f32>" => {
+            // Three 16-byte columns; GL wants the nine floats packed
+            let padded = floats(12);
+            let packed: Vec<f32> = padded
+                .chunks_exact(4)
+                .flat_map(|column| column[..3].to_vec())
+                .collect();
+            gl.uniform_matrix3fv_with_f32_array(location, false, &packed);
+        }
+        "mat4x4<f32>" => gl.uniform_matrix4fv_with_f32_array(location, false, &floats(16)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lays_out_active_uniforms_like_wgsl() {
+        type Gl = WebGl2RenderingContext;
+        let active = [
+            ("time", Gl::FLOAT),
+            ("image", Gl::SAMPLER_2D),
+            ("tint", Gl::FLOAT_VEC3),
+            ("flipped", Gl::BOOL),
+            ("normalMatrix", Gl::FLOAT_MAT3),
+        ]
+        .map(|(name, gl_type)| (name.to_string(), gl_type));
+        let fields = lay_out(&active);
+        let layout: Vec<(&str, usize, &str)> = fields
+            .iter()
+            .map(|field| (field.name.as_str(), field.offset, field.wgsl_type))
+            .collect();
+        // The sampler is skipped, and the bool packs after the vec3
+        assert_eq!(
+            layout,
+            [
+                ("time", 0, "f32"),
+                ("tint", 16, "vec3<f32>"),
+                ("flipped", 28, "u32"),
+                ("normalMatrix", 32, "mat3x3<f32>"),
+            ]
+        );
+    }
+}
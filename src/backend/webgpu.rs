@@ -0,0 +1,409 @@
+use std::cell::RefCell;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::js_sys::JsNullable;
+use web_sys::{
+    gpu_buffer_usage, gpu_shader_stage, gpu_texture_usage, GpuAddressMode, GpuBindGroup,
+    GpuBindGroupDescriptor, GpuBindGroupEntry, GpuBindGroupLayout, GpuBindGroupLayoutDescriptor,
+    GpuBindGroupLayoutEntry, GpuBuffer, GpuBufferBinding, GpuBufferBindingLayout,
+    GpuBufferDescriptor, GpuCanvasAlphaMode, GpuCanvasConfiguration, GpuCanvasContext,
+    GpuColorDict, GpuColorTargetState, GpuCompareFunction, GpuCullMode, GpuDepthStencilState,
+    GpuDevice, GpuExtent3dDict, GpuFilterMode, GpuFragmentState, GpuFrontFace, GpuIndexFormat,
+    GpuLoadOp, GpuPipelineLayoutDescriptor, GpuPrimitiveState, GpuPrimitiveTopology, GpuQueue,
+    GpuRenderPassColorAttachment, GpuRenderPassDepthStencilAttachment, GpuRenderPassDescriptor,
+    GpuRenderPipeline, GpuRenderPipelineDescriptor, GpuSampler, GpuSamplerBindingLayout,
+    GpuSamplerDescriptor, GpuShaderModuleDescriptor, GpuStoreOp, GpuTexelCopyBufferLayout,
+    GpuTexelCopyTextureInfo, GpuTextureBindingLayout, GpuTextureDescriptor, GpuTextureFormat,
+    GpuTextureView, GpuUncapturedErrorEvent, GpuVertexAttribute, GpuVertexBufferLayout,
+    GpuVertexFormat, GpuVertexState, HtmlCanvasElement,
+};
+
+use super::{BackendKind, BufferUsage, Device, Draw, PipelineDesc};
+use crate::gl_errors;
+use crate::wgsl::{self, UniformField};
+
+const DEPTH_FORMAT: GpuTextureFormat = GpuTextureFormat::Depth24plus;
+
+/**
+ * `Device` on WebGPU, drawing into the canvas's `webgpu` context
+ */
+pub struct WebGpuDevice {
+    device: GpuDevice,
+    queue: GpuQueue,
+    context: GpuCanvasContext,
+    format: GpuTextureFormat,
+    // Recreated whenever the canvas's texture changes size
+    depth: RefCell<Option<(GpuTextureView, (u32, u32))>>,
+}
+
+impl WebGpuDevice {
+    pub async fn new(canvas: &HtmlCanvasElement) -> Result<Self, String> {
+        let navigator = web_sys::window()
+            .ok_or_else(|| "No window".to_string())?
+            .navigator();
+        // Where WebGPU hasn't shipped `navigator.gpu` is simply missing
+        let has_gpu =
+            js_sys::Reflect::get(&navigator, &"gpu".into()).is_ok_and(|gpu| !gpu.is_undefined());
+        if !has_gpu {
+            return Err("this browser has no WebGPU".to_string());
+        }
+        let gpu = navigator.gpu();
+        let adapter = JsFuture::from(gpu.request_adapter())
+            .await
+            .map_err(|err| format!("Requesting an adapter failed: {:?}", err))?
+            .into_option()
+            .ok_or_else(|| "no suitable GPU adapter".to_string())?;
+        let device: GpuDevice = JsFuture::from(adapter.request_device())
+            .await
+            .map_err(|err| format!("Requesting a device failed: {:?}", err))?;
+
+        let context = canvas
+            .get_context("webgpu")
+            .map_err(|err| format!("Unable to create a WebGPU context: {:?}", err))?
+            .ok_or_else(|| "the canvas already has a different kind of context".to_string())?
+            .unchecked_into::<GpuCanvasContext>();
+        let format = gpu.get_preferred_canvas_format();
+        let configuration = GpuCanvasConfiguration::new(&device, format);
+        configuration.set_alpha_mode(GpuCanvasAlphaMode::Opaque);
+        context
+            .configure(&configuration)
+            .map_err(|err| format!("Configuring the canvas failed: {:?}", err))?;
+
+        // Validation errors aren't thrown but raised later as events
+        let on_error =
+            Closure::<dyn FnMut(GpuUncapturedErrorEvent)>::new(|evt: GpuUncapturedErrorEvent| {
+                gl_errors::report("backend: webgpu", evt.error().message());
+            });
+        device.set_onuncapturederror(Some(on_error.into_js_value().unchecked_ref()));
+
+        tracing::info!(target: "renderer", "WebGPU device ready, canvas format {:?}", format);
+        Ok(Self {
+            queue: device.queue(),
+            device,
+            context,
+            format,
+            depth: RefCell::new(None),
+        })
+    }
+
+    /**
+     * The depth buffer for a canvas texture of `size`
+     */
+    fn depth_view(&self, size: (u32, u32)) -> Result<GpuTextureView, String> {
+        if let Some((view, depth_size)) = &*self.depth.borrow() {
+            if *depth_size == size {
+                return Ok(view.clone());
+            }
+        }
+        let texture = self
+            .device
+            .create_texture(&GpuTextureDescriptor::new_with_gpu_extent_3d_dict(
+                DEPTH_FORMAT,
+                &extent(size),
+                gpu_texture_usage::RENDER_ATTACHMENT,
+            ))
+            .map_err(|err| format!("Unable to create depth buffer: {:?}", err))?;
+        let view = texture
+            .create_view()
+            .map_err(|err| format!("Unable to view depth buffer: {:?}", err))?;
+        *self.depth.borrow_mut() = Some((view.clone(), size));
+        Ok(view)
+    }
+}
+
+fn extent((width, height): (u32, u32)) -> GpuExtent3dDict {
+    let extent = GpuExtent3dDict::new(width);
+    extent.set_height(height);
+    extent
+}
+
+pub struct Buffer {
+    buffer: GpuBuffer,
+    size: usize,
+}
+
+pub struct Texture {
+    view: GpuTextureView,
+    sampler: GpuSampler,
+}
+
+pub struct Pipeline {
+    pipeline: GpuRenderPipeline,
+    bind_group_layout: GpuBindGroupLayout,
+    uniforms: Vec<UniformField>,
+    textures: usize,
+}
+
+pub struct Bindings(GpuBindGroup);
+
+impl Device for WebGpuDevice {
+    type Buffer = Buffer;
+    type Texture = Texture;
+    type Pipeline = Pipeline;
+    type Bindings = Bindings;
+
+    fn kind(&self) -> BackendKind {
+        BackendKind::WebGpu
+    }
+
+    fn resize(&mut self, _width: u32, _height: u32) {
+        // The canvas's current texture always has the canvas's size, and the
+        // depth buffer follows it
+    }
+
+    fn create_buffer(&self, usage: BufferUsage, bytes: &[u8]) -> Result<Buffer, String> {
+        let usage = match usage {
+            BufferUsage::Vertex => gpu_buffer_usage::VERTEX,
+            BufferUsage::Index => gpu_buffer_usage::INDEX,
+            BufferUsage::Uniform => gpu_buffer_usage::UNIFORM,
+        };
+        // Writes have to be whole 4-byte words
+        let size = bytes.len().next_multiple_of(4).max(4);
+        let buffer = self
+            .device
+            .create_buffer(&GpuBufferDescriptor::new(
+                size as u32,
+                usage | gpu_buffer_usage::COPY_DST,
+            ))
+            .map_err(|err| format!("Unable to create buffer: {:?}", err))?;
+        let buffer = Buffer { buffer, size };
+        self.write_buffer(&buffer, bytes)?;
+        Ok(buffer)
+    }
+
+    fn write_buffer(&self, buffer: &Buffer, bytes: &[u8]) -> Result<(), String> {
+        let mut padded = bytes.to_vec();
+        padded.resize(bytes.len().next_multiple_of(4), 0);
+        if padded.len() > buffer.size {
+            return Err(format!(
+                "{} bytes don't fit a {} byte buffer",
+                bytes.len(),
+                buffer.size
+            ));
+        }
+        self.queue
+            .write_buffer_with_u32_and_u8_slice(&buffer.buffer, 0, &padded)
+            .map_err(|err| format!("Writing a buffer failed: {:?}", err))
+    }
+
+    fn create_texture(&self, size: (u32, u32), pixels: &[u8]) -> Result<Texture, String> {
+        let texture = self
+            .device
+            .create_texture(&GpuTextureDescriptor::new_with_gpu_extent_3d_dict(
+                GpuTextureFormat::Rgba8unorm,
+                &extent(size),
+                gpu_texture_usage::TEXTURE_BINDING | gpu_texture_usage::COPY_DST,
+            ))
+            .map_err(|err| format!("Unable to create texture: {:?}", err))?;
+        let layout = GpuTexelCopyBufferLayout::new();
+        layout.set_bytes_per_row(size.0 * 4);
+        self.queue
+            .write_texture_with_u8_slice_and_gpu_extent_3d_dict(
+                &GpuTexelCopyTextureInfo::new(&texture),
+                pixels,
+                &layout,
+                &extent(size),
+            )
+            .map_err(|err| format!("Uploading the texture failed: {:?}", err))?;
+
+        let sampler = GpuSamplerDescriptor::new();
+        sampler.set_mag_filter(GpuFilterMode::Linear);
+        sampler.set_min_filter(GpuFilterMode::Linear);
+        sampler.set_address_mode_u(GpuAddressMode::Repeat);
+        sampler.set_address_mode_v(GpuAddressMode::Repeat);
+        Ok(Texture {
+            view: texture
+                .create_view()
+                .map_err(|err| format!("Unable to view texture: {:?}", err))?,
+            sampler: self.device.create_sampler_with_descriptor(&sampler),
+        })
+    }
+
+    fn create_pipeline(&self, desc: &PipelineDesc) -> Result<Pipeline, String> {
+        let translated = wgsl::translate(desc.program.vertex, desc.program.fragment, desc.defines)?;
+        let [vertex_module, fragment_module] =
+            [&translated.vertex, &translated.fragment].map(|source| {
+                let module = self
+                    .device
+                    .create_shader_module(&GpuShaderModuleDescriptor::new(source));
+                module.set_label(desc.program.name);
+                module
+            });
+
+        // Laid out the way the translation assigns bindings
+        let visibility = gpu_shader_stage::VERTEX | gpu_shader_stage::FRAGMENT;
+        let mut entries = Vec::new();
+        if !translated.uniforms.is_empty() {
+            let entry = GpuBindGroupLayoutEntry::new(0, visibility);
+            entry.set_buffer(&GpuBufferBindingLayout::new());
+            entries.push(entry);
+        }
+        for index in 0..translated.textures.len() as u32 {
+            let texture = GpuBindGroupLayoutEntry::new(1 + index * 2, visibility);
+            texture.set_texture(&GpuTextureBindingLayout::new());
+            let sampler = GpuBindGroupLayoutEntry::new(2 + index * 2, visibility);
+            sampler.set_sampler(&GpuSamplerBindingLayout::new());
+            entries.extend([texture, sampler]);
+        }
+        let bind_group_layout = self
+            .device
+            .create_bind_group_layout(&GpuBindGroupLayoutDescriptor::new(&entries))
+            .map_err(|err| format!("Unable to create bind group layout: {:?}", err))?;
+        let layout = self
+            .device
+            .create_pipeline_layout(&GpuPipelineLayoutDescriptor::new(&[JsNullable::wrap(
+                bind_group_layout.clone(),
+            )]));
+
+        let mut buffers = Vec::new();
+        for buffer in desc.vertex_buffers {
+            let attributes: Vec<GpuVertexAttribute> = buffer
+                .attributes
+                .iter()
+                .filter_map(|attribute| {
+                    // Data the shaders don't read can stay in the buffer
+                    let input = translated
+                        .attributes
+                        .iter()
+                        .find(|input| input.name == attribute.name)?;
+                    let format = match attribute.components {
+                        1 => GpuVertexFormat::Float32,
+                        2 => GpuVertexFormat::Float32x2,
+                        3 => GpuVertexFormat::Float32x3,
+                        _ => GpuVertexFormat::Float32x4,
+                    };
+                    Some(GpuVertexAttribute::new(
+                        format,
+                        attribute.offset,
+                        input.location,
+                    ))
+                })
+                .collect();
+            buffers.push(JsNullable::wrap(GpuVertexBufferLayout::new(
+                buffer.stride,
+                &attributes,
+            )));
+        }
+        let vertex = GpuVertexState::new(&vertex_module);
+        vertex.set_entry_point("vs_main");
+        vertex.set_buffers(&buffers);
+        let fragment = GpuFragmentState::new(
+            &fragment_module,
+            &[JsNullable::wrap(GpuColorTargetState::new(self.format))],
+        );
+        fragment.set_entry_point("fs_main");
+
+        let primitive = GpuPrimitiveState::new();
+        primitive.set_topology(GpuPrimitiveTopology::TriangleList);
+        primitive.set_front_face(GpuFrontFace::Ccw);
+        primitive.set_cull_mode(if desc.cull_back_faces {
+            GpuCullMode::Back
+        } else {
+            GpuCullMode::None
+        });
+        // Every pass has a depth buffer; without the test it is left alone
+        let depth = GpuDepthStencilState::new(DEPTH_FORMAT);
+        depth.set_depth_write_enabled(desc.depth_test);
+        depth.set_depth_compare(if desc.depth_test {
+            GpuCompareFunction::Less
+        } else {
+            GpuCompareFunction::Always
+        });
+
+        let pipeline = GpuRenderPipelineDescriptor::new(&layout, &vertex);
+        pipeline.set_label(desc.program.name);
+        pipeline.set_fragment(&fragment);
+        pipeline.set_primitive(&primitive);
+        pipeline.set_depth_stencil(&depth);
+        let pipeline = self
+            .device
+            .create_render_pipeline(&pipeline)
+            .map_err(|err| format!("Unable to create pipeline: {:?}", err))?;
+        Ok(Pipeline {
+            pipeline,
+            bind_group_layout,
+            uniforms: translated.uniforms,
+            textures: translated.textures.len(),
+        })
+    }
+
+    fn uniform_layout<'a>(&self, pipeline: &'a Pipeline) -> &'a [UniformField] {
+        &pipeline.uniforms
+    }
+
+    fn create_bindings(
+        &self,
+        pipeline: &Pipeline,
+        uniforms: &Buffer,
+        textures: &[&Texture],
+    ) -> Result<Bindings, String> {
+        if textures.len() != pipeline.textures {
+            return Err(format!(
+                "The pipeline samples {} textures, {} given",
+                pipeline.textures,
+                textures.len()
+            ));
+        }
+        let mut entries = Vec::new();
+        if !pipeline.uniforms.is_empty() {
+            entries.push(GpuBindGroupEntry::new_with_gpu_buffer_binding(
+                0,
+                &GpuBufferBinding::new(&uniforms.buffer),
+            ));
+        }
+        for (index, texture) in textures.iter().enumerate() {
+            let binding = 1 + index as u32 * 2;
+            entries.push(GpuBindGroupEntry::new_with_gpu_texture_view(
+                binding,
+                &texture.view,
+            ));
+            entries.push(GpuBindGroupEntry::new(binding + 1, &texture.sampler));
+        }
+        Ok(Bindings(self.device.create_bind_group(
+            &GpuBindGroupDescriptor::new(&entries, &pipeline.bind_group_layout),
+        )))
+    }
+
+    fn render(&self, clear: [f32; 4], draws: &[Draw<Self>]) -> Result<(), String> {
+        let target = self
+            .context
+            .get_current_texture()
+            .map_err(|err| format!("No canvas texture to draw to: {:?}", err))?;
+        let view = target
+            .create_view()
+            .map_err(|err| format!("Unable to view the canvas texture: {:?}", err))?;
+        let depth = self.depth_view((target.width(), target.height()))?;
+
+        let color = GpuRenderPassColorAttachment::new_with_gpu_texture_view(
+            GpuLoadOp::Clear,
+            GpuStoreOp::Store,
+            &view,
+        );
+        let [r, g, b, a] = clear.map(f64::from);
+        color.set_clear_value_gpu_color_dict(&GpuColorDict::new(a, b, g, r));
+        let depth = GpuRenderPassDepthStencilAttachment::new_with_gpu_texture_view(&depth);
+        depth.set_depth_clear_value(1.0);
+        depth.set_depth_load_op(GpuLoadOp::Clear);
+        depth.set_depth_store_op(GpuStoreOp::Discard);
+        let pass = GpuRenderPassDescriptor::new(&[JsNullable::wrap(color)]);
+        pass.set_depth_stencil_attachment(&depth);
+
+        let encoder = self.device.create_command_encoder();
+        let pass = encoder
+            .begin_render_pass(&pass)
+            .map_err(|err| format!("Unable to begin render pass: {:?}", err))?;
+        for draw in draws {
+            pass.set_pipeline(&draw.pipeline.pipeline);
+            pass.set_bind_group(0, Some(&draw.bindings.0));
+            for (slot, buffer) in draw.vertex_buffers.iter().enumerate() {
+                pass.set_vertex_buffer(slot as u32, Some(&buffer.buffer));
+            }
+            pass.set_index_buffer(&draw.index_buffer.buffer, GpuIndexFormat::Uint16);
+            pass.draw_indexed(draw.index_count);
+        }
+        pass.end();
+        self.queue.submit(&[encoder.finish()]);
+        Ok(())
+    }
+}
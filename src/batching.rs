@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use crate::math;
+use crate::mesh::MeshData;
+use crate::scene::StaticProp;
+
+// 16-bit indices can only address this many vertices per batch
+const MAX_BATCH_VERTICES: usize = u16::MAX as usize + 1;
+
+/**
+ * Static props sharing a material, merged into one mesh with their world
+ * transforms baked into the vertices
+ */
+pub struct StaticBatch {
+    // Index into `Scene::materials`
+    pub material: usize,
+    pub mesh: MeshData,
+}
+
+/**
+ * Merge `props` into as few batches as possible: one per material, plus more
+ * whenever a batch would outgrow 16-bit indices
+ */
+pub fn build_static_batches(meshes: &[MeshData], props: &[StaticProp]) -> Vec<StaticBatch> {
+    let mut batches: Vec<StaticBatch> = Vec::new();
+    // The batch each material is currently filling
+    let mut open = HashMap::<usize, usize>::new();
+    for prop in props {
+        let Some(source) = meshes.get(prop.mesh) else {
+            continue;
+        };
+        let vertex_count = source.positions.len() / 3;
+        let index = match open.get(&prop.material) {
+            Some(&index)
+                if batches[index].mesh.positions.len() / 3 + vertex_count <= MAX_BATCH_VERTICES =>
+            {
+                index
+            }
+            _ => {
+                batches.push(StaticBatch {
+                    material: prop.material,
+                    mesh: MeshData::default(),
+                });
+                open.insert(prop.material, batches.len() - 1);
+                batches.len() - 1
+            }
+        };
+        append_transformed(&mut batches[index].mesh, source, &prop.transform.matrix());
+    }
+    batches
+}
+
+fn append_transformed(target: &mut MeshData, source: &MeshData, model: &math::Mat4) {
+    let base = (target.positions.len() / 3) as u16;
+    for position in source.positions.chunks_exact(3) {
+        let world = math::transform_point(model, [position[0], position[1], position[2]]);
+        target.positions.extend(world);
+    }
+    target.colors.extend_from_slice(&source.colors);
+    target
+        .indices
+        .extend(source.indices.iter().map(|&index| base + index));
+}
@@ -0,0 +1,73 @@
+use crate::math::{self, Mat4, Vec3};
+
+/**
+ * Axis-aligned bounding box
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    /**
+     * Bounds of a packed xyz position array
+     */
+    pub fn from_positions(positions: &[f32]) -> Self {
+        let mut min = [f32::INFINITY; 3];
+        let mut max = [f32::NEG_INFINITY; 3];
+        for p in positions.chunks_exact(3) {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(p[axis]);
+                max[axis] = max[axis].max(p[axis]);
+            }
+        }
+        Self { min, max }
+    }
+
+    pub fn center(&self) -> Vec3 {
+        math::scale(math::add(self.min, self.max), 0.5)
+    }
+
+    pub fn corners(&self) -> [Vec3; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            [a[0], a[1], a[2]],
+            [b[0], a[1], a[2]],
+            [a[0], b[1], a[2]],
+            [b[0], b[1], a[2]],
+            [a[0], a[1], b[2]],
+            [b[0], a[1], b[2]],
+            [a[0], b[1], b[2]],
+            [b[0], b[1], b[2]],
+        ]
+    }
+
+    /**
+     * Bounds enclosing this box after transforming it by `m`
+     */
+    pub fn transformed(&self, m: &Mat4) -> Self {
+        let corners = self.corners().map(|c| math::transform_point(m, c));
+        Self::from_positions(corners.as_flattened())
+    }
+
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        (0..3).all(|axis| self.min[axis] <= other.max[axis] && other.min[axis] <= self.max[axis])
+    }
+
+    /**
+     * Smallest sphere centered on the box that contains it
+     */
+    pub fn bounding_sphere(&self) -> Sphere {
+        Sphere {
+            center: self.center(),
+            radius: math::length(math::sub(self.max, self.min)) * 0.5,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
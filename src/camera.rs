@@ -0,0 +1,230 @@
+use crate::math::{self, Mat4, Vec3};
+
+/**
+ * How the camera maps the view onto the screen
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Projection {
+    #[default]
+    Perspective,
+    // Parallel lines stay parallel and nothing shrinks with depth. The view
+    // is as tall as the perspective one is at the target, so zooming (the
+    // orbit distance) sets the view height
+    Orthographic,
+}
+
+/**
+ * Camera orbiting around a target point, driven by mouse drag and wheel
+ */
+#[derive(Clone, Debug)]
+pub struct OrbitCamera {
+    pub target: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub distance: f32,
+    pub fov_y: f32,
+    pub projection: Projection,
+    drag_origin: Option<(f64, f64)>,
+}
+
+impl Default for OrbitCamera {
+    fn default() -> Self {
+        Self {
+            target: [0.0, 0.0, 0.0],
+            yaw: 0.6,
+            pitch: 0.4,
+            distance: 3.0,
+            fov_y: std::f32::consts::FRAC_PI_4,
+            projection: Projection::default(),
+            drag_origin: None,
+        }
+    }
+}
+
+// Clip planes of the projection
+pub const NEAR: f32 = 0.1;
+pub const FAR: f32 = 100.0;
+
+// Radians of rotation per dragged pixel
+const ROTATE_SPEED: f32 = 0.01;
+// Keep the camera from flipping over the poles
+const PITCH_LIMIT: f32 = 1.5;
+const MIN_DISTANCE: f32 = 0.5;
+const MAX_DISTANCE: f32 = 20.0;
+
+impl OrbitCamera {
+    pub fn eye(&self) -> Vec3 {
+        let (sy, cy) = self.yaw.sin_cos();
+        let (sp, cp) = self.pitch.sin_cos();
+        math::add(
+            self.target,
+            [
+                self.distance * cp * sy,
+                self.distance * sp,
+                self.distance * cp * cy,
+            ],
+        )
+    }
+
+    /**
+     * Unit vector from the eye towards the target
+     */
+    pub fn forward(&self) -> Vec3 {
+        math::normalize(math::sub(self.target, self.eye()))
+    }
+
+    pub fn view_matrix(&self) -> Mat4 {
+        math::look_at(self.eye(), self.target, [0.0, 1.0, 0.0])
+    }
+
+    /**
+     * World-space height of the view at the target
+     */
+    pub fn view_height(&self) -> f32 {
+        2.0 * self.distance * (self.fov_y / 2.0).tan()
+    }
+
+    pub fn projection_matrix(&self, aspect: f32) -> Mat4 {
+        match self.projection {
+            Projection::Perspective => math::perspective(self.fov_y, aspect, NEAR, FAR),
+            Projection::Orthographic => {
+                let top = self.view_height() / 2.0;
+                let right = top * aspect;
+                // Depth reaches behind the eye too: zooming in moves the eye
+                // closer without changing what a parallel view should show
+                math::orthographic(-right, right, -top, top, -FAR, FAR)
+            }
+        }
+    }
+
+    pub fn view_projection(&self, aspect: f32) -> Mat4 {
+        math::multiply(&self.projection_matrix(aspect), &self.view_matrix())
+    }
+
+    pub fn begin_drag(&mut self, x: f64, y: f64) {
+        self.drag_origin = Some((x, y));
+    }
+
+    pub fn drag_to(&mut self, x: f64, y: f64) {
+        let Some((last_x, last_y)) = self.drag_origin else {
+            return;
+        };
+        self.rotate(x - last_x, y - last_y);
+        self.drag_origin = Some((x, y));
+    }
+
+    /**
+     * Orbit by a dragged distance in pixels, for drags tracked elsewhere
+     */
+    pub fn rotate(&mut self, dx: f64, dy: f64) {
+        self.yaw -= dx as f32 * ROTATE_SPEED;
+        self.pitch = (self.pitch + dy as f32 * ROTATE_SPEED).clamp(-PITCH_LIMIT, PITCH_LIMIT);
+    }
+
+    pub fn end_drag(&mut self) {
+        self.drag_origin = None;
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.drag_origin.is_some()
+    }
+
+    /**
+     * Zoom in or out by a wheel delta (positive scrolls away from the target)
+     */
+    pub fn zoom(&mut self, delta: f64) {
+        let factor = (1.0 + delta as f32 * 0.001).max(0.1);
+        self.distance = (self.distance * factor).clamp(MIN_DISTANCE, MAX_DISTANCE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn near(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn eye_orbits_at_distance() {
+        let camera = OrbitCamera {
+            target: [1.0, 2.0, 3.0],
+            distance: 4.0,
+            ..Default::default()
+        };
+        assert!(near(
+            math::length(math::sub(camera.eye(), camera.target)),
+            4.0
+        ));
+        assert!(near(math::length(camera.forward()), 1.0));
+        let towards = math::normalize(math::sub(camera.target, camera.eye()));
+        assert!(near(math::dot(camera.forward(), towards), 1.0));
+    }
+
+    #[test]
+    fn target_projects_to_screen_center() {
+        let camera = OrbitCamera {
+            target: [0.5, -1.0, 2.0],
+            ..Default::default()
+        };
+        let ndc = math::transform_point(&camera.view_projection(1.6), camera.target);
+        assert!(near(ndc[0], 0.0) && near(ndc[1], 0.0), "{:?}", ndc);
+        assert!(ndc[2] > -1.0 && ndc[2] < 1.0);
+    }
+
+    #[test]
+    fn orthographic_keeps_sizes_and_framing() {
+        let mut camera = OrbitCamera {
+            yaw: 0.0,
+            pitch: 0.0,
+            ..Default::default()
+        };
+        // The top edge of the view at the target stays put when switching
+        let edge = [0.0, camera.view_height() / 2.0, 0.0];
+        let perspective = math::transform_point(&camera.view_projection(1.0), edge);
+        camera.projection = Projection::Orthographic;
+        let view_proj = camera.view_projection(1.0);
+        let orthographic = math::transform_point(&view_proj, edge);
+        assert!(near(perspective[1], 1.0) && near(orthographic[1], 1.0));
+        // Nearer and farther points with the same offset land on the same spot
+        let closer = math::transform_point(&view_proj, [0.5, 0.5, 1.0]);
+        let farther = math::transform_point(&view_proj, [0.5, 0.5, -1.0]);
+        assert!(near(closer[0], farther[0]) && near(closer[1], farther[1]));
+        assert!(closer[2] < farther[2]);
+        // Zooming out widens the view
+        camera.zoom(500.0);
+        let zoomed = math::transform_point(&camera.view_projection(1.0), [0.5, 0.5, 1.0]);
+        assert!(zoomed[0] < closer[0]);
+    }
+
+    #[test]
+    fn dragging_rotates_and_clamps_pitch() {
+        let mut camera = OrbitCamera::default();
+        let yaw = camera.yaw;
+        camera.begin_drag(10.0, 10.0);
+        camera.drag_to(30.0, 10.0);
+        assert!(near(camera.yaw, yaw - 20.0 * ROTATE_SPEED));
+        camera.drag_to(30.0, 10_000.0);
+        assert_eq!(camera.pitch, PITCH_LIMIT);
+        camera.end_drag();
+        assert!(!camera.is_dragging());
+        // Moves after the drag ended are ignored
+        camera.drag_to(500.0, 500.0);
+        assert_eq!(camera.pitch, PITCH_LIMIT);
+    }
+
+    #[test]
+    fn zoom_stays_in_range() {
+        let mut camera = OrbitCamera::default();
+        camera.zoom(100.0);
+        assert!(camera.distance > OrbitCamera::default().distance);
+        camera.zoom(1e6);
+        assert_eq!(camera.distance, MAX_DISTANCE);
+        // Each step shrinks the distance by at most a factor of ten
+        for _ in 0..3 {
+            camera.zoom(-1e6);
+        }
+        assert_eq!(camera.distance, MIN_DISTANCE);
+    }
+}
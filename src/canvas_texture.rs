@@ -0,0 +1,83 @@
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, WebGl2RenderingContext};
+
+use crate::texture::Texture;
+
+/**
+ * A 2D canvas of the given size that is never attached to the page, with its
+ * context
+ */
+pub fn offscreen_canvas(
+    width: u32,
+    height: u32,
+) -> Result<(HtmlCanvasElement, CanvasRenderingContext2d), String> {
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or_else(|| "No document".to_string())?;
+    let canvas = document
+        .create_element("canvas")
+        .map_err(|_| "Unable to create 2D canvas".to_string())?
+        .dyn_into::<HtmlCanvasElement>()
+        .map_err(|_| "Unable to create 2D canvas".to_string())?;
+    canvas.set_width(width);
+    canvas.set_height(height);
+    let context = canvas
+        .get_context("2d")
+        .ok()
+        .flatten()
+        .and_then(|context| context.dyn_into::<CanvasRenderingContext2d>().ok())
+        .ok_or_else(|| "Unable to get 2D context".to_string())?;
+    Ok((canvas, context))
+}
+
+/**
+ * Offscreen 2D canvas mirrored into a GL texture. Draw with `draw`; the
+ * texture is re-uploaded the next time it is requested, and only then
+ */
+pub struct CanvasTexture {
+    canvas: HtmlCanvasElement,
+    context: CanvasRenderingContext2d,
+    texture: Texture,
+    dirty: bool,
+}
+
+impl CanvasTexture {
+    pub fn new(gl: &WebGl2RenderingContext, width: u32, height: u32) -> Result<Self, String> {
+        let (canvas, context) = offscreen_canvas(width, height)?;
+        let texture =
+            Texture::empty(gl).ok_or_else(|| "Unable to create canvas texture".to_string())?;
+
+        Ok(Self {
+            canvas,
+            context,
+            texture,
+            dirty: true,
+        })
+    }
+
+    pub fn width(&self) -> f64 {
+        self.canvas.width() as f64
+    }
+
+    pub fn height(&self) -> f64 {
+        self.canvas.height() as f64
+    }
+
+    /**
+     * Draw onto the canvas and flag the texture for re-upload
+     */
+    pub fn draw(&mut self, draw: impl FnOnce(&CanvasRenderingContext2d)) {
+        draw(&self.context);
+        self.dirty = true;
+    }
+
+    /**
+     * The texture, uploading the canvas first if it changed since last time
+     */
+    pub fn texture(&mut self, gl: &WebGl2RenderingContext) -> &Texture {
+        if self.dirty && self.texture.upload_canvas(gl, &self.canvas).is_some() {
+            self.dirty = false;
+        }
+        &self.texture
+    }
+}
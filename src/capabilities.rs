@@ -0,0 +1,166 @@
+use wasm_bindgen::JsValue;
+use web_sys::WebGl2RenderingContext as Gl;
+
+// From WEBGL_debug_renderer_info
+const UNMASKED_VENDOR_WEBGL: u32 = 0x9245;
+const UNMASKED_RENDERER_WEBGL: u32 = 0x9246;
+// From EXT_texture_filter_anisotropic
+const MAX_TEXTURE_MAX_ANISOTROPY_EXT: u32 = 0x84FF;
+
+// Limits worth knowing when a demo fails on someone else's hardware
+const LIMITS: [(&str, u32); 21] = [
+    ("MAX_TEXTURE_SIZE", Gl::MAX_TEXTURE_SIZE),
+    ("MAX_CUBE_MAP_TEXTURE_SIZE", Gl::MAX_CUBE_MAP_TEXTURE_SIZE),
+    ("MAX_3D_TEXTURE_SIZE", Gl::MAX_3D_TEXTURE_SIZE),
+    ("MAX_ARRAY_TEXTURE_LAYERS", Gl::MAX_ARRAY_TEXTURE_LAYERS),
+    ("MAX_RENDERBUFFER_SIZE", Gl::MAX_RENDERBUFFER_SIZE),
+    ("MAX_VIEWPORT_DIMS", Gl::MAX_VIEWPORT_DIMS),
+    ("MAX_SAMPLES", Gl::MAX_SAMPLES),
+    ("MAX_DRAW_BUFFERS", Gl::MAX_DRAW_BUFFERS),
+    ("MAX_COLOR_ATTACHMENTS", Gl::MAX_COLOR_ATTACHMENTS),
+    ("MAX_VERTEX_ATTRIBS", Gl::MAX_VERTEX_ATTRIBS),
+    ("MAX_VERTEX_UNIFORM_VECTORS", Gl::MAX_VERTEX_UNIFORM_VECTORS),
+    (
+        "MAX_FRAGMENT_UNIFORM_VECTORS",
+        Gl::MAX_FRAGMENT_UNIFORM_VECTORS,
+    ),
+    ("MAX_VARYING_VECTORS", Gl::MAX_VARYING_VECTORS),
+    ("MAX_UNIFORM_BLOCK_SIZE", Gl::MAX_UNIFORM_BLOCK_SIZE),
+    (
+        "MAX_UNIFORM_BUFFER_BINDINGS",
+        Gl::MAX_UNIFORM_BUFFER_BINDINGS,
+    ),
+    ("MAX_TEXTURE_IMAGE_UNITS", Gl::MAX_TEXTURE_IMAGE_UNITS),
+    (
+        "MAX_VERTEX_TEXTURE_IMAGE_UNITS",
+        Gl::MAX_VERTEX_TEXTURE_IMAGE_UNITS,
+    ),
+    (
+        "MAX_COMBINED_TEXTURE_IMAGE_UNITS",
+        Gl::MAX_COMBINED_TEXTURE_IMAGE_UNITS,
+    ),
+    (
+        "MAX_TRANSFORM_FEEDBACK_SEPARATE_ATTRIBS",
+        Gl::MAX_TRANSFORM_FEEDBACK_SEPARATE_ATTRIBS,
+    ),
+    ("ALIASED_POINT_SIZE_RANGE", Gl::ALIASED_POINT_SIZE_RANGE),
+    ("ALIASED_LINE_WIDTH_RANGE", Gl::ALIASED_LINE_WIDTH_RANGE),
+];
+
+/**
+ * What a context reports about the GPU and driver behind it
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Capabilities {
+    pub renderer: String,
+    pub vendor: String,
+    pub version: String,
+    pub shading_language: String,
+    // Name and value, ranges as "min, max"
+    pub limits: Vec<(&'static str, String)>,
+    pub extensions: Vec<String>,
+}
+
+impl Capabilities {
+    pub fn query(gl: &Gl) -> Self {
+        let unmasked = matches!(gl.get_extension("WEBGL_debug_renderer_info"), Ok(Some(_)));
+        let string = |parameter| {
+            gl.get_parameter(parameter)
+                .ok()
+                .and_then(|value| value.as_string())
+                .unwrap_or_default()
+        };
+        let mut limits: Vec<_> = LIMITS
+            .iter()
+            .map(|&(name, parameter)| (name, describe(gl.get_parameter(parameter))))
+            .collect();
+        if let Ok(Some(_)) = gl.get_extension("EXT_texture_filter_anisotropic") {
+            limits.push((
+                "MAX_TEXTURE_MAX_ANISOTROPY_EXT",
+                describe(gl.get_parameter(MAX_TEXTURE_MAX_ANISOTROPY_EXT)),
+            ));
+        }
+        let mut extensions: Vec<String> = gl
+            .get_supported_extensions()
+            .map(|names| names.iter().filter_map(|name| name.as_string()).collect())
+            .unwrap_or_default();
+        extensions.sort();
+
+        Self {
+            renderer: string(if unmasked {
+                UNMASKED_RENDERER_WEBGL
+            } else {
+                Gl::RENDERER
+            }),
+            vendor: string(if unmasked {
+                UNMASKED_VENDOR_WEBGL
+            } else {
+                Gl::VENDOR
+            }),
+            version: string(Gl::VERSION),
+            shading_language: string(Gl::SHADING_LANGUAGE_VERSION),
+            limits,
+            extensions,
+        }
+    }
+
+    /**
+     * Plain-text summary to paste into a bug report
+     */
+    pub fn report(&self) -> String {
+        let user_agent = web_sys::window()
+            .and_then(|window| window.navigator().user_agent().ok())
+            .unwrap_or_default();
+        let mut out = format!(
+            "Renderer: {}\nVendor: {}\nVersion: {}\nGLSL: {}\nUser agent: {}\n\n",
+            self.renderer, self.vendor, self.version, self.shading_language, user_agent
+        );
+        for (name, value) in &self.limits {
+            out.push_str(&format!("{}: {}\n", name, value));
+        }
+        out.push_str(&format!("\nExtensions ({}):\n", self.extensions.len()));
+        for extension in &self.extensions {
+            out.push_str(&format!("  {}\n", extension));
+        }
+        out
+    }
+}
+
+/**
+ * The unmasked renderer string where the browser exposes it, otherwise the
+ * generic one
+ */
+pub fn renderer_name(gl: &Gl) -> String {
+    let parameter = match gl.get_extension("WEBGL_debug_renderer_info") {
+        Ok(Some(_)) => UNMASKED_RENDERER_WEBGL,
+        _ => Gl::RENDERER,
+    };
+    gl.get_parameter(parameter)
+        .ok()
+        .and_then(|value| value.as_string())
+        .unwrap_or_else(|| "WebGL2".to_string())
+}
+
+/**
+ * A parameter value as text: numbers as they are, typed arrays as their
+ * elements separated by commas
+ */
+fn describe(value: Result<JsValue, JsValue>) -> String {
+    let Ok(value) = value else {
+        return "unavailable".to_string();
+    };
+    if let Some(number) = value.as_f64() {
+        return number.to_string();
+    }
+    if value.is_object() {
+        let items: Vec<String> = js_sys::Array::from(&value)
+            .iter()
+            .filter_map(|item| item.as_f64())
+            .map(|item| item.to_string())
+            .collect();
+        if !items.is_empty() {
+            return items.join(", ");
+        }
+    }
+    "unavailable".to_string()
+}
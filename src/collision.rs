@@ -0,0 +1,43 @@
+use crate::bounds::Aabb;
+use crate::scene::Scene;
+
+/**
+ * World-space AABB of every scene object, indexed like `Scene::objects`
+ */
+pub fn world_bounds(scene: &Scene) -> Vec<Aabb> {
+    scene
+        .objects
+        .iter()
+        .map(|object| match scene.meshes.get(object.mesh) {
+            Some(mesh) => mesh.bounds().transformed(&object.transform.matrix()),
+            None => Aabb {
+                min: object.transform.translation,
+                max: object.transform.translation,
+            },
+        })
+        .collect()
+}
+
+/**
+ * Index pairs `(a, b)` with `a < b` whose boxes overlap, found with a
+ * sort-and-sweep along the X axis
+ */
+pub fn overlapping_pairs(bounds: &[Aabb]) -> Vec<(usize, usize)> {
+    let mut order: Vec<usize> = (0..bounds.len()).collect();
+    order.sort_by(|&a, &b| bounds[a].min[0].total_cmp(&bounds[b].min[0]));
+
+    let mut pairs = Vec::new();
+    for (i, &a) in order.iter().enumerate() {
+        for &b in &order[i + 1..] {
+            // Sorted by min.x, so nothing further along can overlap on X
+            if bounds[b].min[0] > bounds[a].max[0] {
+                break;
+            }
+            if bounds[a].overlaps(&bounds[b]) {
+                pairs.push((a.min(b), a.max(b)));
+            }
+        }
+    }
+    pairs.sort_unstable();
+    pairs
+}
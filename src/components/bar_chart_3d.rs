@@ -0,0 +1,261 @@
+use dioxus::prelude::*;
+use web_sys::{WebGl2RenderingContext, WebGlBuffer, WebGlVertexArrayObject};
+
+use crate::camera::OrbitCamera;
+use crate::components::{Label3D, PowerPreference, WebGlCanvas};
+use crate::gl_errors;
+use crate::gl_util;
+use crate::grid::InfiniteGrid;
+use crate::hooks::use_frame_loop;
+use crate::math;
+use crate::mesh::MeshData;
+use crate::shaders;
+
+const CANVAS_WIDTH: u32 = 560;
+const CANVAS_HEIGHT: u32 = 360;
+// World-space layout: distance between bar centers, bar footprint, tallest bar
+const SPACING: f32 = 1.0;
+const BAR_WIDTH: f32 = 0.7;
+const MAX_HEIGHT: f32 = 3.0;
+// Fraction of the remaining distance animated bars cover each frame
+const EASING: f32 = 0.12;
+// x offset, height, rgb per bar
+const FLOATS_PER_BAR: usize = 5;
+
+/**
+ * Animated 3D bar chart of `values`, with value, category and scale labels
+ * as DOM elements over the canvas. Bars ease to new values when the data changes
+ */
+#[component]
+pub fn BarChart3D(
+    values: ReadOnlySignal<Vec<f32>>,
+    // Category names under the bars; bars are numbered when empty
+    #[props(default)] labels: Vec<String>,
+) -> Element {
+    let mut context = use_signal(|| None::<WebGl2RenderingContext>);
+    // Values, categories, then the three scale ticks
+    let mut label_positions = use_signal(Vec::<Option<[f32; 2]>>::new);
+    let frame_loop = use_frame_loop();
+
+    use_effect(move || {
+        let Some(gl) = context() else {
+            return;
+        };
+        let frame_loop = frame_loop.clone();
+        spawn(async move {
+            let program = match gl_util::create_program(
+                &gl,
+                shaders::COMPONENTS_BAR_CHART_3D_VERT,
+                shaders::COMPONENTS_BAR_CHART_3D_FRAG,
+            ) {
+                Ok(program) => program,
+                Err(err) => {
+                    gl_errors::report("chart: program setup", err);
+                    return;
+                }
+            };
+            let view_proj_loc = gl.get_uniform_location(&program, "viewProjection");
+            let bar_width_loc = gl.get_uniform_location(&program, "barWidth");
+            let Some((vao, instance_buffer, index_count)) = upload_bar_mesh(&gl) else {
+                gl_errors::report("chart: setup", "Unable to upload the bar mesh");
+                return;
+            };
+            let grid = match InfiniteGrid::new(&gl) {
+                Ok(grid) => grid,
+                Err(err) => {
+                    gl_errors::report("chart: grid setup", err);
+                    return;
+                }
+            };
+
+            // Displayed bar heights in world units, easing towards the data
+            let mut heights = Vec::<f32>::new();
+            let mut instances = Vec::<f32>::new();
+
+            frame_loop.start(move |time: f64, _| {
+                let time = time as f32;
+
+                let values = values.peek();
+                let scale = MAX_HEIGHT / max_magnitude(&values);
+                heights.resize(values.len(), 0.0);
+                instances.clear();
+                for (i, (height, &value)) in heights.iter_mut().zip(values.iter()).enumerate() {
+                    *height += (value * scale - *height) * EASING;
+                    instances.extend([bar_x(i, values.len()), *height]);
+                    instances.extend(bar_color(i, values.len()));
+                }
+
+                // Fixed framing with a gentle sway; widens as bars are added
+                let mut camera = OrbitCamera::default();
+                camera.target = [0.0, MAX_HEIGHT * 0.4, 0.0];
+                camera.yaw = 0.45 + (time * 0.3).sin() * 0.15;
+                camera.pitch = 0.35;
+                camera.distance = (4.0 + values.len() as f32 * 0.9).min(20.0);
+                let view_proj = camera.view_projection(CANVAS_WIDTH as f32 / CANVAS_HEIGHT as f32);
+
+                let width = CANVAS_WIDTH as f32;
+                let height = CANVAS_HEIGHT as f32;
+                let screen = |p| math::world_to_screen(&view_proj, p, width, height);
+                let left = bar_x(0, values.len()) - SPACING;
+                let positions: Vec<_> = heights
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &h)| screen([bar_x(i, values.len()), h.max(0.0) + 0.1, 0.0]))
+                    .chain((0..values.len()).map(|i| {
+                        // Just in front of the bar's base
+                        screen([bar_x(i, values.len()), -0.1, BAR_WIDTH])
+                    }))
+                    .chain((0..3).map(|tick| screen([left, MAX_HEIGHT * tick as f32 / 2.0, 0.0])))
+                    .collect();
+                drop(values);
+                if *label_positions.peek() != positions {
+                    label_positions.set(positions);
+                }
+
+                gl.viewport(0, 0, CANVAS_WIDTH as i32, CANVAS_HEIGHT as i32);
+                gl.enable(WebGl2RenderingContext::DEPTH_TEST);
+                gl.clear_color(0.1, 0.1, 0.1, 1.0);
+                gl.clear(
+                    WebGl2RenderingContext::COLOR_BUFFER_BIT
+                        | WebGl2RenderingContext::DEPTH_BUFFER_BIT,
+                );
+
+                if !instances.is_empty() {
+                    gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&instance_buffer));
+                    unsafe {
+                        let array = js_sys::Float32Array::view(&instances);
+                        gl.buffer_data_with_array_buffer_view(
+                            WebGl2RenderingContext::ARRAY_BUFFER,
+                            &array,
+                            WebGl2RenderingContext::DYNAMIC_DRAW,
+                        );
+                    }
+                    gl.use_program(Some(&program));
+                    gl.uniform_matrix4fv_with_f32_array(view_proj_loc.as_ref(), false, &view_proj);
+                    gl.uniform1f(bar_width_loc.as_ref(), BAR_WIDTH);
+                    gl.bind_vertex_array(Some(&vao));
+                    gl.draw_elements_instanced_with_i32(
+                        WebGl2RenderingContext::TRIANGLES,
+                        index_count,
+                        WebGl2RenderingContext::UNSIGNED_SHORT,
+                        0,
+                        (instances.len() / FLOATS_PER_BAR) as i32,
+                    );
+                    gl.bind_vertex_array(None);
+                }
+                grid.draw(&gl, &view_proj, 0.0);
+            });
+        });
+    });
+
+    let values = values();
+    let max = max_magnitude(&values);
+    let texts: Vec<String> = values
+        .iter()
+        .map(|&value| format_value(value))
+        .chain((0..values.len()).map(|i| match labels.get(i) {
+            Some(label) => label.clone(),
+            None => (i + 1).to_string(),
+        }))
+        .chain((0..3).map(|tick| format_value(max * tick as f32 / 2.0)))
+        .collect();
+    let positions = label_positions();
+
+    rsx! {
+        div {
+            style: "position: relative;",
+            WebGlCanvas {
+                width: CANVAS_WIDTH,
+                height: CANVAS_HEIGHT,
+                style: "border: 2px solid #333; background: #222;",
+                // A dashboard widget; no need to wake up a discrete GPU
+                power_preference: PowerPreference::LowPower,
+                oncontext: move |gl| context.set(Some(gl)),
+            }
+            div {
+                class: "canvas-overlay",
+                for (i, (text, position)) in texts.into_iter().zip(positions).enumerate() {
+                    Label3D { key: "{i}", position, "{text}" }
+                }
+            }
+        }
+    }
+}
+
+/**
+ * Cube VAO with an empty per-instance buffer for bar offsets, heights and colors
+ */
+fn upload_bar_mesh(
+    gl: &WebGl2RenderingContext,
+) -> Option<(WebGlVertexArrayObject, WebGlBuffer, i32)> {
+    let cube = MeshData::cube();
+    let vao = gl.create_vertex_array()?;
+    gl.bind_vertex_array(Some(&vao));
+
+    let position_buffer = gl.create_buffer()?;
+    gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&position_buffer));
+    unsafe {
+        let array = js_sys::Float32Array::view(&cube.positions);
+        gl.buffer_data_with_array_buffer_view(
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            &array,
+            WebGl2RenderingContext::STATIC_DRAW,
+        );
+    }
+    gl.enable_vertex_attrib_array(0);
+    gl.vertex_attrib_pointer_with_i32(0, 3, WebGl2RenderingContext::FLOAT, false, 0, 0);
+
+    let instance_buffer = gl.create_buffer()?;
+    gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&instance_buffer));
+    let stride = (FLOATS_PER_BAR * 4) as i32;
+    gl.enable_vertex_attrib_array(1);
+    gl.vertex_attrib_pointer_with_i32(1, 2, WebGl2RenderingContext::FLOAT, false, stride, 0);
+    gl.vertex_attrib_divisor(1, 1);
+    gl.enable_vertex_attrib_array(2);
+    gl.vertex_attrib_pointer_with_i32(2, 3, WebGl2RenderingContext::FLOAT, false, stride, 8);
+    gl.vertex_attrib_divisor(2, 1);
+
+    let index_buffer = gl.create_buffer()?;
+    gl.bind_buffer(
+        WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER,
+        Some(&index_buffer),
+    );
+    unsafe {
+        let array = js_sys::Uint16Array::view(&cube.indices);
+        gl.buffer_data_with_array_buffer_view(
+            WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER,
+            &array,
+            WebGl2RenderingContext::STATIC_DRAW,
+        );
+    }
+
+    gl.bind_vertex_array(None);
+    Some((vao, instance_buffer, cube.indices.len() as i32))
+}
+
+// Bars are centered on the origin along X
+fn bar_x(index: usize, count: usize) -> f32 {
+    (index as f32 - (count as f32 - 1.0) * 0.5) * SPACING
+}
+
+// Blue to orange across the chart
+fn bar_color(index: usize, count: usize) -> [f32; 3] {
+    let t = index as f32 / (count.max(2) - 1) as f32;
+    [0.25 + 0.7 * t, 0.5 + 0.1 * t, 0.95 - 0.7 * t]
+}
+
+// Largest absolute value, never zero so it can divide
+fn max_magnitude(values: &[f32]) -> f32 {
+    values
+        .iter()
+        .fold(0.0f32, |max, value| max.max(value.abs()))
+        .max(f32::EPSILON)
+}
+
+fn format_value(value: f32) -> String {
+    if value.abs() >= 100.0 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.1}", value)
+    }
+}
@@ -0,0 +1,54 @@
+use dioxus::prelude::*;
+
+use crate::capabilities::Capabilities;
+use crate::components::use_gl_context;
+use crate::download;
+
+/**
+ * Limits, extensions and renderer of the enclosing `WebGlCanvas`'s context,
+ * with a button that saves them as a text file for bug reports. Place it
+ * inside the canvas's children
+ */
+#[component]
+pub fn GlCapabilities() -> Element {
+    let context = use_gl_context();
+    let capabilities = use_memo(move || context.gl().map(|gl| Capabilities::query(&gl)));
+    let Some(capabilities) = capabilities() else {
+        return rsx! {};
+    };
+
+    let on_save = {
+        let report = capabilities.report();
+        move |_| {
+            if let Err(err) =
+                download::save_file(report.as_bytes(), "webgl-report.txt", "text/plain")
+            {
+                tracing::error!(target: "ui", "Saving the report failed: {:?}", err);
+            }
+        }
+    };
+
+    rsx! {
+        div {
+            class: "gl-capabilities",
+            table {
+                tr { th { "Renderer" } td { "{capabilities.renderer}" } }
+                tr { th { "Vendor" } td { "{capabilities.vendor}" } }
+                tr { th { "Version" } td { "{capabilities.version}" } }
+                tr { th { "GLSL" } td { "{capabilities.shading_language}" } }
+                for (name, value) in capabilities.limits.iter() {
+                    tr { key: "{name}", th { "{name}" } td { "{value}" } }
+                }
+            }
+            details {
+                summary { "Extensions ({capabilities.extensions.len()})" }
+                ul {
+                    for extension in capabilities.extensions.iter() {
+                        li { key: "{extension}", "{extension}" }
+                    }
+                }
+            }
+            button { onclick: on_save, "Save report" }
+        }
+    }
+}
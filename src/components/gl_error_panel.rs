@@ -0,0 +1,42 @@
+use dioxus::prelude::*;
+
+use crate::gl_errors::GL_ERRORS;
+
+/**
+ * Collapsible list of the GL errors and setup failures logged so far, newest
+ * first. Hidden while the log is empty
+ */
+#[component]
+pub fn GlErrorPanel() -> Element {
+    let errors = GL_ERRORS.read();
+    if errors.is_empty() {
+        return rsx! {};
+    }
+
+    rsx! {
+        details {
+            class: "gl-error-panel",
+            summary {
+                "GL errors ({errors.len()})"
+                button {
+                    class: "gl-error-clear",
+                    onclick: move |evt| {
+                        // Keep the click from toggling the panel
+                        evt.prevent_default();
+                        GL_ERRORS.write().clear();
+                    },
+                    "Clear"
+                }
+            }
+            ul {
+                for error in errors.iter().rev() {
+                    li {
+                        span { class: "gl-error-time", "{error.timestamp()}" }
+                        span { class: "gl-error-source", "{error.source}" }
+                        "{error.message}"
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use dioxus::prelude::*;
+
+use crate::capabilities::renderer_name;
+use crate::components::use_gl_context;
+
+/**
+ * Small overlay naming the GPU behind the enclosing `WebGlCanvas`, read from
+ * the shared GL context. Place it inside the canvas's children
+ */
+#[component]
+pub fn GlInfo() -> Element {
+    let context = use_gl_context();
+    let Some(gl) = context.gl() else {
+        return rsx! {};
+    };
+    let (width, height) = context.size();
+
+    rsx! {
+        div {
+            class: "gl-info",
+            "{renderer_name(&gl)} · {width}×{height}"
+        }
+    }
+}
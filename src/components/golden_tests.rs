@@ -0,0 +1,99 @@
+use dioxus::prelude::*;
+
+use crate::components::use_gl_context;
+use crate::download;
+use crate::golden::{self, CaseResult};
+use crate::query;
+
+// Runs the tests as soon as the context exists, for headless browser runs
+const QUERY_FLAG: &str = "golden";
+
+/**
+ * Renders the golden-image cases with the enclosing `WebGlCanvas`'s context
+ * and lists how far each frame is from its reference. The outcome is also
+ * exposed as `data-status="pass"` or `"fail"` for automated runs. Place it
+ * inside the canvas's children
+ */
+#[component]
+pub fn GoldenTests() -> Element {
+    let context = use_gl_context();
+    let mut results = use_signal(|| None::<Result<Vec<CaseResult>, String>>);
+    let mut running = use_signal(|| false);
+
+    let run = use_callback({
+        let context = context.clone();
+        move |_: ()| {
+            let Some(gl) = context.gl() else {
+                return;
+            };
+            if *running.peek() {
+                return;
+            }
+            running.set(true);
+            spawn(async move {
+                let outcome = golden::run(&gl).await;
+                match &outcome {
+                    Ok(cases) => {
+                        let failed = cases.iter().filter(|case| !case.passed()).count();
+                        tracing::info!(target: "renderer", "Golden images: {} of {} passed", cases.len() - failed, cases.len());
+                    }
+                    Err(err) => tracing::error!(target: "renderer", "Golden images: {}", err),
+                }
+                results.set(Some(outcome));
+                running.set(false);
+            });
+        }
+    });
+    use_effect(move || {
+        if context.gl().is_some() && query::current().iter().any(|(key, _)| key == QUERY_FLAG) {
+            run(());
+        }
+    });
+
+    let status = match &*results.read() {
+        _ if running() => "running",
+        None => "idle",
+        Some(Ok(cases)) if cases.iter().all(CaseResult::passed) => "pass",
+        Some(_) => "fail",
+    };
+    let on_save = move |_| {
+        let Some(Ok(cases)) = &*results.read() else {
+            return;
+        };
+        let file = golden::signatures_file(cases);
+        if let Err(err) = download::save_file(file.as_bytes(), "golden.txt", "text/plain") {
+            tracing::error!(target: "ui", "Saving the signatures failed: {:?}", err);
+        }
+    };
+
+    rsx! {
+        div {
+            class: "golden-tests",
+            "data-status": status,
+            h4 { "Rendering tests" }
+            button { disabled: running(), onclick: move |_| run(()), "Run" }
+            match &*results.read() {
+                None => rsx! {},
+                Some(Err(err)) => rsx! { p { "Could not run: {err}" } },
+                Some(Ok(cases)) => rsx! {
+                    table {
+                        for case in cases.iter() {
+                            tr {
+                                key: "{case.name}",
+                                th { "{case.name}" }
+                                td {
+                                    match &case.outcome {
+                                        Ok(difference) => format!("off by {}", difference),
+                                        Err(err) => err.clone(),
+                                    }
+                                }
+                                td { if case.passed() { "pass" } else { "FAIL" } }
+                            }
+                        }
+                    }
+                    button { onclick: on_save, "Save signatures" }
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use dioxus::prelude::*;
+
+use crate::gpu_memory::{self, Usage};
+
+// How often the figures are refreshed, in milliseconds
+const REFRESH_MS: u32 = 500;
+
+fn megabytes(bytes: usize) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
+
+/**
+ * Estimated GPU memory held by the page's buffers, textures and
+ * renderbuffers, against an editable budget that is remembered across
+ * visits. Turns red (with `data-status="over"`) once the budget is exceeded
+ */
+#[component]
+pub fn GpuMemory() -> Element {
+    let mut usage = use_signal(gpu_memory::usage);
+    let mut budget = use_signal(gpu_memory::budget);
+
+    use_future(move || async move {
+        loop {
+            gloo_timers::future::TimeoutFuture::new(REFRESH_MS).await;
+            let current = gpu_memory::usage();
+            if *usage.peek() != current {
+                usage.set(current);
+            }
+        }
+    });
+
+    let Usage {
+        buffers,
+        textures,
+        renderbuffers,
+    } = usage();
+    let total = usage().total();
+    let over = total > budget();
+    let budget_mb = budget() / (1024 * 1024);
+
+    rsx! {
+        div {
+            class: "gpu-memory",
+            "data-status": if over { "over" } else { "ok" },
+            span {
+                class: "gpu-memory-total",
+                "GPU memory ≈ {megabytes(total):.1} MB"
+                if over {
+                    " (over budget)"
+                }
+            }
+            span { "buffers {megabytes(buffers):.1} MB" }
+            span { "textures {megabytes(textures):.1} MB" }
+            span { "renderbuffers {megabytes(renderbuffers):.1} MB" }
+            label {
+                "budget "
+                input {
+                    r#type: "number",
+                    min: "1",
+                    value: "{budget_mb}",
+                    onchange: move |evt| {
+                        if let Ok(mb) = evt.value().parse::<usize>() {
+                            let bytes = mb.max(1) * 1024 * 1024;
+                            gpu_memory::set_budget(bytes);
+                            budget.set(bytes);
+                        }
+                    },
+                }
+                " MB"
+            }
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use dioxus::prelude::*;
+
+/**
+ * DOM element pinned over a canvas at a projected scene position (from
+ * `math::world_to_screen`), anchored at its bottom center. Place it inside a
+ * positioned container aligned with the canvas; hidden while `position` is `None`
+ */
+#[component]
+pub fn Label3D(position: Option<[f32; 2]>, children: Element) -> Element {
+    let Some([x, y]) = position else {
+        return rsx! {};
+    };
+
+    rsx! {
+        div {
+            class: "label-3d",
+            style: "left: {x}px; top: {y}px;",
+            {children}
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use dioxus::prelude::*;
+
+use crate::assets::{AssetStatus, LoadState};
+
+/**
+ * Overall progress bar with a spinner, plus one row per asset
+ */
+#[component]
+pub fn LoadingProgress(assets: Vec<AssetStatus>) -> Element {
+    // Assets with an unknown size count as half done until they finish
+    let fractions: Vec<f64> = assets
+        .iter()
+        .map(|asset| match &asset.state {
+            LoadState::Loading(progress) => progress.fraction().unwrap_or(0.5),
+            LoadState::Ready | LoadState::Failed(_) => 1.0,
+        })
+        .collect();
+    let overall = if fractions.is_empty() {
+        100.0
+    } else {
+        fractions.iter().sum::<f64>() / fractions.len() as f64 * 100.0
+    };
+    let done = assets
+        .iter()
+        .all(|asset| !matches!(asset.state, LoadState::Loading(_)));
+
+    rsx! {
+        div {
+            class: "loading-progress",
+            div {
+                class: "loading-progress-header",
+                if !done {
+                    span { class: "loading-spinner" }
+                }
+                "Loading assets {overall:.0}%"
+            }
+            div {
+                class: "loading-progress-bar",
+                div { class: "loading-progress-fill", style: "width: {overall}%;" }
+            }
+            for (asset, fraction) in assets.iter().zip(&fractions) {
+                div {
+                    key: "{asset.name}",
+                    class: "loading-progress-row",
+                    span { "{asset.name}" }
+                    if asset.critical {
+                        span { class: "loading-progress-critical", " (required)" }
+                    }
+                    span {
+                        class: "loading-progress-state",
+                        match &asset.state {
+                            LoadState::Loading(_) => rsx! { "{fraction * 100.0:.0}%" },
+                            LoadState::Ready => rsx! { "ready" },
+                            LoadState::Failed(err) => rsx! { "failed: {err}" },
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use dioxus::prelude::*;
+use tracing::level_filters::LevelFilter;
+
+use crate::logging::{self, LogFilter, SUBSYSTEMS};
+use crate::query;
+
+const LEVELS: [LevelFilter; 6] = [
+    LevelFilter::OFF,
+    LevelFilter::ERROR,
+    LevelFilter::WARN,
+    LevelFilter::INFO,
+    LevelFilter::DEBUG,
+    LevelFilter::TRACE,
+];
+
+/**
+ * Log verbosity per subsystem, applied immediately and mirrored into the
+ * page URL's `log` parameter so a reload (or a shared link) keeps it
+ */
+#[component]
+pub fn LogSettings() -> Element {
+    let mut filter = use_signal(logging::filter);
+
+    let mut apply = move |next: LogFilter| {
+        let mut pairs: Vec<(&str, String)> = Vec::new();
+        let current = query::current();
+        for (key, value) in &current {
+            if key != "log" {
+                pairs.push((key, value.clone()));
+            }
+        }
+        pairs.push(("log", next.to_directives()));
+        query::replace(&pairs);
+        logging::set_filter(next.clone());
+        filter.set(next);
+    };
+
+    rsx! {
+        div {
+            class: "log-settings",
+            h4 { "Log levels" }
+            label {
+                "default "
+                LevelSelect {
+                    level: filter.read().default,
+                    onchange: move |level| {
+                        let mut next = filter();
+                        next.default = level;
+                        apply(next);
+                    },
+                }
+            }
+            for subsystem in SUBSYSTEMS {
+                label {
+                    key: "{subsystem}",
+                    "{subsystem} "
+                    LevelSelect {
+                        level: filter.read().level(subsystem),
+                        onchange: move |level| {
+                            let mut next = filter();
+                            next.set(subsystem, level);
+                            apply(next);
+                        },
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn LevelSelect(level: LevelFilter, onchange: EventHandler<LevelFilter>) -> Element {
+    rsx! {
+        select {
+            onchange: move |evt| {
+                if let Ok(level) = evt.value().parse() {
+                    onchange.call(level);
+                }
+            },
+            for option in LEVELS {
+                option {
+                    value: "{option}",
+                    selected: option == level,
+                    "{option}"
+                }
+            }
+        }
+    }
+}
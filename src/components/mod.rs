@@ -0,0 +1,31 @@
+mod bar_chart_3d;
+mod gl_capabilities;
+mod gl_error_panel;
+mod gl_info;
+mod golden_tests;
+mod gpu_memory;
+mod label_3d;
+mod loading_progress;
+mod log_settings;
+mod texture_picker;
+mod tooltip;
+mod webgl_canvas;
+mod webgl_unavailable;
+mod wgsl_translations;
+
+pub use bar_chart_3d::BarChart3D;
+pub use gl_capabilities::GlCapabilities;
+pub use gl_error_panel::GlErrorPanel;
+pub use gl_info::GlInfo;
+pub use golden_tests::GoldenTests;
+pub use gpu_memory::GpuMemory;
+pub use label_3d::Label3D;
+pub use loading_progress::LoadingProgress;
+pub use log_settings::LogSettings;
+pub use texture_picker::TexturePicker;
+pub use tooltip::Tooltip;
+pub use webgl_canvas::{
+    use_draw, use_gl_context, use_hit_target, Frame, PickEvent, PowerPreference, WebGlCanvas,
+};
+pub use webgl_unavailable::WebGlUnavailable;
+pub use wgsl_translations::WgslTranslations;
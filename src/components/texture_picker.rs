@@ -0,0 +1,84 @@
+use dioxus::prelude::*;
+use js_sys::{Array, Uint8Array};
+use web_sys::{Blob, ImageBitmap, Url};
+
+use crate::assets;
+
+/**
+ * Object URL that is revoked once the last owner lets go of it, so replaced
+ * previews don't keep their file's bytes alive
+ */
+struct ObjectUrl(String);
+
+impl Drop for ObjectUrl {
+    fn drop(&mut self) {
+        let _ = Url::revoke_object_url(&self.0);
+    }
+}
+
+/**
+ * File input that decodes the chosen image and hands it to `onimage`, with a
+ * thumbnail of the current choice. The receiver owns the image from then on,
+ * including freeing any texture it replaces
+ */
+#[component]
+pub fn TexturePicker(label: String, onimage: EventHandler<ImageBitmap>) -> Element {
+    let mut preview = use_signal(|| None::<ObjectUrl>);
+    let mut error = use_signal(|| None::<String>);
+
+    let on_change = move |evt: FormEvent| {
+        let Some(files) = evt.files() else {
+            return;
+        };
+        let Some(name) = files.files().into_iter().next() else {
+            return;
+        };
+        spawn(async move {
+            let Some(bytes) = files.read_file(&name).await else {
+                error.set(Some(format!("Unable to read {}", name)));
+                return;
+            };
+            let decoded =
+                Blob::new_with_u8_array_sequence(&Array::of1(&Uint8Array::from(bytes.as_slice())))
+                    .map_err(|_| "Unable to create blob".to_string())
+                    .and_then(|blob| {
+                        let url = Url::create_object_url_with_blob(&blob)
+                            .map_err(|_| "Unable to create object URL".to_string())?;
+                        Ok((blob, ObjectUrl(url)))
+                    });
+            let (blob, url) = match decoded {
+                Ok(decoded) => decoded,
+                Err(err) => {
+                    error.set(Some(err));
+                    return;
+                }
+            };
+            match assets::decode_blob(&blob).await {
+                Ok(image) => {
+                    // Replacing the previous preview revokes its URL
+                    preview.set(Some(url));
+                    error.set(None);
+                    onimage.call(image);
+                }
+                Err(err) => error.set(Some(format!("{}: {}", name, err))),
+            }
+        });
+    };
+
+    rsx! {
+        label {
+            style: "display: flex; gap: 8px; align-items: center;",
+            "{label} "
+            input { r#type: "file", accept: "image/*", onchange: on_change }
+            if let Some(url) = preview.read().as_ref() {
+                img {
+                    src: "{url.0}",
+                    style: "width: 32px; height: 32px; object-fit: cover; border: 1px solid #333;",
+                }
+            }
+            if let Some(err) = error() {
+                span { style: "color: #a00;", "{err}" }
+            }
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use dioxus::prelude::*;
+
+// Gap between the cursor and the tooltip, in pixels
+const OFFSET: f64 = 14.0;
+
+/**
+ * DOM tooltip next to the cursor at `position`, in pixels within a positioned
+ * container of size `area` (such as a `canvas-overlay`). It sits below and to
+ * the right of the cursor, flipping to the other side in the far half of
+ * `area` so it stays inside. Hidden while `position` is `None`
+ */
+#[component]
+pub fn Tooltip(position: Option<(f64, f64)>, area: (f64, f64), children: Element) -> Element {
+    let Some((x, y)) = position else {
+        return rsx! {};
+    };
+    let (left, shift_x) = if x > area.0 * 0.5 {
+        (x - OFFSET, "-100%")
+    } else {
+        (x + OFFSET, "0")
+    };
+    let (top, shift_y) = if y > area.1 * 0.5 {
+        (y - OFFSET, "-100%")
+    } else {
+        (y + OFFSET, "0")
+    };
+
+    rsx! {
+        div {
+            class: "tooltip",
+            role: "tooltip",
+            style: "left: {left}px; top: {top}px; transform: translate({shift_x}, {shift_y});",
+            {children}
+        }
+    }
+}
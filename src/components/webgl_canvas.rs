@@ -0,0 +1,510 @@
+use dioxus::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use wasm_bindgen::JsCast;
+use web_sys::{
+    HtmlCanvasElement, WebGl2RenderingContext, WebGlContextAttributes, WebGlPowerPreference,
+};
+
+use crate::components::WebGlUnavailable;
+use crate::debug_gl;
+use crate::gl_errors;
+use crate::hooks::{use_animation_frame, use_resize_observer};
+use crate::math;
+
+/**
+ * GPU the browser should pick on multi-GPU systems
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PowerPreference {
+    #[default]
+    Default,
+    HighPerformance,
+    LowPower,
+}
+
+/**
+ * Timing of one frame of a canvas's draw loop, in seconds
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frame {
+    // requestAnimationFrame timestamp
+    pub time: f64,
+    // Since the previous frame; zero on the first
+    pub delta: f64,
+}
+
+type DrawCallback = Box<dyn FnMut(&WebGl2RenderingContext, Frame)>;
+
+struct DrawEntry {
+    id: usize,
+    priority: i32,
+    draw: DrawCallback,
+}
+
+/**
+ * Draw callbacks registered by descendants, kept in the order they run
+ */
+#[derive(Default)]
+struct DrawList {
+    next_id: usize,
+    entries: Vec<DrawEntry>,
+}
+
+impl DrawList {
+    fn add(&mut self, priority: i32, draw: DrawCallback) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        // After every entry of the same priority, so ties run in registration order
+        let index = self
+            .entries
+            .partition_point(|entry| entry.priority <= priority);
+        self.entries.insert(index, DrawEntry { id, priority, draw });
+        id
+    }
+
+    fn remove(&mut self, id: usize) {
+        self.entries.retain(|entry| entry.id != id);
+    }
+}
+
+/**
+ * The pointer on an object of the scene, as found by hit-testing
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PickEvent {
+    // Pointer position in normalized device coordinates
+    pub ndc: [f32; 2],
+    // How far along the pointer's ray the object was hit
+    pub distance: f32,
+}
+
+type HitTest = Box<dyn FnMut(f32, f32) -> Option<f32>>;
+
+#[derive(Clone, Copy, Default)]
+struct HitHandlers {
+    onclick: Option<EventHandler<PickEvent>>,
+    onhover: Option<EventHandler<bool>>,
+}
+
+struct HitEntry {
+    id: usize,
+    test: HitTest,
+    handlers: Rc<Cell<HitHandlers>>,
+}
+
+/**
+ * Hit tests registered by descendants, and which of them the pointer is over
+ */
+#[derive(Default)]
+struct HitList {
+    next_id: usize,
+    entries: Vec<HitEntry>,
+    hovered: Option<usize>,
+}
+
+impl HitList {
+    fn add(&mut self, test: HitTest, handlers: Rc<Cell<HitHandlers>>) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.entries.push(HitEntry { id, test, handlers });
+        id
+    }
+
+    fn remove(&mut self, id: usize) {
+        self.entries.retain(|entry| entry.id != id);
+        if self.hovered == Some(id) {
+            self.hovered = None;
+        }
+    }
+
+    /**
+     * The nearest target under the pointer, with its handlers and event
+     */
+    fn pick(&mut self, ndc: [f32; 2]) -> Option<(usize, HitHandlers, PickEvent)> {
+        self.entries
+            .iter_mut()
+            .filter_map(|entry| {
+                let distance = (entry.test)(ndc[0], ndc[1])?;
+                Some((entry.id, entry.handlers.get(), PickEvent { ndc, distance }))
+            })
+            .min_by(|a, b| a.2.distance.total_cmp(&b.2.distance))
+    }
+
+    fn handlers(&self, id: usize) -> Option<HitHandlers> {
+        self.entries
+            .iter()
+            .find(|entry| entry.id == id)
+            .map(|entry| entry.handlers.get())
+    }
+}
+
+/**
+ * What a `WebGlCanvas` shares with its descendants through context: the GL
+ * context once created, the drawing buffer size, the draw callbacks its
+ * frame loop runs and the hit tests its pointer events go through
+ */
+#[derive(Clone)]
+pub struct GlContext {
+    gl: Signal<Option<WebGl2RenderingContext>>,
+    size: Signal<(u32, u32)>,
+    draws: Rc<RefCell<DrawList>>,
+    hits: Rc<RefCell<HitList>>,
+}
+
+impl GlContext {
+    /**
+     * The context, or `None` before the canvas has mounted. Reading it
+     * subscribes the calling component or effect
+     */
+    pub fn gl(&self) -> Option<WebGl2RenderingContext> {
+        (self.gl)()
+    }
+
+    /**
+     * Drawing buffer width and height, which follow the element's size when
+     * the canvas is `responsive`. Reading it subscribes like `gl`; draw
+     * callbacks should ask the context for `drawing_buffer_width` instead
+     */
+    pub fn size(&self) -> (u32, u32) {
+        (self.size)()
+    }
+}
+
+/**
+ * The nearest enclosing `WebGlCanvas`'s context. Panics outside of one
+ */
+pub fn use_gl_context() -> GlContext {
+    use_context::<GlContext>()
+}
+
+/**
+ * Run `draw` every frame of the enclosing `WebGlCanvas` for as long as the
+ * calling component is mounted. Callbacks run in ascending `priority`, so a
+ * component that clears the canvas or sets up a camera can go before the ones
+ * that draw into it. The callback is registered once; read changing props
+ * and signals through shared state (e.g. `peek`) rather than capturing them
+ */
+pub fn use_draw(priority: i32, draw: impl FnMut(&WebGl2RenderingContext, Frame) + 'static) {
+    let draws = use_gl_context().draws;
+    let id = use_hook({
+        let draws = draws.clone();
+        move || draws.borrow_mut().add(priority, Box::new(draw))
+    });
+    use_drop(move || draws.borrow_mut().remove(id));
+}
+
+/**
+ * Make the calling component a pointer target of the enclosing `WebGlCanvas`,
+ * for as long as it is mounted. `hit_test` gets the pointer in normalized
+ * device coordinates and returns how far along the pointer's ray it hits the
+ * component's geometry, if it does. The nearest target hit gets `onclick`,
+ * and `onhover` is called with `true` when the pointer comes over a target and
+ * `false` when it leaves. Distances are compared across targets, so they
+ * should cast the same camera's ray. Like `use_draw`, `hit_test` is
+ * registered once; the handlers follow the latest props
+ */
+pub fn use_hit_target(
+    hit_test: impl FnMut(f32, f32) -> Option<f32> + 'static,
+    onclick: Option<EventHandler<PickEvent>>,
+    onhover: Option<EventHandler<bool>>,
+) {
+    let hits = use_gl_context().hits;
+    let handlers = use_hook(|| Rc::new(Cell::new(HitHandlers::default())));
+    handlers.set(HitHandlers { onclick, onhover });
+    let id = use_hook({
+        let hits = hits.clone();
+        move || hits.borrow_mut().add(Box::new(hit_test), handlers)
+    });
+    use_drop(move || hits.borrow_mut().remove(id));
+}
+
+/**
+ * A canvas that creates its WebGL2 context with the given attributes once
+ * mounted and hands it to `oncontext`. Attributes can't change after
+ * creation, so later prop changes don't affect an existing context.
+ * `children` are rendered after the canvas and can reach the context with
+ * `use_gl_context`, or draw into it each frame with `use_draw`. `onframe`
+ * is opt-in and can be throttled with `frame_interval`. A `focusable` canvas
+ * is in the tab order and receives key events. A `responsive` canvas
+ * sizes its drawing buffer to match its laid-out size in device pixels, so
+ * size it with CSS; `width` and `height` only apply until the first layout
+ */
+#[component]
+pub fn WebGlCanvas(
+    id: Option<String>,
+    width: u32,
+    height: u32,
+    #[props(default)] style: String,
+    // Drawing buffer has an alpha channel, composited with the page
+    #[props(default = true)] alpha: bool,
+    // Multisampled default framebuffer
+    #[props(default = true)] antialias: bool,
+    #[props(default = true)] depth: bool,
+    #[props(default)] stencil: bool,
+    #[props(default)] power_preference: PowerPreference,
+    // Keep the drawing buffer after compositing, e.g. for `toDataURL` later
+    #[props(default)] preserve_drawing_buffer: bool,
+    // Lower latency by bypassing the compositor where supported
+    #[props(default)] desynchronized: bool,
+    oncontext: EventHandler<WebGl2RenderingContext>,
+    onmousedown: Option<EventHandler<MouseEvent>>,
+    onmousemove: Option<EventHandler<MouseEvent>>,
+    onmouseup: Option<EventHandler<MouseEvent>>,
+    onmouseleave: Option<EventHandler<MouseEvent>>,
+    onwheel: Option<EventHandler<WheelEvent>>,
+    // In the tab order, so it can take keyboard focus and `onkeydown` events
+    #[props(default)] focusable: bool,
+    // Describes the canvas to screen readers
+    #[props(default)] label: String,
+    onkeydown: Option<EventHandler<KeyboardEvent>>,
+    onfocus: Option<EventHandler<FocusEvent>>,
+    onblur: Option<EventHandler<FocusEvent>>,
+    // Files dropped onto the canvas; the browser's own handling is suppressed
+    ondrop: Option<EventHandler<DragEvent>>,
+    // Called from the frame loop after the draw callbacks, for app logic that
+    // has to keep pace with rendering
+    onframe: Option<EventHandler<Frame>>,
+    // Minimum seconds between `onframe` calls; zero calls it every frame
+    #[props(default)] frame_interval: f64,
+    // Follow the element's CSS size instead of the fixed `width` and `height`
+    #[props(default)] responsive: bool,
+    children: Element,
+) -> Element {
+    let shared = use_context_provider(|| GlContext {
+        gl: Signal::new(None),
+        size: Signal::new((width, height)),
+        draws: Rc::default(),
+        hits: Rc::default(),
+    });
+    // For the element's size when turning pointer positions into NDC
+    let element = use_hook(|| Rc::new(RefCell::new(None::<HtmlCanvasElement>)));
+    let mut shared_gl = shared.gl;
+    let mut shared_size = shared.size;
+    let mut failure = use_signal(|| None::<String>);
+    let observed = use_resize_observer();
+    use_effect({
+        let observed = observed.clone();
+        move || {
+            let Some((css_width, css_height)) = observed.size() else {
+                return;
+            };
+            // Match the display's pixel density so the image stays sharp
+            let ratio = web_sys::window().map_or(1.0, |window| window.device_pixel_ratio());
+            let pixels = |css: f64| ((css * ratio).round() as u32).max(1);
+            let size = (pixels(css_width), pixels(css_height));
+            if *shared_size.peek() != size {
+                shared_size.set(size);
+            }
+        }
+    });
+    let (buffer_width, buffer_height) = shared_size();
+    use_animation_frame({
+        let draws = shared.draws.clone();
+        let mut last_event = None::<f64>;
+        move |time, delta| {
+            // Nothing to draw into until the canvas has mounted
+            let Some(gl) = shared_gl.peek().clone() else {
+                return;
+            };
+            let frame = Frame { time, delta };
+            for entry in draws.borrow_mut().entries.iter_mut() {
+                (entry.draw)(&gl, frame);
+            }
+            if let Some(handler) = onframe {
+                // Throttled events report the time since the previous event
+                let since = last_event.map_or(0.0, |last| time - last);
+                if last_event.is_none() || since >= frame_interval {
+                    last_event = Some(time);
+                    handler.call(Frame { time, delta: since });
+                }
+            }
+        }
+    });
+
+    // Nearest hit target under the pointer of a mouse event, if any are registered
+    let pick = {
+        let hits = shared.hits.clone();
+        let element = element.clone();
+        move |evt: &MouseEvent| {
+            if hits.borrow().entries.is_empty() {
+                return None;
+            }
+            let element = element.borrow();
+            let canvas = element.as_ref()?;
+            // Relative to the padding box, which the client size measures
+            let point = evt.element_coordinates();
+            let (x, y) = math::pixel_to_ndc(
+                point.x,
+                point.y,
+                canvas.client_width().max(1) as f64,
+                canvas.client_height().max(1) as f64,
+            );
+            hits.borrow_mut().pick([x, y])
+        }
+    };
+    // Tell the targets the pointer moved between that `hovered` is now under it
+    let set_hovered = {
+        let hits = shared.hits.clone();
+        move |hovered: Option<usize>| {
+            let (left, entered) = {
+                let mut hits = hits.borrow_mut();
+                let previous = hits.hovered;
+                if previous == hovered {
+                    return;
+                }
+                hits.hovered = hovered;
+                (
+                    previous.and_then(|id| hits.handlers(id)),
+                    hovered.and_then(|id| hits.handlers(id)),
+                )
+            };
+            // Called once the list is released, as handlers may unmount targets
+            if let Some(handler) = left.and_then(|handlers| handlers.onhover) {
+                handler.call(false);
+            }
+            if let Some(handler) = entered.and_then(|handlers| handlers.onhover) {
+                handler.call(true);
+            }
+        }
+    };
+
+    let on_mounted = move |evt: MountedEvent| {
+        let Some(canvas) = evt
+            .downcast::<web_sys::Element>()
+            .and_then(|element| element.clone().dyn_into::<HtmlCanvasElement>().ok())
+        else {
+            return;
+        };
+        *element.borrow_mut() = Some(canvas.clone());
+        let (buffer_width, buffer_height) = *shared_size.peek();
+        canvas.set_width(buffer_width);
+        canvas.set_height(buffer_height);
+        if responsive {
+            observed.observe(&canvas);
+        }
+
+        let attributes = WebGlContextAttributes::new();
+        attributes.set_alpha(alpha);
+        attributes.set_antialias(antialias);
+        attributes.set_depth(depth);
+        attributes.set_stencil(stencil);
+        attributes.set_power_preference(match power_preference {
+            PowerPreference::Default => WebGlPowerPreference::Default,
+            PowerPreference::HighPerformance => WebGlPowerPreference::HighPerformance,
+            PowerPreference::LowPower => WebGlPowerPreference::LowPower,
+        });
+        attributes.set_preserve_drawing_buffer(preserve_drawing_buffer);
+        // Not in web-sys's bindings yet
+        let _ = js_sys::Reflect::set(
+            &attributes,
+            &"desynchronized".into(),
+            &desynchronized.into(),
+        );
+
+        match canvas
+            .get_context_with_context_options("webgl2", &attributes)
+            .ok()
+            .flatten()
+            .and_then(|context| context.dyn_into::<WebGl2RenderingContext>().ok())
+        {
+            Some(gl) => {
+                tracing::info!(target: "renderer", "Created WebGL2 context ({}×{})", buffer_width, buffer_height);
+                let gl = debug_gl::wrap(gl);
+                shared_gl.set(Some(gl.clone()));
+                oncontext.call(gl);
+            }
+            None => {
+                let reason = "Unable to create a WebGL2 context for this canvas".to_string();
+                gl_errors::report("WebGlCanvas", reason.clone());
+                failure.set(Some(reason));
+            }
+        }
+    };
+
+    rsx! {
+        canvas {
+            id,
+            width: "{buffer_width}",
+            height: "{buffer_height}",
+            style,
+            tabindex: if focusable { "0" },
+            // Interactive canvases handle keys themselves rather than reading as a picture
+            role: if !label.is_empty() { if focusable { "application" } else { "img" } },
+            aria_label: if !label.is_empty() { label },
+            onmounted: on_mounted,
+            onmousedown: move |evt| {
+                if let Some(handler) = onmousedown {
+                    handler.call(evt);
+                }
+            },
+            onmousemove: {
+                let pick = pick.clone();
+                let set_hovered = set_hovered.clone();
+                move |evt| {
+                    set_hovered(pick(&evt).map(|(id, _, _)| id));
+                    if let Some(handler) = onmousemove {
+                        handler.call(evt);
+                    }
+                }
+            },
+            onclick: move |evt| {
+                if let Some((_, handlers, event)) = pick(&evt) {
+                    if let Some(handler) = handlers.onclick {
+                        handler.call(event);
+                    }
+                }
+            },
+            onmouseup: move |evt| {
+                if let Some(handler) = onmouseup {
+                    handler.call(evt);
+                }
+            },
+            onmouseleave: move |evt| {
+                set_hovered(None);
+                if let Some(handler) = onmouseleave {
+                    handler.call(evt);
+                }
+            },
+            onwheel: move |evt| {
+                if let Some(handler) = onwheel {
+                    handler.call(evt);
+                }
+            },
+            onkeydown: move |evt| {
+                if let Some(handler) = onkeydown {
+                    handler.call(evt);
+                }
+            },
+            onfocus: move |evt| {
+                if let Some(handler) = onfocus {
+                    handler.call(evt);
+                }
+            },
+            onblur: move |evt| {
+                if let Some(handler) = onblur {
+                    handler.call(evt);
+                }
+            },
+            // Cancelling dragover is what marks the canvas as a drop target
+            ondragover: move |evt| {
+                if ondrop.is_some() {
+                    evt.prevent_default();
+                }
+            },
+            ondrop: move |evt| {
+                if let Some(handler) = ondrop {
+                    evt.prevent_default();
+                    handler.call(evt);
+                }
+            },
+        }
+        // Even where WebGL2 works, context creation can fail, e.g. once the
+        // browser's limit on live contexts is reached
+        if let Some(reason) = failure() {
+            WebGlUnavailable { reason }
+        } else {
+            {children}
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use dioxus::prelude::*;
+
+// What the demos look like, for visitors who can't run them
+const SCREENSHOT: Asset = asset!("/assets/webgl2.png");
+
+/**
+ * Explains why nothing can be drawn and what usually fixes it, shown in
+ * place of the demos when no WebGL2 context can be created
+ */
+#[component]
+pub fn WebGlUnavailable(reason: String) -> Element {
+    rsx! {
+        div {
+            class: "webgl-unavailable",
+            h2 { "WebGL2 is not available" }
+            p { class: "webgl-unavailable-reason", "{reason}" }
+            p { "These demos need a browser with WebGL2 enabled:" }
+            ul {
+                li { "Chrome, Edge or Firefox from the last few years, or Safari 15 or newer" }
+                li {
+                    "Hardware acceleration turned on, e.g. "
+                    em { "Settings → System → Use graphics acceleration when available" }
+                    " in Chrome and Edge"
+                }
+                li { "A GPU and driver the browser hasn't blocklisted; updating the driver often helps" }
+            }
+            p {
+                a {
+                    href: "https://get.webgl.org/webgl2/",
+                    target: "_blank",
+                    "Check WebGL2 support and get help"
+                }
+            }
+            p { "This is what you would see:" }
+            img { src: SCREENSHOT, alt: "Screenshot of the spinning cube demo" }
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use dioxus::prelude::*;
+
+use crate::shaders;
+use crate::wgsl;
+
+/**
+ * Whether each program under `shaders/` translates to WGSL, with the
+ * generated modules and the buffer layout they expect, or the reason it
+ * doesn't. Every permutation of the program's defines has to translate;
+ * the one shown has them all set. Translation runs once and needs no context
+ */
+#[component]
+pub fn WgslTranslations() -> Element {
+    let results = use_hook(|| {
+        shaders::PROGRAMS
+            .iter()
+            .map(|program| {
+                let mut result = Err(String::new());
+                for defines in program.permutations() {
+                    result = wgsl::translate(program.vertex, program.fragment, &defines)
+                        .map_err(|err| format!("{} with {:?}: {}", program.name, defines, err));
+                    if result.is_err() {
+                        break;
+                    }
+                }
+                (program.name, result)
+            })
+            .collect::<Vec<_>>()
+    });
+    let translated = results.iter().filter(|(_, result)| result.is_ok()).count();
+
+    rsx! {
+        div {
+            class: "wgsl-translations",
+            h4 { "WGSL translation: {translated} of {results.len()} programs" }
+            for (name, result) in results.iter() {
+                match result {
+                    Ok(program) => rsx! {
+                        details {
+                            key: "{name}",
+                            summary { "{name}" }
+                            p {
+                                "attributes: "
+                                for attribute in program.attributes.iter() {
+                                    "{attribute.location} {attribute.name}: {attribute.wgsl_type}; "
+                                }
+                            }
+                            p {
+                                "uniforms ({program.uniform_size} bytes): "
+                                for field in program.uniforms.iter() {
+                                    "{field.name} @{field.offset}+{field.size}; "
+                                }
+                            }
+                            if !program.textures.is_empty() {
+                                p { "textures: {program.textures.join(\", \")}" }
+                            }
+                            pre { "{program.vertex}" }
+                            pre { "{program.fragment}" }
+                        }
+                    },
+                    Err(err) => rsx! {
+                        p {
+                            key: "{name}",
+                            class: "wgsl-unsupported",
+                            "{err}"
+                        }
+                    },
+                }
+            }
+        }
+    }
+}
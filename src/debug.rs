@@ -0,0 +1,196 @@
+use std::cell::RefCell;
+
+use web_sys::{WebGl2RenderingContext, WebGlProgram, WebGlUniformLocation, WebGlVertexArrayObject};
+
+use crate::bounds::Aabb;
+use crate::dynamic_buffer::DynamicBuffer;
+use crate::frustum;
+use crate::gl_util;
+use crate::math::{self, Mat4, Vec3};
+use crate::shaders;
+
+// position.xyz + rgb per vertex
+const FLOATS_PER_VERTEX: usize = 6;
+const STRIDE: i32 = (FLOATS_PER_VERTEX * 4) as i32;
+// Line segments per great circle of a debug sphere
+const SPHERE_SEGMENTS: usize = 24;
+// Vertex normals and tangents
+const NORMAL_COLOR: [f32; 3] = [0.3, 0.65, 1.0];
+const TANGENT_COLOR: [f32; 3] = [1.0, 0.4, 0.3];
+
+thread_local! {
+    // Primitives queued since the last flush: line vertex pairs and single points
+    static LINES: RefCell<Vec<f32>> = const { RefCell::new(Vec::new()) };
+    static POINTS: RefCell<Vec<f32>> = const { RefCell::new(Vec::new()) };
+}
+
+fn push_vertex(vertices: &mut Vec<f32>, p: Vec3, color: [f32; 3]) {
+    vertices.extend(p);
+    vertices.extend(color);
+}
+
+/**
+ * Queue a world-space line segment for the next `DebugRenderer::flush`
+ */
+pub fn line(a: Vec3, b: Vec3, color: [f32; 3]) {
+    LINES.with_borrow_mut(|lines| {
+        push_vertex(lines, a, color);
+        push_vertex(lines, b, color);
+    });
+}
+
+/**
+ * Queue a world-space point, drawn as a small square
+ */
+pub fn point(p: Vec3, color: [f32; 3]) {
+    POINTS.with_borrow_mut(|points| push_vertex(points, p, color));
+}
+
+/**
+ * Queue the twelve edges of a box
+ */
+pub fn aabb(aabb: &Aabb, color: [f32; 3]) {
+    box_edges(&aabb.corners(), color);
+}
+
+/**
+ * Queue the edges of the volume a camera with `view_proj` sees, such as one
+ * that isn't the one being drawn with
+ */
+pub fn frustum(view_proj: &Mat4, color: [f32; 3]) {
+    if let Some(corners) = frustum::corners(view_proj) {
+        box_edges(&corners, color);
+    }
+}
+
+/**
+ * Queue a line `length` long from each vertex along its normal, and along
+ * its tangent when there are tangents, with `model` placing the mesh in the
+ * world. Normals go through the inverse transpose, so they stay
+ * perpendicular to surfaces under non-uniform scale
+ */
+pub fn vertex_vectors(
+    model: &Mat4,
+    positions: &[f32],
+    normals: &[Vec3],
+    tangents: Option<&[Vec3]>,
+    length: f32,
+) {
+    let Some(inverse) = math::invert(model) else {
+        return;
+    };
+    let normal_matrix = math::transpose(&inverse);
+    for (vertex, p) in positions.chunks_exact(3).enumerate() {
+        let origin = math::transform_point(model, [p[0], p[1], p[2]]);
+        let draw = |direction: Vec3, color: [f32; 3]| {
+            let tip = math::scale(math::normalize(direction), length);
+            line(origin, math::add(origin, tip), color);
+        };
+        draw(
+            math::transform_vector(&normal_matrix, normals[vertex]),
+            NORMAL_COLOR,
+        );
+        if let Some(tangents) = tangents {
+            draw(
+                math::transform_vector(model, tangents[vertex]),
+                TANGENT_COLOR,
+            );
+        }
+    }
+}
+
+fn box_edges(corners: &[Vec3; 8], color: [f32; 3]) {
+    // Corner index bits select max over min on x (1), y (2) and z (4)
+    for bit in [1, 2, 4] {
+        for i in (0..8).filter(|i| i & bit == 0) {
+            line(corners[i], corners[i | bit], color);
+        }
+    }
+}
+
+/**
+ * Queue a wire sphere: one circle around each axis
+ */
+pub fn sphere(center: Vec3, radius: f32, color: [f32; 3]) {
+    let circle_point = |axis: usize, angle: f32| {
+        let (s, c) = angle.sin_cos();
+        let mut p = center;
+        p[(axis + 1) % 3] += c * radius;
+        p[(axis + 2) % 3] += s * radius;
+        p
+    };
+    for axis in 0..3 {
+        for i in 0..SPHERE_SEGMENTS {
+            let step = std::f32::consts::TAU / SPHERE_SEGMENTS as f32;
+            line(
+                circle_point(axis, i as f32 * step),
+                circle_point(axis, (i + 1) as f32 * step),
+                color,
+            );
+        }
+    }
+}
+
+/**
+ * Draws everything queued through this module's functions in one pass per
+ * frame, on top of the scene, then clears the queue
+ */
+pub struct DebugRenderer {
+    program: WebGlProgram,
+    view_proj_loc: Option<WebGlUniformLocation>,
+    vao: WebGlVertexArrayObject,
+    buffer: DynamicBuffer,
+}
+
+impl DebugRenderer {
+    pub fn new(gl: &WebGl2RenderingContext) -> Result<Self, String> {
+        let program = gl_util::create_program(gl, shaders::DEBUG_VERT, shaders::DEBUG_FRAG)?;
+        let vao = gl
+            .create_vertex_array()
+            .ok_or_else(|| "Unable to create debug draw VAO".to_string())?;
+        let buffer = DynamicBuffer::new(gl, WebGl2RenderingContext::ARRAY_BUFFER)?;
+
+        gl.bind_vertex_array(Some(&vao));
+        buffer.bind(gl);
+        gl.enable_vertex_attrib_array(0);
+        gl.vertex_attrib_pointer_with_i32(0, 3, WebGl2RenderingContext::FLOAT, false, STRIDE, 0);
+        gl.enable_vertex_attrib_array(1);
+        gl.vertex_attrib_pointer_with_i32(1, 3, WebGl2RenderingContext::FLOAT, false, STRIDE, 12);
+        gl.bind_vertex_array(None);
+
+        Ok(Self {
+            view_proj_loc: gl.get_uniform_location(&program, "viewProjection"),
+            program,
+            vao,
+            buffer,
+        })
+    }
+
+    pub fn flush(&self, gl: &WebGl2RenderingContext, view_proj: &Mat4) {
+        let lines = LINES.take();
+        let points = POINTS.take();
+        if lines.is_empty() && points.is_empty() {
+            return;
+        }
+        let line_vertices = (lines.len() / FLOATS_PER_VERTEX) as i32;
+        let point_vertices = (points.len() / FLOATS_PER_VERTEX) as i32;
+
+        // Points go right after the lines, each streamed straight from its queue
+        self.buffer.orphan(gl, lines.len() + points.len());
+        self.buffer.write_at(gl, 0, &lines);
+        self.buffer.write_at(gl, lines.len(), &points);
+
+        gl.use_program(Some(&self.program));
+        gl.uniform_matrix4fv_with_f32_array(self.view_proj_loc.as_ref(), false, view_proj);
+        // Always visible, even inside or behind other geometry
+        gl.disable(WebGl2RenderingContext::DEPTH_TEST);
+        gl.bind_vertex_array(Some(&self.vao));
+        gl.draw_arrays(WebGl2RenderingContext::LINES, 0, line_vertices);
+        gl.draw_arrays(
+            WebGl2RenderingContext::POINTS,
+            line_vertices,
+            point_vertices,
+        );
+        gl.bind_vertex_array(None);
+    }
+}
@@ -0,0 +1,72 @@
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::WebGl2RenderingContext;
+
+use crate::gl_errors;
+use crate::query;
+
+// Query parameter that turns the wrapper on without rebuilding, e.g. /scene?debug-gl
+const QUERY_FLAG: &str = "debug-gl";
+
+#[wasm_bindgen(inline_js = r#"
+function describe(arg) {
+    if (arg === null || arg === undefined) return String(arg);
+    if (typeof arg === "string") return JSON.stringify(arg);
+    if (typeof arg !== "object") return String(arg);
+    if (ArrayBuffer.isView(arg)) return `${arg.constructor.name}(${arg.length})`;
+    return arg.constructor ? arg.constructor.name : "object";
+}
+
+export function wrapContext(gl, report) {
+    const wrappers = new Map();
+    return new Proxy(gl, {
+        get(target, name) {
+            const value = Reflect.get(target, name);
+            if (typeof value !== "function" || name === "getError") return value;
+            let wrapper = wrappers.get(name);
+            if (!wrapper) {
+                wrapper = function (...args) {
+                    const result = value.apply(target, args);
+                    for (let error = target.getError(); error !== target.NO_ERROR; error = target.getError()) {
+                        report(String(name), args.map(describe).join(", "), error);
+                        if (error === target.CONTEXT_LOST_WEBGL) break;
+                    }
+                    return result;
+                };
+                wrappers.set(name, wrapper);
+            }
+            return wrapper;
+        },
+    });
+}
+"#)]
+extern "C" {
+    #[wasm_bindgen(js_name = wrapContext)]
+    fn wrap_context(gl: &WebGl2RenderingContext, report: &JsValue) -> JsValue;
+}
+
+/**
+ * Whether GL calls should be checked one by one: always with the `debug-gl`
+ * feature, otherwise when the page URL has a `debug-gl` query parameter
+ */
+pub fn enabled() -> bool {
+    cfg!(feature = "debug-gl") || query::current().iter().any(|(key, _)| key == QUERY_FLAG)
+}
+
+/**
+ * `gl` as is, or when debugging is `enabled`, a `DebugGl` wrapper: a JS
+ * proxy that calls `getError` after every method and logs each error with the
+ * call and its arguments, e.g. `drawElements(4, 36, 5123, 0)`. It is a drop-in
+ * replacement for the context, but slows every call down considerably
+ */
+pub fn wrap(gl: WebGl2RenderingContext) -> WebGl2RenderingContext {
+    if !enabled() {
+        return gl;
+    }
+    tracing::info!(target: "renderer", "DebugGl: checking every GL call for errors");
+    let report =
+        Closure::<dyn FnMut(String, String, u32)>::new(|name: String, args: String, error: u32| {
+            gl_errors::report(&format!("{}({})", name, args), gl_errors::error_name(error));
+        });
+    // Handed over to the JS garbage collector, which frees it with the proxy
+    wrap_context(&gl, &report.into_js_value()).unchecked_into()
+}
@@ -0,0 +1,210 @@
+use dioxus::prelude::*;
+use std::rc::Rc;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlCanvasElement;
+
+use crate::backend::{
+    self, AnyDevice, BackendKind, BufferUsage, Device, Draw, PipelineDesc, UniformBlock,
+    VertexAttribute, VertexBufferLayout,
+};
+use crate::gl_errors;
+use crate::hooks::{use_frame_loop, FrameLoop};
+use crate::math::{look_at, multiply, perspective, rotation_matrix_x, rotation_matrix_y};
+use crate::shaders;
+
+const SIZE: u32 = 480;
+const CHECKER_SIZE: u32 = 64;
+// Position, normal and UV, interleaved
+const STRIDE: u32 = 8 * 4;
+const ATTRIBUTES: [VertexAttribute; 3] = [
+    VertexAttribute {
+        name: "position",
+        components: 3,
+        offset: 0,
+    },
+    VertexAttribute {
+        name: "normal",
+        components: 3,
+        offset: 12,
+    },
+    VertexAttribute {
+        name: "uv",
+        components: 2,
+        offset: 24,
+    },
+];
+
+/**
+ * A textured, lit cube drawn through the backend abstraction: with WebGPU
+ * where the app is built with the `webgpu` feature and the browser supports
+ * it, WebGL2 otherwise. `?backend=webgl2` or `?backend=webgpu` picks one
+ */
+#[component]
+pub fn BackendDemo() -> Element {
+    let mut canvas_mounted = use_signal(|| false);
+    let mut active = use_signal(|| None::<BackendKind>);
+    let frame_loop = use_frame_loop();
+
+    use_effect(move || {
+        if !canvas_mounted() {
+            return;
+        }
+        let frame_loop = frame_loop.clone();
+        spawn(async move {
+            let Some(canvas) = web_sys::window()
+                .and_then(|window| window.document())
+                .and_then(|document| document.get_element_by_id("backend-canvas"))
+                .and_then(|element| element.dyn_into::<HtmlCanvasElement>().ok())
+            else {
+                return;
+            };
+            canvas.set_width(SIZE);
+            canvas.set_height(SIZE);
+            let started = match backend::create(&canvas, BackendKind::from_query()).await {
+                Ok(AnyDevice::WebGl2(device)) => start(device, &frame_loop),
+                #[cfg(feature = "webgpu")]
+                Ok(AnyDevice::WebGpu(device)) => start(device, &frame_loop),
+                Err(err) => Err(err),
+            };
+            match started {
+                Ok(kind) => active.set(Some(kind)),
+                Err(err) => gl_errors::report("backend: setup", err),
+            }
+        });
+    });
+
+    let caption = match active() {
+        Some(kind) => format!("Drawn with {}", kind.name()),
+        None => "Starting…".to_string(),
+    };
+    rsx! {
+        div {
+            style: "display: flex; flex-direction: column; justify-content: center; align-items: center; height: 100vh; background: #f0f0f0;",
+            canvas {
+                id: "backend-canvas",
+                width: "{SIZE}",
+                height: "{SIZE}",
+                style: "border: 2px solid #333; background: #222;",
+                onmounted: move |_| canvas_mounted.set(true),
+            }
+            p {
+                style: "font-family: monospace; color: #333;",
+                "{caption}"
+            }
+        }
+    }
+}
+
+/**
+ * Create the cube's resources on `device` and spin it every frame. Nothing
+ * here knows which API it is talking to
+ */
+fn start<D: Device + 'static>(
+    mut device: D,
+    frame_loop: &FrameLoop,
+) -> Result<BackendKind, String> {
+    device.resize(SIZE, SIZE);
+    let program = shaders::program("demos/backend")
+        .ok_or_else(|| "The backend demo's shaders are missing".to_string())?;
+    let layouts = [VertexBufferLayout {
+        stride: STRIDE,
+        attributes: &ATTRIBUTES,
+    }];
+    let pipeline = device.create_pipeline(&PipelineDesc {
+        program,
+        defines: &[],
+        vertex_buffers: &layouts,
+        depth_test: true,
+        cull_back_faces: true,
+    })?;
+
+    let (vertices, indices) = cube();
+    let vertex_bytes: Vec<u8> = vertices.iter().flat_map(|v| v.to_le_bytes()).collect();
+    let index_bytes: Vec<u8> = indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+    let vertex_buffer = device.create_buffer(BufferUsage::Vertex, &vertex_bytes)?;
+    let index_buffer = device.create_buffer(BufferUsage::Index, &index_bytes)?;
+    let checker = device.create_texture((CHECKER_SIZE, CHECKER_SIZE), &checker_pixels())?;
+    let uniforms = device.create_buffer(
+        BufferUsage::Uniform,
+        UniformBlock::new(device.uniform_layout(&pipeline)).bytes(),
+    )?;
+    let bindings = device.create_bindings(&pipeline, &uniforms, &[&checker])?;
+
+    let kind = device.kind();
+    let projection = perspective(45f32.to_radians(), 1.0, 0.1, 100.0);
+    let view = look_at([0.0, 0.0, 3.0], [0.0, 0.0, 0.0], [0.0, 1.0, 0.0]);
+    let device = Rc::new(device);
+    frame_loop.start(move |time, _delta| {
+        let angle = time as f32;
+        let model = multiply(&rotation_matrix_y(angle), &rotation_matrix_x(angle * 0.6));
+        let model_view_projection = multiply(&projection, &multiply(&view, &model));
+        let mut block = UniformBlock::new(device.uniform_layout(&pipeline));
+        block
+            .set("modelViewProjection", &model_view_projection)
+            .set("model", &model)
+            .set("lightDirection", &[0.36, 0.48, 0.8]);
+        let drawn = device.write_buffer(&uniforms, block.bytes()).and_then(|_| {
+            device.render(
+                [0.1, 0.1, 0.1, 1.0],
+                &[Draw {
+                    pipeline: &pipeline,
+                    bindings: &bindings,
+                    vertex_buffers: &[&vertex_buffer],
+                    index_buffer: &index_buffer,
+                    index_count: indices.len() as u32,
+                }],
+            )
+        });
+        if let Err(err) = drawn {
+            gl_errors::report("backend: draw", err);
+        }
+    });
+    tracing::info!(target: "renderer", "Backend demo drawing with {}", kind.name());
+    Ok(kind)
+}
+
+/**
+ * A unit cube with its own four corners per face, so each face gets the
+ * whole texture and a flat normal
+ */
+fn cube() -> (Vec<f32>, Vec<u16>) {
+    // Normal, then the directions U and V run in, per face
+    let faces: [([f32; 3], [f32; 3], [f32; 3]); 6] = [
+        ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+        ([0.0, 0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+        ([1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+        ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+        ([0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+        ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+    ];
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for (face, (normal, u, v)) in faces.iter().enumerate() {
+        for (s, t) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+            for axis in 0..3 {
+                vertices.push(normal[axis] * 0.5 + u[axis] * (s - 0.5) + v[axis] * (t - 0.5));
+            }
+            vertices.extend_from_slice(normal);
+            vertices.extend_from_slice(&[s, t]);
+        }
+        let base = face as u16 * 4;
+        indices.extend_from_slice(&[base, base + 1, base + 2, base + 2, base + 3, base]);
+    }
+    (vertices, indices)
+}
+
+fn checker_pixels() -> Vec<u8> {
+    let cell = CHECKER_SIZE / 8;
+    let mut pixels = Vec::with_capacity((CHECKER_SIZE * CHECKER_SIZE * 4) as usize);
+    for y in 0..CHECKER_SIZE {
+        for x in 0..CHECKER_SIZE {
+            let light = (x / cell + y / cell).is_multiple_of(2);
+            pixels.extend_from_slice(if light {
+                &[235, 200, 120, 255]
+            } else {
+                &[60, 90, 160, 255]
+            });
+        }
+    }
+    pixels
+}
@@ -0,0 +1,53 @@
+use dioxus::prelude::*;
+
+use crate::components::BarChart3D;
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+#[component]
+pub fn ChartDemo() -> Element {
+    let mut values = use_signal(|| vec![12.0, 18.5, 9.0, 22.0, 30.5, 26.0]);
+
+    let labels: Vec<String> = MONTHS.iter().map(|month| month.to_string()).collect();
+    let count = values.read().len();
+
+    rsx! {
+        div {
+            style: "display: flex; flex-direction: column; justify-content: center; align-items: center; height: 100vh; background: #f0f0f0;",
+            BarChart3D { values, labels }
+            div {
+                style: "display: flex; gap: 8px; margin-top: 10px; font-family: monospace;",
+                button {
+                    onclick: move |_| {
+                        for value in values.write().iter_mut() {
+                            *value = random_value();
+                        }
+                    },
+                    "Randomize"
+                }
+                button {
+                    disabled: count >= MONTHS.len(),
+                    onclick: move |_| values.write().push(random_value()),
+                    "Add bar"
+                }
+                button {
+                    disabled: count == 0,
+                    onclick: move |_| {
+                        values.write().pop();
+                    },
+                    "Remove bar"
+                }
+            }
+            p {
+                style: "color: #333; font-family: monospace;",
+                "Bars are driven by a Dioxus signal passed in as a prop"
+            }
+        }
+    }
+}
+
+fn random_value() -> f32 {
+    (5.0 + js_sys::Math::random() * 35.0).round() as f32
+}
@@ -0,0 +1,369 @@
+use dioxus::prelude::*;
+use std::cell::{Cell, OnceCell};
+use std::rc::Rc;
+use web_sys::{WebGl2RenderingContext, WebGlProgram, WebGlUniformLocation};
+
+use crate::assets::{use_asset, Assets};
+use crate::camera::OrbitCamera;
+use crate::components::{use_draw, use_hit_target, Frame, PickEvent, WebGlCanvas};
+use crate::gl_errors;
+use crate::gl_util;
+use crate::grid::InfiniteGrid;
+use crate::hooks::use_gl_resource;
+use crate::math::{self, Mat4, Vec3};
+use crate::mesh::{GpuMesh, MeshData};
+use crate::raycast::{self, Ray};
+use crate::scene::Transform;
+use crate::shaders;
+
+// Initial drawing buffer size, until the canvas has been laid out
+const CANVAS_WIDTH: u32 = 640;
+const CANVAS_HEIGHT: u32 = 400;
+
+// Draw order of the pieces: camera and clear first, then geometry, grid last
+// so it blends over the cleared background but is hidden behind the cubes
+const CAMERA_PRIORITY: i32 = -100;
+const CUBE_PRIORITY: i32 = 0;
+const GRID_PRIORITY: i32 = 100;
+
+// Four updates a second are plenty for a clock
+const FRAME_EVENT_INTERVAL: f64 = 0.25;
+
+const GEM_MODEL: Asset = asset!("/assets/models/gem.obj");
+const GEM_COLOR: Vec3 = [0.95, 0.75, 0.3];
+
+const CUBES: [(&str, Vec3, Vec3); 3] = [
+    ("Red", [-1.6, 0.0, 0.0], [0.9, 0.3, 0.25]),
+    ("Green", [0.0, 0.0, 0.0], [0.35, 0.8, 0.4]),
+    ("Blue", [1.6, 0.0, 0.0], [0.3, 0.5, 0.95]),
+];
+
+/**
+ * What the pieces of the composed scene share: the camera set up by
+ * `OrbitView` each frame and the cube program, compiled by the first cube
+ */
+#[derive(Clone, Default)]
+struct Shared {
+    view_proj: Rc<Cell<Mat4>>,
+    cube_program: Rc<OnceCell<Option<Rc<CubeProgram>>>>,
+}
+
+struct CubeProgram {
+    program: WebGlProgram,
+    mvp_loc: Option<WebGlUniformLocation>,
+    model_loc: Option<WebGlUniformLocation>,
+    color_loc: Option<WebGlUniformLocation>,
+}
+
+impl Shared {
+    fn cube_program(&self, gl: &WebGl2RenderingContext) -> Option<Rc<CubeProgram>> {
+        self.cube_program
+            .get_or_init(|| {
+                match gl_util::create_program(
+                    gl,
+                    shaders::DEMOS_COMPOSE_VERT,
+                    shaders::DEMOS_COMPOSE_FRAG,
+                ) {
+                    Ok(program) => Some(Rc::new(CubeProgram {
+                        mvp_loc: gl.get_uniform_location(&program, "modelViewProjection"),
+                        model_loc: gl.get_uniform_location(&program, "model"),
+                        color_loc: gl.get_uniform_location(&program, "color"),
+                        program,
+                    })),
+                    Err(err) => {
+                        gl_errors::report("compose: cube program setup", err);
+                        None
+                    }
+                }
+            })
+            .clone()
+    }
+}
+
+/**
+ * A scene built from independent components, each drawing into the canvas's
+ * frame loop from its own draw callback. Unchecking a cube unmounts its
+ * component, which takes its callback out of the loop
+ */
+#[component]
+pub fn ComposeDemo() -> Element {
+    use_context_provider(Shared::default);
+    let mut visible = use_signal(|| [true; CUBES.len()]);
+    let mut show_grid = use_signal(|| true);
+    let mut show_gem = use_signal(|| false);
+    // Seconds the canvas has been running, from its throttled frame events
+    let mut elapsed = use_signal(|| 0.0);
+    // Pointer events from the cubes' own handlers
+    let mut hovered = use_signal(|| None::<usize>);
+    let mut clicks = use_signal(|| [0u32; CUBES.len()]);
+    let cursor = if hovered().is_some() {
+        "pointer"
+    } else {
+        "default"
+    };
+    let hovered_text = match hovered() {
+        Some(i) => format!("Pointing at the {} cube", CUBES[i].0),
+        None => "Point at a cube".to_string(),
+    };
+    let clicks_text = CUBES
+        .iter()
+        .zip(clicks())
+        .map(|((name, _, _), count)| format!("{} {}", name, count))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    rsx! {
+        div {
+            style: "display: flex; flex-direction: column; justify-content: center; align-items: center; height: 100vh; background: #f0f0f0;",
+            // Drag the corner to resize; the drawing buffer and camera follow
+            div {
+                style: "position: relative; width: {CANVAS_WIDTH}px; height: {CANVAS_HEIGHT}px; max-width: 90vw; min-width: 120px; min-height: 120px; resize: both; overflow: hidden;",
+                WebGlCanvas {
+                    width: CANVAS_WIDTH,
+                    height: CANVAS_HEIGHT,
+                    responsive: true,
+                    style: "display: block; box-sizing: border-box; width: 100%; height: 100%; border: 2px solid #333; background: #222; cursor: {cursor};",
+                    oncontext: move |_| {},
+                    onframe: move |frame: Frame| elapsed += frame.delta,
+                    frame_interval: FRAME_EVENT_INTERVAL,
+                    OrbitView {}
+                    for (i, (name, position, color)) in CUBES.into_iter().enumerate() {
+                        if visible()[i] {
+                            SpinningCube {
+                                key: "{name}",
+                                position,
+                                // Lit up while the pointer is on it
+                                color: if hovered() == Some(i) { color.map(|c| (c * 1.4).min(1.0)) } else { color },
+                                speed: 0.6 + i as f32 * 0.4,
+                                onclick: move |_| clicks.write()[i] += 1,
+                                onhover: move |over: bool| {
+                                    if over {
+                                        hovered.set(Some(i));
+                                    } else if hovered() == Some(i) {
+                                        hovered.set(None);
+                                    }
+                                },
+                            }
+                        }
+                    }
+                    if show_grid() {
+                        GroundGrid {}
+                    }
+                    if show_gem() {
+                        ErrorBoundary {
+                            handle_error: |errors: ErrorContext| rsx! {
+                                div { class: "gl-info", "Model failed: {errors.errors()[0]}" }
+                            },
+                            SuspenseBoundary {
+                                fallback: |_| rsx! {
+                                    div { class: "gl-info", "Loading model…" }
+                                },
+                                Gem {}
+                            }
+                        }
+                    }
+                }
+            }
+            div {
+                style: "display: flex; gap: 16px; align-items: center; margin-top: 10px; color: #333; font-family: monospace;",
+                for (i, (name, _, _)) in CUBES.into_iter().enumerate() {
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            checked: visible()[i],
+                            onchange: move |evt| {
+                                visible.write()[i] = evt.checked();
+                                // An unmounted cube can't report the pointer leaving
+                                if hovered() == Some(i) {
+                                    hovered.set(None);
+                                }
+                            },
+                        }
+                        " {name} cube"
+                    }
+                }
+                label {
+                    input {
+                        r#type: "checkbox",
+                        checked: show_grid(),
+                        onchange: move |evt| show_grid.set(evt.checked()),
+                    }
+                    " Grid"
+                }
+                label {
+                    input {
+                        r#type: "checkbox",
+                        checked: show_gem(),
+                        onchange: move |evt| show_gem.set(evt.checked()),
+                    }
+                    " Gem model"
+                }
+            }
+            p {
+                style: "color: #333; font-family: monospace;",
+                "Each piece is its own component registering a draw callback with the canvas"
+            }
+            p {
+                style: "color: #333; font-family: monospace;",
+                "{hovered_text} · clicks: {clicks_text}"
+            }
+            p {
+                style: "color: #333; font-family: monospace;",
+                "Running for {elapsed():.1} s"
+            }
+        }
+    }
+}
+
+/**
+ * Clears the canvas and sets the slowly circling camera for everything after it
+ */
+#[component]
+fn OrbitView() -> Element {
+    let shared = use_context::<Shared>();
+
+    use_draw(CAMERA_PRIORITY, move |gl, frame| {
+        // Current size, as the canvas may have been resized since last frame
+        let (width, height) = (gl.drawing_buffer_width(), gl.drawing_buffer_height());
+        let mut camera = OrbitCamera::default();
+        camera.yaw = (frame.time * 0.2) as f32;
+        camera.pitch = 0.45;
+        camera.distance = 6.5;
+        shared
+            .view_proj
+            .set(camera.view_projection(width as f32 / height as f32));
+
+        gl.viewport(0, 0, width, height);
+        gl.enable(WebGl2RenderingContext::DEPTH_TEST);
+        gl.clear_color(0.1, 0.1, 0.1, 1.0);
+        gl.clear(
+            WebGl2RenderingContext::COLOR_BUFFER_BIT | WebGl2RenderingContext::DEPTH_BUFFER_BIT,
+        );
+    });
+
+    rsx! {}
+}
+
+/**
+ * A cube turning about its vertical axis at `speed` radians per second.
+ * Clicks and hovers that hit it as drawn reach `onclick` and `onhover`
+ */
+#[component]
+fn SpinningCube(
+    position: Vec3,
+    color: Vec3,
+    speed: f32,
+    onclick: Option<EventHandler<PickEvent>>,
+    onhover: Option<EventHandler<bool>>,
+) -> Element {
+    let shared = use_context::<Shared>();
+    // Latest props for the draw callback, which is registered once
+    let props = use_hook(|| Rc::new(Cell::new((position, color, speed))));
+    props.set((position, color, speed));
+    // Where the last frame drew it, for hit-testing
+    let drawn = use_hook(|| Rc::new(Cell::new(None::<Mat4>)));
+    use_hit_target(
+        {
+            let shared = shared.clone();
+            let drawn = drawn.clone();
+            let cube = MeshData::cube();
+            move |x, y| {
+                let inverse = math::invert(&shared.view_proj.get())?;
+                raycast::cast_mesh(&Ray::from_ndc(&inverse, x, y), &cube, &drawn.get()?)
+            }
+        },
+        onclick,
+        onhover,
+    );
+    // Each cube owns its mesh, deleted when the cube is unchecked
+    let mesh = use_gl_resource((), |gl, _| {
+        GpuMesh::upload(gl, &MeshData::cube()).ok_or_else(|| "Unable to upload cube".to_string())
+    });
+
+    use_draw(CUBE_PRIORITY, {
+        let props = props.clone();
+        let mut angle = 0.0f32;
+        move |gl, frame| {
+            let Some(program) = shared.cube_program(gl) else {
+                return;
+            };
+            let Some(mesh) = mesh.get() else {
+                return;
+            };
+            let (position, color, speed) = props.get();
+            angle += speed * frame.delta as f32;
+
+            let model = Transform {
+                translation: position,
+                rotation: [0.0, angle, 0.0],
+                scale: [1.0; 3],
+            }
+            .matrix();
+            drawn.set(Some(model));
+            let mvp = math::multiply(&shared.view_proj.get(), &model);
+            gl.use_program(Some(&program.program));
+            gl.uniform_matrix4fv_with_f32_array(program.mvp_loc.as_ref(), false, &mvp);
+            gl.uniform_matrix4fv_with_f32_array(program.model_loc.as_ref(), false, &model);
+            gl.uniform3fv_with_f32_array(program.color_loc.as_ref(), &color);
+            mesh.draw(gl);
+        }
+    });
+
+    rsx! {}
+}
+
+/**
+ * A model floating over the cubes. Suspends until the download has been
+ * decoded, so the boundary around it shows a fallback until then, and draws
+ * once its mesh is on the GPU
+ */
+#[component]
+fn Gem() -> Element {
+    let shared = use_context::<Shared>();
+    let assets = use_context::<Assets>();
+    let handle = use_hook(|| assets.load_mesh(&GEM_MODEL.to_string()));
+    use_asset(handle)?;
+    let mesh = use_gl_resource((), |gl, _| {
+        let data = assets
+            .mesh(handle)
+            .ok_or_else(|| "Gem model missing after load".to_string())?;
+        GpuMesh::upload(gl, &data).ok_or_else(|| "Unable to upload gem".to_string())
+    });
+
+    use_draw(CUBE_PRIORITY, move |gl, frame| {
+        let (Some(program), Some(mesh)) = (shared.cube_program(gl), mesh.get()) else {
+            return;
+        };
+        let model = Transform {
+            translation: [0.0, 1.6 + 0.15 * (frame.time * 1.5).sin() as f32, 0.0],
+            rotation: [0.0, -frame.time as f32, 0.0],
+            scale: [1.2; 3],
+        }
+        .matrix();
+        let mvp = math::multiply(&shared.view_proj.get(), &model);
+        gl.use_program(Some(&program.program));
+        gl.uniform_matrix4fv_with_f32_array(program.mvp_loc.as_ref(), false, &mvp);
+        gl.uniform_matrix4fv_with_f32_array(program.model_loc.as_ref(), false, &model);
+        gl.uniform3fv_with_f32_array(program.color_loc.as_ref(), &GEM_COLOR);
+        mesh.draw(gl);
+    });
+
+    rsx! {}
+}
+
+/**
+ * The infinite ground grid just under the cubes
+ */
+#[component]
+fn GroundGrid() -> Element {
+    let shared = use_context::<Shared>();
+    let grid = use_gl_resource((), |gl, _| InfiniteGrid::new(gl));
+
+    use_draw(GRID_PRIORITY, move |gl, _| {
+        if let Some(grid) = grid.get() {
+            grid.draw(gl, &shared.view_proj.get(), -0.5);
+        }
+    });
+
+    rsx! {}
+}
@@ -0,0 +1,440 @@
+use dioxus::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use web_sys::{WebGl2RenderingContext, WebGlProgram, WebGlUniformLocation};
+
+use crate::debug::{self, DebugRenderer};
+use crate::dice;
+use crate::gl_errors;
+use crate::gl_util;
+use crate::hooks::use_frame_loop;
+use crate::math::{self, rotation_matrix_y, Vec3};
+use crate::mesh::{GpuTexturedMesh, TexturedMeshData};
+use crate::shaders;
+use crate::texture::Texture;
+
+// Pixels per side of each die face
+const FACE_SIZE: usize = 128;
+// Length of the die's normal and tangent lines, in its own units
+const VECTOR_LENGTH: f32 = 0.3;
+
+/**
+ * What the cube's faces show
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CubeLook {
+    VertexColors,
+    // A die: one texture per face, bound before drawing that face
+    DiceTextures,
+    // A die: every face in one texture, told apart by UVs
+    DiceAtlas,
+}
+
+const LOOKS: [(CubeLook, &str); 3] = [
+    (CubeLook::VertexColors, "Vertex colors"),
+    (CubeLook::DiceTextures, "Die, six textures"),
+    (CubeLook::DiceAtlas, "Die, texture atlas"),
+];
+
+/**
+ * GPU side of the die looks
+ */
+struct Dice {
+    program: WebGlProgram,
+    mvp_loc: Option<WebGlUniformLocation>,
+    texture_loc: Option<WebGlUniformLocation>,
+    mesh: GpuTexturedMesh,
+    face_textures: Vec<Texture>,
+    // The same cube with each face's UVs squeezed into its atlas region
+    atlas_mesh: GpuTexturedMesh,
+    atlas: Texture,
+    // Each vertex's position, normal and tangent, drawn as lines on request
+    positions: Vec<f32>,
+    normals: Vec<Vec3>,
+    tangents: Vec<Vec3>,
+    vectors: DebugRenderer,
+}
+
+impl Dice {
+    fn new(gl: &WebGl2RenderingContext) -> Result<Self, String> {
+        let program =
+            gl_util::create_program(gl, shaders::DEMOS_DICE_VERT, shaders::DEMOS_DICE_FRAG)?;
+        let cube = TexturedMeshData::cube();
+        let size = FACE_SIZE as i32;
+        let face_textures = dice::FACE_PIPS
+            .iter()
+            .map(|&pips| Texture::from_rgba(gl, size, size, &dice::face_pixels(pips, FACE_SIZE)))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| "Unable to upload the die faces".to_string())?;
+
+        let (width, height, pixels, regions) = dice::atlas(FACE_SIZE);
+        let atlas = Texture::from_rgba(gl, width as i32, height as i32, &pixels)
+            .ok_or_else(|| "Unable to upload the die atlas".to_string())?;
+        let mut atlas_cube = cube.clone();
+        for (face, region) in regions.iter().enumerate() {
+            atlas_cube.map_uvs(face * 4..face * 4 + 4, region);
+        }
+
+        let normals = cube.normals();
+        let tangents = cube.tangents(&normals);
+
+        let upload = |data: &TexturedMeshData| {
+            GpuTexturedMesh::upload(gl, data).ok_or_else(|| "Unable to upload the die".to_string())
+        };
+        Ok(Self {
+            mvp_loc: gl.get_uniform_location(&program, "modelViewProjection"),
+            texture_loc: gl.get_uniform_location(&program, "faceTexture"),
+            program,
+            mesh: upload(&cube)?,
+            face_textures,
+            atlas_mesh: upload(&atlas_cube)?,
+            atlas,
+            positions: cube.positions,
+            normals,
+            tangents,
+            vectors: DebugRenderer::new(gl)?,
+        })
+    }
+
+    /**
+     * Draw the die turned by `angle`, and with `vectors` its vertex normals
+     * and tangents on top
+     */
+    fn draw(&self, gl: &WebGl2RenderingContext, angle: f32, atlas: bool, vectors: bool) {
+        let model = math::multiply(&rotation_matrix_y(angle), &math::scaling([0.6; 3]));
+        // Tipped towards the viewer so the top face shows too
+        let view = math::multiply(&math::rotation_matrix_x(-0.5), &model);
+        let projection = math::orthographic(-1.0, 1.0, -1.0, 1.0, -2.0, 2.0);
+        let mvp = math::multiply(&projection, &view);
+        gl.enable(WebGl2RenderingContext::DEPTH_TEST);
+        gl.enable(WebGl2RenderingContext::CULL_FACE);
+        gl.use_program(Some(&self.program));
+        gl.uniform_matrix4fv_with_f32_array(self.mvp_loc.as_ref(), false, &mvp);
+        gl.uniform1i(self.texture_loc.as_ref(), 0);
+        if atlas {
+            self.atlas.bind(gl, 0);
+            self.atlas_mesh.draw(gl);
+        } else {
+            for (face, texture) in self.face_textures.iter().enumerate() {
+                texture.bind(gl, 0);
+                self.mesh.draw_range(gl, face as i32 * 6, 6);
+            }
+        }
+        if vectors {
+            debug::vertex_vectors(
+                &view,
+                &self.positions,
+                &self.normals,
+                Some(&self.tangents),
+                VECTOR_LENGTH,
+            );
+            self.vectors.flush(gl, &projection);
+        }
+    }
+}
+
+#[component]
+pub fn CubeDemo() -> Element {
+    let mut canvas_mounted = use_signal(|| false);
+    let mut look = use_signal(|| CubeLook::VertexColors);
+    // Draw the die's vertex normals and tangents
+    let mut show_vectors = use_signal(|| false);
+    // Set when the die can't be set up; only vertex colors are offered then
+    let mut dice_error = use_signal(|| None::<String>);
+    let frame_loop = use_frame_loop();
+    let initialized = use_hook(|| Rc::new(Cell::new(false)));
+
+    use_effect(move || {
+        if !canvas_mounted() {
+            return;
+        }
+
+        let frame_loop = frame_loop.clone();
+        let initialized = initialized.clone();
+        spawn(async move {
+            gloo_timers::future::TimeoutFuture::new(50).await;
+
+            // Ensure it runs only once per mounted canvas
+            if initialized.replace(true) {
+                tracing::debug!(target: "renderer", "WebGL initialization already completed");
+                return;
+            }
+
+            let canvas = match gl_util::canvas_by_id("webgl-canvas") {
+                Ok(canvas) => canvas,
+                Err(err) => {
+                    gl_errors::report("cube: setup", err);
+                    return;
+                }
+            };
+
+            tracing::debug!(target: "renderer", "Initializing WebGL (single time)...");
+
+            let gl = match gl_util::webgl2_context(&canvas) {
+                Ok(gl) => gl,
+                Err(err) => {
+                    gl_errors::report("cube: setup", err);
+                    return;
+                }
+            };
+
+            // Initial WebGL setup
+            canvas.set_width(480);
+            canvas.set_height(480);
+            gl.viewport(0, 0, 480, 480);
+            // Keep depth test disabled (ensure the cube is always visible)
+            gl.disable(WebGl2RenderingContext::DEPTH_TEST);
+            gl.disable(WebGl2RenderingContext::CULL_FACE);
+
+            tracing::debug!(target: "renderer", "WebGL context configured");
+
+            // Create, compile and link shaders
+            let program = match gl_util::create_program(
+                &gl,
+                shaders::DEMOS_CUBE_VERT,
+                shaders::DEMOS_CUBE_FRAG,
+            ) {
+                Ok(program) => program,
+                Err(err) => {
+                    gl_errors::report("cube: program setup", err);
+                    return;
+                }
+            };
+
+            gl.use_program(Some(&program));
+
+            tracing::debug!(target: "renderer", "Shaders compiled and program linked");
+
+            // Cube vertex data (moderate size to ensure visibility)
+            let vertices: [f32; 24] = [
+                // Four front-face vertices (Z=0.2)
+                -0.4, -0.4, 0.2, 0.4, -0.4, 0.2, 0.4, 0.4, 0.2, -0.4, 0.4, 0.2,
+                // Four back-face vertices (Z=-0.2)
+                -0.4, -0.4, -0.2, 0.4, -0.4, -0.2, 0.4, 0.4, -0.2, -0.4, 0.4, -0.2,
+            ];
+
+            let colors: [f32; 24] = [
+                // Front face colors
+                1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 0.0,
+                // Back face colors
+                1.0, 0.0, 1.0, 0.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.5, 0.5, 0.5,
+            ];
+
+            let indices: [u16; 36] = [
+                // Front
+                0, 1, 2, 2, 3, 0, // Back (clockwise)
+                4, 6, 5, 6, 4, 7, // Left
+                4, 0, 3, 3, 7, 4, // Right
+                1, 5, 6, 6, 2, 1, // Top
+                3, 2, 6, 6, 7, 3, // Bottom
+                4, 5, 1, 1, 0, 4,
+            ];
+
+            // Vertex buffer
+            let Some(pos_buffer) = gl.create_buffer() else {
+                gl_errors::report("cube: setup", "Unable to create the vertex buffer");
+                return;
+            };
+            gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&pos_buffer));
+            unsafe {
+                let vert_array = js_sys::Float32Array::view(&vertices);
+                gl.buffer_data_with_array_buffer_view(
+                    WebGl2RenderingContext::ARRAY_BUFFER,
+                    &vert_array,
+                    WebGl2RenderingContext::STATIC_DRAW,
+                );
+            }
+
+            // Color buffer
+            let Some(color_buffer) = gl.create_buffer() else {
+                gl_errors::report("cube: setup", "Unable to create the color buffer");
+                return;
+            };
+            gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&color_buffer));
+            unsafe {
+                let color_array = js_sys::Float32Array::view(&colors);
+                gl.buffer_data_with_array_buffer_view(
+                    WebGl2RenderingContext::ARRAY_BUFFER,
+                    &color_array,
+                    WebGl2RenderingContext::STATIC_DRAW,
+                );
+            }
+
+            // Index buffer
+            let Some(index_buffer) = gl.create_buffer() else {
+                gl_errors::report("cube: setup", "Unable to create the index buffer");
+                return;
+            };
+            gl.bind_buffer(
+                WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER,
+                Some(&index_buffer),
+            );
+            unsafe {
+                let index_array = js_sys::Uint16Array::view(&indices);
+                gl.buffer_data_with_array_buffer_view(
+                    WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER,
+                    &index_array,
+                    WebGl2RenderingContext::STATIC_DRAW,
+                );
+            }
+
+            // Attribute setup
+            let pos_loc = gl.get_attrib_location(&program, "position");
+            let color_loc = gl.get_attrib_location(&program, "color");
+
+            tracing::debug!(target: "renderer", "Position attribute location: {}, Color attribute location: {}", pos_loc, color_loc);
+
+            if pos_loc < 0 || color_loc < 0 {
+                gl_errors::report("cube: program setup", "Failed to get attribute locations");
+                return;
+            }
+
+            let pos_loc = pos_loc as u32;
+            let color_loc = color_loc as u32;
+
+            gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&pos_buffer));
+            gl.enable_vertex_attrib_array(pos_loc);
+            gl.vertex_attrib_pointer_with_i32(
+                pos_loc,
+                3,
+                WebGl2RenderingContext::FLOAT,
+                false,
+                0,
+                0,
+            );
+
+            gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&color_buffer));
+            gl.enable_vertex_attrib_array(color_loc);
+            gl.vertex_attrib_pointer_with_i32(
+                color_loc,
+                3,
+                WebGl2RenderingContext::FLOAT,
+                false,
+                0,
+                0,
+            );
+
+            tracing::debug!(target: "renderer", "Buffers and attributes configured");
+
+            let dice = match Dice::new(&gl) {
+                Ok(dice) => Some(dice),
+                Err(err) => {
+                    gl_errors::report("cube: die setup", err.clone());
+                    dice_error.set(Some(err));
+                    look.set(CubeLook::VertexColors);
+                    None
+                }
+            };
+
+            // Animation loop
+            let angle = Rc::new(RefCell::new(0.0f32));
+
+            frame_loop.start({
+                let angle = angle.clone();
+                let frame_count = Rc::new(RefCell::new(0u32));
+                move |_time: f64, delta: f64| {
+                    let mut current_angle = *angle.borrow();
+                    let mut count = *frame_count.borrow();
+                    count += 1;
+                    *frame_count.borrow_mut() = count;
+
+                    if count.is_multiple_of(60) {
+                        tracing::debug!(target: "renderer", "Rendering frame {}, angle: {:.2}", count, current_angle);
+                    }
+
+                    gl.clear_color(0.1, 0.1, 0.1, 1.0);
+                    let die = match *look.peek() {
+                        CubeLook::VertexColors => None,
+                        look => dice.as_ref().map(|dice| (dice, look == CubeLook::DiceAtlas)),
+                    };
+                    if let Some((dice, atlas)) = die {
+                        gl.clear(
+                            WebGl2RenderingContext::COLOR_BUFFER_BIT
+                                | WebGl2RenderingContext::DEPTH_BUFFER_BIT,
+                        );
+                        dice.draw(&gl, current_angle, atlas, *show_vectors.peek());
+                    } else {
+                        // Clear background (do not use depth buffer)
+                        gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+                        gl.disable(WebGl2RenderingContext::DEPTH_TEST);
+                        gl.disable(WebGl2RenderingContext::CULL_FACE);
+                        gl.use_program(Some(&program));
+
+                        // Simple rotation matrix only (reliable setting)
+                        let model = rotation_matrix_y(current_angle);
+
+                        // Pass matrix to the uniform variable
+                        let loc = gl.get_uniform_location(&program, "modelViewMatrix");
+                        gl.uniform_matrix4fv_with_f32_array(loc.as_ref(), false, &model);
+
+                        // Draw
+                        gl.bind_buffer(
+                            WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER,
+                            Some(&index_buffer),
+                        );
+                        gl.draw_elements_with_i32(
+                            WebGl2RenderingContext::TRIANGLES,
+                            indices.len() as i32,
+                            WebGl2RenderingContext::UNSIGNED_SHORT,
+                            0,
+                        );
+                    }
+
+                    // Check WebGL errors
+                    gl_errors::check(&gl, "cube: draw");
+
+                    // Update angle, about 1.2 radians per second
+                    current_angle += 1.2 * delta as f32;
+                    *angle.borrow_mut() = current_angle;
+                }
+            });
+
+            tracing::info!(target: "renderer", "Animation started successfully!");
+        });
+    });
+
+    rsx! {
+        div {
+            style: "display: flex; flex-direction: column; justify-content: center; align-items: center; height: 100vh; background: #f0f0f0;",
+            canvas {
+                id: "webgl-canvas",
+                width: "480",
+                height: "480",
+                style: "border: 2px solid #333; background: #222;",
+                onmounted: move |_| {
+                    canvas_mounted.set(true);
+                }
+            }
+            div {
+                style: "display: flex; gap: 16px; align-items: center; margin-top: 10px; color: #333; font-family: monospace;",
+                if let Some(err) = dice_error() {
+                    span { "Vertex colors only ({err})" }
+                } else {
+                    label {
+                        "Faces "
+                        select {
+                            onchange: move |evt| {
+                                if let Some(&(kind, _)) = LOOKS.iter().find(|(_, name)| *name == evt.value()) {
+                                    look.set(kind);
+                                }
+                            },
+                            for (kind, name) in LOOKS {
+                                option { value: name, selected: look() == kind, "{name}" }
+                            }
+                        }
+                    }
+                    label {
+                        title: "Draw each vertex's normal (blue) and tangent (red) on the die",
+                        input {
+                            r#type: "checkbox",
+                            checked: show_vectors(),
+                            disabled: look() == CubeLook::VertexColors,
+                            onchange: move |evt: FormEvent| show_vectors.set(evt.checked()),
+                        }
+                        " Normals"
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use dioxus::prelude::*;
+
+use crate::components::{GlCapabilities, GoldenTests, LogSettings, WebGlCanvas, WgslTranslations};
+
+/**
+ * What this browser's WebGL2 implementation supports, for attaching to bug
+ * reports, rendering tests against reference frames, which shaders translate
+ * to WGSL, and the log levels to reproduce a problem with. The small canvas
+ * is only there to create a context to ask
+ */
+#[component]
+pub fn DiagnosticsDemo() -> Element {
+    rsx! {
+        div {
+            style: "display: flex; flex-direction: column; align-items: center; padding-top: 60px; min-height: 100vh; background: #f0f0f0;",
+            WebGlCanvas {
+                width: 160,
+                height: 90,
+                style: "border: 2px solid #333; background: #222;",
+                oncontext: move |_| {},
+                GlCapabilities {}
+                GoldenTests {}
+            }
+            WgslTranslations {}
+            LogSettings {}
+        }
+    }
+}
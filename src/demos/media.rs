@@ -0,0 +1,539 @@
+use dioxus::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+use web_sys::{
+    CanvasRenderingContext2d, MediaStream, WebGl2RenderingContext, WebGlVertexArrayObject,
+};
+
+use crate::camera::OrbitCamera;
+use crate::canvas_texture::CanvasTexture;
+use crate::gl_errors;
+use crate::gl_util;
+use crate::hooks::use_frame_loop;
+use crate::math;
+use crate::shaders;
+use crate::texture::Texture;
+use crate::unpack_buffer::PixelUnpackBuffer;
+use crate::video_texture::{self, VideoTexture};
+
+const CANVAS_SIZE: u32 = 480;
+// Resolution of the offscreen 2D canvas mapped onto the cube
+const CHART_SIZE: u32 = 256;
+// How often the chart is redrawn (and so re-uploaded), in seconds
+const CHART_INTERVAL: f64 = 0.1;
+const CHART_SAMPLES: usize = 48;
+// Resolution of the pattern generated on the CPU and re-uploaded every frame
+const STREAM_SIZE: i32 = 256;
+// CORS-enabled sample clip; any other CORS-enabled URL or a local file works too
+const DEFAULT_VIDEO_URL: &str =
+    "https://interactive-examples.mdn.mozilla.net/media/cc0-videos/flower.webm";
+
+/**
+ * What the cube's faces show
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MediaSource {
+    Canvas,
+    Streamed,
+    Video,
+    Webcam,
+}
+
+/**
+ * Progress of the camera permission request
+ */
+#[derive(Clone, Debug, PartialEq)]
+enum WebcamState {
+    Off,
+    Requesting,
+    Live(MediaStream),
+    Failed(String),
+}
+
+/**
+ * What the offscreen video element is currently playing
+ */
+#[derive(Clone, Debug, PartialEq)]
+enum Playback {
+    Url(String),
+    Stream(MediaStream),
+}
+
+/**
+ * The offscreen video element and what it plays, owned by the render loop.
+ * The element isn't in the page, so it is stopped explicitly when the loop ends
+ */
+struct Player {
+    video: VideoTexture,
+    playing: Option<Playback>,
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        self.video.stop();
+        if let Some(Playback::Stream(stream)) = self.playing.take() {
+            video_texture::stop_tracks(&stream);
+        }
+    }
+}
+
+/**
+ * Unit cube with four vertices per face so each face maps the whole texture;
+ * interleaved position.xyz + uv, with the image's top edge at each face's top
+ */
+fn textured_cube() -> (Vec<f32>, Vec<u16>) {
+    // (normal, u axis, v axis) with u x v = normal, so faces wind CCW from outside
+    let faces: [([f32; 3], [f32; 3], [f32; 3]); 6] = [
+        ([1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+        ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+        ([0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+        ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+        ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+        ([0.0, 0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ];
+    let mut vertices = Vec::with_capacity(6 * 4 * 5);
+    let mut indices = Vec::with_capacity(6 * 6);
+    for (face, (normal, u, v)) in faces.iter().enumerate() {
+        for (su, sv) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+            for axis in 0..3 {
+                vertices.push((normal[axis] + su * u[axis] + sv * v[axis]) * 0.5);
+            }
+            vertices.extend([(su + 1.0) * 0.5, (1.0 - sv) * 0.5]);
+        }
+        let base = face as u16 * 4;
+        indices.extend([base, base + 1, base + 2, base + 2, base + 3, base]);
+    }
+    (vertices, indices)
+}
+
+fn upload_cube(gl: &WebGl2RenderingContext) -> Option<(WebGlVertexArrayObject, i32)> {
+    let (vertices, indices) = textured_cube();
+    let vao = gl.create_vertex_array()?;
+    gl.bind_vertex_array(Some(&vao));
+
+    let vertex_buffer = gl.create_buffer()?;
+    gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&vertex_buffer));
+    unsafe {
+        let array = js_sys::Float32Array::view(&vertices);
+        gl.buffer_data_with_array_buffer_view(
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            &array,
+            WebGl2RenderingContext::STATIC_DRAW,
+        );
+    }
+    gl.enable_vertex_attrib_array(0);
+    gl.vertex_attrib_pointer_with_i32(0, 3, WebGl2RenderingContext::FLOAT, false, 20, 0);
+    gl.enable_vertex_attrib_array(1);
+    gl.vertex_attrib_pointer_with_i32(1, 2, WebGl2RenderingContext::FLOAT, false, 20, 12);
+
+    let index_buffer = gl.create_buffer()?;
+    gl.bind_buffer(
+        WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER,
+        Some(&index_buffer),
+    );
+    unsafe {
+        let array = js_sys::Uint16Array::view(&indices);
+        gl.buffer_data_with_array_buffer_view(
+            WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER,
+            &array,
+            WebGl2RenderingContext::STATIC_DRAW,
+        );
+    }
+
+    gl.bind_vertex_array(None);
+    Some((vao, indices.len() as i32))
+}
+
+/**
+ * A live line chart with a caption, redrawn on the 2D canvas
+ */
+fn draw_chart(ctx: &CanvasRenderingContext2d, size: f64, samples: &[f64], time: f64) {
+    ctx.set_fill_style_str("#10151f");
+    ctx.fill_rect(0.0, 0.0, size, size);
+
+    // Grid
+    ctx.set_stroke_style_str("#2a3550");
+    ctx.set_line_width(1.0);
+    for i in 1..4 {
+        let y = size * i as f64 / 4.0;
+        ctx.begin_path();
+        ctx.move_to(0.0, y);
+        ctx.line_to(size, y);
+        ctx.stroke();
+    }
+
+    // Signal
+    ctx.set_stroke_style_str("#4fd1a5");
+    ctx.set_line_width(3.0);
+    ctx.begin_path();
+    for (i, sample) in samples.iter().enumerate() {
+        let x = size * i as f64 / (CHART_SAMPLES - 1) as f64;
+        let y = size * (0.55 - sample * 0.3);
+        if i == 0 {
+            ctx.move_to(x, y);
+        } else {
+            ctx.line_to(x, y);
+        }
+    }
+    ctx.stroke();
+
+    ctx.set_fill_style_str("#ffffff");
+    ctx.set_font("bold 22px sans-serif");
+    ctx.set_text_align("left");
+    ctx.set_text_baseline("top");
+    let _ = ctx.fill_text("Canvas 2D", 12.0, 12.0);
+    ctx.set_font("16px monospace");
+    let _ = ctx.fill_text(&format!("t = {:.1}s", time), 12.0, size - 30.0);
+}
+
+/**
+ * An animated plasma written straight into RGBA8 `pixels`, `size` x `size`,
+ * standing in for data that arrives as raw bytes every frame
+ */
+fn draw_plasma(pixels: &mut [u8], size: usize, time: f64) {
+    let t = time as f32;
+    for (i, pixel) in pixels.chunks_exact_mut(4).enumerate() {
+        let x = (i % size) as f32 / size as f32 * 8.0;
+        let y = (i / size) as f32 / size as f32 * 8.0;
+        let value = (x + t).sin()
+            + (y * 0.7 - t * 1.3).sin()
+            + ((x + y) * 0.5 + t * 0.7).sin()
+            + ((x * x + y * y).sqrt() * 0.8 - t * 2.0).sin();
+        let phase = value * std::f32::consts::PI * 0.25;
+        let channel = |offset: f32| ((phase + offset).sin() * 0.5 + 0.5) * 255.0;
+        pixel.copy_from_slice(&[
+            channel(0.0) as u8,
+            channel(2.1) as u8,
+            channel(4.2) as u8,
+            255,
+        ]);
+    }
+}
+
+#[component]
+pub fn MediaDemo() -> Element {
+    let mut canvas_mounted = use_signal(|| false);
+    let mut source = use_signal(|| MediaSource::Canvas);
+    let mut video_url = use_signal(|| DEFAULT_VIDEO_URL.to_string());
+    let mut webcam = use_signal(|| WebcamState::Off);
+    let camera = use_hook(|| {
+        let mut camera = OrbitCamera::default();
+        camera.distance = 2.6;
+        Rc::new(RefCell::new(camera))
+    });
+    let frame_loop = use_frame_loop();
+
+    use_effect({
+        let camera = camera.clone();
+        move || {
+            if !canvas_mounted() {
+                return;
+            }
+
+            let camera = camera.clone();
+            let frame_loop = frame_loop.clone();
+            spawn(async move {
+                gloo_timers::future::TimeoutFuture::new(50).await;
+
+                let canvas = match gl_util::canvas_by_id("media-canvas") {
+                    Ok(canvas) => canvas,
+                    Err(err) => {
+                        gl_errors::report("media: setup", err);
+                        return;
+                    }
+                };
+
+                let gl = match gl_util::webgl2_context(&canvas) {
+                    Ok(gl) => gl,
+                    Err(err) => {
+                        gl_errors::report("media: setup", err);
+                        return;
+                    }
+                };
+
+                canvas.set_width(CANVAS_SIZE);
+                canvas.set_height(CANVAS_SIZE);
+
+                let program = match gl_util::create_program(
+                    &gl,
+                    shaders::DEMOS_MEDIA_VERT,
+                    shaders::DEMOS_MEDIA_FRAG,
+                ) {
+                    Ok(program) => program,
+                    Err(err) => {
+                        gl_errors::report("media: program setup", err);
+                        return;
+                    }
+                };
+                let mvp_loc = gl.get_uniform_location(&program, "modelViewProjection");
+                let image_loc = gl.get_uniform_location(&program, "image");
+                let Some((vao, index_count)) = upload_cube(&gl) else {
+                    gl_errors::report("media: setup", "Unable to upload the cube");
+                    return;
+                };
+
+                let mut chart = match CanvasTexture::new(&gl, CHART_SIZE, CHART_SIZE) {
+                    Ok(chart) => chart,
+                    Err(err) => {
+                        gl_errors::report("media: chart texture setup", err);
+                        return;
+                    }
+                };
+                let mut player = match VideoTexture::new(&gl) {
+                    Ok(video) => Player {
+                        video,
+                        playing: None,
+                    },
+                    Err(err) => {
+                        gl_errors::report("media: video texture setup", err);
+                        return;
+                    }
+                };
+                let Some(streamed) = Texture::with_size(&gl, STREAM_SIZE, STREAM_SIZE) else {
+                    gl_errors::report("media: streamed texture setup", "Unable to create texture");
+                    return;
+                };
+                let mut unpack = match PixelUnpackBuffer::new(&gl) {
+                    Ok(unpack) => unpack,
+                    Err(err) => {
+                        gl_errors::report("media: unpack buffer setup", err);
+                        return;
+                    }
+                };
+                let mut pixels = vec![0u8; (STREAM_SIZE * STREAM_SIZE * 4) as usize];
+                let mut samples = vec![0.0; CHART_SAMPLES];
+                let mut last_chart_update = f64::NEG_INFINITY;
+
+                tracing::info!(target: "renderer", "Media demo ready");
+
+                frame_loop.start(move |time: f64, _| {
+                    // Redrawing marks the canvas dirty; frames in between reuse the upload
+                    if time - last_chart_update >= CHART_INTERVAL {
+                        last_chart_update = time;
+                        samples.remove(0);
+                        samples.push((time * 2.0).sin() * 0.7 + (time * 5.3).sin() * 0.3);
+                        let size = chart.width().min(chart.height());
+                        chart.draw(|ctx| draw_chart(ctx, size, &samples, time));
+                    }
+
+                    gl.viewport(0, 0, CANVAS_SIZE as i32, CANVAS_SIZE as i32);
+                    gl.enable(WebGl2RenderingContext::DEPTH_TEST);
+                    gl.enable(WebGl2RenderingContext::CULL_FACE);
+                    gl.clear_color(0.1, 0.1, 0.1, 1.0);
+                    gl.clear(
+                        WebGl2RenderingContext::COLOR_BUFFER_BIT
+                            | WebGl2RenderingContext::DEPTH_BUFFER_BIT,
+                    );
+
+                    let model = math::multiply(
+                        &math::rotation_matrix_y(time as f32 * 0.5),
+                        &math::rotation_matrix_x(time as f32 * 0.3),
+                    );
+                    let mvp = math::multiply(&camera.borrow().view_projection(1.0), &model);
+
+                    gl.use_program(Some(&program));
+                    gl.uniform_matrix4fv_with_f32_array(mvp_loc.as_ref(), false, &mvp);
+                    gl.uniform1i(image_loc.as_ref(), 0);
+                    let streaming = *source.peek() == MediaSource::Streamed;
+                    if streaming {
+                        draw_plasma(&mut pixels, STREAM_SIZE as usize, time);
+                        let size = (STREAM_SIZE, STREAM_SIZE);
+                        if let Err(err) = unpack.upload(&gl, &streamed, (0, 0), size, &pixels) {
+                            gl_errors::report("media: streamed upload", err);
+                        }
+                    }
+                    let wanted = match *source.peek() {
+                        MediaSource::Canvas | MediaSource::Streamed => None,
+                        MediaSource::Video => Some(Playback::Url(video_url.peek().clone())),
+                        MediaSource::Webcam => match &*webcam.peek() {
+                            WebcamState::Live(stream) => Some(Playback::Stream(stream.clone())),
+                            _ => None,
+                        },
+                    };
+                    if wanted != player.playing {
+                        match &wanted {
+                            Some(Playback::Url(url)) => player.video.play_url(url),
+                            Some(Playback::Stream(stream)) => player.video.play_stream(stream),
+                            None => player.video.stop(),
+                        }
+                        player.playing = wanted;
+                    }
+                    // The chart stands in until the first video frame arrives
+                    let frame = match player.playing {
+                        Some(_) => player.video.texture(&gl),
+                        None => None,
+                    };
+                    match frame {
+                        Some(texture) => texture.bind(&gl, 0),
+                        None if streaming => streamed.bind(&gl, 0),
+                        None => chart.texture(&gl).bind(&gl, 0),
+                    }
+                    gl.bind_vertex_array(Some(&vao));
+                    gl.draw_elements_with_i32(
+                        WebGl2RenderingContext::TRIANGLES,
+                        index_count,
+                        WebGl2RenderingContext::UNSIGNED_SHORT,
+                        0,
+                    );
+                    gl.bind_vertex_array(None);
+                });
+            });
+        }
+    });
+
+    let on_mouse_down = {
+        let camera = camera.clone();
+        move |evt: MouseEvent| {
+            let point = evt.client_coordinates();
+            camera.borrow_mut().begin_drag(point.x, point.y);
+        }
+    };
+    let on_mouse_move = {
+        let camera = camera.clone();
+        move |evt: MouseEvent| {
+            let point = evt.client_coordinates();
+            camera.borrow_mut().drag_to(point.x, point.y);
+        }
+    };
+    let on_mouse_up = {
+        let camera = camera.clone();
+        move |_| camera.borrow_mut().end_drag()
+    };
+    let on_mouse_leave = {
+        let camera = camera.clone();
+        move |_| camera.borrow_mut().end_drag()
+    };
+    let on_wheel = {
+        let camera = camera.clone();
+        move |evt: WheelEvent| {
+            evt.prevent_default();
+            camera.borrow_mut().zoom(evt.delta().strip_units().y);
+        }
+    };
+
+    let on_video_file = move |evt: FormEvent| async move {
+        let Some(files) = evt.files() else {
+            return;
+        };
+        let Some(name) = files.files().into_iter().next() else {
+            return;
+        };
+        let Some(bytes) = files.read_file(&name).await else {
+            return;
+        };
+        // Local files play from a blob URL, which needs no CORS
+        let array = js_sys::Uint8Array::from(bytes.as_slice());
+        let Ok(blob) = web_sys::Blob::new_with_u8_array_sequence(&js_sys::Array::of1(&array))
+        else {
+            return;
+        };
+        if let Ok(url) = web_sys::Url::create_object_url_with_blob(&blob) {
+            let previous = video_url.replace(url);
+            if previous.starts_with("blob:") {
+                let _ = web_sys::Url::revoke_object_url(&previous);
+            }
+            source.set(MediaSource::Video);
+        }
+    };
+
+    let mut request_webcam = move || {
+        webcam.set(WebcamState::Requesting);
+        spawn(async move {
+            let state = match video_texture::open_webcam().await {
+                // The user may have picked another source while the prompt was up
+                Ok(stream) if *source.peek() != MediaSource::Webcam => {
+                    video_texture::stop_tracks(&stream);
+                    WebcamState::Off
+                }
+                Ok(stream) => WebcamState::Live(stream),
+                Err(err) => WebcamState::Failed(err),
+            };
+            webcam.set(state);
+        });
+    };
+    let on_source_change = move |evt: FormEvent| {
+        let next = match evt.value().as_str() {
+            "streamed" => MediaSource::Streamed,
+            "video" => MediaSource::Video,
+            "webcam" => MediaSource::Webcam,
+            _ => MediaSource::Canvas,
+        };
+        source.set(next);
+        if next == MediaSource::Webcam {
+            request_webcam();
+        } else if let WebcamState::Live(stream) = webcam.replace(WebcamState::Off) {
+            video_texture::stop_tracks(&stream);
+        }
+    };
+    let webcam_state = webcam();
+    let webcam_message = match &webcam_state {
+        WebcamState::Requesting => Some("Waiting for camera permission...".to_string()),
+        WebcamState::Failed(err) => Some(format!("{}; showing the chart instead", err)),
+        WebcamState::Off | WebcamState::Live(_) => None,
+    };
+    let webcam_failed = matches!(webcam_state, WebcamState::Failed(_));
+
+    rsx! {
+        div {
+            style: "display: flex; flex-direction: column; justify-content: center; align-items: center; height: 100vh; background: #f0f0f0;",
+            canvas {
+                id: "media-canvas",
+                width: "{CANVAS_SIZE}",
+                height: "{CANVAS_SIZE}",
+                style: "border: 2px solid #333; background: #222; cursor: grab;",
+                onmounted: move |_| {
+                    canvas_mounted.set(true);
+                },
+                onmousedown: on_mouse_down,
+                onmousemove: on_mouse_move,
+                onmouseup: on_mouse_up,
+                onmouseleave: on_mouse_leave,
+                onwheel: on_wheel,
+            }
+            div {
+                style: "display: flex; gap: 8px; align-items: center; margin-top: 10px; color: #333; font-family: monospace;",
+                label {
+                    "Source "
+                    select {
+                        onchange: on_source_change,
+                        option { value: "canvas", selected: source() == MediaSource::Canvas, "Canvas 2D chart" }
+                        option { value: "streamed", selected: source() == MediaSource::Streamed, "Streamed pixels" }
+                        option { value: "video", selected: source() == MediaSource::Video, "Video" }
+                        option { value: "webcam", selected: source() == MediaSource::Webcam, "Webcam" }
+                    }
+                }
+            }
+            if source() == MediaSource::Video {
+                div {
+                    style: "display: flex; gap: 8px; align-items: center; margin-top: 6px; color: #333; font-family: monospace;",
+                    input {
+                        r#type: "url",
+                        style: "width: 320px;",
+                        value: "{video_url}",
+                        onchange: move |evt| video_url.set(evt.value()),
+                    }
+                    input {
+                        r#type: "file",
+                        accept: "video/*",
+                        onchange: on_video_file,
+                    }
+                }
+                p {
+                    style: "color: #666; font-family: monospace; font-size: small;",
+                    "Remote videos must be served with CORS headers; local files always work"
+                }
+            }
+            if source() == MediaSource::Webcam {
+                if let Some(message) = webcam_message {
+                    div {
+                        style: "display: flex; gap: 8px; align-items: center; margin-top: 6px; color: #333; font-family: monospace;",
+                        span { "{message}" }
+                        if webcam_failed {
+                            button { onclick: move |_| request_webcam(), "Try again" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,35 @@
+mod backend;
+mod chart;
+mod compose;
+mod cube;
+mod diagnostics;
+mod media;
+mod objects;
+mod particles;
+mod post;
+mod raymarch;
+mod scene;
+mod server_mesh;
+mod surface;
+mod textures;
+mod transparency;
+mod volume;
+mod webgpu_cube;
+
+pub use backend::BackendDemo;
+pub use chart::ChartDemo;
+pub use compose::ComposeDemo;
+pub use cube::CubeDemo;
+pub use diagnostics::DiagnosticsDemo;
+pub use media::MediaDemo;
+pub use objects::ObjectsDemo;
+pub use particles::ParticlesDemo;
+pub use post::PostDemo;
+pub use raymarch::RaymarchDemo;
+pub use scene::SceneDemo;
+pub use server_mesh::ServerMeshDemo;
+pub use surface::SurfaceDemo;
+pub use textures::TexturesDemo;
+pub use transparency::TransparencyDemo;
+pub use volume::VolumeDemo;
+pub use webgpu_cube::WebGpuCubeDemo;
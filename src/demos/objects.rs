@@ -0,0 +1,1024 @@
+use dioxus::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+use web_sys::WebGl2RenderingContext;
+
+use super::scene::parse_hex_color;
+use crate::camera::OrbitCamera;
+use crate::components::Tooltip;
+use crate::debug::{self, DebugRenderer};
+use crate::frustum::Frustum;
+use crate::gl_errors;
+use crate::gl_util::{self, GlResource};
+use crate::hooks::{use_frame_loop, use_hover_dwell};
+use crate::labels::{Label, LabelRenderer};
+use crate::math::{self, Vec3};
+use crate::msdf::{self, FontAtlas, MsdfFont, TextMesh};
+use crate::object_list::{self, MeshCounts, ObjectDesc, ObjectListRenderer, Shape};
+use crate::raycast::Ray;
+use crate::scene::Transform;
+use crate::selection::{ScreenRect, Selection};
+use crate::text_texture::TextStyle;
+use crate::transform_gizmo::{self, Gizmo, GizmoDrag, GizmoMode, Handle, Snap};
+
+const CANVAS_SIZE: u32 = 480;
+// Spawned objects land within this distance of the origin along X and Z
+const SPAWN_RANGE: f32 = 2.5;
+// A press that moves further than this many pixels orbits, or drags out a
+// selection box with Shift held, instead of clicking
+const CLICK_SLOP: f64 = 4.0;
+// Color of a frozen camera's frustum and eye
+const FRUSTUM_COLOR: [f32; 3] = [0.95, 0.6, 0.95];
+// Bounds kept by culling and culled, boxes then spheres
+const KEPT_COLORS: [[f32; 3]; 2] = [[0.3, 0.9, 0.4], [0.2, 0.6, 0.3]];
+const CULLED_COLORS: [[f32; 3]; 2] = [[0.95, 0.3, 0.25], [0.65, 0.2, 0.15]];
+// Length of the vertex normal lines, in world units
+const NORMAL_LENGTH: f32 = 0.12;
+// Range of the size slider
+const MIN_SIZE: f32 = 0.2;
+const MAX_SIZE: f32 = 1.2;
+// Most objects the generator makes at once
+const MAX_GENERATED: usize = 200;
+// How long the cursor rests on an object before its tooltip shows
+const TOOLTIP_DELAY_MS: u32 = 500;
+// Height of the name labels, in world units
+const LABEL_HEIGHT: f32 = 0.3;
+// The atlas generated at startup: printable ASCII from a system font
+const FONT_FAMILY: &str = "sans-serif";
+const FONT_SIZE: f64 = 48.0;
+const FONT_RANGE: f64 = 6.0;
+// Size of an em of the title above the scene, in world units, and of the
+// status line in the corner, in pixels
+const TITLE_EM: f32 = 0.6;
+const STATUS_EM: f32 = 14.0;
+
+// An atlas and its RGBA pixels, as `msdf::generate` and `msdf::load` return them
+type FontImage = (FontAtlas, u32, u32, Vec<u8>);
+const TINTS: [[f32; 4]; 4] = [
+    [1.0, 0.45, 0.35, 1.0],
+    [0.4, 0.8, 0.45, 1.0],
+    [0.35, 0.55, 1.0, 1.0],
+    [1.0, 0.85, 0.3, 1.0],
+];
+
+/**
+ * An object of `shape` at `translation`, its tint cycling with `id`
+ */
+fn object(id: u64, shape: Shape, translation: Vec3) -> ObjectDesc {
+    ObjectDesc {
+        id,
+        name: format!("{} {}", shape.name(), id + 1),
+        shape,
+        transform: Transform {
+            translation,
+            scale: [0.6; 3],
+            ..Transform::default()
+        },
+        tint: TINTS[id as usize % TINTS.len()],
+        visible: true,
+        spin: [0.0; 3],
+    }
+}
+
+/**
+ * Rebuild `cached` with `font` unless it already shows `text`
+ */
+fn update_text(
+    gl: &WebGl2RenderingContext,
+    font: &MsdfFont,
+    cached: &mut Option<(String, TextMesh)>,
+    text: &str,
+) -> Result<(), String> {
+    if cached.as_ref().is_some_and(|(shown, _)| shown == text) {
+        return Ok(());
+    }
+    if let Some((_, mesh)) = cached.take() {
+        mesh.delete(gl);
+    }
+    *cached = Some((text.to_string(), font.build(gl, text)?));
+    Ok(())
+}
+
+/**
+ * Somewhere in the area objects are spawned in
+ */
+fn random_position() -> Vec3 {
+    let spread = || (js_sys::Math::random() as f32 * 2.0 - 1.0) * SPAWN_RANGE;
+    [spread(), js_sys::Math::random() as f32, spread()]
+}
+
+#[component]
+pub fn ObjectsDemo() -> Element {
+    let mut canvas_mounted = use_signal(|| false);
+    // The scene: the renderer diffs this against what it last drew every frame
+    let mut objects = use_signal(|| {
+        vec![
+            object(0, Shape::Cube, [-1.2, 0.0, 0.0]),
+            object(1, Shape::Sphere, [0.0, 0.0, 0.0]),
+            object(2, Shape::Cube, [1.2, 0.0, 0.0]),
+        ]
+    });
+    let mut next_id = use_signal(|| 3u64);
+    // What the sidebar shows and edits; Shift adds to it
+    let mut selected = use_signal(Selection::default);
+    // The box a Shift-drag is selecting with, in canvas pixels
+    let mut rubber_band = use_signal(|| None::<ScreenRect>);
+    // The manipulator on the last selected object: what it edits, the handle
+    // under the cursor, and the handle being dragged with the object it moves
+    // and the time the drag started
+    let mut gizmo_mode = use_signal(GizmoMode::default);
+    let mut gizmo_hover = use_signal(|| None::<Handle>);
+    let gizmo_drag = use_hook(|| Rc::new(RefCell::new(None::<(u64, GizmoDrag, f32)>)));
+    // Whether gizmo drags and size edits snap, and to what. Holding Ctrl
+    // while dragging does the opposite
+    let mut snapping = use_signal(|| false);
+    let mut snap = use_signal(Snap::default);
+    // Generator input: the same seed and count give the same scene
+    let mut seed = use_signal(|| 1u64);
+    let mut generate_count = use_signal(|| 40usize);
+    let mut show_labels = use_signal(|| true);
+    // Leave a copy of the camera where it is, showing what it sees, while the
+    // view orbits on
+    let mut freeze_camera = use_signal(|| false);
+    // Draw every object's world bounds, colored by whether culling keeps them
+    let mut show_bounds = use_signal(|| false);
+    // Draw each vertex's normal, to check the lighting has the right ones
+    let mut show_normals = use_signal(|| false);
+    // Base URL of a pre-generated atlas (`.json` and `.png`), and where the
+    // current one came from
+    let mut font_url = use_signal(String::new);
+    let mut font_source = use_signal(|| format!("generated from {}", FONT_FAMILY));
+    // A loaded atlas waiting for the frame loop to upload it
+    let pending_font = use_hook(|| Rc::new(RefCell::new(None::<FontImage>)));
+    // Seconds the loop has run, so clicks pick spinning objects where they are drawn
+    let clock = use_hook(|| Rc::new(Cell::new(0.0f32)));
+    // Where the current press started and whether Shift was held, to tell a
+    // click from an orbit or a box selection
+    let press = use_hook(|| Rc::new(Cell::new(None::<(f64, f64, bool)>)));
+    let mut counts = use_signal(MeshCounts::default);
+    let camera = use_hook(|| {
+        let mut camera = OrbitCamera::default();
+        camera.distance = 6.0;
+        Rc::new(RefCell::new(camera))
+    });
+    let frame_loop = use_frame_loop();
+    // Object under the resting cursor and where the cursor is, for the tooltip
+    let dwell = use_hover_dwell(TOOLTIP_DELAY_MS);
+    let mut tooltip = use_signal(|| None::<(u64, f64, f64)>);
+
+    use_effect({
+        let camera = camera.clone();
+        let clock = clock.clone();
+        let pending_font = pending_font.clone();
+        let gizmo_drag = gizmo_drag.clone();
+        move || {
+            if !canvas_mounted() {
+                return;
+            }
+
+            let camera = camera.clone();
+            let clock = clock.clone();
+            let pending_font = pending_font.clone();
+            let gizmo_drag = gizmo_drag.clone();
+            let frame_loop = frame_loop.clone();
+            spawn(async move {
+                gloo_timers::future::TimeoutFuture::new(50).await;
+
+                let canvas = match gl_util::canvas_by_id("objects-canvas") {
+                    Ok(canvas) => canvas,
+                    Err(err) => {
+                        gl_errors::report("objects: setup", err);
+                        return;
+                    }
+                };
+
+                let gl = match gl_util::webgl2_context(&canvas) {
+                    Ok(gl) => gl,
+                    Err(err) => {
+                        gl_errors::report("objects: setup", err);
+                        return;
+                    }
+                };
+
+                canvas.set_width(CANVAS_SIZE);
+                canvas.set_height(CANVAS_SIZE);
+
+                let mut renderer = match ObjectListRenderer::new(&gl) {
+                    Ok(renderer) => renderer,
+                    Err(err) => {
+                        gl_errors::report("objects: program setup", err);
+                        return;
+                    }
+                };
+
+                let mut labels = match LabelRenderer::new(
+                    &gl,
+                    TextStyle {
+                        bold: true,
+                        background: Some("rgba(0, 0, 0, 0.6)".to_string()),
+                        padding: 6.0,
+                        ..TextStyle::default()
+                    },
+                ) {
+                    Ok(labels) => labels,
+                    Err(err) => {
+                        gl_errors::report("objects: label setup", err);
+                        return;
+                    }
+                };
+
+                let characters: String = (' '..='~').collect();
+                let generated = msdf::generate(FONT_FAMILY, FONT_SIZE, FONT_RANGE, &characters);
+                let mut font = match generated.and_then(|(atlas, width, height, pixels)| {
+                    MsdfFont::new(&gl, atlas, width, height, &pixels)
+                }) {
+                    Ok(font) => font,
+                    Err(err) => {
+                        gl_errors::report("objects: font atlas", err);
+                        return;
+                    }
+                };
+                let debug_lines = match DebugRenderer::new(&gl) {
+                    Ok(lines) => lines,
+                    Err(err) => {
+                        gl_errors::report("objects: debug draw setup", err);
+                        return;
+                    }
+                };
+                let mut title: Option<(String, TextMesh)> = None;
+                let mut frozen: Option<OrbitCamera> = None;
+                // Positions and smooth normals of each shape, for the normals view
+                let shape_normals: HashMap<Shape, (Vec<f32>, Vec<Vec3>)> =
+                    [Shape::Cube, Shape::Sphere]
+                        .into_iter()
+                        .map(|shape| {
+                            let mesh = shape.mesh();
+                            let normals = mesh.normals();
+                            (shape, (mesh.positions, normals))
+                        })
+                        .collect();
+                let mut status: Option<(String, TextMesh)> = None;
+
+                tracing::info!(target: "renderer", "Objects ready");
+
+                frame_loop.start(move |time: f64, _| {
+                    let time = time as f32;
+                    clock.set(time);
+                    match renderer.sync(&gl, &objects.peek()) {
+                        Ok(changes) if changes.is_empty() => {}
+                        Ok(changes) => {
+                            tracing::debug!(target: "renderer", "Object list changes: {:?}", changes);
+                        }
+                        Err(err) => gl_errors::report("objects: sync", err),
+                    }
+                    if *counts.peek() != renderer.counts() {
+                        counts.set(renderer.counts());
+                    }
+
+                    let size = CANVAS_SIZE as i32;
+                    gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+                    gl.viewport(0, 0, size, size);
+                    gl.clear_color(0.1, 0.1, 0.1, 1.0);
+                    gl.clear(
+                        WebGl2RenderingContext::COLOR_BUFFER_BIT
+                            | WebGl2RenderingContext::DEPTH_BUFFER_BIT,
+                    );
+                    let camera = camera.borrow();
+                    let view_proj = camera.view_projection(1.0);
+                    if !*freeze_camera.peek() {
+                        frozen = None;
+                    } else if frozen.is_none() {
+                        frozen = Some(camera.clone());
+                    }
+                    // While a camera is frozen culling uses its frustum, so orbiting away
+                    // shows what it leaves out
+                    let culling = frozen.as_ref().unwrap_or(&camera).view_projection(1.0);
+                    let frustum = Frustum::from_view_proj(&culling);
+                    let drawn = renderer.draw(&gl, &view_proj, &frustum, time, &selected.peek());
+
+                    let objects = objects.peek();
+                    let shown: Vec<Label> = if *show_labels.peek() {
+                        objects
+                            .iter()
+                            .filter(|object| object.visible)
+                            .map(|object| {
+                                let [x, y, z] = object.transform.translation;
+                                // Clear of the shape however it has turned
+                                let lift = object.transform.scale[1] * 0.75;
+                                Label {
+                                    id: object.id,
+                                    text: &object.name,
+                                    anchor: [x, y + lift, z],
+                                }
+                            })
+                            .collect()
+                    } else {
+                        Vec::new()
+                    };
+                    if let Err(err) =
+                        labels.draw(&gl, &camera.view_matrix(), &view_proj, &shown, LABEL_HEIGHT)
+                    {
+                        gl_errors::report("objects: labels", err);
+                    }
+
+                    let loaded = pending_font.borrow_mut().take();
+                    if let Some((atlas, width, height, pixels)) = loaded {
+                        match MsdfFont::new(&gl, atlas, width, height, &pixels) {
+                            Ok(loaded) => {
+                                // Text meshes hold UVs into the old atlas
+                                for (_, mesh) in title.take().into_iter().chain(status.take()) {
+                                    mesh.delete(&gl);
+                                }
+                                font.delete(&gl);
+                                font = loaded;
+                            }
+                            Err(err) => gl_errors::report("objects: font atlas", err),
+                        }
+                    }
+                    let status_text = format!(
+                        "{} objects, {} drawn, seed {}",
+                        objects.len(),
+                        drawn,
+                        seed.peek()
+                    );
+                    if let Err(err) = update_text(&gl, &font, &mut title, "Object list")
+                        .and_then(|_| update_text(&gl, &font, &mut status, &status_text))
+                    {
+                        gl_errors::report("objects: text", err);
+                    }
+                    if let Some((_, title)) = &title {
+                        // Centered above the scene, facing +Z
+                        let (width, _) = title.size();
+                        let model = math::multiply(
+                            &math::translation([-width * TITLE_EM * 0.5, 2.4, -1.5]),
+                            &math::scaling([TITLE_EM; 3]),
+                        );
+                        font.draw(
+                            &gl,
+                            title,
+                            &math::multiply(&view_proj, &model),
+                            [0.95, 0.95, 0.95, 1.0],
+                        );
+                    }
+                    if let Some((_, status)) = &status {
+                        // Canvas pixels, y down, so the em square is flipped
+                        let pixels =
+                            math::orthographic(0.0, size as f32, size as f32, 0.0, -1.0, 1.0);
+                        let top = size as f32 - 10.0 - STATUS_EM * status.size().1;
+                        let model = math::multiply(
+                            &math::translation([10.0, top, 0.0]),
+                            &math::scaling([STATUS_EM, -STATUS_EM, 1.0]),
+                        );
+                        gl.disable(WebGl2RenderingContext::DEPTH_TEST);
+                        font.draw(
+                            &gl,
+                            status,
+                            &math::multiply(&pixels, &model),
+                            [0.8, 0.8, 0.8, 1.0],
+                        );
+                    }
+
+                    let primary = selected.peek().primary();
+                    let target = objects
+                        .iter()
+                        .find(|object| Some(object.id) == primary && object.visible);
+                    if let Some(object) = target {
+                        let gizmo = Gizmo::new(
+                            *gizmo_mode.peek(),
+                            &object.transform_at(time),
+                            camera.eye(),
+                        );
+                        let drag = gizmo_drag.borrow();
+                        let dragged = drag.as_ref().map(|(_, drag, _)| drag.handle());
+                        let highlighted = dragged.or(*gizmo_hover.peek());
+                        for (a, b, color) in gizmo.lines(highlighted) {
+                            debug::line(a, b, color);
+                        }
+                    }
+                    if let Some(frozen) = &frozen {
+                        debug::frustum(&frozen.view_projection(1.0), FRUSTUM_COLOR);
+                        debug::point(frozen.eye(), FRUSTUM_COLOR);
+                    }
+                    if *show_bounds.peek() {
+                        for object in objects.iter().filter(|object| object.visible) {
+                            let aabb = object.world_bounds(time);
+                            let sphere = aabb.bounding_sphere();
+                            let [box_color, _] = if frustum.intersects_aabb(&aabb) {
+                                KEPT_COLORS
+                            } else {
+                                CULLED_COLORS
+                            };
+                            let [_, sphere_color] = if frustum.intersects_sphere(&sphere) {
+                                KEPT_COLORS
+                            } else {
+                                CULLED_COLORS
+                            };
+                            debug::aabb(&aabb, box_color);
+                            debug::sphere(sphere.center, sphere.radius, sphere_color);
+                        }
+                    }
+                    if *show_normals.peek() {
+                        let kept = objects.iter().filter(|object| {
+                            object.visible && frustum.intersects_aabb(&object.world_bounds(time))
+                        });
+                        for object in kept {
+                            let (positions, normals) = &shape_normals[&object.shape];
+                            // Only textured meshes have tangents
+                            debug::vertex_vectors(
+                                &object.matrix(time),
+                                positions,
+                                normals,
+                                None,
+                                NORMAL_LENGTH,
+                            );
+                        }
+                    }
+                    debug_lines.flush(&gl, &view_proj);
+                });
+            });
+        }
+    });
+
+    // World-space ray through canvas pixel (`x`, `y`)
+    let ray_at = {
+        let camera = camera.clone();
+        move |x: f64, y: f64| {
+            let size = CANVAS_SIZE as f64;
+            let inverse = math::invert(&camera.borrow().view_projection(1.0))?;
+            let (ndc_x, ndc_y) = math::pixel_to_ndc(x, y, size, size);
+            Some(Ray::from_ndc(&inverse, ndc_x, ndc_y))
+        }
+    };
+    // Id of the visible object under canvas pixel (`x`, `y`), as drawn now
+    let pick_at = {
+        let clock = clock.clone();
+        let ray_at = ray_at.clone();
+        move |x: f64, y: f64| object_list::pick(&objects.peek(), &ray_at(x, y)?, clock.get())
+    };
+    // The gizmo on the last selected object, if it is shown, with the
+    // object's id and transform as drawn now and the ray through (`x`, `y`)
+    let gizmo_at = {
+        let camera = camera.clone();
+        let clock = clock.clone();
+        let ray_at = ray_at.clone();
+        move |x: f64, y: f64| {
+            let id = selected.peek().primary()?;
+            let objects = objects.peek();
+            let object = objects
+                .iter()
+                .find(|object| object.id == id && object.visible)?;
+            let transform = object.transform_at(clock.get());
+            let gizmo = Gizmo::new(*gizmo_mode.peek(), &transform, camera.borrow().eye());
+            Some((id, gizmo, transform, ray_at(x, y)?))
+        }
+    };
+
+    // Ids of the visible objects drawn at least partly inside `rect`
+    let in_box = {
+        let camera = camera.clone();
+        let clock = clock.clone();
+        move |rect: ScreenRect| {
+            let size = CANVAS_SIZE as f32;
+            let view_proj = camera.borrow().view_projection(1.0);
+            object_list::in_rect(&objects.peek(), &view_proj, &rect, size, size, clock.get())
+        }
+    };
+
+    // Once the cursor has rested over an object, show its tooltip there
+    use_effect({
+        let dwell = dwell.clone();
+        let pick_at = pick_at.clone();
+        move || {
+            let Some((x, y)) = dwell.rested() else {
+                return;
+            };
+            if let Some(id) = pick_at(x, y) {
+                tooltip.set(Some((id, x, y)));
+            }
+        }
+    });
+
+    let on_mouse_down = {
+        let camera = camera.clone();
+        let clock = clock.clone();
+        let press = press.clone();
+        let dwell = dwell.clone();
+        let gizmo_drag = gizmo_drag.clone();
+        let gizmo_at = gizmo_at.clone();
+        move |evt: MouseEvent| {
+            dwell.left();
+            tooltip.set(None);
+            let point = evt.element_coordinates();
+            // Grabbing a gizmo handle edits the object instead of orbiting
+            let grabbed = gizmo_at(point.x, point.y).and_then(|(id, gizmo, transform, ray)| {
+                let drag = gizmo.begin_drag(gizmo.pick(&ray)?, &transform, &ray)?;
+                Some((id, drag, clock.get()))
+            });
+            if grabbed.is_some() {
+                *gizmo_drag.borrow_mut() = grabbed;
+                return;
+            }
+            let shift = evt.modifiers().shift();
+            // Shift-drags select rather than orbit
+            if !shift {
+                let point = evt.client_coordinates();
+                camera.borrow_mut().begin_drag(point.x, point.y);
+            }
+            press.set(Some((point.x, point.y, shift)));
+        }
+    };
+    let on_mouse_move = {
+        let camera = camera.clone();
+        let press = press.clone();
+        let dwell = dwell.clone();
+        let pick_at = pick_at.clone();
+        let gizmo_drag = gizmo_drag.clone();
+        let ray_at = ray_at.clone();
+        move |evt: MouseEvent| {
+            if let Some((id, drag, started)) = &*gizmo_drag.borrow() {
+                let point = evt.element_coordinates();
+                let ray = ray_at(point.x, point.y);
+                let snapped = *snapping.peek() != evt.modifiers().ctrl();
+                let steps = snapped.then(|| *snap.peek());
+                let Some(dragged) = ray.and_then(|ray| drag.update(&ray, steps.as_ref())) else {
+                    return;
+                };
+                if let Some(object) = objects.write().iter_mut().find(|object| object.id == *id) {
+                    // The gizmo worked on the spun rotation; store it unspun
+                    let spun = math::scale(object.spin, *started);
+                    object.transform = Transform {
+                        rotation: math::sub(dragged.rotation, spun),
+                        ..dragged
+                    };
+                }
+                return;
+            }
+            if let Some((x, y, true)) = press.get() {
+                let point = evt.element_coordinates();
+                if (point.x - x).hypot(point.y - y) > CLICK_SLOP {
+                    rubber_band.set(Some(ScreenRect::from_corners(
+                        [x as f32, y as f32],
+                        [point.x as f32, point.y as f32],
+                    )));
+                }
+                return;
+            }
+            let point = evt.client_coordinates();
+            camera.borrow_mut().drag_to(point.x, point.y);
+            if camera.borrow().is_dragging() {
+                return;
+            }
+            let point = evt.element_coordinates();
+            let handle = gizmo_at(point.x, point.y).and_then(|(_, gizmo, _, ray)| gizmo.pick(&ray));
+            if *gizmo_hover.peek() != handle {
+                gizmo_hover.set(handle);
+            }
+            dwell.moved(point.x, point.y);
+            // The tooltip stays up while the cursor stays on its object
+            let shown = (*tooltip.peek()).map(|(id, _, _)| id);
+            if shown.is_some() && pick_at(point.x, point.y) != shown {
+                tooltip.set(None);
+            }
+        }
+    };
+    let on_mouse_up = {
+        let camera = camera.clone();
+        let press = press.clone();
+        let gizmo_drag = gizmo_drag.clone();
+        move |evt: MouseEvent| {
+            if gizmo_drag.borrow_mut().take().is_some() {
+                return;
+            }
+            camera.borrow_mut().end_drag();
+            let point = evt.element_coordinates();
+            let Some((x, y, shift)) = press.take() else {
+                return;
+            };
+            if let Some(rect) = rubber_band.take() {
+                selected.write().select(in_box(rect), true);
+                return;
+            }
+            if (point.x - x).hypot(point.y - y) > CLICK_SLOP {
+                return;
+            }
+            selected.write().click(pick_at(point.x, point.y), shift);
+        }
+    };
+    let on_mouse_leave = {
+        let camera = camera.clone();
+        let press = press.clone();
+        let gizmo_drag = gizmo_drag.clone();
+        let dwell = dwell.clone();
+        move |_| {
+            camera.borrow_mut().end_drag();
+            gizmo_drag.borrow_mut().take();
+            gizmo_hover.set(None);
+            press.set(None);
+            rubber_band.set(None);
+            dwell.left();
+            tooltip.set(None);
+        }
+    };
+    let on_wheel = {
+        let camera = camera.clone();
+        move |evt: WheelEvent| {
+            evt.prevent_default();
+            camera.borrow_mut().zoom(evt.delta().strip_units().y);
+        }
+    };
+
+    let rows: Vec<(usize, ObjectDesc, String)> = objects
+        .read()
+        .iter()
+        .enumerate()
+        .map(|(index, object)| {
+            let [r, g, b, _] = object
+                .tint
+                .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+            (
+                index,
+                object.clone(),
+                format!("{:02x}{:02x}{:02x}", r, g, b),
+            )
+        })
+        .collect();
+    let MeshCounts {
+        live,
+        created,
+        deleted,
+    } = counts();
+    let object_count = objects.read().len();
+    let mut generate = move |scene_seed: u64| {
+        let count = generate_count();
+        let first_id = next_id();
+        seed.set(scene_seed);
+        objects.set(object_list::generate(scene_seed, count, first_id));
+        next_id.set(first_id + count as u64);
+        selected.write().clear();
+    };
+    let mut add_object = move |shape: Shape| {
+        let id = next_id();
+        next_id.set(id + 1);
+        objects.write().push(object(id, shape, random_position()));
+        selected.write().click(Some(id), false);
+    };
+
+    // The tooltip's object may have been deleted since it was shown
+    let (tooltip_position, tooltip_name, tooltip_details) = tooltip()
+        .and_then(|(id, x, y)| {
+            let objects = objects.read();
+            let object = objects.iter().find(|object| object.id == id)?;
+            let [px, py, pz] = object.transform.translation;
+            let mut details = vec![
+                format!(
+                    "{}, size {:.2}",
+                    object.shape.name(),
+                    object.transform.scale[0]
+                ),
+                format!("at ({:.1}, {:.1}, {:.1})", px, py, pz),
+            ];
+            if object.spin != [0.0; 3] {
+                details.push(format!("spins {:.1} rad/s", math::length(object.spin)));
+            }
+            Some((Some((x, y)), object.name.clone(), details))
+        })
+        .unwrap_or_default();
+    let selection = selected();
+    let selected_name = selection.primary().and_then(|id| {
+        let objects = objects.read();
+        let object = objects.iter().find(|object| object.id == id)?;
+        Some(object.name.clone())
+    });
+    let band_style = rubber_band().map(|rect| {
+        let [width, height] = rect.size();
+        format!(
+            "left: {}px; top: {}px; width: {}px; height: {}px;",
+            rect.min[0], rect.min[1], width, height
+        )
+    });
+
+    rsx! {
+        div {
+            style: "display: flex; gap: 24px; justify-content: center; align-items: center; height: 100vh; background: #f0f0f0;",
+            div {
+                style: "position: relative;",
+                canvas {
+                    id: "objects-canvas",
+                    width: "{CANVAS_SIZE}",
+                    height: "{CANVAS_SIZE}",
+                    style: if gizmo_hover().is_some() { "border: 2px solid #333; background: #222; cursor: pointer;" } else { "border: 2px solid #333; background: #222; cursor: grab;" },
+                    onmounted: move |_| {
+                        canvas_mounted.set(true);
+                    },
+                    onmousedown: on_mouse_down,
+                    onmousemove: on_mouse_move,
+                    onmouseup: on_mouse_up,
+                    onmouseleave: on_mouse_leave,
+                    onwheel: on_wheel,
+                }
+                div {
+                    class: "canvas-overlay",
+                    if let Some(style) = band_style {
+                        div { class: "rubber-band", style: "{style}" }
+                    }
+                    Tooltip {
+                        position: tooltip_position,
+                        area: (CANVAS_SIZE as f64, CANVAS_SIZE as f64),
+                        strong { "{tooltip_name}" }
+                        for line in tooltip_details {
+                            div { "{line}" }
+                        }
+                    }
+                }
+            }
+            div {
+                style: "display: flex; flex-direction: column; gap: 6px; width: 320px; color: #333; font-family: monospace;",
+                div {
+                    style: "display: flex; gap: 8px;",
+                    button { onclick: move |_| add_object(Shape::Cube), "Add cube" }
+                    button { onclick: move |_| add_object(Shape::Sphere), "Add sphere" }
+                    button {
+                        disabled: selection.is_empty(),
+                        onclick: move |_| {
+                            let doomed = selected.peek().ids().to_vec();
+                            objects.write().retain(|object| !doomed.contains(&object.id));
+                            selected.write().clear();
+                        },
+                        "Delete selected"
+                    }
+                }
+                div {
+                    style: "display: flex; gap: 8px; align-items: center;",
+                    span {
+                        title: "Click or Shift-click objects or rows, or Shift-drag a box on the canvas",
+                        match (selection.len(), &selected_name) {
+                            (0, _) => "Nothing selected".to_string(),
+                            (1, Some(name)) => format!("Selected: {}", name),
+                            (count, Some(name)) => format!("{} selected, last {}", count, name),
+                            (count, None) => format!("{} selected", count),
+                        }
+                    }
+                    button {
+                        disabled: selection.is_empty(),
+                        onclick: move |_| selected.write().clear(),
+                        "Clear"
+                    }
+                    label {
+                        title: "What dragging the handles on the last selected object changes",
+                        "Gizmo "
+                        select {
+                            onchange: move |evt| {
+                                if let Some(&mode) = GizmoMode::ALL.iter().find(|mode| mode.name() == evt.value()) {
+                                    gizmo_mode.set(mode);
+                                }
+                            },
+                            for mode in GizmoMode::ALL {
+                                option { value: mode.name(), selected: gizmo_mode() == mode, "{mode.name()}" }
+                            }
+                        }
+                    }
+                }
+                div {
+                    style: "display: flex; flex-wrap: wrap; gap: 8px; align-items: center;",
+                    label {
+                        title: "Snap gizmo drags and sizes; hold Ctrl while dragging to do the opposite",
+                        input {
+                            r#type: "checkbox",
+                            checked: snapping(),
+                            onchange: move |evt: FormEvent| snapping.set(evt.checked()),
+                        }
+                        " Snap"
+                    }
+                    label {
+                        "grid "
+                        input {
+                            r#type: "number",
+                            min: "0",
+                            step: "0.05",
+                            value: "{snap().grid}",
+                            style: "width: 50px;",
+                            onchange: move |evt: FormEvent| {
+                                if let Ok(grid) = evt.value().parse::<f32>() {
+                                    snap.write().grid = grid.max(0.0);
+                                }
+                            },
+                        }
+                    }
+                    label {
+                        "angle "
+                        input {
+                            r#type: "number",
+                            min: "0",
+                            max: "180",
+                            value: "{snap().angle.to_degrees().round()}",
+                            style: "width: 45px;",
+                            onchange: move |evt: FormEvent| {
+                                if let Ok(degrees) = evt.value().parse::<f32>() {
+                                    snap.write().angle = degrees.clamp(0.0, 180.0).to_radians();
+                                }
+                            },
+                        }
+                        "°"
+                    }
+                    label {
+                        "scale "
+                        input {
+                            r#type: "number",
+                            min: "0",
+                            step: "0.05",
+                            value: "{snap().scale}",
+                            style: "width: 50px;",
+                            onchange: move |evt: FormEvent| {
+                                if let Ok(step) = evt.value().parse::<f32>() {
+                                    snap.write().scale = step.max(0.0);
+                                }
+                            },
+                        }
+                    }
+                }
+                div {
+                    style: "display: flex; gap: 8px; align-items: center;",
+                    label {
+                        "Seed "
+                        input {
+                            r#type: "number",
+                            min: "0",
+                            value: "{seed}",
+                            style: "width: 80px;",
+                            onchange: move |evt: FormEvent| {
+                                if let Ok(value) = evt.value().parse() {
+                                    seed.set(value);
+                                }
+                            },
+                        }
+                    }
+                    label {
+                        "Count "
+                        input {
+                            r#type: "number",
+                            min: "1",
+                            max: "{MAX_GENERATED}",
+                            value: "{generate_count}",
+                            style: "width: 50px;",
+                            onchange: move |evt: FormEvent| {
+                                if let Ok(value) = evt.value().parse::<usize>() {
+                                    generate_count.set(value.clamp(1, MAX_GENERATED));
+                                }
+                            },
+                        }
+                    }
+                }
+                div {
+                    style: "display: flex; flex-wrap: wrap; gap: 8px;",
+                    button { onclick: move |_| generate(seed()), "Generate scene" }
+                    button {
+                        title: "Generate from a new random seed",
+                        onclick: move |_| generate((js_sys::Math::random() * 1e9) as u64),
+                        "Random seed"
+                    }
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            checked: show_labels(),
+                            onchange: move |evt: FormEvent| show_labels.set(evt.checked()),
+                        }
+                        " Labels"
+                    }
+                    label {
+                        title: "Leave a copy of the camera where it is and draw its frustum while you orbit away",
+                        input {
+                            r#type: "checkbox",
+                            checked: freeze_camera(),
+                            onchange: move |evt: FormEvent| freeze_camera.set(evt.checked()),
+                        }
+                        " Freeze camera"
+                    }
+                    label {
+                        title: "Draw each object's world bounding box and sphere: green if culling keeps it, red if it is culled",
+                        input {
+                            r#type: "checkbox",
+                            checked: show_bounds(),
+                            onchange: move |evt: FormEvent| show_bounds.set(evt.checked()),
+                        }
+                        " Bounds"
+                    }
+                    label {
+                        title: "Draw a short line along each vertex normal",
+                        input {
+                            r#type: "checkbox",
+                            checked: show_normals(),
+                            onchange: move |evt: FormEvent| show_normals.set(evt.checked()),
+                        }
+                        " Normals"
+                    }
+                }
+                for (index, object, hex) in rows {
+                    div {
+                        key: "{object.id}",
+                        style: if selection.contains(object.id) { "display: flex; gap: 8px; align-items: center; background: #dde6f5;" } else { "display: flex; gap: 8px; align-items: center;" },
+                        onclick: move |evt: MouseEvent| {
+                            selected.write().click(Some(object.id), evt.modifiers().shift());
+                        },
+                        input {
+                            r#type: "checkbox",
+                            checked: object.visible,
+                            title: "Visible",
+                            onchange: move |evt: FormEvent| {
+                                if let Some(object) = objects.write().get_mut(index) {
+                                    object.visible = evt.checked();
+                                }
+                            },
+                        }
+                        input {
+                            r#type: "color",
+                            value: "#{hex}",
+                            title: "Tint",
+                            oninput: move |evt: FormEvent| {
+                                let Some(rgb) = parse_hex_color(&evt.value()) else {
+                                    return;
+                                };
+                                if let Some(object) = objects.write().get_mut(index) {
+                                    let [r, g, b] = rgb.map(|c| c as f32 / 255.0);
+                                    object.tint = [r, g, b, object.tint[3]];
+                                }
+                            },
+                        }
+                        input {
+                            r#type: "text",
+                            value: "{object.name}",
+                            style: "width: 90px;",
+                            oninput: move |evt: FormEvent| {
+                                if let Some(object) = objects.write().get_mut(index) {
+                                    object.name = evt.value();
+                                }
+                            },
+                        }
+                        input {
+                            r#type: "range",
+                            min: "{MIN_SIZE}",
+                            max: "{MAX_SIZE}",
+                            step: "0.05",
+                            value: "{object.transform.scale[0]}",
+                            title: "Size",
+                            oninput: move |evt: FormEvent| {
+                                let Ok(size) = evt.value().parse::<f32>() else {
+                                    return;
+                                };
+                                let size = if snapping() {
+                                    transform_gizmo::round_to(size, snap().scale).clamp(MIN_SIZE, MAX_SIZE)
+                                } else {
+                                    size
+                                };
+                                if let Some(object) = objects.write().get_mut(index) {
+                                    object.transform.scale = [size; 3];
+                                }
+                            },
+                        }
+                    }
+                }
+                div {
+                    style: "display: flex; gap: 8px; align-items: center;",
+                    input {
+                        r#type: "text",
+                        value: "{font_url}",
+                        placeholder: "Atlas URL, without .json/.png",
+                        style: "flex: 1;",
+                        oninput: move |evt: FormEvent| font_url.set(evt.value()),
+                    }
+                    button {
+                        disabled: font_url().is_empty(),
+                        title: "Load an msdf-atlas-gen atlas: its JSON metrics and PNG image",
+                        onclick: {
+                            let pending_font = pending_font.clone();
+                            move |_| {
+                                let base = font_url();
+                                let pending_font = pending_font.clone();
+                                spawn(async move {
+                                    let json = format!("{}.json", base);
+                                    let image = format!("{}.png", base);
+                                    match msdf::load(&json, &image).await {
+                                        Ok(font) => {
+                                            *pending_font.borrow_mut() = Some(font);
+                                            font_source.set(format!("loaded from {}", base));
+                                        }
+                                        Err(err) => gl_errors::report("objects: font atlas", err),
+                                    }
+                                });
+                            }
+                        },
+                        "Load font"
+                    }
+                }
+                span {
+                    style: "color: #666; font-size: small;",
+                    "MSDF font atlas {font_source}"
+                }
+                span {
+                    style: "color: #666; font-size: small;",
+                    "{object_count} objects · GPU meshes: {live} live, {created} created, {deleted} deleted"
+                }
+            }
+        }
+    }
+}
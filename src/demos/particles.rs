@@ -0,0 +1,309 @@
+use dioxus::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use web_sys::{WebGl2RenderingContext, WebGlBuffer, WebGlVertexArrayObject};
+
+use crate::assets::Assets;
+use crate::camera::OrbitCamera;
+use crate::gl_errors;
+use crate::gl_util;
+use crate::hooks::use_frame_loop;
+use crate::shaders;
+use crate::sprite_sheet::SpriteSheet;
+
+const PARTICLE_COUNT: i32 = 100_000;
+// position.xyz + age, velocity.xyz + lifespan
+const FLOATS_PER_PARTICLE: usize = 8;
+const STRIDE: i32 = (FLOATS_PER_PARTICLE * 4) as i32;
+
+const PUFF_TEXTURE: Asset = asset!("/assets/textures/puff.png");
+// Plays once over the first second of each particle's life, then holds the last frame
+const PUFF_SHEET: SpriteSheet = SpriteSheet {
+    columns: 4,
+    rows: 2,
+    frame_count: 8,
+    frames_per_second: 8.0,
+};
+
+/**
+ * Initial particle state with staggered (negative) ages so the fountain fills up gradually
+ */
+fn initial_particles() -> Vec<f32> {
+    let mut data = Vec::with_capacity(PARTICLE_COUNT as usize * FLOATS_PER_PARTICLE);
+    let mut seed = 0x2545_f491u32;
+    for i in 0..PARTICLE_COUNT {
+        // xorshift keeps lifespans varied without pulling in a RNG crate
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        let lifespan = 2.0 + (seed % 1000) as f32 / 1000.0 * 2.0;
+        let age = -lifespan * i as f32 / PARTICLE_COUNT as f32;
+        data.extend_from_slice(&[0.0, -0.5, 0.0, age, 0.0, 0.0, 0.0, lifespan]);
+    }
+    data
+}
+
+/**
+ * VAO reading the interleaved particle layout from `buffer`
+ */
+fn particle_vao(
+    gl: &WebGl2RenderingContext,
+    buffer: &WebGlBuffer,
+) -> Option<WebGlVertexArrayObject> {
+    let vao = gl.create_vertex_array()?;
+    gl.bind_vertex_array(Some(&vao));
+    gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(buffer));
+    gl.enable_vertex_attrib_array(0);
+    gl.vertex_attrib_pointer_with_i32(0, 4, WebGl2RenderingContext::FLOAT, false, STRIDE, 0);
+    gl.enable_vertex_attrib_array(1);
+    gl.vertex_attrib_pointer_with_i32(1, 4, WebGl2RenderingContext::FLOAT, false, STRIDE, 16);
+    gl.bind_vertex_array(None);
+    Some(vao)
+}
+
+#[component]
+pub fn ParticlesDemo() -> Element {
+    let mut canvas_mounted = use_signal(|| false);
+    let mut use_flipbook = use_signal(|| false);
+    let assets = use_context::<Assets>();
+    let puff = use_hook({
+        let assets = assets.clone();
+        move || assets.load_texture(&PUFF_TEXTURE.to_string())
+    });
+    let camera = use_hook(|| {
+        let mut camera = OrbitCamera::default();
+        camera.distance = 5.0;
+        camera.pitch = 0.2;
+        Rc::new(RefCell::new(camera))
+    });
+    let frame_loop = use_frame_loop();
+
+    use_effect({
+        let camera = camera.clone();
+        move || {
+            if !canvas_mounted() {
+                return;
+            }
+
+            let camera = camera.clone();
+            let assets = assets.clone();
+            let frame_loop = frame_loop.clone();
+            spawn(async move {
+                gloo_timers::future::TimeoutFuture::new(50).await;
+
+                let canvas = match gl_util::canvas_by_id("particles-canvas") {
+                    Ok(canvas) => canvas,
+                    Err(err) => {
+                        gl_errors::report("particles: setup", err);
+                        return;
+                    }
+                };
+
+                let gl = match gl_util::webgl2_context(&canvas) {
+                    Ok(gl) => gl,
+                    Err(err) => {
+                        gl_errors::report("particles: setup", err);
+                        return;
+                    }
+                };
+
+                canvas.set_width(480);
+                canvas.set_height(480);
+                gl.viewport(0, 0, 480, 480);
+                gl.disable(WebGl2RenderingContext::DEPTH_TEST);
+                // Additive blending so dense regions glow
+                gl.enable(WebGl2RenderingContext::BLEND);
+                gl.blend_func(WebGl2RenderingContext::ONE, WebGl2RenderingContext::ONE);
+
+                let update_program = match gl_util::create_transform_feedback_program(
+                    &gl,
+                    shaders::DEMOS_PARTICLES_UPDATE_VERT,
+                    shaders::DEMOS_PARTICLES_UPDATE_FRAG,
+                    &["outPosition", "outVelocity"],
+                ) {
+                    Ok(program) => program,
+                    Err(err) => {
+                        gl_errors::report("particles: update program setup", err);
+                        return;
+                    }
+                };
+                let render_program = match gl_util::create_program(
+                    &gl,
+                    shaders::DEMOS_PARTICLES_RENDER_VERT,
+                    shaders::DEMOS_PARTICLES_RENDER_FRAG,
+                ) {
+                    Ok(program) => program,
+                    Err(err) => {
+                        gl_errors::report("particles: render program setup", err);
+                        return;
+                    }
+                };
+
+                // Two buffer sets: each frame reads one and writes the other
+                let initial = initial_particles();
+                let (Some(front), Some(back)) = (gl.create_buffer(), gl.create_buffer()) else {
+                    gl_errors::report("particles: setup", "Unable to create the particle buffers");
+                    return;
+                };
+                let buffers = [front, back];
+                for buffer in &buffers {
+                    gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(buffer));
+                    unsafe {
+                        let array = js_sys::Float32Array::view(&initial);
+                        gl.buffer_data_with_array_buffer_view(
+                            WebGl2RenderingContext::ARRAY_BUFFER,
+                            &array,
+                            WebGl2RenderingContext::STREAM_COPY,
+                        );
+                    }
+                }
+                gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, None);
+
+                let (Some(front_vao), Some(back_vao)) = (
+                    particle_vao(&gl, &buffers[0]),
+                    particle_vao(&gl, &buffers[1]),
+                ) else {
+                    gl_errors::report(
+                        "particles: setup",
+                        "Unable to create the particle vertex arrays",
+                    );
+                    return;
+                };
+                let vaos = [front_vao, back_vao];
+                let Some(feedback) = gl.create_transform_feedback() else {
+                    gl_errors::report(
+                        "particles: setup",
+                        "Unable to create the transform feedback object",
+                    );
+                    return;
+                };
+
+                let delta_time_loc = gl.get_uniform_location(&update_program, "deltaTime");
+                let time_loc = gl.get_uniform_location(&update_program, "time");
+                let view_proj_loc = gl.get_uniform_location(&render_program, "viewProjection");
+                let flipbook_loc = gl.get_uniform_location(&render_program, "flipbook");
+                let use_flipbook_loc = gl.get_uniform_location(&render_program, "useFlipbook");
+                let sheet_loc = gl.get_uniform_location(&render_program, "sheet");
+
+                tracing::info!(target: "renderer", "Particle buffers ready ({} particles)", PARTICLE_COUNT);
+
+                let source = Cell::new(0usize);
+
+                frame_loop.start(move |time: f64, delta: f64| {
+                    let delta = delta.min(0.05);
+                    let src = source.get();
+                    let dst = 1 - src;
+
+                    // Simulate: read from `src`, capture into `dst`
+                    gl.use_program(Some(&update_program));
+                    gl.uniform1f(delta_time_loc.as_ref(), delta as f32);
+                    gl.uniform1f(time_loc.as_ref(), time as f32);
+                    gl.bind_vertex_array(Some(&vaos[src]));
+                    gl.bind_transform_feedback(
+                        WebGl2RenderingContext::TRANSFORM_FEEDBACK,
+                        Some(&feedback),
+                    );
+                    gl.bind_buffer_base(
+                        WebGl2RenderingContext::TRANSFORM_FEEDBACK_BUFFER,
+                        0,
+                        Some(&buffers[dst]),
+                    );
+                    gl.enable(WebGl2RenderingContext::RASTERIZER_DISCARD);
+                    gl.begin_transform_feedback(WebGl2RenderingContext::POINTS);
+                    gl.draw_arrays(WebGl2RenderingContext::POINTS, 0, PARTICLE_COUNT);
+                    gl.end_transform_feedback();
+                    gl.disable(WebGl2RenderingContext::RASTERIZER_DISCARD);
+                    gl.bind_buffer_base(WebGl2RenderingContext::TRANSFORM_FEEDBACK_BUFFER, 0, None);
+                    gl.bind_transform_feedback(WebGl2RenderingContext::TRANSFORM_FEEDBACK, None);
+
+                    // Render the freshly written state
+                    gl.clear_color(0.02, 0.02, 0.05, 1.0);
+                    gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+                    gl.use_program(Some(&render_program));
+                    let view_proj = camera.borrow().view_projection(1.0);
+                    gl.uniform_matrix4fv_with_f32_array(view_proj_loc.as_ref(), false, &view_proj);
+                    // Falls back to plain round points until the sheet has loaded
+                    let sheet = use_flipbook
+                        .peek()
+                        .then(|| assets.texture(&gl, puff))
+                        .flatten();
+                    gl.uniform1i(use_flipbook_loc.as_ref(), sheet.is_some() as i32);
+                    if let Some(sheet) = &sheet {
+                        sheet.bind(&gl, 0);
+                        gl.uniform1i(sheet_loc.as_ref(), 0);
+                        gl.uniform4fv_with_f32_array(
+                            flipbook_loc.as_ref(),
+                            &PUFF_SHEET.shader_params(),
+                        );
+                    }
+                    gl.bind_vertex_array(Some(&vaos[dst]));
+                    gl.draw_arrays(WebGl2RenderingContext::POINTS, 0, PARTICLE_COUNT);
+                    gl.bind_vertex_array(None);
+
+                    source.set(dst);
+
+                    gl_errors::check(&gl, "particles: update and draw");
+                });
+            });
+        }
+    });
+
+    let on_mouse_down = {
+        let camera = camera.clone();
+        move |evt: MouseEvent| {
+            let point = evt.client_coordinates();
+            camera.borrow_mut().begin_drag(point.x, point.y);
+        }
+    };
+    let on_mouse_move = {
+        let camera = camera.clone();
+        move |evt: MouseEvent| {
+            let point = evt.client_coordinates();
+            camera.borrow_mut().drag_to(point.x, point.y);
+        }
+    };
+    let on_mouse_up = {
+        let camera = camera.clone();
+        move |_| camera.borrow_mut().end_drag()
+    };
+    let on_mouse_leave = {
+        let camera = camera.clone();
+        move |_| camera.borrow_mut().end_drag()
+    };
+    let on_wheel = {
+        let camera = camera.clone();
+        move |evt: WheelEvent| {
+            evt.prevent_default();
+            camera.borrow_mut().zoom(evt.delta().strip_units().y);
+        }
+    };
+
+    rsx! {
+        div {
+            style: "display: flex; flex-direction: column; justify-content: center; align-items: center; height: 100vh; background: #f0f0f0;",
+            canvas {
+                id: "particles-canvas",
+                width: "480",
+                height: "480",
+                style: "border: 2px solid #333; background: #222; cursor: grab;",
+                onmounted: move |_| {
+                    canvas_mounted.set(true);
+                },
+                onmousedown: on_mouse_down,
+                onmousemove: on_mouse_move,
+                onmouseup: on_mouse_up,
+                onmouseleave: on_mouse_leave,
+                onwheel: on_wheel,
+            }
+            label {
+                style: "color: #333; font-family: monospace;",
+                input {
+                    r#type: "checkbox",
+                    checked: use_flipbook(),
+                    onchange: move |evt| use_flipbook.set(evt.checked()),
+                }
+                " Flipbook sprites"
+            }
+        }
+    }
+}
@@ -0,0 +1,631 @@
+use dioxus::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlCanvasElement, WebGl2RenderingContext};
+
+use crate::assets;
+use crate::camera::OrbitCamera;
+use crate::components::TexturePicker;
+use crate::gl_errors;
+use crate::gl_util;
+use crate::hooks::use_frame_loop;
+use crate::math::{self, Vec3};
+use crate::mesh::{GpuMesh, MeshData};
+use crate::post::{
+    Bloom, BloomSettings, ColorGrading, DepthOfField, DofSettings, Fxaa, GradingSettings, Lut,
+    LutPreset, MotionBlur, MultisampleTarget, Present, SceneTarget, TemporalAa,
+};
+use crate::scene::Transform;
+use crate::shaders;
+
+const CANVAS_WIDTH: u32 = 640;
+const CANVAS_HEIGHT: u32 = 400;
+const LIGHT_DIRECTION: Vec3 = [0.36, 0.8, 0.48];
+
+// A row of cubes running away from the camera, so some are always out of focus
+const CUBES: [(Vec3, [f32; 3]); 7] = [
+    ([1.2, 0.0, 2.0], [1.0, 1.0, 1.0]),
+    ([0.4, 0.0, 0.5], [1.0, 0.8, 0.8]),
+    ([-0.4, 0.0, -1.0], [0.8, 1.0, 0.8]),
+    ([-1.2, 0.0, -2.5], [0.8, 0.8, 1.0]),
+    ([-2.0, 0.0, -4.0], [1.0, 1.0, 0.7]),
+    ([-2.8, 0.0, -5.5], [0.7, 1.0, 1.0]),
+    ([-3.6, 0.0, -7.0], [1.0, 0.7, 1.0]),
+];
+// Small emissive cubes floating over the row, bright enough to bloom: (position, color)
+const LAMPS: [(Vec3, [f32; 3]); 3] = [
+    ([0.8, 0.9, 1.2], [1.0, 0.55, 0.2]),
+    ([-0.8, 0.9, -1.8], [0.3, 0.7, 1.0]),
+    ([-2.4, 0.9, -4.8], [1.0, 0.3, 0.8]),
+];
+// Emitted light is this many times over what the display can show
+const LAMP_EMISSION: f32 = 4.0;
+// A cube circling the row fast enough to blur: center, radius and radians per second
+const ORBIT: (Vec3, f32, f32) = ([0.0, 0.5, -0.5], 1.8, 2.5);
+// Samples per pixel asked for with MSAA
+const MSAA_SAMPLES: i32 = 4;
+const GROUND: Transform = Transform {
+    translation: [0.0, -0.55, -2.0],
+    rotation: [0.0; 3],
+    scale: [14.0, 0.1, 14.0],
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Antialiasing {
+    Off,
+    Msaa,
+    Fxaa,
+    Taa,
+}
+
+impl Antialiasing {
+    const ALL: [Antialiasing; 4] = [
+        Antialiasing::Off,
+        Antialiasing::Msaa,
+        Antialiasing::Fxaa,
+        Antialiasing::Taa,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Antialiasing::Off => "Off",
+            Antialiasing::Msaa => "MSAA",
+            Antialiasing::Fxaa => "FXAA",
+            Antialiasing::Taa => "TAA",
+        }
+    }
+}
+
+/**
+ * A small scene rendered into an offscreen target and run through post
+ * passes from `post` before it reaches the canvas
+ */
+#[component]
+pub fn PostDemo() -> Element {
+    let mut canvas_mounted = use_signal(|| false);
+    let mut dof_enabled = use_signal(|| true);
+    let mut dof = use_signal(DofSettings::default);
+    let mut bloom_enabled = use_signal(|| true);
+    let mut bloom = use_signal(BloomSettings::default);
+    let mut motion_enabled = use_signal(|| true);
+    // Fraction of a frame's motion smeared over
+    let mut shutter = use_signal(|| 1.0f32);
+    // Set when there is no velocity buffer to blur along
+    let mut motion_error = use_signal(|| None::<String>);
+    let mut antialiasing = use_signal(|| Antialiasing::Taa);
+    // Modes this context can't do, with the reason
+    let mut aa_unavailable = use_signal(Vec::<(Antialiasing, String)>::new);
+    let mut grading_enabled = use_signal(|| true);
+    let mut grading = use_signal(GradingSettings::default);
+    // None once a strip image has been loaded in place of a preset
+    let mut lut_preset = use_signal(|| Some(LutPreset::Neutral));
+    let mut lut_error = use_signal(|| None::<String>);
+    // Picked tables wait here for the next frame to upload them
+    let pending_lut = use_hook(|| Rc::new(RefCell::new(None::<Lut>)));
+    let camera = use_hook(|| {
+        let mut camera = OrbitCamera::default();
+        camera.distance = 4.0;
+        camera.pitch = 0.25;
+        Rc::new(RefCell::new(camera))
+    });
+    let frame_loop = use_frame_loop();
+
+    use_effect({
+        let camera = camera.clone();
+        let pending_lut = pending_lut.clone();
+        move || {
+            if !canvas_mounted() {
+                return;
+            }
+
+            let camera = camera.clone();
+            let frame_loop = frame_loop.clone();
+            let pending_lut = pending_lut.clone();
+            spawn(async move {
+                let Some(canvas) = web_sys::window()
+                    .and_then(|window| window.document())
+                    .and_then(|document| document.get_element_by_id("post-canvas"))
+                    .and_then(|element| element.dyn_into::<HtmlCanvasElement>().ok())
+                else {
+                    return;
+                };
+                let gl = match gl_util::webgl2_context(&canvas) {
+                    Ok(gl) => gl,
+                    Err(err) => {
+                        gl_errors::report("post: setup", err);
+                        return;
+                    }
+                };
+                canvas.set_width(CANVAS_WIDTH);
+                canvas.set_height(CANVAS_HEIGHT);
+                let size = (CANVAS_WIDTH as i32, CANVAS_HEIGHT as i32);
+
+                let program = match gl_util::create_program(
+                    &gl,
+                    shaders::DEMOS_POST_VERT,
+                    shaders::DEMOS_POST_FRAG,
+                ) {
+                    Ok(program) => program,
+                    Err(err) => {
+                        gl_errors::report("post: program setup", err);
+                        return;
+                    }
+                };
+                let mvp_loc = gl.get_uniform_location(&program, "modelViewProjection");
+                let previous_mvp_loc =
+                    gl.get_uniform_location(&program, "previousModelViewProjection");
+                let model_loc = gl.get_uniform_location(&program, "model");
+                let tint_loc = gl.get_uniform_location(&program, "tint");
+                let light_loc = gl.get_uniform_location(&program, "lightDirection");
+                let emission_loc = gl.get_uniform_location(&program, "emission");
+                let jitter_loc = gl.get_uniform_location(&program, "jitter");
+                let Some(cube) = GpuMesh::upload(&gl, &MeshData::cube()) else {
+                    gl_errors::report("post: setup", "Unable to upload the cube");
+                    return;
+                };
+
+                let passes = SceneTarget::new(&gl, size.0, size.1).and_then(|scene| {
+                    let dof = DepthOfField::new(&gl, &scene)?;
+                    let bloom = Bloom::new(&gl, &scene)?;
+                    let present = Present::new(&gl)?;
+                    let grading = ColorGrading::new(&gl)?;
+                    let fxaa = Fxaa::new(&gl, &scene)?;
+                    Ok((scene, dof, bloom, present, grading, fxaa))
+                });
+                let (scene, depth_of_field, bloom_pass, present, mut color_grading, fxaa) =
+                    match passes {
+                        Ok(passes) => passes,
+                        Err(err) => {
+                            gl_errors::report("post: target setup", err);
+                            return;
+                        }
+                    };
+                let motion_blur = match MotionBlur::new(&gl, &scene) {
+                    Ok(motion_blur) => Some(motion_blur),
+                    Err(err) => {
+                        motion_error.set(Some(err));
+                        None
+                    }
+                };
+                let msaa = MultisampleTarget::new(&gl, &scene, MSAA_SAMPLES)
+                    .map_err(|err| aa_unavailable.write().push((Antialiasing::Msaa, err)))
+                    .ok();
+                let mut taa = TemporalAa::new(&gl, &scene)
+                    .map_err(|err| aa_unavailable.write().push((Antialiasing::Taa, err)))
+                    .ok();
+                if taa.is_none() {
+                    antialiasing.set(Antialiasing::Fxaa);
+                }
+                if let Some(msaa) = &msaa {
+                    tracing::info!(target: "renderer", "MSAA with {} samples", msaa.samples());
+                }
+                tracing::info!(target: "renderer", "Post-processing ready, HDR targets: {}", scene.is_hdr());
+
+                // Each object's model-view-projection from the last frame, in draw order
+                let mut previous: Vec<math::Mat4> = Vec::new();
+                let mut last_mode = None;
+                frame_loop.start(move |time: f64, _| {
+                    let time = time as f32;
+                    let view_proj = camera
+                        .borrow()
+                        .view_projection(CANVAS_WIDTH as f32 / CANVAS_HEIGHT as f32);
+
+                    let mode = *antialiasing.peek();
+                    // The history was built without jitter, or not at all
+                    if last_mode != Some(mode) {
+                        if let Some(taa) = &mut taa {
+                            taa.reset();
+                        }
+                        last_mode = Some(mode);
+                    }
+                    let (multisample, temporal) = match mode {
+                        Antialiasing::Msaa => (msaa.as_ref(), None),
+                        Antialiasing::Taa => (None, taa.as_mut()),
+                        _ => (None, None),
+                    };
+                    let sky = [0.55, 0.65, 0.8, 1.0];
+                    if let Some(msaa) = multisample {
+                        msaa.bind(&gl);
+                        msaa.clear(&gl, sky);
+                    } else {
+                        scene.bind(&gl);
+                        scene.clear(&gl, sky);
+                    }
+                    gl.enable(WebGl2RenderingContext::DEPTH_TEST);
+                    gl.enable(WebGl2RenderingContext::CULL_FACE);
+                    gl.use_program(Some(&program));
+                    let jitter = temporal.as_ref().map_or([0.0; 2], |taa| taa.jitter());
+                    gl.uniform2fv_with_f32_array(jitter_loc.as_ref(), &jitter);
+                    gl.uniform3fv_with_f32_array(
+                        light_loc.as_ref(),
+                        &math::normalize(LIGHT_DIRECTION),
+                    );
+                    // (model, tint, emission) of everything in the frame
+                    let mut objects = vec![(GROUND.matrix(), [0.45, 0.45, 0.45], 0.0)];
+                    for (i, (translation, tint)) in CUBES.iter().enumerate() {
+                        let transform = Transform {
+                            translation: *translation,
+                            rotation: [0.0, time * 0.5 + i as f32, 0.0],
+                            scale: [0.9; 3],
+                        };
+                        objects.push((transform.matrix(), *tint, 0.0));
+                    }
+                    for (i, (translation, color)) in LAMPS.iter().enumerate() {
+                        let transform = Transform {
+                            translation: *translation,
+                            rotation: [time + i as f32, time * 0.7, 0.0],
+                            scale: [0.25; 3],
+                        };
+                        objects.push((transform.matrix(), *color, LAMP_EMISSION));
+                    }
+                    let (center, radius, speed) = ORBIT;
+                    let angle = time * speed;
+                    let orbiter = Transform {
+                        translation: math::add(
+                            center,
+                            [angle.cos() * radius, 0.0, angle.sin() * radius],
+                        ),
+                        rotation: [0.0, -angle, 0.0],
+                        scale: [0.4; 3],
+                    };
+                    objects.push((orbiter.matrix(), [1.0, 0.9, 0.5], 0.0));
+
+                    let mvps: Vec<math::Mat4> = objects
+                        .iter()
+                        .map(|(model, _, _)| math::multiply(&view_proj, model))
+                        .collect();
+                    // Nothing to compare against on the first frame, so nothing moves
+                    if previous.len() != mvps.len() {
+                        previous = mvps.clone();
+                    }
+                    for (((model, tint, emission), mvp), previous_mvp) in
+                        objects.iter().zip(&mvps).zip(&previous)
+                    {
+                        gl.uniform_matrix4fv_with_f32_array(mvp_loc.as_ref(), false, mvp);
+                        gl.uniform_matrix4fv_with_f32_array(
+                            previous_mvp_loc.as_ref(),
+                            false,
+                            previous_mvp,
+                        );
+                        gl.uniform_matrix4fv_with_f32_array(model_loc.as_ref(), false, model);
+                        gl.uniform3fv_with_f32_array(tint_loc.as_ref(), tint);
+                        gl.uniform1f(emission_loc.as_ref(), *emission);
+                        cube.draw(&gl);
+                    }
+                    previous = mvps;
+                    if let Some(msaa) = multisample {
+                        msaa.resolve(&gl, &scene);
+                    }
+
+                    let mut output = scene.color();
+                    // First, while the frame still matches its velocities
+                    if let Some(taa) = temporal {
+                        output = taa.apply(&gl, output, &scene);
+                    }
+                    if let (true, Some(motion_blur)) = (*motion_enabled.peek(), &motion_blur) {
+                        output = motion_blur.apply(&gl, output, &scene, *shutter.peek());
+                    }
+                    if *dof_enabled.peek() {
+                        output = depth_of_field.apply(&gl, output, &scene, &dof.peek());
+                    }
+                    if *bloom_enabled.peek() {
+                        output = bloom_pass.apply(&gl, output, &bloom.peek());
+                    }
+                    // Last, so it smooths the edges the other passes leave
+                    if mode == Antialiasing::Fxaa {
+                        output = fxaa.apply(&gl, output);
+                    }
+                    if let Some(lut) = pending_lut.borrow_mut().take() {
+                        if let Err(err) = color_grading.set_lut(&gl, &lut) {
+                            gl_errors::report("post: LUT upload", err);
+                        }
+                    }
+                    if *grading_enabled.peek() {
+                        color_grading.draw(&gl, output, size, &grading.peek());
+                    } else {
+                        present.draw(&gl, output, size);
+                    }
+                    gl_errors::check(&gl, "post: draw");
+                });
+            });
+        }
+    });
+
+    let on_mouse_down = {
+        let camera = camera.clone();
+        move |evt: MouseEvent| {
+            let point = evt.client_coordinates();
+            camera.borrow_mut().begin_drag(point.x, point.y);
+        }
+    };
+    let on_mouse_move = {
+        let camera = camera.clone();
+        move |evt: MouseEvent| {
+            let point = evt.client_coordinates();
+            camera.borrow_mut().drag_to(point.x, point.y);
+        }
+    };
+    let on_mouse_up = {
+        let camera = camera.clone();
+        move |_| camera.borrow_mut().end_drag()
+    };
+    let on_mouse_leave = {
+        let camera = camera.clone();
+        move |_| camera.borrow_mut().end_drag()
+    };
+    let on_wheel = {
+        let camera = camera.clone();
+        move |evt: WheelEvent| {
+            evt.prevent_default();
+            camera.borrow_mut().zoom(evt.delta().strip_units().y);
+        }
+    };
+
+    let on_lut_preset = {
+        let pending_lut = pending_lut.clone();
+        move |evt: FormEvent| {
+            let Some(preset) = LutPreset::ALL
+                .into_iter()
+                .find(|preset| preset.name() == evt.value())
+            else {
+                return;
+            };
+            *pending_lut.borrow_mut() = Some(preset.lut());
+            lut_preset.set(Some(preset));
+            lut_error.set(None);
+        }
+    };
+    let on_lut_image = {
+        let pending_lut = pending_lut.clone();
+        move |image: web_sys::ImageBitmap| {
+            let lut = assets::image_pixels(&image).and_then(|(width, height, rgba)| {
+                Lut::from_strip(width as usize, height as usize, &rgba)
+            });
+            image.close();
+            match lut {
+                Ok(lut) => {
+                    *pending_lut.borrow_mut() = Some(lut);
+                    lut_preset.set(None);
+                    lut_error.set(None);
+                }
+                Err(err) => lut_error.set(Some(err)),
+            }
+        }
+    };
+
+    let on_antialiasing = move |evt: FormEvent| {
+        if let Some(mode) = Antialiasing::ALL
+            .into_iter()
+            .find(|mode| mode.name() == evt.value())
+        {
+            antialiasing.set(mode);
+        }
+    };
+
+    let settings = dof();
+    let glow = bloom();
+    let look = grading();
+    rsx! {
+        div {
+            style: "display: flex; flex-direction: column; justify-content: center; align-items: center; height: 100vh; background: #f0f0f0;",
+            canvas {
+                id: "post-canvas",
+                width: "{CANVAS_WIDTH}",
+                height: "{CANVAS_HEIGHT}",
+                style: "border: 2px solid #333; background: #222; cursor: grab;",
+                onmounted: move |_| {
+                    canvas_mounted.set(true);
+                },
+                onmousedown: on_mouse_down,
+                onmousemove: on_mouse_move,
+                onmouseup: on_mouse_up,
+                onmouseleave: on_mouse_leave,
+                onwheel: on_wheel,
+            }
+            div {
+                style: "display: flex; gap: 16px; align-items: center; margin-top: 10px; color: #333; font-family: monospace;",
+                label {
+                    "Antialiasing "
+                    select {
+                        onchange: on_antialiasing,
+                        for mode in Antialiasing::ALL {
+                            option {
+                                value: mode.name(),
+                                selected: antialiasing() == mode,
+                                disabled: aa_unavailable.read().iter().any(|(unavailable, _)| *unavailable == mode),
+                                "{mode.name()}"
+                            }
+                        }
+                    }
+                }
+                for (mode, err) in aa_unavailable() {
+                    span { "No {mode.name()} ({err})" }
+                }
+            }
+            div {
+                style: "display: flex; gap: 16px; align-items: center; margin-top: 6px; color: #333; font-family: monospace;",
+                label {
+                    input {
+                        r#type: "checkbox",
+                        checked: dof_enabled(),
+                        onchange: move |evt| dof_enabled.set(evt.checked()),
+                    }
+                    " Depth of field"
+                }
+                label {
+                    "Focus "
+                    input {
+                        r#type: "range",
+                        min: "0.5",
+                        max: "12",
+                        step: "0.1",
+                        value: "{settings.focus_distance}",
+                        oninput: move |evt| {
+                            if let Ok(value) = evt.value().parse() {
+                                dof.write().focus_distance = value;
+                            }
+                        },
+                    }
+                    " {settings.focus_distance:.1}"
+                }
+                label {
+                    "Aperture "
+                    input {
+                        r#type: "range",
+                        min: "0",
+                        max: "2",
+                        step: "0.05",
+                        value: "{settings.aperture}",
+                        oninput: move |evt| {
+                            if let Ok(value) = evt.value().parse() {
+                                dof.write().aperture = value;
+                            }
+                        },
+                    }
+                    " {settings.aperture:.2}"
+                }
+            }
+            div {
+                style: "display: flex; gap: 16px; align-items: center; margin-top: 6px; color: #333; font-family: monospace;",
+                label {
+                    input {
+                        r#type: "checkbox",
+                        checked: bloom_enabled(),
+                        onchange: move |evt| bloom_enabled.set(evt.checked()),
+                    }
+                    " Bloom"
+                }
+                label {
+                    "Threshold "
+                    input {
+                        r#type: "range",
+                        min: "0",
+                        max: "3",
+                        step: "0.05",
+                        value: "{glow.threshold}",
+                        oninput: move |evt| {
+                            if let Ok(value) = evt.value().parse() {
+                                bloom.write().threshold = value;
+                            }
+                        },
+                    }
+                    " {glow.threshold:.2}"
+                }
+                label {
+                    "Intensity "
+                    input {
+                        r#type: "range",
+                        min: "0",
+                        max: "2",
+                        step: "0.05",
+                        value: "{glow.intensity}",
+                        oninput: move |evt| {
+                            if let Ok(value) = evt.value().parse() {
+                                bloom.write().intensity = value;
+                            }
+                        },
+                    }
+                    " {glow.intensity:.2}"
+                }
+            }
+            div {
+                style: "display: flex; gap: 16px; align-items: center; margin-top: 6px; color: #333; font-family: monospace;",
+                if let Some(err) = motion_error() {
+                    span { "No motion blur ({err})" }
+                } else {
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            checked: motion_enabled(),
+                            onchange: move |evt| motion_enabled.set(evt.checked()),
+                        }
+                        " Motion blur"
+                    }
+                    label {
+                        "Shutter "
+                        input {
+                            r#type: "range",
+                            min: "0",
+                            max: "2",
+                            step: "0.05",
+                            value: "{shutter}",
+                            oninput: move |evt| {
+                                if let Ok(value) = evt.value().parse() {
+                                    shutter.set(value);
+                                }
+                            },
+                        }
+                        " {shutter:.2}"
+                    }
+                }
+            }
+            div {
+                style: "display: flex; gap: 16px; align-items: center; margin-top: 6px; color: #333; font-family: monospace;",
+                label {
+                    input {
+                        r#type: "checkbox",
+                        checked: grading_enabled(),
+                        onchange: move |evt| grading_enabled.set(evt.checked()),
+                    }
+                    " Color grading"
+                }
+                label {
+                    "LUT "
+                    select {
+                        onchange: on_lut_preset,
+                        for preset in LutPreset::ALL {
+                            option {
+                                value: preset.name(),
+                                selected: lut_preset() == Some(preset),
+                                "{preset.name()}"
+                            }
+                        }
+                        if lut_preset().is_none() {
+                            option { value: "", selected: true, disabled: true, "Loaded strip" }
+                        }
+                    }
+                }
+                TexturePicker { label: "Strip", onimage: on_lut_image }
+                if let Some(err) = lut_error() {
+                    span { style: "color: #a00;", "{err}" }
+                }
+            }
+            div {
+                style: "display: flex; gap: 16px; align-items: center; margin-top: 6px; color: #333; font-family: monospace;",
+                label {
+                    "Saturation "
+                    input {
+                        r#type: "range",
+                        min: "0",
+                        max: "2",
+                        step: "0.05",
+                        value: "{look.saturation}",
+                        oninput: move |evt| {
+                            if let Ok(value) = evt.value().parse() {
+                                grading.write().saturation = value;
+                            }
+                        },
+                    }
+                    " {look.saturation:.2}"
+                }
+                label {
+                    "Vignette "
+                    input {
+                        r#type: "range",
+                        min: "0",
+                        max: "1",
+                        step: "0.05",
+                        value: "{look.vignette}",
+                        oninput: move |evt| {
+                            if let Ok(value) = evt.value().parse() {
+                                grading.write().vignette = value;
+                            }
+                        },
+                    }
+                    " {look.vignette:.2}"
+                }
+            }
+        }
+    }
+}
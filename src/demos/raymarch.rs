@@ -0,0 +1,145 @@
+use dioxus::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+use web_sys::WebGl2RenderingContext;
+
+use crate::camera::OrbitCamera;
+use crate::gl_errors;
+use crate::gl_util;
+use crate::hooks::use_frame_loop;
+use crate::math;
+use crate::shaders;
+
+#[component]
+pub fn RaymarchDemo() -> Element {
+    let mut canvas_mounted = use_signal(|| false);
+    let camera = use_hook(|| Rc::new(RefCell::new(OrbitCamera::default())));
+    let frame_loop = use_frame_loop();
+
+    use_effect({
+        let camera = camera.clone();
+        move || {
+            if !canvas_mounted() {
+                return;
+            }
+
+            let camera = camera.clone();
+            let frame_loop = frame_loop.clone();
+            spawn(async move {
+                gloo_timers::future::TimeoutFuture::new(50).await;
+
+                let canvas = match gl_util::canvas_by_id("raymarch-canvas") {
+                    Ok(canvas) => canvas,
+                    Err(err) => {
+                        gl_errors::report("raymarch: setup", err);
+                        return;
+                    }
+                };
+
+                let gl = match gl_util::webgl2_context(&canvas) {
+                    Ok(gl) => gl,
+                    Err(err) => {
+                        gl_errors::report("raymarch: setup", err);
+                        return;
+                    }
+                };
+
+                canvas.set_width(480);
+                canvas.set_height(480);
+                gl.viewport(0, 0, 480, 480);
+                gl.disable(WebGl2RenderingContext::DEPTH_TEST);
+
+                let program = match gl_util::create_program(
+                    &gl,
+                    shaders::DEMOS_RAYMARCH_VERT,
+                    shaders::DEMOS_RAYMARCH_FRAG,
+                ) {
+                    Ok(program) => program,
+                    Err(err) => {
+                        gl_errors::report("raymarch: program setup", err);
+                        return;
+                    }
+                };
+                gl.use_program(Some(&program));
+
+                // The triangle is generated in the vertex shader, so an empty VAO is enough
+                let vao = gl.create_vertex_array();
+                gl.bind_vertex_array(vao.as_ref());
+
+                let inv_view_proj_loc = gl.get_uniform_location(&program, "inverseViewProjection");
+                let camera_pos_loc = gl.get_uniform_location(&program, "cameraPosition");
+                let time_loc = gl.get_uniform_location(&program, "time");
+
+                tracing::info!(target: "renderer", "Raymarching program ready");
+
+                frame_loop.start(move |time: f64, _| {
+                    let camera = camera.borrow();
+                    let view_proj = camera.view_projection(1.0);
+                    let inverse = math::invert(&view_proj).unwrap_or_else(math::identity);
+
+                    gl.uniform_matrix4fv_with_f32_array(
+                        inv_view_proj_loc.as_ref(),
+                        false,
+                        &inverse,
+                    );
+                    gl.uniform3fv_with_f32_array(camera_pos_loc.as_ref(), &camera.eye());
+                    gl.uniform1f(time_loc.as_ref(), time as f32);
+
+                    gl.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, 3);
+
+                    gl_errors::check(&gl, "raymarch: draw");
+                });
+            });
+        }
+    });
+
+    let on_mouse_down = {
+        let camera = camera.clone();
+        move |evt: MouseEvent| {
+            let point = evt.client_coordinates();
+            camera.borrow_mut().begin_drag(point.x, point.y);
+        }
+    };
+    let on_mouse_move = {
+        let camera = camera.clone();
+        move |evt: MouseEvent| {
+            let point = evt.client_coordinates();
+            camera.borrow_mut().drag_to(point.x, point.y);
+        }
+    };
+    let on_mouse_up = {
+        let camera = camera.clone();
+        move |_| camera.borrow_mut().end_drag()
+    };
+    let on_mouse_leave = {
+        let camera = camera.clone();
+        move |_| camera.borrow_mut().end_drag()
+    };
+    let on_wheel = {
+        let camera = camera.clone();
+        move |evt: WheelEvent| {
+            evt.prevent_default();
+            camera.borrow_mut().zoom(evt.delta().strip_units().y);
+        }
+    };
+
+    rsx! {
+        div {
+            style: "display: flex; justify-content: center; align-items: center; height: 100vh; background: #f0f0f0;",
+            canvas {
+                id: "raymarch-canvas",
+                width: "480",
+                height: "480",
+                style: "border: 2px solid #333; background: #222; cursor: grab;",
+                onmounted: move |_| {
+                    canvas_mounted.set(true);
+                },
+                onmousedown: on_mouse_down,
+                onmousemove: on_mouse_move,
+                onmouseup: on_mouse_up,
+                onmouseleave: on_mouse_leave,
+                onwheel: on_wheel,
+            }
+        }
+    }
+}
@@ -0,0 +1,2015 @@
+use dioxus::html::HasFileData;
+use dioxus::prelude::*;
+use js_sys::Uint8Array;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use web_sys::{ImageBitmap, WebGl2RenderingContext};
+
+use crate::assets::{self, Assets};
+use crate::axis_gizmo::AxisGizmo;
+use crate::bounds::Aabb;
+use crate::camera::{OrbitCamera, Projection};
+use crate::collision;
+use crate::components::{GlInfo, GpuMemory, Label3D, PowerPreference, TexturePicker, WebGlCanvas};
+use crate::debug::{self, DebugRenderer};
+use crate::download;
+use crate::drag::ObjectDrag;
+use crate::dynamic_resolution::{ResolutionScaler, ScaledTarget};
+use crate::gif::GifCapture;
+use crate::gl_errors;
+use crate::gl_util::GlResource;
+use crate::gltf;
+use crate::grid::InfiniteGrid;
+use crate::hooks::use_frame_loop;
+use crate::hud::Hud;
+use crate::keyboard::{self, KeyAction};
+use crate::material::{Material, ShaderFeatures, STANDARD_SHADER};
+use crate::math::{self, Mat4};
+use crate::mesh::MeshData;
+use crate::obj;
+use crate::optimize::{self, OptimizeStats};
+use crate::perf;
+#[cfg(feature = "physics")]
+use crate::physics::PhysicsWorld;
+use crate::picking::Picker;
+use crate::polyline::{LineJoin, PolylinePoint, PolylineRenderer};
+use crate::query;
+use crate::raycast::{self, Ray};
+use crate::readback::Readback;
+use crate::render_queue::RenderStats;
+use crate::renderer::{Mirror, SceneRenderer};
+use crate::replay::{InputEvent, InputRecorder, ReplayStatus};
+use crate::scene::{BodyKind, Scene, SceneObject, Transform};
+use crate::scene_sync::{ObjectRef, SceneSocket, SceneUpdate, SyncStatus};
+use crate::shaders;
+use crate::storage;
+use crate::texture::Texture;
+use crate::timestep::{FixedTimestep, FrameLimiter};
+use crate::vertex_format::VertexFormat;
+
+const CANVAS_SIZE: u32 = 480;
+// Added to the scene once the asset manager has fetched and parsed it
+const GEM_MODEL: Asset = asset!("/assets/models/gem.obj");
+// Applied to the "Checker" material once it has loaded
+const CHECKER_TEXTURE: Asset = asset!("/assets/textures/checker.png");
+// Simulation step in seconds, independent of the display refresh rate
+const SIMULATION_STEP: f64 = 1.0 / 60.0;
+// The grid lies on the ground's top face
+const GRID_HEIGHT: f32 = -1.0;
+// Rendering rate caps for battery-sensitive use; the simulation keeps real time
+const FRAME_CAPS: [(Option<u32>, &str); 3] = [
+    (None, "Display rate"),
+    (Some(60), "60 FPS"),
+    (Some(30), "30 FPS"),
+];
+// Render resolution relative to the canvas; above 1 supersamples for quality
+const RENDER_SCALES: [(f32, &str); 3] = [(1.0, "100%"), (1.5, "150%"), (2.0, "200%")];
+const MAX_RENDER_SCALE: f32 = 2.0;
+// Registered with the renderer's shader library next to the standard shader
+const NORMALS_SHADER: &str = "normals";
+// Shader every material is switched to: (library name, label)
+const SHADERS: [(&str, &str); 2] = [(STANDARD_SHADER, "Standard"), (NORMALS_SHADER, "Normals")];
+// GIF export: 4 seconds at 10 frames per second, at half the canvas size
+const GIF_FRAMES: usize = 40;
+const GIF_INTERVAL_MS: f64 = 100.0;
+const GIF_SHRINK: usize = 2;
+// Clear color unless the URL sets `bg`
+const DEFAULT_BACKGROUND: [u8; 3] = [0x1a, 0x1a, 0x1a];
+// WebSocket server the sync panel offers before one is typed in
+const DEFAULT_SYNC_URL: &str = "ws://localhost:8080";
+
+// Pebbles scattered around the objects, merged into a few static batches
+const PROP_COUNT: usize = 400;
+// Wall mirror behind the objects
+const MIRROR: Mirror = Mirror {
+    center: [0.0, 0.4, -2.5],
+    half_size: [2.5, 1.4],
+};
+// Corners of the thick wavy loop drawn around the scene
+const LOOP_POINTS: usize = 24;
+// Pixels of drag one arrow key press orbits by
+const ORBIT_STEP: f64 = 15.0;
+// World units one arrow key press moves a held object
+const NUDGE_STEP: f64 = 0.1;
+
+#[component]
+pub fn SceneDemo() -> Element {
+    // Read once; the URL then follows the settings
+    let config = use_hook(|| SceneConfig::from_query(&query::current()));
+    // Last session's settings; the URL takes precedence where it sets one
+    let saved = use_hook(SceneSettings::load);
+    // Set by the canvas once its context exists
+    let mut context = use_signal(|| None::<WebGl2RenderingContext>);
+    let mut hovered = use_signal(|| None::<usize>);
+    // Object chosen with Tab, highlighted while the pointer isn't over another
+    let mut selected = use_signal(|| None::<usize>);
+    // Whether the arrow keys move the selected object instead of the camera
+    let mut holding = use_signal(|| false);
+    // Key hints are shown while the canvas has keyboard focus
+    let mut focused = use_signal(|| false);
+    let mut collisions = use_signal(Vec::<(usize, usize)>::new);
+    let mut render_stats = use_signal(RenderStats::default);
+    // Set by the button, taken by the render loop when it starts capturing
+    let mut gif_requested = use_signal(|| false);
+    // (frames captured, total) while a GIF is being captured
+    let mut gif_progress = use_signal(|| None::<(usize, usize)>);
+    let mut flash_collisions = use_signal(|| saved.flash_collisions);
+    let mut show_hud = use_signal(|| saved.show_hud);
+    let mut show_labels = use_signal(|| saved.show_labels);
+    let mut show_grid = use_signal(|| saved.show_grid);
+    let mut show_axes = use_signal(|| saved.show_axes);
+    let mut show_debug = use_signal(|| saved.show_debug);
+    let mut show_mirror = use_signal(|| saved.show_mirror);
+    let mut show_shadows = use_signal(|| saved.show_shadows);
+    let mut show_props = use_signal(|| saved.show_props);
+    // One solid color per face instead of blended corner colors
+    let mut flat_faces = use_signal(|| saved.flat_faces);
+    // Parallel projection instead of perspective
+    let mut orthographic = use_signal(|| saved.orthographic);
+    // Lower the render resolution while frames run over budget
+    let mut dynamic_resolution = use_signal(|| saved.dynamic_resolution);
+    // Rendering rate cap in frames per second, `None` for the display rate
+    let mut frame_cap = use_signal(|| saved.frame_cap);
+    let mut render_scale = use_signal(|| saved.render_scale);
+    // Library name of the shader every material draws with
+    let mut shader = use_signal(|| saved.shader);
+    // Multiplies the animation delta: 0 pauses, below 1 is slow motion
+    let time_scale = use_signal(|| config.speed.unwrap_or(saved.time_scale).clamp(0.0, 2.0));
+    let mut background = use_signal(|| config.background.unwrap_or(saved.background));
+    let mut compact_props = use_signal(|| saved.compact_props);
+    // GPU memory of the batched pebbles' vertices
+    let mut prop_bytes = use_signal(|| 0usize);
+    // Set once the imported gem has been through the mesh optimizer
+    let mut gem_stats = use_signal(|| None::<OptimizeStats>);
+    // `None` hides the thick line loop
+    let mut loop_join = use_signal(|| saved.loop_join);
+    // Screen position of each object's label, indexed like `Scene::objects`
+    let mut label_positions = use_signal(Vec::<Option<[f32; 2]>>::new);
+    let scene = use_hook(|| Rc::new(RefCell::new(Scene::demo())));
+    let assets = use_context::<Assets>();
+    let gem = use_hook({
+        let assets = assets.clone();
+        move || assets.load_mesh(&GEM_MODEL.to_string())
+    });
+    let checker = use_hook({
+        let assets = assets.clone();
+        move || assets.load_texture(&CHECKER_TEXTURE.to_string())
+    });
+    // Extra model named in the URL, added like a dropped file once loaded:
+    // (name from the file name, handle)
+    let linked_model = use_hook({
+        let assets = assets.clone();
+        let url = config.model.clone();
+        move || {
+            url.map(|url| {
+                let name = url
+                    .rsplit('/')
+                    .next()
+                    .and_then(|file| file.split(['.', '?', '#']).next())
+                    .filter(|name| !name.is_empty())
+                    .unwrap_or("Model")
+                    .to_string();
+                (name, assets.load_mesh(&url))
+            })
+        }
+    });
+    let camera = use_hook(|| Rc::new(RefCell::new(saved.camera())));
+    // Yaw, pitch and distance, updated from the render loop when a camera move ends
+    let mut camera_pose = use_signal(|| saved.camera);
+    // Last cursor position in canvas pixels, `None` while outside the canvas
+    let pointer = use_hook(|| Rc::new(Cell::new(None::<(i32, i32)>)));
+    // Active object drag; when `None`, dragging orbits the camera instead
+    let object_drag = use_hook(|| Rc::new(RefCell::new(None::<ObjectDrag>)));
+    // Object held with the keyboard, as applied by the render loop
+    let key_hold = use_hook(|| Rc::new(Cell::new(None::<usize>)));
+    // Pointer and UI input on its way to the render loop, recorded or replayed
+    let input = use_hook(|| Rc::new(RefCell::new(InputRecorder::default())));
+    let mut replay_status = use_signal(ReplayStatus::default);
+    // Files dropped onto the canvas, decoded and waiting for the render loop
+    let dropped = use_hook(|| Rc::new(RefCell::new(Vec::<DroppedAsset>::new())));
+    // Result of the last dropped file
+    let mut drop_status = use_signal(|| None::<String>);
+    // Optional live connection that other views and data sources update the scene through
+    let sync = use_hook(|| Rc::new(RefCell::new(None::<SceneSocket>)));
+    let mut sync_url = use_signal(|| DEFAULT_SYNC_URL.to_string());
+    let mut sync_status = use_signal(|| None::<SyncStatus>);
+    let frame_loop = use_frame_loop();
+
+    use_effect(move || {
+        SceneSettings {
+            camera: camera_pose(),
+            time_scale: time_scale(),
+            background: background(),
+            frame_cap: frame_cap(),
+            render_scale: render_scale(),
+            shader: shader(),
+            loop_join: loop_join(),
+            show_hud: show_hud(),
+            show_labels: show_labels(),
+            show_grid: show_grid(),
+            show_axes: show_axes(),
+            show_debug: show_debug(),
+            show_mirror: show_mirror(),
+            show_shadows: show_shadows(),
+            show_props: show_props(),
+            flat_faces: flat_faces(),
+            orthographic: orthographic(),
+            compact_props: compact_props(),
+            flash_collisions: flash_collisions(),
+            dynamic_resolution: dynamic_resolution(),
+        }
+        .save();
+    });
+
+    use_effect({
+        let model = config.model.clone();
+        move || {
+            let speed = time_scale();
+            let background = background();
+            let shared = SceneConfig {
+                speed: (speed != 1.0).then_some(speed),
+                background: (background != DEFAULT_BACKGROUND).then_some(background),
+                model: model.clone(),
+            };
+            query::replace(&shared.to_query());
+        }
+    });
+
+    use_effect({
+        let scene = scene.clone();
+        let camera = camera.clone();
+        let pointer = pointer.clone();
+        let object_drag = object_drag.clone();
+        let key_hold = key_hold.clone();
+        let input = input.clone();
+        let dropped = dropped.clone();
+        let linked_model = linked_model.clone();
+        let sync = sync.clone();
+        move || {
+            let Some(gl) = context() else {
+                return;
+            };
+
+            let scene = scene.clone();
+            let assets = assets.clone();
+            let camera = camera.clone();
+            let pointer = pointer.clone();
+            let object_drag = object_drag.clone();
+            let key_hold = key_hold.clone();
+            let input = input.clone();
+            let dropped = dropped.clone();
+            let linked_model = linked_model.clone();
+            let sync = sync.clone();
+            let frame_loop = frame_loop.clone();
+            spawn(async move {
+                let mut renderer = match SceneRenderer::new(
+                    gl.clone(),
+                    &scene.borrow(),
+                    CANVAS_SIZE as i32,
+                    CANVAS_SIZE as i32,
+                ) {
+                    Ok(renderer) => renderer,
+                    Err(err) => {
+                        gl_errors::report("scene: renderer setup", err);
+                        return;
+                    }
+                };
+
+                let normals = shaders::program("demos/scene/normals")
+                    .ok_or_else(|| "The normals shader is missing from shaders/".to_string())
+                    .and_then(|program| {
+                        renderer
+                            .shaders()
+                            .register_program(&gl, NORMALS_SHADER, program)
+                    });
+                if let Err(err) = normals {
+                    gl_errors::report("scene: normals shader", err);
+                }
+
+                let mut hud = match Hud::new(&gl, CANVAS_SIZE as i32, CANVAS_SIZE as i32) {
+                    Ok(hud) => hud,
+                    Err(err) => {
+                        gl_errors::report("scene: HUD setup", err);
+                        return;
+                    }
+                };
+
+                let grid = match InfiniteGrid::new(&gl) {
+                    Ok(grid) => grid,
+                    Err(err) => {
+                        gl_errors::report("scene: grid setup", err);
+                        return;
+                    }
+                };
+                let axis_gizmo = match AxisGizmo::new(&gl) {
+                    Ok(gizmo) => gizmo,
+                    Err(err) => {
+                        gl_errors::report("scene: axis gizmo setup", err);
+                        return;
+                    }
+                };
+                let polylines = match PolylineRenderer::new(&gl) {
+                    Ok(polylines) => polylines,
+                    Err(err) => {
+                        gl_errors::report("scene: polyline setup", err);
+                        return;
+                    }
+                };
+                let debug_renderer = match DebugRenderer::new(&gl) {
+                    Ok(debug_renderer) => debug_renderer,
+                    Err(err) => {
+                        gl_errors::report("scene: debug renderer setup", err);
+                        return;
+                    }
+                };
+                let scaled_target = match ScaledTarget::new(
+                    &gl,
+                    CANVAS_SIZE as i32,
+                    CANVAS_SIZE as i32,
+                    MAX_RENDER_SCALE,
+                ) {
+                    Ok(target) => target,
+                    Err(err) => {
+                        gl_errors::report("scene: scaled target setup", err);
+                        return;
+                    }
+                };
+
+                tracing::info!(target: "renderer", "Scene renderer ready");
+
+                let mut gem_added = false;
+                let mut linked_model_added = false;
+                let mut checker_applied = false;
+                // Texture uploaded from a dropped or picked image; owned here, unlike
+                // the asset manager's shared checker texture
+                let mut user_texture = None::<Texture>;
+                let mut dropped_models = 0;
+                // What the static batches were last built with: (shown, format)
+                let mut built_props = None::<(bool, VertexFormat)>;
+                // Whether the uploaded meshes are flat shaded
+                let mut built_flat = false;
+                // Smoothed frames per second for the HUD
+                let mut fps = 60.0;
+                let mut last_frame = None::<f64>;
+                let mut scaler = ResolutionScaler::default();
+                let mut gif = None::<GifCapture>;
+                // Object under the pointer as of the last finished pick, and the
+                // pick still on its way back from the GPU
+                let mut hit = None::<usize>;
+                let mut pending_pick = None::<Readback>;
+                let mut timestep = FixedTimestep::new(SIMULATION_STEP);
+                let mut limiter = FrameLimiter::default();
+                #[cfg(feature = "physics")]
+                let mut physics = PhysicsWorld::new(&scene.borrow(), SIMULATION_STEP as f32);
+                // Object currently handed over from the simulation to the pointer
+                #[cfg(feature = "physics")]
+                let mut held = None::<usize>;
+                // Last dragged position sent to the sync server: (object, translation)
+                let mut sent_drag = None::<(usize, [f32; 3])>;
+
+                frame_loop.start(move |time: f64, _| {
+                    // The limiter, timestep and FPS gauge work in milliseconds
+                    let timestamp = time * 1000.0;
+                    limiter.set_target_fps(*frame_cap.peek());
+                    if !limiter.ready(timestamp) {
+                        return;
+                    }
+                    // Named stages for the browser profiler's timings track
+                    let _frame = perf::measure("scene:frame");
+                    let update = perf::measure("scene:update");
+
+                    if input.borrow_mut().take_restart() {
+                        // Recording and replay both start from the initial scene
+                        *scene.borrow_mut() = Scene::demo();
+                        if let Err(err) = renderer.reload_meshes(&scene.borrow()) {
+                            gl_errors::report("scene: mesh reload", err);
+                        }
+                        *camera.borrow_mut() = initial_camera();
+                        object_drag.borrow_mut().take();
+                        key_hold.set(None);
+                        selected.set(None);
+                        holding.set(false);
+                        pointer.set(None);
+                        timestep = FixedTimestep::new(SIMULATION_STEP);
+                        gem_added = false;
+                        linked_model_added = false;
+                        checker_applied = false;
+                        if let Some(texture) = user_texture.take() {
+                            texture.delete(&gl);
+                        }
+                        dropped_models = 0;
+                        built_props = None;
+                        #[cfg(feature = "physics")]
+                        {
+                            physics = PhysicsWorld::new(&scene.borrow(), SIMULATION_STEP as f32);
+                            held = None;
+                        }
+                    }
+
+                    // Inputs go in between simulation steps, at the step they were
+                    // (or, when replaying, were recorded) applied before
+                    let apply = |event| {
+                        apply_input(
+                            event,
+                            &scene,
+                            &camera,
+                            &object_drag,
+                            &key_hold,
+                            &pointer,
+                            time_scale,
+                        )
+                    };
+                    timestep.set_time_scale(*time_scale.peek());
+                    let due = timestep.advance(timestamp) as u64;
+                    let first = timestep.step_count() - due;
+                    for step in first..first + due {
+                        let events = input.borrow_mut().events_for_step(step);
+                        events.into_iter().for_each(&apply);
+
+                        #[cfg(feature = "physics")]
+                        {
+                            let dragged = object_drag
+                                .borrow()
+                                .as_ref()
+                                .map(|drag| drag.object)
+                                .or(key_hold.get());
+                            if held != dragged {
+                                if let Some(object) = held {
+                                    physics.set_held(object, false);
+                                }
+                                if let Some(object) = dragged {
+                                    physics.set_held(object, true);
+                                }
+                                held = dragged;
+                            }
+                            if let Some(object) = held {
+                                physics
+                                    .move_held(object, &scene.borrow().objects[object].transform);
+                            }
+                            physics.step();
+                            physics.sync_to_scene(&mut scene.borrow_mut());
+                        }
+                    }
+                    // Input from a frame too short for a step applies before the next one
+                    let events = input.borrow_mut().events_for_step(timestep.step_count());
+                    events.into_iter().for_each(&apply);
+                    let status = input.borrow().status();
+                    if *replay_status.peek() != status {
+                        replay_status.set(status);
+                    }
+                    // Only settled poses, so a drag doesn't save every frame
+                    let pose = {
+                        let camera = camera.borrow();
+                        (!camera.is_dragging()).then_some([
+                            camera.yaw,
+                            camera.pitch,
+                            camera.distance,
+                        ])
+                    };
+                    if let Some(pose) = pose.filter(|pose| *camera_pose.peek() != *pose) {
+                        camera_pose.set(pose);
+                    }
+
+                    if let Some(socket) = sync.borrow().as_ref() {
+                        let mut scene = scene.borrow_mut();
+                        for update in socket.take_updates() {
+                            update.apply(&mut scene);
+                        }
+                        // Objects moved here move in the other connected views too
+                        let moving = object_drag
+                            .borrow()
+                            .as_ref()
+                            .map(|drag| drag.object)
+                            .or(key_hold.get());
+                        if let Some(index) = moving {
+                            let object = &scene.objects[index];
+                            let moved = (index, object.transform.translation);
+                            if sent_drag != Some(moved) {
+                                socket.send(&SceneUpdate {
+                                    object: ObjectRef::Name(object.name.clone()),
+                                    translation: Some(moved.1),
+                                    rotation: None,
+                                    scale: None,
+                                    color: None,
+                                });
+                                sent_drag = Some(moved);
+                            }
+                        }
+                    }
+
+                    if !gem_added {
+                        if let Some(mesh) = assets.mesh(gem) {
+                            let (mesh, stats) = optimize::optimize_mesh(&mesh);
+                            gem_stats.set(Some(stats));
+                            add_gem(&mut scene.borrow_mut(), &mesh);
+                            if let Err(err) = renderer.upload_new_meshes(&scene.borrow()) {
+                                gl_errors::report("scene: gem upload", err);
+                            }
+                            gem_added = true;
+                        }
+                    }
+
+                    if !linked_model_added {
+                        if let Some((name, mesh)) = linked_model
+                            .as_ref()
+                            .and_then(|(name, model)| Some((name, assets.mesh(*model)?)))
+                        {
+                            add_dropped_model(
+                                &mut scene.borrow_mut(),
+                                name.clone(),
+                                (*mesh).clone(),
+                                dropped_models,
+                            );
+                            dropped_models += 1;
+                            if let Err(err) = renderer.upload_new_meshes(&scene.borrow()) {
+                                gl_errors::report("scene: linked model upload", err);
+                            }
+                            linked_model_added = true;
+                        }
+                    }
+
+                    if !checker_applied {
+                        if let Some(texture) = assets.texture(&gl, checker) {
+                            let mut scene = scene.borrow_mut();
+                            if let Some(index) = scene.material_index("Checker") {
+                                scene.materials[index].texture = Some(texture);
+                            }
+                            checker_applied = true;
+                        }
+                    }
+
+                    for asset in dropped.borrow_mut().drain(..) {
+                        match asset {
+                            DroppedAsset::Model { name, mesh } => {
+                                add_dropped_model(
+                                    &mut scene.borrow_mut(),
+                                    name,
+                                    mesh,
+                                    dropped_models,
+                                );
+                                dropped_models += 1;
+                                if let Err(err) = renderer.upload_new_meshes(&scene.borrow()) {
+                                    gl_errors::report("scene: dropped model upload", err);
+                                }
+                            }
+                            // Dropped and picked images replace the checker texture
+                            DroppedAsset::Image(image) => {
+                                match Texture::from_image(&gl, &image) {
+                                    Some(texture) => {
+                                        let mut scene = scene.borrow_mut();
+                                        if let Some(index) = scene.material_index("Checker") {
+                                            scene.materials[index].texture = Some(texture.clone());
+                                        }
+                                        checker_applied = true;
+                                        // Nothing else refers to the one it replaced
+                                        if let Some(old) = user_texture.replace(texture) {
+                                            old.delete(&gl);
+                                        }
+                                    }
+                                    None => gl_errors::report(
+                                        "scene: image upload",
+                                        "Unable to upload image",
+                                    ),
+                                }
+                                // The pixels are on the GPU now
+                                image.close();
+                            }
+                        }
+                    }
+
+                    let format = if *compact_props.peek() {
+                        VertexFormat::Compact
+                    } else {
+                        VertexFormat::Float
+                    };
+                    // Materials added since (dropped models, the gem) get it too
+                    let shader = *shader.peek();
+                    for material in &mut scene.borrow_mut().materials {
+                        if material.shader != shader {
+                            material.shader = shader.to_string();
+                        }
+                    }
+
+                    let flat = *flat_faces.peek();
+                    if built_flat != flat {
+                        built_flat = flat;
+                        renderer.set_flat_faces(flat);
+                        if let Err(err) = renderer.reload_meshes(&scene.borrow()) {
+                            gl_errors::report("scene: mesh reload", err);
+                        }
+                        built_props = None;
+                    }
+                    let props = (*show_props.peek(), format);
+                    if built_props != Some(props) {
+                        built_props = Some(props);
+                        let mut scene = scene.borrow_mut();
+                        scene.statics = if props.0 {
+                            scene.pebbles(PROP_COUNT, GRID_HEIGHT)
+                        } else {
+                            Vec::new()
+                        };
+                        if let Err(err) = renderer.rebuild_static_batches(&scene, format) {
+                            gl_errors::report("scene: prop batching", err);
+                        }
+                        prop_bytes.set(renderer.static_batch_bytes());
+                    }
+
+                    drop(update);
+
+                    // What is under the cursor, colliding and labelled; the scene is
+                    // small enough to draw without frustum culling
+                    camera.borrow_mut().projection = if *orthographic.peek() {
+                        Projection::Orthographic
+                    } else {
+                        Projection::Perspective
+                    };
+                    let visibility = perf::measure("scene:visibility");
+                    let scene = scene.borrow();
+                    let camera = camera.borrow();
+                    let view_proj = camera.view_projection(renderer.aspect());
+
+                    // Hover detection runs every frame so it tracks camera motion too. The
+                    // pick is read back through a fence instead of stalling on the GPU, so
+                    // the result is a frame or two behind; a new pick starts once the last
+                    // one has landed
+                    if let Some(readback) = pending_pick.take() {
+                        match readback.is_ready(&gl) {
+                            Ok(true) => hit = Picker::picked(&readback.finish(&gl)),
+                            Ok(false) => pending_pick = Some(readback),
+                            Err(err) => {
+                                readback.delete(&gl);
+                                gl_errors::report("scene: pick readback", err);
+                            }
+                        }
+                    }
+                    match pointer.get() {
+                        None => {
+                            hit = None;
+                            if let Some(readback) = pending_pick.take() {
+                                readback.delete(&gl);
+                            }
+                        }
+                        Some((x, y)) if pending_pick.is_none() => {
+                            match renderer.start_pick(&scene, &view_proj, x, y) {
+                                Some(Ok(readback)) => pending_pick = Some(readback),
+                                Some(Err(err)) => gl_errors::report("scene: picking", err),
+                                None => hit = None,
+                            }
+                        }
+                        Some(_) => {}
+                    }
+                    if *hovered.peek() != hit {
+                        hovered.set(hit);
+                    }
+
+                    // Broad-phase collision check on world-space AABBs
+                    let bounds = collision::world_bounds(&scene);
+                    // Hidden objects sit out the sweep, which indexes the visible ones
+                    let visible: Vec<usize> = (0..scene.objects.len())
+                        .filter(|&i| scene.objects[i].visible)
+                        .collect();
+                    let visible_bounds: Vec<_> = visible.iter().map(|&i| bounds[i]).collect();
+                    let pairs: Vec<(usize, usize)> = collision::overlapping_pairs(&visible_bounds)
+                        .into_iter()
+                        .map(|(a, b)| (visible[a], visible[b]))
+                        .collect();
+                    let flashing: Vec<usize> = if *flash_collisions.peek() {
+                        pairs.iter().flat_map(|&(a, b)| [a, b]).collect()
+                    } else {
+                        Vec::new()
+                    };
+                    if *collisions.peek() != pairs {
+                        collisions.set(pairs);
+                    }
+
+                    if *show_debug.peek() {
+                        draw_debug(&scene, &bounds, &view_proj, hit, pointer.get());
+                    }
+
+                    // Labels float just above the top of each object's bounds
+                    let positions: Vec<_> = if *show_labels.peek() {
+                        bounds
+                            .iter()
+                            .zip(&scene.objects)
+                            .map(|(aabb, object)| {
+                                if !object.visible {
+                                    return None;
+                                }
+                                let [x, _, z] = aabb.center();
+                                let size = CANVAS_SIZE as f32;
+                                math::world_to_screen(&view_proj, [x, aabb.max[1], z], size, size)
+                            })
+                            .collect()
+                    } else {
+                        Vec::new()
+                    };
+                    if *label_positions.peek() != positions {
+                        label_positions.set(positions);
+                    }
+
+                    drop(visibility);
+
+                    let opaque = perf::measure("scene:opaque");
+                    // Dynamic resolution backs off from the chosen scale when frames run long
+                    let dynamic_scale = if *dynamic_resolution.peek() {
+                        let target_fps = frame_cap.peek().unwrap_or(60) as f64;
+                        match last_frame {
+                            Some(last) => scaler.update(timestamp - last, target_fps),
+                            None => scaler.scale(),
+                        }
+                    } else {
+                        scaler = ResolutionScaler::default();
+                        1.0
+                    };
+                    let scale = *render_scale.peek() * dynamic_scale;
+                    let (render_width, render_height) = scaled_target.scaled_size(scale);
+                    let scaled = scale != 1.0;
+                    if scaled {
+                        scaled_target.begin(&gl, render_width, render_height);
+                    }
+                    renderer.set_render_size(render_width, render_height);
+
+                    renderer.set_background((*background.peek()).map(|c| c as f32 / 255.0));
+                    // A hidden selection keeps its place but isn't outlined
+                    let highlighted = hit
+                        .or(*selected.peek())
+                        .filter(|&i| scene.objects.get(i).is_some_and(|object| object.visible));
+                    let stats = renderer.render(
+                        &scene,
+                        &view_proj,
+                        highlighted,
+                        &flashing,
+                        timestep.elapsed(),
+                    );
+                    if *render_stats.peek() != stats {
+                        render_stats.set(stats);
+                    }
+                    drop(opaque);
+
+                    // Shadows, mirror and overlays, then resampling, gizmo, capture and HUD
+                    let _post = perf::measure("scene:post");
+                    if *show_shadows.peek() {
+                        renderer.render_shadows(&scene, &view_proj, GRID_HEIGHT);
+                    }
+                    if *show_mirror.peek() {
+                        renderer.render_mirror(&scene, &view_proj, &MIRROR);
+                    }
+                    if *show_grid.peek() {
+                        grid.draw(&gl, &view_proj, GRID_HEIGHT);
+                    }
+                    if let Some(join) = *loop_join.peek() {
+                        let points = wavy_loop(timestep.elapsed() as f32);
+                        polylines.draw(&gl, &view_proj, &points, join);
+                    }
+                    debug_renderer.flush(&gl, &view_proj);
+                    if scaled {
+                        scaled_target.finish(&gl, render_width, render_height);
+                    }
+                    if *show_axes.peek() {
+                        let size = CANVAS_SIZE as i32;
+                        axis_gizmo.draw(&gl, &camera.view_matrix(), size, size);
+                    }
+
+                    // Captured before the HUD, which is about this machine, not the scene
+                    if *gif_requested.peek() && gif.is_none() {
+                        gif_requested.set(false);
+                        gif = Some(GifCapture::new(
+                            gl.drawing_buffer_width() as usize,
+                            gl.drawing_buffer_height() as usize,
+                            GIF_SHRINK,
+                            GIF_FRAMES,
+                            GIF_INTERVAL_MS,
+                        ));
+                    }
+                    if let Some(mut capture) = gif.take() {
+                        // Frames are read back asynchronously and encoded as they arrive
+                        let captured = capture.poll(&gl).and_then(|()| {
+                            if capture.due(timestamp) {
+                                capture.capture(&gl)
+                            } else {
+                                Ok(())
+                            }
+                        });
+                        match captured {
+                            Ok(()) => {
+                                if *gif_progress.peek() != Some(capture.progress()) {
+                                    gif_progress.set(Some(capture.progress()));
+                                }
+                                gif = Some(capture);
+                            }
+                            Err(err) => {
+                                tracing::error!(target: "renderer", "GIF capture failed: {}", err);
+                                capture.cancel(&gl);
+                                gif_progress.set(None);
+                            }
+                        }
+                    }
+                    if let Some(capture) = gif.take_if(|capture| capture.is_done()) {
+                        let bytes = capture.finish();
+                        if let Err(err) = download::save_file(&bytes, "scene.gif", "image/gif") {
+                            tracing::error!(target: "ui", "Saving the GIF failed: {:?}", err);
+                        }
+                        gif_progress.set(None);
+                    }
+
+                    if let Some(last) = last_frame.replace(timestamp) {
+                        if timestamp > last {
+                            fps = fps * 0.95 + 1000.0 / (timestamp - last) * 0.05;
+                        }
+                    }
+                    if *show_hud.peek() {
+                        draw_hud(&mut hud, &gl, fps as f32, scale);
+                    }
+                });
+            });
+        }
+    });
+
+    // The loop applies queued input, so recordings replay at the same steps
+    let on_mouse_down = {
+        let input = input.clone();
+        move |evt: MouseEvent| {
+            let point = evt.element_coordinates();
+            input.borrow_mut().push(InputEvent::PointerDown {
+                x: point.x,
+                y: point.y,
+            });
+        }
+    };
+    let on_mouse_move = {
+        let input = input.clone();
+        move |evt: MouseEvent| {
+            let point = evt.element_coordinates();
+            input.borrow_mut().push(InputEvent::PointerMove {
+                x: point.x,
+                y: point.y,
+            });
+        }
+    };
+    let on_mouse_up = {
+        let input = input.clone();
+        move |_| input.borrow_mut().push(InputEvent::PointerUp)
+    };
+    let on_mouse_leave = {
+        let input = input.clone();
+        move |_| input.borrow_mut().push(InputEvent::PointerLeave)
+    };
+    let on_wheel = {
+        let input = input.clone();
+        move |evt: WheelEvent| {
+            evt.prevent_default();
+            input.borrow_mut().push(InputEvent::Wheel {
+                delta: evt.delta().strip_units().y,
+            });
+        }
+    };
+    // Keys go through the input queue too; selection is UI state, but what it
+    // does to the camera and objects is recorded
+    let on_key_down = {
+        let input = input.clone();
+        let scene = scene.clone();
+        move |evt: KeyboardEvent| {
+            let Some(action) = keyboard::action(&evt.key(), evt.modifiers().shift()) else {
+                return;
+            };
+            let mut input = input.borrow_mut();
+            let mut release = move |input: &mut InputRecorder| {
+                if holding() {
+                    holding.set(false);
+                    input.push(InputEvent::Hold(None));
+                }
+            };
+            match action {
+                KeyAction::Direction { right, up } => match selected().filter(|_| holding()) {
+                    Some(object) => input.push(InputEvent::Nudge {
+                        object,
+                        right: (right * NUDGE_STEP) as f32,
+                        forward: (up * NUDGE_STEP) as f32,
+                    }),
+                    // The way dragging in that direction would turn it
+                    None => input.push(InputEvent::Orbit {
+                        dx: right * ORBIT_STEP,
+                        dy: -up * ORBIT_STEP,
+                    }),
+                },
+                KeyAction::Zoom(delta) => input.push(InputEvent::Wheel { delta }),
+                KeyAction::Cycle { backwards } => {
+                    release(&mut input);
+                    let count = scene.borrow().objects.len();
+                    let next = keyboard::cycle(selected(), count, backwards);
+                    selected.set(next);
+                    // Past the last object, Tab moves on to the rest of the page
+                    if next.is_none() {
+                        return;
+                    }
+                }
+                KeyAction::Activate => {
+                    let Some(object) = selected() else {
+                        return;
+                    };
+                    if holding() {
+                        release(&mut input);
+                    } else if scene.borrow().objects[object].body != Some(BodyKind::Fixed) {
+                        holding.set(true);
+                        input.push(InputEvent::Hold(Some(object)));
+                    }
+                }
+                KeyAction::Cancel => {
+                    if holding() {
+                        release(&mut input);
+                    } else if selected().is_some() {
+                        selected.set(None);
+                    } else {
+                        return;
+                    }
+                }
+            }
+            evt.prevent_default();
+        }
+    };
+    let on_time_scale = {
+        let input = input.clone();
+        move |evt: FormEvent| {
+            if let Ok(value) = evt.value().parse() {
+                input.borrow_mut().push(InputEvent::TimeScale(value));
+            }
+        }
+    };
+    let on_record = {
+        let input = input.clone();
+        move |_| {
+            let mut input = input.borrow_mut();
+            input.start_recording();
+            // Replays start from the time scale the recording started with
+            input.push(InputEvent::TimeScale(time_scale()));
+        }
+    };
+    let on_stop = {
+        let input = input.clone();
+        move |_| input.borrow_mut().stop()
+    };
+    let on_replay = {
+        let input = input.clone();
+        move |_| input.borrow_mut().start_replay()
+    };
+    let on_export = {
+        let scene = scene.clone();
+        move |_| {
+            let glb = gltf::export_glb(&scene.borrow());
+            if let Err(err) = download::save_file(&glb, "scene.glb", "model/gltf-binary") {
+                tracing::error!(target: "ui", "Saving the glTF failed: {:?}", err);
+            }
+        }
+    };
+    let on_drop = {
+        let dropped = dropped.clone();
+        move |evt: DragEvent| {
+            let Some(files) = evt.files() else {
+                return;
+            };
+            for name in files.files() {
+                let files = files.clone();
+                let dropped = dropped.clone();
+                spawn(async move {
+                    let loaded = match files.read_file(&name).await {
+                        Some(bytes) => load_dropped(&name, bytes).await,
+                        None => Err(format!("Unable to read {}", name)),
+                    };
+                    match loaded {
+                        Ok(asset) => {
+                            dropped.borrow_mut().push(asset);
+                            drop_status.set(Some(format!("Loaded {}", name)));
+                        }
+                        Err(err) => drop_status.set(Some(err)),
+                    }
+                });
+            }
+        }
+    };
+    let on_sync = {
+        let sync = sync.clone();
+        move |_| {
+            let mut sync = sync.borrow_mut();
+            if sync.take().is_some() {
+                sync_status.set(None);
+                return;
+            }
+            match SceneSocket::connect(&sync_url(), move |status| sync_status.set(Some(status))) {
+                Ok(socket) => *sync = Some(socket),
+                Err(err) => sync_status.set(Some(SyncStatus::Closed(err))),
+            }
+        }
+    };
+    let on_reset_settings = {
+        let camera = camera.clone();
+        let input = input.clone();
+        move |_| {
+            let defaults = SceneSettings::default();
+            *camera.borrow_mut() = defaults.camera();
+            // Through the input queue like the slider, so recordings see it
+            input
+                .borrow_mut()
+                .push(InputEvent::TimeScale(defaults.time_scale));
+            background.set(defaults.background);
+            frame_cap.set(defaults.frame_cap);
+            render_scale.set(defaults.render_scale);
+            shader.set(defaults.shader);
+            loop_join.set(defaults.loop_join);
+            show_hud.set(defaults.show_hud);
+            show_labels.set(defaults.show_labels);
+            show_grid.set(defaults.show_grid);
+            show_axes.set(defaults.show_axes);
+            show_debug.set(defaults.show_debug);
+            show_mirror.set(defaults.show_mirror);
+            show_shadows.set(defaults.show_shadows);
+            show_props.set(defaults.show_props);
+            flat_faces.set(defaults.flat_faces);
+            orthographic.set(defaults.orthographic);
+            compact_props.set(defaults.compact_props);
+            flash_collisions.set(defaults.flash_collisions);
+            dynamic_resolution.set(defaults.dynamic_resolution);
+        }
+    };
+    let background_hex = {
+        let [r, g, b] = background();
+        format!("{:02x}{:02x}{:02x}", r, g, b)
+    };
+    let sync_text = match sync_status() {
+        None => String::new(),
+        Some(SyncStatus::Connecting) => "Connecting…".to_string(),
+        Some(SyncStatus::Open) => "Connected".to_string(),
+        Some(SyncStatus::Closed(reason)) => format!("Disconnected ({})", reason),
+    };
+    let connected = sync.borrow().is_some();
+    let can_replay = input.borrow().has_recording();
+    let replay_text = match replay_status() {
+        ReplayStatus::Live => String::new(),
+        ReplayStatus::Recording { events } => format!("Recording: {} events", events),
+        ReplayStatus::Replaying { applied, total } => format!("Replaying: {}/{}", applied, total),
+    };
+
+    let hovered_name =
+        hovered().and_then(|i| scene.borrow().objects.get(i).map(|o| o.name.clone()));
+    let selected_name =
+        selected().and_then(|i| scene.borrow().objects.get(i).map(|o| o.name.clone()));
+    let collision_text = {
+        let scene = scene.borrow();
+        collisions()
+            .iter()
+            .map(|&(a, b)| format!("{} ↔ {}", scene.objects[a].name, scene.objects[b].name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let label_names: Vec<String> = scene
+        .borrow()
+        .objects
+        .iter()
+        .map(|object| object.name.clone())
+        .collect();
+    // (index, name, tint as hex, visible) for the object list
+    let object_rows: Vec<(usize, String, String, bool)> = scene
+        .borrow()
+        .objects
+        .iter()
+        .enumerate()
+        .map(|(index, object)| {
+            let [r, g, b, _] = object
+                .tint
+                .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+            let hex = format!("{:02x}{:02x}{:02x}", r, g, b);
+            (index, object.name.clone(), hex, object.visible)
+        })
+        .collect();
+
+    let cursor = if hovered().is_some() { "move" } else { "grab" };
+
+    rsx! {
+        div {
+            style: "display: flex; flex-direction: column; justify-content: center; align-items: center; height: 100vh; background: #f0f0f0;",
+            div {
+                style: "position: relative;",
+                WebGlCanvas {
+                    id: "scene-canvas",
+                    width: CANVAS_SIZE,
+                    height: CANVAS_SIZE,
+                    style: "border: 2px solid #333; background: #222; cursor: {cursor};",
+                    // The mirror pass masks its surface in the stencil buffer
+                    stencil: true,
+                    power_preference: PowerPreference::HighPerformance,
+                    oncontext: move |gl| context.set(Some(gl)),
+                    onmousedown: on_mouse_down,
+                    onmousemove: on_mouse_move,
+                    onmouseup: on_mouse_up,
+                    onmouseleave: on_mouse_leave,
+                    ondrop: on_drop,
+                    onwheel: on_wheel,
+                    focusable: true,
+                    label: "3D scene: arrow keys orbit, Tab selects objects",
+                    onkeydown: on_key_down,
+                    onfocus: move |_| focused.set(true),
+                    onblur: move |_| focused.set(false),
+                    GlInfo {}
+                }
+                div {
+                    class: "canvas-overlay",
+                    for (name, position) in label_names.into_iter().zip(label_positions()) {
+                        Label3D { key: "{name}", position, "{name}" }
+                    }
+                    if focused() {
+                        div {
+                            class: "key-hints",
+                            if holding() {
+                                div { "←↑→↓ move object" }
+                                div { "Enter / Esc put down" }
+                            } else {
+                                div { "←↑→↓ orbit · + − zoom" }
+                                div { "Tab / Shift+Tab select object" }
+                                div { "Enter pick up selected" }
+                            }
+                        }
+                    }
+                }
+            }
+            p {
+                style: "color: #333; font-family: monospace;",
+                match (hovered_name, selected_name) {
+                    (Some(name), _) => rsx! { "Hovering: {name}" },
+                    (None, Some(name)) if holding() => rsx! { "Moving {name} with the arrow keys" },
+                    (None, Some(name)) => rsx! { "Selected: {name}" },
+                    (None, None) => rsx! { "Hover an object to highlight it, drag it to move it" },
+                }
+            }
+            p {
+                style: "color: #666; font-family: monospace; font-size: small;",
+                match drop_status() {
+                    Some(status) => rsx! { "{status}" },
+                    None => rsx! { "Drop .gltf, .glb, .obj or image files onto the canvas to load them" },
+                }
+            }
+            details {
+                style: "color: #333; font-family: monospace;",
+                summary { "Objects" }
+                for (index, name, hex, visible) in object_rows {
+                    div {
+                        key: "{index}",
+                        style: "display: flex; gap: 8px; align-items: center;",
+                        input {
+                            r#type: "checkbox",
+                            checked: visible,
+                            title: "Visible",
+                            onchange: {
+                                let input = input.clone();
+                                move |evt: FormEvent| {
+                                    input.borrow_mut().push(InputEvent::Visible {
+                                        object: index,
+                                        visible: evt.checked(),
+                                    });
+                                }
+                            },
+                        }
+                        input {
+                            r#type: "color",
+                            value: "#{hex}",
+                            title: "Tint",
+                            oninput: {
+                                let input = input.clone();
+                                move |evt: FormEvent| {
+                                    if let Some(rgb) = parse_hex_color(&evt.value()) {
+                                        input.borrow_mut().push(InputEvent::Tint {
+                                            object: index,
+                                            rgb: rgb.map(|c| c as f32 / 255.0),
+                                        });
+                                    }
+                                }
+                            },
+                        }
+                        "{name}"
+                    }
+                }
+            }
+            label {
+                style: "color: #333; font-family: monospace;",
+                input {
+                    r#type: "checkbox",
+                    checked: flash_collisions(),
+                    onchange: move |evt| flash_collisions.set(evt.checked()),
+                }
+                " Flash colliding objects"
+            }
+            label {
+                style: "color: #333; font-family: monospace;",
+                input {
+                    r#type: "checkbox",
+                    checked: show_hud(),
+                    onchange: move |evt| show_hud.set(evt.checked()),
+                }
+                " Show HUD"
+            }
+            label {
+                style: "color: #333; font-family: monospace;",
+                input {
+                    r#type: "checkbox",
+                    checked: dynamic_resolution(),
+                    onchange: move |evt| dynamic_resolution.set(evt.checked()),
+                }
+                " Dynamic resolution"
+            }
+            label {
+                style: "color: #333; font-family: monospace;",
+                input {
+                    r#type: "checkbox",
+                    checked: show_labels(),
+                    onchange: move |evt| show_labels.set(evt.checked()),
+                }
+                " Show labels"
+            }
+            label {
+                style: "color: #333; font-family: monospace;",
+                input {
+                    r#type: "checkbox",
+                    checked: show_grid(),
+                    onchange: move |evt| show_grid.set(evt.checked()),
+                }
+                " Show grid"
+            }
+            label {
+                style: "color: #333; font-family: monospace;",
+                input {
+                    r#type: "checkbox",
+                    checked: show_axes(),
+                    onchange: move |evt| show_axes.set(evt.checked()),
+                }
+                " Show axis gizmo"
+            }
+            label {
+                style: "color: #333; font-family: monospace;",
+                input {
+                    r#type: "checkbox",
+                    checked: show_mirror(),
+                    onchange: move |evt| show_mirror.set(evt.checked()),
+                }
+                " Mirror"
+            }
+            label {
+                style: "color: #333; font-family: monospace;",
+                input {
+                    r#type: "checkbox",
+                    checked: show_shadows(),
+                    onchange: move |evt| show_shadows.set(evt.checked()),
+                }
+                " Planar shadows"
+            }
+            label {
+                style: "color: #333; font-family: monospace;",
+                input {
+                    r#type: "checkbox",
+                    checked: flat_faces(),
+                    onchange: move |evt| flat_faces.set(evt.checked()),
+                }
+                " Flat faces"
+            }
+            label {
+                style: "color: #333; font-family: monospace;",
+                input {
+                    r#type: "checkbox",
+                    checked: orthographic(),
+                    onchange: move |evt| orthographic.set(evt.checked()),
+                }
+                " Orthographic"
+            }
+            label {
+                style: "color: #333; font-family: monospace;",
+                input {
+                    r#type: "checkbox",
+                    checked: show_props(),
+                    onchange: move |evt| show_props.set(evt.checked()),
+                }
+                " {PROP_COUNT} batched pebbles"
+            }
+            div {
+                style: "color: #333; font-family: monospace;",
+                TexturePicker {
+                    label: "Checker texture",
+                    onimage: {
+                        let dropped = dropped.clone();
+                        move |image| dropped.borrow_mut().push(DroppedAsset::Image(image))
+                    },
+                }
+            }
+            label {
+                style: "color: #333; font-family: monospace;",
+                input {
+                    r#type: "checkbox",
+                    checked: compact_props(),
+                    onchange: move |evt| compact_props.set(evt.checked()),
+                }
+                " Compact pebble vertices ({prop_bytes() / 1024} KB)"
+            }
+            label {
+                style: "color: #333; font-family: monospace;",
+                "Time scale "
+                input {
+                    r#type: "range",
+                    min: "0",
+                    max: "2",
+                    step: "0.05",
+                    value: "{time_scale}",
+                    oninput: on_time_scale,
+                }
+                if time_scale() == 0.0 {
+                    " paused"
+                } else {
+                    " {time_scale():.2}×"
+                }
+            }
+            label {
+                style: "color: #333; font-family: monospace;",
+                "Background "
+                input {
+                    r#type: "color",
+                    value: "#{background_hex}",
+                    oninput: move |evt| {
+                        if let Some(color) = parse_hex_color(&evt.value()) {
+                            background.set(color);
+                        }
+                    },
+                }
+            }
+            label {
+                style: "color: #333; font-family: monospace;",
+                "Frame rate "
+                select {
+                    onchange: move |evt| {
+                        if let Some(&(cap, _)) = FRAME_CAPS.iter().find(|(_, name)| *name == evt.value()) {
+                            frame_cap.set(cap);
+                        }
+                    },
+                    for (cap, name) in FRAME_CAPS {
+                        option { value: name, selected: frame_cap() == cap, "{name}" }
+                    }
+                }
+            }
+            label {
+                style: "color: #333; font-family: monospace;",
+                "Render scale "
+                select {
+                    onchange: move |evt| {
+                        if let Some(&(scale, _)) = RENDER_SCALES.iter().find(|(_, name)| *name == evt.value()) {
+                            render_scale.set(scale);
+                        }
+                    },
+                    for (scale, name) in RENDER_SCALES {
+                        option { value: name, selected: render_scale() == scale, "{name}" }
+                    }
+                }
+            }
+            label {
+                style: "color: #333; font-family: monospace;",
+                "Shader "
+                select {
+                    onchange: move |evt| {
+                        if let Some(&(name, _)) = SHADERS.iter().find(|(name, _)| *name == evt.value()) {
+                            shader.set(name);
+                        }
+                    },
+                    for (name, label) in SHADERS {
+                        option { value: name, selected: shader() == name, "{label}" }
+                    }
+                }
+            }
+            label {
+                style: "color: #333; font-family: monospace;",
+                "Line loop "
+                select {
+                    onchange: move |evt| {
+                        loop_join.set(match evt.value().as_str() {
+                            "miter" => Some(LineJoin::Miter),
+                            "round" => Some(LineJoin::Round),
+                            _ => None,
+                        });
+                    },
+                    option { value: "off", selected: loop_join().is_none(), "Off" }
+                    option { value: "miter", selected: loop_join() == Some(LineJoin::Miter), "Miter joins" }
+                    option { value: "round", selected: loop_join() == Some(LineJoin::Round), "Round joins" }
+                }
+            }
+            label {
+                style: "color: #333; font-family: monospace;",
+                input {
+                    r#type: "checkbox",
+                    checked: show_debug(),
+                    onchange: move |evt| show_debug.set(evt.checked()),
+                }
+                " Debug draw (bounds, hover sphere, cursor ray hit)"
+            }
+            p {
+                style: "color: #333; font-family: monospace;",
+                if collision_text.is_empty() {
+                    "No collisions"
+                } else {
+                    "Collisions: {collision_text}"
+                }
+            }
+            div {
+                style: "display: flex; gap: 16px; align-items: center; margin-top: 10px; color: #333; font-family: monospace;",
+                if let ReplayStatus::Live = replay_status() {
+                    button { onclick: on_record, "Record input" }
+                    button { disabled: !can_replay, onclick: on_replay, "Replay" }
+                } else {
+                    button { onclick: on_stop, "Stop" }
+                }
+                span { "{replay_text}" }
+                button { onclick: on_export, "Export glTF" }
+                if let Some((captured, total)) = gif_progress() {
+                    span { "Recording GIF {captured}/{total}" }
+                } else {
+                    button {
+                        disabled: gif_requested(),
+                        onclick: move |_| gif_requested.set(true),
+                        "Export GIF"
+                    }
+                }
+                button { onclick: on_reset_settings, "Reset settings" }
+            }
+            div {
+                style: "display: flex; gap: 16px; align-items: center; margin-top: 10px; color: #333; font-family: monospace;",
+                "Sync "
+                input {
+                    r#type: "text",
+                    value: "{sync_url}",
+                    disabled: connected,
+                    oninput: move |evt| sync_url.set(evt.value()),
+                }
+                button {
+                    onclick: on_sync,
+                    if connected { "Disconnect" } else { "Connect" }
+                }
+                span { "{sync_text}" }
+            }
+            p {
+                style: "color: #333; font-family: monospace;",
+                "{render_stats().draws} draws, {render_stats().program_switches} program switches, {render_stats().material_switches} material switches"
+            }
+            GpuMemory {}
+            if let Some(stats) = gem_stats() {
+                p {
+                    style: "color: #333; font-family: monospace;",
+                    "Gem optimized: {stats.vertices_before} → {stats.vertices_after} vertices, ACMR {stats.acmr_before:.2} → {stats.acmr_after:.2}"
+                }
+            }
+        }
+    }
+}
+
+fn add_gem(scene: &mut Scene, mesh: &MeshData) {
+    scene.meshes.push(mesh.clone());
+    scene.materials.push(Material {
+        name: "Gold".to_string(),
+        features: ShaderFeatures {
+            lighting: true,
+            ..Default::default()
+        },
+        color: [1.0, 0.78, 0.3, 1.0],
+        ..Default::default()
+    });
+    scene.objects.push(SceneObject {
+        name: "Gem".to_string(),
+        mesh: scene.meshes.len() - 1,
+        material: scene.materials.len() - 1,
+        transform: Transform {
+            translation: [0.2, 0.3, -1.6],
+            ..Default::default()
+        },
+        // Decoration only; it isn't part of the physics world built at startup
+        body: None,
+        tint: [1.0; 4],
+        visible: true,
+    });
+}
+
+/**
+ * Settings that can be shared as a link, e.g. `/scene?speed=0.5&bg=112233&model=...`:
+ * the time scale, the background color in hex and the URL of an extra glTF or
+ * OBJ model. `None` leaves the default
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+struct SceneConfig {
+    speed: Option<f64>,
+    background: Option<[u8; 3]>,
+    model: Option<String>,
+}
+
+impl SceneConfig {
+    /**
+     * Unknown keys and malformed values are ignored
+     */
+    fn from_query(pairs: &[(String, String)]) -> Self {
+        let mut config = Self::default();
+        for (key, value) in pairs {
+            match key.as_str() {
+                "speed" => {
+                    config.speed = value.parse().ok().filter(|speed: &f64| speed.is_finite())
+                }
+                "bg" => config.background = parse_hex_color(value),
+                "model" if !value.is_empty() => config.model = Some(value.clone()),
+                _ => {}
+            }
+        }
+        config
+    }
+
+    fn to_query(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = Vec::new();
+        if let Some(speed) = self.speed {
+            pairs.push(("speed", speed.to_string()));
+        }
+        if let Some([r, g, b]) = self.background {
+            pairs.push(("bg", format!("{:02x}{:02x}{:02x}", r, g, b)));
+        }
+        if let Some(model) = &self.model {
+            pairs.push(("model", model.clone()));
+        }
+        pairs
+    }
+}
+
+// localStorage key for `SceneSettings`
+const SETTINGS_KEY: &str = "scene-settings";
+
+/**
+ * Everything the controls below the canvas and the camera set, saved to
+ * localStorage whenever it changes and restored on the next visit
+ */
+#[derive(Clone, Debug, PartialEq)]
+struct SceneSettings {
+    // Orbit camera yaw, pitch and distance
+    camera: [f32; 3],
+    time_scale: f64,
+    background: [u8; 3],
+    frame_cap: Option<u32>,
+    render_scale: f32,
+    shader: &'static str,
+    loop_join: Option<LineJoin>,
+    show_hud: bool,
+    show_labels: bool,
+    show_grid: bool,
+    show_axes: bool,
+    show_debug: bool,
+    show_mirror: bool,
+    show_shadows: bool,
+    show_props: bool,
+    flat_faces: bool,
+    orthographic: bool,
+    compact_props: bool,
+    flash_collisions: bool,
+    dynamic_resolution: bool,
+}
+
+impl Default for SceneSettings {
+    fn default() -> Self {
+        let camera = initial_camera();
+        Self {
+            camera: [camera.yaw, camera.pitch, camera.distance],
+            time_scale: 1.0,
+            background: DEFAULT_BACKGROUND,
+            frame_cap: None,
+            render_scale: 1.0,
+            shader: STANDARD_SHADER,
+            loop_join: Some(LineJoin::Round),
+            show_hud: true,
+            show_labels: true,
+            show_grid: true,
+            show_axes: true,
+            show_debug: false,
+            show_mirror: true,
+            show_shadows: true,
+            show_props: true,
+            flat_faces: false,
+            orthographic: false,
+            compact_props: false,
+            flash_collisions: true,
+            dynamic_resolution: true,
+        }
+    }
+}
+
+impl SceneSettings {
+    /**
+     * The saved settings, falling back to the defaults for anything missing
+     * or unreadable. Stored in query string form, like the shareable URL
+     */
+    fn load() -> Self {
+        let mut settings = Self::default();
+        let Some(stored) = storage::load(SETTINGS_KEY) else {
+            return settings;
+        };
+        for (key, value) in query::parse(&stored) {
+            match key.as_str() {
+                "camera" => {
+                    let pose: Vec<f32> = value.split(',').filter_map(|v| v.parse().ok()).collect();
+                    if let &[yaw, pitch, distance] = pose.as_slice() {
+                        settings.camera = [yaw, pitch, distance];
+                    }
+                }
+                "speed" => {
+                    if let Ok(speed) = value.parse::<f64>() {
+                        settings.time_scale = speed.clamp(0.0, 2.0);
+                    }
+                }
+                "bg" => {
+                    if let Some(color) = parse_hex_color(&value) {
+                        settings.background = color;
+                    }
+                }
+                "fps" => settings.frame_cap = value.parse().ok(),
+                "scale" => {
+                    if let Ok(scale) = value.parse::<f32>() {
+                        settings.render_scale = scale.clamp(1.0, MAX_RENDER_SCALE);
+                    }
+                }
+                "shader" => {
+                    if let Some(&(name, _)) = SHADERS.iter().find(|(name, _)| *name == value) {
+                        settings.shader = name;
+                    }
+                }
+                "loop" => {
+                    settings.loop_join = match value.as_str() {
+                        "miter" => Some(LineJoin::Miter),
+                        "round" => Some(LineJoin::Round),
+                        _ => None,
+                    }
+                }
+                flag => {
+                    if let Some((_, enabled)) =
+                        settings.flags().into_iter().find(|(name, _)| *name == flag)
+                    {
+                        *enabled = value == "1";
+                    }
+                }
+            }
+        }
+        settings
+    }
+
+    fn save(&self) {
+        let [yaw, pitch, distance] = self.camera;
+        let mut pairs = vec![
+            ("camera", format!("{},{},{}", yaw, pitch, distance)),
+            ("speed", self.time_scale.to_string()),
+            ("bg", {
+                let [r, g, b] = self.background;
+                format!("{:02x}{:02x}{:02x}", r, g, b)
+            }),
+            (
+                "fps",
+                self.frame_cap
+                    .map(|cap| cap.to_string())
+                    .unwrap_or_default(),
+            ),
+            ("scale", self.render_scale.to_string()),
+            ("shader", self.shader.to_string()),
+            (
+                "loop",
+                match self.loop_join {
+                    None => "off",
+                    Some(LineJoin::Miter) => "miter",
+                    Some(LineJoin::Round) => "round",
+                }
+                .to_string(),
+            ),
+        ];
+        let mut flags = self.clone();
+        pairs.extend(
+            flags
+                .flags()
+                .into_iter()
+                .map(|(name, enabled)| (name, if *enabled { "1" } else { "0" }.to_string())),
+        );
+        storage::save(SETTINGS_KEY, &query::encode(&pairs));
+    }
+
+    fn flags(&mut self) -> [(&'static str, &mut bool); 13] {
+        [
+            ("hud", &mut self.show_hud),
+            ("labels", &mut self.show_labels),
+            ("grid", &mut self.show_grid),
+            ("axes", &mut self.show_axes),
+            ("debug", &mut self.show_debug),
+            ("mirror", &mut self.show_mirror),
+            ("shadows", &mut self.show_shadows),
+            ("props", &mut self.show_props),
+            ("flat", &mut self.flat_faces),
+            ("ortho", &mut self.orthographic),
+            ("compact", &mut self.compact_props),
+            ("flash", &mut self.flash_collisions),
+            ("dynres", &mut self.dynamic_resolution),
+        ]
+    }
+
+    fn camera(&self) -> OrbitCamera {
+        let mut camera = initial_camera();
+        let [yaw, pitch, distance] = self.camera;
+        camera.yaw = yaw;
+        camera.pitch = pitch;
+        camera.distance = distance;
+        camera
+    }
+}
+
+/**
+ * `rrggbb`, with or without a leading `#`
+ */
+pub(super) fn parse_hex_color(text: &str) -> Option<[u8; 3]> {
+    let hex = text.strip_prefix('#').unwrap_or(text);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok();
+    Some([channel(0)?, channel(1)?, channel(2)?])
+}
+
+/**
+ * A file dropped onto the canvas, decoded off the render loop
+ */
+enum DroppedAsset {
+    Model { name: String, mesh: MeshData },
+    Image(ImageBitmap),
+}
+
+/**
+ * Decode a dropped file by its extension
+ */
+async fn load_dropped(name: &str, bytes: Vec<u8>) -> Result<DroppedAsset, String> {
+    let (stem, extension) = name.rsplit_once('.').unwrap_or((name, ""));
+    let model = |mesh: Result<MeshData, String>| {
+        mesh.map(|mesh| DroppedAsset::Model {
+            name: stem.to_string(),
+            mesh,
+        })
+        .map_err(|err| format!("{}: {}", name, err))
+    };
+    match extension.to_ascii_lowercase().as_str() {
+        "obj" => model(
+            String::from_utf8(bytes)
+                .map_err(|_| "not valid UTF-8".to_string())
+                .and_then(|source| obj::parse(&source)),
+        ),
+        "gltf" | "glb" => model(gltf::import(&bytes)),
+        "png" | "jpg" | "jpeg" | "gif" | "webp" | "bmp" => {
+            assets::decode_image(&Uint8Array::from(bytes.as_slice()).buffer())
+                .await
+                .map(DroppedAsset::Image)
+                .map_err(|err| format!("{}: {}", name, err))
+        }
+        _ => Err(format!(
+            "{}: unsupported file type (drop .gltf, .glb, .obj or an image)",
+            name
+        )),
+    }
+}
+
+/**
+ * Add a dropped model as a new object, scaled to fit a unit box and lined up
+ * above the scene in drop order
+ */
+fn add_dropped_model(scene: &mut Scene, name: String, mut mesh: MeshData, slot: usize) {
+    let bounds = mesh.bounds();
+    let center = math::scale(math::add(bounds.min, bounds.max), 0.5);
+    let size = (0..3)
+        .map(|axis| bounds.max[axis] - bounds.min[axis])
+        .fold(0.0f32, f32::max);
+    let scale = if size > 0.0 { 1.0 / size } else { 1.0 };
+    for position in mesh.positions.chunks_exact_mut(3) {
+        for axis in 0..3 {
+            position[axis] = (position[axis] - center[axis]) * scale;
+        }
+    }
+
+    scene.meshes.push(mesh);
+    scene.materials.push(Material {
+        name: format!("{} material", name),
+        features: ShaderFeatures {
+            vertex_colors: true,
+            lighting: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    // Labels are keyed by name, so repeated drops of one file get numbered
+    let name = if scene.objects.iter().any(|object| object.name == name) {
+        format!("{} ({})", name, slot + 1)
+    } else {
+        name
+    };
+    scene.objects.push(SceneObject {
+        name,
+        mesh: scene.meshes.len() - 1,
+        material: scene.materials.len() - 1,
+        transform: Transform {
+            translation: [(slot % 5) as f32 * 1.2 - 2.4, 1.2, -0.5],
+            ..Default::default()
+        },
+        body: None,
+        tint: [1.0; 4],
+        visible: true,
+    });
+}
+
+/**
+ * Closed zigzag ring around the scene whose width and color vary along it
+ */
+fn wavy_loop(time: f32) -> Vec<PolylinePoint> {
+    (0..=LOOP_POINTS)
+        .map(|i| {
+            let t = i as f32 / LOOP_POINTS as f32;
+            let angle = t * std::f32::consts::TAU;
+            // Alternate corners up and down for visible joins
+            let zigzag = if i % 2 == 0 { 0.25 } else { -0.25 };
+            let height = zigzag + (angle * 2.0 + time).sin() * 0.2;
+            let (s, c) = angle.sin_cos();
+            PolylinePoint {
+                position: [c * 2.6, height, s * 2.6],
+                width: 3.0 + 7.0 * (0.5 + 0.5 * (angle * 3.0 - time * 2.0).sin()),
+                color: [0.5 + 0.5 * c, 0.5 + 0.5 * s, 1.0 - 0.5 * t, 0.9],
+            }
+        })
+        .collect()
+}
+
+/**
+ * Queue world bounds, the hovered object's bounding sphere and where the
+ * cursor ray meets the scene, with a drop line down to the grid
+ */
+fn draw_debug(
+    scene: &Scene,
+    bounds: &[Aabb],
+    view_proj: &Mat4,
+    hovered: Option<usize>,
+    pointer: Option<(i32, i32)>,
+) {
+    for aabb in bounds {
+        debug::aabb(aabb, [0.3, 0.9, 0.4]);
+    }
+    if let Some(sphere) = hovered.map(|i| bounds[i].bounding_sphere()) {
+        debug::sphere(sphere.center, sphere.radius, [1.0, 0.75, 0.1]);
+    }
+    let Some((x, y)) = pointer else {
+        return;
+    };
+    let Some(inverse) = math::invert(view_proj) else {
+        return;
+    };
+    let size = CANVAS_SIZE as f64;
+    let (x, y) = math::pixel_to_ndc(x as f64, y as f64, size, size);
+    if let Some(hit) = raycast::cast_scene(&Ray::from_ndc(&inverse, x, y), scene) {
+        debug::point(hit.point, [1.0, 0.2, 0.9]);
+        let [px, _, pz] = hit.point;
+        debug::line(hit.point, [px, GRID_HEIGHT, pz], [1.0, 0.2, 0.9]);
+    }
+}
+
+/**
+ * Crosshair, frame-rate bar, render scale and watermark, drawn in canvas
+ * pixels over the scene
+ */
+fn draw_hud(hud: &mut Hud, gl: &WebGl2RenderingContext, fps: f32, scale: f32) {
+    let size = CANVAS_SIZE as f32;
+    let center = size * 0.5;
+    let crosshair = [1.0, 1.0, 1.0, 0.8];
+    hud.rect(center - 8.0, center - 1.0, 16.0, 2.0, crosshair);
+    hud.rect(center - 1.0, center - 8.0, 2.0, 16.0, crosshair);
+
+    // Health-bar style gauge: full and green at 60 fps, shrinking and reddening below
+    let health = (fps / 60.0).clamp(0.0, 1.0);
+    hud.rect(10.0, 10.0, 104.0, 12.0, [0.0, 0.0, 0.0, 0.6]);
+    hud.rect(
+        12.0,
+        12.0,
+        100.0 * health,
+        8.0,
+        [1.0 - health, health, 0.2, 1.0],
+    );
+    hud.text(10.0, 28.0, 2.0, &format!("FPS {:.0}", fps), [1.0; 4]);
+    hud.text(
+        10.0,
+        46.0,
+        2.0,
+        &format!("RES {:.0}%", scale * 100.0),
+        [1.0; 4],
+    );
+
+    let watermark = "DIOXUS + WEBGL2";
+    let width = Hud::text_width(watermark, 2.0);
+    hud.text(
+        size - width - 10.0,
+        size - 24.0,
+        2.0,
+        watermark,
+        [1.0, 1.0, 1.0, 0.35],
+    );
+
+    hud.draw(gl);
+}
+
+fn initial_camera() -> OrbitCamera {
+    let mut camera = OrbitCamera::default();
+    camera.distance = 5.0;
+    camera
+}
+
+/**
+ * Apply one input to the camera, the object drag, the hover state or the
+ * objects. Runs in the render loop between simulation steps so replays match
+ * recordings
+ */
+fn apply_input(
+    event: InputEvent,
+    scene: &RefCell<Scene>,
+    camera: &RefCell<OrbitCamera>,
+    object_drag: &RefCell<Option<ObjectDrag>>,
+    key_hold: &Cell<Option<usize>>,
+    pointer: &Cell<Option<(i32, i32)>>,
+    mut time_scale: Signal<f64>,
+) {
+    // Inverse view-projection used to unproject the cursor for object dragging
+    let inverse_view_proj = || {
+        let view_proj = camera.borrow().view_projection(1.0);
+        math::invert(&view_proj).unwrap_or_else(math::identity)
+    };
+    let size = CANVAS_SIZE as f64;
+    match event {
+        InputEvent::PointerDown { x, y } => {
+            // Grab the object under the cursor (CPU raycast) if there is one, otherwise orbit
+            let (ndc_x, ndc_y) = math::pixel_to_ndc(x, y, size, size);
+            let ray = Ray::from_ndc(&inverse_view_proj(), ndc_x, ndc_y);
+            let hit = raycast::cast_scene(&ray, &scene.borrow());
+            let movable =
+                |object: usize| scene.borrow().objects[object].body != Some(BodyKind::Fixed);
+            if let Some(hit) = hit.filter(|hit| movable(hit.object)) {
+                let position = scene.borrow().objects[hit.object].transform.translation;
+                let forward = camera.borrow().forward();
+                *object_drag.borrow_mut() =
+                    Some(ObjectDrag::begin(hit.object, position, hit.point, forward));
+                return;
+            }
+            camera.borrow_mut().begin_drag(x, y);
+        }
+        InputEvent::PointerMove { x, y } => {
+            pointer.set(Some((x as i32, y as i32)));
+
+            if let Some(drag) = object_drag.borrow().as_ref() {
+                let ndc = math::pixel_to_ndc(x, y, size, size);
+                if let Some(position) = drag.update(&inverse_view_proj(), ndc) {
+                    if let Some(object) = scene.borrow_mut().objects.get_mut(drag.object) {
+                        object.transform.translation = position;
+                    }
+                }
+                return;
+            }
+
+            camera.borrow_mut().drag_to(x, y);
+        }
+        InputEvent::PointerUp => {
+            object_drag.borrow_mut().take();
+            camera.borrow_mut().end_drag();
+        }
+        InputEvent::PointerLeave => {
+            pointer.set(None);
+            object_drag.borrow_mut().take();
+            camera.borrow_mut().end_drag();
+        }
+        InputEvent::Wheel { delta } => camera.borrow_mut().zoom(delta),
+        InputEvent::Orbit { dx, dy } => camera.borrow_mut().rotate(dx, dy),
+        InputEvent::Nudge {
+            object,
+            right,
+            forward,
+        } => {
+            // Along the ground, so up moves away from the camera whatever its pitch
+            let view = camera.borrow().forward();
+            let ahead = math::normalize([view[0], 0.0, view[2]]);
+            let side = math::cross(ahead, [0.0, 1.0, 0.0]);
+            let offset = math::add(math::scale(side, right), math::scale(ahead, forward));
+            if let Some(object) = scene.borrow_mut().objects.get_mut(object) {
+                object.transform.translation = math::add(object.transform.translation, offset);
+            }
+        }
+        InputEvent::Hold(object) => key_hold.set(object),
+        InputEvent::Visible { object, visible } => {
+            if let Some(object) = scene.borrow_mut().objects.get_mut(object) {
+                object.visible = visible;
+            }
+        }
+        InputEvent::Tint {
+            object,
+            rgb: [r, g, b],
+        } => {
+            if let Some(object) = scene.borrow_mut().objects.get_mut(object) {
+                let [_, _, _, alpha] = object.tint;
+                object.tint = [r, g, b, alpha];
+            }
+        }
+        InputEvent::TimeScale(scale) => time_scale.set(scale),
+    }
+}
@@ -0,0 +1,198 @@
+use dioxus::prelude::*;
+
+// Segments around the sphere; it gets half as many rings
+const DETAILS: [(u16, &str); 3] = [(8, "Low"), (32, "Medium"), (128, "High")];
+
+/**
+ * A sphere generated by a server function and uploaded into WebGL buffers
+ * on the client: the fullstack data path into the GPU. The select asks the
+ * server again at another detail level and the new mesh replaces the old
+ */
+#[component]
+pub fn ServerMeshDemo() -> Element {
+    let mut canvas_mounted = use_signal(|| false);
+    let mut detail = use_signal(|| DETAILS[1].0);
+    #[cfg(feature = "fullstack")]
+    let status = served::use_server_mesh(canvas_mounted, detail);
+    #[cfg(not(feature = "fullstack"))]
+    let status = use_signal(|| {
+        "Built without server functions: run `dx serve --fullstack --features fullstack`"
+            .to_string()
+    });
+
+    rsx! {
+        div {
+            style: "display: flex; flex-direction: column; justify-content: center; align-items: center; height: 100vh; background: #f0f0f0;",
+            canvas {
+                id: "server-mesh-canvas",
+                width: "480",
+                height: "480",
+                style: "border: 2px solid #333; background: #222;",
+                onmounted: move |_| canvas_mounted.set(true),
+            }
+            div {
+                style: "display: flex; gap: 16px; align-items: center; margin-top: 10px; color: #333; font-family: monospace;",
+                label {
+                    "Detail "
+                    select {
+                        onchange: move |evt| {
+                            if let Some(&(segments, _)) = DETAILS.iter().find(|(_, name)| *name == evt.value()) {
+                                detail.set(segments);
+                            }
+                        },
+                        for (segments, name) in DETAILS {
+                            option { value: name, selected: detail() == segments, "{name}" }
+                        }
+                    }
+                }
+                span { "{status}" }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "fullstack")]
+mod served {
+    use dioxus::prelude::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use web_sys::WebGl2RenderingContext;
+
+    use crate::camera::OrbitCamera;
+    use crate::gl_errors;
+    use crate::gl_util::{self, GlResource};
+    use crate::hooks::use_frame_loop;
+    use crate::math;
+    use crate::mesh::{GpuMesh, MeshData};
+    use crate::shaders;
+
+    const CANVAS_SIZE: u32 = 480;
+
+    /**
+     * Sphere with `detail` segments and half as many rings. Runs on the
+     * server; the client gets its positions, colors and indices back
+     */
+    #[server]
+    async fn sphere_mesh(detail: u16) -> Result<MeshData, ServerFnError> {
+        // Keeps the vertex count within 16-bit indices whatever the client asks for
+        let detail = detail.clamp(3, 256);
+        Ok(MeshData::sphere(detail, detail / 2))
+    }
+
+    /**
+     * Fetch the sphere at `detail` and draw it once the canvas is mounted;
+     * the signal describes the last response
+     */
+    pub fn use_server_mesh(canvas_mounted: Signal<bool>, detail: Signal<u16>) -> Signal<String> {
+        let mut status = use_signal(|| "Asking the server...".to_string());
+        // Mesh data from the server waiting to be uploaded by the next frame
+        let pending = use_hook(|| Rc::new(RefCell::new(None::<MeshData>)));
+        let camera = use_hook(|| {
+            let mut camera = OrbitCamera::default();
+            camera.distance = 2.0;
+            Rc::new(RefCell::new(camera))
+        });
+        let frame_loop = use_frame_loop();
+
+        let response = use_resource(move || sphere_mesh(detail()));
+
+        use_effect({
+            let pending = pending.clone();
+            move || match &*response.read() {
+                None => status.set("Asking the server...".to_string()),
+                Some(Ok(data)) => {
+                    status.set(format!(
+                        "{} vertices, {} triangles from the server",
+                        data.positions.len() / 3,
+                        data.indices.len() / 3
+                    ));
+                    pending.replace(Some(data.clone()));
+                }
+                Some(Err(err)) => {
+                    gl_errors::report("server mesh: fetch", err.to_string());
+                    status.set(format!("Server function failed: {err}"));
+                }
+            }
+        });
+
+        use_effect(move || {
+            if !canvas_mounted() {
+                return;
+            }
+
+            let pending = pending.clone();
+            let camera = camera.clone();
+            let frame_loop = frame_loop.clone();
+            spawn(async move {
+                gloo_timers::future::TimeoutFuture::new(50).await;
+
+                let canvas = match gl_util::canvas_by_id("server-mesh-canvas") {
+                    Ok(canvas) => canvas,
+                    Err(err) => {
+                        gl_errors::report("server mesh: setup", err);
+                        return;
+                    }
+                };
+
+                let gl = match gl_util::webgl2_context(&canvas) {
+                    Ok(gl) => gl,
+                    Err(err) => {
+                        gl_errors::report("server mesh: setup", err);
+                        return;
+                    }
+                };
+
+                canvas.set_width(CANVAS_SIZE);
+                canvas.set_height(CANVAS_SIZE);
+
+                let program = match gl_util::create_program(
+                    &gl,
+                    shaders::DEMOS_SERVER_MESH_VERT,
+                    shaders::DEMOS_SERVER_MESH_FRAG,
+                ) {
+                    Ok(program) => program,
+                    Err(err) => {
+                        gl_errors::report("server mesh: program setup", err);
+                        return;
+                    }
+                };
+                let mvp_loc = gl.get_uniform_location(&program, "modelViewProjection");
+
+                let mut mesh = None::<GpuMesh>;
+                frame_loop.start(move |time: f64, _| {
+                    // A new response replaces the mesh the previous one uploaded
+                    if let Some(data) = pending.take() {
+                        if let Some(old) = mesh.take() {
+                            old.delete(&gl);
+                        }
+                        mesh = GpuMesh::upload(&gl, &data);
+                        if mesh.is_none() {
+                            gl_errors::report("server mesh: upload", "Unable to upload the mesh");
+                        }
+                    }
+
+                    let size = CANVAS_SIZE as i32;
+                    gl.viewport(0, 0, size, size);
+                    gl.enable(WebGl2RenderingContext::DEPTH_TEST);
+                    gl.enable(WebGl2RenderingContext::CULL_FACE);
+                    gl.clear_color(0.1, 0.1, 0.1, 1.0);
+                    gl.clear(
+                        WebGl2RenderingContext::COLOR_BUFFER_BIT
+                            | WebGl2RenderingContext::DEPTH_BUFFER_BIT,
+                    );
+                    let Some(mesh) = &mesh else {
+                        return;
+                    };
+                    let model = math::rotation_matrix_y(time as f32 * 0.5);
+                    let mvp = math::multiply(&camera.borrow().view_projection(1.0), &model);
+                    gl.use_program(Some(&program));
+                    gl.uniform_matrix4fv_with_f32_array(mvp_loc.as_ref(), false, &mvp);
+                    mesh.draw(&gl);
+                    gl_errors::check(&gl, "server mesh: draw");
+                });
+            });
+        });
+
+        status
+    }
+}
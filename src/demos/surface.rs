@@ -0,0 +1,457 @@
+use dioxus::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use web_sys::WebGl2RenderingContext;
+
+use crate::camera::OrbitCamera;
+use crate::download;
+use crate::expr::{self, Expr, Vars};
+use crate::gl_errors;
+use crate::gl_util;
+use crate::hooks::{use_frame_loop, use_pointer_drag};
+use crate::mesh::MeshData;
+use crate::obj;
+use crate::shaders;
+
+const CANVAS_SIZE: u32 = 480;
+// The function is plotted over x, y in [-EXTENT, EXTENT]
+const EXTENT: f32 = 3.0;
+// Heights beyond this are clamped so poles don't swallow the view
+const HEIGHT_LIMIT: f32 = 4.0;
+
+// (name, expression)
+const PRESETS: [(&str, &str); 4] = [
+    ("Ripple", "b * sin(a * sqrt(x^2 + y^2) - 2 * t)"),
+    ("Waves", "b * sin(a * x + t) * cos(a * y)"),
+    ("Saddle", "b * (x^2 - y^2) / (2 * a)"),
+    ("Gaussian", "2 * b * exp(-a * (x^2 + y^2) / 2)"),
+];
+
+/**
+ * Inputs the surface was last built from; any change triggers a rebuild
+ */
+#[derive(Clone, Debug, PartialEq)]
+struct SurfaceInputs {
+    expr: Expr,
+    a: f32,
+    b: f32,
+    resolution: usize,
+}
+
+#[component]
+pub fn SurfaceDemo() -> Element {
+    let mut canvas_mounted = use_signal(|| false);
+    let mut source = use_signal(|| PRESETS[0].1.to_string());
+    let mut a = use_signal(|| 1.5f32);
+    let mut b = use_signal(|| 1.0f32);
+    // Vertices along each side of the grid
+    let mut resolution = use_signal(|| 64usize);
+    let parsed = use_memo(move || expr::parse(&source()));
+    let camera = use_hook(|| {
+        let mut camera = OrbitCamera::default();
+        camera.distance = 11.0;
+        camera.pitch = 0.6;
+        Rc::new(RefCell::new(camera))
+    });
+    // Time the surface was last evaluated at, so an export matches the screen
+    let surface_time = use_hook(|| Rc::new(Cell::new(0.0f32)));
+    let frame_loop = use_frame_loop();
+
+    use_effect({
+        let camera = camera.clone();
+        let surface_time = surface_time.clone();
+        move || {
+            if !canvas_mounted() {
+                return;
+            }
+
+            let camera = camera.clone();
+            let surface_time = surface_time.clone();
+            let frame_loop = frame_loop.clone();
+            spawn(async move {
+                gloo_timers::future::TimeoutFuture::new(50).await;
+
+                let canvas = match gl_util::canvas_by_id("surface-canvas") {
+                    Ok(canvas) => canvas,
+                    Err(err) => {
+                        gl_errors::report("surface: setup", err);
+                        return;
+                    }
+                };
+
+                let gl = match gl_util::webgl2_context(&canvas) {
+                    Ok(gl) => gl,
+                    Err(err) => {
+                        gl_errors::report("surface: setup", err);
+                        return;
+                    }
+                };
+
+                canvas.set_width(CANVAS_SIZE);
+                canvas.set_height(CANVAS_SIZE);
+
+                let program = match gl_util::create_program(
+                    &gl,
+                    shaders::DEMOS_SURFACE_VERT,
+                    shaders::DEMOS_SURFACE_FRAG,
+                ) {
+                    Ok(program) => program,
+                    Err(err) => {
+                        gl_errors::report("surface: program setup", err);
+                        return;
+                    }
+                };
+                let view_proj_loc = gl.get_uniform_location(&program, "viewProjection");
+                let height_range_loc = gl.get_uniform_location(&program, "heightRange");
+
+                let (Some(vao), Some(position_buffer), Some(index_buffer)) = (
+                    gl.create_vertex_array(),
+                    gl.create_buffer(),
+                    gl.create_buffer(),
+                ) else {
+                    gl_errors::report("surface: setup", "Unable to create the surface buffers");
+                    return;
+                };
+                gl.bind_vertex_array(Some(&vao));
+                gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&position_buffer));
+                gl.enable_vertex_attrib_array(0);
+                gl.vertex_attrib_pointer_with_i32(0, 3, WebGl2RenderingContext::FLOAT, false, 0, 0);
+                gl.bind_buffer(
+                    WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER,
+                    Some(&index_buffer),
+                );
+                gl.bind_vertex_array(None);
+
+                let mut built = None::<SurfaceInputs>;
+                let mut positions = Vec::<f32>::new();
+                let mut index_count = 0;
+                let mut height_range = (0.0f32, 0.0f32);
+
+                frame_loop.start(move |time: f64, _| {
+                    // Parse errors keep the last good surface on screen
+                    let inputs = match &*parsed.peek() {
+                        Ok(expr) => Some(SurfaceInputs {
+                            expr: expr.clone(),
+                            a: *a.peek(),
+                            b: *b.peek(),
+                            resolution: *resolution.peek(),
+                        }),
+                        Err(_) => None,
+                    };
+                    if let Some(inputs) = inputs {
+                        let animated = inputs.expr.uses_time();
+                        if animated || built.as_ref() != Some(&inputs) {
+                            let resized = built
+                                .as_ref()
+                                .is_none_or(|built| built.resolution != inputs.resolution);
+                            if resized {
+                                let indices = grid_indices(inputs.resolution);
+                                index_count = indices.len() as i32;
+                                gl.bind_buffer(
+                                    WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER,
+                                    Some(&index_buffer),
+                                );
+                                unsafe {
+                                    let array = js_sys::Uint16Array::view(&indices);
+                                    gl.buffer_data_with_array_buffer_view(
+                                        WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER,
+                                        &array,
+                                        WebGl2RenderingContext::STATIC_DRAW,
+                                    );
+                                }
+                            }
+                            let time = time as f32;
+                            height_range = evaluate_grid(&inputs, time, &mut positions);
+                            surface_time.set(time);
+                            gl.bind_buffer(
+                                WebGl2RenderingContext::ARRAY_BUFFER,
+                                Some(&position_buffer),
+                            );
+                            unsafe {
+                                let array = js_sys::Float32Array::view(&positions);
+                                gl.buffer_data_with_array_buffer_view(
+                                    WebGl2RenderingContext::ARRAY_BUFFER,
+                                    &array,
+                                    WebGl2RenderingContext::DYNAMIC_DRAW,
+                                );
+                            }
+                            built = Some(inputs);
+                        }
+                    }
+
+                    gl.viewport(0, 0, CANVAS_SIZE as i32, CANVAS_SIZE as i32);
+                    gl.enable(WebGl2RenderingContext::DEPTH_TEST);
+                    gl.clear_color(0.1, 0.1, 0.1, 1.0);
+                    gl.clear(
+                        WebGl2RenderingContext::COLOR_BUFFER_BIT
+                            | WebGl2RenderingContext::DEPTH_BUFFER_BIT,
+                    );
+
+                    let view_proj = camera.borrow().view_projection(1.0);
+                    gl.use_program(Some(&program));
+                    gl.uniform_matrix4fv_with_f32_array(view_proj_loc.as_ref(), false, &view_proj);
+                    gl.uniform2f(height_range_loc.as_ref(), height_range.0, height_range.1);
+                    gl.bind_vertex_array(Some(&vao));
+                    gl.draw_elements_with_i32(
+                        WebGl2RenderingContext::TRIANGLES,
+                        index_count,
+                        WebGl2RenderingContext::UNSIGNED_SHORT,
+                        0,
+                    );
+                    gl.bind_vertex_array(None);
+                });
+            });
+        }
+    });
+
+    // Pointer capture keeps the orbit going when the pointer leaves the canvas
+    let mut pointer_drag = use_pointer_drag();
+    let on_pointer_move = {
+        let camera = camera.clone();
+        move |evt: PointerEvent| {
+            if let Some(drag) = pointer_drag.pointer_move(&evt) {
+                camera.borrow_mut().rotate(drag.delta.0, drag.delta.1);
+            }
+        }
+    };
+    let cursor = if pointer_drag.drag().is_some() {
+        "grabbing"
+    } else {
+        "grab"
+    };
+    let on_wheel = {
+        let camera = camera.clone();
+        move |evt: WheelEvent| {
+            evt.prevent_default();
+            camera.borrow_mut().zoom(evt.delta().strip_units().y);
+        }
+    };
+
+    let on_export = move |_| {
+        let Ok(expr) = parsed() else {
+            return;
+        };
+        let inputs = SurfaceInputs {
+            expr,
+            a: a(),
+            b: b(),
+            resolution: resolution(),
+        };
+        let mut positions = Vec::new();
+        let (low, high) = evaluate_grid(&inputs, surface_time.get(), &mut positions);
+        // Bake the on-screen height colormap into the vertex colors
+        let colors = positions
+            .chunks_exact(3)
+            .flat_map(|p| colormap((p[1] - low) / (high - low).max(1e-4)))
+            .collect();
+        let mesh = MeshData {
+            positions,
+            colors,
+            indices: grid_indices(inputs.resolution),
+        };
+        let obj = obj::write(&mesh, "surface");
+        if let Err(err) = download::save_file(obj.as_bytes(), "surface.obj", "model/obj") {
+            tracing::error!(target: "ui", "Saving the OBJ failed: {:?}", err);
+        }
+    };
+
+    let parse_error = parsed.read().as_ref().err().cloned();
+    let can_export = parse_error.is_none();
+
+    rsx! {
+        div {
+            style: "display: flex; flex-direction: column; justify-content: center; align-items: center; height: 100vh; background: #f0f0f0;",
+            canvas {
+                id: "surface-canvas",
+                width: "{CANVAS_SIZE}",
+                height: "{CANVAS_SIZE}",
+                style: "border: 2px solid #333; background: #222; cursor: {cursor}; touch-action: none;",
+                onmounted: move |_| {
+                    canvas_mounted.set(true);
+                },
+                onpointerdown: move |evt| {
+                    pointer_drag.pointer_down(&evt);
+                },
+                onpointermove: on_pointer_move,
+                onpointerup: move |evt| {
+                    pointer_drag.pointer_up(&evt);
+                },
+                onpointercancel: move |evt| {
+                    pointer_drag.pointer_up(&evt);
+                },
+                onwheel: on_wheel,
+            }
+            div {
+                style: "display: flex; gap: 8px; align-items: center; margin-top: 10px; color: #333; font-family: monospace;",
+                label {
+                    "Preset "
+                    select {
+                        onchange: move |evt| {
+                            if let Some((_, expression)) = PRESETS.iter().find(|(name, _)| *name == evt.value()) {
+                                source.set(expression.to_string());
+                            }
+                        },
+                        for (name, expression) in PRESETS {
+                            option { value: name, selected: source() == expression, "{name}" }
+                        }
+                    }
+                }
+                label {
+                    "z = "
+                    input {
+                        style: "width: 300px; font-family: monospace;",
+                        value: "{source}",
+                        oninput: move |evt| source.set(evt.value()),
+                    }
+                }
+            }
+            p {
+                style: "color: #a00; font-family: monospace; min-height: 1em;",
+                if let Some(err) = parse_error {
+                    "{err}"
+                }
+            }
+            div {
+                style: "display: flex; gap: 16px; align-items: center; color: #333; font-family: monospace;",
+                label {
+                    "a "
+                    input {
+                        r#type: "range",
+                        min: "0.1",
+                        max: "4",
+                        step: "0.1",
+                        value: "{a}",
+                        oninput: move |evt| {
+                            if let Ok(value) = evt.value().parse() {
+                                a.set(value);
+                            }
+                        },
+                    }
+                    " {a:.1}"
+                }
+                label {
+                    "b "
+                    input {
+                        r#type: "range",
+                        min: "0",
+                        max: "2",
+                        step: "0.05",
+                        value: "{b}",
+                        oninput: move |evt| {
+                            if let Ok(value) = evt.value().parse() {
+                                b.set(value);
+                            }
+                        },
+                    }
+                    " {b:.2}"
+                }
+                label {
+                    "Resolution "
+                    input {
+                        r#type: "range",
+                        min: "8",
+                        max: "128",
+                        step: "8",
+                        value: "{resolution}",
+                        oninput: move |evt| {
+                            if let Ok(value) = evt.value().parse() {
+                                resolution.set(value);
+                            }
+                        },
+                    }
+                    " {resolution}"
+                }
+                button { disabled: !can_export, onclick: on_export, "Export OBJ" }
+            }
+            p {
+                style: "color: #666; font-family: monospace; font-size: small;",
+                "Use x, y, t (seconds), a, b, pi, e, + - * / ^ and sin cos tan sqrt abs exp ln"
+            }
+        }
+    }
+}
+
+/**
+ * Sample the function on a `resolution` x `resolution` grid into xyz
+ * positions (function z becomes world Y), returning the height range
+ */
+fn evaluate_grid(inputs: &SurfaceInputs, time: f32, positions: &mut Vec<f32>) -> (f32, f32) {
+    let n = inputs.resolution;
+    positions.clear();
+    let mut range = (f32::INFINITY, f32::NEG_INFINITY);
+    for row in 0..n {
+        for column in 0..n {
+            let x = (column as f32 / (n - 1) as f32 * 2.0 - 1.0) * EXTENT;
+            let y = (row as f32 / (n - 1) as f32 * 2.0 - 1.0) * EXTENT;
+            let vars = Vars {
+                x,
+                y,
+                t: time,
+                a: inputs.a,
+                b: inputs.b,
+            };
+            let z = inputs.expr.eval(&vars);
+            // Holes (NaN, division by zero) are flattened rather than breaking the mesh
+            let z = if z.is_finite() {
+                z.clamp(-HEIGHT_LIMIT, HEIGHT_LIMIT)
+            } else {
+                0.0
+            };
+            range = (range.0.min(z), range.1.max(z));
+            positions.extend([x, z, y]);
+        }
+    }
+    range
+}
+
+/**
+ * The fragment shader's height colormap, for baking into exported meshes
+ */
+fn colormap(t: f32) -> [f32; 3] {
+    let t = t.clamp(0.0, 1.0);
+    [
+        1.5 * t - 0.5,
+        1.0 - (2.0 * t - 1.0).abs() * 0.8,
+        1.0 - 1.5 * t,
+    ]
+    .map(|c| c.clamp(0.0, 1.0))
+}
+
+fn grid_indices(n: usize) -> Vec<u16> {
+    let mut indices = Vec::with_capacity((n - 1) * (n - 1) * 6);
+    for row in 0..n - 1 {
+        for column in 0..n - 1 {
+            let i = (row * n + column) as u16;
+            let below = i + n as u16;
+            indices.extend([i, below, i + 1, i + 1, below, below + 1]);
+        }
+    }
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_indices_cover_every_cell() {
+        let n = 4;
+        let indices = grid_indices(n);
+        assert_eq!(indices.len(), (n - 1) * (n - 1) * 6);
+        assert!(indices.iter().all(|&index| (index as usize) < n * n));
+        // First cell: two triangles sharing its diagonal
+        assert_eq!(&indices[..6], &[0, 4, 1, 1, 4, 5]);
+    }
+
+    #[test]
+    fn colormap_runs_blue_to_red() {
+        let [r, g, b] = colormap(0.0);
+        assert_eq!((r, b), (0.0, 1.0));
+        assert!((g - 0.2).abs() < 1e-6);
+        let [r, g, b] = colormap(1.0);
+        assert_eq!((r, b), (1.0, 0.0));
+        assert!((g - 0.2).abs() < 1e-6);
+        // Out of range heights are clamped
+        assert_eq!(colormap(-3.0), colormap(0.0));
+    }
+}
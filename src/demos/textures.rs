@@ -0,0 +1,399 @@
+use dioxus::prelude::*;
+use std::cell::Cell;
+use std::rc::Rc;
+use web_sys::WebGl2RenderingContext;
+
+use crate::assets::{self, AssetStatus, Assets, Handle};
+use crate::atlas::TextureAtlas;
+use crate::components::LoadingProgress;
+use crate::gl_errors;
+use crate::gl_util;
+use crate::hooks::use_frame_loop;
+use crate::math;
+use crate::shaders;
+use crate::sprite_sheet::SpriteSheet;
+use crate::sprites::{Sprite, SpriteBatch};
+use crate::texture::{Texture, TextureArray};
+
+const CANVAS_SIZE: u32 = 480;
+// Small sprites drawn from the atlas in a single call
+const SWARM_SIZE: usize = 150;
+const PUFF_COUNT: usize = 6;
+
+// (name, url, critical)
+const TEXTURES: [(&str, Asset, bool); 4] = [
+    ("checker.png", asset!("/assets/textures/checker.png"), true),
+    ("bricks.png", asset!("/assets/textures/bricks.png"), false),
+    ("rings.png", asset!("/assets/textures/rings.png"), false),
+    ("puff.png", asset!("/assets/textures/puff.png"), false),
+];
+// The first few textures are tiles shown whole; the last one is a sprite sheet
+const TILE_COUNT: usize = 3;
+const PUFF_TEXTURE: usize = 3;
+const PUFF_SHEET: SpriteSheet = SpriteSheet {
+    columns: 4,
+    rows: 2,
+    frame_count: 8,
+    frames_per_second: 10.0,
+};
+
+/**
+ * How the tiles get their textures
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TileBinding {
+    // One texture bind and draw per tile
+    Separate,
+    // One texture array bind, then a layer uniform and draw per tile
+    LayerPerDraw,
+    // One texture array bind and a single instanced draw
+    LayerPerInstance,
+}
+
+const BINDINGS: [(TileBinding, &str); 3] = [
+    (TileBinding::Separate, "Separate textures"),
+    (TileBinding::LayerPerDraw, "Array, layer per draw"),
+    (TileBinding::LayerPerInstance, "Array, layer per instance"),
+];
+
+// rect.xyzw + angle + layer per tile instance
+const FLOATS_PER_TILE: usize = 6;
+
+#[component]
+pub fn TexturesDemo() -> Element {
+    let mut canvas_mounted = use_signal(|| false);
+    let mut binding = use_signal(|| TileBinding::LayerPerInstance);
+    let assets = use_context::<Assets>();
+    // Shared with any other component that loads the same URLs
+    let handles = use_hook({
+        let assets = assets.clone();
+        move || TEXTURES.map(|(_, url, _)| assets.load_texture(&url.to_string()))
+    });
+    let started = use_hook(|| Rc::new(Cell::new(false)));
+    let frame_loop = use_frame_loop();
+
+    let statuses = texture_statuses(&assets, &handles);
+    let ready = assets::critical_ready(&statuses);
+
+    // The render loop waits for the critical textures; the others start as placeholders
+    use_effect(move || {
+        let ready = assets::critical_ready(&texture_statuses(&assets, &handles));
+        if !canvas_mounted() || !ready || started.get() {
+            return;
+        }
+        started.set(true);
+
+        let assets = assets.clone();
+        let frame_loop = frame_loop.clone();
+        spawn(async move {
+            gloo_timers::future::TimeoutFuture::new(50).await;
+
+            let canvas = match gl_util::canvas_by_id("textures-canvas") {
+                Ok(canvas) => canvas,
+                Err(err) => {
+                    gl_errors::report("textures: setup", err);
+                    return;
+                }
+            };
+
+            let gl = match gl_util::webgl2_context(&canvas) {
+                Ok(gl) => gl,
+                Err(err) => {
+                    gl_errors::report("textures: setup", err);
+                    return;
+                }
+            };
+
+            canvas.set_width(CANVAS_SIZE);
+            canvas.set_height(CANVAS_SIZE);
+
+            let program = match gl_util::create_program(
+                &gl,
+                shaders::DEMOS_TEXTURES_VERT,
+                shaders::DEMOS_TEXTURES_FRAG,
+            ) {
+                Ok(program) => program,
+                Err(err) => {
+                    gl_errors::report("textures: program setup", err);
+                    return;
+                }
+            };
+            let rect_loc = gl.get_uniform_location(&program, "rect");
+            let angle_loc = gl.get_uniform_location(&program, "angle");
+            let image_loc = gl.get_uniform_location(&program, "image");
+            // gl_VertexID only, but a bound VAO keeps every implementation happy
+            let Some(vao) = gl.create_vertex_array() else {
+                gl_errors::report("textures: setup", "Unable to create vertex array");
+                return;
+            };
+
+            let array_program = match gl_util::create_program(
+                &gl,
+                shaders::DEMOS_TEXTURES_ARRAY_VERT,
+                shaders::DEMOS_TEXTURES_ARRAY_FRAG,
+            ) {
+                Ok(program) => program,
+                Err(err) => {
+                    gl_errors::report("textures: array program setup", err);
+                    return;
+                }
+            };
+            let per_instance_loc = gl.get_uniform_location(&array_program, "perInstance");
+            let array_rect_loc = gl.get_uniform_location(&array_program, "rect");
+            let array_angle_loc = gl.get_uniform_location(&array_program, "angle");
+            let layer_loc = gl.get_uniform_location(&array_program, "layer");
+            let images_loc = gl.get_uniform_location(&array_program, "images");
+            let Some((instance_vao, instance_buffer)) = create_tile_instances(&gl) else {
+                gl_errors::report("textures: setup", "Unable to create the tile instances");
+                return;
+            };
+
+            let Some(placeholder) = Texture::placeholder(&gl) else {
+                gl_errors::report(
+                    "textures: setup",
+                    "Unable to create the placeholder texture",
+                );
+                return;
+            };
+            let mut sprite_batch = match SpriteBatch::new(&gl) {
+                Ok(batch) => batch,
+                Err(err) => {
+                    gl_errors::report("textures: sprite batch setup", err);
+                    return;
+                }
+            };
+            // Built once every image has been decoded
+            let mut atlas = None::<TextureAtlas>;
+            // Likewise the tile layers
+            let mut tile_array = None::<TextureArray>;
+            // Screen-space pixels, y down
+            let pixels =
+                math::orthographic(0.0, CANVAS_SIZE as f32, CANVAS_SIZE as f32, 0.0, -1.0, 1.0);
+
+            tracing::info!(target: "renderer", "Textures ready");
+
+            frame_loop.start(move |time: f64, _| {
+                let time = time as f32;
+                gl.viewport(0, 0, CANVAS_SIZE as i32, CANVAS_SIZE as i32);
+                gl.clear_color(0.1, 0.1, 0.1, 1.0);
+                gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+
+                if tile_array.is_none() {
+                    let images = handles[..TILE_COUNT]
+                        .iter()
+                        .map(|&handle| assets.image(handle))
+                        .collect::<Option<Vec<_>>>();
+                    tile_array = images.and_then(|images| TextureArray::from_images(&gl, &images));
+                }
+                // Separate textures until every layer of the array is in
+                let mode = tile_array
+                    .as_ref()
+                    .map(|tile_array| (*binding.peek(), tile_array));
+                match mode {
+                    None | Some((TileBinding::Separate, _)) => {
+                        gl.use_program(Some(&program));
+                        gl.bind_vertex_array(Some(&vao));
+                        gl.uniform1i(image_loc.as_ref(), 0);
+                        for (i, &handle) in handles[..TILE_COUNT].iter().enumerate() {
+                            // Placeholder until the texture has finished loading
+                            let texture = assets
+                                .texture(&gl, handle)
+                                .unwrap_or_else(|| placeholder.clone());
+                            let (rect, angle) = tile_placement(i, time);
+                            gl.uniform4fv_with_f32_array(rect_loc.as_ref(), &rect);
+                            gl.uniform1f(angle_loc.as_ref(), angle);
+                            texture.bind(&gl, 0);
+                            gl.draw_arrays(WebGl2RenderingContext::TRIANGLE_STRIP, 0, 4);
+                        }
+                    }
+                    Some((TileBinding::LayerPerDraw, tile_array)) => {
+                        gl.use_program(Some(&array_program));
+                        gl.bind_vertex_array(Some(&vao));
+                        gl.uniform1i(images_loc.as_ref(), 0);
+                        gl.uniform1i(per_instance_loc.as_ref(), 0);
+                        tile_array.bind(&gl, 0);
+                        for i in 0..TILE_COUNT {
+                            let (rect, angle) = tile_placement(i, time);
+                            gl.uniform4fv_with_f32_array(array_rect_loc.as_ref(), &rect);
+                            gl.uniform1f(array_angle_loc.as_ref(), angle);
+                            gl.uniform1f(layer_loc.as_ref(), i as f32);
+                            gl.draw_arrays(WebGl2RenderingContext::TRIANGLE_STRIP, 0, 4);
+                        }
+                    }
+                    Some((TileBinding::LayerPerInstance, tile_array)) => {
+                        let mut data = Vec::with_capacity(TILE_COUNT * FLOATS_PER_TILE);
+                        for i in 0..TILE_COUNT {
+                            let (rect, angle) = tile_placement(i, time);
+                            data.extend(rect);
+                            data.extend([angle, i as f32]);
+                        }
+                        gl.bind_buffer(
+                            WebGl2RenderingContext::ARRAY_BUFFER,
+                            Some(&instance_buffer),
+                        );
+                        unsafe {
+                            let array = js_sys::Float32Array::view(&data);
+                            gl.buffer_data_with_array_buffer_view(
+                                WebGl2RenderingContext::ARRAY_BUFFER,
+                                &array,
+                                WebGl2RenderingContext::DYNAMIC_DRAW,
+                            );
+                        }
+                        gl.use_program(Some(&array_program));
+                        gl.bind_vertex_array(Some(&instance_vao));
+                        gl.uniform1i(images_loc.as_ref(), 0);
+                        gl.uniform1i(per_instance_loc.as_ref(), 1);
+                        tile_array.bind(&gl, 0);
+                        gl.draw_arrays_instanced(
+                            WebGl2RenderingContext::TRIANGLE_STRIP,
+                            0,
+                            4,
+                            TILE_COUNT as i32,
+                        );
+                    }
+                }
+                gl.bind_vertex_array(None);
+
+                if atlas.is_none() {
+                    let images = handles
+                        .iter()
+                        .map(|&handle| assets.image(handle))
+                        .collect::<Option<Vec<_>>>();
+                    atlas = images.and_then(|images| TextureAtlas::build(&gl, &images));
+                }
+                if let Some(atlas) = &atlas {
+                    let size = CANVAS_SIZE as f32;
+                    for i in 0..SWARM_SIZE {
+                        let seed = i as f32;
+                        let speed = 20.0 + hash(seed * 3.1) * 60.0;
+                        let x = (hash(seed) * size + time * speed) % (size + 40.0) - 20.0;
+                        let y = 290.0 + hash(seed * 7.3) * 170.0 + (time * 2.0 + seed).sin() * 8.0;
+                        sprite_batch.push(&Sprite {
+                            center: [x, y],
+                            size: [20.0, 20.0],
+                            rotation: time * (hash(seed * 1.7) - 0.5) * 4.0,
+                            uv: atlas.region(i % TILE_COUNT),
+                            color: [1.0; 4],
+                        });
+                    }
+                    // Flipbook frames come from the sheet's own region of the atlas
+                    for i in 0..PUFF_COUNT {
+                        let frame = PUFF_SHEET.frame_at(time + i as f32 * 0.25);
+                        sprite_batch.push(&Sprite {
+                            center: [(i as f32 + 0.5) / PUFF_COUNT as f32 * size, 250.0],
+                            size: [64.0, 64.0],
+                            rotation: 0.0,
+                            uv: atlas
+                                .region(PUFF_TEXTURE)
+                                .sub_rect(PUFF_SHEET.frame_uv(frame)),
+                            color: [1.0; 4],
+                        });
+                    }
+                    sprite_batch.flush(&gl, &pixels, atlas.texture());
+                }
+            });
+        });
+    });
+
+    rsx! {
+        div {
+            style: "display: flex; flex-direction: column; justify-content: center; align-items: center; height: 100vh; background: #f0f0f0;",
+            canvas {
+                id: "textures-canvas",
+                width: "{CANVAS_SIZE}",
+                height: "{CANVAS_SIZE}",
+                style: "border: 2px solid #333; background: #222;",
+                // Hidden (but still mounted) until the critical textures are in
+                visibility: if ready { "visible" } else { "hidden" },
+                onmounted: move |_| {
+                    canvas_mounted.set(true);
+                },
+            }
+            div {
+                style: "display: flex; gap: 16px; align-items: center; margin-top: 10px; color: #333; font-family: monospace;",
+                label {
+                    "Tiles "
+                    select {
+                        onchange: move |evt| {
+                            if let Some(&(kind, _)) = BINDINGS.iter().find(|(_, name)| *name == evt.value()) {
+                                binding.set(kind);
+                            }
+                        },
+                        for (kind, name) in BINDINGS {
+                            option { value: name, selected: binding() == kind, "{name}" }
+                        }
+                    }
+                }
+                span { "{binding_cost(binding())}" }
+            }
+            LoadingProgress { assets: statuses }
+        }
+    }
+}
+
+/**
+ * NDC rect (center, half size) and rotation of tile `index` at `time` seconds
+ */
+fn tile_placement(index: usize, time: f32) -> ([f32; 4], f32) {
+    let x = (index as f32 + 0.5) / TILE_COUNT as f32 * 2.0 - 1.0;
+    let bob = (time * 1.5 + index as f32).sin() * 0.05;
+    let angle = (time * 0.7 + index as f32).sin() * 0.2;
+    ([x, 0.4 + bob, 0.27, 0.27], angle)
+}
+
+/**
+ * Texture binds and draw calls the tiles take each frame
+ */
+fn binding_cost(binding: TileBinding) -> String {
+    let (binds, draws) = match binding {
+        TileBinding::Separate => (TILE_COUNT, TILE_COUNT),
+        TileBinding::LayerPerDraw => (1, TILE_COUNT),
+        TileBinding::LayerPerInstance => (1, 1),
+    };
+    format!("{} texture binds, {} draw calls", binds, draws)
+}
+
+/**
+ * Instance attributes for the array shader: rect at location 0, angle and
+ * layer at location 1
+ */
+fn create_tile_instances(
+    gl: &WebGl2RenderingContext,
+) -> Option<(web_sys::WebGlVertexArrayObject, web_sys::WebGlBuffer)> {
+    let vao = gl.create_vertex_array()?;
+    let buffer = gl.create_buffer()?;
+    gl.bind_vertex_array(Some(&vao));
+    gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&buffer));
+    let stride = (FLOATS_PER_TILE * 4) as i32;
+    for (location, size, offset) in [(0, 4, 0), (1, 2, 16)] {
+        gl.enable_vertex_attrib_array(location);
+        gl.vertex_attrib_pointer_with_i32(
+            location,
+            size,
+            WebGl2RenderingContext::FLOAT,
+            false,
+            stride,
+            offset,
+        );
+        gl.vertex_attrib_divisor(location, 1);
+    }
+    gl.bind_vertex_array(None);
+    Some((vao, buffer))
+}
+
+fn texture_statuses(assets: &Assets, handles: &[Handle<Texture>]) -> Vec<AssetStatus> {
+    TEXTURES
+        .iter()
+        .zip(handles)
+        .map(|(&(name, _, critical), &handle)| AssetStatus {
+            name: name.to_string(),
+            critical,
+            state: assets.state(handle),
+        })
+        .collect()
+}
+
+// Cheap deterministic 0..1 noise for scattering sprites
+fn hash(seed: f32) -> f32 {
+    ((seed * 12.9898).sin() * 43758.547).fract().abs()
+}
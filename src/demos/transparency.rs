@@ -0,0 +1,273 @@
+use dioxus::prelude::*;
+use std::cell::RefCell;
+use std::f32::consts::FRAC_PI_2;
+use std::rc::Rc;
+use web_sys::WebGl2RenderingContext;
+
+use crate::camera::OrbitCamera;
+use crate::gl_errors;
+use crate::gl_util;
+use crate::hooks::use_frame_loop;
+use crate::math::{self, Vec3};
+use crate::mesh::{GpuMesh, MeshData};
+use crate::oit::WeightedOit;
+use crate::scene::Transform;
+use crate::shaders;
+
+const CANVAS_SIZE: u32 = 480;
+
+/**
+ * How the transparent panes are blended
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Compositing {
+    // Any order, resolved in one pass
+    WeightedOit,
+    // Classic back-to-front blending of whole objects, which breaks where they intersect
+    Sorted,
+}
+
+const MODES: [(Compositing, &str); 2] = [
+    (Compositing::WeightedOit, "Weighted blended OIT"),
+    (Compositing::Sorted, "Sorted alpha blending"),
+];
+
+// Three mutually intersecting panes: (center, rotation, color)
+const PANES: [(Vec3, Vec3, [f32; 4]); 3] = [
+    ([0.0, 0.0, 0.15], [0.0, 0.0, 0.0], [1.0, 0.25, 0.2, 0.45]),
+    (
+        [0.15, 0.0, 0.0],
+        [0.0, FRAC_PI_2, 0.0],
+        [0.2, 1.0, 0.3, 0.45],
+    ),
+    (
+        [0.0, 0.15, 0.0],
+        [FRAC_PI_2, 0.0, 0.0],
+        [0.25, 0.45, 1.0, 0.45],
+    ),
+];
+const PANE_SCALE: Vec3 = [1.6, 1.6, 0.04];
+
+#[component]
+pub fn TransparencyDemo() -> Element {
+    let mut canvas_mounted = use_signal(|| false);
+    let mut mode = use_signal(|| Compositing::WeightedOit);
+    // Set when the OIT targets can't be created; only sorting is offered then
+    let mut oit_error = use_signal(|| None::<String>);
+    let camera = use_hook(|| {
+        let mut camera = OrbitCamera::default();
+        camera.distance = 3.5;
+        Rc::new(RefCell::new(camera))
+    });
+    let frame_loop = use_frame_loop();
+
+    use_effect({
+        let camera = camera.clone();
+        move || {
+            if !canvas_mounted() {
+                return;
+            }
+
+            let camera = camera.clone();
+            let frame_loop = frame_loop.clone();
+            spawn(async move {
+                gloo_timers::future::TimeoutFuture::new(50).await;
+
+                let canvas = match gl_util::canvas_by_id("transparency-canvas") {
+                    Ok(canvas) => canvas,
+                    Err(err) => {
+                        gl_errors::report("transparency: setup", err);
+                        return;
+                    }
+                };
+
+                let gl = match gl_util::webgl2_context(&canvas) {
+                    Ok(gl) => gl,
+                    Err(err) => {
+                        gl_errors::report("transparency: setup", err);
+                        return;
+                    }
+                };
+
+                canvas.set_width(CANVAS_SIZE);
+                canvas.set_height(CANVAS_SIZE);
+
+                let program = match gl_util::create_program(
+                    &gl,
+                    shaders::DEMOS_TRANSPARENCY_VERT,
+                    shaders::DEMOS_TRANSPARENCY_FRAG,
+                ) {
+                    Ok(program) => program,
+                    Err(err) => {
+                        gl_errors::report("transparency: program setup", err);
+                        return;
+                    }
+                };
+                let mvp_loc = gl.get_uniform_location(&program, "modelViewProjection");
+                let color_loc = gl.get_uniform_location(&program, "color");
+                let tinted_loc = gl.get_uniform_location(&program, "tinted");
+                let Some(cube) = GpuMesh::upload(&gl, &MeshData::cube()) else {
+                    gl_errors::report("transparency: setup", "Unable to upload the cube");
+                    return;
+                };
+
+                let size = CANVAS_SIZE as i32;
+                let oit = match WeightedOit::new(&gl, size, size) {
+                    Ok(oit) => Some(oit),
+                    Err(err) => {
+                        gl_errors::report("transparency: OIT setup", err.clone());
+                        oit_error.set(Some(err));
+                        mode.set(Compositing::Sorted);
+                        None
+                    }
+                };
+
+                tracing::info!(target: "renderer", "Transparency ready");
+
+                frame_loop.start(move |time: f64, _| {
+                    let time = time as f32;
+                    let camera = camera.borrow();
+                    let view_proj = camera.view_projection(1.0);
+                    // The opaque cube spins through the panes
+                    let solid = math::multiply(
+                        &view_proj,
+                        &math::multiply(
+                            &math::rotation_matrix_y(time * 0.6),
+                            &math::multiply(
+                                &math::rotation_matrix_x(time * 0.4),
+                                &math::scaling([0.45; 3]),
+                            ),
+                        ),
+                    );
+
+                    gl.viewport(0, 0, size, size);
+                    gl.enable(WebGl2RenderingContext::DEPTH_TEST);
+                    gl.enable(WebGl2RenderingContext::CULL_FACE);
+                    gl.clear_color(0.1, 0.1, 0.1, 1.0);
+                    gl.clear(
+                        WebGl2RenderingContext::COLOR_BUFFER_BIT
+                            | WebGl2RenderingContext::DEPTH_BUFFER_BIT,
+                    );
+                    gl.use_program(Some(&program));
+                    gl.uniform1f(tinted_loc.as_ref(), 0.0);
+                    gl.uniform_matrix4fv_with_f32_array(mvp_loc.as_ref(), false, &solid);
+                    cube.draw(&gl);
+
+                    let panes = PANES.map(|(translation, rotation, color)| {
+                        let transform = Transform {
+                            translation,
+                            rotation,
+                            scale: PANE_SCALE,
+                        };
+                        (math::multiply(&view_proj, &transform.matrix()), color)
+                    });
+                    match (*mode.peek(), &oit) {
+                        (Compositing::WeightedOit, Some(oit)) => {
+                            oit.begin(&gl, [(&cube, solid)]);
+                            for (mvp, color) in &panes {
+                                oit.draw(&gl, &cube, mvp, *color);
+                            }
+                            oit.resolve(&gl);
+                        }
+                        _ => {
+                            // Farthest pane center first
+                            let mut order: Vec<usize> = (0..panes.len()).collect();
+                            let depth = |i: usize| {
+                                let eye = camera.eye();
+                                math::length(math::sub(PANES[i].0, eye))
+                            };
+                            order.sort_by(|&a, &b| depth(b).total_cmp(&depth(a)));
+
+                            gl.uniform1f(tinted_loc.as_ref(), 1.0);
+                            gl.disable(WebGl2RenderingContext::CULL_FACE);
+                            gl.enable(WebGl2RenderingContext::BLEND);
+                            gl.blend_func(
+                                WebGl2RenderingContext::SRC_ALPHA,
+                                WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+                            );
+                            gl.depth_mask(false);
+                            for i in order {
+                                let (mvp, color) = &panes[i];
+                                gl.uniform_matrix4fv_with_f32_array(mvp_loc.as_ref(), false, mvp);
+                                gl.uniform4fv_with_f32_array(color_loc.as_ref(), color);
+                                cube.draw(&gl);
+                            }
+                            gl.depth_mask(true);
+                            gl.disable(WebGl2RenderingContext::BLEND);
+                        }
+                    }
+                });
+            });
+        }
+    });
+
+    let on_mouse_down = {
+        let camera = camera.clone();
+        move |evt: MouseEvent| {
+            let point = evt.client_coordinates();
+            camera.borrow_mut().begin_drag(point.x, point.y);
+        }
+    };
+    let on_mouse_move = {
+        let camera = camera.clone();
+        move |evt: MouseEvent| {
+            let point = evt.client_coordinates();
+            camera.borrow_mut().drag_to(point.x, point.y);
+        }
+    };
+    let on_mouse_up = {
+        let camera = camera.clone();
+        move |_| camera.borrow_mut().end_drag()
+    };
+    let on_mouse_leave = {
+        let camera = camera.clone();
+        move |_| camera.borrow_mut().end_drag()
+    };
+    let on_wheel = {
+        let camera = camera.clone();
+        move |evt: WheelEvent| {
+            evt.prevent_default();
+            camera.borrow_mut().zoom(evt.delta().strip_units().y);
+        }
+    };
+
+    rsx! {
+        div {
+            style: "display: flex; flex-direction: column; justify-content: center; align-items: center; height: 100vh; background: #f0f0f0;",
+            canvas {
+                id: "transparency-canvas",
+                width: "{CANVAS_SIZE}",
+                height: "{CANVAS_SIZE}",
+                style: "border: 2px solid #333; background: #222; cursor: grab;",
+                onmounted: move |_| {
+                    canvas_mounted.set(true);
+                },
+                onmousedown: on_mouse_down,
+                onmousemove: on_mouse_move,
+                onmouseup: on_mouse_up,
+                onmouseleave: on_mouse_leave,
+                onwheel: on_wheel,
+            }
+            div {
+                style: "display: flex; gap: 16px; align-items: center; margin-top: 10px; color: #333; font-family: monospace;",
+                if let Some(err) = oit_error() {
+                    span { "Sorted alpha blending only ({err})" }
+                } else {
+                    label {
+                        "Transparency "
+                        select {
+                            onchange: move |evt| {
+                                if let Some(&(kind, _)) = MODES.iter().find(|(_, name)| *name == evt.value()) {
+                                    mode.set(kind);
+                                }
+                            },
+                            for (kind, name) in MODES {
+                                option { value: name, selected: mode() == kind, "{name}" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,383 @@
+use dioxus::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+use web_sys::WebGl2RenderingContext;
+
+use crate::camera::OrbitCamera;
+use crate::gl_errors;
+use crate::gl_util;
+use crate::hooks::use_frame_loop;
+use crate::math::{self, Vec3};
+use crate::mesh::MeshData;
+use crate::shaders;
+use crate::texture::{Texture, Texture3D};
+
+const CANVAS_SIZE: u32 = 480;
+// Voxels along each side of the procedural volume
+const VOLUME_SIZE: usize = 64;
+// Entries in each transfer function lookup texture
+const TRANSFER_SIZE: usize = 256;
+
+/**
+ * Color and opacity ramp applied to normalized density
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TransferPreset {
+    Fire,
+    Ice,
+    // Opaque shells at a few density levels, like stacked isosurfaces
+    Bands,
+}
+
+const PRESETS: [(TransferPreset, &str); 3] = [
+    (TransferPreset::Fire, "Fire"),
+    (TransferPreset::Ice, "Ice"),
+    (TransferPreset::Bands, "Iso bands"),
+];
+
+#[component]
+pub fn VolumeDemo() -> Element {
+    let mut canvas_mounted = use_signal(|| false);
+    let mut preset = use_signal(|| TransferPreset::Fire);
+    let mut window_low = use_signal(|| 0.15f32);
+    let mut window_high = use_signal(|| 0.9f32);
+    let mut opacity = use_signal(|| 1.0f32);
+    let camera = use_hook(|| {
+        let mut camera = OrbitCamera::default();
+        camera.distance = 2.2;
+        Rc::new(RefCell::new(camera))
+    });
+    let frame_loop = use_frame_loop();
+
+    use_effect({
+        let camera = camera.clone();
+        move || {
+            if !canvas_mounted() {
+                return;
+            }
+
+            let camera = camera.clone();
+            let frame_loop = frame_loop.clone();
+            spawn(async move {
+                gloo_timers::future::TimeoutFuture::new(50).await;
+
+                let canvas = match gl_util::canvas_by_id("volume-canvas") {
+                    Ok(canvas) => canvas,
+                    Err(err) => {
+                        gl_errors::report("volume: setup", err);
+                        return;
+                    }
+                };
+
+                let gl = match gl_util::webgl2_context(&canvas) {
+                    Ok(gl) => gl,
+                    Err(err) => {
+                        gl_errors::report("volume: setup", err);
+                        return;
+                    }
+                };
+
+                canvas.set_width(CANVAS_SIZE);
+                canvas.set_height(CANVAS_SIZE);
+
+                let program = match gl_util::create_program(
+                    &gl,
+                    shaders::DEMOS_VOLUME_VERT,
+                    shaders::DEMOS_VOLUME_FRAG,
+                ) {
+                    Ok(program) => program,
+                    Err(err) => {
+                        gl_errors::report("volume: program setup", err);
+                        return;
+                    }
+                };
+                let view_proj_loc = gl.get_uniform_location(&program, "viewProjection");
+                let eye_loc = gl.get_uniform_location(&program, "eye");
+                let volume_loc = gl.get_uniform_location(&program, "volume");
+                let transfer_loc = gl.get_uniform_location(&program, "transfer");
+                let window_loc = gl.get_uniform_location(&program, "window");
+                let opacity_loc = gl.get_uniform_location(&program, "opacity");
+
+                let size = VOLUME_SIZE as i32;
+                let Some(volume) = Texture3D::from_r8(&gl, size, size, size, &blobs(VOLUME_SIZE))
+                else {
+                    gl_errors::report("volume: setup", "Unable to create the volume texture");
+                    return;
+                };
+                let transfers: Option<Vec<(TransferPreset, Texture)>> = PRESETS
+                    .iter()
+                    .map(|&(kind, _)| {
+                        let pixels = transfer_function(kind);
+                        Texture::from_rgba(&gl, TRANSFER_SIZE as i32, 1, &pixels)
+                            .map(|texture| (kind, texture))
+                    })
+                    .collect();
+                let Some(transfers) = transfers else {
+                    gl_errors::report(
+                        "volume: setup",
+                        "Unable to create the transfer function textures",
+                    );
+                    return;
+                };
+                let Some((vao, index_count)) = upload_box(&gl) else {
+                    gl_errors::report("volume: setup", "Unable to upload the box");
+                    return;
+                };
+
+                tracing::info!(target: "renderer", "Volume ready");
+
+                frame_loop.start(move |_, _| {
+                    gl.viewport(0, 0, CANVAS_SIZE as i32, CANVAS_SIZE as i32);
+                    gl.clear_color(0.1, 0.1, 0.1, 1.0);
+                    gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+
+                    let camera = camera.borrow();
+                    let view_proj = camera.view_projection(1.0);
+                    gl.use_program(Some(&program));
+                    gl.uniform_matrix4fv_with_f32_array(view_proj_loc.as_ref(), false, &view_proj);
+                    gl.uniform3fv_with_f32_array(eye_loc.as_ref(), &camera.eye());
+                    gl.uniform2f(window_loc.as_ref(), *window_low.peek(), *window_high.peek());
+                    gl.uniform1f(opacity_loc.as_ref(), *opacity.peek());
+                    volume.bind(&gl, 0);
+                    gl.uniform1i(volume_loc.as_ref(), 0);
+                    let kind = *preset.peek();
+                    if let Some((_, transfer)) = transfers.iter().find(|(k, _)| *k == kind) {
+                        transfer.bind(&gl, 1);
+                    }
+                    gl.uniform1i(transfer_loc.as_ref(), 1);
+
+                    // Back faces, so the ray starts correctly even with the eye inside the box
+                    gl.enable(WebGl2RenderingContext::CULL_FACE);
+                    gl.cull_face(WebGl2RenderingContext::FRONT);
+                    gl.enable(WebGl2RenderingContext::BLEND);
+                    gl.blend_func(
+                        WebGl2RenderingContext::ONE,
+                        WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+                    );
+                    gl.bind_vertex_array(Some(&vao));
+                    gl.draw_elements_with_i32(
+                        WebGl2RenderingContext::TRIANGLES,
+                        index_count,
+                        WebGl2RenderingContext::UNSIGNED_SHORT,
+                        0,
+                    );
+                    gl.bind_vertex_array(None);
+                    gl.disable(WebGl2RenderingContext::BLEND);
+                    gl.cull_face(WebGl2RenderingContext::BACK);
+                });
+            });
+        }
+    });
+
+    let on_mouse_down = {
+        let camera = camera.clone();
+        move |evt: MouseEvent| {
+            let point = evt.client_coordinates();
+            camera.borrow_mut().begin_drag(point.x, point.y);
+        }
+    };
+    let on_mouse_move = {
+        let camera = camera.clone();
+        move |evt: MouseEvent| {
+            let point = evt.client_coordinates();
+            camera.borrow_mut().drag_to(point.x, point.y);
+        }
+    };
+    let on_mouse_up = {
+        let camera = camera.clone();
+        move |_| camera.borrow_mut().end_drag()
+    };
+    let on_mouse_leave = {
+        let camera = camera.clone();
+        move |_| camera.borrow_mut().end_drag()
+    };
+    let on_wheel = {
+        let camera = camera.clone();
+        move |evt: WheelEvent| {
+            evt.prevent_default();
+            camera.borrow_mut().zoom(evt.delta().strip_units().y);
+        }
+    };
+
+    rsx! {
+        div {
+            style: "display: flex; flex-direction: column; justify-content: center; align-items: center; height: 100vh; background: #f0f0f0;",
+            canvas {
+                id: "volume-canvas",
+                width: "{CANVAS_SIZE}",
+                height: "{CANVAS_SIZE}",
+                style: "border: 2px solid #333; background: #222; cursor: grab;",
+                onmounted: move |_| {
+                    canvas_mounted.set(true);
+                },
+                onmousedown: on_mouse_down,
+                onmousemove: on_mouse_move,
+                onmouseup: on_mouse_up,
+                onmouseleave: on_mouse_leave,
+                onwheel: on_wheel,
+            }
+            div {
+                style: "display: flex; gap: 16px; align-items: center; margin-top: 10px; color: #333; font-family: monospace;",
+                label {
+                    "Transfer function "
+                    select {
+                        onchange: move |evt| {
+                            if let Some(&(kind, _)) = PRESETS.iter().find(|(_, name)| *name == evt.value()) {
+                                preset.set(kind);
+                            }
+                        },
+                        for (kind, name) in PRESETS {
+                            option { value: name, selected: preset() == kind, "{name}" }
+                        }
+                    }
+                }
+                label {
+                    "Opacity "
+                    input {
+                        r#type: "range",
+                        min: "0.1",
+                        max: "3",
+                        step: "0.1",
+                        value: "{opacity}",
+                        oninput: move |evt| {
+                            if let Ok(value) = evt.value().parse() {
+                                opacity.set(value);
+                            }
+                        },
+                    }
+                }
+            }
+            div {
+                style: "display: flex; gap: 16px; align-items: center; margin-top: 6px; color: #333; font-family: monospace;",
+                label {
+                    "Density from "
+                    input {
+                        r#type: "range",
+                        min: "0",
+                        max: "1",
+                        step: "0.01",
+                        value: "{window_low}",
+                        oninput: move |evt| {
+                            if let Ok(value) = evt.value().parse() {
+                                window_low.set(value);
+                            }
+                        },
+                    }
+                    " {window_low:.2}"
+                }
+                label {
+                    "to "
+                    input {
+                        r#type: "range",
+                        min: "0",
+                        max: "1",
+                        step: "0.01",
+                        value: "{window_high}",
+                        oninput: move |evt| {
+                            if let Ok(value) = evt.value().parse() {
+                                window_high.set(value);
+                            }
+                        },
+                    }
+                    " {window_high:.2}"
+                }
+            }
+        }
+    }
+}
+
+/**
+ * Procedural density volume: a few soft metaballs inside a thin torus shell
+ */
+fn blobs(size: usize) -> Vec<u8> {
+    let balls: [(Vec3, f32); 4] = [
+        ([-0.15, -0.1, 0.0], 0.12),
+        ([0.18, 0.05, 0.1], 0.1),
+        ([0.0, 0.2, -0.15], 0.08),
+        ([0.05, -0.2, 0.2], 0.07),
+    ];
+    let mut voxels = Vec::with_capacity(size * size * size);
+    for z in 0..size {
+        for y in 0..size {
+            for x in 0..size {
+                // Voxel center in [-0.5, 0.5]
+                let p = [x, y, z].map(|i| (i as f32 + 0.5) / size as f32 - 0.5);
+                let field: f32 = balls
+                    .iter()
+                    .map(|&(center, radius)| {
+                        let d = math::length(math::sub(p, center));
+                        radius * radius / (d * d + 1e-4)
+                    })
+                    .sum();
+                let blob = (field * 0.35).min(1.0);
+                let ring = (p[0] * p[0] + p[2] * p[2]).sqrt() - 0.36;
+                let torus = (ring * ring + p[1] * p[1]).sqrt();
+                let shell = (1.0 - (torus - 0.06).abs() / 0.03).clamp(0.0, 1.0) * 0.3;
+                voxels.push((blob.max(shell) * 255.0) as u8);
+            }
+        }
+    }
+    voxels
+}
+
+/**
+ * RGBA lookup from normalized density, `TRANSFER_SIZE` texels wide
+ */
+fn transfer_function(kind: TransferPreset) -> Vec<u8> {
+    let mut pixels = Vec::with_capacity(TRANSFER_SIZE * 4);
+    for i in 0..TRANSFER_SIZE {
+        let v = i as f32 / (TRANSFER_SIZE - 1) as f32;
+        let (rgb, alpha) = match kind {
+            TransferPreset::Fire => ([v * 3.0, v * 3.0 - 1.0, v * 3.0 - 2.0], v * v),
+            TransferPreset::Ice => ([v * v, 0.3 + v * 0.7, 0.6 + v * 0.4], v),
+            TransferPreset::Bands => {
+                let band = |center: f32| (1.0 - (v - center).abs() / 0.04).max(0.0);
+                let alpha = band(0.2) * 0.4 + band(0.5) * 0.7 + band(0.85);
+                ([v, 1.0 - (v - 0.5).abs() * 1.5, 1.0 - v], alpha)
+            }
+        };
+        let rgb = rgb.map(|c| (c.clamp(0.0, 1.0) * 255.0) as u8);
+        pixels.extend(rgb);
+        pixels.push((alpha.clamp(0.0, 1.0) * 255.0) as u8);
+    }
+    pixels
+}
+
+/**
+ * Bounding box of the volume, positions only
+ */
+fn upload_box(gl: &WebGl2RenderingContext) -> Option<(web_sys::WebGlVertexArrayObject, i32)> {
+    let cube = MeshData::cube();
+    let vao = gl.create_vertex_array()?;
+    gl.bind_vertex_array(Some(&vao));
+
+    let position_buffer = gl.create_buffer()?;
+    gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&position_buffer));
+    unsafe {
+        let array = js_sys::Float32Array::view(&cube.positions);
+        gl.buffer_data_with_array_buffer_view(
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            &array,
+            WebGl2RenderingContext::STATIC_DRAW,
+        );
+    }
+    gl.enable_vertex_attrib_array(0);
+    gl.vertex_attrib_pointer_with_i32(0, 3, WebGl2RenderingContext::FLOAT, false, 0, 0);
+
+    let index_buffer = gl.create_buffer()?;
+    gl.bind_buffer(
+        WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER,
+        Some(&index_buffer),
+    );
+    unsafe {
+        let array = js_sys::Uint16Array::view(&cube.indices);
+        gl.buffer_data_with_array_buffer_view(
+            WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER,
+            &array,
+            WebGl2RenderingContext::STATIC_DRAW,
+        );
+    }
+
+    gl.bind_vertex_array(None);
+    Some((vao, cube.indices.len() as i32))
+}
@@ -0,0 +1,273 @@
+use dioxus::prelude::*;
+
+/**
+ * The cube demo again, written straight against web-sys's WebGPU bindings
+ * instead of WebGL2: request an adapter and device, configure the canvas,
+ * build a render pipeline and a bind group for the matrix, then encode a
+ * render pass every frame. Compare with `cube.rs` line for line; the
+ * `backend` module hides both behind one API
+ */
+#[component]
+pub fn WebGpuCubeDemo() -> Element {
+    let mut canvas_mounted = use_signal(|| false);
+    #[cfg(feature = "webgpu")]
+    let status = raw::use_webgpu_cube(canvas_mounted);
+    #[cfg(not(feature = "webgpu"))]
+    let status: Signal<Option<String>> =
+        use_signal(|| Some("Built without WebGPU: run `dx serve --features webgpu`".to_string()));
+
+    rsx! {
+        div {
+            style: "display: flex; flex-direction: column; justify-content: center; align-items: center; height: 100vh; background: #f0f0f0;",
+            canvas {
+                id: "webgpu-canvas",
+                width: "480",
+                height: "480",
+                style: "border: 2px solid #333; background: #222;",
+                onmounted: move |_| canvas_mounted.set(true),
+            }
+            if let Some(status) = status() {
+                p {
+                    style: "font-family: monospace; color: #a33;",
+                    "{status}"
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "webgpu")]
+mod raw {
+    use dioxus::prelude::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::JsFuture;
+    use web_sys::js_sys::JsNullable;
+    use web_sys::{
+        gpu_buffer_usage, gpu_shader_stage, GpuBindGroupDescriptor, GpuBindGroupEntry,
+        GpuBindGroupLayoutDescriptor, GpuBindGroupLayoutEntry, GpuBufferBinding,
+        GpuBufferBindingLayout, GpuBufferDescriptor, GpuCanvasAlphaMode, GpuCanvasConfiguration,
+        GpuCanvasContext, GpuColorDict, GpuColorTargetState, GpuCullMode, GpuDevice,
+        GpuFragmentState, GpuIndexFormat, GpuLoadOp, GpuPipelineLayoutDescriptor,
+        GpuPrimitiveState, GpuPrimitiveTopology, GpuRenderPassColorAttachment,
+        GpuRenderPassDescriptor, GpuRenderPipelineDescriptor, GpuShaderModuleDescriptor,
+        GpuStoreOp, GpuVertexAttribute, GpuVertexBufferLayout, GpuVertexFormat, GpuVertexState,
+        HtmlCanvasElement,
+    };
+
+    use crate::gl_errors;
+    use crate::hooks::{use_frame_loop, FrameLoop};
+    use crate::math::rotation_matrix_y;
+
+    const SHADER: &str = include_str!("../../shaders/demos/webgpu_cube.wgsl");
+
+    /**
+     * Start the cube once the canvas is mounted; the signal holds why it
+     * couldn't start, if it couldn't
+     */
+    pub fn use_webgpu_cube(canvas_mounted: Signal<bool>) -> Signal<Option<String>> {
+        let mut status = use_signal(|| None::<String>);
+        let frame_loop = use_frame_loop();
+        let started = use_hook(|| Rc::new(Cell::new(false)));
+
+        use_effect(move || {
+            if !canvas_mounted() || started.replace(true) {
+                return;
+            }
+            let frame_loop = frame_loop.clone();
+            spawn(async move {
+                let Some(canvas) = web_sys::window()
+                    .and_then(|window| window.document())
+                    .and_then(|document| document.get_element_by_id("webgpu-canvas"))
+                    .and_then(|element| element.dyn_into::<HtmlCanvasElement>().ok())
+                else {
+                    return;
+                };
+                if let Err(err) = start(&canvas, &frame_loop).await {
+                    gl_errors::report("webgpu cube: setup", err.clone());
+                    status.set(Some(err));
+                }
+            });
+        });
+        status
+    }
+
+    async fn start(canvas: &HtmlCanvasElement, frame_loop: &FrameLoop) -> Result<(), String> {
+        canvas.set_width(480);
+        canvas.set_height(480);
+
+        // Adapter and device. Browsers without WebGPU have no `navigator.gpu`
+        let navigator = web_sys::window()
+            .ok_or_else(|| "No window".to_string())?
+            .navigator();
+        if js_sys::Reflect::get(&navigator, &"gpu".into()).map_or(true, |gpu| gpu.is_undefined()) {
+            return Err("This browser doesn't support WebGPU".to_string());
+        }
+        let gpu = navigator.gpu();
+        let adapter = JsFuture::from(gpu.request_adapter())
+            .await
+            .map_err(|err| format!("Requesting an adapter failed: {:?}", err))?
+            .into_option()
+            .ok_or_else(|| "No suitable GPU adapter".to_string())?;
+        let device: GpuDevice = JsFuture::from(adapter.request_device())
+            .await
+            .map_err(|err| format!("Requesting a device failed: {:?}", err))?;
+        let queue = device.queue();
+
+        // The canvas context, in the format the browser composites fastest
+        let context = canvas
+            .get_context("webgpu")
+            .map_err(|err| format!("Unable to create a WebGPU context: {:?}", err))?
+            .ok_or_else(|| "The canvas already has a different kind of context".to_string())?
+            .unchecked_into::<GpuCanvasContext>();
+        let format = gpu.get_preferred_canvas_format();
+        let configuration = GpuCanvasConfiguration::new(&device, format);
+        configuration.set_alpha_mode(GpuCanvasAlphaMode::Opaque);
+        context
+            .configure(&configuration)
+            .map_err(|err| format!("Configuring the canvas failed: {:?}", err))?;
+
+        tracing::debug!(target: "renderer", "WebGPU device ready, canvas format {:?}", format);
+
+        // Same cube as the WebGL2 demo
+        let vertices: [f32; 24] = [
+            // Four front-face vertices (Z=0.2)
+            -0.4, -0.4, 0.2, 0.4, -0.4, 0.2, 0.4, 0.4, 0.2, -0.4, 0.4, 0.2,
+            // Four back-face vertices (Z=-0.2)
+            -0.4, -0.4, -0.2, 0.4, -0.4, -0.2, 0.4, 0.4, -0.2, -0.4, 0.4, -0.2,
+        ];
+        let colors: [f32; 24] = [
+            // Front face colors
+            1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 0.0,
+            // Back face colors
+            1.0, 0.0, 1.0, 0.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.5, 0.5, 0.5,
+        ];
+        let indices: [u16; 36] = [
+            0, 1, 2, 2, 3, 0, 4, 6, 5, 6, 4, 7, 4, 0, 3, 3, 7, 4, 1, 5, 6, 6, 2, 1, 3, 2, 6, 6, 7,
+            3, 4, 5, 1, 1, 0, 4,
+        ];
+
+        // Buffers are created with their size and usage, then filled through the queue
+        let create_buffer = |bytes: &[u8], usage: u32| {
+            let buffer = device
+                .create_buffer(&GpuBufferDescriptor::new(
+                    bytes.len() as u32,
+                    usage | gpu_buffer_usage::COPY_DST,
+                ))
+                .map_err(|err| format!("Unable to create buffer: {:?}", err))?;
+            queue
+                .write_buffer_with_u32_and_u8_slice(&buffer, 0, bytes)
+                .map_err(|err| format!("Writing a buffer failed: {:?}", err))?;
+            Ok::<_, String>(buffer)
+        };
+        let pos_buffer = create_buffer(&float_bytes(&vertices), gpu_buffer_usage::VERTEX)?;
+        let color_buffer = create_buffer(&float_bytes(&colors), gpu_buffer_usage::VERTEX)?;
+        let index_bytes: Vec<u8> = indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+        let index_buffer = create_buffer(&index_bytes, gpu_buffer_usage::INDEX)?;
+        let uniform_buffer = create_buffer(&[0; 64], gpu_buffer_usage::UNIFORM)?;
+
+        // Bind group: the matrix's uniform buffer at group 0, binding 0
+        let uniform_entry = GpuBindGroupLayoutEntry::new(0, gpu_shader_stage::VERTEX);
+        uniform_entry.set_buffer(&GpuBufferBindingLayout::new());
+        let bind_group_layout = device
+            .create_bind_group_layout(&GpuBindGroupLayoutDescriptor::new(&[uniform_entry]))
+            .map_err(|err| format!("Unable to create bind group layout: {:?}", err))?;
+        let bind_group = device.create_bind_group(&GpuBindGroupDescriptor::new(
+            &[GpuBindGroupEntry::new_with_gpu_buffer_binding(
+                0,
+                &GpuBufferBinding::new(&uniform_buffer),
+            )],
+            &bind_group_layout,
+        ));
+
+        // Render pipeline: shaders, vertex layout and fixed-function state in one object.
+        // Shader errors aren't thrown; they show up in the console when the
+        // pipeline is used
+        let module = device.create_shader_module(&GpuShaderModuleDescriptor::new(SHADER));
+        let pipeline_layout =
+            device.create_pipeline_layout(&GpuPipelineLayoutDescriptor::new(&[JsNullable::wrap(
+                bind_group_layout,
+            )]));
+        let vertex_buffer = |location: u32| {
+            JsNullable::wrap(GpuVertexBufferLayout::new(
+                12,
+                &[GpuVertexAttribute::new(
+                    GpuVertexFormat::Float32x3,
+                    0,
+                    location,
+                )],
+            ))
+        };
+        let vertex = GpuVertexState::new(&module);
+        vertex.set_entry_point("vs_main");
+        vertex.set_buffers(&[vertex_buffer(0), vertex_buffer(1)]);
+        let fragment = GpuFragmentState::new(
+            &module,
+            &[JsNullable::wrap(GpuColorTargetState::new(format))],
+        );
+        fragment.set_entry_point("fs_main");
+        // No depth buffer and no culling, like the WebGL2 cube
+        let primitive = GpuPrimitiveState::new();
+        primitive.set_topology(GpuPrimitiveTopology::TriangleList);
+        primitive.set_cull_mode(GpuCullMode::None);
+        let descriptor = GpuRenderPipelineDescriptor::new(&pipeline_layout, &vertex);
+        descriptor.set_fragment(&fragment);
+        descriptor.set_primitive(&primitive);
+        let pipeline = device
+            .create_render_pipeline(&descriptor)
+            .map_err(|err| format!("Unable to create pipeline: {:?}", err))?;
+
+        tracing::debug!(target: "renderer", "WebGPU pipeline and buffers created");
+
+        // Animation loop
+        let mut angle = 0.0f32;
+        frame_loop.start(move |_time: f64, delta: f64| {
+            let model = rotation_matrix_y(angle);
+            let drawn = queue
+                .write_buffer_with_u32_and_u8_slice(&uniform_buffer, 0, &float_bytes(&model))
+                .map_err(|err| format!("Writing the matrix failed: {:?}", err))
+                .and_then(|_| {
+                    // Record one render pass into a command buffer and submit it
+                    let target = context
+                        .get_current_texture()
+                        .and_then(|texture| texture.create_view())
+                        .map_err(|err| format!("No canvas texture to draw to: {:?}", err))?;
+                    let color = GpuRenderPassColorAttachment::new_with_gpu_texture_view(
+                        GpuLoadOp::Clear,
+                        GpuStoreOp::Store,
+                        &target,
+                    );
+                    color.set_clear_value_gpu_color_dict(&GpuColorDict::new(1.0, 0.1, 0.1, 0.1));
+                    let encoder = device.create_command_encoder();
+                    let pass = encoder
+                        .begin_render_pass(&GpuRenderPassDescriptor::new(&[JsNullable::wrap(
+                            color,
+                        )]))
+                        .map_err(|err| format!("Unable to begin render pass: {:?}", err))?;
+                    pass.set_pipeline(&pipeline);
+                    pass.set_bind_group(0, Some(&bind_group));
+                    pass.set_vertex_buffer(0, Some(&pos_buffer));
+                    pass.set_vertex_buffer(1, Some(&color_buffer));
+                    pass.set_index_buffer(&index_buffer, GpuIndexFormat::Uint16);
+                    pass.draw_indexed(indices.len() as u32);
+                    pass.end();
+                    queue.submit(&[encoder.finish()]);
+                    Ok(())
+                });
+            if let Err(err) = drawn {
+                gl_errors::report("webgpu cube: draw", err);
+            }
+
+            // Update angle, about 1.2 radians per second
+            angle += 1.2 * delta as f32;
+        });
+
+        tracing::info!(target: "renderer", "WebGPU animation started successfully!");
+        Ok(())
+    }
+
+    fn float_bytes(values: &[f32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+}
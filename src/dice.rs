@@ -0,0 +1,142 @@
+use crate::atlas::UvRect;
+
+/**
+ * Pips on each face of `TexturedMeshData::cube` (+X, -X, +Y, -Y, +Z, -Z), so
+ * opposite faces add up to seven like a real die
+ */
+pub const FACE_PIPS: [u32; 6] = [3, 4, 1, 6, 2, 5];
+
+const BACKGROUND: [u8; 3] = [245, 240, 228];
+const PIP: [u8; 3] = [30, 30, 36];
+// The single pip is traditionally red and larger
+const ACE: [u8; 3] = [200, 30, 40];
+
+/**
+ * Centers of the pips of a face with `pips` dots, in 0..1 across it
+ */
+fn pip_centers(pips: u32) -> Vec<[f32; 2]> {
+    let (low, mid, high) = (0.25, 0.5, 0.75);
+    match pips {
+        1 => vec![[mid, mid]],
+        2 => vec![[low, low], [high, high]],
+        3 => vec![[low, low], [mid, mid], [high, high]],
+        4 => vec![[low, low], [high, low], [low, high], [high, high]],
+        5 => vec![
+            [low, low],
+            [high, low],
+            [mid, mid],
+            [low, high],
+            [high, high],
+        ],
+        6 => vec![
+            [low, low],
+            [high, low],
+            [low, mid],
+            [high, mid],
+            [low, high],
+            [high, high],
+        ],
+        _ => Vec::new(),
+    }
+}
+
+/**
+ * RGBA pixels of a `size`×`size` die face, top row first, with pip edges
+ * antialiased by their coverage of each pixel
+ */
+pub fn face_pixels(pips: u32, size: usize) -> Vec<u8> {
+    let centers = pip_centers(pips);
+    let (radius, color) = if pips == 1 { (0.16, ACE) } else { (0.1, PIP) };
+    let mut pixels = Vec::with_capacity(size * size * 4);
+    for y in 0..size {
+        for x in 0..size {
+            let point = [
+                (x as f32 + 0.5) / size as f32,
+                (y as f32 + 0.5) / size as f32,
+            ];
+            let distance = centers
+                .iter()
+                .map(|c| ((point[0] - c[0]).powi(2) + (point[1] - c[1]).powi(2)).sqrt())
+                .fold(f32::MAX, f32::min);
+            // About one pixel of falloff at the edge
+            let coverage = ((radius - distance) * size as f32 + 0.5).clamp(0.0, 1.0);
+            for channel in 0..3 {
+                let mixed = BACKGROUND[channel] as f32
+                    + (color[channel] as f32 - BACKGROUND[channel] as f32) * coverage;
+                pixels.push(mixed.round() as u8);
+            }
+            pixels.push(255);
+        }
+    }
+    pixels
+}
+
+/**
+ * All six faces side by side in one strip, `size * 6` pixels wide and `size`
+ * tall, with the UV region of each face in `FACE_PIPS` order
+ */
+pub fn atlas(size: usize) -> (usize, usize, Vec<u8>, [UvRect; 6]) {
+    let width = size * FACE_PIPS.len();
+    let mut pixels = vec![0; width * size * 4];
+    for (face, &pips) in FACE_PIPS.iter().enumerate() {
+        let tile = face_pixels(pips, size);
+        for row in 0..size {
+            let target = (row * width + face * size) * 4;
+            pixels[target..target + size * 4]
+                .copy_from_slice(&tile[row * size * 4..(row + 1) * size * 4]);
+        }
+    }
+    let regions = std::array::from_fn(|face| {
+        // Inset by half a texel so sampling stays inside the face
+        let (x, y) = (0.5 / width as f32, 0.5 / size as f32);
+        UvRect {
+            min: [(face * size) as f32 / width as f32 + x, y],
+            max: [((face + 1) * size) as f32 / width as f32 - x, 1.0 - y],
+        }
+    });
+    (width, size, pixels, regions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opposite_faces_add_up_to_seven() {
+        for pair in FACE_PIPS.chunks_exact(2) {
+            assert_eq!(pair[0] + pair[1], 7);
+        }
+        for pips in FACE_PIPS {
+            assert_eq!(pip_centers(pips).len(), pips as usize);
+        }
+    }
+
+    #[test]
+    fn atlas_tiles_match_the_separate_faces() {
+        let size = 16;
+        let (width, height, pixels, regions) = atlas(size);
+        assert_eq!((width, height), (size * 6, size));
+        assert_eq!(pixels.len(), width * height * 4);
+        for (face, &pips) in FACE_PIPS.iter().enumerate() {
+            let tile = face_pixels(pips, size);
+            // Middle row of the face
+            let row = size / 2;
+            let start = (row * width + face * size) * 4;
+            assert_eq!(
+                &pixels[start..start + size * 4],
+                &tile[row * size * 4..(row + 1) * size * 4]
+            );
+            let region = regions[face];
+            assert!(region.min[0] > face as f32 / 6.0 && region.max[0] < (face + 1) as f32 / 6.0);
+        }
+    }
+
+    #[test]
+    fn pips_are_drawn_over_the_background() {
+        let size = 32;
+        let one = face_pixels(1, size);
+        let center = (size / 2 * size + size / 2) * 4;
+        assert_eq!(&one[center..center + 3], &ACE);
+        assert_eq!(&one[..3], &BACKGROUND);
+    }
+}
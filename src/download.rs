@@ -0,0 +1,22 @@
+use wasm_bindgen::{JsCast, JsValue};
+
+/**
+ * Offer `bytes` to the user as a file download called `filename`
+ */
+pub fn save_file(bytes: &[u8], filename: &str, mime_type: &str) -> Result<(), JsValue> {
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or("no document")?;
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type(mime_type);
+    let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+
+    let anchor: web_sys::HtmlAnchorElement = document.create_element("a")?.unchecked_into();
+    anchor.set_href(&url);
+    anchor.set_download(filename);
+    anchor.click();
+    // The download has its own reference to the blob by now
+    web_sys::Url::revoke_object_url(&url)
+}
@@ -0,0 +1,44 @@
+use crate::math::{self, Mat4, Vec3};
+use crate::raycast::Ray;
+
+/**
+ * Moves an object across the plane that faces the camera and passes through
+ * the point where it was grabbed
+ */
+#[derive(Clone, Debug)]
+pub struct ObjectDrag {
+    pub object: usize,
+    plane_point: Vec3,
+    plane_normal: Vec3,
+    // Object origin relative to the grabbed point, so it doesn't jump to the cursor
+    offset: Vec3,
+}
+
+impl ObjectDrag {
+    /**
+     * Start dragging `object` from `grab_point`, a point on its surface under the cursor
+     */
+    pub fn begin(
+        object: usize,
+        object_position: Vec3,
+        grab_point: Vec3,
+        camera_forward: Vec3,
+    ) -> Self {
+        Self {
+            object,
+            plane_point: grab_point,
+            plane_normal: math::normalize(camera_forward),
+            offset: math::sub(object_position, grab_point),
+        }
+    }
+
+    /**
+     * New object position for the cursor at `ndc`, or `None` if the ray is
+     * parallel to the drag plane
+     */
+    pub fn update(&self, inverse_view_proj: &Mat4, ndc: (f32, f32)) -> Option<Vec3> {
+        let ray = Ray::from_ndc(inverse_view_proj, ndc.0, ndc.1);
+        let hit = ray.at(ray.intersect_plane(self.plane_point, self.plane_normal)?);
+        Some(math::add(hit, self.offset))
+    }
+}
@@ -0,0 +1,84 @@
+use std::cell::Cell;
+
+use web_sys::{WebGl2RenderingContext, WebGlBuffer};
+
+use crate::gpu_memory::{Allocation, Kind};
+
+// Smallest allocation, so a handful of small writes don't each grow the buffer
+const MIN_CAPACITY: usize = 1024;
+
+/**
+ * Float vertex data rewritten every frame. Storage only grows (to the next
+ * power of two); otherwise each frame orphans it and streams into it with
+ * `bufferSubData`, so the driver never reallocates or waits on draws still
+ * reading last frame's contents
+ */
+pub struct DynamicBuffer {
+    buffer: WebGlBuffer,
+    target: u32,
+    // Allocated size in floats
+    capacity: Cell<usize>,
+    allocation: Allocation,
+}
+
+impl DynamicBuffer {
+    pub fn new(gl: &WebGl2RenderingContext, target: u32) -> Result<Self, String> {
+        let buffer = gl
+            .create_buffer()
+            .ok_or_else(|| "Unable to create dynamic buffer".to_string())?;
+        Ok(Self {
+            buffer,
+            target,
+            capacity: Cell::new(0),
+            allocation: Allocation::new(Kind::Buffer, 0),
+        })
+    }
+
+    pub fn bind(&self, gl: &WebGl2RenderingContext) {
+        gl.bind_buffer(self.target, Some(&self.buffer));
+    }
+
+    /**
+     * Start a new frame's contents: bind, and hand the driver fresh storage for
+     * at least `len` floats. Earlier contents are undefined afterwards
+     */
+    pub fn orphan(&self, gl: &WebGl2RenderingContext, len: usize) {
+        self.bind(gl);
+        let mut capacity = self.capacity.get();
+        if len > capacity {
+            capacity = len.max(MIN_CAPACITY).next_power_of_two();
+            self.capacity.set(capacity);
+            self.allocation.resize(capacity * 4);
+        }
+        gl.buffer_data_with_i32(
+            self.target,
+            (capacity * 4) as i32,
+            WebGl2RenderingContext::STREAM_DRAW,
+        );
+    }
+
+    /**
+     * Overwrite the floats starting at `offset` (in floats), leaving the rest
+     * untouched. The range must fit in the storage from the last `orphan`
+     */
+    pub fn write_at(&self, gl: &WebGl2RenderingContext, offset: usize, data: &[f32]) {
+        debug_assert!(offset + data.len() <= self.capacity.get());
+        self.bind(gl);
+        unsafe {
+            let array = js_sys::Float32Array::view(data);
+            gl.buffer_sub_data_with_i32_and_array_buffer_view(
+                self.target,
+                (offset * 4) as i32,
+                &array,
+            );
+        }
+    }
+
+    /**
+     * Replace the whole contents with `data`
+     */
+    pub fn write(&self, gl: &WebGl2RenderingContext, data: &[f32]) {
+        self.orphan(gl, data.len());
+        self.write_at(gl, 0, data);
+    }
+}
@@ -0,0 +1,352 @@
+use web_sys::{
+    WebGl2RenderingContext, WebGlFramebuffer, WebGlProgram, WebGlRenderbuffer, WebGlTexture,
+    WebGlUniformLocation, WebGlVertexArrayObject,
+};
+
+use crate::gl_util::{self, GlResource};
+use crate::gpu_memory::{self, Allocation, Kind};
+use crate::shaders;
+
+// Render scale never goes below this fraction of the canvas size per axis
+const MIN_SCALE: f32 = 0.5;
+const SCALE_STEP: f32 = 0.1;
+// Weight of the newest frame in the averaged frame time
+const SMOOTHING: f64 = 0.1;
+// Frames this far over budget on average make the scaler back off
+const OVER_BUDGET: f64 = 1.2;
+// Wait after a change before judging its effect, in milliseconds
+const SETTLE_MS: f64 = 500.0;
+// Wait at a steady frame rate before trying a higher scale, doubled every time
+// that turns out to be too slow, in milliseconds
+const PROBE_MS: f64 = 2000.0;
+const MAX_PROBE_MS: f64 = 30000.0;
+// Longer frames (a background tab, a hitch) say nothing about GPU load
+const MAX_SAMPLE_MS: f64 = 250.0;
+
+/**
+ * Picks the fraction of the canvas resolution to render at from measured
+ * frame times. Frames are paced by the display, so a fast GPU can't be told
+ * apart from one that just keeps up: the scale drops when frames run over
+ * budget and is raised again on probation after a steady stretch
+ */
+#[derive(Clone, Debug)]
+pub struct ResolutionScaler {
+    scale: f32,
+    // Smoothed frame time in milliseconds, `None` before the first sample
+    average_ms: Option<f64>,
+    // Since the scale last changed
+    since_change_ms: f64,
+    probe_ms: f64,
+    // The last change was a probe that hasn't proven itself yet
+    probing: bool,
+}
+
+impl Default for ResolutionScaler {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            average_ms: None,
+            since_change_ms: 0.0,
+            probe_ms: PROBE_MS,
+            probing: false,
+        }
+    }
+}
+
+impl ResolutionScaler {
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /**
+     * Account for a frame that took `frame_ms` and return the scale for the
+     * next one
+     */
+    pub fn update(&mut self, frame_ms: f64, target_fps: f64) -> f32 {
+        if !(0.0..MAX_SAMPLE_MS).contains(&frame_ms) {
+            return self.scale;
+        }
+        let average = match self.average_ms {
+            Some(average) => average + (frame_ms - average) * SMOOTHING,
+            None => frame_ms,
+        };
+        self.average_ms = Some(average);
+        self.since_change_ms += frame_ms;
+        if self.since_change_ms < SETTLE_MS {
+            return self.scale;
+        }
+
+        let budget = 1000.0 / target_fps;
+        if average > budget * OVER_BUDGET && self.scale > MIN_SCALE {
+            if self.probing {
+                // The higher scale was too much; stay below it for longer
+                self.probe_ms = (self.probe_ms * 2.0).min(MAX_PROBE_MS);
+            }
+            self.change((self.scale - SCALE_STEP).max(MIN_SCALE), false);
+        } else if average <= budget * OVER_BUDGET {
+            self.probing = false;
+            if self.scale < 1.0 && self.since_change_ms >= self.probe_ms {
+                self.change((self.scale + SCALE_STEP).min(1.0), true);
+            }
+        }
+        self.scale
+    }
+
+    fn change(&mut self, scale: f32, probing: bool) {
+        // Steps are decimal, so snap away the rounding they accumulate
+        self.scale = (scale * 100.0).round() / 100.0;
+        self.since_change_ms = 0.0;
+        self.probing = probing;
+        // Frames from before the change don't describe the new scale
+        self.average_ms = None;
+    }
+}
+
+/**
+ * Offscreen color and depth-stencil target for rendering at a size other than
+ * the canvas's. Frames are rendered into its bottom-left corner, up to
+ * `max_scale` times the canvas size, and then resampled to the canvas:
+ * stretched when rendered smaller, averaged down when supersampled
+ */
+pub struct ScaledTarget {
+    framebuffer: WebGlFramebuffer,
+    color: WebGlTexture,
+    depth_stencil: WebGlRenderbuffer,
+    program: WebGlProgram,
+    source_loc: Option<WebGlUniformLocation>,
+    source_size_loc: Option<WebGlUniformLocation>,
+    output_size_loc: Option<WebGlUniformLocation>,
+    // gl_VertexID only, but a bound VAO keeps every implementation happy
+    vao: WebGlVertexArrayObject,
+    // Canvas size
+    width: i32,
+    height: i32,
+    // Allocated size
+    capacity: (i32, i32),
+    _allocations: [Allocation; 2],
+}
+
+impl ScaledTarget {
+    pub fn new(
+        gl: &WebGl2RenderingContext,
+        width: i32,
+        height: i32,
+        max_scale: f32,
+    ) -> Result<Self, String> {
+        let program = gl_util::create_program(
+            gl,
+            shaders::DYNAMIC_RESOLUTION_RESAMPLE_VERT,
+            shaders::DYNAMIC_RESOLUTION_RESAMPLE_FRAG,
+        )?;
+        let capacity = (
+            (width as f32 * max_scale.max(1.0)).ceil() as i32,
+            (height as f32 * max_scale.max(1.0)).ceil() as i32,
+        );
+        let color = gl
+            .create_texture()
+            .ok_or_else(|| "Unable to create scaled color target".to_string())?;
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&color));
+        gl.tex_storage_2d(
+            WebGl2RenderingContext::TEXTURE_2D,
+            1,
+            WebGl2RenderingContext::RGBA8,
+            capacity.0,
+            capacity.1,
+        );
+        for (parameter, value) in [
+            (
+                WebGl2RenderingContext::TEXTURE_MIN_FILTER,
+                WebGl2RenderingContext::LINEAR,
+            ),
+            (
+                WebGl2RenderingContext::TEXTURE_MAG_FILTER,
+                WebGl2RenderingContext::LINEAR,
+            ),
+        ] {
+            gl.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, parameter, value as i32);
+        }
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+
+        // The mirror and planar shadows need a stencil buffer
+        let depth_stencil = gl
+            .create_renderbuffer()
+            .ok_or_else(|| "Unable to create scaled depth target".to_string())?;
+        gl.bind_renderbuffer(WebGl2RenderingContext::RENDERBUFFER, Some(&depth_stencil));
+        gl.renderbuffer_storage(
+            WebGl2RenderingContext::RENDERBUFFER,
+            WebGl2RenderingContext::DEPTH24_STENCIL8,
+            capacity.0,
+            capacity.1,
+        );
+        gl.bind_renderbuffer(WebGl2RenderingContext::RENDERBUFFER, None);
+
+        let framebuffer = gl
+            .create_framebuffer()
+            .ok_or_else(|| "Unable to create scaled framebuffer".to_string())?;
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(&framebuffer));
+        gl.framebuffer_texture_2d(
+            WebGl2RenderingContext::FRAMEBUFFER,
+            WebGl2RenderingContext::COLOR_ATTACHMENT0,
+            WebGl2RenderingContext::TEXTURE_2D,
+            Some(&color),
+            0,
+        );
+        gl.framebuffer_renderbuffer(
+            WebGl2RenderingContext::FRAMEBUFFER,
+            WebGl2RenderingContext::DEPTH_STENCIL_ATTACHMENT,
+            WebGl2RenderingContext::RENDERBUFFER,
+            Some(&depth_stencil),
+        );
+        let status = gl.check_framebuffer_status(WebGl2RenderingContext::FRAMEBUFFER);
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+        if status != WebGl2RenderingContext::FRAMEBUFFER_COMPLETE {
+            return Err(format!("Scaled framebuffer incomplete: {}", status));
+        }
+
+        let vao = gl
+            .create_vertex_array()
+            .ok_or_else(|| "Unable to create resample VAO".to_string())?;
+
+        Ok(Self {
+            source_loc: gl.get_uniform_location(&program, "source"),
+            source_size_loc: gl.get_uniform_location(&program, "sourceSize"),
+            output_size_loc: gl.get_uniform_location(&program, "outputSize"),
+            framebuffer,
+            color,
+            depth_stencil,
+            program,
+            vao,
+            width,
+            height,
+            capacity,
+            // RGBA8 color, and 24-bit depth with 8-bit stencil
+            _allocations: [Kind::Texture, Kind::Renderbuffer].map(|kind| {
+                Allocation::new(
+                    kind,
+                    gpu_memory::texture_bytes(
+                        capacity.0 as usize,
+                        capacity.1 as usize,
+                        1,
+                        4,
+                        false,
+                    ),
+                )
+            }),
+        })
+    }
+
+    /**
+     * Canvas size scaled by `scale`, the size to render at
+     */
+    pub fn scaled_size(&self, scale: f32) -> (i32, i32) {
+        let scaled = |size: i32, max: i32| ((size as f32 * scale).round() as i32).clamp(1, max);
+        (
+            scaled(self.width, self.capacity.0),
+            scaled(self.height, self.capacity.1),
+        )
+    }
+
+    /**
+     * Direct drawing into a `width` x `height` corner of the target
+     */
+    pub fn begin(&self, gl: &WebGl2RenderingContext, width: i32, height: i32) {
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(&self.framebuffer));
+        gl.viewport(0, 0, width, height);
+    }
+
+    /**
+     * Resample what was drawn since `begin` to the default framebuffer,
+     * leaving it bound with a full-size viewport
+     */
+    pub fn finish(&self, gl: &WebGl2RenderingContext, width: i32, height: i32) {
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+        gl.viewport(0, 0, self.width, self.height);
+        gl.disable(WebGl2RenderingContext::DEPTH_TEST);
+        gl.disable(WebGl2RenderingContext::BLEND);
+        gl.use_program(Some(&self.program));
+        gl.active_texture(WebGl2RenderingContext::TEXTURE0);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&self.color));
+        gl.uniform1i(self.source_loc.as_ref(), 0);
+        gl.uniform2f(self.source_size_loc.as_ref(), width as f32, height as f32);
+        gl.uniform2f(
+            self.output_size_loc.as_ref(),
+            self.width as f32,
+            self.height as f32,
+        );
+        gl.bind_vertex_array(Some(&self.vao));
+        gl.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, 3);
+        gl.bind_vertex_array(None);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+        gl.enable(WebGl2RenderingContext::DEPTH_TEST);
+    }
+}
+
+impl GlResource for ScaledTarget {
+    fn delete(&self, gl: &WebGl2RenderingContext) {
+        self.framebuffer.delete(gl);
+        self.color.delete(gl);
+        self.depth_stencil.delete(gl);
+        self.program.delete(gl);
+        self.vao.delete(gl);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(scaler: &mut ResolutionScaler, frame_ms: f64, duration_ms: f64) -> f32 {
+        let mut elapsed = 0.0;
+        while elapsed < duration_ms {
+            scaler.update(frame_ms, 60.0);
+            elapsed += frame_ms;
+        }
+        scaler.scale()
+    }
+
+    #[test]
+    fn stays_at_full_scale_on_budget() {
+        let mut scaler = ResolutionScaler::default();
+        assert_eq!(run(&mut scaler, 16.7, 10_000.0), 1.0);
+    }
+
+    #[test]
+    fn scales_down_when_slow_and_stops_at_minimum() {
+        let mut scaler = ResolutionScaler::default();
+        let scale = run(&mut scaler, 33.3, 1_500.0);
+        assert!((MIN_SCALE..1.0).contains(&scale), "{}", scale);
+        assert_eq!(run(&mut scaler, 33.3, 20_000.0), MIN_SCALE);
+    }
+
+    #[test]
+    fn probes_back_up_once_fast_again() {
+        let mut scaler = ResolutionScaler::default();
+        run(&mut scaler, 33.3, 20_000.0);
+        assert_eq!(run(&mut scaler, 16.7, 20_000.0), 1.0);
+    }
+
+    #[test]
+    fn failed_probes_wait_longer() {
+        let mut scaler = ResolutionScaler::default();
+        let low = run(&mut scaler, 33.3, 5_000.0);
+        assert_eq!(low, MIN_SCALE);
+        // Steady long enough for a probe, which then runs slow and backs off
+        let mut waited = 0.0;
+        while scaler.scale() == low && waited < PROBE_MS * 2.0 {
+            scaler.update(16.7, 60.0);
+            waited += 16.7;
+        }
+        assert!(scaler.scale() > low);
+        run(&mut scaler, 33.3, SETTLE_MS + 100.0);
+        assert_eq!(scaler.scale(), low);
+        assert_eq!(scaler.probe_ms, PROBE_MS * 2.0);
+    }
+
+    #[test]
+    fn ignores_hitches() {
+        let mut scaler = ResolutionScaler::default();
+        scaler.update(16.7, 60.0);
+        assert_eq!(scaler.update(5_000.0, 60.0), 1.0);
+        assert_eq!(scaler.average_ms, Some(16.7));
+    }
+}
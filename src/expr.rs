@@ -0,0 +1,310 @@
+/**
+ * Variables an expression can refer to
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Var {
+    X,
+    Y,
+    // Seconds, for animated expressions
+    T,
+    // User parameters
+    A,
+    B,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Func {
+    Sin,
+    Cos,
+    Tan,
+    Sqrt,
+    Abs,
+    Exp,
+    Ln,
+}
+
+/**
+ * Parsed arithmetic expression over `x`, `y`, `t`, `a` and `b`
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Number(f32),
+    Var(Var),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(Func, Box<Expr>),
+}
+
+/**
+ * Values bound to each `Var` during evaluation
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vars {
+    pub x: f32,
+    pub y: f32,
+    pub t: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+impl Expr {
+    pub fn eval(&self, vars: &Vars) -> f32 {
+        match self {
+            Expr::Number(value) => *value,
+            Expr::Var(var) => match var {
+                Var::X => vars.x,
+                Var::Y => vars.y,
+                Var::T => vars.t,
+                Var::A => vars.a,
+                Var::B => vars.b,
+            },
+            Expr::Neg(inner) => -inner.eval(vars),
+            Expr::Binary(op, lhs, rhs) => {
+                let (l, r) = (lhs.eval(vars), rhs.eval(vars));
+                match op {
+                    BinaryOp::Add => l + r,
+                    BinaryOp::Sub => l - r,
+                    BinaryOp::Mul => l * r,
+                    BinaryOp::Div => l / r,
+                    BinaryOp::Pow => l.powf(r),
+                }
+            }
+            Expr::Call(func, arg) => {
+                let v = arg.eval(vars);
+                match func {
+                    Func::Sin => v.sin(),
+                    Func::Cos => v.cos(),
+                    Func::Tan => v.tan(),
+                    Func::Sqrt => v.sqrt(),
+                    Func::Abs => v.abs(),
+                    Func::Exp => v.exp(),
+                    Func::Ln => v.ln(),
+                }
+            }
+        }
+    }
+
+    /**
+     * Whether the value changes over time, i.e. `t` appears anywhere
+     */
+    pub fn uses_time(&self) -> bool {
+        match self {
+            Expr::Number(_) => false,
+            Expr::Var(var) => *var == Var::T,
+            Expr::Neg(inner) | Expr::Call(_, inner) => inner.uses_time(),
+            Expr::Binary(_, lhs, rhs) => lhs.uses_time() || rhs.uses_time(),
+        }
+    }
+}
+
+/**
+ * Parse an expression such as `b * sin(a * sqrt(x^2 + y^2) - t)`.
+ * Supports `+ - * / ^`, parentheses, unary minus, `pi`, `e` and the
+ * functions sin, cos, tan, sqrt, abs, exp and ln
+ */
+pub fn parse(source: &str) -> Result<Expr, String> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+    };
+    let expr = parser.expression()?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(token) => Err(format!("Unexpected {}", token.describe())),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f32),
+    Ident(String),
+    Symbol(char),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Number(value) => format!("number {}", value),
+            Token::Ident(name) => format!("'{}'", name),
+            Token::Symbol(symbol) => format!("'{}'", symbol),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut text = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit() || **c == '.') {
+                text.push(c);
+                chars.next();
+            }
+            let value = text
+                .parse()
+                .map_err(|_| format!("Invalid number '{}'", text))?;
+            tokens.push(Token::Number(value));
+        } else if c.is_ascii_alphabetic() {
+            let mut name = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_ascii_alphanumeric()) {
+                name.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Ident(name));
+        } else if "+-*/^()".contains(c) {
+            tokens.push(Token::Symbol(c));
+            chars.next();
+        } else {
+            return Err(format!("Unexpected character '{}'", c));
+        }
+    }
+    Ok(tokens)
+}
+
+// Recursive descent, one method per precedence level
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        if self.peek() == Some(&Token::Symbol(symbol)) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    // sum := product (('+' | '-') product)*
+    fn expression(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.product()?;
+        loop {
+            let op = if self.eat('+') {
+                BinaryOp::Add
+            } else if self.eat('-') {
+                BinaryOp::Sub
+            } else {
+                return Ok(lhs);
+            };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.product()?));
+        }
+    }
+
+    // product := unary (('*' | '/') unary)*
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.unary()?;
+        loop {
+            let op = if self.eat('*') {
+                BinaryOp::Mul
+            } else if self.eat('/') {
+                BinaryOp::Div
+            } else {
+                return Ok(lhs);
+            };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+    }
+
+    // unary := '-' unary | power, so -x^2 is -(x^2)
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat('-') {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.power()
+    }
+
+    // power := atom ('^' unary)?, right-associative
+    fn power(&mut self) -> Result<Expr, String> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            let exponent = self.unary()?;
+            return Ok(Expr::Binary(
+                BinaryOp::Pow,
+                Box::new(base),
+                Box::new(exponent),
+            ));
+        }
+        Ok(base)
+    }
+
+    // atom := number | variable | constant | function '(' sum ')' | '(' sum ')'
+    fn atom(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::Symbol('(')) => {
+                let inner = self.expression()?;
+                self.expect_close()?;
+                Ok(inner)
+            }
+            Some(Token::Ident(name)) => {
+                let var = match name.as_str() {
+                    "x" => Some(Var::X),
+                    "y" => Some(Var::Y),
+                    "t" => Some(Var::T),
+                    "a" => Some(Var::A),
+                    "b" => Some(Var::B),
+                    _ => None,
+                };
+                if let Some(var) = var {
+                    return Ok(Expr::Var(var));
+                }
+                match name.as_str() {
+                    "pi" => return Ok(Expr::Number(std::f32::consts::PI)),
+                    "e" => return Ok(Expr::Number(std::f32::consts::E)),
+                    _ => {}
+                }
+                let func = match name.as_str() {
+                    "sin" => Func::Sin,
+                    "cos" => Func::Cos,
+                    "tan" => Func::Tan,
+                    "sqrt" => Func::Sqrt,
+                    "abs" => Func::Abs,
+                    "exp" => Func::Exp,
+                    "ln" => Func::Ln,
+                    _ => return Err(format!("Unknown name '{}'", name)),
+                };
+                if !self.eat('(') {
+                    return Err(format!("Expected '(' after {}", name));
+                }
+                let arg = self.expression()?;
+                self.expect_close()?;
+                Ok(Expr::Call(func, Box::new(arg)))
+            }
+            Some(token) => Err(format!("Unexpected {}", token.describe())),
+            None => Err("Unexpected end of expression".to_string()),
+        }
+    }
+
+    fn expect_close(&mut self) -> Result<(), String> {
+        if self.eat(')') {
+            Ok(())
+        } else {
+            Err("Expected ')'".to_string())
+        }
+    }
+}
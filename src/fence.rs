@@ -0,0 +1,116 @@
+use web_sys::{WebGl2RenderingContext, WebGlSync};
+
+use crate::gl_util::GlResource;
+
+// Between polls of a fence that hasn't signalled yet, in milliseconds
+const POLL_MS: u32 = 1;
+
+/**
+ * A point in the GL command stream the CPU can check on: it signals once the
+ * GPU has finished every command issued before it. WebGL never lets the CPU
+ * block on one, so it is polled instead
+ */
+pub struct Fence {
+    sync: WebGlSync,
+}
+
+impl Fence {
+    /**
+     * Fence the commands issued so far
+     */
+    pub fn insert(gl: &WebGl2RenderingContext) -> Result<Self, String> {
+        let sync = gl
+            .fence_sync(WebGl2RenderingContext::SYNC_GPU_COMMANDS_COMPLETE, 0)
+            .ok_or_else(|| "Unable to create fence".to_string())?;
+        // Without a flush the fence may sit in the command queue and never signal
+        gl.flush();
+        Ok(Self { sync })
+    }
+
+    /**
+     * Whether the GPU has got past the fence, without waiting for it
+     */
+    pub fn is_signaled(&self, gl: &WebGl2RenderingContext) -> Result<bool, String> {
+        match gl.client_wait_sync_with_u32(&self.sync, 0, 0) {
+            WebGl2RenderingContext::ALREADY_SIGNALED
+            | WebGl2RenderingContext::CONDITION_SATISFIED => Ok(true),
+            WebGl2RenderingContext::TIMEOUT_EXPIRED => Ok(false),
+            _ => Err("Waiting on a fence failed".to_string()),
+        }
+    }
+
+    /**
+     * Resolve once the fence has signalled, polling in between so the page
+     * stays responsive
+     */
+    pub async fn wait(&self, gl: &WebGl2RenderingContext) -> Result<(), String> {
+        while !self.is_signaled(gl)? {
+            gloo_timers::future::TimeoutFuture::new(POLL_MS).await;
+        }
+        Ok(())
+    }
+}
+
+impl GlResource for Fence {
+    fn delete(&self, gl: &WebGl2RenderingContext) {
+        gl.delete_sync(Some(&self.sync));
+    }
+}
+
+/**
+ * Fences for a fixed number of copies of a resource (buffers, usually) that
+ * are written in turn: each copy is fenced after the commands that read it,
+ * so the next write to it can tell whether the GPU still needs the old
+ * contents. With enough copies the answer is nearly always no, and the copy
+ * can be overwritten in place instead of orphaned
+ */
+pub struct FenceRing {
+    fences: Vec<Option<Fence>>,
+    next: usize,
+}
+
+impl FenceRing {
+    pub fn new(len: usize) -> Self {
+        Self {
+            fences: (0..len.max(1)).map(|_| None).collect(),
+            next: 0,
+        }
+    }
+
+    /**
+     * The copy to write next, and whether the GPU is done reading it
+     */
+    pub fn acquire(&mut self, gl: &WebGl2RenderingContext) -> Result<(usize, bool), String> {
+        let slot = self.next;
+        self.next = (self.next + 1) % self.fences.len();
+        let idle = match &self.fences[slot] {
+            Some(fence) => fence.is_signaled(gl)?,
+            None => true,
+        };
+        if idle {
+            if let Some(fence) = self.fences[slot].take() {
+                fence.delete(gl);
+            }
+        }
+        Ok((slot, idle))
+    }
+
+    /**
+     * Fence `slot` after the commands issued so far, the last ones to read it
+     */
+    pub fn release(&mut self, gl: &WebGl2RenderingContext, slot: usize) -> Result<(), String> {
+        let fence = Fence::insert(gl)?;
+        if let Some(previous) = self.fences[slot].replace(fence) {
+            previous.delete(gl);
+        }
+        Ok(())
+    }
+}
+
+impl GlResource for FenceRing {
+    fn delete(&self, gl: &WebGl2RenderingContext) {
+        for fence in self.fences.iter().flatten() {
+            fence.delete(gl);
+        }
+    }
+}
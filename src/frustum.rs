@@ -0,0 +1,129 @@
+use crate::bounds::{Aabb, Sphere};
+use crate::math::{self, Mat4, Vec3};
+
+/**
+ * World-space corners of the volume `view_proj` sees, in the order of
+ * `Aabb::corners`: bit 0 of the index picks right over left, bit 1 top over
+ * bottom and bit 2 the far plane over the near one. `None` if the matrix
+ * can't be inverted
+ */
+pub fn corners(view_proj: &Mat4) -> Option<[Vec3; 8]> {
+    let inverse = math::invert(view_proj)?;
+    Some(std::array::from_fn(|i| {
+        let ndc = |bit: usize| if i & bit == 0 { -1.0 } else { 1.0 };
+        math::transform_point(&inverse, [ndc(1), ndc(2), ndc(4)])
+    }))
+}
+
+/**
+ * The six planes bounding what a view-projection sees, normals pointing in,
+ * for culling bounds that can't be on screen
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frustum {
+    // Normal xyz and offset w, so points inside have normal·p + w >= 0
+    planes: [[f32; 4]; 6],
+}
+
+impl Frustum {
+    /**
+     * Planes of clip space's -w <= x, y, z <= w, pulled back to world space
+     * by adding and subtracting rows of the matrix
+     */
+    pub fn from_view_proj(m: &Mat4) -> Self {
+        let row = |i: usize| [m[i], m[4 + i], m[8 + i], m[12 + i]];
+        let w = row(3);
+        let planes = std::array::from_fn(|i| {
+            let axis = row(i / 2);
+            let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+            let plane: [f32; 4] = std::array::from_fn(|j| w[j] + sign * axis[j]);
+            let length = math::length([plane[0], plane[1], plane[2]]);
+            plane.map(|v| v / length)
+        });
+        Self { planes }
+    }
+
+    fn distance(plane: &[f32; 4], p: Vec3) -> f32 {
+        plane[0] * p[0] + plane[1] * p[1] + plane[2] * p[2] + plane[3]
+    }
+
+    /**
+     * Whether any of `aabb` might be inside. Boxes near a corner of the
+     * frustum can pass without touching it, which only costs a wasted draw
+     */
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // The corner furthest along the normal
+            let corner = std::array::from_fn(|axis| {
+                if plane[axis] >= 0.0 {
+                    aabb.max[axis]
+                } else {
+                    aabb.min[axis]
+                }
+            });
+            Self::distance(plane, corner) >= 0.0
+        })
+    }
+
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| Self::distance(plane, sphere.center) >= -sphere.radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: Vec3, expected: Vec3) {
+        let error = math::length(math::sub(actual, expected));
+        assert!(error < 1e-3, "{:?} != {:?}", actual, expected);
+    }
+
+    fn camera() -> Mat4 {
+        // 90 degrees tall and square, looking down -Z from z = 5
+        math::multiply(
+            &math::perspective(std::f32::consts::FRAC_PI_2, 1.0, 1.0, 10.0),
+            &math::look_at([0.0, 0.0, 5.0], [0.0; 3], [0.0, 1.0, 0.0]),
+        )
+    }
+
+    #[test]
+    fn culls_boxes_outside_any_plane() {
+        let frustum = Frustum::from_view_proj(&camera());
+        let unit = |center: Vec3| Aabb {
+            min: math::sub(center, [0.5; 3]),
+            max: math::add(center, [0.5; 3]),
+        };
+        assert!(frustum.intersects_aabb(&unit([0.0; 3])));
+        // Straddling the right side plane, x = 5 at the origin's depth
+        assert!(frustum.intersects_aabb(&unit([5.3, 0.0, 0.0])));
+        assert!(!frustum.intersects_aabb(&unit([6.2, 0.0, 0.0])));
+        // Behind the eye, and beyond the far plane at z = -5
+        assert!(!frustum.intersects_aabb(&unit([0.0, 0.0, 6.0])));
+        assert!(!frustum.intersects_aabb(&unit([0.0, 0.0, -6.0])));
+    }
+
+    #[test]
+    fn culls_spheres_by_their_radius() {
+        let frustum = Frustum::from_view_proj(&camera());
+        let sphere = |center: Vec3, radius: f32| Sphere { center, radius };
+        assert!(frustum.intersects_sphere(&sphere([0.0, 4.0, 0.0], 0.5)));
+        // The top plane leans out at 45 degrees, so the sphere at y = 6
+        // is 1 / sqrt(2) outside it
+        assert!(frustum.intersects_sphere(&sphere([0.0, 6.0, 0.0], 0.8)));
+        assert!(!frustum.intersects_sphere(&sphere([0.0, 6.0, 0.0], 0.6)));
+    }
+
+    #[test]
+    fn corners_span_the_near_and_far_planes() {
+        // Each plane is as wide as it is far from the eye
+        let seen = corners(&camera()).unwrap();
+        assert_near(seen[0], [-1.0, -1.0, 4.0]);
+        assert_near(seen[3], [1.0, 1.0, 4.0]);
+        assert_near(seen[4], [-10.0, -10.0, -5.0]);
+        assert_near(seen[7], [10.0, 10.0, -5.0]);
+        assert_eq!(corners(&[0.0; 16]), None);
+    }
+}
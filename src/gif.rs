@@ -0,0 +1,463 @@
+use std::collections::HashMap;
+
+use web_sys::WebGl2RenderingContext;
+
+use crate::gl_util::GlResource;
+use crate::readback::Readback;
+
+// Levels per channel of the fixed palette, a 6x7x6 color cube (green gets the
+// extra level, the eye being most sensitive to it)
+const LEVELS: [usize; 3] = [6, 7, 6];
+// Ordered dithering thresholds, in sixteenths
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+// Codes are at most 12 bits wide
+const MAX_CODES: u16 = 4096;
+// Every palette index fits in 8 bits
+const MIN_CODE_SIZE: u8 = 8;
+
+/**
+ * Animated GIF89a writer with one fixed, dithered 256-color palette shared by
+ * every frame, so frames can be encoded as they are captured. Loops forever
+ */
+pub struct GifEncoder {
+    width: u16,
+    height: u16,
+    bytes: Vec<u8>,
+}
+
+impl GifEncoder {
+    pub fn new(width: u16, height: u16) -> Self {
+        let mut bytes = b"GIF89a".to_vec();
+        bytes.extend(width.to_le_bytes());
+        bytes.extend(height.to_le_bytes());
+        // Global color table of 2^(7 + 1) entries, 8 bits per primary
+        bytes.extend([0xf7, 0, 0]);
+        bytes.extend(palette());
+        // Netscape extension: repeat forever
+        bytes.extend([0x21, 0xff, 0x0b]);
+        bytes.extend(b"NETSCAPE2.0");
+        bytes.extend([0x03, 0x01, 0x00, 0x00, 0x00]);
+        Self {
+            width,
+            height,
+            bytes,
+        }
+    }
+
+    /**
+     * Append a frame of RGBA pixels, rows top to bottom, shown for `delay`
+     * hundredths of a second
+     */
+    pub fn add_frame(&mut self, rgba: &[u8], delay: u16) {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let mut indices = Vec::with_capacity(width * height);
+        for (i, pixel) in rgba.chunks_exact(4).take(width * height).enumerate() {
+            let (x, y) = (i % width, i / width);
+            indices.push(quantize([pixel[0], pixel[1], pixel[2]], x, y));
+        }
+
+        // Graphic control extension: no transparency, just the delay
+        self.bytes.extend([0x21, 0xf9, 0x04, 0x00]);
+        self.bytes.extend(delay.to_le_bytes());
+        self.bytes.extend([0x00, 0x00]);
+        // Image descriptor covering the whole screen, using the global palette
+        self.bytes.push(0x2c);
+        self.bytes.extend([0, 0, 0, 0]);
+        self.bytes.extend(self.width.to_le_bytes());
+        self.bytes.extend(self.height.to_le_bytes());
+        self.bytes.push(0x00);
+
+        self.bytes.push(MIN_CODE_SIZE);
+        for block in lzw(&indices).chunks(255) {
+            self.bytes.push(block.len() as u8);
+            self.bytes.extend(block);
+        }
+        self.bytes.push(0x00);
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        self.bytes.push(0x3b);
+        self.bytes
+    }
+}
+
+/**
+ * Captures a fixed number of canvas frames a fixed interval apart into a GIF,
+ * shrunk by an integer factor to keep the file small
+ */
+pub struct GifCapture {
+    encoder: GifEncoder,
+    // Canvas size frames are read back at
+    width: usize,
+    height: usize,
+    frames: usize,
+    // Frames read back and encoded, and frames whose readback has started
+    captured: usize,
+    requested: usize,
+    pending: Option<Readback>,
+    interval_ms: f64,
+    // Timestamp the next frame is due at, `None` until the first capture
+    next_ms: Option<f64>,
+    shrink: usize,
+}
+
+impl GifCapture {
+    /**
+     * Capture of a `width` x `height` canvas, shrunk `shrink` times per axis
+     */
+    pub fn new(
+        width: usize,
+        height: usize,
+        shrink: usize,
+        frames: usize,
+        interval_ms: f64,
+    ) -> Self {
+        let shrink = shrink.max(1);
+        Self {
+            encoder: GifEncoder::new((width / shrink) as u16, (height / shrink) as u16),
+            width,
+            height,
+            frames,
+            captured: 0,
+            requested: 0,
+            pending: None,
+            interval_ms,
+            next_ms: None,
+            shrink,
+        }
+    }
+
+    /**
+     * Whether the frame rendered at `timestamp` (milliseconds) should be captured
+     */
+    pub fn due(&mut self, timestamp: f64) -> bool {
+        if self.requested >= self.frames || self.next_ms.is_some_and(|next| timestamp < next) {
+            return false;
+        }
+        // Keep to the schedule, but don't try to catch up after a stall
+        let next = self.next_ms.unwrap_or(timestamp) + self.interval_ms;
+        self.next_ms = Some(next.max(timestamp));
+        true
+    }
+
+    /**
+     * Start reading back the default framebuffer as the next frame. Call after
+     * drawing and before the browser presents it. Fails if the drawing buffer
+     * is no longer the size the capture started at, since every frame of a
+     * GIF has the same size
+     */
+    pub fn capture(&mut self, gl: &WebGl2RenderingContext) -> Result<(), String> {
+        let size = (
+            gl.drawing_buffer_width() as usize,
+            gl.drawing_buffer_height() as usize,
+        );
+        if size != (self.width, self.height) {
+            return Err(format!(
+                "The canvas changed size from {}x{} to {}x{} during the capture",
+                self.width, self.height, size.0, size.1
+            ));
+        }
+        // Frames are far enough apart that this hardly ever has to wait
+        if let Some(readback) = self.pending.take() {
+            self.encode(gl, readback);
+        }
+        let readback = Readback::start(gl, 0, 0, self.width as i32, self.height as i32)?;
+        self.pending = Some(readback);
+        self.requested += 1;
+        Ok(())
+    }
+
+    /**
+     * Encode the frame being read back once it has arrived; call every frame
+     */
+    pub fn poll(&mut self, gl: &WebGl2RenderingContext) -> Result<(), String> {
+        let Some(readback) = self.pending.take() else {
+            return Ok(());
+        };
+        match readback.is_ready(gl) {
+            Ok(true) => self.encode(gl, readback),
+            Ok(false) => self.pending = Some(readback),
+            Err(err) => {
+                readback.delete(gl);
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    fn encode(&mut self, gl: &WebGl2RenderingContext, readback: Readback) {
+        let pixels = readback.finish(gl);
+        let frame = shrink_flipped(&pixels, self.width, self.height, self.shrink);
+        let delay = (self.interval_ms / 10.0).round() as u16;
+        self.encoder.add_frame(&frame, delay);
+        self.captured += 1;
+    }
+
+    /**
+     * Frames captured so far and in total
+     */
+    pub fn progress(&self) -> (usize, usize) {
+        (self.captured, self.frames)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.captured >= self.frames
+    }
+
+    /**
+     * Give up on the capture, freeing a readback still in flight
+     */
+    pub fn cancel(self, gl: &WebGl2RenderingContext) {
+        if let Some(readback) = &self.pending {
+            readback.delete(gl);
+        }
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.encoder.finish()
+    }
+}
+
+/**
+ * Box-filter RGBA `pixels` (rows bottom to top, as GL reads them) down by
+ * `shrink` per axis, returning rows top to bottom
+ */
+fn shrink_flipped(pixels: &[u8], width: usize, height: usize, shrink: usize) -> Vec<u8> {
+    let (out_width, out_height) = (width / shrink, height / shrink);
+    let samples = (shrink * shrink) as u32;
+    let mut out = Vec::with_capacity(out_width * out_height * 4);
+    for out_y in 0..out_height {
+        // The first output row is the top of the canvas, the end of the GL rows
+        let y0 = height - (out_y + 1) * shrink;
+        for out_x in 0..out_width {
+            let mut sum = [0u32; 4];
+            for y in y0..y0 + shrink {
+                for x in out_x * shrink..(out_x + 1) * shrink {
+                    let i = (y * width + x) * 4;
+                    for (total, &value) in sum.iter_mut().zip(&pixels[i..i + 4]) {
+                        *total += value as u32;
+                    }
+                }
+            }
+            out.extend(sum.map(|total| ((total + samples / 2) / samples) as u8));
+        }
+    }
+    out
+}
+
+/**
+ * The color cube, padded with black to 256 entries
+ */
+fn palette() -> Vec<u8> {
+    let level = |index: usize, levels: usize| (index * 255 / (levels - 1)) as u8;
+    let mut colors = Vec::with_capacity(256 * 3);
+    for r in 0..LEVELS[0] {
+        for g in 0..LEVELS[1] {
+            for b in 0..LEVELS[2] {
+                colors.extend([
+                    level(r, LEVELS[0]),
+                    level(g, LEVELS[1]),
+                    level(b, LEVELS[2]),
+                ]);
+            }
+        }
+    }
+    colors.resize(256 * 3, 0);
+    colors
+}
+
+/**
+ * Palette index for `color` at pixel (`x`, `y`), dithered between the
+ * nearest levels
+ */
+fn quantize(color: [u8; 3], x: usize, y: usize) -> u8 {
+    let threshold = (BAYER[y % 4][x % 4] as f32 + 0.5) / 16.0;
+    let [r, g, b] = [0, 1, 2].map(|channel| {
+        let levels = LEVELS[channel];
+        let scaled = color[channel] as f32 / 255.0 * (levels - 1) as f32;
+        ((scaled + threshold) as usize).min(levels - 1)
+    });
+    ((r * LEVELS[1] + g) * LEVELS[2] + b) as u8
+}
+
+/**
+ * GIF-flavored LZW compression of palette indices: variable-width codes
+ * packed least significant bit first, starting over with a clear code once
+ * the code table is full
+ */
+fn lzw(indices: &[u8]) -> Vec<u8> {
+    let clear = 1u16 << MIN_CODE_SIZE;
+    let end = clear + 1;
+    let mut writer = BitWriter::default();
+    let mut table = HashMap::<(u16, u8), u16>::new();
+    let mut code_size = MIN_CODE_SIZE + 1;
+    let mut next_code = end + 1;
+    writer.write(clear, code_size);
+
+    let Some((&first, rest)) = indices.split_first() else {
+        writer.write(end, code_size);
+        return writer.finish();
+    };
+    let mut prefix = first as u16;
+    for &index in rest {
+        if let Some(&code) = table.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+        writer.write(prefix, code_size);
+        if next_code < MAX_CODES {
+            // The decoder widens its codes once its table reaches this entry
+            if next_code == 1 << code_size {
+                code_size += 1;
+            }
+            table.insert((prefix, index), next_code);
+            next_code += 1;
+        } else {
+            writer.write(clear, code_size);
+            table.clear();
+            code_size = MIN_CODE_SIZE + 1;
+            next_code = end + 1;
+        }
+        prefix = index as u16;
+    }
+    writer.write(prefix, code_size);
+    writer.write(end, code_size);
+    writer.finish()
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * Straightforward GIF LZW decoder to check the encoder against
+     */
+    fn decode(data: &[u8]) -> Vec<u8> {
+        let clear = 1usize << MIN_CODE_SIZE;
+        let end = clear + 1;
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let reset = |table: &mut Vec<Vec<u8>>| {
+            *table = (0..clear).map(|i| vec![i as u8]).collect();
+            table.extend([Vec::new(), Vec::new()]);
+        };
+        reset(&mut table);
+        let mut code_size = MIN_CODE_SIZE as usize + 1;
+        let (mut position, mut previous) = (0usize, None::<usize>);
+        let mut out = Vec::new();
+        loop {
+            let mut code = 0;
+            for bit in 0..code_size {
+                let index = position + bit;
+                code |= ((data[index / 8] >> (index % 8)) as usize & 1) << bit;
+            }
+            position += code_size;
+            if code == clear {
+                reset(&mut table);
+                code_size = MIN_CODE_SIZE as usize + 1;
+                previous = None;
+                continue;
+            }
+            if code == end {
+                return out;
+            }
+            let entry = match (table.get(code), previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) => {
+                    let mut entry = table[previous].clone();
+                    entry.push(table[previous][0]);
+                    entry
+                }
+                (None, None) => panic!("code {} before any entry", code),
+            };
+            if let Some(previous) = previous {
+                let mut added = table[previous].clone();
+                added.push(entry[0]);
+                table.push(added);
+                if table.len() == 1 << code_size && code_size < 12 {
+                    code_size += 1;
+                }
+            }
+            out.extend(&entry);
+            previous = Some(code);
+        }
+    }
+
+    #[test]
+    fn lzw_roundtrips() {
+        assert_eq!(decode(&lzw(&[])), Vec::<u8>::new());
+        assert_eq!(decode(&lzw(&[7])), vec![7]);
+        let repetitive: Vec<u8> = (0..5000).map(|i| (i / 7 % 3) as u8).collect();
+        assert_eq!(decode(&lzw(&repetitive)), repetitive);
+        // Noisy enough to fill the code table several times over
+        let mut state = 1u32;
+        let noisy: Vec<u8> = (0..40_000)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (state >> 16) as u8
+            })
+            .collect();
+        assert_eq!(decode(&lzw(&noisy)), noisy);
+    }
+
+    #[test]
+    fn quantizes_cube_colors_exactly() {
+        let palette = palette();
+        for color in [[0, 0, 0], [255, 255, 255], [255, 0, 0], [0, 255, 255]] {
+            for (x, y) in [(0, 0), (1, 2), (3, 3)] {
+                let index = quantize(color, x, y) as usize;
+                assert_eq!(palette[index * 3..index * 3 + 3], color);
+            }
+        }
+    }
+
+    #[test]
+    fn writes_a_complete_file() {
+        let mut encoder = GifEncoder::new(2, 1);
+        encoder.add_frame(&[255, 0, 0, 255, 0, 0, 255, 255], 5);
+        let bytes = encoder.finish();
+        assert_eq!(&bytes[..6], b"GIF89a");
+        assert_eq!(&bytes[6..10], &[2, 0, 1, 0]);
+        assert_eq!(bytes.last(), Some(&0x3b));
+        // Header, screen descriptor, palette and loop extension come first
+        let frame = 6 + 7 + 256 * 3 + 19;
+        assert_eq!(&bytes[frame..frame + 4], &[0x21, 0xf9, 0x04, 0x00]);
+        assert_eq!(&bytes[frame + 4..frame + 6], &5u16.to_le_bytes());
+    }
+
+    #[test]
+    fn shrinks_and_flips() {
+        // 2x2 RGBA, bottom row first: dark at the bottom, light at the top
+        let pixels = [
+            0, 0, 0, 255, 0, 0, 0, 255, //
+            200, 200, 200, 255, 200, 200, 200, 255,
+        ];
+        assert_eq!(shrink_flipped(&pixels, 2, 2, 1)[..4], [200, 200, 200, 255]);
+        assert_eq!(shrink_flipped(&pixels, 2, 2, 2), vec![100, 100, 100, 255]);
+    }
+}
@@ -0,0 +1,91 @@
+use dioxus::prelude::*;
+use std::collections::VecDeque;
+use web_sys::WebGl2RenderingContext;
+
+// Oldest entries are dropped past this, so a per-frame error can't grow it forever
+const CAPACITY: usize = 200;
+
+/**
+ * One GL error or initialization failure, with where it came from
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct GlError {
+    // Wall-clock milliseconds since the epoch
+    pub time: f64,
+    // Pass, draw or setup step that reported it, e.g. "scene: render"
+    pub source: String,
+    pub message: String,
+}
+
+impl GlError {
+    /**
+     * Local time of day as HH:MM:SS.mmm
+     */
+    pub fn timestamp(&self) -> String {
+        let date = js_sys::Date::new(&self.time.into());
+        format!(
+            "{:02}:{:02}:{:02}.{:03}",
+            date.get_hours(),
+            date.get_minutes(),
+            date.get_seconds(),
+            date.get_milliseconds()
+        )
+    }
+}
+
+/**
+ * Most recent errors, oldest first; a signal so the error panel updates live
+ */
+pub static GL_ERRORS: GlobalSignal<VecDeque<GlError>> = Signal::global(VecDeque::new);
+
+/**
+ * Record a failure from `source` in the log, and in the devtools console
+ */
+pub fn report(source: &str, message: impl Into<String>) {
+    let message = message.into();
+    tracing::error!(target: "renderer", "{}: {}", source, message);
+    let mut errors = GL_ERRORS.write();
+    if errors.len() == CAPACITY {
+        errors.pop_front();
+    }
+    errors.push_back(GlError {
+        time: js_sys::Date::now(),
+        source: source.to_string(),
+        message,
+    });
+}
+
+/**
+ * Drain `gl`'s error flags into the log, attributing them to `source`.
+ * Several flags can be set at once, so this loops until NO_ERROR
+ */
+pub fn check(gl: &WebGl2RenderingContext, source: &str) {
+    loop {
+        let error = gl.get_error();
+        if error == WebGl2RenderingContext::NO_ERROR {
+            return;
+        }
+        report(source, error_name(error));
+        // Reported once per loss rather than cleared like the other flags
+        if error == WebGl2RenderingContext::CONTEXT_LOST_WEBGL {
+            return;
+        }
+    }
+}
+
+/**
+ * Name of a `get_error` code
+ */
+pub fn error_name(error: u32) -> String {
+    match error {
+        WebGl2RenderingContext::INVALID_ENUM => "INVALID_ENUM".to_string(),
+        WebGl2RenderingContext::INVALID_VALUE => "INVALID_VALUE".to_string(),
+        WebGl2RenderingContext::INVALID_OPERATION => "INVALID_OPERATION".to_string(),
+        WebGl2RenderingContext::INVALID_FRAMEBUFFER_OPERATION => {
+            "INVALID_FRAMEBUFFER_OPERATION".to_string()
+        }
+        WebGl2RenderingContext::OUT_OF_MEMORY => "OUT_OF_MEMORY".to_string(),
+        WebGl2RenderingContext::CONTEXT_LOST_WEBGL => "CONTEXT_LOST_WEBGL".to_string(),
+        other => format!("GL error 0x{:04x}", other),
+    }
+}
@@ -0,0 +1,196 @@
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    HtmlCanvasElement, WebGl2RenderingContext, WebGlBuffer, WebGlFramebuffer, WebGlProgram,
+    WebGlRenderbuffer, WebGlShader, WebGlTexture, WebGlVertexArrayObject, WebglLoseContext,
+};
+
+use crate::debug_gl;
+
+/**
+ * The canvas's WebGL2 context, wrapped for debugging when that is enabled
+ */
+pub fn webgl2_context(canvas: &HtmlCanvasElement) -> Result<WebGl2RenderingContext, String> {
+    let context = canvas
+        .get_context("webgl2")
+        .map_err(|err| format!("Unable to create WebGL2 context: {:?}", err))?
+        .ok_or_else(|| "WebGL2 is unavailable or disabled in this browser".to_string())?;
+    let gl = context
+        .dyn_into::<WebGl2RenderingContext>()
+        .map_err(|_| "The canvas already has a different kind of context".to_string())?;
+    Ok(debug_gl::wrap(gl))
+}
+
+/**
+ * The canvas element with `id`, as the demos look theirs up once mounted
+ */
+pub fn canvas_by_id(id: &str) -> Result<HtmlCanvasElement, String> {
+    web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.get_element_by_id(id))
+        .and_then(|element| element.dyn_into::<HtmlCanvasElement>().ok())
+        .ok_or_else(|| format!("No canvas with id {}", id))
+}
+
+/**
+ * A WebGL2 context on a detached canvas, for the browser tests
+ */
+#[cfg(test)]
+pub fn test_context() -> WebGl2RenderingContext {
+    let canvas = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.create_element("canvas").ok())
+        .and_then(|element| element.dyn_into::<HtmlCanvasElement>().ok())
+        .expect("a document to create a canvas in");
+    webgl2_context(&canvas).expect("a WebGL2 context")
+}
+
+/**
+ * Whether WebGL2 works here at all, tried on a throwaway canvas so the app
+ * can explain the problem up front instead of each demo failing on its own.
+ * The context is lost again straight away, so it doesn't hold one of the
+ * browser's few live contexts until it is garbage collected
+ */
+pub fn probe_webgl2() -> Result<(), String> {
+    let canvas = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.create_element("canvas").ok())
+        .and_then(|element| element.dyn_into::<HtmlCanvasElement>().ok())
+        .ok_or_else(|| "No document to create a canvas in".to_string())?;
+    match canvas.get_context("webgl2") {
+        Ok(Some(context)) => {
+            let lose_context = context
+                .dyn_into::<WebGl2RenderingContext>()
+                .ok()
+                .and_then(|gl| gl.get_extension("WEBGL_lose_context").ok().flatten())
+                .map(|extension| extension.unchecked_into::<WebglLoseContext>());
+            if let Some(lose_context) = lose_context {
+                lose_context.lose_context();
+            }
+            Ok(())
+        }
+        Ok(None) => Err(
+            "The browser has WebGL2 turned off, or doesn't allow it on this GPU or driver"
+                .to_string(),
+        ),
+        Err(err) => Err(format!("Creating a WebGL2 context failed: {:?}", err)),
+    }
+}
+
+/**
+ * Compile a single shader stage, returning the info log on failure
+ */
+pub fn compile_shader(
+    gl: &WebGl2RenderingContext,
+    shader_type: u32,
+    source: &str,
+) -> Result<WebGlShader, String> {
+    let shader = gl
+        .create_shader(shader_type)
+        .ok_or_else(|| "Unable to create shader object".to_string())?;
+    gl.shader_source(&shader, source);
+    gl.compile_shader(&shader);
+
+    if gl
+        .get_shader_parameter(&shader, WebGl2RenderingContext::COMPILE_STATUS)
+        .as_bool()
+        .unwrap_or(false)
+    {
+        Ok(shader)
+    } else {
+        let log = gl.get_shader_info_log(&shader).unwrap_or_default();
+        gl.delete_shader(Some(&shader));
+        Err(log)
+    }
+}
+
+/**
+ * Compile both stages and link them into a program
+ */
+pub fn create_program(
+    gl: &WebGl2RenderingContext,
+    vert_source: &str,
+    frag_source: &str,
+) -> Result<WebGlProgram, String> {
+    create_transform_feedback_program(gl, vert_source, frag_source, &[])
+}
+
+/**
+ * Like `create_program`, but captures the given vertex outputs into
+ * interleaved transform feedback buffers
+ */
+pub fn create_transform_feedback_program(
+    gl: &WebGl2RenderingContext,
+    vert_source: &str,
+    frag_source: &str,
+    varyings: &[&str],
+) -> Result<WebGlProgram, String> {
+    let vert_shader = compile_shader(gl, WebGl2RenderingContext::VERTEX_SHADER, vert_source)
+        .map_err(|log| format!("Vertex shader compilation error: {}", log))?;
+    let frag_shader = compile_shader(gl, WebGl2RenderingContext::FRAGMENT_SHADER, frag_source)
+        .map_err(|log| format!("Fragment shader compilation error: {}", log))?;
+
+    let program = gl
+        .create_program()
+        .ok_or_else(|| "Unable to create program object".to_string())?;
+    gl.attach_shader(&program, &vert_shader);
+    gl.attach_shader(&program, &frag_shader);
+
+    // Varyings must be declared before linking
+    if !varyings.is_empty() {
+        let names = varyings
+            .iter()
+            .map(|name| JsValue::from_str(name))
+            .collect::<js_sys::Array>();
+        gl.transform_feedback_varyings(
+            &program,
+            &names,
+            WebGl2RenderingContext::INTERLEAVED_ATTRIBS,
+        );
+    }
+
+    gl.link_program(&program);
+
+    // Shaders are no longer needed once linked into the program
+    gl.delete_shader(Some(&vert_shader));
+    gl.delete_shader(Some(&frag_shader));
+
+    if gl
+        .get_program_parameter(&program, WebGl2RenderingContext::LINK_STATUS)
+        .as_bool()
+        .unwrap_or(false)
+    {
+        Ok(program)
+    } else {
+        let log = gl.get_program_info_log(&program).unwrap_or_default();
+        gl.delete_program(Some(&program));
+        Err(format!("Program linking error: {}", log))
+    }
+}
+
+/**
+ * Something that owns GPU memory and has to be deleted explicitly; the
+ * browser only frees it once the context itself goes away
+ */
+pub trait GlResource {
+    fn delete(&self, gl: &WebGl2RenderingContext);
+}
+
+macro_rules! gl_resource {
+    ($($ty:ty => $delete:ident),* $(,)?) => {
+        $(impl GlResource for $ty {
+            fn delete(&self, gl: &WebGl2RenderingContext) {
+                gl.$delete(Some(self));
+            }
+        })*
+    };
+}
+
+gl_resource! {
+    WebGlBuffer => delete_buffer,
+    WebGlFramebuffer => delete_framebuffer,
+    WebGlProgram => delete_program,
+    WebGlRenderbuffer => delete_renderbuffer,
+    WebGlShader => delete_shader,
+    WebGlTexture => delete_texture,
+    WebGlVertexArrayObject => delete_vertex_array,
+}
@@ -0,0 +1,475 @@
+// Shared with build.rs, which only validates
+#![allow(dead_code)]
+
+use std::error::Error;
+
+pub use naga::ShaderStage;
+
+// Binding of the uniform block; sampled textures follow it in pairs
+pub const UNIFORM_BINDING: u32 = 0;
+
+/**
+ * A top-level declaration: its GLSL type, without precision, and name
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Declaration {
+    pub ty: String,
+    pub name: String,
+}
+
+/**
+ * What the stages of one program declare, so each stage can be rewritten
+ * with the same uniform block, texture bindings and varying locations
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Interface {
+    // Non-opaque uniforms, in block order
+    pub uniforms: Vec<Declaration>,
+    // Sampler uniforms, in binding order
+    pub samplers: Vec<Declaration>,
+    // Vertex outputs, which fragment inputs are matched to by name
+    varyings: Vec<String>,
+}
+
+impl Interface {
+    /**
+     * Declarations across a program's stages. Ones inside `#ifdef`s count
+     * too, so every permutation gets the same layout
+     */
+    pub fn new(stages: &[(ShaderStage, &str)]) -> Self {
+        let mut interface = Interface::default();
+        for (stage, source) in stages {
+            for line in strip_comments(source).lines() {
+                let Some((qualifier, declaration)) = declaration(line) else {
+                    continue;
+                };
+                let list = match (qualifier, stage) {
+                    ("uniform", _) if is_sampler(&declaration.ty) => &mut interface.samplers,
+                    ("uniform", _) => &mut interface.uniforms,
+                    ("out", ShaderStage::Vertex) => {
+                        interface.varyings.push(declaration.name);
+                        continue;
+                    }
+                    _ => continue,
+                };
+                if !list.iter().any(|known| known.name == declaration.name) {
+                    list.push(declaration);
+                }
+            }
+        }
+        interface
+    }
+}
+
+fn is_sampler(ty: &str) -> bool {
+    ty.starts_with("sampler")
+}
+
+/**
+ * A `uniform`, `in` or `out` declaration on `line` without a layout of its
+ * own: the qualifier and what it declares
+ */
+fn declaration(line: &str) -> Option<(&'static str, Declaration)> {
+    let words: Vec<&str> = line
+        .trim()
+        .trim_end_matches(';')
+        .split_whitespace()
+        .filter(|word| !matches!(*word, "flat" | "smooth" | "centroid"))
+        .filter(|word| !matches!(*word, "highp" | "mediump" | "lowp"))
+        .collect();
+    let qualifier = match *words.first()? {
+        "uniform" => "uniform",
+        "in" => "in",
+        "out" => "out",
+        _ => return None,
+    };
+    let [_, ty, name] = words[..] else {
+        return None;
+    };
+    Some((
+        qualifier,
+        Declaration {
+            ty: ty.to_string(),
+            name: name.to_string(),
+        },
+    ))
+}
+
+/**
+ * `source`, a GLSL ES 3.00 stage, rewritten as the Vulkan-flavoured GLSL 4.50
+ * naga's frontend reads: the plain uniforms gathered into one std140 block
+ * at binding 0 (laid out like WGSL's uniform buffers, for the types shaders
+ * use), each sampler split into a texture and a sampler at the next two
+ * bindings, `bool` uniforms stored as `uint`, locations on every `in` and
+ * `out`, and precision statements dropped. A vertex shader that only
+ * feeds transform feedback gets a `main` that also writes `gl_Position`,
+ * which naga insists on. Also returns the original line of each line of the
+ * result, for error messages
+ */
+pub fn to_vulkan(source: &str, stage: ShaderStage, interface: &Interface) -> (String, Vec<usize>) {
+    let mut lines = Vec::new();
+    let mut push = |line: String, origin: usize| lines.push((line, origin));
+    push("#version 450".to_string(), 1);
+
+    if !interface.uniforms.is_empty() {
+        push(
+            format!(
+                "layout(std140, set = 0, binding = {}) uniform Uniforms {{",
+                UNIFORM_BINDING
+            ),
+            1,
+        );
+        for uniform in &interface.uniforms {
+            if uniform.ty == "bool" {
+                push(format!("    uint {}_bool;", uniform.name), 1);
+            } else {
+                push(format!("    {} {};", uniform.ty, uniform.name), 1);
+            }
+        }
+        push("};".to_string(), 1);
+        for uniform in interface
+            .uniforms
+            .iter()
+            .filter(|uniform| uniform.ty == "bool")
+        {
+            push(format!("#define {0} ({0}_bool != 0u)", uniform.name), 1);
+        }
+    }
+    for (index, sampler) in interface.samplers.iter().enumerate() {
+        let binding = UNIFORM_BINDING + 1 + index as u32 * 2;
+        let texture = sampler.ty.replacen("sampler", "texture", 1);
+        push(
+            format!(
+                "layout(set = 0, binding = {}) uniform {} {}_texture;",
+                binding, texture, sampler.name
+            ),
+            1,
+        );
+        push(
+            format!(
+                "layout(set = 0, binding = {}) uniform sampler {}_sampler;",
+                binding + 1,
+                sampler.name
+            ),
+            1,
+        );
+        push(
+            format!(
+                "#define {0} {1}({0}_texture, {0}_sampler)",
+                sampler.name, sampler.ty
+            ),
+            1,
+        );
+    }
+
+    // Locations already given with `layout(location = N)`
+    let taken: Vec<u32> = source.lines().filter_map(explicit_location).collect();
+    let free = |next: &mut u32| {
+        while taken.contains(next) {
+            *next += 1;
+        }
+        *next += 1;
+        *next - 1
+    };
+    let (mut next_input, mut next_output, mut unmatched) = (0, 0, 0);
+    let source = if stage == ShaderStage::Vertex && !source.contains("gl_Position") {
+        wrap_main(source, "gl_Position = vec4(0.0);")
+    } else {
+        source.to_string()
+    };
+    for (index, line) in strip_comments(&source).lines().enumerate().skip(1) {
+        let origin = index + 1;
+        let line = ["gl_VertexID", "gl_InstanceID"]
+            .iter()
+            .zip(["gl_VertexIndex", "gl_InstanceIndex"])
+            .fold(line.to_string(), |line, (from, to)| {
+                replace_identifier(&line, from, to)
+            });
+        let trimmed = line.trim();
+        let location = match declaration(trimmed) {
+            _ if trimmed.starts_with("precision ") => {
+                push(String::new(), origin);
+                continue;
+            }
+            Some(("uniform", _)) => {
+                push(String::new(), origin);
+                continue;
+            }
+            Some(("in", _)) if stage == ShaderStage::Vertex => free(&mut next_input),
+            Some(("out", _)) if stage == ShaderStage::Fragment => free(&mut next_output),
+            // Varyings take the place of the vertex output with their name
+            Some((_, declaration)) => interface
+                .varyings
+                .iter()
+                .position(|name| *name == declaration.name)
+                .map(|position| position as u32)
+                .unwrap_or_else(|| {
+                    unmatched += 1;
+                    (interface.varyings.len() + unmatched - 1) as u32
+                }),
+            None => {
+                push(line.clone(), origin);
+                continue;
+            }
+        };
+        push(
+            format!("layout(location = {}) {}", location, trimmed),
+            origin,
+        );
+    }
+
+    let (lines, origins): (Vec<String>, Vec<usize>) = lines.into_iter().unzip();
+    (lines.join("\n") + "\n", origins)
+}
+
+/**
+ * `source` with `main` renamed, and a new `main` on an added last line that
+ * calls it and then runs `epilogue`
+ */
+pub fn wrap_main(source: &str, epilogue: &str) -> String {
+    let mut wrapped: String = source
+        .lines()
+        .map(|line| {
+            if line.trim().starts_with("void main(") {
+                line.replacen("main", "wrapped_main", 1) + "\n"
+            } else {
+                line.to_string() + "\n"
+            }
+        })
+        .collect();
+    wrapped.push_str(&format!("void main() {{ wrapped_main(); {} }}\n", epilogue));
+    wrapped
+}
+
+fn explicit_location(line: &str) -> Option<u32> {
+    let rest = line.trim().strip_prefix("layout")?.trim_start();
+    let rest = rest.strip_prefix('(')?;
+    let (inside, _) = rest.split_once(')')?;
+    let (key, value) = inside.split_once('=')?;
+    if key.trim() != "location" {
+        return None;
+    }
+    value.trim().parse().ok()
+}
+
+/**
+ * `line` with whole-word uses of `from` replaced by `to`
+ */
+pub fn replace_identifier(line: &str, from: &str, to: &str) -> String {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find(from) {
+        let end = start + from.len();
+        let before = rest[..start].chars().next_back();
+        let after = rest[end..].chars().next();
+        out.push_str(&rest[..start]);
+        if before.is_some_and(is_word) || after.is_some_and(is_word) {
+            out.push_str(from);
+        } else {
+            out.push_str(to);
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/**
+ * Parse and validate one stage of a program with `defines` set, as naga
+ * sees it after `to_vulkan`. Errors are "line: message", with the line in
+ * `source`
+ */
+pub fn parse(
+    source: &str,
+    stage: ShaderStage,
+    interface: &Interface,
+    defines: &[&str],
+) -> Result<(naga::Module, naga::valid::ModuleInfo), Vec<String>> {
+    let (vulkan, origins) = to_vulkan(source, stage, interface);
+    let line = |location: naga::SourceLocation| {
+        origins
+            .get(location.line_number as usize - 1)
+            .copied()
+            .unwrap_or(1)
+    };
+
+    let mut options = naga::front::glsl::Options::from(stage);
+    for define in defines {
+        options.defines.insert(define.to_string(), String::new());
+    }
+    let module = naga::front::glsl::Frontend::default()
+        .parse(&options, &vulkan)
+        .map_err(|errors| {
+            errors
+                .errors
+                .iter()
+                .map(|err| {
+                    let number = err.location(&vulkan).map(line).unwrap_or(1);
+                    format!("{}: {}", number, err.kind)
+                })
+                .collect::<Vec<_>>()
+        })?;
+    let info = naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::empty(),
+    )
+    .validate(&module)
+    .map_err(|err| {
+        // The outer error only names the function; the causes say what's wrong
+        let mut message = err.as_inner().to_string();
+        let mut cause = err.as_inner().source();
+        while let Some(inner) = cause {
+            message.push_str(&format!(": {}", inner));
+            cause = inner.source();
+        }
+        // Spans run from the function inwards to the expression at fault
+        let number = err
+            .spans()
+            .last()
+            .map(|(span, _)| line(span.location(&vulkan)))
+            .unwrap_or(1);
+        vec![format!("{}: {}", number, message)]
+    })?;
+    Ok((module, info))
+}
+
+/**
+ * `source` with comments blanked out, keeping the line breaks so line
+ * numbers still match
+ */
+pub fn strip_comments(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('/', Some('/')) => {
+                while chars.peek().is_some_and(|&next| next != '\n') {
+                    chars.next();
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        out.push('\n');
+                    }
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VERT: &str = "#version 300 es
+in vec3 position;
+uniform mat4 modelViewProjection;
+uniform bool flipped;
+out vec2 vUv;
+void main() {
+    vUv = flipped ? -position.xy : position.xy;
+    gl_Position = modelViewProjection * vec4(position, 1.0);
+}
+";
+
+    const FRAG: &str = "#version 300 es
+precision mediump float;
+in vec2 vUv;
+uniform sampler2D image;
+// Multiplies the image
+uniform vec4 tint;
+out vec4 fragColor;
+void main() {
+    fragColor = texture(image, vUv) * tint;
+}
+";
+
+    fn program() -> Interface {
+        Interface::new(&[(ShaderStage::Vertex, VERT), (ShaderStage::Fragment, FRAG)])
+    }
+
+    #[test]
+    fn gathers_uniforms_and_samplers_across_stages() {
+        let interface = program();
+        let names = |list: &[Declaration]| -> Vec<String> {
+            list.iter()
+                .map(|declaration| declaration.name.clone())
+                .collect()
+        };
+        assert_eq!(
+            names(&interface.uniforms),
+            ["modelViewProjection", "flipped", "tint"]
+        );
+        assert_eq!(names(&interface.samplers), ["image"]);
+
+        let (fragment, origins) = to_vulkan(FRAG, ShaderStage::Fragment, &interface);
+        assert!(fragment.starts_with("#version 450\n"));
+        // Both stages declare the whole block
+        assert!(fragment.contains("    uint flipped_bool;\n    vec4 tint;"));
+        assert!(fragment.contains("binding = 1) uniform texture2D image_texture;"));
+        assert!(fragment.contains("#define image sampler2D(image_texture, image_sampler)"));
+        assert!(fragment.contains("layout(location = 0) in vec2 vUv;"));
+        assert!(fragment.contains("layout(location = 0) out vec4 fragColor;"));
+        // Lines keep track of where they came from
+        let main = fragment
+            .lines()
+            .position(|line| line.starts_with("void main"))
+            .unwrap();
+        assert_eq!(origins[main], 8);
+    }
+
+    #[test]
+    fn validates_each_stage() {
+        let interface = program();
+        assert!(parse(VERT, ShaderStage::Vertex, &interface, &[]).is_ok());
+        assert!(parse(FRAG, ShaderStage::Fragment, &interface, &[]).is_ok());
+
+        let errors =
+            |source: &str| parse(source, ShaderStage::Fragment, &interface, &[]).unwrap_err();
+        let undeclared = FRAG.replace("* tint", "* tnit");
+        assert_eq!(errors(&undeclared), ["9: Unknown variable: tnit"]);
+        let mistyped = FRAG.replace("* tint", "* tint.xyz");
+        assert!(
+            errors(&mistyped)[0].starts_with("9: "),
+            "{:?}",
+            errors(&mistyped)
+        );
+    }
+
+    #[test]
+    fn only_validates_the_defined_branch() {
+        let frag = FRAG.replace(
+            "* tint;",
+            "* tint;\n#ifdef BROKEN\n    fragColor = 1;\n#endif",
+        );
+        let interface = program();
+        assert!(parse(&frag, ShaderStage::Fragment, &interface, &[]).is_ok());
+        assert!(parse(&frag, ShaderStage::Fragment, &interface, &["BROKEN"]).is_err());
+    }
+
+    #[test]
+    fn gives_feedback_shaders_a_position() {
+        let vert = "#version 300 es
+in float age;
+out float vAge;
+void main() {
+    vAge = age + 1.0;
+}
+";
+        let interface = Interface::new(&[(ShaderStage::Vertex, vert)]);
+        assert!(parse(vert, ShaderStage::Vertex, &interface, &[]).is_ok());
+        let wrapped = wrap_main(vert, "gl_Position = vec4(0.0);");
+        assert!(wrapped.contains("void wrapped_main() {"));
+        assert!(wrapped.ends_with("void main() { wrapped_main(); gl_Position = vec4(0.0); }\n"));
+    }
+}
@@ -0,0 +1,676 @@
+use std::collections::HashMap;
+
+use crate::json;
+use crate::material::BlendMode;
+use crate::math::{self, Mat4};
+use crate::mesh::MeshData;
+use crate::scene::{Scene, Transform};
+
+const GLB_MAGIC: u32 = 0x4654_6c67;
+const CHUNK_JSON: u32 = 0x4e4f_534a;
+const CHUNK_BIN: u32 = 0x004e_4942;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const BYTE: u32 = 5120;
+const UNSIGNED_BYTE: u32 = 5121;
+const SHORT: u32 = 5122;
+const UNSIGNED_SHORT: u32 = 5123;
+const UNSIGNED_INT: u32 = 5125;
+const FLOAT: u32 = 5126;
+
+/**
+ * Serialize the scene's objects and static props as a binary glTF 2.0 file:
+ * one node per object with its transform, one mesh per mesh/material pair in
+ * use, vertex colors as `COLOR_0` where the material uses them, and materials as PBR base colors. Textures
+ * only exist on the GPU, so textured materials export their base color only
+ */
+pub fn export_glb(scene: &Scene) -> Vec<u8> {
+    let mut bin = Vec::new();
+    let mut buffer_views = Vec::new();
+    let mut accessors = Vec::new();
+
+    // Vertex data is written once per scene mesh, even if several materials use it
+    let mut mesh_accessors = HashMap::<usize, (usize, usize, usize)>::new();
+    let mut gltf_meshes = Vec::new();
+    let mut mesh_for_pair = HashMap::<(usize, usize), usize>::new();
+
+    let instances = scene
+        .objects
+        .iter()
+        .map(|object| {
+            (
+                object.name.clone(),
+                object.mesh,
+                object.material,
+                &object.transform,
+            )
+        })
+        .chain(scene.statics.iter().enumerate().map(|(i, prop)| {
+            (
+                format!("Prop {}", i + 1),
+                prop.mesh,
+                prop.material,
+                &prop.transform,
+            )
+        }));
+
+    let mut nodes = Vec::new();
+    for (name, mesh, material, transform) in instances {
+        let data = &scene.meshes[mesh];
+        let (position, color, indices) = *mesh_accessors
+            .entry(mesh)
+            .or_insert_with(|| write_mesh(data, &mut bin, &mut buffer_views, &mut accessors));
+        let gltf_mesh = *mesh_for_pair.entry((mesh, material)).or_insert_with(|| {
+            // glTF always multiplies `COLOR_0` in, so only materials using it get it
+            let color = if scene.materials[material].features.vertex_colors {
+                format!(r#","COLOR_0":{}"#, color)
+            } else {
+                String::new()
+            };
+            gltf_meshes.push(format!(
+                r#"{{"primitives":[{{"attributes":{{"POSITION":{}{}}},"indices":{},"material":{}}}]}}"#,
+                position, color, indices, material
+            ));
+            gltf_meshes.len() - 1
+        });
+        nodes.push(node_json(&name, gltf_mesh, transform));
+    }
+
+    let materials: Vec<String> = scene
+        .materials
+        .iter()
+        .map(|material| {
+            let [r, g, b, a] = material.color;
+            let alpha_mode = match material.blend {
+                BlendMode::Opaque => "OPAQUE",
+                BlendMode::Alpha => "BLEND",
+            };
+            // Unlit materials are marked so viewers don't shade them
+            let unlit = if material.features.lighting {
+                ""
+            } else {
+                r#","extensions":{"KHR_materials_unlit":{}}"#
+            };
+            format!(
+                r#"{{"name":{},"pbrMetallicRoughness":{{"baseColorFactor":[{},{},{},{}],"metallicFactor":0,"roughnessFactor":1}},"alphaMode":"{}","doubleSided":{}{}}}"#,
+                json_string(&material.name),
+                r,
+                g,
+                b,
+                a,
+                alpha_mode,
+                material.double_sided,
+                unlit
+            )
+        })
+        .collect();
+    let uses_unlit = scene
+        .materials
+        .iter()
+        .any(|material| !material.features.lighting);
+
+    let json = format!(
+        r#"{{"asset":{{"version":"2.0","generator":"webgl-1"}},{}"scene":0,"scenes":[{{"nodes":[{}]}}],"nodes":[{}],"meshes":[{}],"materials":[{}],"accessors":[{}],"bufferViews":[{}],"buffers":[{{"byteLength":{}}}]}}"#,
+        if uses_unlit {
+            r#""extensionsUsed":["KHR_materials_unlit"],"#
+        } else {
+            ""
+        },
+        (0..nodes.len())
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(","),
+        nodes.join(","),
+        gltf_meshes.join(","),
+        materials.join(","),
+        accessors.join(","),
+        buffer_views.join(","),
+        bin.len()
+    );
+
+    let mut json = json.into_bytes();
+    json.resize(json.len().next_multiple_of(4), b' ');
+    bin.resize(bin.len().next_multiple_of(4), 0);
+
+    let total = 12 + 8 + json.len() + 8 + bin.len();
+    let mut glb = Vec::with_capacity(total);
+    glb.extend(GLB_MAGIC.to_le_bytes());
+    glb.extend(2u32.to_le_bytes());
+    glb.extend((total as u32).to_le_bytes());
+    glb.extend((json.len() as u32).to_le_bytes());
+    glb.extend(CHUNK_JSON.to_le_bytes());
+    glb.extend(json);
+    glb.extend((bin.len() as u32).to_le_bytes());
+    glb.extend(CHUNK_BIN.to_le_bytes());
+    glb.extend(bin);
+    glb
+}
+
+/**
+ * Append a mesh's positions, colors and indices to the binary chunk, returning
+ * the accessor indices for each
+ */
+fn write_mesh(
+    mesh: &MeshData,
+    bin: &mut Vec<u8>,
+    buffer_views: &mut Vec<String>,
+    accessors: &mut Vec<String>,
+) -> (usize, usize, usize) {
+    let vertex_count = mesh.positions.len() / 3;
+    let bounds = mesh.bounds();
+
+    let mut view = |bytes: Vec<u8>, target: u32| {
+        // Every view starts 4-byte aligned, as float accessors require
+        bin.resize(bin.len().next_multiple_of(4), 0);
+        buffer_views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":{}}}"#,
+            bin.len(),
+            bytes.len(),
+            target
+        ));
+        bin.extend(bytes);
+        buffer_views.len() - 1
+    };
+    let floats = |values: &[f32]| values.iter().flat_map(|v| v.to_le_bytes()).collect();
+
+    let positions = view(floats(&mesh.positions), ARRAY_BUFFER);
+    let colors = view(floats(&mesh.colors), ARRAY_BUFFER);
+    let indices = view(
+        mesh.indices.iter().flat_map(|i| i.to_le_bytes()).collect(),
+        ELEMENT_ARRAY_BUFFER,
+    );
+
+    // Viewers need position bounds to frame the model
+    accessors.push(format!(
+        r#"{{"bufferView":{},"componentType":{},"count":{},"type":"VEC3","min":{},"max":{}}}"#,
+        positions,
+        FLOAT,
+        vertex_count,
+        json_floats(&bounds.min),
+        json_floats(&bounds.max)
+    ));
+    accessors.push(format!(
+        r#"{{"bufferView":{},"componentType":{},"count":{},"type":"VEC3"}}"#,
+        colors, FLOAT, vertex_count
+    ));
+    accessors.push(format!(
+        r#"{{"bufferView":{},"componentType":{},"count":{},"type":"SCALAR"}}"#,
+        indices,
+        UNSIGNED_SHORT,
+        mesh.indices.len()
+    ));
+    let first = accessors.len() - 3;
+    (first, first + 1, first + 2)
+}
+
+fn node_json(name: &str, mesh: usize, transform: &Transform) -> String {
+    format!(
+        r#"{{"name":{},"mesh":{},"translation":{},"rotation":{},"scale":{}}}"#,
+        json_string(name),
+        mesh,
+        json_floats(&transform.translation),
+        json_floats(&euler_to_quaternion(transform.rotation)),
+        json_floats(&transform.scale)
+    )
+}
+
+/**
+ * The quaternion for `Transform`'s rotation order (X, then Y, then Z), as
+ * glTF's `[x, y, z, w]`
+ */
+fn euler_to_quaternion(rotation: [f32; 3]) -> [f32; 4] {
+    // `math::rotation_matrix_*` turn clockwise looking down the axis, hence -angle
+    let (sx, cx) = (-rotation[0] * 0.5).sin_cos();
+    let (sy, cy) = (-rotation[1] * 0.5).sin_cos();
+    let (sz, cz) = (-rotation[2] * 0.5).sin_cos();
+    // qz * qy * qx
+    [
+        sx * cy * cz - cx * sy * sz,
+        cx * sy * cz + sx * cy * sz,
+        cx * cy * sz - sx * sy * cz,
+        cx * cy * cz + sx * sy * sz,
+    ]
+}
+
+fn json_floats(values: &[f32]) -> String {
+    let values: Vec<String> = values.iter().map(|v| format!("{}", v)).collect();
+    format!("[{}]", values.join(","))
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/**
+ * Load the triangle geometry of a glTF 2.0 file (`.glb`, or `.gltf` with
+ * embedded base64 buffers) as one mesh: every primitive in the default scene
+ * with its node transforms baked in. Vertices are colored by `COLOR_0` times
+ * the material's base color; textures, skins and animations are ignored
+ */
+pub fn import(bytes: &[u8]) -> Result<MeshData, String> {
+    let (document, bin) = if bytes.starts_with(&GLB_MAGIC.to_le_bytes()) {
+        split_glb(bytes)?
+    } else {
+        let source = std::str::from_utf8(bytes).map_err(|_| "glTF is not valid UTF-8")?;
+        (source, None)
+    };
+    let document = json::parse(document)?;
+    let buffers = document
+        .get("buffers")
+        .and_then(json::Value::as_array)
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+        .map(
+            |(index, buffer)| match buffer.get("uri").and_then(json::Value::as_str) {
+                Some(uri) => decode_data_uri(uri),
+                // Only the GLB's own buffer may leave out the URI
+                None if index == 0 => bin
+                    .map(<[u8]>::to_vec)
+                    .ok_or_else(|| "glTF buffer 0 has no data".to_string()),
+                None => Err(format!("glTF buffer {} has no data", index)),
+            },
+        )
+        .collect::<Result<Vec<_>, _>>()?;
+    let importer = Importer {
+        document: &document,
+        buffers,
+    };
+
+    let nodes = importer.list("nodes");
+    let roots: Vec<usize> = match importer.list("scenes").get(
+        document
+            .get("scene")
+            .and_then(json::Value::as_usize)
+            .unwrap_or(0),
+    ) {
+        Some(scene) => indices(scene.get("nodes")),
+        // No scenes: every node nobody lists as a child is a root
+        None => {
+            let children: Vec<usize> = nodes
+                .iter()
+                .flat_map(|node| indices(node.get("children")))
+                .collect();
+            (0..nodes.len())
+                .filter(|node| !children.contains(node))
+                .collect()
+        }
+    };
+
+    let mut mesh = MeshData::default();
+    let mut stack: Vec<(usize, Mat4)> = roots
+        .into_iter()
+        .map(|node| (node, math::identity()))
+        .collect();
+    // Bounds the walk in case a malformed file has cycles
+    let mut visits = 0;
+    while let Some((index, parent)) = stack.pop() {
+        visits += 1;
+        if visits > nodes.len().max(1) * 4 {
+            return Err("glTF node hierarchy has cycles".to_string());
+        }
+        let node = nodes
+            .get(index)
+            .ok_or_else(|| format!("glTF node {} is missing", index))?;
+        let world = math::multiply(&parent, &node_matrix(node));
+        if let Some(gltf_mesh) = node.get("mesh").and_then(json::Value::as_usize) {
+            importer.append_mesh(gltf_mesh, &world, &mut mesh)?;
+        }
+        stack.extend(
+            indices(node.get("children"))
+                .into_iter()
+                .map(|child| (child, world)),
+        );
+    }
+
+    if mesh.indices.is_empty() {
+        return Err("glTF file has no triangles".to_string());
+    }
+    Ok(mesh)
+}
+
+// Decodes one little-endian component
+type ReadComponent = fn(&[u8]) -> f32;
+
+struct Importer<'a> {
+    document: &'a json::Value,
+    buffers: Vec<Vec<u8>>,
+}
+
+impl Importer<'_> {
+    fn list(&self, key: &str) -> &[json::Value] {
+        self.document
+            .get(key)
+            .and_then(json::Value::as_array)
+            .unwrap_or_default()
+    }
+
+    fn append_mesh(&self, index: usize, world: &Mat4, out: &mut MeshData) -> Result<(), String> {
+        let primitives = self
+            .list("meshes")
+            .get(index)
+            .and_then(|mesh| mesh.get("primitives"))
+            .and_then(json::Value::as_array)
+            .ok_or_else(|| format!("glTF mesh {} is missing", index))?;
+        for primitive in primitives {
+            // Points and lines have nothing to fill
+            if primitive
+                .get("mode")
+                .and_then(json::Value::as_usize)
+                .unwrap_or(4)
+                != 4
+            {
+                continue;
+            }
+            let attributes = primitive.get("attributes");
+            let position = attributes
+                .and_then(|attributes| attributes.get("POSITION"))
+                .and_then(json::Value::as_usize)
+                .ok_or("glTF primitive has no positions")?;
+            let (positions, _) = self.read_accessor(position)?;
+            let vertex_count = positions.len() / 3;
+
+            let base_color = primitive
+                .get("material")
+                .and_then(json::Value::as_usize)
+                .and_then(|material| self.list("materials").get(material))
+                .and_then(|material| material.get("pbrMetallicRoughness"))
+                .and_then(|pbr| pbr.get("baseColorFactor"))
+                .and_then(json::Value::as_array)
+                .map(|factor| {
+                    let channel =
+                        |i: usize| factor.get(i).and_then(json::Value::as_f64).unwrap_or(1.0);
+                    [channel(0), channel(1), channel(2)].map(|c| c as f32)
+                })
+                .unwrap_or([1.0; 3]);
+            let colors = match attributes
+                .and_then(|attributes| attributes.get("COLOR_0"))
+                .and_then(json::Value::as_usize)
+            {
+                Some(color) => {
+                    let (colors, components) = self.read_accessor(color)?;
+                    if components < 3 || colors.len() != vertex_count * components {
+                        return Err("glTF COLOR_0 doesn't match the positions".to_string());
+                    }
+                    colors
+                        .chunks_exact(components)
+                        .flat_map(|color| [0, 1, 2].map(|i| color[i] * base_color[i]))
+                        .collect()
+                }
+                None => base_color.repeat(vertex_count),
+            };
+
+            let indices: Vec<usize> = match primitive.get("indices").and_then(json::Value::as_usize)
+            {
+                Some(indices) => self
+                    .read_accessor(indices)?
+                    .0
+                    .into_iter()
+                    .map(|index| index as usize)
+                    .collect(),
+                None => (0..vertex_count).collect(),
+            };
+
+            let base = out.positions.len() / 3;
+            if base + vertex_count > u16::MAX as usize + 1 {
+                return Err("glTF model has more than 65536 vertices".to_string());
+            }
+            if indices.iter().any(|&index| index >= vertex_count) {
+                return Err("glTF index out of range".to_string());
+            }
+            for position in positions.chunks_exact(3) {
+                out.positions.extend(math::transform_point(
+                    world,
+                    [position[0], position[1], position[2]],
+                ));
+            }
+            out.colors.extend(colors);
+            for corners in indices.chunks_exact(3) {
+                out.indices
+                    .extend(corners.iter().map(|&index| (base + index) as u16));
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * An accessor's elements as floats, with normalized integers mapped to
+     * 0..1 (or -1..1), and its number of components per element
+     */
+    fn read_accessor(&self, index: usize) -> Result<(Vec<f32>, usize), String> {
+        let error = |message: &str| format!("glTF accessor {}: {}", index, message);
+        let accessor = self
+            .list("accessors")
+            .get(index)
+            .ok_or_else(|| error("missing"))?;
+        let field = |key: &str| accessor.get(key).and_then(json::Value::as_usize);
+        let count = field("count").ok_or_else(|| error("no count"))?;
+        let components = match accessor.get("type").and_then(json::Value::as_str) {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") => 4,
+            _ => return Err(error("unsupported type")),
+        };
+        // Size, reader and, for integers, the value normalized data divides by
+        let (size, read, max): (usize, ReadComponent, f32) =
+            match field("componentType").map(|kind| kind as u32) {
+                Some(BYTE) => (1, |b| b[0] as i8 as f32, i8::MAX as f32),
+                Some(UNSIGNED_BYTE) => (1, |b| b[0] as f32, u8::MAX as f32),
+                Some(SHORT) => (
+                    2,
+                    |b| i16::from_le_bytes([b[0], b[1]]) as f32,
+                    i16::MAX as f32,
+                ),
+                Some(UNSIGNED_SHORT) => (
+                    2,
+                    |b| u16::from_le_bytes([b[0], b[1]]) as f32,
+                    u16::MAX as f32,
+                ),
+                Some(UNSIGNED_INT) => (
+                    4,
+                    |b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32,
+                    u32::MAX as f32,
+                ),
+                Some(FLOAT) => (4, |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]), 1.0),
+                _ => return Err(error("unsupported component type")),
+            };
+        let normalized = accessor
+            .get("normalized")
+            .and_then(json::Value::as_bool)
+            .unwrap_or(false);
+        let scale = if normalized { 1.0 / max } else { 1.0 };
+
+        // Accessors without a view are all zeros (used with sparse data)
+        let Some(view_index) = field("bufferView") else {
+            return Ok((vec![0.0; count * components], components));
+        };
+        let view = self
+            .list("bufferViews")
+            .get(view_index)
+            .ok_or_else(|| error("missing buffer view"))?;
+        let view_field = |key: &str| view.get(key).and_then(json::Value::as_usize);
+        let buffer = view_field("buffer")
+            .and_then(|buffer| self.buffers.get(buffer))
+            .ok_or_else(|| error("missing buffer"))?;
+        let start = view_field("byteOffset").unwrap_or(0) + field("byteOffset").unwrap_or(0);
+        let stride = view_field("byteStride").unwrap_or(size * components);
+        let end = start + stride * count.saturating_sub(1) + size * components;
+        if count > 0 && end > buffer.len() {
+            return Err(error("reads past the end of its buffer"));
+        }
+
+        let mut values = Vec::with_capacity(count * components);
+        for element in 0..count {
+            let offset = start + element * stride;
+            for component in 0..components {
+                let at = offset + component * size;
+                values.push(read(&buffer[at..at + size]) * scale);
+            }
+        }
+        Ok((values, components))
+    }
+}
+
+/**
+ * The JSON text and optional binary chunk of a GLB container
+ */
+fn split_glb(bytes: &[u8]) -> Result<(&str, Option<&[u8]>), String> {
+    let word = |at: usize| {
+        bytes
+            .get(at..at + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or_else(|| "GLB file is truncated".to_string())
+    };
+    if word(4)? != 2 {
+        return Err("only glTF 2.0 GLB files are supported".to_string());
+    }
+    let mut json = None;
+    let mut bin = None;
+    let mut at = 12;
+    while at + 8 <= bytes.len() {
+        let length = word(at)? as usize;
+        let kind = word(at + 4)?;
+        let chunk = bytes
+            .get(at + 8..at + 8 + length)
+            .ok_or("GLB chunk is truncated")?;
+        match kind {
+            CHUNK_JSON => json = Some(chunk),
+            CHUNK_BIN => bin = Some(chunk),
+            _ => {}
+        }
+        at += 8 + length;
+    }
+    let json = json.ok_or("GLB file has no JSON chunk")?;
+    let json = std::str::from_utf8(json).map_err(|_| "GLB JSON is not valid UTF-8")?;
+    Ok((json, bin))
+}
+
+fn decode_data_uri(uri: &str) -> Result<Vec<u8>, String> {
+    let data = uri
+        .strip_prefix("data:")
+        .and_then(|rest| rest.split_once(";base64,"))
+        .map(|(_, data)| data)
+        .ok_or_else(|| format!("external glTF buffer {} can't be loaded from a file", uri))?;
+    decode_base64(data).ok_or_else(|| "invalid base64 in glTF buffer".to_string())
+}
+
+fn decode_base64(data: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len() * 3 / 4);
+    let mut bits = 0u32;
+    let mut bit_count = 0;
+    for byte in data.bytes().take_while(|&byte| byte != b'=') {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        bits = (bits << 6) | value as u32;
+        bit_count += 6;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            out.push((bits >> bit_count) as u8);
+        }
+    }
+    Some(out)
+}
+
+/**
+ * A node's local transform, from `matrix` or translation/rotation/scale
+ */
+fn node_matrix(node: &json::Value) -> Mat4 {
+    let floats = |key: &str| -> Option<Vec<f32>> {
+        node.get(key)?
+            .as_array()?
+            .iter()
+            .map(|value| value.as_f64().map(|value| value as f32))
+            .collect()
+    };
+    if let Some(matrix) = floats("matrix").and_then(|matrix| Mat4::try_from(matrix).ok()) {
+        // Both column-major
+        return matrix;
+    }
+    let vec3 = |key: &str, default: f32| match floats(key).as_deref() {
+        Some(&[x, y, z]) => [x, y, z],
+        _ => [default; 3],
+    };
+    let rotation = match floats("rotation").as_deref() {
+        Some(&[x, y, z, w]) => quaternion_matrix([x, y, z, w]),
+        _ => math::identity(),
+    };
+    math::multiply(
+        &math::translation(vec3("translation", 0.0)),
+        &math::multiply(&rotation, &math::scaling(vec3("scale", 1.0))),
+    )
+}
+
+fn quaternion_matrix([x, y, z, w]: [f32; 4]) -> Mat4 {
+    #[rustfmt::skip]
+    let matrix = [
+        1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y + z * w), 2.0 * (x * z - y * w), 0.0,
+        2.0 * (x * y - z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z + x * w), 0.0,
+        2.0 * (x * z + y * w), 2.0 * (y * z - x * w), 1.0 - 2.0 * (x * x + y * y), 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    ];
+    matrix
+}
+
+fn indices(list: Option<&json::Value>) -> Vec<usize> {
+    list.and_then(json::Value::as_array)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(json::Value::as_usize)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bounds::Aabb;
+
+    #[test]
+    fn exported_scene_imports_back() {
+        let scene = Scene::demo();
+        let mesh = import(&export_glb(&scene)).unwrap();
+        let cube = MeshData::cube();
+        assert_eq!(mesh.indices.len(), scene.objects.len() * cube.indices.len());
+        assert_eq!(mesh.colors.len(), mesh.positions.len());
+
+        // Node transforms are baked into the imported vertices
+        let mut expected = Aabb::from_positions(&[]);
+        for object in &scene.objects {
+            let placed = cube.bounds().transformed(&object.transform.matrix());
+            for axis in 0..3 {
+                expected.min[axis] = expected.min[axis].min(placed.min[axis]);
+                expected.max[axis] = expected.max[axis].max(placed.max[axis]);
+            }
+        }
+        let bounds = mesh.bounds();
+        for axis in 0..3 {
+            assert!((bounds.min[axis] - expected.min[axis]).abs() < 1e-4);
+            assert!((bounds.max[axis] - expected.max[axis]).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn rejects_files_without_triangles() {
+        assert_eq!(
+            import(br#"{"asset": {"version": "2.0"}}"#).unwrap_err(),
+            "glTF file has no triangles"
+        );
+        assert!(import(b"glTF\x02\x00\x00\x00").is_err());
+    }
+}
@@ -0,0 +1,384 @@
+use web_sys::WebGl2RenderingContext;
+
+use crate::camera::OrbitCamera;
+use crate::gl_errors;
+use crate::gl_util::{self, GlResource};
+use crate::math;
+use crate::mesh::{GpuMesh, MeshData};
+use crate::readback::Readback;
+use crate::shaders;
+
+// Golden frames are rendered at this size, off screen and without multisampling
+const SIZE: i32 = 64;
+// Frames are compared as a grid of CELLS x CELLS averaged colors, so a pixel
+// or two along an edge rasterized differently by another GPU doesn't count
+const CELLS: usize = 8;
+// Largest difference in any channel of any cell that still passes, out of 255
+const TOLERANCE: u8 = 12;
+
+// One `name hex` line per case. `clear` is known exactly: every cell is the
+// clear color. The others come from "Save signatures" on the diagnostics page
+// and must only be filled from a real browser run: a case with no line here
+// reports that it has no reference instead of passing
+const REFERENCES: &str = include_str!("golden.txt");
+
+/**
+ * A frame reduced to the average RGBA of each cell, bottom row first like
+ * `readPixels`
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Signature(Vec<u8>);
+
+impl Signature {
+    /**
+     * Average tightly packed RGBA8 `pixels` of a `width` x `height` frame
+     */
+    pub fn from_pixels(pixels: &[u8], width: usize, height: usize) -> Self {
+        let mut sums = vec![0u32; CELLS * CELLS * 4];
+        let mut counts = vec![0u32; CELLS * CELLS];
+        for y in 0..height {
+            for x in 0..width {
+                let cell = y * CELLS / height * CELLS + x * CELLS / width;
+                counts[cell] += 1;
+                let pixel = &pixels[(y * width + x) * 4..][..4];
+                for (sum, &value) in sums[cell * 4..][..4].iter_mut().zip(pixel) {
+                    *sum += value as u32;
+                }
+            }
+        }
+        Self(
+            sums.iter()
+                .enumerate()
+                .map(|(i, &sum)| {
+                    let count = counts[i / 4].max(1);
+                    ((sum + count / 2) / count) as u8
+                })
+                .collect(),
+        )
+    }
+
+    pub fn parse(hex: &str) -> Result<Self, String> {
+        if hex.len() != CELLS * CELLS * 8 {
+            return Err(format!(
+                "Signature should be {} hex digits",
+                CELLS * CELLS * 8
+            ));
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                u8::from_str_radix(&hex[i..i + 2], 16)
+                    .map_err(|_| format!("Invalid hex digits at {}", i))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    pub fn to_hex(&self) -> String {
+        self.0.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /**
+     * Largest channel difference from `other` and the (column, row) of the
+     * cell it is in
+     */
+    pub fn max_difference(&self, other: &Signature) -> (u8, (usize, usize)) {
+        self.0
+            .iter()
+            .zip(&other.0)
+            .enumerate()
+            .map(|(i, (a, b))| (a.abs_diff(*b), ((i / 4) % CELLS, i / 4 / CELLS)))
+            .max_by_key(|(difference, _)| *difference)
+            .unwrap_or((0, (0, 0)))
+    }
+}
+
+/**
+ * The stored signature for case `name`
+ */
+fn reference(name: &str) -> Option<Result<Signature, String>> {
+    REFERENCES
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .find(|(case, _)| *case == name)
+        .map(|(_, hex)| Signature::parse(hex.trim()))
+}
+
+/**
+ * How one case's frame compared with its reference
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct CaseResult {
+    pub name: &'static str,
+    // What was rendered, `None` when rendering failed
+    pub signature: Option<Signature>,
+    // Largest channel difference, or why there was nothing to compare
+    pub outcome: Result<u8, String>,
+}
+
+impl CaseResult {
+    pub fn passed(&self) -> bool {
+        matches!(self.outcome, Ok(difference) if difference <= TOLERANCE)
+    }
+}
+
+type RenderCase = fn(&WebGl2RenderingContext, &Resources);
+
+/**
+ * Deterministic scenes: nothing depends on time, input or the canvas size
+ */
+const CASES: [(&str, RenderCase); 3] = [
+    ("clear", render_clear),
+    ("gradient", render_gradient),
+    ("cube", render_cube),
+];
+
+struct Resources {
+    program: web_sys::WebGlProgram,
+    mvp_loc: Option<web_sys::WebGlUniformLocation>,
+    quad: GpuMesh,
+    cube: GpuMesh,
+}
+
+fn render_clear(gl: &WebGl2RenderingContext, _: &Resources) {
+    gl.clear_color(0.2, 0.4, 0.6, 1.0);
+    gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+}
+
+fn render_gradient(gl: &WebGl2RenderingContext, resources: &Resources) {
+    gl.use_program(Some(&resources.program));
+    gl.uniform_matrix4fv_with_f32_array(resources.mvp_loc.as_ref(), false, &math::identity());
+    resources.quad.draw(gl);
+}
+
+fn render_cube(gl: &WebGl2RenderingContext, resources: &Resources) {
+    gl.enable(WebGl2RenderingContext::DEPTH_TEST);
+    gl.enable(WebGl2RenderingContext::CULL_FACE);
+    let mvp = OrbitCamera::default().view_projection(1.0);
+    gl.use_program(Some(&resources.program));
+    gl.uniform_matrix4fv_with_f32_array(resources.mvp_loc.as_ref(), false, &mvp);
+    resources.cube.draw(gl);
+    gl.disable(WebGl2RenderingContext::CULL_FACE);
+    gl.disable(WebGl2RenderingContext::DEPTH_TEST);
+}
+
+/**
+ * A full-screen quad with a different color in each corner
+ */
+fn gradient_quad() -> MeshData {
+    MeshData {
+        positions: vec![
+            -1.0, -1.0, 0.0, 1.0, -1.0, 0.0, 1.0, 1.0, 0.0, -1.0, 1.0, 0.0,
+        ],
+        colors: vec![1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0],
+        indices: vec![0, 1, 2, 2, 3, 0],
+    }
+}
+
+/**
+ * Render every case into an offscreen framebuffer, read it back without
+ * stalling the page and compare it with its reference. Leaves the default
+ * framebuffer bound
+ */
+pub async fn run(gl: &WebGl2RenderingContext) -> Result<Vec<CaseResult>, String> {
+    let program = gl_util::create_program(gl, shaders::GOLDEN_VERT, shaders::GOLDEN_FRAG)?;
+    let resources = Resources {
+        mvp_loc: gl.get_uniform_location(&program, "modelViewProjection"),
+        quad: GpuMesh::upload(gl, &gradient_quad()).ok_or("Unable to upload the quad")?,
+        cube: GpuMesh::upload(gl, &MeshData::cube()).ok_or("Unable to upload the cube")?,
+        program,
+    };
+    let target = Target::new(gl)?;
+
+    let mut results = Vec::with_capacity(CASES.len());
+    for &(name, render) in CASES.iter() {
+        gl.bind_framebuffer(
+            WebGl2RenderingContext::FRAMEBUFFER,
+            Some(&target.framebuffer),
+        );
+        gl.viewport(0, 0, SIZE, SIZE);
+        gl.clear_color(0.0, 0.0, 0.0, 1.0);
+        gl.clear_depth(1.0);
+        gl.clear(
+            WebGl2RenderingContext::COLOR_BUFFER_BIT | WebGl2RenderingContext::DEPTH_BUFFER_BIT,
+        );
+        render(gl, &resources);
+        let signature = read_signature(gl).await;
+        gl_errors::check(gl, &format!("golden: {}", name));
+        let outcome = match (&signature, reference(name)) {
+            (Err(err), _) => Err(err.clone()),
+            (_, None) => Err("No reference yet; save signatures to add one".to_string()),
+            (_, Some(Err(err))) => Err(format!("Bad reference: {}", err)),
+            (Ok(signature), Some(Ok(reference))) => {
+                let (difference, (column, row)) = signature.max_difference(&reference);
+                if difference > TOLERANCE {
+                    tracing::warn!(target: "renderer", "Golden {}: off by {} in cell ({}, {})", name, difference, column, row);
+                }
+                Ok(difference)
+            }
+        };
+        results.push(CaseResult {
+            name,
+            signature: signature.ok(),
+            outcome,
+        });
+    }
+
+    gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+    target.delete(gl);
+    resources.quad.delete(gl);
+    resources.cube.delete(gl);
+    resources.program.delete(gl);
+    Ok(results)
+}
+
+async fn read_signature(gl: &WebGl2RenderingContext) -> Result<Signature, String> {
+    let pixels = Readback::start(gl, 0, 0, SIZE, SIZE)?.wait(gl).await?;
+    Ok(Signature::from_pixels(
+        &pixels,
+        SIZE as usize,
+        SIZE as usize,
+    ))
+}
+
+/**
+ * `name hex` lines for every rendered case, the format of the references file
+ */
+pub fn signatures_file(results: &[CaseResult]) -> String {
+    results
+        .iter()
+        .filter_map(|result| {
+            let signature = result.signature.as_ref()?;
+            Some(format!("{} {}\n", result.name, signature.to_hex()))
+        })
+        .collect()
+}
+
+/**
+ * RGBA8 color and depth attachments the cases render into
+ */
+struct Target {
+    framebuffer: web_sys::WebGlFramebuffer,
+    color: web_sys::WebGlRenderbuffer,
+    depth: web_sys::WebGlRenderbuffer,
+}
+
+impl Target {
+    fn new(gl: &WebGl2RenderingContext) -> Result<Self, String> {
+        let renderbuffer = |format: u32, attachment: u32| {
+            let renderbuffer = gl
+                .create_renderbuffer()
+                .ok_or("Unable to create a renderbuffer")?;
+            gl.bind_renderbuffer(WebGl2RenderingContext::RENDERBUFFER, Some(&renderbuffer));
+            gl.renderbuffer_storage(WebGl2RenderingContext::RENDERBUFFER, format, SIZE, SIZE);
+            gl.framebuffer_renderbuffer(
+                WebGl2RenderingContext::FRAMEBUFFER,
+                attachment,
+                WebGl2RenderingContext::RENDERBUFFER,
+                Some(&renderbuffer),
+            );
+            Ok::<_, String>(renderbuffer)
+        };
+        let framebuffer = gl
+            .create_framebuffer()
+            .ok_or("Unable to create a framebuffer")?;
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(&framebuffer));
+        let target = Self {
+            color: renderbuffer(
+                WebGl2RenderingContext::RGBA8,
+                WebGl2RenderingContext::COLOR_ATTACHMENT0,
+            )?,
+            depth: renderbuffer(
+                WebGl2RenderingContext::DEPTH_COMPONENT24,
+                WebGl2RenderingContext::DEPTH_ATTACHMENT,
+            )?,
+            framebuffer,
+        };
+        gl.bind_renderbuffer(WebGl2RenderingContext::RENDERBUFFER, None);
+        let status = gl.check_framebuffer_status(WebGl2RenderingContext::FRAMEBUFFER);
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+        if status != WebGl2RenderingContext::FRAMEBUFFER_COMPLETE {
+            target.delete(gl);
+            return Err(format!("Golden framebuffer incomplete (0x{:x})", status));
+        }
+        Ok(target)
+    }
+}
+
+impl GlResource for Target {
+    fn delete(&self, gl: &WebGl2RenderingContext) {
+        self.framebuffer.delete(gl);
+        self.color.delete(gl);
+        self.depth.delete(gl);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+
+    #[test]
+    fn signature_averages_cells() {
+        // Left half black, right half white
+        let (width, height) = (16, 16);
+        let pixels: Vec<u8> = (0..width * height)
+            .flat_map(|i| {
+                let value = if i % width < width / 2 { 0 } else { 255 };
+                [value, value, value, 255]
+            })
+            .collect();
+        let signature = Signature::from_pixels(&pixels, width, height);
+        assert_eq!(&signature.0[..4], &[0, 0, 0, 255]);
+        assert_eq!(&signature.0[(CELLS - 1) * 4..][..4], &[255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn signature_hex_round_trips() {
+        let signature = Signature((0..CELLS * CELLS * 4).map(|i| i as u8).collect());
+        assert_eq!(Signature::parse(&signature.to_hex()), Ok(signature));
+        assert!(Signature::parse("00ff").is_err());
+    }
+
+    #[test]
+    fn max_difference_finds_the_worst_cell() {
+        let a = Signature(vec![100; CELLS * CELLS * 4]);
+        let mut b = a.clone();
+        b.0[(2 * CELLS + 3) * 4 + 1] = 120;
+        b.0[0] = 95;
+        assert_eq!(a.max_difference(&b), (20, (3, 2)));
+    }
+
+    #[test]
+    fn references_parse_and_name_cases() {
+        for line in REFERENCES.lines() {
+            let (name, hex) = line.split_once(' ').unwrap_or((line, ""));
+            assert!(CASES.iter().any(|(case, _)| *case == name), "{}", name);
+            assert!(Signature::parse(hex.trim()).is_ok(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn clear_reference_is_the_clear_color() {
+        // 0.2, 0.4 and 0.6 of 255, opaque
+        let pixels = [0x33, 0x66, 0x99, 0xff].repeat((SIZE * SIZE) as usize);
+        let frame = Signature::from_pixels(&pixels, SIZE as usize, SIZE as usize);
+        let stored = reference("clear").unwrap().unwrap();
+        assert_eq!(stored.max_difference(&frame).0, 0);
+    }
+
+    #[wasm_bindgen_test]
+    async fn rendered_frames_match_their_references() {
+        let gl = crate::gl_util::test_context();
+        let results = run(&gl).await.unwrap();
+        let compared: Vec<_> = results
+            .iter()
+            .filter(|result| reference(result.name).is_some())
+            .collect();
+        assert!(!compared.is_empty());
+        for result in compared {
+            assert!(result.passed(), "{}: {:?}", result.name, result.outcome);
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use std::cell::{Cell, RefCell};
+
+// localStorage key for the budget, in bytes
+const BUDGET_KEY: &str = "gpu-memory-budget";
+// Conservative enough for integrated GPUs sharing memory with the system
+pub const DEFAULT_BUDGET: usize = 256 * 1024 * 1024;
+
+/**
+ * What an allocation holds, for the per-kind breakdown
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Buffer,
+    Texture,
+    Renderbuffer,
+}
+
+/**
+ * Estimated bytes currently allocated, per kind
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub buffers: usize,
+    pub textures: usize,
+    pub renderbuffers: usize,
+}
+
+impl Usage {
+    pub fn total(&self) -> usize {
+        self.buffers + self.textures + self.renderbuffers
+    }
+
+    fn get_mut(&mut self, kind: Kind) -> &mut usize {
+        match kind {
+            Kind::Buffer => &mut self.buffers,
+            Kind::Texture => &mut self.textures,
+            Kind::Renderbuffer => &mut self.renderbuffers,
+        }
+    }
+}
+
+struct Tracker {
+    usage: Usage,
+    // `None` until first asked for, then loaded from storage
+    budget: Option<usize>,
+    // Whether the last change left the total over budget, so crossing it
+    // warns once instead of on every allocation
+    over_budget: bool,
+}
+
+thread_local! {
+    static TRACKER: RefCell<Tracker> = const {
+        RefCell::new(Tracker {
+            usage: Usage {
+                buffers: 0,
+                textures: 0,
+                renderbuffers: 0,
+            },
+            budget: None,
+            over_budget: false,
+        })
+    };
+}
+
+/**
+ * Bytes a resource abstraction has asked the GPU for. The estimate follows
+ * the owning value's lifetime: it counts until the value is dropped, which is
+ * when the browser may free an object that was never deleted explicitly.
+ * Drivers add padding and alignment the estimate can't see
+ */
+#[derive(Debug)]
+pub struct Allocation {
+    kind: Kind,
+    bytes: Cell<usize>,
+}
+
+impl Allocation {
+    pub fn new(kind: Kind, bytes: usize) -> Self {
+        change(kind, bytes as isize);
+        Self {
+            kind,
+            bytes: Cell::new(bytes),
+        }
+    }
+
+    /**
+     * Storage was reallocated at a new size
+     */
+    pub fn resize(&self, bytes: usize) {
+        change(
+            self.kind,
+            bytes as isize - self.bytes.replace(bytes) as isize,
+        );
+    }
+}
+
+impl Drop for Allocation {
+    fn drop(&mut self) {
+        change(self.kind, -(self.bytes.get() as isize));
+    }
+}
+
+/**
+ * Size of a 2D (or, with `layers` above 1, array or 3D) texture, including
+ * the mip chain below the base level when `mipmapped`
+ */
+pub fn texture_bytes(
+    width: usize,
+    height: usize,
+    layers: usize,
+    bytes_per_texel: usize,
+    mipmapped: bool,
+) -> usize {
+    let base = width * height * layers * bytes_per_texel;
+    // Each level is a quarter of the one above; the chain adds up to a third
+    if mipmapped {
+        base + base / 3
+    } else {
+        base
+    }
+}
+
+pub fn usage() -> Usage {
+    TRACKER.with_borrow(|tracker| tracker.usage)
+}
+
+pub fn budget() -> usize {
+    TRACKER.with_borrow_mut(|tracker| *tracker.budget.get_or_insert_with(load_budget))
+}
+
+/**
+ * Change the budget and remember it for the next visit
+ */
+pub fn set_budget(bytes: usize) {
+    TRACKER.with_borrow_mut(|tracker| {
+        tracker.budget = Some(bytes);
+        tracker.over_budget = false;
+    });
+    if cfg!(target_arch = "wasm32") {
+        crate::storage::save(BUDGET_KEY, &bytes.to_string());
+    }
+    check_budget();
+}
+
+fn load_budget() -> usize {
+    if !cfg!(target_arch = "wasm32") {
+        return DEFAULT_BUDGET;
+    }
+    crate::storage::load(BUDGET_KEY)
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_BUDGET)
+}
+
+fn change(kind: Kind, delta: isize) {
+    TRACKER.with_borrow_mut(|tracker| {
+        let bytes = tracker.usage.get_mut(kind);
+        *bytes = bytes.saturating_add_signed(delta);
+    });
+    check_budget();
+}
+
+/**
+ * Warn when the total crosses the budget, and note when it is back under
+ */
+fn check_budget() {
+    let (total, budget) = (usage().total(), budget());
+    let crossed = TRACKER.with_borrow_mut(|tracker| {
+        let over = total > budget;
+        let crossed = over != tracker.over_budget;
+        tracker.over_budget = over;
+        crossed
+    });
+    if !crossed {
+        return;
+    }
+    let mb = |bytes: usize| bytes as f64 / (1024.0 * 1024.0);
+    if total > budget {
+        tracing::warn!(
+            target: "renderer",
+            "Estimated GPU memory {:.1} MB is over the {:.1} MB budget",
+            mb(total),
+            mb(budget)
+        );
+    } else {
+        tracing::info!(
+            target: "renderer",
+            "Estimated GPU memory {:.1} MB is back within the {:.1} MB budget",
+            mb(total),
+            mb(budget)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocations_count_until_dropped() {
+        let buffer = Allocation::new(Kind::Buffer, 1000);
+        let texture = Allocation::new(Kind::Texture, 4096);
+        assert_eq!(usage().buffers, 1000);
+        assert_eq!(usage().total(), 5096);
+        buffer.resize(3000);
+        assert_eq!(usage().buffers, 3000);
+        drop(buffer);
+        drop(texture);
+        assert_eq!(usage(), Usage::default());
+    }
+
+    fn is_over_budget() -> bool {
+        usage().total() > budget()
+    }
+
+    #[test]
+    fn tracks_the_budget() {
+        set_budget(100);
+        let small = Allocation::new(Kind::Renderbuffer, 60);
+        assert!(!is_over_budget());
+        let large = Allocation::new(Kind::Texture, 60);
+        assert!(is_over_budget());
+        drop(large);
+        assert!(!is_over_budget());
+        drop(small);
+    }
+
+    #[test]
+    fn estimates_texture_sizes() {
+        assert_eq!(texture_bytes(4, 4, 1, 4, false), 64);
+        // 16x16 RGBA with mips: 1024 + 256 + 64 + 16 + 4 bytes, close to 4/3
+        assert_eq!(texture_bytes(16, 16, 1, 4, true), 1365);
+        assert_eq!(texture_bytes(8, 8, 3, 1, false), 192);
+    }
+}
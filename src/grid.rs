@@ -0,0 +1,75 @@
+use web_sys::{WebGl2RenderingContext, WebGlProgram, WebGlUniformLocation, WebGlVertexArrayObject};
+
+use crate::gl_util::{self, GlResource};
+use crate::math::{self, Mat4};
+use crate::shaders;
+
+// World units from the camera at which the grid has faded out completely
+const FADE_DISTANCE: f32 = 20.0;
+
+/**
+ * Ground grid on a horizontal plane that extends to the horizon, drawn as a
+ * single full-screen pass after the opaque scene
+ */
+pub struct InfiniteGrid {
+    program: WebGlProgram,
+    inverse_view_proj_loc: Option<WebGlUniformLocation>,
+    view_proj_loc: Option<WebGlUniformLocation>,
+    height_loc: Option<WebGlUniformLocation>,
+    fade_distance_loc: Option<WebGlUniformLocation>,
+    // gl_VertexID only, but a bound VAO keeps every implementation happy
+    vao: WebGlVertexArrayObject,
+}
+
+impl InfiniteGrid {
+    pub fn new(gl: &WebGl2RenderingContext) -> Result<Self, String> {
+        let program = gl_util::create_program(gl, shaders::GRID_VERT, shaders::GRID_FRAG)?;
+        let vao = gl
+            .create_vertex_array()
+            .ok_or_else(|| "Unable to create grid VAO".to_string())?;
+        Ok(Self {
+            inverse_view_proj_loc: gl.get_uniform_location(&program, "inverseViewProjection"),
+            view_proj_loc: gl.get_uniform_location(&program, "viewProjection"),
+            height_loc: gl.get_uniform_location(&program, "height"),
+            fade_distance_loc: gl.get_uniform_location(&program, "fadeDistance"),
+            program,
+            vao,
+        })
+    }
+
+    /**
+     * Draw the grid on the plane y = `height`, depth-tested against what's already drawn
+     */
+    pub fn draw(&self, gl: &WebGl2RenderingContext, view_proj: &Mat4, height: f32) {
+        let Some(inverse) = math::invert(view_proj) else {
+            return;
+        };
+        gl.use_program(Some(&self.program));
+        gl.uniform_matrix4fv_with_f32_array(self.inverse_view_proj_loc.as_ref(), false, &inverse);
+        gl.uniform_matrix4fv_with_f32_array(self.view_proj_loc.as_ref(), false, view_proj);
+        gl.uniform1f(self.height_loc.as_ref(), height);
+        gl.uniform1f(self.fade_distance_loc.as_ref(), FADE_DISTANCE);
+
+        gl.enable(WebGl2RenderingContext::DEPTH_TEST);
+        gl.disable(WebGl2RenderingContext::CULL_FACE);
+        gl.enable(WebGl2RenderingContext::BLEND);
+        gl.blend_func(
+            WebGl2RenderingContext::SRC_ALPHA,
+            WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+        );
+        // Translucent, so it mustn't hide anything drawn after it
+        gl.depth_mask(false);
+        gl.bind_vertex_array(Some(&self.vao));
+        gl.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, 3);
+        gl.bind_vertex_array(None);
+        gl.depth_mask(true);
+        gl.disable(WebGl2RenderingContext::BLEND);
+    }
+}
+
+impl GlResource for InfiniteGrid {
+    fn delete(&self, gl: &WebGl2RenderingContext) {
+        self.program.delete(gl);
+        self.vao.delete(gl);
+    }
+}
@@ -0,0 +1,117 @@
+use dioxus::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+use wasm_bindgen::{prelude::*, JsCast};
+
+type FrameCallback = Closure<dyn FnMut(f64)>;
+
+// Frames slower than this are logged as spikes; much longer gaps are the
+// browser pausing a hidden tab rather than slow work
+const SPIKE_SECONDS: f64 = 0.05;
+const PAUSE_SECONDS: f64 = 1.0;
+
+/**
+ * A requestAnimationFrame loop, cancelled when dropped
+ */
+struct AnimationFrame {
+    state: Rc<LoopState>,
+}
+
+#[derive(Default)]
+struct LoopState {
+    closure: RefCell<Option<FrameCallback>>,
+    // Pending request, cancelled if the loop is dropped before it fires
+    request: Cell<Option<i32>>,
+}
+
+impl LoopState {
+    fn request(&self) {
+        let Some(window) = web_sys::window() else {
+            return;
+        };
+        if let Some(closure) = self.closure.borrow().as_ref() {
+            self.request.set(
+                window
+                    .request_animation_frame(closure.as_ref().unchecked_ref())
+                    .ok(),
+            );
+        }
+    }
+}
+
+impl AnimationFrame {
+    fn start(mut callback: impl FnMut(f64, f64) + 'static) -> Self {
+        let state = Rc::new(LoopState::default());
+        // Weak so the closure doesn't keep its own loop alive
+        let weak: Weak<LoopState> = Rc::downgrade(&state);
+        let mut last_time = None::<f64>;
+        let closure = FrameCallback::new(move |timestamp: f64| {
+            let time = timestamp / 1000.0;
+            let delta = last_time.map_or(0.0, |last| (time - last).max(0.0));
+            last_time = Some(time);
+            if (SPIKE_SECONDS..PAUSE_SECONDS).contains(&delta) {
+                tracing::debug!(target: "renderer", "Frame spike: {:.1} ms", delta * 1000.0);
+            }
+            callback(time, delta);
+            // Gone if the callback (or anything it triggered) dropped the loop
+            if let Some(state) = weak.upgrade() {
+                state.request();
+            }
+        });
+        *state.closure.borrow_mut() = Some(closure);
+        state.request();
+        Self { state }
+    }
+}
+
+impl Drop for AnimationFrame {
+    fn drop(&mut self) {
+        if let (Some(window), Some(request)) = (web_sys::window(), self.state.request.take()) {
+            let _ = window.cancel_animation_frame(request);
+        }
+        self.state.closure.borrow_mut().take();
+    }
+}
+
+/**
+ * Handle to a component's animation loop. `start` runs a callback every
+ * animation frame with the time and the time since the previous frame, in
+ * seconds, replacing any loop started before. The loop stops when the
+ * component unmounts
+ */
+#[derive(Clone, Default)]
+pub struct FrameLoop {
+    current: Rc<RefCell<Option<AnimationFrame>>>,
+}
+
+impl FrameLoop {
+    pub fn start(&self, callback: impl FnMut(f64, f64) + 'static) {
+        *self.current.borrow_mut() = Some(AnimationFrame::start(callback));
+    }
+
+    pub fn stop(&self) {
+        self.current.borrow_mut().take();
+    }
+}
+
+/**
+ * A frame loop for a component that has to set things up (e.g. GL resources
+ * in a spawned task) before it can start animating
+ */
+pub fn use_frame_loop() -> FrameLoop {
+    let frame_loop = use_hook(FrameLoop::default);
+    use_drop({
+        let frame_loop = frame_loop.clone();
+        move || frame_loop.stop()
+    });
+    frame_loop
+}
+
+/**
+ * Run `callback` with `(time, dt)` in seconds every animation frame from the
+ * first render until the component unmounts
+ */
+pub fn use_animation_frame(callback: impl FnMut(f64, f64) + 'static) {
+    let frame_loop = use_frame_loop();
+    use_hook(move || frame_loop.start(callback));
+}
@@ -0,0 +1,85 @@
+use dioxus::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+use web_sys::WebGl2RenderingContext;
+
+use crate::components::use_gl_context;
+use crate::gl_errors;
+use crate::gl_util::GlResource;
+
+/**
+ * Shared handle to a resource made by `use_gl_resource`. Clones see the
+ * resource the hook currently holds, so a draw callback registered once
+ * picks up a replacement after its dependencies change
+ */
+pub struct GlHandle<T> {
+    current: Rc<RefCell<Option<Rc<T>>>>,
+}
+
+impl<T> Clone for GlHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            current: self.current.clone(),
+        }
+    }
+}
+
+impl<T: GlResource> GlHandle<T> {
+    /**
+     * The resource, or `None` before the context exists or when creating it
+     * failed. It is deleted on unmount, so don't hold on to it across frames
+     */
+    pub fn get(&self) -> Option<Rc<T>> {
+        self.current.borrow().clone()
+    }
+
+    fn replace(&self, gl: &WebGl2RenderingContext, resource: Option<Rc<T>>) {
+        if let Some(old) = self.current.replace(resource) {
+            old.delete(gl);
+        }
+    }
+}
+
+/**
+ * Create a GL resource with the enclosing `WebGlCanvas`'s context once it
+ * exists, and delete it when the component unmounts or `deps` changes, in
+ * which case `create` runs again for the new value. Failures are logged and
+ * leave the handle empty until the next change
+ */
+pub fn use_gl_resource<T: GlResource + 'static, D: PartialEq + 'static>(
+    deps: D,
+    create: impl FnOnce(&WebGl2RenderingContext, &D) -> Result<T, String>,
+) -> GlHandle<T> {
+    let gl = use_gl_context().gl();
+    let handle = use_hook(|| GlHandle::<T> {
+        current: Rc::default(),
+    });
+    // Dependencies the resource was made from, and the context it lives in,
+    // which has to outlive the canvas signal for the final delete
+    let made_from = use_hook(|| Rc::new(RefCell::new(None::<(D, WebGl2RenderingContext)>)));
+
+    if let Some(gl) = gl {
+        let stale = made_from
+            .borrow()
+            .as_ref()
+            .is_none_or(|(made_deps, _)| *made_deps != deps);
+        if stale {
+            let resource = create(&gl, &deps)
+                .map_err(|err| gl_errors::report("use_gl_resource", err))
+                .ok()
+                .map(Rc::new);
+            handle.replace(&gl, resource);
+            *made_from.borrow_mut() = Some((deps, gl));
+        }
+    }
+
+    use_drop({
+        let handle = handle.clone();
+        move || {
+            if let Some((_, gl)) = made_from.take() {
+                handle.replace(&gl, None);
+            }
+        }
+    });
+    handle
+}
@@ -0,0 +1,68 @@
+use dioxus::prelude::*;
+use gloo_timers::callback::Timeout;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/**
+ * Where the pointer came to rest, once it has stayed put for a delay. Feed it
+ * the pointer with `moved` and `left`
+ */
+#[derive(Clone)]
+pub struct HoverDwell {
+    rested: Signal<Option<(f64, f64)>>,
+    delay_ms: u32,
+    // Dropping a pending timeout cancels it
+    timer: Rc<RefCell<Option<Timeout>>>,
+}
+
+impl HoverDwell {
+    /**
+     * The pointer moved to (`x`, `y`): forget any rest and start waiting again
+     */
+    pub fn moved(&self, x: f64, y: f64) {
+        let mut rested = self.rested;
+        if rested.peek().is_some() {
+            rested.set(None);
+        }
+        let timeout = Timeout::new(self.delay_ms, move || rested.set(Some((x, y))));
+        *self.timer.borrow_mut() = Some(timeout);
+    }
+
+    /**
+     * The pointer left the element or is busy, e.g. dragging: stop waiting
+     */
+    pub fn left(&self) {
+        self.timer.borrow_mut().take();
+        let mut rested = self.rested;
+        if rested.peek().is_some() {
+            rested.set(None);
+        }
+    }
+
+    /**
+     * Where the pointer rests, or `None` while it moves. Reading it
+     * subscribes the calling component or effect
+     */
+    pub fn rested(&self) -> Option<(f64, f64)> {
+        (self.rested)()
+    }
+}
+
+/**
+ * Notice the pointer resting for `delay_ms`, as hover tooltips wait for; the
+ * pending timer is cancelled on unmount
+ */
+pub fn use_hover_dwell(delay_ms: u32) -> HoverDwell {
+    let dwell = use_hook(|| HoverDwell {
+        rested: Signal::new(None),
+        delay_ms,
+        timer: Rc::default(),
+    });
+    use_drop({
+        let timer = dwell.timer.clone();
+        move || {
+            timer.borrow_mut().take();
+        }
+    });
+    dwell
+}
@@ -0,0 +1,11 @@
+mod animation_frame;
+mod gl_resource;
+mod hover_dwell;
+mod pointer_drag;
+mod resize_observer;
+
+pub use animation_frame::{use_animation_frame, use_frame_loop, FrameLoop};
+pub use gl_resource::use_gl_resource;
+pub use hover_dwell::use_hover_dwell;
+pub use pointer_drag::use_pointer_drag;
+pub use resize_observer::use_resize_observer;
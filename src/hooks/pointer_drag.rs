@@ -0,0 +1,116 @@
+use dioxus::html::input_data::MouseButtonSet;
+use dioxus::prelude::*;
+use wasm_bindgen::JsCast;
+
+/**
+ * State of an active drag, in pixels relative to the element's top left
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Drag {
+    pub start: (f64, f64),
+    pub position: (f64, f64),
+    // Movement since the previous pointer event of this drag
+    pub delta: (f64, f64),
+    // Buttons held, e.g. to orbit with the primary button and pan with another
+    pub buttons: MouseButtonSet,
+}
+
+/**
+ * Pointer handlers for one element and the drag they track. Wire
+ * `pointer_down`, `pointer_move` and `pointer_up` to the element's
+ * `onpointerdown`, `onpointermove` and `onpointerup`/`onpointercancel`.
+ * The element captures the pointer while a button is held, so a drag carries
+ * on when the pointer leaves it and always sees its release
+ */
+#[derive(Clone, Copy)]
+pub struct PointerDrag {
+    drag: Signal<Option<Drag>>,
+    // Pointer the drag follows; other pointers are ignored until it ends
+    pointer_id: Signal<Option<i32>>,
+}
+
+impl PointerDrag {
+    /**
+     * The active drag, if any. Reading it subscribes the calling component or
+     * effect
+     */
+    pub fn drag(&self) -> Option<Drag> {
+        (self.drag)()
+    }
+
+    /**
+     * Start a drag unless one is already active; returns it when started
+     */
+    pub fn pointer_down(&mut self, evt: &PointerEvent) -> Option<Drag> {
+        if self.pointer_id.peek().is_some() {
+            return None;
+        }
+        let pointer_id = evt.pointer_id();
+        if let Some(element) = current_target(evt) {
+            // Fails for pointers that are already gone, which end on their own
+            let _ = element.set_pointer_capture(pointer_id);
+        }
+        let point = evt.element_coordinates();
+        let drag = Drag {
+            start: (point.x, point.y),
+            position: (point.x, point.y),
+            delta: (0.0, 0.0),
+            buttons: evt.held_buttons(),
+        };
+        tracing::debug!(target: "input", "Drag started at {:?} with {:?}", drag.start, drag.buttons);
+        self.pointer_id.set(Some(pointer_id));
+        self.drag.set(Some(drag));
+        Some(drag)
+    }
+
+    /**
+     * Follow the dragging pointer; returns the updated drag, or `None` when
+     * the event isn't part of one
+     */
+    pub fn pointer_move(&mut self, evt: &PointerEvent) -> Option<Drag> {
+        if *self.pointer_id.peek() != Some(evt.pointer_id()) {
+            return None;
+        }
+        let mut drag = (*self.drag.peek())?;
+        let point = evt.element_coordinates();
+        drag.delta = (point.x - drag.position.0, point.y - drag.position.1);
+        drag.position = (point.x, point.y);
+        drag.buttons = evt.held_buttons();
+        tracing::trace!(target: "input", "Dragged to {:?}", drag.position);
+        self.drag.set(Some(drag));
+        Some(drag)
+    }
+
+    /**
+     * End the drag on release or cancellation; returns its final state
+     */
+    pub fn pointer_up(&mut self, evt: &PointerEvent) -> Option<Drag> {
+        if *self.pointer_id.peek() != Some(evt.pointer_id()) {
+            return None;
+        }
+        // Capture is released by the browser after pointerup on its own, but
+        // not necessarily after cancellation
+        if let Some(element) = current_target(evt) {
+            let _ = element.release_pointer_capture(evt.pointer_id());
+        }
+        tracing::debug!(target: "input", "Drag ended");
+        self.pointer_id.set(None);
+        self.drag.take()
+    }
+}
+
+fn current_target(evt: &PointerEvent) -> Option<web_sys::Element> {
+    evt.downcast::<web_sys::PointerEvent>()?
+        .current_target()?
+        .dyn_into::<web_sys::Element>()
+        .ok()
+}
+
+/**
+ * Track drags on an element with its pointer events
+ */
+pub fn use_pointer_drag() -> PointerDrag {
+    let drag = use_signal(|| None);
+    let pointer_id = use_signal(|| None);
+    PointerDrag { drag, pointer_id }
+}
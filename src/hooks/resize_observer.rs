@@ -0,0 +1,85 @@
+use dioxus::prelude::*;
+use js_sys::Array;
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{ResizeObserver, ResizeObserverEntry};
+
+/**
+ * A connected observer and the callback it calls; disconnects when dropped
+ */
+struct Observer {
+    observer: ResizeObserver,
+    _callback: Closure<dyn FnMut(Array)>,
+}
+
+impl Drop for Observer {
+    fn drop(&mut self) {
+        self.observer.disconnect();
+    }
+}
+
+/**
+ * Content-box size of one element in CSS pixels, kept up to date by a
+ * `ResizeObserver`. Hand it the element with `observe`, e.g. from `onmounted`
+ */
+#[derive(Clone)]
+pub struct ElementSize {
+    size: Signal<Option<(f64, f64)>>,
+    observer: Rc<RefCell<Option<Observer>>>,
+}
+
+impl ElementSize {
+    /**
+     * Start tracking `element`, replacing any element observed before
+     */
+    pub fn observe(&self, element: &web_sys::Element) {
+        let mut size = self.size;
+        let callback = Closure::<dyn FnMut(Array)>::new(move |entries: Array| {
+            // Only the latest size matters when several changes were batched
+            let Some(entry) = entries.pop().dyn_into::<ResizeObserverEntry>().ok() else {
+                return;
+            };
+            let rect = entry.content_rect();
+            let observed = Some((rect.width(), rect.height()));
+            if *size.peek() != observed {
+                size.set(observed);
+            }
+        });
+        match ResizeObserver::new(callback.as_ref().unchecked_ref()) {
+            Ok(observer) => {
+                observer.observe(element);
+                *self.observer.borrow_mut() = Some(Observer {
+                    observer,
+                    _callback: callback,
+                });
+            }
+            Err(err) => tracing::error!(target: "ui", "Unable to observe resizes: {:?}", err),
+        }
+    }
+
+    /**
+     * Width and height, or `None` until the first observation. Reading it
+     * subscribes the calling component or effect
+     */
+    pub fn size(&self) -> Option<(f64, f64)> {
+        (self.size)()
+    }
+}
+
+/**
+ * Track an element's content size; the observer is disconnected on unmount
+ */
+pub fn use_resize_observer() -> ElementSize {
+    let element_size = use_hook(|| ElementSize {
+        size: Signal::new(None),
+        observer: Rc::default(),
+    });
+    use_drop({
+        let observer = element_size.observer.clone();
+        move || {
+            observer.borrow_mut().take();
+        }
+    });
+    element_size
+}
@@ -0,0 +1,151 @@
+mod font;
+
+use web_sys::WebGl2RenderingContext;
+
+use crate::atlas::UvRect;
+use crate::math::{self, Mat4};
+use crate::sprites::{Sprite, SpriteBatch};
+use crate::texture::Texture;
+use font::{GLYPHS, GLYPH_HEIGHT, GLYPH_WIDTH};
+
+// Font texture cells: a glyph plus one empty column and row of spacing.
+// Cell 0 is solid white and backs plain rectangles
+const CELL_WIDTH: u32 = GLYPH_WIDTH + 1;
+const CELL_HEIGHT: u32 = GLYPH_HEIGHT + 1;
+const CELL_COLUMNS: u32 = 16;
+
+/**
+ * Screen-space overlay drawn after the 3D scene: rectangles and bitmap text
+ * in canvas pixels (origin top-left), with depth testing off
+ */
+pub struct Hud {
+    batch: SpriteBatch,
+    font: Texture,
+    font_size: (f32, f32),
+    projection: Mat4,
+}
+
+impl Hud {
+    pub fn new(gl: &WebGl2RenderingContext, width: i32, height: i32) -> Result<Self, String> {
+        let cell_count = GLYPHS.len() as u32 + 1;
+        let font_width = CELL_COLUMNS * CELL_WIDTH;
+        let font_height = cell_count.div_ceil(CELL_COLUMNS) * CELL_HEIGHT;
+
+        let mut pixels = vec![0u8; (font_width * font_height * 4) as usize];
+        let mut set_white = |x: u32, y: u32| {
+            let i = ((y * font_width + x) * 4) as usize;
+            pixels[i..i + 4].copy_from_slice(&[255; 4]);
+        };
+        for y in 0..CELL_HEIGHT {
+            for x in 0..CELL_WIDTH {
+                set_white(x, y);
+            }
+        }
+        for (i, (_, rows)) in GLYPHS.iter().enumerate() {
+            let (cell_x, cell_y) = cell_origin(i as u32 + 1);
+            for (y, row) in rows.iter().enumerate() {
+                for x in 0..GLYPH_WIDTH {
+                    if row & (1 << (GLYPH_WIDTH - 1 - x)) != 0 {
+                        set_white(cell_x + x, cell_y + y as u32);
+                    }
+                }
+            }
+        }
+        let font = Texture::from_rgba(gl, font_width as i32, font_height as i32, &pixels)
+            .ok_or_else(|| "Unable to create HUD font texture".to_string())?;
+
+        Ok(Self {
+            batch: SpriteBatch::new(gl)?,
+            font,
+            font_size: (font_width as f32, font_height as f32),
+            projection: math::orthographic(0.0, width as f32, height as f32, 0.0, -1.0, 1.0),
+        })
+    }
+
+    /**
+     * Solid rectangle with its top-left corner at (`x`, `y`)
+     */
+    pub fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: [f32; 4]) {
+        // Every texel of the solid cell is white; sample its middle
+        let (font_width, font_height) = self.font_size;
+        let middle = [
+            CELL_WIDTH as f32 * 0.5 / font_width,
+            CELL_HEIGHT as f32 * 0.5 / font_height,
+        ];
+        let uv = UvRect {
+            min: middle,
+            max: middle,
+        };
+        self.batch.push(&Sprite {
+            center: [x + width * 0.5, y + height * 0.5],
+            size: [width, height],
+            rotation: 0.0,
+            uv,
+            color,
+        });
+    }
+
+    /**
+     * One line of text with its top-left corner at (`x`, `y`); `scale` is the
+     * size of a font pixel in canvas pixels. Lowercase is drawn as uppercase
+     * and unknown characters as `?`
+     */
+    pub fn text(&mut self, x: f32, y: f32, scale: f32, text: &str, color: [f32; 4]) {
+        let size = [GLYPH_WIDTH as f32 * scale, GLYPH_HEIGHT as f32 * scale];
+        for (i, c) in text.chars().enumerate() {
+            let c = c.to_ascii_uppercase();
+            let glyph = GLYPHS
+                .iter()
+                .position(|&(glyph, _)| glyph == c)
+                .or_else(|| GLYPHS.iter().position(|&(glyph, _)| glyph == '?'))
+                .unwrap_or(0);
+            let left = x + i as f32 * CELL_WIDTH as f32 * scale;
+            let uv = self.glyph_uv(glyph as u32 + 1);
+            self.batch.push(&Sprite {
+                center: [left + size[0] * 0.5, y + size[1] * 0.5],
+                size,
+                rotation: 0.0,
+                uv,
+                color,
+            });
+        }
+    }
+
+    /**
+     * Width in canvas pixels of `text` drawn at `scale`
+     */
+    pub fn text_width(text: &str, scale: f32) -> f32 {
+        let count = text.chars().count() as f32;
+        (count * CELL_WIDTH as f32 - 1.0).max(0.0) * scale
+    }
+
+    /**
+     * Draw everything queued this frame on top of whatever is in the framebuffer
+     */
+    pub fn draw(&mut self, gl: &WebGl2RenderingContext) {
+        gl.disable(WebGl2RenderingContext::DEPTH_TEST);
+        // The y-down projection flips winding, so don't cull either
+        gl.disable(WebGl2RenderingContext::CULL_FACE);
+        self.batch.flush(gl, &self.projection, &self.font);
+    }
+
+    // UV rect of the glyph in font cell `cell`, without the spacing
+    fn glyph_uv(&self, cell: u32) -> UvRect {
+        let (x, y) = cell_origin(cell);
+        let (font_width, font_height) = self.font_size;
+        UvRect {
+            min: [x as f32 / font_width, y as f32 / font_height],
+            max: [
+                (x + GLYPH_WIDTH) as f32 / font_width,
+                (y + GLYPH_HEIGHT) as f32 / font_height,
+            ],
+        }
+    }
+}
+
+fn cell_origin(cell: u32) -> (u32, u32) {
+    (
+        (cell % CELL_COLUMNS) * CELL_WIDTH,
+        (cell / CELL_COLUMNS) * CELL_HEIGHT,
+    )
+}
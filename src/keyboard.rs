@@ -0,0 +1,94 @@
+use dioxus::prelude::Key;
+
+/**
+ * What a key does in a 3D view, independent of what is selected
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyAction {
+    // Arrow keys, one unit to the right and/or up
+    Direction { right: f64, up: f64 },
+    // Positive zooms out, in wheel-delta units
+    Zoom(f64),
+    // Tab and Shift+Tab
+    Cycle { backwards: bool },
+    // Enter or Space
+    Activate,
+    // Escape
+    Cancel,
+}
+
+// Wheel delta one +/- press stands for
+const ZOOM_STEP: f64 = 100.0;
+
+/**
+ * The action bound to `key`, if any
+ */
+pub fn action(key: &Key, shift: bool) -> Option<KeyAction> {
+    let direction = |right, up| Some(KeyAction::Direction { right, up });
+    match key {
+        Key::ArrowLeft => direction(-1.0, 0.0),
+        Key::ArrowRight => direction(1.0, 0.0),
+        Key::ArrowUp => direction(0.0, 1.0),
+        Key::ArrowDown => direction(0.0, -1.0),
+        Key::Tab => Some(KeyAction::Cycle { backwards: shift }),
+        Key::Enter => Some(KeyAction::Activate),
+        Key::Escape => Some(KeyAction::Cancel),
+        Key::Character(text) => match text.as_str() {
+            "+" | "=" => Some(KeyAction::Zoom(-ZOOM_STEP)),
+            "-" | "_" => Some(KeyAction::Zoom(ZOOM_STEP)),
+            " " => Some(KeyAction::Activate),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/**
+ * The item after (or before) `current` among `count`, starting from the first
+ * (or last) when nothing is current. `None` past either end, so focus can
+ * move on to the rest of the page instead of being trapped
+ */
+pub fn cycle(current: Option<usize>, count: usize, backwards: bool) -> Option<usize> {
+    let next = match (current, backwards) {
+        (None, false) => Some(0),
+        (None, true) => count.checked_sub(1),
+        (Some(index), false) => Some(index + 1),
+        (Some(index), true) => index.checked_sub(1),
+    };
+    next.filter(|&index| index < count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_keys_to_actions() {
+        assert_eq!(
+            action(&Key::ArrowUp, false),
+            Some(KeyAction::Direction {
+                right: 0.0,
+                up: 1.0
+            })
+        );
+        assert_eq!(
+            action(&Key::Tab, true),
+            Some(KeyAction::Cycle { backwards: true })
+        );
+        assert_eq!(
+            action(&Key::Character("=".to_string()), false),
+            Some(KeyAction::Zoom(-ZOOM_STEP))
+        );
+        assert_eq!(action(&Key::Character("x".to_string()), false), None);
+    }
+
+    #[test]
+    fn cycle_stops_at_the_ends() {
+        assert_eq!(cycle(None, 3, false), Some(0));
+        assert_eq!(cycle(Some(1), 3, false), Some(2));
+        assert_eq!(cycle(Some(2), 3, false), None);
+        assert_eq!(cycle(None, 3, true), Some(2));
+        assert_eq!(cycle(Some(0), 3, true), None);
+        assert_eq!(cycle(None, 0, true), None);
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::{HashMap, HashSet};
+
+use web_sys::{WebGl2RenderingContext, WebGlProgram, WebGlUniformLocation, WebGlVertexArrayObject};
+
+use crate::gl_util;
+use crate::math::{Mat4, Vec3};
+use crate::shaders;
+use crate::text_texture::{TextStyle, TextTexture};
+
+/**
+ * A text label floating in the scene: `text` drawn above `anchor`, always
+ * facing the camera. `id` ties it to the texture rendered for it
+ */
+pub struct Label<'a> {
+    pub id: u64,
+    pub text: &'a str,
+    pub anchor: Vec3,
+}
+
+/**
+ * Draws camera-facing text labels, each rendered into a `TextTexture` the
+ * first time it is seen and again only when its text changes
+ */
+pub struct LabelRenderer {
+    program: WebGlProgram,
+    // gl_VertexID only, but a bound VAO keeps every implementation happy
+    vao: WebGlVertexArrayObject,
+    view_proj_loc: Option<WebGlUniformLocation>,
+    anchor_loc: Option<WebGlUniformLocation>,
+    right_loc: Option<WebGlUniformLocation>,
+    up_loc: Option<WebGlUniformLocation>,
+    size_loc: Option<WebGlUniformLocation>,
+    label_loc: Option<WebGlUniformLocation>,
+    style: TextStyle,
+    // None where rendering the text failed, so it isn't retried every frame
+    textures: HashMap<u64, (String, Option<TextTexture>)>,
+}
+
+impl LabelRenderer {
+    pub fn new(gl: &WebGl2RenderingContext, style: TextStyle) -> Result<Self, String> {
+        let program = gl_util::create_program(gl, shaders::LABELS_VERT, shaders::LABELS_FRAG)?;
+        let vao = gl
+            .create_vertex_array()
+            .ok_or_else(|| "Unable to create label VAO".to_string())?;
+        let location = |name: &str| gl.get_uniform_location(&program, name);
+        Ok(Self {
+            view_proj_loc: location("viewProjection"),
+            anchor_loc: location("anchor"),
+            right_loc: location("cameraRight"),
+            up_loc: location("cameraUp"),
+            size_loc: location("size"),
+            label_loc: location("label"),
+            program,
+            vao,
+            style,
+            textures: HashMap::new(),
+        })
+    }
+
+    /**
+     * Draw `labels` `height` world units tall, facing the camera of `view`.
+     * Textures of labels no longer passed in are deleted. A label whose text
+     * can't be rendered is skipped until its text changes, and the first
+     * error returned
+     */
+    pub fn draw(
+        &mut self,
+        gl: &WebGl2RenderingContext,
+        view: &Mat4,
+        view_proj: &Mat4,
+        labels: &[Label],
+        height: f32,
+    ) -> Result<(), String> {
+        let mut result = Ok(());
+        let live: HashSet<u64> = labels.iter().map(|label| label.id).collect();
+        self.textures.retain(|id, (_, texture)| {
+            let keep = live.contains(id);
+            if let Some(texture) = texture.as_ref().filter(|_| !keep) {
+                texture.delete(gl);
+            }
+            keep
+        });
+        for label in labels {
+            let current = self.textures.get(&label.id);
+            if current.is_some_and(|(text, _)| text == label.text) {
+                continue;
+            }
+            let texture = match TextTexture::new(gl, label.text, &self.style) {
+                Ok(texture) => Some(texture),
+                Err(err) => {
+                    if result.is_ok() {
+                        result = Err(err);
+                    }
+                    None
+                }
+            };
+            if let Some((_, Some(old))) = self
+                .textures
+                .insert(label.id, (label.text.to_string(), texture))
+            {
+                old.delete(gl);
+            }
+        }
+
+        // The rows of the view matrix are the camera's axes in world space
+        let right = [view[0], view[4], view[8]];
+        let up = [view[1], view[5], view[9]];
+        gl.use_program(Some(&self.program));
+        gl.bind_vertex_array(Some(&self.vao));
+        gl.uniform_matrix4fv_with_f32_array(self.view_proj_loc.as_ref(), false, view_proj);
+        gl.uniform3fv_with_f32_array(self.right_loc.as_ref(), &right);
+        gl.uniform3fv_with_f32_array(self.up_loc.as_ref(), &up);
+        gl.uniform1i(self.label_loc.as_ref(), 0);
+        gl.enable(WebGl2RenderingContext::DEPTH_TEST);
+        gl.disable(WebGl2RenderingContext::CULL_FACE);
+        gl.enable(WebGl2RenderingContext::BLEND);
+        gl.blend_func(
+            WebGl2RenderingContext::SRC_ALPHA,
+            WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+        );
+        // Overlapping labels blend instead of cutting holes in each other
+        gl.depth_mask(false);
+        for label in labels {
+            let Some((_, Some(texture))) = self.textures.get(&label.id) else {
+                continue;
+            };
+            texture.texture().bind(gl, 0);
+            gl.uniform3fv_with_f32_array(self.anchor_loc.as_ref(), &label.anchor);
+            gl.uniform2f(self.size_loc.as_ref(), height * texture.aspect(), height);
+            gl.draw_arrays(WebGl2RenderingContext::TRIANGLE_STRIP, 0, 4);
+        }
+        gl.depth_mask(true);
+        gl.disable(WebGl2RenderingContext::BLEND);
+        gl.bind_vertex_array(None);
+        result
+    }
+}
@@ -0,0 +1,164 @@
+use std::cell::RefCell;
+use tracing::level_filters::LevelFilter;
+use tracing::Metadata;
+use tracing_subscriber::filter::filter_fn;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::{Layer, Registry};
+
+use crate::query;
+
+/**
+ * Targets the app tags its events with, one per subsystem
+ */
+pub const SUBSYSTEMS: [&str; 6] = ["renderer", "assets", "input", "sync", "storage", "ui"];
+
+// Query parameter with the initial filter, e.g. ?log=renderer=trace,assets=off
+const QUERY_KEY: &str = "log";
+
+/**
+ * Verbosity per target, with a default for everything else (including the
+ * libraries underneath)
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogFilter {
+    pub default: LevelFilter,
+    pub targets: Vec<(String, LevelFilter)>,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        Self {
+            default: if cfg!(debug_assertions) {
+                LevelFilter::DEBUG
+            } else {
+                LevelFilter::INFO
+            },
+            targets: Vec::new(),
+        }
+    }
+}
+
+impl LogFilter {
+    /**
+     * Comma-separated directives: `target=level` for one subsystem (and the
+     * targets nested under it), a bare level for the default. Invalid
+     * directives are skipped with a warning
+     */
+    pub fn parse(directives: &str) -> Self {
+        let mut filter = Self::default();
+        for directive in directives.split(',').map(str::trim) {
+            if directive.is_empty() {
+                continue;
+            }
+            let (target, level) = match directive.split_once('=') {
+                Some((target, level)) => (Some(target), level),
+                None => (None, directive),
+            };
+            let Ok(level) = level.parse::<LevelFilter>() else {
+                tracing::warn!(target: "ui", "Ignoring log directive {:?}", directive);
+                continue;
+            };
+            match target {
+                Some(target) => filter.set(target, level),
+                None => filter.default = level,
+            }
+        }
+        filter
+    }
+
+    /**
+     * The inverse of `parse`
+     */
+    pub fn to_directives(&self) -> String {
+        std::iter::once(self.default.to_string())
+            .chain(
+                self.targets
+                    .iter()
+                    .map(|(target, level)| format!("{}={}", target, level)),
+            )
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub fn set(&mut self, target: &str, level: LevelFilter) {
+        match self.targets.iter_mut().find(|(name, _)| name == target) {
+            Some((_, current)) => *current = level,
+            None => self.targets.push((target.to_string(), level)),
+        }
+    }
+
+    /**
+     * The level in effect for `target`
+     */
+    pub fn level(&self, target: &str) -> LevelFilter {
+        self.targets
+            .iter()
+            .filter(|(name, _)| {
+                target == name
+                    || target
+                        .strip_prefix(name.as_str())
+                        .is_some_and(|rest| rest.starts_with("::"))
+            })
+            // The most specific match wins
+            .max_by_key(|(name, _)| name.len())
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        *metadata.level() <= self.level(metadata.target())
+    }
+}
+
+thread_local! {
+    static FILTER: RefCell<LogFilter> = RefCell::new(LogFilter::default());
+}
+
+/**
+ * The filter currently applied
+ */
+pub fn filter() -> LogFilter {
+    FILTER.with_borrow(Clone::clone)
+}
+
+/**
+ * Apply a new filter right away, e.g. to turn on frame-level renderer logs
+ * while chasing a bug
+ */
+pub fn set_filter(filter: LogFilter) {
+    FILTER.with_borrow_mut(|current| *current = filter);
+    // Call sites cache whether they are enabled; make them ask again
+    tracing::callsite::rebuild_interest_cache();
+}
+
+/**
+ * Route `tracing` events to the browser console through `tracing-wasm`, with
+ * spans also recorded as performance measures for the devtools timeline.
+ * Call before launching so Dioxus keeps this subscriber instead of
+ * installing its default one. The initial filter comes from the `log` query
+ * parameter; see `LogFilter::parse`
+ */
+pub fn init() {
+    // Native builds (desktop, mobile) have no console; Dioxus logs to stdout there
+    if !cfg!(target_arch = "wasm32") {
+        return;
+    }
+    console_error_panic_hook::set_once();
+    if let Some((_, directives)) = query::current()
+        .into_iter()
+        .find(|(key, _)| key == QUERY_KEY)
+    {
+        FILTER.with_borrow_mut(|current| *current = LogFilter::parse(&directives));
+    }
+    // The console layer lets everything through; the filter decides
+    let console = tracing_wasm::WASMLayer::new(
+        tracing_wasm::WASMLayerConfigBuilder::new()
+            .set_max_level(tracing::Level::TRACE)
+            .build(),
+    )
+    .with_filter(filter_fn(|metadata| {
+        FILTER.with_borrow(|filter| filter.enabled(metadata))
+    }));
+    if let Err(err) = tracing::subscriber::set_global_default(Registry::default().with(console)) {
+        web_sys::console::warn_1(&format!("Logging already set up: {}", err).into());
+    }
+}
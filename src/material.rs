@@ -0,0 +1,357 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use web_sys::{WebGl2RenderingContext, WebGlProgram, WebGlUniformLocation};
+
+use crate::gl_util;
+use crate::shaders::{self, ShaderProgram};
+use crate::texture::Texture;
+
+// Name the built-in material shader is registered under
+pub const STANDARD_SHADER: &str = "standard";
+
+/**
+ * Shader permutation: each flag compiles in one optional part of the material shader
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ShaderFeatures {
+    // Multiply by the mesh's per-vertex colors
+    pub vertex_colors: bool,
+    // Multiply by `Material::texture`, projected onto the faces
+    pub texture: bool,
+    // Diffuse lighting from a single directional light
+    pub lighting: bool,
+    // Cel shading: the lighting snaps to `Material::toon_bands` flat tones.
+    // Only matters with `lighting`
+    pub toon: bool,
+}
+
+impl ShaderFeatures {
+    /**
+     * The features switched on by a list of define names; others are ignored
+     */
+    pub fn from_defines(defines: &[&str]) -> Self {
+        Self {
+            vertex_colors: defines.contains(&"VERTEX_COLORS"),
+            texture: defines.contains(&"TEXTURE"),
+            lighting: defines.contains(&"LIGHTING"),
+            toon: defines.contains(&"TOON"),
+        }
+    }
+
+    /**
+     * Just the features whose define is in `defines`
+     */
+    fn restricted_to(self, defines: &[&str]) -> Self {
+        let available = Self::from_defines(defines);
+        Self {
+            vertex_colors: self.vertex_colors && available.vertex_colors,
+            texture: self.texture && available.texture,
+            lighting: self.lighting && available.lighting,
+            toon: self.toon && available.toon,
+        }
+    }
+
+    fn defines(&self) -> String {
+        [
+            (self.vertex_colors, "VERTEX_COLORS"),
+            (self.texture, "TEXTURE"),
+            (self.lighting, "LIGHTING"),
+            (self.toon, "TOON"),
+        ]
+        .iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, name)| format!("#define {}\n", name))
+        .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlendMode {
+    #[default]
+    Opaque,
+    // Straight alpha over what's behind; drawn after the opaque objects without
+    // writing depth
+    Alpha,
+}
+
+/**
+ * Silhouette line drawn around objects with the material, as an enlarged
+ * copy of the mesh showing only its back faces (an inverted hull)
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Outline {
+    pub color: [f32; 3],
+    // How much larger the hull is than the object, as a fraction of its size
+    pub width: f32,
+}
+
+impl Default for Outline {
+    fn default() -> Self {
+        Self {
+            color: [0.05; 3],
+            width: 0.06,
+        }
+    }
+}
+
+/**
+ * How an object looks: shader variant, uniform values, textures and the
+ * pipeline state to draw it with
+ */
+#[derive(Clone, Debug)]
+pub struct Material {
+    pub name: String,
+    // Name of the `ShaderLibrary` entry to draw with
+    pub shader: String,
+    pub features: ShaderFeatures,
+    // Straight RGBA multiplied into the result
+    pub color: [f32; 4],
+    // Used when `features.texture` is set; a placeholder stands in while `None`
+    pub texture: Option<Texture>,
+    pub blend: BlendMode,
+    // Draw back faces too
+    pub double_sided: bool,
+    // Tones in the lighting ramp when `features.toon` is set
+    pub toon_bands: u32,
+    // Drawn around the object's silhouette; static props merged into batches
+    // and reflections go without
+    pub outline: Option<Outline>,
+}
+
+impl Default for Material {
+    /**
+     * Plain vertex colors, like the scene before materials existed
+     */
+    fn default() -> Self {
+        Self {
+            name: "Vertex colors".to_string(),
+            shader: STANDARD_SHADER.to_string(),
+            features: ShaderFeatures {
+                vertex_colors: true,
+                ..Default::default()
+            },
+            color: [1.0; 4],
+            texture: None,
+            blend: BlendMode::Opaque,
+            double_sided: false,
+            toon_bands: 3,
+            outline: None,
+        }
+    }
+}
+
+impl Material {
+    /**
+     * Set this material's pipeline state and uniforms on `program`, which must be
+     * the library's program for `self.shader` and `self.features`, already in use
+     */
+    pub fn apply(
+        &self,
+        gl: &WebGl2RenderingContext,
+        program: &MaterialProgram,
+        placeholder: &Texture,
+    ) {
+        gl.uniform4fv_with_f32_array(program.base_color_loc.as_ref(), &self.color);
+        if self.features.texture {
+            self.texture.as_ref().unwrap_or(placeholder).bind(gl, 0);
+            gl.uniform1i(program.base_texture_loc.as_ref(), 0);
+        }
+        if self.features.toon {
+            gl.uniform1f(program.toon_bands_loc.as_ref(), self.toon_bands as f32);
+        }
+        match self.blend {
+            BlendMode::Opaque => {
+                gl.disable(WebGl2RenderingContext::BLEND);
+                gl.depth_mask(true);
+            }
+            BlendMode::Alpha => {
+                gl.enable(WebGl2RenderingContext::BLEND);
+                gl.blend_func(
+                    WebGl2RenderingContext::SRC_ALPHA,
+                    WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+                );
+                gl.depth_mask(false);
+            }
+        }
+        if self.double_sided {
+            gl.disable(WebGl2RenderingContext::CULL_FACE);
+        } else {
+            gl.enable(WebGl2RenderingContext::CULL_FACE);
+        }
+    }
+}
+
+/**
+ * A compiled material shader permutation and its uniform locations. Shaders
+ * other than the standard one may leave out any of the uniforms
+ */
+pub struct MaterialProgram {
+    // Unique per library, in compilation order; a cheap sort key
+    pub id: usize,
+    pub program: WebGlProgram,
+    pub mvp_loc: Option<WebGlUniformLocation>,
+    pub model_loc: Option<WebGlUniformLocation>,
+    pub light_direction_loc: Option<WebGlUniformLocation>,
+    pub flash_loc: Option<WebGlUniformLocation>,
+    pub tint_loc: Option<WebGlUniformLocation>,
+    base_color_loc: Option<WebGlUniformLocation>,
+    base_texture_loc: Option<WebGlUniformLocation>,
+    toon_bands_loc: Option<WebGlUniformLocation>,
+}
+
+/**
+ * A registered shader's source and the permutations compiled from it so far
+ */
+struct Shader {
+    vertex: String,
+    fragment: String,
+    // Defines the source tests for; features it ignores share one program
+    // instead of compiling identical copies
+    defines: &'static [&'static str],
+    programs: HashMap<ShaderFeatures, Rc<MaterialProgram>>,
+}
+
+/**
+ * Material shaders by name. Each permutation is compiled once, on first use,
+ * and shared between every material that asks for the same shader and
+ * features; registering a name again swaps its source in at runtime
+ */
+pub struct ShaderLibrary {
+    shaders: RefCell<HashMap<String, Shader>>,
+    next_id: Cell<usize>,
+}
+
+impl ShaderLibrary {
+    /**
+     * A library holding the standard shader, `shaders/material`
+     */
+    pub fn new(gl: &WebGl2RenderingContext) -> Result<Self, String> {
+        let library = Self {
+            shaders: RefCell::default(),
+            next_id: Cell::new(0),
+        };
+        let standard = shaders::program("material")
+            .ok_or_else(|| "The material shader is missing from shaders/".to_string())?;
+        library.register_program(gl, STANDARD_SHADER, standard)?;
+        Ok(library)
+    }
+
+    /**
+     * Register a program from `shaders/` and compile every combination of
+     * its defines right away, so materials switching to it never wait on a
+     * compile mid-frame
+     */
+    pub fn register_program(
+        &self,
+        gl: &WebGl2RenderingContext,
+        name: &str,
+        program: &ShaderProgram,
+    ) -> Result<(), String> {
+        self.register(gl, name, program.vertex, program.fragment, program.defines)?;
+        for defines in program.permutations() {
+            self.get(gl, name, ShaderFeatures::from_defines(&defines))?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Add a shader, or replace the source of the one already called `name`.
+     * A replaced shader's permutations in use are recompiled right away, and
+     * if any of them fails the old source stays in place
+     */
+    fn register(
+        &self,
+        gl: &WebGl2RenderingContext,
+        name: &str,
+        vertex: &str,
+        fragment: &str,
+        defines: &'static [&'static str],
+    ) -> Result<(), String> {
+        let in_use: Vec<ShaderFeatures> = match self.shaders.borrow().get(name) {
+            Some(shader) => shader.programs.keys().copied().collect(),
+            None => Vec::new(),
+        };
+        let mut programs = HashMap::new();
+        for features in in_use {
+            let program = compile(gl, vertex, fragment, features, self.take_id())
+                .map_err(|err| format!("Shader {} not replaced: {}", name, err))?;
+            programs.insert(features, Rc::new(program));
+        }
+        let shader = Shader {
+            vertex: vertex.to_string(),
+            fragment: fragment.to_string(),
+            defines,
+            programs,
+        };
+        if let Some(old) = self.shaders.borrow_mut().insert(name.to_string(), shader) {
+            for program in old.programs.values() {
+                gl.delete_program(Some(&program.program));
+            }
+            tracing::info!(target: "renderer", "Replaced shader {}", name);
+        }
+        Ok(())
+    }
+
+    /**
+     * The program for shader `name` with `features`, compiling it if needed
+     */
+    pub fn get(
+        &self,
+        gl: &WebGl2RenderingContext,
+        name: &str,
+        features: ShaderFeatures,
+    ) -> Result<Rc<MaterialProgram>, String> {
+        let mut shaders = self.shaders.borrow_mut();
+        let shader = shaders
+            .get_mut(name)
+            .ok_or_else(|| format!("No shader named {}", name))?;
+        let features = features.restricted_to(shader.defines);
+        if let Some(program) = shader.programs.get(&features) {
+            return Ok(program.clone());
+        }
+        let program = Rc::new(compile(
+            gl,
+            &shader.vertex,
+            &shader.fragment,
+            features,
+            self.take_id(),
+        )?);
+        shader.programs.insert(features, program.clone());
+        Ok(program)
+    }
+
+    fn take_id(&self) -> usize {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        id
+    }
+}
+
+fn compile(
+    gl: &WebGl2RenderingContext,
+    vertex: &str,
+    fragment: &str,
+    features: ShaderFeatures,
+    id: usize,
+) -> Result<MaterialProgram, String> {
+    // Defines have to follow the #version line
+    let with_defines = |source: &str| {
+        let (version, body) = source.split_once('\n').unwrap_or((source, ""));
+        format!("{}\n{}{}", version, features.defines(), body)
+    };
+    let program = gl_util::create_program(gl, &with_defines(vertex), &with_defines(fragment))?;
+    Ok(MaterialProgram {
+        id,
+        mvp_loc: gl.get_uniform_location(&program, "modelViewProjection"),
+        model_loc: gl.get_uniform_location(&program, "model"),
+        light_direction_loc: gl.get_uniform_location(&program, "lightDirection"),
+        flash_loc: gl.get_uniform_location(&program, "flash"),
+        tint_loc: gl.get_uniform_location(&program, "tint"),
+        base_color_loc: gl.get_uniform_location(&program, "baseColor"),
+        base_texture_loc: gl.get_uniform_location(&program, "baseTexture"),
+        toon_bands_loc: gl.get_uniform_location(&program, "toonBands"),
+        program,
+    })
+}
@@ -0,0 +1,502 @@
+// Column-major 4x4 matrices and 3D vectors, laid out the way WebGL expects them
+pub type Mat4 = [f32; 16];
+pub type Vec3 = [f32; 3];
+
+/**
+ * Identity matrix
+ */
+pub fn identity() -> Mat4 {
+    [
+        1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+    ]
+}
+
+/**
+ * X-axis rotation matrix
+ */
+pub fn rotation_matrix_x(angle: f32) -> Mat4 {
+    let (s, c) = angle.sin_cos();
+    [
+        1.0, 0.0, 0.0, 0.0, 0.0, c, -s, 0.0, 0.0, s, c, 0.0, 0.0, 0.0, 0.0, 1.0,
+    ]
+}
+
+/**
+ * Y-axis rotation matrix
+ */
+pub fn rotation_matrix_y(angle: f32) -> Mat4 {
+    let (s, c) = angle.sin_cos();
+    [
+        c, 0.0, s, 0.0, 0.0, 1.0, 0.0, 0.0, -s, 0.0, c, 0.0, 0.0, 0.0, 0.0, 1.0,
+    ]
+}
+
+/**
+ * Z-axis rotation matrix
+ */
+pub fn rotation_matrix_z(angle: f32) -> Mat4 {
+    let (s, c) = angle.sin_cos();
+    [
+        c, -s, 0.0, 0.0, s, c, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+    ]
+}
+
+/**
+ * Translation matrix
+ */
+pub fn translation(t: Vec3) -> Mat4 {
+    [
+        1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, t[0], t[1], t[2], 1.0,
+    ]
+}
+
+/**
+ * Non-uniform scale matrix
+ */
+pub fn scaling(s: Vec3) -> Mat4 {
+    [
+        s[0], 0.0, 0.0, 0.0, 0.0, s[1], 0.0, 0.0, 0.0, 0.0, s[2], 0.0, 0.0, 0.0, 0.0, 1.0,
+    ]
+}
+
+/**
+ * Flattens geometry onto the plane y = `height` along the directional light
+ * `light_dir` (pointing towards the light, with a positive y), for planar shadows
+ */
+pub fn planar_shadow(light_dir: Vec3, height: f32) -> Mat4 {
+    let sx = light_dir[0] / light_dir[1];
+    let sz = light_dir[2] / light_dir[1];
+    let (ox, oz) = (sx * height, sz * height);
+    [
+        1.0, 0.0, 0.0, 0.0, -sx, 0.0, -sz, 0.0, 0.0, 0.0, 1.0, 0.0, ox, height, oz, 1.0,
+    ]
+}
+
+/**
+ * Perspective projection matrix (right-handed, clip space z in [-1, 1])
+ */
+pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> Mat4 {
+    let f = 1.0 / (fov_y / 2.0).tan();
+    let nf = 1.0 / (near - far);
+    [
+        f / aspect,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        f,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        (far + near) * nf,
+        -1.0,
+        0.0,
+        0.0,
+        2.0 * far * near * nf,
+        0.0,
+    ]
+}
+
+/**
+ * Orthographic projection matrix mapping the given box to clip space
+ */
+pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Mat4 {
+    let rl = 1.0 / (right - left);
+    let tb = 1.0 / (top - bottom);
+    let nf = 1.0 / (near - far);
+    [
+        2.0 * rl,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        2.0 * tb,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        2.0 * nf,
+        0.0,
+        -(right + left) * rl,
+        -(top + bottom) * tb,
+        (far + near) * nf,
+        1.0,
+    ]
+}
+
+/**
+ * View matrix looking from `eye` towards `target`
+ */
+pub fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> Mat4 {
+    let z = normalize(sub(eye, target));
+    let x = normalize(cross(up, z));
+    let y = cross(z, x);
+    [
+        x[0],
+        y[0],
+        z[0],
+        0.0,
+        x[1],
+        y[1],
+        z[1],
+        0.0,
+        x[2],
+        y[2],
+        z[2],
+        0.0,
+        -dot(x, eye),
+        -dot(y, eye),
+        -dot(z, eye),
+        1.0,
+    ]
+}
+
+/**
+ * Matrix product `a * b`
+ */
+pub fn multiply(a: &Mat4, b: &Mat4) -> Mat4 {
+    let mut out = [0.0; 16];
+    for col in 0..4 {
+        for row in 0..4 {
+            out[col * 4 + row] = (0..4).map(|k| a[k * 4 + row] * b[col * 4 + k]).sum();
+        }
+    }
+    out
+}
+
+/**
+ * General 4x4 inverse, `None` when the matrix is singular
+ */
+pub fn invert(m: &Mat4) -> Option<Mat4> {
+    let mut inv = [0.0f32; 16];
+
+    inv[0] = m[5] * m[10] * m[15] - m[5] * m[11] * m[14] - m[9] * m[6] * m[15]
+        + m[9] * m[7] * m[14]
+        + m[13] * m[6] * m[11]
+        - m[13] * m[7] * m[10];
+    inv[4] = -m[4] * m[10] * m[15] + m[4] * m[11] * m[14] + m[8] * m[6] * m[15]
+        - m[8] * m[7] * m[14]
+        - m[12] * m[6] * m[11]
+        + m[12] * m[7] * m[10];
+    inv[8] = m[4] * m[9] * m[15] - m[4] * m[11] * m[13] - m[8] * m[5] * m[15]
+        + m[8] * m[7] * m[13]
+        + m[12] * m[5] * m[11]
+        - m[12] * m[7] * m[9];
+    inv[12] = -m[4] * m[9] * m[14] + m[4] * m[10] * m[13] + m[8] * m[5] * m[14]
+        - m[8] * m[6] * m[13]
+        - m[12] * m[5] * m[10]
+        + m[12] * m[6] * m[9];
+    inv[1] = -m[1] * m[10] * m[15] + m[1] * m[11] * m[14] + m[9] * m[2] * m[15]
+        - m[9] * m[3] * m[14]
+        - m[13] * m[2] * m[11]
+        + m[13] * m[3] * m[10];
+    inv[5] = m[0] * m[10] * m[15] - m[0] * m[11] * m[14] - m[8] * m[2] * m[15]
+        + m[8] * m[3] * m[14]
+        + m[12] * m[2] * m[11]
+        - m[12] * m[3] * m[10];
+    inv[9] = -m[0] * m[9] * m[15] + m[0] * m[11] * m[13] + m[8] * m[1] * m[15]
+        - m[8] * m[3] * m[13]
+        - m[12] * m[1] * m[11]
+        + m[12] * m[3] * m[9];
+    inv[13] = m[0] * m[9] * m[14] - m[0] * m[10] * m[13] - m[8] * m[1] * m[14]
+        + m[8] * m[2] * m[13]
+        + m[12] * m[1] * m[10]
+        - m[12] * m[2] * m[9];
+    inv[2] = m[1] * m[6] * m[15] - m[1] * m[7] * m[14] - m[5] * m[2] * m[15]
+        + m[5] * m[3] * m[14]
+        + m[13] * m[2] * m[7]
+        - m[13] * m[3] * m[6];
+    inv[6] = -m[0] * m[6] * m[15] + m[0] * m[7] * m[14] + m[4] * m[2] * m[15]
+        - m[4] * m[3] * m[14]
+        - m[12] * m[2] * m[7]
+        + m[12] * m[3] * m[6];
+    inv[10] = m[0] * m[5] * m[15] - m[0] * m[7] * m[13] - m[4] * m[1] * m[15]
+        + m[4] * m[3] * m[13]
+        + m[12] * m[1] * m[7]
+        - m[12] * m[3] * m[5];
+    inv[14] = -m[0] * m[5] * m[14] + m[0] * m[6] * m[13] + m[4] * m[1] * m[14]
+        - m[4] * m[2] * m[13]
+        - m[12] * m[1] * m[6]
+        + m[12] * m[2] * m[5];
+    inv[3] = -m[1] * m[6] * m[11] + m[1] * m[7] * m[10] + m[5] * m[2] * m[11]
+        - m[5] * m[3] * m[10]
+        - m[9] * m[2] * m[7]
+        + m[9] * m[3] * m[6];
+    inv[7] = m[0] * m[6] * m[11] - m[0] * m[7] * m[10] - m[4] * m[2] * m[11]
+        + m[4] * m[3] * m[10]
+        + m[8] * m[2] * m[7]
+        - m[8] * m[3] * m[6];
+    inv[11] = -m[0] * m[5] * m[11] + m[0] * m[7] * m[9] + m[4] * m[1] * m[11]
+        - m[4] * m[3] * m[9]
+        - m[8] * m[1] * m[7]
+        + m[8] * m[3] * m[5];
+    inv[15] = m[0] * m[5] * m[10] - m[0] * m[6] * m[9] - m[4] * m[1] * m[10]
+        + m[4] * m[2] * m[9]
+        + m[8] * m[1] * m[6]
+        - m[8] * m[2] * m[5];
+
+    let det = m[0] * inv[0] + m[1] * inv[4] + m[2] * inv[8] + m[3] * inv[12];
+    if det == 0.0 {
+        return None;
+    }
+
+    let inv_det = 1.0 / det;
+    Some(inv.map(|v| v * inv_det))
+}
+
+/**
+ * Transform a point by `m`, including the perspective divide
+ */
+pub fn transform_point(m: &Mat4, p: Vec3) -> Vec3 {
+    let x = m[0] * p[0] + m[4] * p[1] + m[8] * p[2] + m[12];
+    let y = m[1] * p[0] + m[5] * p[1] + m[9] * p[2] + m[13];
+    let z = m[2] * p[0] + m[6] * p[1] + m[10] * p[2] + m[14];
+    let w = m[3] * p[0] + m[7] * p[1] + m[11] * p[2] + m[15];
+    if w != 0.0 && w != 1.0 {
+        [x / w, y / w, z / w]
+    } else {
+        [x, y, z]
+    }
+}
+
+/**
+ * Transform a direction by `m`, leaving out the translation
+ */
+pub fn transform_vector(m: &Mat4, v: Vec3) -> Vec3 {
+    [
+        m[0] * v[0] + m[4] * v[1] + m[8] * v[2],
+        m[1] * v[0] + m[5] * v[1] + m[9] * v[2],
+        m[2] * v[0] + m[6] * v[1] + m[10] * v[2],
+    ]
+}
+
+pub fn transpose(m: &Mat4) -> Mat4 {
+    std::array::from_fn(|i| m[(i % 4) * 4 + i / 4])
+}
+
+/**
+ * Angles that rebuild rotation matrix `m` when applied X, then Y, then Z, as
+ * `rotation_matrix_z(z) * rotation_matrix_y(y) * rotation_matrix_x(x)`.
+ * With Y at a quarter turn X and Z turn about the same axis, so Z is zero
+ */
+pub fn euler_angles(m: &Mat4) -> Vec3 {
+    let y = m[2].clamp(-1.0, 1.0).asin();
+    if m[2].abs() < 0.9999 {
+        [-m[6].atan2(m[10]), y, -m[1].atan2(m[0])]
+    } else {
+        [m[9].atan2(m[5]), y, 0.0]
+    }
+}
+
+/**
+ * Canvas pixel coordinates (top-left origin) to normalized device coordinates
+ */
+pub fn pixel_to_ndc(x: f64, y: f64, width: f64, height: f64) -> (f32, f32) {
+    (
+        (2.0 * x / width - 1.0) as f32,
+        (1.0 - 2.0 * y / height) as f32,
+    )
+}
+
+/**
+ * Canvas pixel position (top-left origin) of world-space point `p`, or `None`
+ * when it is behind the camera
+ */
+pub fn world_to_screen(view_proj: &Mat4, p: Vec3, width: f32, height: f32) -> Option<[f32; 2]> {
+    let m = view_proj;
+    let w = m[3] * p[0] + m[7] * p[1] + m[11] * p[2] + m[15];
+    if w <= 0.0 {
+        return None;
+    }
+    let x = (m[0] * p[0] + m[4] * p[1] + m[8] * p[2] + m[12]) / w;
+    let y = (m[1] * p[0] + m[5] * p[1] + m[9] * p[2] + m[13]) / w;
+    Some([(x + 1.0) * 0.5 * width, (1.0 - y) * 0.5 * height])
+}
+
+pub fn add(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+pub fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+pub fn scale(v: Vec3, s: f32) -> Vec3 {
+    [v[0] * s, v[1] * s, v[2] * s]
+}
+
+pub fn dot(a: Vec3, b: Vec3) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+pub fn length(v: Vec3) -> f32 {
+    dot(v, v).sqrt()
+}
+
+pub fn normalize(v: Vec3) -> Vec3 {
+    let len = length(v);
+    if len > 0.0 {
+        scale(v, 1.0 / len)
+    } else {
+        v
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_3};
+
+    fn assert_near(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-4, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn identity_is_neutral() {
+        let m = translation([1.0, 2.0, 3.0]);
+        assert_eq!(multiply(&identity(), &m), m);
+        assert_eq!(multiply(&m, &identity()), m);
+    }
+
+    #[test]
+    fn multiply_applies_right_operand_first() {
+        let m = multiply(&translation([1.0, 0.0, 0.0]), &scaling([2.0; 3]));
+        assert_near(&transform_point(&m, [1.0, 1.0, 1.0]), &[3.0, 2.0, 2.0]);
+    }
+
+    #[test]
+    fn rotations_turn_axes() {
+        // Written out row by row, so in column-major storage each one turns
+        // clockwise looking down its axis towards the origin
+        let x = rotation_matrix_x(FRAC_PI_2);
+        assert_near(&transform_point(&x, [0.0, 1.0, 0.0]), &[0.0, 0.0, -1.0]);
+        let y = rotation_matrix_y(FRAC_PI_2);
+        assert_near(&transform_point(&y, [1.0, 0.0, 0.0]), &[0.0, 0.0, 1.0]);
+        let z = rotation_matrix_z(FRAC_PI_2);
+        assert_near(&transform_point(&z, [1.0, 0.0, 0.0]), &[0.0, -1.0, 0.0]);
+        // The opposite angle undoes the rotation
+        let m = multiply(&rotation_matrix_y(0.8), &rotation_matrix_y(-0.8));
+        assert_near(&m, &identity());
+    }
+
+    #[test]
+    fn invert_round_trips() {
+        let m = multiply(
+            &translation([1.0, -2.0, 0.5]),
+            &multiply(&rotation_matrix_y(0.7), &scaling([2.0, 3.0, 0.5])),
+        );
+        let inverse = invert(&m).unwrap();
+        assert_near(&multiply(&m, &inverse), &identity());
+        assert_near(&multiply(&inverse, &m), &identity());
+    }
+
+    #[test]
+    fn invert_rejects_singular_matrices() {
+        assert_eq!(invert(&scaling([1.0, 0.0, 1.0])), None);
+    }
+
+    #[test]
+    fn perspective_maps_near_and_far_to_clip_range() {
+        let m = perspective(FRAC_PI_3, 1.5, 0.1, 100.0);
+        assert_near(&[transform_point(&m, [0.0, 0.0, -0.1])[2]], &[-1.0]);
+        assert_near(&[transform_point(&m, [0.0, 0.0, -100.0])[2]], &[1.0]);
+        // Half the vertical field of view reaches the top edge
+        let top = (FRAC_PI_3 / 2.0).tan() * 10.0;
+        assert_near(&[transform_point(&m, [0.0, top, -10.0])[1]], &[1.0]);
+        assert_near(&[transform_point(&m, [top * 1.5, 0.0, -10.0])[0]], &[1.0]);
+    }
+
+    #[test]
+    fn orthographic_maps_box_to_clip_cube() {
+        let m = orthographic(-2.0, 4.0, -1.0, 3.0, 0.5, 10.0);
+        assert_near(
+            &transform_point(&m, [-2.0, -1.0, -0.5]),
+            &[-1.0, -1.0, -1.0],
+        );
+        assert_near(&transform_point(&m, [4.0, 3.0, -10.0]), &[1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn look_at_puts_target_down_negative_z() {
+        let eye = [3.0, 2.0, 5.0];
+        let target = [1.0, 0.0, -1.0];
+        let view = look_at(eye, target, [0.0, 1.0, 0.0]);
+        assert_near(&transform_point(&view, eye), &[0.0; 3]);
+        let distance = length(sub(target, eye));
+        assert_near(&transform_point(&view, target), &[0.0, 0.0, -distance]);
+    }
+
+    #[test]
+    fn world_to_screen_centers_the_view_axis() {
+        let view_proj = multiply(
+            &perspective(FRAC_PI_3, 2.0, 0.1, 100.0),
+            &look_at([0.0, 0.0, 5.0], [0.0; 3], [0.0, 1.0, 0.0]),
+        );
+        assert_near(
+            &world_to_screen(&view_proj, [0.0; 3], 800.0, 400.0).unwrap(),
+            &[400.0, 200.0],
+        );
+        assert_eq!(
+            world_to_screen(&view_proj, [0.0, 0.0, 10.0], 800.0, 400.0),
+            None
+        );
+    }
+
+    #[test]
+    fn euler_angles_rebuild_the_rotation() {
+        let rotation = |[x, y, z]: Vec3| {
+            multiply(
+                &rotation_matrix_z(z),
+                &multiply(&rotation_matrix_y(y), &rotation_matrix_x(x)),
+            )
+        };
+        for angles in [
+            [0.3, -0.7, 1.2],
+            [-2.5, 0.1, 0.4],
+            [0.0, 0.0, -3.0],
+            [0.8, FRAC_PI_2, 0.0],
+        ] {
+            let m = rotation(angles);
+            assert_near(&euler_angles(&m), &angles);
+        }
+        // At the pole only the combined turn is recoverable
+        let pole = rotation([0.5, -FRAC_PI_2, 0.25]);
+        assert_near(&rotation(euler_angles(&pole)), &pole);
+    }
+
+    #[test]
+    fn vectors_ignore_translation() {
+        let m = multiply(&translation([5.0, 0.0, 0.0]), &scaling([2.0, 1.0, 1.0]));
+        assert_eq!(transform_vector(&m, [1.0, 1.0, 0.0]), [2.0, 1.0, 0.0]);
+        assert_eq!(transpose(&transpose(&m)), m);
+        assert_eq!(transpose(&m)[3], 5.0);
+    }
+
+    #[test]
+    fn pixel_to_ndc_flips_y() {
+        assert_eq!(pixel_to_ndc(0.0, 0.0, 200.0, 100.0), (-1.0, 1.0));
+        assert_eq!(pixel_to_ndc(200.0, 100.0, 200.0, 100.0), (1.0, -1.0));
+        assert_eq!(pixel_to_ndc(100.0, 50.0, 200.0, 100.0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn vector_helpers() {
+        assert_eq!(cross([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]), [0.0, 0.0, 1.0]);
+        assert_eq!(dot([1.0, 2.0, 3.0], [4.0, 5.0, 6.0]), 32.0);
+        assert_near(&normalize([3.0, 0.0, 4.0]), &[0.6, 0.0, 0.8]);
+        // Zero vectors are left alone rather than turned into NaN
+        assert_eq!(normalize([0.0; 3]), [0.0; 3]);
+    }
+}
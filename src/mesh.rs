@@ -0,0 +1,864 @@
+use std::collections::HashMap;
+
+use web_sys::{WebGl2RenderingContext, WebGlBuffer, WebGlVertexArrayObject};
+
+use crate::atlas::UvRect;
+use crate::bounds::Aabb;
+use crate::gl_util::GlResource;
+use crate::gpu_memory::{Allocation, Kind};
+use crate::math::{self, Vec3};
+use crate::vertex_format::{AttributeFormat, VertexFormat};
+
+// Attribute locations shared by every mesh shader (`layout(location = N)`)
+pub const POSITION_LOCATION: u32 = 0;
+pub const COLOR_LOCATION: u32 = 1;
+pub const UV_LOCATION: u32 = 2;
+// Cosine between triangle normals above which they count as one flat face
+const COPLANAR: f32 = 0.999;
+
+/**
+ * How a mesh's vertex attributes are laid out in GPU buffers
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VertexLayout {
+    // One tightly packed buffer per attribute
+    #[default]
+    Separate,
+    // A single buffer of whole vertices (position then color), so everything
+    // one vertex needs is fetched from the same cache lines
+    Interleaved,
+}
+
+/**
+ * Smooth normal of each vertex of an indexed triangle list: the normals of
+ * the faces around it, weighted by their area, then normalized. Vertices no
+ * triangle uses get a zero normal
+ */
+pub fn vertex_normals(positions: &[f32], indices: &[u16]) -> Vec<Vec3> {
+    let position = |index: usize| -> Vec3 {
+        let i = index * 3;
+        [positions[i], positions[i + 1], positions[i + 2]]
+    };
+    let mut normals = vec![[0.0f32; 3]; positions.len() / 3];
+    for corners in indices.chunks_exact(3) {
+        let [a, b, c] = [corners[0], corners[1], corners[2]].map(|index| index as usize);
+        // The cross product's length is twice the triangle's area
+        let normal = math::cross(
+            math::sub(position(b), position(a)),
+            math::sub(position(c), position(a)),
+        );
+        for vertex in [a, b, c] {
+            normals[vertex] = math::add(normals[vertex], normal);
+        }
+    }
+    normals.into_iter().map(math::normalize).collect()
+}
+
+/**
+ * CPU-side mesh: tightly packed xyz positions, rgb colors and triangle indices
+ */
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "fullstack", derive(serde::Serialize, serde::Deserialize))]
+pub struct MeshData {
+    pub positions: Vec<f32>,
+    pub colors: Vec<f32>,
+    pub indices: Vec<u16>,
+}
+
+impl MeshData {
+    /**
+     * Unit cube centered on the origin with the classic per-corner colors
+     */
+    pub fn cube() -> Self {
+        Self {
+            positions: vec![
+                // Four front-face vertices (Z=0.5)
+                -0.5, -0.5, 0.5, 0.5, -0.5, 0.5, 0.5, 0.5, 0.5, -0.5, 0.5, 0.5,
+                // Four back-face vertices (Z=-0.5)
+                -0.5, -0.5, -0.5, 0.5, -0.5, -0.5, 0.5, 0.5, -0.5, -0.5, 0.5, -0.5,
+            ],
+            colors: vec![
+                // Front face colors
+                1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 0.0,
+                // Back face colors
+                1.0, 0.0, 1.0, 0.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.5, 0.5, 0.5,
+            ],
+            indices: vec![
+                // Front
+                0, 1, 2, 2, 3, 0, // Back
+                4, 6, 5, 6, 4, 7, // Left
+                4, 0, 3, 3, 7, 4, // Right
+                1, 5, 6, 6, 2, 1, // Top
+                3, 2, 6, 6, 7, 3, // Bottom
+                4, 5, 1, 1, 0, 4,
+            ],
+        }
+    }
+
+    /**
+     * Sphere of diameter 1 centered on the origin, split into `segments`
+     * around the vertical axis and `rings` from pole to pole, colored by
+     * its normals
+     */
+    pub fn sphere(segments: u16, rings: u16) -> Self {
+        let (segments, rings) = (segments.max(3), rings.max(2));
+        let mut mesh = Self::default();
+        for ring in 0..=rings {
+            let theta = ring as f32 / rings as f32 * std::f32::consts::PI;
+            for segment in 0..=segments {
+                let phi = segment as f32 / segments as f32 * std::f32::consts::TAU;
+                let normal = [
+                    theta.sin() * phi.cos(),
+                    theta.cos(),
+                    -theta.sin() * phi.sin(),
+                ];
+                mesh.positions.extend(normal.map(|n| n * 0.5));
+                mesh.colors.extend(normal.map(|n| n * 0.5 + 0.5));
+            }
+        }
+        // The seam repeats the first column so every ring has its own end
+        let row = segments + 1;
+        for ring in 0..rings {
+            for segment in 0..segments {
+                let a = ring * row + segment;
+                let (b, c, d) = (a + row, a + row + 1, a + 1);
+                // Triangles that collapse into a pole are skipped
+                if ring > 0 {
+                    mesh.indices.extend([a, b, d]);
+                }
+                if ring < rings - 1 {
+                    mesh.indices.extend([d, b, c]);
+                }
+            }
+        }
+        mesh
+    }
+
+    /**
+     * The same surface with no vertices shared between faces, so every face
+     * is one solid color instead of a blend of its corners. A face is a set
+     * of coplanar triangles joined by edges, like the two halves of a cube
+     * side, and takes the average color of its corners. Fails when the
+     * unshared vertices wouldn't fit 16-bit indices
+     */
+    pub fn flat_shaded(&self) -> Result<Self, String> {
+        let triangles: Vec<[usize; 3]> = self
+            .indices
+            .chunks_exact(3)
+            .map(|triangle| [0, 1, 2].map(|corner| triangle[corner] as usize))
+            .collect();
+        if triangles.len() * 3 > u16::MAX as usize + 1 {
+            return Err(format!(
+                "{} triangles are too many to flat shade with 16-bit indices",
+                triangles.len()
+            ));
+        }
+        let position = |index: usize| -> Vec3 {
+            let i = index * 3;
+            [
+                self.positions[i],
+                self.positions[i + 1],
+                self.positions[i + 2],
+            ]
+        };
+        let normals: Vec<Vec3> = triangles
+            .iter()
+            .map(|&[a, b, c]| {
+                let (a, b, c) = (position(a), position(b), position(c));
+                math::normalize(math::cross(math::sub(b, a), math::sub(c, a)))
+            })
+            .collect();
+
+        // Union-find over triangles: the first triangle seen on each edge is
+        // joined by any later coplanar one
+        let mut parents: Vec<usize> = (0..triangles.len()).collect();
+        let mut edges = HashMap::<(usize, usize), usize>::new();
+        for (triangle, corners) in triangles.iter().enumerate() {
+            for (k, &a) in corners.iter().enumerate() {
+                let b = corners[(k + 1) % 3];
+                let edge = (a.min(b), a.max(b));
+                match edges.get(&edge) {
+                    Some(&other) if math::dot(normals[triangle], normals[other]) > COPLANAR => {
+                        let (root, other_root) = (
+                            find_root(&mut parents, triangle),
+                            find_root(&mut parents, other),
+                        );
+                        parents[root] = other_root;
+                    }
+                    Some(_) => {}
+                    None => {
+                        edges.insert(edge, triangle);
+                    }
+                }
+            }
+        }
+
+        // Each face's distinct corners, averaged
+        let mut corners = HashMap::<usize, Vec<usize>>::new();
+        for (triangle, vertices) in triangles.iter().enumerate() {
+            let face = corners
+                .entry(find_root(&mut parents, triangle))
+                .or_default();
+            for vertex in vertices {
+                if !face.contains(vertex) {
+                    face.push(*vertex);
+                }
+            }
+        }
+        let face_colors: HashMap<usize, [f32; 3]> = corners
+            .into_iter()
+            .map(|(face, vertices)| {
+                let mut sum = [0.0; 3];
+                for vertex in &vertices {
+                    for (channel, total) in sum.iter_mut().enumerate() {
+                        *total += self
+                            .colors
+                            .get(vertex * 3 + channel)
+                            .copied()
+                            .unwrap_or(1.0);
+                    }
+                }
+                (face, sum.map(|total| total / vertices.len() as f32))
+            })
+            .collect();
+
+        let mut flat = Self::default();
+        for (triangle, vertices) in triangles.iter().enumerate() {
+            let color = face_colors[&find_root(&mut parents, triangle)];
+            for &vertex in vertices {
+                flat.indices.push((flat.positions.len() / 3) as u16);
+                flat.positions.extend(position(vertex));
+                flat.colors.extend(color);
+            }
+        }
+        Ok(flat)
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        Aabb::from_positions(&self.positions)
+    }
+
+    pub fn normals(&self) -> Vec<Vec3> {
+        vertex_normals(&self.positions, &self.indices)
+    }
+
+    /**
+     * Vertex attributes encoded in `format`; each vertex gets its position
+     * and/or color, so both together give an interleaved buffer
+     */
+    pub fn encode(&self, format: VertexFormat, positions: bool, colors: bool) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for (position, color) in self
+            .positions
+            .chunks_exact(3)
+            .zip(self.colors.chunks_exact(3))
+        {
+            if positions {
+                format.encode_position(position, &mut bytes);
+            }
+            if colors {
+                format.encode_color(color, &mut bytes);
+            }
+        }
+        bytes
+    }
+}
+
+fn find_root(parents: &mut [usize], mut node: usize) -> usize {
+    while parents[node] != node {
+        // Path halving keeps later lookups short
+        parents[node] = parents[parents[node]];
+        node = parents[node];
+    }
+    node
+}
+
+/**
+ * Mesh uploaded to the GPU, with its attribute bindings captured in a VAO
+ */
+pub struct GpuMesh {
+    vao: WebGlVertexArrayObject,
+    // Kept alive alongside the VAO that references them
+    buffers: Vec<WebGlBuffer>,
+    index_count: i32,
+    vertex_bytes: usize,
+    _allocation: Allocation,
+}
+
+impl GpuMesh {
+    pub fn upload(gl: &WebGl2RenderingContext, data: &MeshData) -> Option<Self> {
+        Self::upload_with(gl, data, VertexLayout::default(), VertexFormat::default())
+    }
+
+    pub fn upload_with(
+        gl: &WebGl2RenderingContext,
+        data: &MeshData,
+        layout: VertexLayout,
+        format: VertexFormat,
+    ) -> Option<Self> {
+        let vao = gl.create_vertex_array()?;
+        gl.bind_vertex_array(Some(&vao));
+
+        let position = format.position();
+        let color = format.color();
+        let mut buffers = match layout {
+            VertexLayout::Separate => vec![
+                create_attribute_buffer(
+                    gl,
+                    &data.encode(format, true, false),
+                    3,
+                    &[(POSITION_LOCATION, position, 0)],
+                    0,
+                )?,
+                create_attribute_buffer(
+                    gl,
+                    &data.encode(format, false, true),
+                    3,
+                    &[(COLOR_LOCATION, color, 0)],
+                    0,
+                )?,
+            ],
+            VertexLayout::Interleaved => vec![create_attribute_buffer(
+                gl,
+                &data.encode(format, true, true),
+                3,
+                &[
+                    (POSITION_LOCATION, position, 0),
+                    (COLOR_LOCATION, color, position.bytes),
+                ],
+                position.bytes + color.bytes,
+            )?],
+        };
+
+        // The element array binding is stored in the VAO
+        buffers.push(create_index_buffer(gl, &data.indices)?);
+        gl.bind_vertex_array(None);
+
+        let vertex_bytes = data.positions.len() / 3 * (position.bytes + color.bytes) as usize;
+        let index_bytes = data.indices.len() * std::mem::size_of::<u16>();
+        Some(Self {
+            vao,
+            buffers,
+            index_count: data.indices.len() as i32,
+            vertex_bytes,
+            _allocation: Allocation::new(Kind::Buffer, vertex_bytes + index_bytes),
+        })
+    }
+
+    /**
+     * GPU memory taken by the vertex attributes, not counting indices
+     */
+    pub fn vertex_bytes(&self) -> usize {
+        self.vertex_bytes
+    }
+
+    pub fn draw(&self, gl: &WebGl2RenderingContext) {
+        gl.bind_vertex_array(Some(&self.vao));
+        gl.draw_elements_with_i32(
+            WebGl2RenderingContext::TRIANGLES,
+            self.index_count,
+            WebGl2RenderingContext::UNSIGNED_SHORT,
+            0,
+        );
+        gl.bind_vertex_array(None);
+    }
+}
+
+impl GlResource for GpuMesh {
+    fn delete(&self, gl: &WebGl2RenderingContext) {
+        self.vao.delete(gl);
+        for buffer in &self.buffers {
+            buffer.delete(gl);
+        }
+    }
+}
+
+/**
+ * CPU-side mesh with texture coordinates instead of colors: xyz positions,
+ * uv pairs and triangle indices
+ */
+#[derive(Clone, Debug, Default)]
+pub struct TexturedMeshData {
+    pub positions: Vec<f32>,
+    pub uvs: Vec<f32>,
+    pub indices: Vec<u16>,
+}
+
+impl TexturedMeshData {
+    /**
+     * Unit cube centered on the origin where every face has its own four
+     * vertices, spanning 0..1 in UV with v increasing downwards like image
+     * rows. Faces come in +X, -X, +Y, -Y, +Z, -Z order; face `f` owns
+     * vertices `4f..4f + 4` and indices `6f..6f + 6`, so it can be drawn or
+     * textured on its own
+     */
+    pub fn cube() -> Self {
+        // (normal, right, up) with right × up = normal, so faces wind outwards
+        const FACES: [[Vec3; 3]; 6] = [
+            [[1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]],
+            [[-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]],
+            [[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]],
+            [[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]],
+            [[0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            [[0.0, 0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+        ];
+        let mut mesh = Self::default();
+        for (face, [normal, right, up]) in FACES.iter().enumerate() {
+            // Bottom-left, bottom-right, top-right, top-left
+            for uv in [[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]] {
+                let position = math::add(
+                    math::scale(*normal, 0.5),
+                    math::add(
+                        math::scale(*right, uv[0] - 0.5),
+                        math::scale(*up, 0.5 - uv[1]),
+                    ),
+                );
+                mesh.positions.extend(position);
+                mesh.uvs.extend(uv);
+            }
+            let first = face as u16 * 4;
+            mesh.indices
+                .extend([0, 1, 2, 2, 3, 0].map(|corner| first + corner));
+        }
+        mesh
+    }
+
+    pub fn normals(&self) -> Vec<Vec3> {
+        vertex_normals(&self.positions, &self.indices)
+    }
+
+    /**
+     * Direction of increasing u at each vertex, made perpendicular to its
+     * normal from `normals`, as a normal map's tangent space expects.
+     * Vertices where u doesn't change get a zero tangent
+     */
+    pub fn tangents(&self, normals: &[Vec3]) -> Vec<Vec3> {
+        let position = |index: usize| -> Vec3 {
+            let i = index * 3;
+            [
+                self.positions[i],
+                self.positions[i + 1],
+                self.positions[i + 2],
+            ]
+        };
+        let uv = |index: usize| [self.uvs[index * 2], self.uvs[index * 2 + 1]];
+        let mut tangents = vec![[0.0f32; 3]; normals.len()];
+        for corners in self.indices.chunks_exact(3) {
+            let [a, b, c] = [corners[0], corners[1], corners[2]].map(|index| index as usize);
+            let (edge1, edge2) = (
+                math::sub(position(b), position(a)),
+                math::sub(position(c), position(a)),
+            );
+            let (uv_a, uv_b, uv_c) = (uv(a), uv(b), uv(c));
+            let (du1, dv1) = (uv_b[0] - uv_a[0], uv_b[1] - uv_a[1]);
+            let (du2, dv2) = (uv_c[0] - uv_a[0], uv_c[1] - uv_a[1]);
+            let det = du1 * dv2 - du2 * dv1;
+            if det.abs() < 1e-12 {
+                continue;
+            }
+            let tangent = math::scale(
+                math::sub(math::scale(edge1, dv2), math::scale(edge2, dv1)),
+                1.0 / det,
+            );
+            for vertex in [a, b, c] {
+                tangents[vertex] = math::add(tangents[vertex], tangent);
+            }
+        }
+        tangents
+            .iter()
+            .zip(normals)
+            .map(|(&tangent, &normal)| {
+                let along_normal = math::scale(normal, math::dot(normal, tangent));
+                math::normalize(math::sub(tangent, along_normal))
+            })
+            .collect()
+    }
+
+    /**
+     * Squeeze the UVs of the vertices in `vertices` into `rect`, such as one
+     * image's region of an atlas
+     */
+    pub fn map_uvs(&mut self, vertices: std::ops::Range<usize>, rect: &UvRect) {
+        for uv in self.uvs[vertices.start * 2..vertices.end * 2].chunks_exact_mut(2) {
+            let [u, v] = rect.remap([uv[0], uv[1]]);
+            uv.copy_from_slice(&[u, v]);
+        }
+    }
+}
+
+/**
+ * Textured mesh uploaded to the GPU: float positions and UVs in their own
+ * buffers, captured in a VAO
+ */
+pub struct GpuTexturedMesh {
+    vao: WebGlVertexArrayObject,
+    buffers: Vec<WebGlBuffer>,
+    index_count: i32,
+    _allocation: Allocation,
+}
+
+impl GpuTexturedMesh {
+    pub fn upload(gl: &WebGl2RenderingContext, data: &TexturedMeshData) -> Option<Self> {
+        let vao = gl.create_vertex_array()?;
+        gl.bind_vertex_array(Some(&vao));
+        let float =
+            |values: &[f32]| -> Vec<u8> { values.iter().flat_map(|v| v.to_le_bytes()).collect() };
+        let uv = AttributeFormat {
+            kind: WebGl2RenderingContext::FLOAT,
+            normalized: false,
+            bytes: 8,
+        };
+        let buffers = vec![
+            create_attribute_buffer(
+                gl,
+                &float(&data.positions),
+                3,
+                &[(POSITION_LOCATION, VertexFormat::Float.position(), 0)],
+                0,
+            )?,
+            create_attribute_buffer(gl, &float(&data.uvs), 2, &[(UV_LOCATION, uv, 0)], 0)?,
+            create_index_buffer(gl, &data.indices)?,
+        ];
+        gl.bind_vertex_array(None);
+
+        let bytes = (data.positions.len() + data.uvs.len()) * std::mem::size_of::<f32>()
+            + data.indices.len() * std::mem::size_of::<u16>();
+        Some(Self {
+            vao,
+            buffers,
+            index_count: data.indices.len() as i32,
+            _allocation: Allocation::new(Kind::Buffer, bytes),
+        })
+    }
+
+    pub fn draw(&self, gl: &WebGl2RenderingContext) {
+        self.draw_range(gl, 0, self.index_count);
+    }
+
+    /**
+     * Draw `count` indices from `first` on, such as one face of
+     * `TexturedMeshData::cube`
+     */
+    pub fn draw_range(&self, gl: &WebGl2RenderingContext, first: i32, count: i32) {
+        gl.bind_vertex_array(Some(&self.vao));
+        gl.draw_elements_with_i32(
+            WebGl2RenderingContext::TRIANGLES,
+            count,
+            WebGl2RenderingContext::UNSIGNED_SHORT,
+            first * std::mem::size_of::<u16>() as i32,
+        );
+        gl.bind_vertex_array(None);
+    }
+}
+
+impl GlResource for GpuTexturedMesh {
+    fn delete(&self, gl: &WebGl2RenderingContext) {
+        self.vao.delete(gl);
+        for buffer in &self.buffers {
+            buffer.delete(gl);
+        }
+    }
+}
+
+/**
+ * Upload 16-bit triangle indices into a buffer bound to the current VAO
+ */
+fn create_index_buffer(gl: &WebGl2RenderingContext, indices: &[u16]) -> Option<WebGlBuffer> {
+    let buffer = gl.create_buffer()?;
+    gl.bind_buffer(WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER, Some(&buffer));
+    unsafe {
+        let array = js_sys::Uint16Array::view(indices);
+        gl.buffer_data_with_array_buffer_view(
+            WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER,
+            &array,
+            WebGl2RenderingContext::STATIC_DRAW,
+        );
+    }
+    Some(buffer)
+}
+
+/**
+ * Upload `data` and point each (location, format, byte offset) attribute of
+ * `components` values into it
+ */
+fn create_attribute_buffer(
+    gl: &WebGl2RenderingContext,
+    data: &[u8],
+    components: i32,
+    attributes: &[(u32, AttributeFormat, i32)],
+    stride: i32,
+) -> Option<WebGlBuffer> {
+    let buffer = gl.create_buffer()?;
+    gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&buffer));
+    unsafe {
+        let array = js_sys::Uint8Array::view(data);
+        gl.buffer_data_with_array_buffer_view(
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            &array,
+            WebGl2RenderingContext::STATIC_DRAW,
+        );
+    }
+    for &(location, format, offset) in attributes {
+        gl.enable_vertex_attrib_array(location);
+        gl.vertex_attrib_pointer_with_i32(
+            location,
+            components,
+            format.kind,
+            format.normalized,
+            stride,
+            offset,
+        );
+    }
+    Some(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::gl_util;
+
+    #[test]
+    fn cube_spans_unit_box() {
+        let cube = MeshData::cube();
+        assert_eq!(cube.positions.len(), 8 * 3);
+        assert_eq!(cube.colors.len(), cube.positions.len());
+        assert_eq!(cube.indices.len(), 12 * 3);
+        let bounds = cube.bounds();
+        assert_eq!(bounds.min, [-0.5; 3]);
+        assert_eq!(bounds.max, [0.5; 3]);
+    }
+
+    #[test]
+    fn smooth_normals_average_the_faces_around_a_vertex() {
+        // A square in the XY plane, wound counterclockwise
+        let square = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0];
+        let normals = vertex_normals(&square, &[0, 1, 2, 2, 3, 0]);
+        assert_eq!(normals, vec![[0.0, 0.0, 1.0]; 4]);
+        // Corner 0 of the cube is (-0.5, -0.5, 0.5), between three faces
+        let corner = MeshData::cube().normals()[0];
+        assert!(corner[0] < 0.0 && corner[1] < 0.0 && corner[2] > 0.0);
+        assert!((math::length(corner) - 1.0).abs() < 1e-5);
+        assert_eq!(vertex_normals(&[0.0; 3], &[]), vec![[0.0; 3]]);
+    }
+
+    #[test]
+    fn tangents_follow_u_across_each_face() {
+        let cube = TexturedMeshData::cube();
+        let normals = cube.normals();
+        let tangents = cube.tangents(&normals);
+        // +X face: faces outwards, u runs towards -Z
+        assert_eq!(normals[0], [1.0, 0.0, 0.0]);
+        assert_eq!(tangents[0], [0.0, 0.0, -1.0]);
+        // +Y face, whose u runs along +X
+        assert_eq!(tangents[8], [1.0, 0.0, 0.0]);
+        for (normal, tangent) in normals.iter().zip(&tangents) {
+            assert!(math::dot(*normal, *tangent).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn textured_cube_faces_wind_outwards_with_their_own_uvs() {
+        let cube = TexturedMeshData::cube();
+        assert_eq!(cube.positions.len(), 24 * 3);
+        assert_eq!(cube.uvs.len(), 24 * 2);
+        let vertex = |index: u16| {
+            let i = index as usize * 3;
+            [
+                cube.positions[i],
+                cube.positions[i + 1],
+                cube.positions[i + 2],
+            ]
+        };
+        for (face, triangles) in cube.indices.chunks_exact(6).enumerate() {
+            for triangle in triangles.chunks_exact(3) {
+                assert!(triangle.iter().all(|&i| i as usize / 4 == face));
+                let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(vertex);
+                let normal = math::cross(math::sub(b, a), math::sub(c, a));
+                let center = math::scale(math::add(math::add(a, b), c), 1.0 / 3.0);
+                assert!(math::dot(normal, center) > 0.0);
+            }
+        }
+        // Every face covers the whole image once
+        for face in cube.uvs.chunks_exact(8) {
+            let mut corners: Vec<[i32; 2]> = face
+                .chunks_exact(2)
+                .map(|uv| [uv[0] as i32, uv[1] as i32])
+                .collect();
+            corners.sort();
+            assert_eq!(corners, [[0, 0], [0, 1], [1, 0], [1, 1]]);
+        }
+    }
+
+    #[test]
+    fn map_uvs_only_moves_the_given_vertices() {
+        let mut cube = TexturedMeshData::cube();
+        let rect = UvRect {
+            min: [0.5, 0.0],
+            max: [0.75, 0.5],
+        };
+        cube.map_uvs(4..8, &rect);
+        assert_eq!(&cube.uvs[..8], &TexturedMeshData::cube().uvs[..8]);
+        assert_eq!(&cube.uvs[8..10], &[0.5, 0.5]);
+        assert_eq!(&cube.uvs[12..14], &[0.75, 0.0]);
+    }
+
+    #[test]
+    fn sphere_points_lie_on_its_surface_and_wind_outwards() {
+        let sphere = MeshData::sphere(12, 8);
+        assert_eq!(sphere.positions.len(), 13 * 9 * 3);
+        assert_eq!(sphere.indices.len(), (12 * 8 * 2 - 2 * 12) * 3);
+        let vertex = |index: u16| {
+            let i = index as usize * 3;
+            [
+                sphere.positions[i],
+                sphere.positions[i + 1],
+                sphere.positions[i + 2],
+            ]
+        };
+        for position in sphere.positions.chunks_exact(3) {
+            assert!((math::length([position[0], position[1], position[2]]) - 0.5).abs() < 1e-5);
+        }
+        for triangle in sphere.indices.chunks_exact(3) {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(vertex);
+            let normal = math::cross(math::sub(b, a), math::sub(c, a));
+            let center = math::scale(math::add(math::add(a, b), c), 1.0 / 3.0);
+            assert!(math::dot(normal, center) > 0.0);
+        }
+    }
+
+    #[test]
+    fn cube_faces_wind_outwards() {
+        let cube = MeshData::cube();
+        let vertex = |index: u16| {
+            let i = index as usize * 3;
+            [
+                cube.positions[i],
+                cube.positions[i + 1],
+                cube.positions[i + 2],
+            ]
+        };
+        for triangle in cube.indices.chunks_exact(3) {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(vertex);
+            let normal = math::cross(math::sub(b, a), math::sub(c, a));
+            let center = math::scale(math::add(math::add(a, b), c), 1.0 / 3.0);
+            // Counterclockwise from outside, so culling keeps the front faces
+            assert!(math::dot(normal, center) > 0.0, "{:?}", triangle);
+        }
+    }
+
+    #[test]
+    fn flat_shading_gives_each_cube_side_one_color() {
+        let cube = MeshData::cube();
+        let flat = cube.flat_shaded().unwrap();
+        assert_eq!(flat.positions.len(), 36 * 3);
+        assert_eq!(flat.colors.len(), flat.positions.len());
+        assert_eq!(flat.indices, (0..36).collect::<Vec<u16>>());
+        let color = |vertex: usize| &flat.colors[vertex * 3..vertex * 3 + 3];
+        // Six vertices, two triangles, per side
+        for side in 0..6 {
+            let first = color(side * 6);
+            assert!((side * 6..side * 6 + 6).all(|vertex| color(vertex) == first));
+        }
+        // The front side averages its four corners: red, green, blue and yellow
+        assert_eq!(color(0), [0.5, 0.5, 0.25]);
+        assert_ne!(color(0), color(6));
+    }
+
+    #[test]
+    fn flat_shading_keeps_the_surface() {
+        let cube = MeshData::cube();
+        let flat = cube.flat_shaded().unwrap();
+        assert_eq!(flat.bounds(), cube.bounds());
+        for (triangle, original) in flat
+            .indices
+            .chunks_exact(3)
+            .zip(cube.indices.chunks_exact(3))
+        {
+            for (&vertex, &source) in triangle.iter().zip(original) {
+                let (v, s) = (vertex as usize * 3, source as usize * 3);
+                assert_eq!(flat.positions[v..v + 3], cube.positions[s..s + 3]);
+            }
+        }
+    }
+
+    #[test]
+    fn flat_shading_needs_16_bit_room() {
+        let mesh = MeshData {
+            positions: vec![0.0; 9],
+            colors: vec![0.0; 9],
+            indices: [0, 1, 2].repeat(30_000),
+        };
+        assert!(mesh.flat_shaded().is_err());
+    }
+
+    #[test]
+    fn encode_sizes_follow_format() {
+        let cube = MeshData::cube();
+        let vertices = cube.positions.len() / 3;
+        assert_eq!(
+            cube.encode(VertexFormat::Float, true, true).len(),
+            vertices * 24
+        );
+        assert_eq!(
+            cube.encode(VertexFormat::Float, true, false).len(),
+            vertices * 12
+        );
+        assert_eq!(
+            cube.encode(VertexFormat::Compact, true, true).len(),
+            vertices * 12
+        );
+        assert_eq!(
+            cube.encode(VertexFormat::Compact, false, true).len(),
+            vertices * 4
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn uploads_each_layout_and_format() {
+        let gl = gl_util::test_context();
+        let cube = MeshData::cube();
+        let buffer_size = |buffer: &WebGlBuffer| {
+            gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(buffer));
+            let size = gl
+                .get_buffer_parameter(
+                    WebGl2RenderingContext::ARRAY_BUFFER,
+                    WebGl2RenderingContext::BUFFER_SIZE,
+                )
+                .as_f64()
+                .unwrap() as usize;
+            gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, None);
+            size
+        };
+        for layout in [VertexLayout::Separate, VertexLayout::Interleaved] {
+            for format in [VertexFormat::Float, VertexFormat::Compact] {
+                let mesh = GpuMesh::upload_with(&gl, &cube, layout, format).unwrap();
+                let (index_buffer, vertex_buffers) = mesh.buffers.split_last().unwrap();
+                let vertex_bytes: usize = vertex_buffers.iter().map(buffer_size).sum();
+                assert_eq!(
+                    vertex_bytes,
+                    mesh.vertex_bytes(),
+                    "{:?} {:?}",
+                    layout,
+                    format
+                );
+                assert_eq!(buffer_size(index_buffer), cube.indices.len() * 2);
+                assert_eq!(mesh.index_count, cube.indices.len() as i32);
+
+                // The VAO captured both attributes
+                gl.bind_vertex_array(Some(&mesh.vao));
+                for location in [POSITION_LOCATION, COLOR_LOCATION] {
+                    let enabled = gl.get_vertex_attrib(
+                        location,
+                        WebGl2RenderingContext::VERTEX_ATTRIB_ARRAY_ENABLED,
+                    );
+                    assert_eq!(enabled.unwrap().as_bool(), Some(true));
+                }
+                gl.bind_vertex_array(None);
+                assert_eq!(gl.get_error(), WebGl2RenderingContext::NO_ERROR);
+                mesh.delete(&gl);
+            }
+        }
+    }
+}
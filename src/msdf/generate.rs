@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+
+use crate::atlas::UvRect;
+use crate::canvas_texture::offscreen_canvas;
+
+use super::{Bounds, FontAtlas, Glyph};
+
+// Atlas width glyph cells are packed into, in pixels
+const ATLAS_WIDTH: u32 = 1024;
+
+/**
+ * Build an atlas for `characters` from a font the browser already has, so
+ * text can be drawn without shipping an atlas. Each glyph is drawn with the
+ * 2D canvas at `size` pixels and turned into a single-channel signed distance
+ * field `range` pixels wide, copied into all three channels: the median of
+ * equal channels is that one distance, so the MSDF shader draws it unchanged,
+ * just with rounder corners than a true multi-channel field. Advances and
+ * kerning come from the canvas's text measurements
+ */
+pub fn generate(
+    font_family: &str,
+    size: f64,
+    range: f64,
+    characters: &str,
+) -> Result<(FontAtlas, u32, u32, Vec<u8>), String> {
+    let (canvas, context) = offscreen_canvas(1, 1)?;
+    let font = format!("{}px {}", size, font_family);
+    context.set_font(&font);
+    let measure = |text: &str| {
+        context
+            .measure_text(text)
+            .map_err(|_| "Unable to measure text".to_string())
+    };
+
+    let characters: Vec<char> = {
+        let mut unique: Vec<char> = characters.chars().filter(|c| *c != '\n').collect();
+        unique.sort_unstable();
+        unique.dedup();
+        unique
+    };
+    let padding = range.ceil();
+    // Per character: advance and, for anything visible, its ink box
+    // (left, right, ascent, descent from the pen on the baseline)
+    let mut measured = Vec::with_capacity(characters.len());
+    for &character in &characters {
+        let metrics = measure(&character.to_string())?;
+        let ink = [
+            metrics.actual_bounding_box_left(),
+            metrics.actual_bounding_box_right(),
+            metrics.actual_bounding_box_ascent(),
+            metrics.actual_bounding_box_descent(),
+        ];
+        let visible = ink[0] + ink[1] > 0.0 && ink[2] + ink[3] > 0.0;
+        measured.push((metrics.width(), visible.then_some(ink)));
+    }
+    let cells: Vec<(u32, u32)> = measured
+        .iter()
+        .map(|(_, ink)| match ink {
+            Some([left, right, ascent, descent]) => (
+                ((left + right).ceil() + padding * 2.0) as u32,
+                ((ascent + descent).ceil() + padding * 2.0) as u32,
+            ),
+            None => (0, 0),
+        })
+        .collect();
+    let (positions, height) = pack(&cells, ATLAS_WIDTH);
+    let width = ATLAS_WIDTH;
+
+    // Resizing resets the context, font included
+    canvas.set_width(width);
+    canvas.set_height(height);
+    context.set_font(&font);
+    context.set_fill_style_str("#ffffff");
+    context.set_text_align("left");
+    context.set_text_baseline("alphabetic");
+    for ((&character, (_, ink)), &(x, y)) in characters.iter().zip(&measured).zip(&positions) {
+        if let Some([left, _, ascent, _]) = ink {
+            let _ = context.fill_text(
+                &character.to_string(),
+                x as f64 + padding + left,
+                y as f64 + padding + ascent,
+            );
+        }
+    }
+    let coverage: Vec<u8> = context
+        .get_image_data(0, 0, width as i32, height as i32)
+        .map_err(|_| "Unable to read back glyphs".to_string())?
+        .data()
+        .0
+        .chunks_exact(4)
+        .map(|pixel| pixel[3])
+        .collect();
+    let field = distance_field(&coverage, width as usize, height as usize, range as f32);
+    let pixels = field
+        .iter()
+        .flat_map(|&distance| [distance, distance, distance, 255])
+        .collect();
+
+    let mut glyphs = HashMap::new();
+    for (((&character, &(advance, ink)), &(x, y)), &(cell_width, cell_height)) in
+        characters.iter().zip(&measured).zip(&positions).zip(&cells)
+    {
+        let quad = ink.map(|[left, _, ascent, _]| {
+            let em = |pixels: f64| (pixels / size) as f32;
+            let bounds = Bounds {
+                left: em(-left - padding),
+                right: em(cell_width as f64 - left - padding),
+                top: em(ascent + padding),
+                bottom: em(ascent + padding - cell_height as f64),
+            };
+            let uv = UvRect {
+                min: [x as f32 / width as f32, y as f32 / height as f32],
+                max: [
+                    (x + cell_width) as f32 / width as f32,
+                    (y + cell_height) as f32 / height as f32,
+                ],
+            };
+            (bounds, uv)
+        });
+        glyphs.insert(
+            character,
+            Glyph {
+                advance: (advance / size) as f32,
+                quad,
+            },
+        );
+    }
+
+    // What a pair measures beyond its two glyphs on their own is kerning
+    let mut kerning = HashMap::new();
+    for (&first, &(first_advance, _)) in characters.iter().zip(&measured) {
+        for (&second, &(second_advance, _)) in characters.iter().zip(&measured) {
+            let pair = measure(&format!("{}{}", first, second))?.width();
+            let adjustment = ((pair - first_advance - second_advance) / size) as f32;
+            if adjustment.abs() > 1e-3 {
+                kerning.insert((first, second), adjustment);
+            }
+        }
+    }
+
+    let line = measure("Hg")?;
+    let ascender = (line.font_bounding_box_ascent() / size) as f32;
+    let descender = -(line.font_bounding_box_descent() / size) as f32;
+    let atlas = FontAtlas {
+        distance_range: range as f32,
+        width,
+        height,
+        line_height: (ascender - descender) * 1.15,
+        ascender,
+        descender,
+        glyphs,
+        kerning,
+    };
+    Ok((atlas, width, height, pixels))
+}
+
+/**
+ * Shelf-pack cells of the given sizes left to right into rows of `width`
+ * pixels, returning each cell's top-left corner and the total height
+ */
+fn pack(cells: &[(u32, u32)], width: u32) -> (Vec<(u32, u32)>, u32) {
+    let (mut x, mut y, mut row_height) = (0, 0, 0);
+    let mut positions = Vec::with_capacity(cells.len());
+    for &(cell_width, cell_height) in cells {
+        if x + cell_width > width && x > 0 {
+            x = 0;
+            y += row_height;
+            row_height = 0;
+        }
+        positions.push((x, y));
+        x += cell_width;
+        row_height = row_height.max(cell_height);
+    }
+    (positions, (y + row_height).max(1))
+}
+
+/**
+ * Signed distance to the edge of the shape covering more than half a pixel in
+ * `coverage`, encoded like an MSDF channel: 128 on the edge, rising to 255
+ * `range / 2` pixels inside and falling to 0 as far outside
+ */
+fn distance_field(coverage: &[u8], width: usize, height: usize, range: f32) -> Vec<u8> {
+    let inside: Vec<bool> = coverage.iter().map(|&alpha| alpha >= 128).collect();
+    // Squared distance from each pixel to the nearest one on the other side
+    let to_outside = squared_distances(&inside, width, height, false);
+    let to_inside = squared_distances(&inside, width, height, true);
+    inside
+        .iter()
+        .zip(to_outside.iter().zip(&to_inside))
+        .map(|(&inside, (&out, &into))| {
+            // The edge lies halfway between the nearest pixels either side
+            let signed = if inside {
+                out.sqrt() - 0.5
+            } else {
+                0.5 - into.sqrt()
+            };
+            ((0.5 + signed / range).clamp(0.0, 1.0) * 255.0).round() as u8
+        })
+        .collect()
+}
+
+/**
+ * Exact squared Euclidean distance from every pixel to the nearest pixel
+ * whose `inside` equals `target`, by Felzenszwalb and Huttenlocher's two
+ * passes of one-dimensional lower envelopes
+ */
+fn squared_distances(inside: &[bool], width: usize, height: usize, target: bool) -> Vec<f32> {
+    let mut grid: Vec<f32> = inside
+        .iter()
+        .map(|&value| if value == target { 0.0 } else { f32::INFINITY })
+        .collect();
+    let mut column = vec![0.0; height];
+    for x in 0..width {
+        for y in 0..height {
+            column[y] = grid[y * width + x];
+        }
+        let transformed = transform_1d(&column);
+        for y in 0..height {
+            grid[y * width + x] = transformed[y];
+        }
+    }
+    for row in grid.chunks_exact_mut(width) {
+        let transformed = transform_1d(row);
+        row.copy_from_slice(&transformed);
+    }
+    grid
+}
+
+fn transform_1d(values: &[f32]) -> Vec<f32> {
+    let n = values.len();
+    let mut result = vec![f32::INFINITY; n];
+    // Parabola vertices and the boundaries between them
+    let mut vertices = Vec::with_capacity(n);
+    let mut boundaries: Vec<f32> = Vec::with_capacity(n + 1);
+    for (q, &value) in values.iter().enumerate() {
+        if value == f32::INFINITY {
+            continue;
+        }
+        let q_f = q as f32;
+        loop {
+            let Some(&v) = vertices.last() else {
+                vertices.push(q);
+                boundaries.clear();
+                boundaries.push(f32::NEG_INFINITY);
+                break;
+            };
+            let v_f = v as f32;
+            let s = ((value + q_f * q_f) - (values[v] + v_f * v_f)) / (2.0 * (q_f - v_f));
+            if s <= *boundaries.last().unwrap() {
+                vertices.pop();
+                boundaries.pop();
+                continue;
+            }
+            vertices.push(q);
+            boundaries.push(s);
+            break;
+        }
+    }
+    if vertices.is_empty() {
+        return result;
+    }
+    let mut k = 0;
+    for (q, distance) in result.iter_mut().enumerate() {
+        let q_f = q as f32;
+        while k + 1 < vertices.len() && boundaries[k + 1] < q_f {
+            k += 1;
+        }
+        let v = vertices[k];
+        *distance = (q_f - v as f32).powi(2) + values[v];
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shelves_wrap_at_the_atlas_width() {
+        let (positions, height) = pack(&[(40, 10), (50, 20), (30, 5), (0, 0)], 100);
+        assert_eq!(positions, vec![(0, 0), (40, 0), (0, 20), (30, 20)]);
+        assert_eq!(height, 25);
+    }
+
+    #[test]
+    fn distances_are_exact_along_a_row() {
+        let inside = [true, false, false, false, true];
+        let distances = squared_distances(&inside, 5, 1, true);
+        assert_eq!(distances, vec![0.0, 1.0, 4.0, 1.0, 0.0]);
+        let diagonal = squared_distances(&[true, false, false, false], 2, 2, true);
+        assert_eq!(diagonal, vec![0.0, 1.0, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn field_crosses_the_midpoint_at_the_edge() {
+        // Left half covered
+        let (width, height) = (16, 4);
+        let coverage: Vec<u8> = (0..width * height)
+            .map(|i| if i % width < 8 { 255 } else { 0 })
+            .collect();
+        let field = distance_field(&coverage, width, height, 4.0);
+        let row = &field[width..width * 2];
+        assert!(row[7] > 128 && row[8] < 128);
+        // Symmetric about the edge, saturating two pixels either side
+        assert_eq!(row[7] as i32 - 128, 127 - row[8] as i32);
+        assert_eq!((row[0], row[15]), (255, 0));
+    }
+}
@@ -0,0 +1,364 @@
+mod generate;
+mod renderer;
+
+use std::collections::HashMap;
+
+use crate::assets;
+use crate::atlas::UvRect;
+use crate::json::{self, Value};
+use crate::mesh::TexturedMeshData;
+
+pub use generate::generate;
+pub use renderer::{MsdfFont, TextMesh};
+
+/**
+ * Rectangle in ems around a glyph, relative to the pen position on the
+ * baseline, y up
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+    pub left: f32,
+    pub bottom: f32,
+    pub right: f32,
+    pub top: f32,
+}
+
+/**
+ * One character of a font atlas. Whitespace has an advance but nothing to draw
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Glyph {
+    pub advance: f32,
+    pub quad: Option<(Bounds, UvRect)>,
+}
+
+/**
+ * Glyph metrics and kerning of a multi-channel signed distance field font
+ * atlas, in the JSON layout msdf-atlas-gen writes. Lengths are in ems, so
+ * text is laid out once and scaled freely
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct FontAtlas {
+    // Width of the band of distances around an edge the field encodes, in
+    // atlas pixels
+    pub distance_range: f32,
+    pub width: u32,
+    pub height: u32,
+    pub line_height: f32,
+    pub ascender: f32,
+    // Below the baseline, so negative
+    pub descender: f32,
+    glyphs: HashMap<char, Glyph>,
+    kerning: HashMap<(char, char), f32>,
+}
+
+/**
+ * A glyph placed by `FontAtlas::layout`: where it goes, in ems, and the part
+ * of the atlas it shows
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GlyphQuad {
+    pub bounds: Bounds,
+    pub uv: UvRect,
+}
+
+/**
+ * Laid out text, its top-left corner at the origin: the first baseline is
+ * `ascender` below it and lines continue downwards, y up
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextLayout {
+    pub quads: Vec<GlyphQuad>,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl FontAtlas {
+    /**
+     * Read msdf-atlas-gen's JSON output: `atlas`, `metrics`, `glyphs` and
+     * optionally `kerning`
+     */
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let root = json::parse(source)?;
+        let atlas = root.get("atlas").ok_or("Font atlas has no atlas section")?;
+        let metrics = root
+            .get("metrics")
+            .ok_or("Font atlas has no metrics section")?;
+        let number = |value: &Value, key: &str| {
+            value
+                .get(key)
+                .and_then(Value::as_f64)
+                .map(|number| number as f32)
+                .ok_or_else(|| format!("Font atlas is missing {}", key))
+        };
+
+        let width = number(atlas, "width")?;
+        let height = number(atlas, "height")?;
+        // Atlas y runs up from the bottom unless it says otherwise
+        let y_down = atlas.get("yOrigin").and_then(Value::as_str) == Some("top");
+        let em = metrics.get("emSize").and_then(Value::as_f64).unwrap_or(1.0) as f32;
+        if em <= 0.0 || width <= 0.0 || height <= 0.0 {
+            return Err("Font atlas has an empty size".to_string());
+        }
+
+        let bounds = |value: &Value, scale: f32| -> Result<Bounds, String> {
+            Ok(Bounds {
+                left: number(value, "left")? * scale,
+                bottom: number(value, "bottom")? * scale,
+                right: number(value, "right")? * scale,
+                top: number(value, "top")? * scale,
+            })
+        };
+        let mut glyphs = HashMap::new();
+        for glyph in root
+            .get("glyphs")
+            .and_then(Value::as_array)
+            .ok_or("Font atlas has no glyphs")?
+        {
+            let Some(character) = code_point(glyph, "unicode") else {
+                continue;
+            };
+            let plane = glyph.get("planeBounds");
+            let pixels = glyph.get("atlasBounds");
+            let quad = match (plane, pixels) {
+                (Some(plane), Some(pixels)) => {
+                    let plane = bounds(plane, 1.0 / em)?;
+                    let pixels = bounds(pixels, 1.0)?;
+                    let v = |y: f32| if y_down { y / height } else { 1.0 - y / height };
+                    let uv = UvRect {
+                        min: [pixels.left / width, v(pixels.top)],
+                        max: [pixels.right / width, v(pixels.bottom)],
+                    };
+                    Some((plane, uv))
+                }
+                _ => None,
+            };
+            glyphs.insert(
+                character,
+                Glyph {
+                    advance: number(glyph, "advance")? / em,
+                    quad,
+                },
+            );
+        }
+
+        let mut kerning = HashMap::new();
+        for pair in root
+            .get("kerning")
+            .and_then(Value::as_array)
+            .unwrap_or_default()
+        {
+            if let (Some(first), Some(second)) =
+                (code_point(pair, "unicode1"), code_point(pair, "unicode2"))
+            {
+                kerning.insert((first, second), number(pair, "advance")? / em);
+            }
+        }
+
+        Ok(Self {
+            distance_range: number(atlas, "distanceRange")?,
+            width: width as u32,
+            height: height as u32,
+            line_height: number(metrics, "lineHeight")? / em,
+            ascender: number(metrics, "ascender")? / em,
+            descender: number(metrics, "descender")? / em,
+            glyphs,
+            kerning,
+        })
+    }
+
+    /**
+     * Place `text` glyph by glyph, applying kerning between neighbours and
+     * starting a new line at each `\n`. Characters the atlas lacks are drawn
+     * as `?` where it has one and skipped otherwise
+     */
+    pub fn layout(&self, text: &str) -> TextLayout {
+        let mut quads = Vec::new();
+        let (mut pen, mut baseline) = (0.0f32, -self.ascender);
+        let mut width = 0.0f32;
+        let mut lines = 1;
+        let mut previous = None;
+        for character in text.chars() {
+            if character == '\n' {
+                width = width.max(pen);
+                pen = 0.0;
+                baseline -= self.line_height;
+                lines += 1;
+                previous = None;
+                continue;
+            }
+            let Some((character, glyph)) = self
+                .glyphs
+                .get_key_value(&character)
+                .or_else(|| self.glyphs.get_key_value(&'?'))
+            else {
+                continue;
+            };
+            if let Some(previous) = previous {
+                pen += self.kerning(previous, *character);
+            }
+            if let Some((bounds, uv)) = glyph.quad {
+                quads.push(GlyphQuad {
+                    bounds: Bounds {
+                        left: pen + bounds.left,
+                        bottom: baseline + bounds.bottom,
+                        right: pen + bounds.right,
+                        top: baseline + bounds.top,
+                    },
+                    uv,
+                });
+            }
+            pen += glyph.advance;
+            previous = Some(*character);
+        }
+        TextLayout {
+            quads,
+            width: width.max(pen),
+            height: (lines - 1) as f32 * self.line_height + self.ascender - self.descender,
+        }
+    }
+
+    /**
+     * Extra advance between `first` and a `second` that follows it
+     */
+    pub fn kerning(&self, first: char, second: char) -> f32 {
+        self.kerning.get(&(first, second)).copied().unwrap_or(0.0)
+    }
+}
+
+impl TextLayout {
+    /**
+     * One quad per glyph in the z = 0 plane, facing +Z
+     */
+    pub fn to_mesh(&self) -> TexturedMeshData {
+        let mut mesh = TexturedMeshData {
+            positions: Vec::with_capacity(self.quads.len() * 12),
+            uvs: Vec::with_capacity(self.quads.len() * 8),
+            indices: Vec::with_capacity(self.quads.len() * 6),
+        };
+        for (index, quad) in self.quads.iter().enumerate() {
+            let Bounds {
+                left,
+                bottom,
+                right,
+                top,
+            } = quad.bounds;
+            let UvRect { min, max } = quad.uv;
+            // Top-left, top-right, bottom-left, bottom-right
+            mesh.positions.extend([
+                left, top, 0.0, right, top, 0.0, left, bottom, 0.0, right, bottom, 0.0,
+            ]);
+            mesh.uvs.extend([
+                min[0], min[1], max[0], min[1], min[0], max[1], max[0], max[1],
+            ]);
+            let first = (index * 4) as u16;
+            mesh.indices
+                .extend([0, 2, 1, 1, 2, 3].map(|corner| first + corner));
+        }
+        mesh
+    }
+}
+
+fn code_point(value: &Value, key: &str) -> Option<char> {
+    value
+        .get(key)
+        .and_then(Value::as_usize)
+        .and_then(|code| char::from_u32(code as u32))
+}
+
+/**
+ * Download a pre-generated atlas: msdf-atlas-gen's JSON metrics and the
+ * matching image, returned as RGBA pixels, top row first
+ */
+pub async fn load(
+    json_url: &str,
+    image_url: &str,
+) -> Result<(FontAtlas, u32, u32, Vec<u8>), String> {
+    let bytes = assets::fetch_bytes(json_url, |_| {}).await?;
+    let source = String::from_utf8(js_sys::Uint8Array::new(&bytes).to_vec())
+        .map_err(|_| format!("{} is not UTF-8", json_url))?;
+    let atlas = FontAtlas::parse(&source)?;
+    let image = assets::decode_image(&assets::fetch_bytes(image_url, |_| {}).await?).await?;
+    let (width, height, pixels) = assets::image_pixels(&image)?;
+    if (width, height) != (atlas.width, atlas.height) {
+        return Err(format!(
+            "{} is {}x{}, but its metrics describe a {}x{} atlas",
+            image_url, width, height, atlas.width, atlas.height
+        ));
+    }
+    Ok((atlas, width, height, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Trimmed from msdf-atlas-gen output, with an emSize of 2 to check scaling
+    const SAMPLE: &str = r#"{
+        "atlas": {"type": "msdf", "distanceRange": 4, "size": 32, "width": 64, "height": 32, "yOrigin": "bottom"},
+        "metrics": {"emSize": 2, "lineHeight": 2.4, "ascender": 1.8, "descender": -0.4},
+        "glyphs": [
+            {"unicode": 32, "advance": 0.5},
+            {"unicode": 65, "advance": 1.2,
+             "planeBounds": {"left": 0, "bottom": 0, "right": 1.2, "top": 1.4},
+             "atlasBounds": {"left": 0, "bottom": 0, "right": 32, "top": 32}},
+            {"unicode": 86, "advance": 1.2,
+             "planeBounds": {"left": 0, "bottom": 0, "right": 1.2, "top": 1.4},
+             "atlasBounds": {"left": 32, "bottom": 0, "right": 64, "top": 32}}
+        ],
+        "kerning": [{"unicode1": 65, "unicode2": 86, "advance": -0.2}]
+    }"#;
+
+    #[test]
+    fn parses_metrics_in_ems() {
+        let atlas = FontAtlas::parse(SAMPLE).unwrap();
+        assert_eq!((atlas.width, atlas.height), (64, 32));
+        assert_eq!(atlas.distance_range, 4.0);
+        assert!((atlas.line_height - 1.2).abs() < 1e-6);
+        assert!((atlas.ascender - 0.9).abs() < 1e-6);
+        assert!((atlas.kerning('A', 'V') + 0.1).abs() < 1e-6);
+        assert_eq!(atlas.kerning('V', 'A'), 0.0);
+        assert!(atlas.glyphs[&' '].quad.is_none());
+
+        let (bounds, uv) = atlas.glyphs[&'V'].quad.unwrap();
+        assert!((bounds.top - 0.7).abs() < 1e-6);
+        // The bottom-up atlas puts the glyph's top at v = 0
+        assert_eq!(uv.min, [0.5, 0.0]);
+        assert_eq!(uv.max, [1.0, 1.0]);
+    }
+
+    #[test]
+    fn layout_applies_kerning_and_line_breaks() {
+        let atlas = FontAtlas::parse(SAMPLE).unwrap();
+        let layout = atlas.layout("AV\nA V");
+        assert_eq!(layout.quads.len(), 4);
+        // V is pulled towards A by the kerning pair
+        assert!((layout.quads[1].bounds.left - 0.5).abs() < 1e-6);
+        // The second line starts one line height further down
+        let first = layout.quads[0].bounds;
+        let third = layout.quads[2].bounds;
+        assert_eq!(third.left, 0.0);
+        assert!((first.bottom - third.bottom - 1.2).abs() < 1e-6);
+        // A space separates the glyphs instead of kerning
+        assert!((layout.quads[3].bounds.left - 0.85).abs() < 1e-6);
+        assert!((layout.width - 1.45).abs() < 1e-6);
+        assert!((layout.height - (1.2 + 0.9 + 0.2)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn missing_glyphs_are_skipped_without_a_fallback() {
+        let atlas = FontAtlas::parse(SAMPLE).unwrap();
+        let layout = atlas.layout("A?A");
+        assert_eq!(layout.quads.len(), 2);
+        assert!((layout.width - 1.2).abs() < 1e-6);
+
+        let mesh = layout.to_mesh();
+        assert_eq!(mesh.positions.len(), 2 * 4 * 3);
+        assert_eq!(mesh.indices[6..], [4, 6, 5, 5, 6, 7]);
+    }
+
+    #[test]
+    fn rejects_atlases_without_metrics() {
+        assert!(FontAtlas::parse(r#"{"atlas": {"width": 1, "height": 1}}"#).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use web_sys::{WebGl2RenderingContext, WebGlProgram, WebGlUniformLocation};
+
+use crate::gl_util::{self, GlResource};
+use crate::math::Mat4;
+use crate::mesh::GpuTexturedMesh;
+use crate::shaders;
+use crate::texture::Texture;
+
+use super::FontAtlas;
+
+/**
+ * Text laid out and uploaded once, one quad per glyph, drawn as often as
+ * needed. Its top-left corner is at the origin with y up, in ems
+ */
+pub struct TextMesh {
+    mesh: GpuTexturedMesh,
+    width: f32,
+    height: f32,
+}
+
+impl TextMesh {
+    /**
+     * Size in ems: the widest line by all the lines
+     */
+    pub fn size(&self) -> (f32, f32) {
+        (self.width, self.height)
+    }
+}
+
+impl GlResource for TextMesh {
+    fn delete(&self, gl: &WebGl2RenderingContext) {
+        self.mesh.delete(gl);
+    }
+}
+
+/**
+ * A font atlas on the GPU with the shader that draws it. The atlas stays
+ * sharp when magnified because its texels hold distances to the glyph edges,
+ * which the shader thresholds at about a screen pixel wide
+ */
+pub struct MsdfFont {
+    atlas: FontAtlas,
+    texture: Texture,
+    program: WebGlProgram,
+    mvp_loc: Option<WebGlUniformLocation>,
+    atlas_loc: Option<WebGlUniformLocation>,
+    unit_range_loc: Option<WebGlUniformLocation>,
+    color_loc: Option<WebGlUniformLocation>,
+}
+
+impl MsdfFont {
+    /**
+     * Upload the atlas image, RGBA pixels top row first, as `generate` and
+     * `load` return them
+     */
+    pub fn new(
+        gl: &WebGl2RenderingContext,
+        atlas: FontAtlas,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+    ) -> Result<Self, String> {
+        let program = gl_util::create_program(gl, shaders::MSDF_VERT, shaders::MSDF_FRAG)?;
+        let texture = Texture::from_rgba_linear(gl, width as i32, height as i32, pixels)
+            .ok_or_else(|| "Unable to create font atlas texture".to_string())?;
+        Ok(Self {
+            mvp_loc: gl.get_uniform_location(&program, "modelViewProjection"),
+            atlas_loc: gl.get_uniform_location(&program, "atlas"),
+            unit_range_loc: gl.get_uniform_location(&program, "unitRange"),
+            color_loc: gl.get_uniform_location(&program, "color"),
+            atlas,
+            texture,
+            program,
+        })
+    }
+
+    /**
+     * Lay out `text` and upload its glyph quads
+     */
+    pub fn build(&self, gl: &WebGl2RenderingContext, text: &str) -> Result<TextMesh, String> {
+        let layout = self.atlas.layout(text);
+        let mesh = GpuTexturedMesh::upload(gl, &layout.to_mesh())
+            .ok_or_else(|| "Unable to upload text mesh".to_string())?;
+        Ok(TextMesh {
+            mesh,
+            width: layout.width,
+            height: layout.height,
+        })
+    }
+
+    /**
+     * Draw `text` blended over what is already there, transformed by
+     * `matrix` from ems to clip space. Depth is tested but not written
+     */
+    pub fn draw(
+        &self,
+        gl: &WebGl2RenderingContext,
+        text: &TextMesh,
+        matrix: &Mat4,
+        color: [f32; 4],
+    ) {
+        gl.use_program(Some(&self.program));
+        self.texture.bind(gl, 0);
+        gl.uniform1i(self.atlas_loc.as_ref(), 0);
+        gl.uniform_matrix4fv_with_f32_array(self.mvp_loc.as_ref(), false, matrix);
+        gl.uniform2f(
+            self.unit_range_loc.as_ref(),
+            self.atlas.distance_range / self.atlas.width as f32,
+            self.atlas.distance_range / self.atlas.height as f32,
+        );
+        gl.uniform4fv_with_f32_array(self.color_loc.as_ref(), &color);
+        gl.disable(WebGl2RenderingContext::CULL_FACE);
+        gl.enable(WebGl2RenderingContext::BLEND);
+        gl.blend_func(
+            WebGl2RenderingContext::SRC_ALPHA,
+            WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+        );
+        gl.depth_mask(false);
+        text.mesh.draw(gl);
+        gl.depth_mask(true);
+        gl.disable(WebGl2RenderingContext::BLEND);
+    }
+}
+
+impl GlResource for MsdfFont {
+    fn delete(&self, gl: &WebGl2RenderingContext) {
+        self.texture.delete(gl);
+        gl.delete_program(Some(&self.program));
+    }
+}
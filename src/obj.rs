@@ -0,0 +1,179 @@
+use crate::bounds::Aabb;
+use crate::mesh::MeshData;
+
+/**
+ * Parse the geometry of a Wavefront OBJ file (`v` and `f` records; texture
+ * coordinates, normals, groups and materials are ignored). Polygons are
+ * triangulated as fans, and vertices are colored by their position within the
+ * model's bounds since OBJ has no standard vertex colors
+ */
+pub fn parse(source: &str) -> Result<MeshData, String> {
+    let mut positions = Vec::new();
+    let mut indices = Vec::new();
+
+    for (line_number, line) in source.lines().enumerate() {
+        let mut fields = line.split_whitespace();
+        let error = |message: &str| format!("OBJ line {}: {}", line_number + 1, message);
+        match fields.next() {
+            Some("v") => {
+                let coords = fields
+                    .take(3)
+                    .map(|field| field.parse::<f32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| error("invalid vertex coordinate"))?;
+                if coords.len() != 3 {
+                    return Err(error("vertex needs three coordinates"));
+                }
+                positions.extend(coords);
+            }
+            Some("f") => {
+                let vertex_count = positions.len() / 3;
+                let corners = fields
+                    .map(|field| {
+                        // `v`, `v/vt`, `v//vn` or `v/vt/vn`; negative indices count from the end
+                        let index = field
+                            .split('/')
+                            .next()
+                            .and_then(|index| index.parse::<i64>().ok())
+                            .ok_or_else(|| error("invalid face index"))?;
+                        let resolved = if index < 0 {
+                            vertex_count as i64 + index
+                        } else {
+                            index - 1
+                        };
+                        if resolved < 0 || resolved >= vertex_count as i64 {
+                            return Err(error("face index out of range"));
+                        }
+                        u16::try_from(resolved).map_err(|_| error("more than 65536 vertices"))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                if corners.len() < 3 {
+                    return Err(error("face needs at least three vertices"));
+                }
+                for i in 1..corners.len() - 1 {
+                    indices.extend([corners[0], corners[i], corners[i + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if indices.is_empty() {
+        return Err("OBJ file has no faces".to_string());
+    }
+
+    let bounds = Aabb::from_positions(&positions);
+    let colors = positions
+        .chunks_exact(3)
+        .flat_map(|p| {
+            (0..3).map(move |axis| {
+                let extent = bounds.max[axis] - bounds.min[axis];
+                if extent > 0.0 {
+                    0.25 + 0.75 * (p[axis] - bounds.min[axis]) / extent
+                } else {
+                    1.0
+                }
+            })
+        })
+        .collect();
+
+    Ok(MeshData {
+        positions,
+        colors,
+        indices,
+    })
+}
+
+/**
+ * Serialize a mesh as a Wavefront OBJ file named `name`. Vertex colors go on
+ * the `v` lines as the widely read `v x y z r g b` extension, and smooth
+ * normals are averaged from the faces around each vertex, area-weighted
+ */
+pub fn write(mesh: &MeshData, name: &str) -> String {
+    let vertex_count = mesh.positions.len() / 3;
+    let position = |index: usize| {
+        let i = index * 3;
+        [
+            mesh.positions[i],
+            mesh.positions[i + 1],
+            mesh.positions[i + 2],
+        ]
+    };
+    let normals = mesh.normals();
+
+    let mut out = format!(
+        "# {} vertices, {} triangles\no {}\n",
+        vertex_count,
+        mesh.indices.len() / 3,
+        name
+    );
+    let has_colors = mesh.colors.len() == mesh.positions.len();
+    for vertex in 0..vertex_count {
+        let [x, y, z] = position(vertex);
+        if has_colors {
+            let color = &mesh.colors[vertex * 3..vertex * 3 + 3];
+            out.push_str(&format!(
+                "v {} {} {} {} {} {}\n",
+                x, y, z, color[0], color[1], color[2]
+            ));
+        } else {
+            out.push_str(&format!("v {} {} {}\n", x, y, z));
+        }
+    }
+    for normal in &normals {
+        // Vertices no face uses keep a zero normal
+        let [x, y, z] = *normal;
+        out.push_str(&format!("vn {} {} {}\n", x, y, z));
+    }
+    // OBJ indices are 1-based; each corner uses the normal with its own index
+    for corners in mesh.indices.chunks_exact(3) {
+        let [a, b, c] = [corners[0], corners[1], corners[2]].map(|index| index as usize + 1);
+        out.push_str(&format!("f {a}//{a} {b}//{b} {c}//{c}\n"));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn triangulates_polygons_as_fans() {
+        let mesh = parse(
+            "# a quad and a triangle\nv 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 0 0 1\no quad\nf 1/1/1 2/2/1 3/3/1 4/4/1\nf -1 1 2\n",
+        )
+        .unwrap();
+        assert_eq!(mesh.positions.len(), 15);
+        assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3, 4, 0, 1]);
+        assert_eq!(mesh.colors.len(), mesh.positions.len());
+    }
+
+    #[test]
+    fn colors_span_bounds() {
+        let mesh = parse("v 0 0 0\nv 2 0 0\nv 0 4 0\nf 1 2 3\n").unwrap();
+        // Flat along z, so that channel stays at full brightness
+        assert_eq!(&mesh.colors[..3], &[0.25, 0.25, 1.0]);
+        assert_eq!(&mesh.colors[3..6], &[1.0, 0.25, 1.0]);
+    }
+
+    #[test]
+    fn reports_errors_with_line_numbers() {
+        assert_eq!(
+            parse("v 0 0 0\nv 1 0\n").unwrap_err(),
+            "OBJ line 2: vertex needs three coordinates"
+        );
+        assert_eq!(
+            parse("v 0 0 0\nf 1 2 3\n").unwrap_err(),
+            "OBJ line 2: face index out of range"
+        );
+        assert_eq!(parse("v 0 0 0\n").unwrap_err(), "OBJ file has no faces");
+    }
+
+    #[test]
+    fn written_files_parse_back() {
+        let cube = MeshData::cube();
+        let parsed = parse(&write(&cube, "cube")).unwrap();
+        assert_eq!(parsed.positions, cube.positions);
+        assert_eq!(parsed.indices, cube.indices);
+    }
+}
@@ -0,0 +1,515 @@
+use std::collections::{HashMap, HashSet};
+
+use web_sys::{WebGl2RenderingContext, WebGlProgram, WebGlUniformLocation};
+
+use crate::bounds::Aabb;
+use crate::frustum::Frustum;
+use crate::gl_util::{self, GlResource};
+use crate::math::{self, Mat4, Vec3};
+use crate::mesh::{GpuMesh, MeshData};
+use crate::raycast::{self, Ray};
+use crate::scene::Transform;
+use crate::selection::{self, ScreenRect, Selection};
+use crate::shaders;
+
+const LIGHT_DIRECTION: Vec3 = [0.36, 0.8, 0.48];
+
+/**
+ * The mesh an object is drawn with
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Shape {
+    Cube,
+    Sphere,
+}
+
+impl Shape {
+    pub fn name(self) -> &'static str {
+        match self {
+            Shape::Cube => "Cube",
+            Shape::Sphere => "Sphere",
+        }
+    }
+
+    pub fn mesh(self) -> MeshData {
+        match self {
+            Shape::Cube => MeshData::cube(),
+            Shape::Sphere => MeshData::sphere(24, 16),
+        }
+    }
+
+    /**
+     * Local bounds of the mesh; both shapes fill the unit box around the origin
+     */
+    pub fn bounds(self) -> Aabb {
+        Aabb {
+            min: [-0.5; 3],
+            max: [0.5; 3],
+        }
+    }
+}
+
+/**
+ * One entry of a scene described as plain data, the way a UI edits it. `id`
+ * identifies the entry across edits, so it has to be unique and must not
+ * change while the entry exists
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjectDesc {
+    pub id: u64,
+    pub name: String,
+    pub shape: Shape,
+    pub transform: Transform,
+    pub tint: [f32; 4],
+    pub visible: bool,
+    // Radians per second about each axis, added to the rotation over time
+    pub spin: Vec3,
+}
+
+impl ObjectDesc {
+    /**
+     * Model matrix `time` seconds in, with the spin applied
+     */
+    pub fn matrix(&self, time: f32) -> Mat4 {
+        self.transform_at(time).matrix()
+    }
+
+    /**
+     * World-space box around the object `time` seconds in
+     */
+    pub fn world_bounds(&self, time: f32) -> Aabb {
+        self.shape.bounds().transformed(&self.matrix(time))
+    }
+
+    /**
+     * The transform `time` seconds in, with the spin added to the rotation
+     */
+    pub fn transform_at(&self, time: f32) -> Transform {
+        Transform {
+            rotation: math::add(self.transform.rotation, math::scale(self.spin, time)),
+            ..self.transform
+        }
+    }
+}
+
+/**
+ * What happened to one entry between two versions of a list
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change {
+    Added(u64),
+    Removed(u64),
+    // Only per-draw state differs, so the GPU objects stay
+    Updated(u64),
+    // A different shape, so the mesh is rebuilt
+    Reshaped(u64),
+}
+
+/**
+ * Changes turning `old` into `new`, matching entries by id: removals first,
+ * then the rest in `new`'s order. Entries that are equal produce nothing
+ */
+pub fn diff(old: &[ObjectDesc], new: &[ObjectDesc]) -> Vec<Change> {
+    let previous: HashMap<u64, &ObjectDesc> =
+        old.iter().map(|object| (object.id, object)).collect();
+    let current: HashSet<u64> = new.iter().map(|object| object.id).collect();
+    let mut changes: Vec<Change> = old
+        .iter()
+        .filter(|object| !current.contains(&object.id))
+        .map(|object| Change::Removed(object.id))
+        .collect();
+    for object in new {
+        match previous.get(&object.id) {
+            None => changes.push(Change::Added(object.id)),
+            Some(before) if before.shape != object.shape => {
+                changes.push(Change::Reshaped(object.id))
+            }
+            Some(before) if *before != object => changes.push(Change::Updated(object.id)),
+            Some(_) => {}
+        }
+    }
+    changes
+}
+
+/**
+ * Id of the nearest visible entry `ray` hits `time` seconds in
+ */
+pub fn pick(objects: &[ObjectDesc], ray: &Ray, time: f32) -> Option<u64> {
+    let meshes: HashMap<Shape, MeshData> = objects
+        .iter()
+        .map(|object| (object.shape, object.shape.mesh()))
+        .collect();
+    objects
+        .iter()
+        .filter(|object| object.visible)
+        .filter_map(|object| {
+            let distance = raycast::cast_mesh(ray, &meshes[&object.shape], &object.matrix(time))?;
+            Some((object.id, distance))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(id, _)| id)
+}
+
+/**
+ * Ids of the visible entries whose bounds, projected onto a `width`×`height`
+ * canvas `time` seconds in, overlap `rect`, in list order. The test is
+ * against the screen rectangle around each object's transformed bounding
+ * box, so it errs towards including objects near a corner of the box
+ */
+pub fn in_rect(
+    objects: &[ObjectDesc],
+    view_proj: &Mat4,
+    rect: &ScreenRect,
+    width: f32,
+    height: f32,
+    time: f32,
+) -> Vec<u64> {
+    objects
+        .iter()
+        .filter(|object| object.visible)
+        .filter(|object| {
+            let corners = object.world_bounds(time).corners();
+            selection::projected_rect(view_proj, &corners, width, height)
+                .is_some_and(|projected| projected.intersects(rect))
+        })
+        .map(|object| object.id)
+        .collect()
+}
+
+/**
+ * `count` objects of random shapes, colors, sizes, placements and spins,
+ * numbered from `first_id`. The same seed always gives the same list, so a
+ * scene can be reproduced from its seed alone
+ */
+pub fn generate(seed: u64, count: usize, first_id: u64) -> Vec<ObjectDesc> {
+    let mut rng = Rng(seed);
+    // Roughly constant density however many there are
+    let range = 1.0 + (count as f32).sqrt() * 0.6;
+    (0..count as u64)
+        .map(|i| {
+            let id = first_id + i;
+            let shape = if rng.next_f32() < 0.5 {
+                Shape::Cube
+            } else {
+                Shape::Sphere
+            };
+            let translation = [
+                rng.range(-range, range),
+                rng.range(0.0, 1.5),
+                rng.range(-range, range),
+            ];
+            let rotation = [0.0; 3].map(|_: f32| rng.range(0.0, std::f32::consts::TAU));
+            let size = rng.range(0.25, 0.75);
+            let spin = [0.0; 3].map(|_: f32| rng.range(-1.5, 1.5));
+            // A saturated hue, softened towards white
+            let hue = rng.next_f32();
+            let [r, g, b] = [0.0, 2.0 / 3.0, 1.0 / 3.0]
+                .map(|offset: f32| ((hue + offset).fract() * 6.0 - 3.0).abs() - 1.0)
+                .map(|c| 0.25 + 0.75 * c.clamp(0.0, 1.0));
+            ObjectDesc {
+                id,
+                name: format!("{} {}", shape.name(), id + 1),
+                shape,
+                transform: Transform {
+                    translation,
+                    rotation,
+                    scale: [size; 3],
+                },
+                tint: [r, g, b, 1.0],
+                visible: true,
+                spin,
+            }
+        })
+        .collect()
+}
+
+/**
+ * SplitMix64: small, seedable and plenty for scattering objects
+ */
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /**
+     * Uniform in 0..1
+     */
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+}
+
+/**
+ * GPU objects created and deleted by an `ObjectListRenderer` so far
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MeshCounts {
+    pub live: usize,
+    pub created: usize,
+    pub deleted: usize,
+}
+
+/**
+ * Draws a list of `ObjectDesc`s, owning a mesh per entry. Each `sync` diffs
+ * the list against the previous one and only uploads meshes for new or
+ * reshaped entries and deletes those of removed ones
+ */
+pub struct ObjectListRenderer {
+    program: WebGlProgram,
+    model_loc: Option<WebGlUniformLocation>,
+    view_proj_loc: Option<WebGlUniformLocation>,
+    tint_loc: Option<WebGlUniformLocation>,
+    highlight_loc: Option<WebGlUniformLocation>,
+    light_loc: Option<WebGlUniformLocation>,
+    // The list as of the last sync, in draw order
+    objects: Vec<ObjectDesc>,
+    meshes: HashMap<u64, GpuMesh>,
+    counts: MeshCounts,
+}
+
+impl ObjectListRenderer {
+    pub fn new(gl: &WebGl2RenderingContext) -> Result<Self, String> {
+        let program =
+            gl_util::create_program(gl, shaders::DEMOS_OBJECTS_VERT, shaders::DEMOS_OBJECTS_FRAG)?;
+        Ok(Self {
+            model_loc: gl.get_uniform_location(&program, "model"),
+            view_proj_loc: gl.get_uniform_location(&program, "viewProjection"),
+            tint_loc: gl.get_uniform_location(&program, "tint"),
+            highlight_loc: gl.get_uniform_location(&program, "highlight"),
+            light_loc: gl.get_uniform_location(&program, "lightDirection"),
+            program,
+            objects: Vec::new(),
+            meshes: HashMap::new(),
+            counts: MeshCounts::default(),
+        })
+    }
+
+    /**
+     * Bring the GPU side in line with `objects` and return what changed.
+     * An entry whose mesh fails to upload is left undrawn and reported in
+     * the error once the rest of the list has been applied
+     */
+    pub fn sync(
+        &mut self,
+        gl: &WebGl2RenderingContext,
+        objects: &[ObjectDesc],
+    ) -> Result<Vec<Change>, String> {
+        if self.objects == objects {
+            return Ok(Vec::new());
+        }
+        let changes = diff(&self.objects, objects);
+        let mut failed = Vec::new();
+        for change in &changes {
+            match *change {
+                Change::Removed(id) => self.delete_mesh(gl, id),
+                Change::Added(id) | Change::Reshaped(id) => {
+                    self.delete_mesh(gl, id);
+                    let Some(object) = objects.iter().find(|object| object.id == id) else {
+                        continue;
+                    };
+                    match GpuMesh::upload(gl, &object.shape.mesh()) {
+                        Some(mesh) => {
+                            self.meshes.insert(id, mesh);
+                            self.counts.created += 1;
+                        }
+                        None => failed.push(object.name.clone()),
+                    }
+                }
+                Change::Updated(_) => {}
+            }
+        }
+        self.objects = objects.to_vec();
+        self.counts.live = self.meshes.len();
+        if failed.is_empty() {
+            Ok(changes)
+        } else {
+            Err(format!("Unable to upload meshes for {}", failed.join(", ")))
+        }
+    }
+
+    fn delete_mesh(&mut self, gl: &WebGl2RenderingContext, id: u64) {
+        if let Some(mesh) = self.meshes.remove(&id) {
+            mesh.delete(gl);
+            self.counts.deleted += 1;
+        }
+    }
+
+    pub fn counts(&self) -> MeshCounts {
+        self.counts
+    }
+
+    /**
+     * Draw the visible entries of the last synced list whose bounds are in
+     * `frustum` into the bound framebuffer, with depth testing, lightening
+     * the selected ones. Returns how many were drawn
+     */
+    pub fn draw(
+        &self,
+        gl: &WebGl2RenderingContext,
+        view_proj: &Mat4,
+        frustum: &Frustum,
+        time: f32,
+        selected: &Selection,
+    ) -> usize {
+        gl.enable(WebGl2RenderingContext::DEPTH_TEST);
+        gl.enable(WebGl2RenderingContext::CULL_FACE);
+        gl.use_program(Some(&self.program));
+        gl.uniform_matrix4fv_with_f32_array(self.view_proj_loc.as_ref(), false, view_proj);
+        gl.uniform3fv_with_f32_array(self.light_loc.as_ref(), &math::normalize(LIGHT_DIRECTION));
+        let mut drawn = 0;
+        for object in self.objects.iter().filter(|object| object.visible) {
+            let Some(mesh) = self.meshes.get(&object.id) else {
+                continue;
+            };
+            if !frustum.intersects_aabb(&object.world_bounds(time)) {
+                continue;
+            }
+            gl.uniform_matrix4fv_with_f32_array(
+                self.model_loc.as_ref(),
+                false,
+                &object.matrix(time),
+            );
+            gl.uniform4fv_with_f32_array(self.tint_loc.as_ref(), &object.tint);
+            let highlight = if selected.contains(object.id) {
+                1.0
+            } else {
+                0.0
+            };
+            gl.uniform1f(self.highlight_loc.as_ref(), highlight);
+            mesh.draw(gl);
+            drawn += 1;
+        }
+        drawn
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(id: u64) -> ObjectDesc {
+        ObjectDesc {
+            id,
+            name: format!("Object {}", id),
+            shape: Shape::Cube,
+            transform: Transform::default(),
+            tint: [1.0; 4],
+            visible: true,
+            spin: [0.0; 3],
+        }
+    }
+
+    #[test]
+    fn identical_lists_have_no_changes() {
+        let list = vec![object(1), object(2)];
+        assert!(diff(&list, &list).is_empty());
+    }
+
+    #[test]
+    fn matches_entries_by_id_not_position() {
+        let old = vec![object(1), object(2), object(3)];
+        let mut moved = object(3);
+        moved.transform.translation = [1.0, 0.0, 0.0];
+        let new = vec![moved, object(1), object(4)];
+        assert_eq!(
+            diff(&old, &new),
+            vec![Change::Removed(2), Change::Updated(3), Change::Added(4)]
+        );
+    }
+
+    #[test]
+    fn edits_that_keep_the_shape_are_updates() {
+        let old = vec![object(1)];
+        let mut hidden = object(1);
+        hidden.visible = false;
+        hidden.tint = [0.5, 0.5, 0.5, 1.0];
+        assert_eq!(diff(&old, &[hidden]), vec![Change::Updated(1)]);
+    }
+
+    #[test]
+    fn a_new_shape_is_a_reshape() {
+        let mut sphere = object(1);
+        sphere.shape = Shape::Sphere;
+        assert_eq!(diff(&[object(1)], &[sphere]), vec![Change::Reshaped(1)]);
+    }
+
+    #[test]
+    fn picks_the_nearest_visible_object() {
+        let mut near = object(1);
+        near.transform.translation = [0.0, 0.0, 2.0];
+        near.shape = Shape::Sphere;
+        let far = object(2);
+        let ray = Ray::new([0.0, 0.0, 5.0], [0.0, 0.0, -1.0]);
+        assert_eq!(pick(&[far.clone(), near.clone()], &ray, 0.0), Some(1));
+        near.visible = false;
+        assert_eq!(pick(&[far.clone(), near], &ray, 0.0), Some(2));
+        let miss = Ray::new([3.0, 0.0, 5.0], [0.0, 0.0, -1.0]);
+        assert_eq!(pick(&[far], &miss, 0.0), None);
+    }
+
+    #[test]
+    fn box_selects_objects_whose_projected_bounds_overlap() {
+        let mut left = object(1);
+        left.transform.translation = [-0.5, 0.0, 0.0];
+        left.transform.scale = [0.2; 3];
+        let mut right = object(2);
+        right.transform.translation = [0.5, 0.0, 0.0];
+        right.transform.scale = [0.2; 3];
+        let mut hidden = left.clone();
+        hidden.id = 3;
+        hidden.visible = false;
+        let objects = [left, right, hidden];
+        // Identity view-projection: x -0.5 lands at pixel 25 of 100
+        let view_proj = math::identity();
+        let rect = ScreenRect::from_corners([0.0, 0.0], [40.0, 100.0]);
+        assert_eq!(
+            in_rect(&objects, &view_proj, &rect, 100.0, 100.0, 0.0),
+            vec![1]
+        );
+        let all = ScreenRect::from_corners([20.0, 45.0], [80.0, 55.0]);
+        assert_eq!(
+            in_rect(&objects, &view_proj, &all, 100.0, 100.0, 0.0),
+            vec![1, 2]
+        );
+        let between = ScreenRect::from_corners([45.0, 0.0], [55.0, 100.0]);
+        assert!(in_rect(&objects, &view_proj, &between, 100.0, 100.0, 0.0).is_empty());
+    }
+
+    #[test]
+    fn shape_bounds_enclose_their_meshes() {
+        for shape in [Shape::Cube, Shape::Sphere] {
+            let mesh = Aabb::from_positions(&shape.mesh().positions);
+            assert!(shape.bounds().overlaps(&mesh));
+            for axis in 0..3 {
+                assert!(mesh.min[axis] >= shape.bounds().min[axis] - 1e-6);
+                assert!(mesh.max[axis] <= shape.bounds().max[axis] + 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn generated_scenes_repeat_for_a_seed() {
+        let scene = generate(42, 50, 10);
+        assert_eq!(scene, generate(42, 50, 10));
+        assert_ne!(scene, generate(43, 50, 10));
+        assert_eq!(scene.len(), 50);
+        let ids: HashSet<u64> = scene.iter().map(|object| object.id).collect();
+        assert_eq!(ids, (10..60).collect());
+        assert!(scene.iter().any(|object| object.shape == Shape::Cube));
+        assert!(scene.iter().any(|object| object.shape == Shape::Sphere));
+        for object in &scene {
+            assert!(object.tint.iter().all(|c| (0.0..=1.0).contains(c)));
+            assert!((0.25..=0.75).contains(&object.transform.scale[0]));
+        }
+    }
+}
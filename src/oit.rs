@@ -0,0 +1,245 @@
+use web_sys::{
+    WebGl2RenderingContext, WebGlFramebuffer, WebGlProgram, WebGlRenderbuffer, WebGlTexture,
+    WebGlUniformLocation, WebGlVertexArrayObject,
+};
+
+use crate::gl_util;
+use crate::gpu_memory::{self, Allocation, Kind};
+use crate::math::Mat4;
+use crate::mesh::GpuMesh;
+use crate::shaders;
+
+/**
+ * Weighted blended order-independent transparency: transparent surfaces are
+ * accumulated into float targets in any order, then composited over the
+ * frame in one resolve pass. Needs `EXT_color_buffer_float`
+ */
+pub struct WeightedOit {
+    surface_program: WebGlProgram,
+    surface_mvp_loc: Option<WebGlUniformLocation>,
+    surface_color_loc: Option<WebGlUniformLocation>,
+    occluder_program: WebGlProgram,
+    occluder_mvp_loc: Option<WebGlUniformLocation>,
+    resolve_program: WebGlProgram,
+    accum_texture_loc: Option<WebGlUniformLocation>,
+    weight_texture_loc: Option<WebGlUniformLocation>,
+    framebuffer: WebGlFramebuffer,
+    accum: WebGlTexture,
+    weight: WebGlTexture,
+    _depth: WebGlRenderbuffer,
+    // gl_VertexID only, but a bound VAO keeps every implementation happy
+    resolve_vao: WebGlVertexArrayObject,
+    width: i32,
+    height: i32,
+    _allocations: [Allocation; 2],
+}
+
+impl WeightedOit {
+    pub fn new(gl: &WebGl2RenderingContext, width: i32, height: i32) -> Result<Self, String> {
+        // Half-float targets are only renderable (and blendable) with this extension
+        if gl
+            .get_extension("EXT_color_buffer_float")
+            .ok()
+            .flatten()
+            .is_none()
+        {
+            return Err("EXT_color_buffer_float is not supported".to_string());
+        }
+
+        let surface_program =
+            gl_util::create_program(gl, shaders::OIT_SURFACE_VERT, shaders::OIT_SURFACE_FRAG)?;
+        let occluder_program =
+            gl_util::create_program(gl, shaders::OIT_SURFACE_VERT, shaders::OIT_OCCLUDER_FRAG)?;
+        let resolve_program =
+            gl_util::create_program(gl, shaders::OIT_RESOLVE_VERT, shaders::OIT_RESOLVE_FRAG)?;
+        let accum = create_target(gl, WebGl2RenderingContext::RGBA16F, width, height)?;
+        let weight = create_target(gl, WebGl2RenderingContext::R16F, width, height)?;
+        let depth = gl
+            .create_renderbuffer()
+            .ok_or_else(|| "Unable to create OIT depth buffer".to_string())?;
+        gl.bind_renderbuffer(WebGl2RenderingContext::RENDERBUFFER, Some(&depth));
+        gl.renderbuffer_storage(
+            WebGl2RenderingContext::RENDERBUFFER,
+            WebGl2RenderingContext::DEPTH_COMPONENT24,
+            width,
+            height,
+        );
+        gl.bind_renderbuffer(WebGl2RenderingContext::RENDERBUFFER, None);
+
+        let framebuffer = gl
+            .create_framebuffer()
+            .ok_or_else(|| "Unable to create OIT framebuffer".to_string())?;
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(&framebuffer));
+        for (attachment, texture) in [
+            (WebGl2RenderingContext::COLOR_ATTACHMENT0, &accum),
+            (WebGl2RenderingContext::COLOR_ATTACHMENT1, &weight),
+        ] {
+            gl.framebuffer_texture_2d(
+                WebGl2RenderingContext::FRAMEBUFFER,
+                attachment,
+                WebGl2RenderingContext::TEXTURE_2D,
+                Some(texture),
+                0,
+            );
+        }
+        gl.framebuffer_renderbuffer(
+            WebGl2RenderingContext::FRAMEBUFFER,
+            WebGl2RenderingContext::DEPTH_ATTACHMENT,
+            WebGl2RenderingContext::RENDERBUFFER,
+            Some(&depth),
+        );
+        // Draw buffer selection is framebuffer state, so this sticks
+        let buffers = js_sys::Array::of2(
+            &WebGl2RenderingContext::COLOR_ATTACHMENT0.into(),
+            &WebGl2RenderingContext::COLOR_ATTACHMENT1.into(),
+        );
+        gl.draw_buffers(&buffers);
+        let status = gl.check_framebuffer_status(WebGl2RenderingContext::FRAMEBUFFER);
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+        if status != WebGl2RenderingContext::FRAMEBUFFER_COMPLETE {
+            return Err(format!("OIT framebuffer incomplete: {}", status));
+        }
+
+        let resolve_vao = gl
+            .create_vertex_array()
+            .ok_or_else(|| "Unable to create OIT VAO".to_string())?;
+
+        Ok(Self {
+            surface_mvp_loc: gl.get_uniform_location(&surface_program, "modelViewProjection"),
+            surface_color_loc: gl.get_uniform_location(&surface_program, "color"),
+            occluder_mvp_loc: gl.get_uniform_location(&occluder_program, "modelViewProjection"),
+            accum_texture_loc: gl.get_uniform_location(&resolve_program, "accumTexture"),
+            weight_texture_loc: gl.get_uniform_location(&resolve_program, "weightTexture"),
+            surface_program,
+            occluder_program,
+            resolve_program,
+            framebuffer,
+            accum,
+            weight,
+            _depth: depth,
+            resolve_vao,
+            width,
+            height,
+            // RGBA16F accumulation and R16F weights; 24-bit depth
+            _allocations: [(Kind::Texture, 8 + 2), (Kind::Renderbuffer, 4)].map(
+                |(kind, bytes_per_pixel)| {
+                    Allocation::new(
+                        kind,
+                        gpu_memory::texture_bytes(
+                            width as usize,
+                            height as usize,
+                            1,
+                            bytes_per_pixel,
+                            false,
+                        ),
+                    )
+                },
+            ),
+        })
+    }
+
+    /**
+     * Start accumulating. `occluders` (mesh, model-view-projection) are the
+     * opaque objects already drawn to the frame; their depth hides the
+     * transparent surfaces behind them
+     */
+    pub fn begin<'a>(
+        &self,
+        gl: &WebGl2RenderingContext,
+        occluders: impl IntoIterator<Item = (&'a GpuMesh, Mat4)>,
+    ) {
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(&self.framebuffer));
+        gl.viewport(0, 0, self.width, self.height);
+        gl.enable(WebGl2RenderingContext::DEPTH_TEST);
+        gl.depth_mask(true);
+        gl.clear(WebGl2RenderingContext::DEPTH_BUFFER_BIT);
+
+        gl.color_mask(false, false, false, false);
+        gl.use_program(Some(&self.occluder_program));
+        for (mesh, mvp) in occluders {
+            gl.uniform_matrix4fv_with_f32_array(self.occluder_mvp_loc.as_ref(), false, &mvp);
+            mesh.draw(gl);
+        }
+        gl.color_mask(true, true, true, true);
+
+        // Nothing accumulated yet: no color, no weight, fully revealed
+        gl.clear_bufferfv_with_f32_array(WebGl2RenderingContext::COLOR, 0, &[0.0, 0.0, 0.0, 1.0]);
+        gl.clear_bufferfv_with_f32_array(WebGl2RenderingContext::COLOR, 1, &[0.0; 4]);
+
+        // Surfaces are tested against the occluders but never hide each other
+        gl.depth_mask(false);
+        gl.disable(WebGl2RenderingContext::CULL_FACE);
+        gl.enable(WebGl2RenderingContext::BLEND);
+        gl.blend_func_separate(
+            WebGl2RenderingContext::ONE,
+            WebGl2RenderingContext::ONE,
+            WebGl2RenderingContext::ZERO,
+            WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+        );
+        gl.use_program(Some(&self.surface_program));
+    }
+
+    /**
+     * Accumulate one transparent mesh; `color` is straight (not premultiplied) RGBA.
+     * Only valid between `begin` and `resolve`
+     */
+    pub fn draw(&self, gl: &WebGl2RenderingContext, mesh: &GpuMesh, mvp: &Mat4, color: [f32; 4]) {
+        gl.uniform_matrix4fv_with_f32_array(self.surface_mvp_loc.as_ref(), false, mvp);
+        gl.uniform4fv_with_f32_array(self.surface_color_loc.as_ref(), &color);
+        mesh.draw(gl);
+    }
+
+    /**
+     * Composite the accumulated surfaces over the default framebuffer
+     */
+    pub fn resolve(&self, gl: &WebGl2RenderingContext) {
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+        gl.depth_mask(true);
+        gl.disable(WebGl2RenderingContext::DEPTH_TEST);
+        gl.blend_func(
+            WebGl2RenderingContext::SRC_ALPHA,
+            WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+        );
+
+        gl.use_program(Some(&self.resolve_program));
+        gl.active_texture(WebGl2RenderingContext::TEXTURE0);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&self.accum));
+        gl.uniform1i(self.accum_texture_loc.as_ref(), 0);
+        gl.active_texture(WebGl2RenderingContext::TEXTURE1);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&self.weight));
+        gl.uniform1i(self.weight_texture_loc.as_ref(), 1);
+        gl.bind_vertex_array(Some(&self.resolve_vao));
+        gl.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, 3);
+        gl.bind_vertex_array(None);
+        gl.active_texture(WebGl2RenderingContext::TEXTURE0);
+
+        gl.disable(WebGl2RenderingContext::BLEND);
+        gl.enable(WebGl2RenderingContext::DEPTH_TEST);
+        gl.enable(WebGl2RenderingContext::CULL_FACE);
+    }
+}
+
+fn create_target(
+    gl: &WebGl2RenderingContext,
+    format: u32,
+    width: i32,
+    height: i32,
+) -> Result<WebGlTexture, String> {
+    let texture = gl
+        .create_texture()
+        .ok_or_else(|| "Unable to create OIT target".to_string())?;
+    gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&texture));
+    gl.tex_storage_2d(WebGl2RenderingContext::TEXTURE_2D, 1, format, width, height);
+    gl.tex_parameteri(
+        WebGl2RenderingContext::TEXTURE_2D,
+        WebGl2RenderingContext::TEXTURE_MIN_FILTER,
+        WebGl2RenderingContext::NEAREST as i32,
+    );
+    gl.tex_parameteri(
+        WebGl2RenderingContext::TEXTURE_2D,
+        WebGl2RenderingContext::TEXTURE_MAG_FILTER,
+        WebGl2RenderingContext::NEAREST as i32,
+    );
+    gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+    Ok(texture)
+}
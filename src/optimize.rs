@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+
+use crate::math::{self, Vec3};
+use crate::mesh::MeshData;
+
+// Vertices Forsyth's scoring tracks; larger than any real post-transform cache
+const SCORING_CACHE_SIZE: usize = 32;
+const CACHE_DECAY_POWER: f32 = 1.5;
+const LAST_TRIANGLE_SCORE: f32 = 0.75;
+const VALENCE_BOOST_SCALE: f32 = 2.0;
+const VALENCE_BOOST_POWER: f32 = 0.5;
+// FIFO cache the before/after statistics are measured against
+const MEASURE_CACHE_SIZE: u32 = 16;
+
+/**
+ * What `optimize_mesh` changed. ACMR is the average cache miss ratio: vertex
+ * shader runs per triangle, from 3.0 (no reuse) down to about 0.5
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct OptimizeStats {
+    pub vertices_before: usize,
+    pub vertices_after: usize,
+    pub acmr_before: f32,
+    pub acmr_after: f32,
+}
+
+/**
+ * Reindex, reorder and compact an imported mesh for faster rendering:
+ *
+ * 1. Merge vertices with identical attributes
+ * 2. Order triangles for post-transform vertex cache reuse (Forsyth)
+ * 3. Reorder runs of triangles so outward-facing ones draw first, cutting
+ *    overdraw without giving up much of that reuse (Sander et al.)
+ * 4. Renumber vertices in first-use order so vertex fetches stream linearly
+ *    and unreferenced vertices drop out
+ */
+pub fn optimize_mesh(mesh: &MeshData) -> (MeshData, OptimizeStats) {
+    let vertex_count = mesh.positions.len() / 3;
+    let canonical = canonical_vertices(mesh);
+    let indices: Vec<u16> = mesh
+        .indices
+        .iter()
+        .map(|&index| canonical[index as usize])
+        .collect();
+    let indices = optimize_vertex_cache(&indices, vertex_count);
+    let indices = optimize_overdraw(&indices, &mesh.positions, vertex_count);
+
+    let mut remap = vec![None::<u16>; vertex_count];
+    let mut optimized = MeshData::default();
+    for &index in &indices {
+        let index = index as usize;
+        let new_index = *remap[index].get_or_insert_with(|| {
+            optimized
+                .positions
+                .extend_from_slice(&mesh.positions[index * 3..index * 3 + 3]);
+            optimized
+                .colors
+                .extend_from_slice(&mesh.colors[index * 3..index * 3 + 3]);
+            (optimized.positions.len() / 3 - 1) as u16
+        });
+        optimized.indices.push(new_index);
+    }
+
+    let stats = OptimizeStats {
+        vertices_before: vertex_count,
+        vertices_after: optimized.positions.len() / 3,
+        acmr_before: acmr(&mesh.indices, vertex_count),
+        acmr_after: acmr(&optimized.indices, optimized.positions.len() / 3),
+    };
+    (optimized, stats)
+}
+
+/**
+ * Vertex shader invocations per triangle with a FIFO post-transform cache
+ */
+pub fn acmr(indices: &[u16], vertex_count: usize) -> f32 {
+    let triangles = indices.len() / 3;
+    if triangles == 0 {
+        return 0.0;
+    }
+    // A vertex is cached while fewer than MEASURE_CACHE_SIZE misses followed its own
+    let mut loaded_at = vec![0u32; vertex_count];
+    let mut clock = MEASURE_CACHE_SIZE + 1;
+    let mut misses = 0;
+    for &index in indices {
+        let loaded = &mut loaded_at[index as usize];
+        if clock - *loaded > MEASURE_CACHE_SIZE {
+            *loaded = clock;
+            clock += 1;
+            misses += 1;
+        }
+    }
+    misses as f32 / triangles as f32
+}
+
+/**
+ * For each vertex, the first vertex with exactly the same position and color
+ */
+fn canonical_vertices(mesh: &MeshData) -> Vec<u16> {
+    let mut first = HashMap::<[u32; 6], u16>::new();
+    mesh.positions
+        .chunks_exact(3)
+        .zip(mesh.colors.chunks_exact(3))
+        .enumerate()
+        .map(|(index, (position, color))| {
+            let mut key = [0; 6];
+            for (bits, value) in key.iter_mut().zip(position.iter().chain(color)) {
+                *bits = value.to_bits();
+            }
+            *first.entry(key).or_insert(index as u16)
+        })
+        .collect()
+}
+
+fn vertex_score(cache_position: Option<usize>, remaining: u32) -> f32 {
+    if remaining == 0 {
+        // No triangles left to help
+        return -1.0;
+    }
+    let cache_score = match cache_position {
+        None => 0.0,
+        // The last triangle's vertices score a fixed amount so the next triangle
+        // doesn't simply reuse the same edge every time
+        Some(position) if position < 3 => LAST_TRIANGLE_SCORE,
+        Some(position) => {
+            let scale = 1.0 / (SCORING_CACHE_SIZE - 3) as f32;
+            (1.0 - (position - 3) as f32 * scale).powf(CACHE_DECAY_POWER)
+        }
+    };
+    // Favour finishing off vertices with few triangles left, so they leave the cache
+    cache_score + VALENCE_BOOST_SCALE * (remaining as f32).powf(-VALENCE_BOOST_POWER)
+}
+
+/**
+ * Tom Forsyth's linear-speed vertex cache optimization: greedily emit the
+ * triangle whose vertices score best, by cache position and remaining valence
+ */
+fn optimize_vertex_cache(indices: &[u16], vertex_count: usize) -> Vec<u16> {
+    let triangle_count = indices.len() / 3;
+    let corners = |triangle: usize| {
+        let i = triangle * 3;
+        [
+            indices[i] as usize,
+            indices[i + 1] as usize,
+            indices[i + 2] as usize,
+        ]
+    };
+
+    // Triangles around each vertex, in one flat array
+    let mut remaining = vec![0u32; vertex_count];
+    for &index in indices {
+        remaining[index as usize] += 1;
+    }
+    let mut offsets = vec![0usize; vertex_count + 1];
+    for vertex in 0..vertex_count {
+        offsets[vertex + 1] = offsets[vertex] + remaining[vertex] as usize;
+    }
+    let mut adjacency = vec![0usize; indices.len()];
+    let mut fill = offsets.clone();
+    for triangle in 0..triangle_count {
+        for vertex in corners(triangle) {
+            adjacency[fill[vertex]] = triangle;
+            fill[vertex] += 1;
+        }
+    }
+
+    let mut scores: Vec<f32> = remaining
+        .iter()
+        .map(|&count| vertex_score(None, count))
+        .collect();
+    let mut emitted = vec![false; triangle_count];
+    let mut cache: Vec<usize> = Vec::with_capacity(SCORING_CACHE_SIZE + 3);
+    let mut output = Vec::with_capacity(indices.len());
+    // Where the search for a fresh start resumes when the cache runs dry
+    let mut scan = 0;
+    let triangle_score = |triangle: usize, scores: &[f32]| -> f32 {
+        corners(triangle).iter().map(|&v| scores[v]).sum()
+    };
+    let mut best = (0..triangle_count)
+        .max_by(|&a, &b| triangle_score(a, &scores).total_cmp(&triangle_score(b, &scores)));
+
+    while let Some(triangle) = best {
+        emitted[triangle] = true;
+        let triangle_corners = corners(triangle);
+        for vertex in triangle_corners {
+            output.push(vertex as u16);
+            remaining[vertex] -= 1;
+        }
+
+        // Move the corners to the front of the LRU cache
+        let mut touched = Vec::with_capacity(SCORING_CACHE_SIZE + 3);
+        for vertex in triangle_corners {
+            if !touched.contains(&vertex) {
+                touched.push(vertex);
+            }
+        }
+        touched.extend(cache.iter().filter(|v| !triangle_corners.contains(v)));
+        for (position, &vertex) in touched.iter().enumerate() {
+            let position = (position < SCORING_CACHE_SIZE).then_some(position);
+            scores[vertex] = vertex_score(position, remaining[vertex]);
+        }
+
+        // Only triangles around touched vertices changed score
+        best = None;
+        let mut best_score = f32::NEG_INFINITY;
+        for &vertex in &touched {
+            for &candidate in &adjacency[offsets[vertex]..offsets[vertex + 1]] {
+                if emitted[candidate] {
+                    continue;
+                }
+                let score = triangle_score(candidate, &scores);
+                if score > best_score {
+                    best_score = score;
+                    best = Some(candidate);
+                }
+            }
+        }
+        touched.truncate(SCORING_CACHE_SIZE);
+        cache = touched;
+
+        if best.is_none() {
+            while scan < triangle_count && emitted[scan] {
+                scan += 1;
+            }
+            best = (scan < triangle_count).then_some(scan);
+        }
+    }
+    output
+}
+
+/**
+ * Split cache-ordered triangles into clusters at points where all three
+ * vertices miss the cache, then draw the clusters that face away from the
+ * mesh's center first, since they're most likely to occlude the others
+ */
+fn optimize_overdraw(indices: &[u16], positions: &[f32], vertex_count: usize) -> Vec<u16> {
+    let position = |index: u16| {
+        let i = index as usize * 3;
+        [positions[i], positions[i + 1], positions[i + 2]]
+    };
+
+    let mut clusters: Vec<&[u16]> = Vec::new();
+    let mut loaded_at = vec![0u32; vertex_count];
+    let mut clock = MEASURE_CACHE_SIZE + 1;
+    let mut start = 0;
+    for (triangle, corners) in indices.chunks_exact(3).enumerate() {
+        let mut misses = 0;
+        for &index in corners {
+            let loaded = &mut loaded_at[index as usize];
+            if clock - *loaded > MEASURE_CACHE_SIZE {
+                *loaded = clock;
+                clock += 1;
+                misses += 1;
+            }
+        }
+        if misses == 3 && triangle * 3 > start {
+            clusters.push(&indices[start..triangle * 3]);
+            start = triangle * 3;
+        }
+    }
+    clusters.push(&indices[start..]);
+
+    let mesh_center = centroid(indices.iter().map(|&index| position(index)));
+    let facing = |cluster: &[u16]| {
+        // Area-weighted: the cross product's length is twice the triangle area
+        let mut normal: Vec3 = [0.0; 3];
+        for corners in cluster.chunks_exact(3) {
+            let [a, b, c] = [corners[0], corners[1], corners[2]].map(position);
+            normal = math::add(normal, math::cross(math::sub(b, a), math::sub(c, a)));
+        }
+        let center = centroid(cluster.iter().map(|&index| position(index)));
+        math::dot(math::sub(center, mesh_center), math::normalize(normal))
+    };
+    let mut keyed: Vec<(f32, &[u16])> = clusters
+        .into_iter()
+        .map(|cluster| (facing(cluster), cluster))
+        .collect();
+    keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+    keyed
+        .into_iter()
+        .flat_map(|(_, cluster)| cluster)
+        .copied()
+        .collect()
+}
+
+fn centroid(points: impl Iterator<Item = Vec3>) -> Vec3 {
+    let mut sum: Vec3 = [0.0; 3];
+    let mut count = 0;
+    for point in points {
+        sum = math::add(sum, point);
+        count += 1;
+    }
+    math::scale(sum, 1.0 / count.max(1) as f32)
+}
@@ -0,0 +1,44 @@
+use web_sys::Performance;
+
+/**
+ * A stage of work shown by name in the browser profiler's timings track:
+ * `performance.mark` when created, `performance.measure` from that mark when
+ * dropped. The entries are cleared right after, so a render loop doesn't grow
+ * the performance buffer; a profiler recording has already captured them
+ */
+pub struct Measure {
+    name: &'static str,
+    start_mark: String,
+    performance: Option<Performance>,
+}
+
+/**
+ * Start measuring `name` until the returned value is dropped
+ */
+pub fn measure(name: &'static str) -> Measure {
+    let performance = web_sys::window().and_then(|window| window.performance());
+    let start_mark = format!("{}:start", name);
+    if let Some(performance) = &performance {
+        let _ = performance.mark(&start_mark);
+    }
+    Measure {
+        name,
+        start_mark,
+        performance,
+    }
+}
+
+impl Drop for Measure {
+    fn drop(&mut self) {
+        let Some(performance) = &self.performance else {
+            return;
+        };
+        // web-sys renames this with its unstable APIs on, which WebGPU needs
+        #[cfg(web_sys_unstable_apis)]
+        let _ = performance.measure_with_str(self.name, &self.start_mark);
+        #[cfg(not(web_sys_unstable_apis))]
+        let _ = performance.measure_with_start_mark(self.name, &self.start_mark);
+        performance.clear_marks_with_mark_name(&self.start_mark);
+        performance.clear_measures_with_measure_name(self.name);
+    }
+}
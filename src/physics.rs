@@ -0,0 +1,200 @@
+use rapier3d::na::UnitQuaternion;
+use rapier3d::prelude::*;
+
+use crate::scene::{BodyKind, Scene, Transform};
+
+/**
+ * Rapier world mirroring the scene objects tagged with a `BodyKind`
+ */
+pub struct PhysicsWorld {
+    gravity: Vector<Real>,
+    integration_parameters: IntegrationParameters,
+    pipeline: PhysicsPipeline,
+    islands: IslandManager,
+    broad_phase: DefaultBroadPhase,
+    narrow_phase: NarrowPhase,
+    bodies: RigidBodySet,
+    colliders: ColliderSet,
+    impulse_joints: ImpulseJointSet,
+    multibody_joints: MultibodyJointSet,
+    ccd_solver: CCDSolver,
+    // Rigid body for each scene object, indexed like `Scene::objects`
+    handles: Vec<Option<RigidBodyHandle>>,
+}
+
+impl PhysicsWorld {
+    /**
+     * Build bodies and box colliders (from mesh bounds) for the tagged objects
+     */
+    pub fn new(scene: &Scene, step: f32) -> Self {
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+
+        let handles = scene
+            .objects
+            .iter()
+            .map(|object| {
+                let kind = object.body?;
+                // Before inserting, so a bad mesh index leaves no body behind
+                let bounds = scene.meshes.get(object.mesh)?.bounds();
+                let builder = match kind {
+                    BodyKind::Dynamic => RigidBodyBuilder::dynamic(),
+                    BodyKind::Fixed => RigidBodyBuilder::fixed(),
+                };
+                let handle = bodies.insert(builder.position(isometry(&object.transform)).build());
+
+                let [sx, sy, sz] = object.transform.scale;
+                let center = bounds.center();
+                let collider = ColliderBuilder::cuboid(
+                    (bounds.max[0] - bounds.min[0]) * sx * 0.5,
+                    (bounds.max[1] - bounds.min[1]) * sy * 0.5,
+                    (bounds.max[2] - bounds.min[2]) * sz * 0.5,
+                )
+                .translation(vector![center[0] * sx, center[1] * sy, center[2] * sz])
+                .restitution(0.3)
+                .friction(0.7)
+                .build();
+                colliders.insert_with_parent(collider, handle, &mut bodies);
+
+                Some(handle)
+            })
+            .collect();
+
+        let integration_parameters = IntegrationParameters {
+            dt: step,
+            ..Default::default()
+        };
+
+        Self {
+            gravity: vector![0.0, -9.81, 0.0],
+            integration_parameters,
+            pipeline: PhysicsPipeline::new(),
+            islands: IslandManager::new(),
+            broad_phase: DefaultBroadPhase::new(),
+            narrow_phase: NarrowPhase::new(),
+            bodies,
+            colliders,
+            impulse_joints: ImpulseJointSet::new(),
+            multibody_joints: MultibodyJointSet::new(),
+            ccd_solver: CCDSolver::new(),
+            handles,
+        }
+    }
+
+    /**
+     * Advance the simulation by one fixed step
+     */
+    pub fn step(&mut self) {
+        self.pipeline.step(
+            &self.gravity,
+            &self.integration_parameters,
+            &mut self.islands,
+            &mut self.broad_phase,
+            &mut self.narrow_phase,
+            &mut self.bodies,
+            &mut self.colliders,
+            &mut self.impulse_joints,
+            &mut self.multibody_joints,
+            &mut self.ccd_solver,
+            None,
+            &(),
+            &(),
+        );
+    }
+
+    /**
+     * Hand a dynamic body over to user control (kinematic) while it is being
+     * dragged, or give it back to the simulation
+     */
+    pub fn set_held(&mut self, object: usize, held: bool) {
+        let Some(body) = self.body_mut(object) else {
+            return;
+        };
+        if body.body_type() == RigidBodyType::Fixed {
+            return;
+        }
+        let body_type = if held {
+            RigidBodyType::KinematicPositionBased
+        } else {
+            RigidBodyType::Dynamic
+        };
+        body.set_body_type(body_type, true);
+    }
+
+    /**
+     * Move a held body to the scene's transform during the next step
+     */
+    pub fn move_held(&mut self, object: usize, transform: &Transform) {
+        if let Some(body) = self.body_mut(object) {
+            if body.is_kinematic() {
+                body.set_next_kinematic_position(isometry(transform));
+            }
+        }
+    }
+
+    /**
+     * Copy simulated dynamic body poses back into the scene transforms
+     */
+    pub fn sync_to_scene(&self, scene: &mut Scene) {
+        for (object, handle) in scene.objects.iter_mut().zip(&self.handles) {
+            let Some(body) = handle.and_then(|handle| self.bodies.get(handle)) else {
+                continue;
+            };
+            if !body.is_dynamic() {
+                continue;
+            }
+            let translation = body.translation();
+            object.transform.translation = [translation.x, translation.y, translation.z];
+            // Scene rotations are the negated nalgebra roll/pitch/yaw, see `isometry`
+            let (roll, pitch, yaw) = body.rotation().euler_angles();
+            object.transform.rotation = [-roll, -pitch, -yaw];
+        }
+    }
+
+    fn body_mut(&mut self, object: usize) -> Option<&mut RigidBody> {
+        let handle = (*self.handles.get(object)?)?;
+        self.bodies.get_mut(handle)
+    }
+}
+
+/**
+ * Rapier pose for a scene transform. The scene's rotation matrices turn the
+ * opposite way to nalgebra's, hence the negated Euler angles
+ */
+fn isometry(transform: &Transform) -> Isometry<Real> {
+    let [x, y, z] = transform.translation;
+    let [rx, ry, rz] = transform.rotation;
+    Isometry::from_parts(
+        Translation::new(x, y, z),
+        UnitQuaternion::from_euler_angles(-rx, -ry, -rz),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::MeshData;
+    use crate::scene::SceneObject;
+
+    #[test]
+    fn skips_objects_whose_mesh_is_missing() {
+        let object = |mesh: usize| SceneObject {
+            name: format!("Mesh {}", mesh),
+            mesh,
+            material: 0,
+            transform: Transform::default(),
+            body: Some(BodyKind::Dynamic),
+            tint: [1.0; 4],
+            visible: true,
+        };
+        let scene = Scene {
+            meshes: vec![MeshData::cube()],
+            objects: vec![object(0), object(3)],
+            ..Default::default()
+        };
+        let world = PhysicsWorld::new(&scene, 1.0 / 60.0);
+        assert_eq!(world.bodies.len(), 1);
+        assert!(world.handles[0].is_some());
+        assert_eq!(world.handles[1], None);
+    }
+}
@@ -0,0 +1,170 @@
+use web_sys::{
+    WebGl2RenderingContext, WebGlFramebuffer, WebGlProgram, WebGlRenderbuffer, WebGlUniformLocation,
+};
+
+use crate::gl_util;
+use crate::gpu_memory::{self, Allocation, Kind};
+use crate::math::Mat4;
+use crate::mesh::GpuMesh;
+use crate::readback::Readback;
+use crate::shaders;
+
+/**
+ * GPU color-id picking: renders object ids into an off-screen framebuffer and
+ * reads back the pixel under the cursor
+ */
+pub struct Picker {
+    program: WebGlProgram,
+    framebuffer: WebGlFramebuffer,
+    color: WebGlRenderbuffer,
+    depth: WebGlRenderbuffer,
+    mvp_loc: Option<WebGlUniformLocation>,
+    id_color_loc: Option<WebGlUniformLocation>,
+    width: i32,
+    height: i32,
+    _allocation: Allocation,
+}
+
+impl Picker {
+    pub fn new(gl: &WebGl2RenderingContext, width: i32, height: i32) -> Result<Self, String> {
+        let program =
+            gl_util::create_program(gl, shaders::PICKING_ID_VERT, shaders::PICKING_ID_FRAG)?;
+        let framebuffer = gl
+            .create_framebuffer()
+            .ok_or_else(|| "Unable to create picking framebuffer".to_string())?;
+        let color = gl
+            .create_renderbuffer()
+            .ok_or_else(|| "Unable to create picking renderbuffer".to_string())?;
+        let depth = gl
+            .create_renderbuffer()
+            .ok_or_else(|| "Unable to create picking renderbuffer".to_string())?;
+
+        let mvp_loc = gl.get_uniform_location(&program, "modelViewProjection");
+        let id_color_loc = gl.get_uniform_location(&program, "idColor");
+
+        let picker = Self {
+            program,
+            framebuffer,
+            color,
+            depth,
+            mvp_loc,
+            id_color_loc,
+            width,
+            height,
+            // RGBA8 ids and 16-bit depth
+            _allocation: Allocation::new(
+                Kind::Renderbuffer,
+                gpu_memory::texture_bytes(width as usize, height as usize, 1, 6, false),
+            ),
+        };
+        picker.allocate(gl)?;
+        Ok(picker)
+    }
+
+    fn allocate(&self, gl: &WebGl2RenderingContext) -> Result<(), String> {
+        let (width, height) = (self.width, self.height);
+        gl.bind_renderbuffer(WebGl2RenderingContext::RENDERBUFFER, Some(&self.color));
+        gl.renderbuffer_storage(
+            WebGl2RenderingContext::RENDERBUFFER,
+            WebGl2RenderingContext::RGBA8,
+            width,
+            height,
+        );
+        gl.bind_renderbuffer(WebGl2RenderingContext::RENDERBUFFER, Some(&self.depth));
+        gl.renderbuffer_storage(
+            WebGl2RenderingContext::RENDERBUFFER,
+            WebGl2RenderingContext::DEPTH_COMPONENT16,
+            width,
+            height,
+        );
+        gl.bind_renderbuffer(WebGl2RenderingContext::RENDERBUFFER, None);
+
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(&self.framebuffer));
+        gl.framebuffer_renderbuffer(
+            WebGl2RenderingContext::FRAMEBUFFER,
+            WebGl2RenderingContext::COLOR_ATTACHMENT0,
+            WebGl2RenderingContext::RENDERBUFFER,
+            Some(&self.color),
+        );
+        gl.framebuffer_renderbuffer(
+            WebGl2RenderingContext::FRAMEBUFFER,
+            WebGl2RenderingContext::DEPTH_ATTACHMENT,
+            WebGl2RenderingContext::RENDERBUFFER,
+            Some(&self.depth),
+        );
+        let status = gl.check_framebuffer_status(WebGl2RenderingContext::FRAMEBUFFER);
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+
+        if status == WebGl2RenderingContext::FRAMEBUFFER_COMPLETE {
+            Ok(())
+        } else {
+            Err(format!("Picking framebuffer incomplete: {}", status))
+        }
+    }
+
+    /**
+     * Render `objects` (index, mesh, model-view-projection) as ids and start
+     * reading back the pixel (`x`, `y`), measured from the top-left. Pass the
+     * finished pixel to `picked`. `None` outside the framebuffer
+     */
+    pub fn start_pick<'a>(
+        &self,
+        gl: &WebGl2RenderingContext,
+        objects: impl IntoIterator<Item = (usize, &'a GpuMesh, Mat4)>,
+        x: i32,
+        y: i32,
+    ) -> Option<Result<Readback, String>> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        // GL's origin is bottom-left
+        let gl_y = self.height - 1 - y;
+
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(&self.framebuffer));
+        gl.viewport(0, 0, self.width, self.height);
+        // Only the pixel under the cursor matters
+        gl.enable(WebGl2RenderingContext::SCISSOR_TEST);
+        gl.scissor(x, gl_y, 1, 1);
+        gl.enable(WebGl2RenderingContext::DEPTH_TEST);
+        gl.clear_color(0.0, 0.0, 0.0, 0.0);
+        gl.clear(
+            WebGl2RenderingContext::COLOR_BUFFER_BIT | WebGl2RenderingContext::DEPTH_BUFFER_BIT,
+        );
+
+        gl.use_program(Some(&self.program));
+        for (index, mesh, mvp) in objects {
+            gl.uniform_matrix4fv_with_f32_array(self.mvp_loc.as_ref(), false, &mvp);
+            let [r, g, b] = encode_id(index + 1);
+            gl.uniform3f(self.id_color_loc.as_ref(), r, g, b);
+            mesh.draw(gl);
+        }
+
+        let readback = Readback::start(gl, x, gl_y, 1, 1);
+
+        gl.disable(WebGl2RenderingContext::SCISSOR_TEST);
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+        Some(readback)
+    }
+
+    /**
+     * Index of the object a finished pick readback landed on
+     */
+    pub fn picked(pixel: &[u8]) -> Option<usize> {
+        match *pixel {
+            [r, g, b, a] => decode_id([r, g, b, a]).checked_sub(1),
+            _ => None,
+        }
+    }
+}
+
+fn encode_id(id: usize) -> [f32; 3] {
+    [
+        (id & 0xff) as f32 / 255.0,
+        ((id >> 8) & 0xff) as f32 / 255.0,
+        ((id >> 16) & 0xff) as f32 / 255.0,
+    ]
+}
+
+fn decode_id(pixel: [u8; 4]) -> usize {
+    pixel[0] as usize | (pixel[1] as usize) << 8 | (pixel[2] as usize) << 16
+}
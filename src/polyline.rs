@@ -0,0 +1,166 @@
+use js_sys::Int32Array;
+use web_sys::{WebGl2RenderingContext, WebGlProgram, WebGlUniformLocation, WebGlVertexArrayObject};
+
+use crate::dynamic_buffer::DynamicBuffer;
+use crate::gl_util;
+use crate::math::{Mat4, Vec3};
+use crate::shaders;
+
+// position.xyz + width + rgba per point
+const FLOATS_PER_POINT: usize = 8;
+const STRIDE: i32 = (FLOATS_PER_POINT * 4) as i32;
+
+/**
+ * Polyline vertex; `width` is in pixels, so lines keep their thickness at any distance
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PolylinePoint {
+    pub position: Vec3,
+    pub width: f32,
+    pub color: [f32; 4],
+}
+
+/**
+ * How consecutive segments meet
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineJoin {
+    // Sharp corners, limited to a few line widths on tight turns
+    Miter,
+    // Round joins and caps
+    Round,
+}
+
+/**
+ * Draws thick antialiased world-space polylines as camera-facing quads,
+ * sidestepping WebGL's one-pixel `lineWidth` limit
+ */
+pub struct PolylineRenderer {
+    program: WebGlProgram,
+    view_proj_loc: Option<WebGlUniformLocation>,
+    viewport_loc: Option<WebGlUniformLocation>,
+    round_joins_loc: Option<WebGlUniformLocation>,
+    vao: WebGlVertexArrayObject,
+    buffer: DynamicBuffer,
+}
+
+impl PolylineRenderer {
+    pub fn new(gl: &WebGl2RenderingContext) -> Result<Self, String> {
+        let program = gl_util::create_program(gl, shaders::POLYLINE_VERT, shaders::POLYLINE_FRAG)?;
+        let vao = gl
+            .create_vertex_array()
+            .ok_or_else(|| "Unable to create polyline VAO".to_string())?;
+        let buffer = DynamicBuffer::new(gl, WebGl2RenderingContext::ARRAY_BUFFER)?;
+
+        // Every attribute steps once per instance; instance i reads points i..i+3
+        // of the padded point list, so consecutive segments share their data
+        gl.bind_vertex_array(Some(&vao));
+        buffer.bind(gl);
+        let attributes: [(u32, i32, i32); 8] = [
+            (0, 3, 0),
+            (1, 3, STRIDE),
+            (2, 1, STRIDE + 12),
+            (3, 4, STRIDE + 16),
+            (4, 3, 2 * STRIDE),
+            (5, 1, 2 * STRIDE + 12),
+            (6, 4, 2 * STRIDE + 16),
+            (7, 3, 3 * STRIDE),
+        ];
+        for (location, size, offset) in attributes {
+            gl.enable_vertex_attrib_array(location);
+            gl.vertex_attrib_pointer_with_i32(
+                location,
+                size,
+                WebGl2RenderingContext::FLOAT,
+                false,
+                STRIDE,
+                offset,
+            );
+            gl.vertex_attrib_divisor(location, 1);
+        }
+        gl.bind_vertex_array(None);
+
+        Ok(Self {
+            view_proj_loc: gl.get_uniform_location(&program, "viewProjection"),
+            viewport_loc: gl.get_uniform_location(&program, "viewport"),
+            round_joins_loc: gl.get_uniform_location(&program, "roundJoins"),
+            program,
+            vao,
+            buffer,
+        })
+    }
+
+    /**
+     * Draw one connected polyline through `points`, depth-tested against the scene.
+     * Widths are in canvas pixels, also when the viewport is a scaled-down target
+     */
+    pub fn draw(
+        &self,
+        gl: &WebGl2RenderingContext,
+        view_proj: &Mat4,
+        points: &[PolylinePoint],
+        join: LineJoin,
+    ) {
+        if points.len() < 2 {
+            return;
+        }
+        let [viewport_width, viewport_height] = current_viewport(gl);
+        let width_scale = viewport_width / gl.drawing_buffer_width().max(1) as f32;
+        // Repeat the end points so the first and last segments have neighbours to read
+        let padded = [&points[..1], points, &points[points.len() - 1..]];
+        let mut data = Vec::with_capacity((points.len() + 2) * FLOATS_PER_POINT);
+        for point in padded.concat() {
+            data.extend(point.position);
+            data.push(point.width * width_scale);
+            data.extend(point.color);
+        }
+
+        self.buffer.write(gl, &data);
+
+        gl.use_program(Some(&self.program));
+        gl.uniform_matrix4fv_with_f32_array(self.view_proj_loc.as_ref(), false, view_proj);
+        gl.uniform2f(self.viewport_loc.as_ref(), viewport_width, viewport_height);
+        gl.uniform1i(
+            self.round_joins_loc.as_ref(),
+            (join == LineJoin::Round) as i32,
+        );
+
+        gl.enable(WebGl2RenderingContext::DEPTH_TEST);
+        gl.disable(WebGl2RenderingContext::CULL_FACE);
+        gl.enable(WebGl2RenderingContext::BLEND);
+        gl.blend_func(
+            WebGl2RenderingContext::SRC_ALPHA,
+            WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+        );
+        // Soft edges shouldn't punch holes in lines drawn behind them
+        gl.depth_mask(false);
+        gl.bind_vertex_array(Some(&self.vao));
+        gl.draw_arrays_instanced(
+            WebGl2RenderingContext::TRIANGLES,
+            0,
+            6,
+            points.len() as i32 - 1,
+        );
+        gl.bind_vertex_array(None);
+        gl.depth_mask(true);
+        gl.disable(WebGl2RenderingContext::BLEND);
+    }
+}
+
+/**
+ * Width and height of the viewport currently set on `gl`
+ */
+fn current_viewport(gl: &WebGl2RenderingContext) -> [f32; 2] {
+    let viewport = gl
+        .get_parameter(WebGl2RenderingContext::VIEWPORT)
+        .ok()
+        .map(|value| Int32Array::from(value).to_vec())
+        .unwrap_or_default();
+    match viewport.as_slice() {
+        &[_, _, width, height] => [width as f32, height as f32],
+        _ => [
+            gl.drawing_buffer_width() as f32,
+            gl.drawing_buffer_height() as f32,
+        ],
+    }
+}
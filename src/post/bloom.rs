@@ -0,0 +1,186 @@
+use web_sys::{WebGl2RenderingContext, WebGlTexture, WebGlUniformLocation};
+
+use super::{ColorTarget, FullscreenPass, SceneTarget};
+use crate::shaders;
+
+// Levels below the half-resolution bright pass, each half the size of the last
+const LEVELS: usize = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BloomSettings {
+    // Brightness above which pixels start to glow; above 1 only HDR values do
+    pub threshold: f32,
+    // How much of the glow is added back onto the frame
+    pub intensity: f32,
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        Self {
+            threshold: 1.0,
+            intensity: 0.6,
+        }
+    }
+}
+
+/**
+ * Sizes of the pyramid's levels for a frame of `size`: half of it first,
+ * then halving until a level would be under 2 pixels across or `levels` are
+ * reached
+ */
+fn pyramid_sizes(size: (i32, i32), levels: usize) -> Vec<(i32, i32)> {
+    let mut sizes = Vec::new();
+    let (mut width, mut height) = size;
+    while sizes.len() < levels {
+        width /= 2;
+        height /= 2;
+        if width < 2 || height < 2 {
+            break;
+        }
+        sizes.push((width, height));
+    }
+    sizes
+}
+
+/**
+ * Bloom: a bright pass keeps what is over the threshold at half resolution,
+ * which is filtered down a pyramid of ever smaller levels and back up again,
+ * each level adding its blurred copy onto the one above. The top level, a
+ * wide and smooth glow, is then added onto the frame
+ */
+pub struct Bloom {
+    bright_pass: FullscreenPass,
+    bright_source_loc: Option<WebGlUniformLocation>,
+    threshold_loc: Option<WebGlUniformLocation>,
+    down_pass: FullscreenPass,
+    down_source_loc: Option<WebGlUniformLocation>,
+    down_texel_loc: Option<WebGlUniformLocation>,
+    up_pass: FullscreenPass,
+    up_source_loc: Option<WebGlUniformLocation>,
+    up_texel_loc: Option<WebGlUniformLocation>,
+    composite_pass: FullscreenPass,
+    frame_loc: Option<WebGlUniformLocation>,
+    glow_loc: Option<WebGlUniformLocation>,
+    intensity_loc: Option<WebGlUniformLocation>,
+    // Largest first
+    levels: Vec<(ColorTarget, (i32, i32))>,
+    output: ColorTarget,
+}
+
+impl Bloom {
+    pub fn new(gl: &WebGl2RenderingContext, scene: &SceneTarget) -> Result<Self, String> {
+        let (width, height) = scene.size();
+        let levels = pyramid_sizes((width, height), LEVELS)
+            .into_iter()
+            .map(|size| Ok((ColorTarget::new(gl, scene, size.0, size.1)?, size)))
+            .collect::<Result<Vec<_>, String>>()?;
+        if levels.is_empty() {
+            return Err("The frame is too small for bloom".to_string());
+        }
+        let bright_pass = FullscreenPass::new(gl, shaders::POST_BLOOM_BRIGHT_FRAG)?;
+        let down_pass = FullscreenPass::new(gl, shaders::POST_BLOOM_DOWNSAMPLE_FRAG)?;
+        let up_pass = FullscreenPass::new(gl, shaders::POST_BLOOM_UPSAMPLE_FRAG)?;
+        let composite_pass = FullscreenPass::new(gl, shaders::POST_BLOOM_COMPOSITE_FRAG)?;
+        Ok(Self {
+            bright_source_loc: bright_pass.location(gl, "source"),
+            threshold_loc: bright_pass.location(gl, "threshold"),
+            down_source_loc: down_pass.location(gl, "source"),
+            down_texel_loc: down_pass.location(gl, "texel"),
+            up_source_loc: up_pass.location(gl, "source"),
+            up_texel_loc: up_pass.location(gl, "texel"),
+            frame_loc: composite_pass.location(gl, "frame"),
+            glow_loc: composite_pass.location(gl, "glow"),
+            intensity_loc: composite_pass.location(gl, "intensity"),
+            bright_pass,
+            down_pass,
+            up_pass,
+            composite_pass,
+            levels,
+            output: ColorTarget::new(gl, scene, width, height)?,
+        })
+    }
+
+    /**
+     * Add the glow of `source`, a frame the size of the scene target, and
+     * return the texture holding the result
+     */
+    pub fn apply(
+        &self,
+        gl: &WebGl2RenderingContext,
+        source: &WebGlTexture,
+        settings: &BloomSettings,
+    ) -> &WebGlTexture {
+        let (top, _) = &self.levels[0];
+        top.bind(gl);
+        self.bright_pass
+            .begin(gl, &[(&self.bright_source_loc, source)]);
+        gl.uniform1f(self.threshold_loc.as_ref(), settings.threshold);
+        self.bright_pass.draw(gl);
+
+        for pair in self.levels.windows(2) {
+            let [(larger, larger_size), (smaller, _)] = pair else {
+                continue;
+            };
+            smaller.bind(gl);
+            self.down_pass
+                .begin(gl, &[(&self.down_source_loc, larger.texture())]);
+            gl.uniform2f(
+                self.down_texel_loc.as_ref(),
+                1.0 / larger_size.0 as f32,
+                1.0 / larger_size.1 as f32,
+            );
+            self.down_pass.draw(gl);
+        }
+
+        // Back up, each level adding its blurred smaller neighbor
+        gl.enable(WebGl2RenderingContext::BLEND);
+        gl.blend_func(WebGl2RenderingContext::ONE, WebGl2RenderingContext::ONE);
+        for pair in self.levels.windows(2).rev() {
+            let [(larger, _), (smaller, smaller_size)] = pair else {
+                continue;
+            };
+            larger.bind(gl);
+            self.up_pass
+                .begin(gl, &[(&self.up_source_loc, smaller.texture())]);
+            gl.uniform2f(
+                self.up_texel_loc.as_ref(),
+                1.0 / smaller_size.0 as f32,
+                1.0 / smaller_size.1 as f32,
+            );
+            self.up_pass.draw(gl);
+        }
+        gl.disable(WebGl2RenderingContext::BLEND);
+
+        self.output.bind(gl);
+        self.composite_pass.begin(
+            gl,
+            &[(&self.frame_loc, source), (&self.glow_loc, top.texture())],
+        );
+        // Every level has been added into the top one
+        gl.uniform1f(
+            self.intensity_loc.as_ref(),
+            settings.intensity / self.levels.len() as f32,
+        );
+        self.composite_pass.draw(gl);
+        self.output.texture()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn halves_from_half_resolution() {
+        assert_eq!(
+            pyramid_sizes((640, 400), 4),
+            [(320, 200), (160, 100), (80, 50), (40, 25)]
+        );
+    }
+
+    #[test]
+    fn stops_before_levels_get_too_small() {
+        assert_eq!(pyramid_sizes((16, 8), 6), [(8, 4), (4, 2)]);
+        assert!(pyramid_sizes((2, 2), 6).is_empty());
+    }
+}
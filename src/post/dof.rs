@@ -0,0 +1,136 @@
+use web_sys::{WebGl2RenderingContext, WebGlTexture, WebGlUniformLocation};
+
+use super::{ColorTarget, FullscreenPass, SceneTarget};
+use crate::camera::{FAR, NEAR};
+use crate::shaders;
+
+// Widest blur, in full-resolution pixels, at a circle of confusion of 1
+const MAX_RADIUS: f32 = 12.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DofSettings {
+    // Distance from the camera that stays sharp, in world units
+    pub focus_distance: f32,
+    // How quickly things blur away from the focal plane; 0 keeps everything sharp
+    pub aperture: f32,
+}
+
+impl Default for DofSettings {
+    fn default() -> Self {
+        Self {
+            focus_distance: 4.0,
+            aperture: 0.5,
+        }
+    }
+}
+
+/**
+ * Depth of field in three steps: the circle of confusion of every pixel
+ * from the scene's depth, a separable blur at half resolution that scales
+ * its taps by it, and a composite blending the sharp frame into the blurred
+ * one where the circle is small
+ */
+pub struct DepthOfField {
+    coc_pass: FullscreenPass,
+    coc_color_loc: Option<WebGlUniformLocation>,
+    coc_depth_loc: Option<WebGlUniformLocation>,
+    near_loc: Option<WebGlUniformLocation>,
+    far_loc: Option<WebGlUniformLocation>,
+    focus_loc: Option<WebGlUniformLocation>,
+    aperture_loc: Option<WebGlUniformLocation>,
+    blur_pass: FullscreenPass,
+    blur_source_loc: Option<WebGlUniformLocation>,
+    direction_loc: Option<WebGlUniformLocation>,
+    max_radius_loc: Option<WebGlUniformLocation>,
+    composite_pass: FullscreenPass,
+    sharp_loc: Option<WebGlUniformLocation>,
+    blurred_loc: Option<WebGlUniformLocation>,
+    // Scene color with the circle of confusion in alpha
+    coc: ColorTarget,
+    // Half resolution, blurred across and then down
+    across: ColorTarget,
+    down: ColorTarget,
+    output: ColorTarget,
+    size: (i32, i32),
+}
+
+impl DepthOfField {
+    pub fn new(gl: &WebGl2RenderingContext, scene: &SceneTarget) -> Result<Self, String> {
+        let (width, height) = scene.size();
+        let half = ((width / 2).max(1), (height / 2).max(1));
+        let coc_pass = FullscreenPass::new(gl, shaders::POST_DOF_COC_FRAG)?;
+        let blur_pass = FullscreenPass::new(gl, shaders::POST_DOF_BLUR_FRAG)?;
+        let composite_pass = FullscreenPass::new(gl, shaders::POST_DOF_COMPOSITE_FRAG)?;
+        Ok(Self {
+            coc_color_loc: coc_pass.location(gl, "colorTexture"),
+            coc_depth_loc: coc_pass.location(gl, "depthTexture"),
+            near_loc: coc_pass.location(gl, "near"),
+            far_loc: coc_pass.location(gl, "far"),
+            focus_loc: coc_pass.location(gl, "focusDistance"),
+            aperture_loc: coc_pass.location(gl, "aperture"),
+            blur_source_loc: blur_pass.location(gl, "source"),
+            direction_loc: blur_pass.location(gl, "direction"),
+            max_radius_loc: blur_pass.location(gl, "maxRadius"),
+            sharp_loc: composite_pass.location(gl, "sharpTexture"),
+            blurred_loc: composite_pass.location(gl, "blurredTexture"),
+            coc_pass,
+            blur_pass,
+            composite_pass,
+            coc: ColorTarget::new(gl, scene, width, height)?,
+            across: ColorTarget::new(gl, scene, half.0, half.1)?,
+            down: ColorTarget::new(gl, scene, half.0, half.1)?,
+            output: ColorTarget::new(gl, scene, width, height)?,
+            size: (width, height),
+        })
+    }
+
+    /**
+     * Run the passes over `source`, a frame with the depth in `scene`, and
+     * return the texture holding the result
+     */
+    pub fn apply(
+        &self,
+        gl: &WebGl2RenderingContext,
+        source: &WebGlTexture,
+        scene: &SceneTarget,
+        settings: &DofSettings,
+    ) -> &WebGlTexture {
+        self.coc.bind(gl);
+        self.coc_pass.begin(
+            gl,
+            &[
+                (&self.coc_color_loc, source),
+                (&self.coc_depth_loc, scene.depth()),
+            ],
+        );
+        gl.uniform1f(self.near_loc.as_ref(), NEAR);
+        gl.uniform1f(self.far_loc.as_ref(), FAR);
+        gl.uniform1f(self.focus_loc.as_ref(), settings.focus_distance);
+        gl.uniform1f(self.aperture_loc.as_ref(), settings.aperture);
+        self.coc_pass.draw(gl);
+
+        // Steps are in full-resolution pixels whatever size is being written
+        let texel = [1.0 / self.size.0 as f32, 1.0 / self.size.1 as f32];
+        for (source, target, direction) in [
+            (self.coc.texture(), &self.across, [texel[0], 0.0]),
+            (self.across.texture(), &self.down, [0.0, texel[1]]),
+        ] {
+            target.bind(gl);
+            self.blur_pass.begin(gl, &[(&self.blur_source_loc, source)]);
+            gl.uniform2f(self.direction_loc.as_ref(), direction[0], direction[1]);
+            gl.uniform1f(self.max_radius_loc.as_ref(), MAX_RADIUS);
+            self.blur_pass.draw(gl);
+        }
+
+        self.output.bind(gl);
+        self.composite_pass.begin(
+            gl,
+            &[
+                (&self.sharp_loc, self.coc.texture()),
+                (&self.blurred_loc, self.down.texture()),
+            ],
+        );
+        self.composite_pass.draw(gl);
+        self.output.texture()
+    }
+}
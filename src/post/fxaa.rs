@@ -0,0 +1,43 @@
+use web_sys::{WebGl2RenderingContext, WebGlTexture, WebGlUniformLocation};
+
+use super::{ColorTarget, FullscreenPass, SceneTarget};
+use crate::shaders;
+
+/**
+ * FXAA: finds edges from the brightness of each pixel's corners and blurs
+ * along them, so it needs nothing but the finished frame. Cheap, but also
+ * softens texture detail and can't fix shimmering between frames
+ */
+pub struct Fxaa {
+    pass: FullscreenPass,
+    source_loc: Option<WebGlUniformLocation>,
+    texel_loc: Option<WebGlUniformLocation>,
+    output: ColorTarget,
+    size: (i32, i32),
+}
+
+impl Fxaa {
+    pub fn new(gl: &WebGl2RenderingContext, scene: &SceneTarget) -> Result<Self, String> {
+        let (width, height) = scene.size();
+        let pass = FullscreenPass::new(gl, shaders::POST_FXAA_FRAG)?;
+        Ok(Self {
+            source_loc: pass.location(gl, "source"),
+            texel_loc: pass.location(gl, "texel"),
+            pass,
+            output: ColorTarget::new(gl, scene, width, height)?,
+            size: (width, height),
+        })
+    }
+
+    pub fn apply(&self, gl: &WebGl2RenderingContext, source: &WebGlTexture) -> &WebGlTexture {
+        self.output.bind(gl);
+        self.pass.begin(gl, &[(&self.source_loc, source)]);
+        gl.uniform2f(
+            self.texel_loc.as_ref(),
+            1.0 / self.size.0 as f32,
+            1.0 / self.size.1 as f32,
+        );
+        self.pass.draw(gl);
+        self.output.texture()
+    }
+}
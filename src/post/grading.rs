@@ -0,0 +1,293 @@
+use web_sys::{WebGl2RenderingContext, WebGlTexture, WebGlUniformLocation};
+
+use super::FullscreenPass;
+use crate::shaders;
+use crate::texture::Texture3D;
+
+// Entries per axis of the generated tables
+const PRESET_SIZE: usize = 16;
+// Loaded strips with more entries per axis than this are rejected
+const MAX_LUT_SIZE: usize = 64;
+
+/**
+ * A color lookup table: `size`³ RGBA8 entries, red varying fastest, then
+ * green, then blue, so the input color picks a voxel and its value is the
+ * graded color
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lut {
+    pub size: usize,
+    pub texels: Vec<u8>,
+}
+
+impl Lut {
+    /**
+     * A table mapping every color through `grade`, which takes and returns
+     * colors in 0..1
+     */
+    pub fn from_fn(size: usize, grade: impl Fn([f32; 3]) -> [f32; 3]) -> Self {
+        let mut texels = Vec::with_capacity(size * size * size * 4);
+        let step = 1.0 / (size - 1).max(1) as f32;
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    let color = grade([r as f32 * step, g as f32 * step, b as f32 * step]);
+                    texels.extend(color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8));
+                    texels.push(255);
+                }
+            }
+        }
+        Self { size, texels }
+    }
+
+    pub fn identity(size: usize) -> Self {
+        Self::from_fn(size, |color| color)
+    }
+
+    /**
+     * Read a table from the usual LUT image strip: `size` square tiles of
+     * `size`×`size` pixels, one per blue level, side by side (or stacked for
+     * a vertical strip). Within a tile red increases to the right and green
+     * downwards. `rgba` is the image's pixels, top row first
+     */
+    pub fn from_strip(width: usize, height: usize, rgba: &[u8]) -> Result<Self, String> {
+        if rgba.len() != width * height * 4 {
+            return Err(format!(
+                "{} bytes of pixels for a {}x{} image",
+                rgba.len(),
+                width,
+                height
+            ));
+        }
+        let (size, vertical) = if width == height * height {
+            (height, false)
+        } else if height == width * width {
+            (width, true)
+        } else {
+            return Err(format!(
+                "A {}x{} image isn't a LUT strip; expected N²×N or N×N² pixels, like 256x16",
+                width, height
+            ));
+        };
+        if !(2..=MAX_LUT_SIZE).contains(&size) {
+            return Err(format!(
+                "LUT strips must have 2 to {} entries per axis, not {}",
+                MAX_LUT_SIZE, size
+            ));
+        }
+        let mut texels = Vec::with_capacity(size * size * size * 4);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    let (x, y) = if vertical {
+                        (r, b * size + g)
+                    } else {
+                        (b * size + r, g)
+                    };
+                    let pixel = (y * width + x) * 4;
+                    texels.extend_from_slice(&rgba[pixel..pixel + 3]);
+                    texels.push(255);
+                }
+            }
+        }
+        Ok(Self { size, texels })
+    }
+}
+
+/**
+ * Built-in looks, generated rather than loaded
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LutPreset {
+    Neutral,
+    Warm,
+    Cool,
+    Faded,
+    Noir,
+}
+
+impl LutPreset {
+    pub const ALL: [LutPreset; 5] = [
+        LutPreset::Neutral,
+        LutPreset::Warm,
+        LutPreset::Cool,
+        LutPreset::Faded,
+        LutPreset::Noir,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LutPreset::Neutral => "Neutral",
+            LutPreset::Warm => "Warm",
+            LutPreset::Cool => "Cool",
+            LutPreset::Faded => "Faded film",
+            LutPreset::Noir => "Noir",
+        }
+    }
+
+    pub fn lut(self) -> Lut {
+        match self {
+            LutPreset::Neutral => Lut::identity(PRESET_SIZE),
+            LutPreset::Warm => Lut::from_fn(PRESET_SIZE, |[r, g, b]| {
+                [r * 1.08 + 0.02, g * 1.0 + 0.01, b * 0.85]
+            }),
+            LutPreset::Cool => Lut::from_fn(PRESET_SIZE, |[r, g, b]| {
+                [r * 0.88, g * 0.98 + 0.01, b * 1.1 + 0.03]
+            }),
+            // Lifted blacks, lowered whites and a little less contrast
+            LutPreset::Faded => Lut::from_fn(PRESET_SIZE, |color| {
+                color.map(|c| 0.08 + 0.84 * (c * 0.85 + 0.075))
+            }),
+            // Luminance with a steeper curve
+            LutPreset::Noir => Lut::from_fn(PRESET_SIZE, |[r, g, b]| {
+                let luma = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+                let curved = luma * luma * (3.0 - 2.0 * luma);
+                [curved; 3]
+            }),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GradingSettings {
+    // 0 is grayscale, 1 leaves colors alone, above 1 boosts them
+    pub saturation: f32,
+    // How dark the corners get, 0 to 1
+    pub vignette: f32,
+}
+
+impl Default for GradingSettings {
+    fn default() -> Self {
+        Self {
+            saturation: 1.0,
+            vignette: 0.3,
+        }
+    }
+}
+
+/**
+ * The final pass, in place of `Present`: colors are clamped, looked up in a
+ * 3D LUT, resaturated and darkened towards the corners on their way to the
+ * canvas
+ */
+pub struct ColorGrading {
+    pass: FullscreenPass,
+    source_loc: Option<WebGlUniformLocation>,
+    lut_loc: Option<WebGlUniformLocation>,
+    lut_size_loc: Option<WebGlUniformLocation>,
+    saturation_loc: Option<WebGlUniformLocation>,
+    vignette_loc: Option<WebGlUniformLocation>,
+    lut: Texture3D,
+    lut_size: usize,
+}
+
+impl ColorGrading {
+    pub fn new(gl: &WebGl2RenderingContext) -> Result<Self, String> {
+        let pass = FullscreenPass::new(gl, shaders::POST_GRADING_FRAG)?;
+        let neutral = LutPreset::Neutral.lut();
+        Ok(Self {
+            source_loc: pass.location(gl, "source"),
+            lut_loc: pass.location(gl, "lut"),
+            lut_size_loc: pass.location(gl, "lutSize"),
+            saturation_loc: pass.location(gl, "saturation"),
+            vignette_loc: pass.location(gl, "vignette"),
+            pass,
+            lut: upload(gl, &neutral)?,
+            lut_size: neutral.size,
+        })
+    }
+
+    /**
+     * Grade with `lut` from the next frame on
+     */
+    pub fn set_lut(&mut self, gl: &WebGl2RenderingContext, lut: &Lut) -> Result<(), String> {
+        self.lut = upload(gl, lut)?;
+        self.lut_size = lut.size;
+        Ok(())
+    }
+
+    pub fn draw(
+        &self,
+        gl: &WebGl2RenderingContext,
+        source: &WebGlTexture,
+        size: (i32, i32),
+        settings: &GradingSettings,
+    ) {
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+        gl.viewport(0, 0, size.0, size.1);
+        self.pass.begin(gl, &[(&self.source_loc, source)]);
+        // The 2D textures take the first units
+        self.lut.bind(gl, 1);
+        gl.uniform1i(self.lut_loc.as_ref(), 1);
+        gl.active_texture(WebGl2RenderingContext::TEXTURE0);
+        gl.uniform1f(self.lut_size_loc.as_ref(), self.lut_size as f32);
+        gl.uniform1f(self.saturation_loc.as_ref(), settings.saturation);
+        gl.uniform1f(self.vignette_loc.as_ref(), settings.vignette);
+        self.pass.draw(gl);
+    }
+}
+
+fn upload(gl: &WebGl2RenderingContext, lut: &Lut) -> Result<Texture3D, String> {
+    let size = lut.size as i32;
+    Texture3D::from_rgba8(gl, size, size, size, &lut.texels)
+        .ok_or_else(|| "Unable to upload the LUT".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * The strip image a table would be saved as
+     */
+    fn strip(lut: &Lut, vertical: bool) -> (usize, usize, Vec<u8>) {
+        let size = lut.size;
+        let (width, height) = if vertical {
+            (size, size * size)
+        } else {
+            (size * size, size)
+        };
+        let mut rgba = vec![0; width * height * 4];
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    let (x, y) = if vertical {
+                        (r, b * size + g)
+                    } else {
+                        (b * size + r, g)
+                    };
+                    let texel = ((b * size + g) * size + r) * 4;
+                    let pixel = (y * width + x) * 4;
+                    rgba[pixel..pixel + 4].copy_from_slice(&lut.texels[texel..texel + 4]);
+                }
+            }
+        }
+        (width, height, rgba)
+    }
+
+    #[test]
+    fn identity_maps_corners_to_themselves() {
+        let lut = Lut::identity(4);
+        assert_eq!(lut.texels.len(), 4 * 4 * 4 * 4);
+        assert_eq!(&lut.texels[..4], &[0, 0, 0, 255]);
+        // Last red entry of the first row
+        assert_eq!(&lut.texels[12..16], &[255, 0, 0, 255]);
+        assert_eq!(&lut.texels[lut.texels.len() - 4..], &[255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn reads_horizontal_and_vertical_strips() {
+        let lut = LutPreset::Warm.lut();
+        for vertical in [false, true] {
+            let (width, height, rgba) = strip(&lut, vertical);
+            assert_eq!(Lut::from_strip(width, height, &rgba).unwrap(), lut);
+        }
+    }
+
+    #[test]
+    fn rejects_images_that_are_not_strips() {
+        assert!(Lut::from_strip(100, 20, &[0; 100 * 20 * 4]).is_err());
+        assert!(Lut::from_strip(256, 16, &[0; 12]).is_err());
+        assert!(Lut::from_strip(1, 1, &[0; 4]).is_err());
+    }
+}
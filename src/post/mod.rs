@@ -0,0 +1,357 @@
+mod bloom;
+mod dof;
+mod fxaa;
+mod grading;
+mod motion_blur;
+mod msaa;
+mod taa;
+
+use web_sys::{
+    WebGl2RenderingContext, WebGlFramebuffer, WebGlProgram, WebGlTexture, WebGlUniformLocation,
+    WebGlVertexArrayObject,
+};
+
+use crate::gl_util;
+use crate::gpu_memory::{self, Allocation, Kind};
+use crate::shaders;
+
+pub use bloom::{Bloom, BloomSettings};
+pub use dof::{DepthOfField, DofSettings};
+pub use fxaa::Fxaa;
+pub use grading::{ColorGrading, GradingSettings, Lut, LutPreset};
+pub use motion_blur::MotionBlur;
+pub use msaa::MultisampleTarget;
+pub use taa::TemporalAa;
+
+/**
+ * Where a frame is rendered before post-processing: a color texture, half
+ * float where `EXT_color_buffer_float` allows so values above 1 survive to
+ * the post passes, and a depth texture they can sample. With half floats
+ * there is also a second color attachment for screen-space velocity, which
+ * shaders write as `layout(location = 1)`: how far the surface moved across
+ * the screen since the last frame, in texture coordinates
+ */
+pub struct SceneTarget {
+    framebuffer: WebGlFramebuffer,
+    color: WebGlTexture,
+    depth: WebGlTexture,
+    velocity: Option<WebGlTexture>,
+    hdr: bool,
+    width: i32,
+    height: i32,
+    _allocations: Vec<Allocation>,
+}
+
+impl SceneTarget {
+    pub fn new(gl: &WebGl2RenderingContext, width: i32, height: i32) -> Result<Self, String> {
+        // Half-float targets are only renderable with this extension
+        let hdr = gl
+            .get_extension("EXT_color_buffer_float")
+            .ok()
+            .flatten()
+            .is_some();
+        let format = if hdr {
+            WebGl2RenderingContext::RGBA16F
+        } else {
+            WebGl2RenderingContext::RGBA8
+        };
+        let color = create_texture(gl, format, width, height, WebGl2RenderingContext::LINEAR)?;
+        // Depth is read back as a float, so no filtering
+        let depth = create_texture(
+            gl,
+            WebGl2RenderingContext::DEPTH_COMPONENT24,
+            width,
+            height,
+            WebGl2RenderingContext::NEAREST,
+        )?;
+        // Velocities are small signed fractions, which 8-bit targets can't hold
+        let velocity = if hdr {
+            Some(create_texture(
+                gl,
+                WebGl2RenderingContext::RG16F,
+                width,
+                height,
+                WebGl2RenderingContext::NEAREST,
+            )?)
+        } else {
+            None
+        };
+        let mut colors = vec![&color];
+        colors.extend(velocity.as_ref());
+        let framebuffer = create_framebuffer(gl, &colors, Some(&depth))?;
+
+        let mut texel_bytes = vec![if hdr { 8 } else { 4 }, 4];
+        if velocity.is_some() {
+            texel_bytes.push(4);
+        }
+        Ok(Self {
+            framebuffer,
+            color,
+            depth,
+            velocity,
+            hdr,
+            width,
+            height,
+            _allocations: texel_bytes
+                .into_iter()
+                .map(|bytes_per_pixel| {
+                    Allocation::new(
+                        Kind::Texture,
+                        gpu_memory::texture_bytes(
+                            width as usize,
+                            height as usize,
+                            1,
+                            bytes_per_pixel,
+                            false,
+                        ),
+                    )
+                })
+                .collect(),
+        })
+    }
+
+    /**
+     * Render into the target from here on
+     */
+    pub fn bind(&self, gl: &WebGl2RenderingContext) {
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(&self.framebuffer));
+        gl.viewport(0, 0, self.width, self.height);
+    }
+
+    /**
+     * Clear the bound target: color to `color`, velocity to still and depth
+     * to the far plane
+     */
+    pub fn clear(&self, gl: &WebGl2RenderingContext, color: [f32; 4]) {
+        gl.clear_bufferfv_with_f32_array(WebGl2RenderingContext::COLOR, 0, &color);
+        if self.velocity.is_some() {
+            gl.clear_bufferfv_with_f32_array(WebGl2RenderingContext::COLOR, 1, &[0.0; 4]);
+        }
+        gl.clear_bufferfv_with_f32_array(WebGl2RenderingContext::DEPTH, 0, &[1.0]);
+    }
+
+    pub fn color(&self) -> &WebGlTexture {
+        &self.color
+    }
+
+    pub fn depth(&self) -> &WebGlTexture {
+        &self.depth
+    }
+
+    pub fn velocity(&self) -> Option<&WebGlTexture> {
+        self.velocity.as_ref()
+    }
+
+    /**
+     * Whether colors are stored as half floats rather than clamped to 0..1
+     */
+    pub fn is_hdr(&self) -> bool {
+        self.hdr
+    }
+
+    pub fn size(&self) -> (i32, i32) {
+        (self.width, self.height)
+    }
+}
+
+/**
+ * A color texture a post pass renders into and a later one samples, in the
+ * scene's color format, sampled linearly and clamped at the edges
+ */
+pub struct ColorTarget {
+    framebuffer: WebGlFramebuffer,
+    texture: WebGlTexture,
+    width: i32,
+    height: i32,
+    _allocation: Allocation,
+}
+
+impl ColorTarget {
+    pub fn new(
+        gl: &WebGl2RenderingContext,
+        scene: &SceneTarget,
+        width: i32,
+        height: i32,
+    ) -> Result<Self, String> {
+        let (format, bytes_per_pixel) = if scene.is_hdr() {
+            (WebGl2RenderingContext::RGBA16F, 8)
+        } else {
+            (WebGl2RenderingContext::RGBA8, 4)
+        };
+        let texture = create_texture(gl, format, width, height, WebGl2RenderingContext::LINEAR)?;
+        let framebuffer = create_framebuffer(gl, &[&texture], None)?;
+        Ok(Self {
+            framebuffer,
+            texture,
+            width,
+            height,
+            _allocation: Allocation::new(
+                Kind::Texture,
+                gpu_memory::texture_bytes(
+                    width as usize,
+                    height as usize,
+                    1,
+                    bytes_per_pixel,
+                    false,
+                ),
+            ),
+        })
+    }
+
+    pub fn bind(&self, gl: &WebGl2RenderingContext) {
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(&self.framebuffer));
+        gl.viewport(0, 0, self.width, self.height);
+    }
+
+    pub fn texture(&self) -> &WebGlTexture {
+        &self.texture
+    }
+}
+
+/**
+ * A fragment shader run once per pixel of the bound framebuffer, by a
+ * full-screen triangle. Its vertex shader passes `vUv` in 0..1
+ */
+pub struct FullscreenPass {
+    program: WebGlProgram,
+    // gl_VertexID only, but a bound VAO keeps every implementation happy
+    vao: WebGlVertexArrayObject,
+}
+
+impl FullscreenPass {
+    pub fn new(gl: &WebGl2RenderingContext, fragment: &str) -> Result<Self, String> {
+        let program = gl_util::create_program(gl, shaders::POST_FULLSCREEN_VERT, fragment)?;
+        let vao = gl
+            .create_vertex_array()
+            .ok_or_else(|| "Unable to create post pass VAO".to_string())?;
+        Ok(Self { program, vao })
+    }
+
+    pub fn location(
+        &self,
+        gl: &WebGl2RenderingContext,
+        name: &str,
+    ) -> Option<WebGlUniformLocation> {
+        gl.get_uniform_location(&self.program, name)
+    }
+
+    /**
+     * Use the program and bind `textures` to units 0, 1, ... for the given
+     * samplers; other uniforms are set after this
+     */
+    pub fn begin(
+        &self,
+        gl: &WebGl2RenderingContext,
+        textures: &[(&Option<WebGlUniformLocation>, &WebGlTexture)],
+    ) {
+        gl.use_program(Some(&self.program));
+        for (unit, (location, texture)) in textures.iter().enumerate() {
+            gl.active_texture(WebGl2RenderingContext::TEXTURE0 + unit as u32);
+            gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(texture));
+            gl.uniform1i(location.as_ref(), unit as i32);
+        }
+        gl.active_texture(WebGl2RenderingContext::TEXTURE0);
+    }
+
+    pub fn draw(&self, gl: &WebGl2RenderingContext) {
+        gl.disable(WebGl2RenderingContext::DEPTH_TEST);
+        gl.bind_vertex_array(Some(&self.vao));
+        gl.draw_arrays(WebGl2RenderingContext::TRIANGLES, 0, 3);
+        gl.bind_vertex_array(None);
+    }
+}
+
+/**
+ * The last pass: copies a post-processed texture to the canvas
+ */
+pub struct Present {
+    pass: FullscreenPass,
+    source_loc: Option<WebGlUniformLocation>,
+}
+
+impl Present {
+    pub fn new(gl: &WebGl2RenderingContext) -> Result<Self, String> {
+        let pass = FullscreenPass::new(gl, shaders::POST_PRESENT_FRAG)?;
+        Ok(Self {
+            source_loc: pass.location(gl, "source"),
+            pass,
+        })
+    }
+
+    pub fn draw(&self, gl: &WebGl2RenderingContext, source: &WebGlTexture, size: (i32, i32)) {
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+        gl.viewport(0, 0, size.0, size.1);
+        self.pass.begin(gl, &[(&self.source_loc, source)]);
+        self.pass.draw(gl);
+    }
+}
+
+fn create_texture(
+    gl: &WebGl2RenderingContext,
+    format: u32,
+    width: i32,
+    height: i32,
+    filter: u32,
+) -> Result<WebGlTexture, String> {
+    let texture = gl
+        .create_texture()
+        .ok_or_else(|| "Unable to create post target".to_string())?;
+    gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&texture));
+    gl.tex_storage_2d(WebGl2RenderingContext::TEXTURE_2D, 1, format, width, height);
+    for (parameter, value) in [
+        (WebGl2RenderingContext::TEXTURE_MIN_FILTER, filter),
+        (WebGl2RenderingContext::TEXTURE_MAG_FILTER, filter),
+        (
+            WebGl2RenderingContext::TEXTURE_WRAP_S,
+            WebGl2RenderingContext::CLAMP_TO_EDGE,
+        ),
+        (
+            WebGl2RenderingContext::TEXTURE_WRAP_T,
+            WebGl2RenderingContext::CLAMP_TO_EDGE,
+        ),
+    ] {
+        gl.tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, parameter, value as i32);
+    }
+    gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+    Ok(texture)
+}
+
+fn create_framebuffer(
+    gl: &WebGl2RenderingContext,
+    colors: &[&WebGlTexture],
+    depth: Option<&WebGlTexture>,
+) -> Result<WebGlFramebuffer, String> {
+    let framebuffer = gl
+        .create_framebuffer()
+        .ok_or_else(|| "Unable to create post framebuffer".to_string())?;
+    gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(&framebuffer));
+    let buffers = js_sys::Array::new();
+    for (index, color) in colors.iter().enumerate() {
+        let attachment = WebGl2RenderingContext::COLOR_ATTACHMENT0 + index as u32;
+        gl.framebuffer_texture_2d(
+            WebGl2RenderingContext::FRAMEBUFFER,
+            attachment,
+            WebGl2RenderingContext::TEXTURE_2D,
+            Some(color),
+            0,
+        );
+        buffers.push(&attachment.into());
+    }
+    // Draw buffer selection is framebuffer state, so this sticks
+    gl.draw_buffers(&buffers);
+    if let Some(depth) = depth {
+        gl.framebuffer_texture_2d(
+            WebGl2RenderingContext::FRAMEBUFFER,
+            WebGl2RenderingContext::DEPTH_ATTACHMENT,
+            WebGl2RenderingContext::TEXTURE_2D,
+            Some(depth),
+            0,
+        );
+    }
+    let status = gl.check_framebuffer_status(WebGl2RenderingContext::FRAMEBUFFER);
+    gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+    if status != WebGl2RenderingContext::FRAMEBUFFER_COMPLETE {
+        return Err(format!("Post framebuffer incomplete: {}", status));
+    }
+    Ok(framebuffer)
+}
@@ -0,0 +1,59 @@
+use web_sys::{WebGl2RenderingContext, WebGlTexture, WebGlUniformLocation};
+
+use super::{ColorTarget, FullscreenPass, SceneTarget};
+use crate::shaders;
+
+/**
+ * Per-object motion blur: every pixel is averaged along the screen-space
+ * velocity its surface wrote into the scene target, so moving objects smear
+ * in the direction they travel while still ones stay sharp. Needs the
+ * velocity attachment, so half-float targets
+ */
+pub struct MotionBlur {
+    pass: FullscreenPass,
+    source_loc: Option<WebGlUniformLocation>,
+    velocity_loc: Option<WebGlUniformLocation>,
+    shutter_loc: Option<WebGlUniformLocation>,
+    output: ColorTarget,
+}
+
+impl MotionBlur {
+    pub fn new(gl: &WebGl2RenderingContext, scene: &SceneTarget) -> Result<Self, String> {
+        if scene.velocity().is_none() {
+            return Err("Motion blur needs a velocity buffer (EXT_color_buffer_float)".to_string());
+        }
+        let (width, height) = scene.size();
+        let pass = FullscreenPass::new(gl, shaders::POST_MOTION_BLUR_FRAG)?;
+        Ok(Self {
+            source_loc: pass.location(gl, "source"),
+            velocity_loc: pass.location(gl, "velocityTexture"),
+            shutter_loc: pass.location(gl, "shutter"),
+            pass,
+            output: ColorTarget::new(gl, scene, width, height)?,
+        })
+    }
+
+    /**
+     * Blur `source` along the velocities in `scene`. `shutter` is the
+     * fraction of the last frame's motion the blur covers
+     */
+    pub fn apply<'a>(
+        &'a self,
+        gl: &WebGl2RenderingContext,
+        source: &'a WebGlTexture,
+        scene: &SceneTarget,
+        shutter: f32,
+    ) -> &'a WebGlTexture {
+        let Some(velocity) = scene.velocity() else {
+            return source;
+        };
+        self.output.bind(gl);
+        self.pass.begin(
+            gl,
+            &[(&self.source_loc, source), (&self.velocity_loc, velocity)],
+        );
+        gl.uniform1f(self.shutter_loc.as_ref(), shutter);
+        self.pass.draw(gl);
+        self.output.texture()
+    }
+}
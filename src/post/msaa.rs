@@ -0,0 +1,232 @@
+use wasm_bindgen::JsCast;
+use web_sys::{WebGl2RenderingContext, WebGlFramebuffer, WebGlRenderbuffer};
+
+use super::SceneTarget;
+use crate::gpu_memory::{self, Allocation, Kind};
+
+/**
+ * The largest of `supported` sample counts that is at most `wanted`
+ */
+fn pick_samples(supported: &[i32], wanted: i32) -> Option<i32> {
+    supported
+        .iter()
+        .copied()
+        .filter(|&samples| samples > 1 && samples <= wanted)
+        .max()
+}
+
+/**
+ * Multisampled stand-in for a `SceneTarget`, with the same attachments as
+ * renderbuffers. Draw into it instead of the scene target, then `resolve`
+ * averages the samples into the scene target for the post passes
+ */
+pub struct MultisampleTarget {
+    framebuffer: WebGlFramebuffer,
+    _renderbuffers: Vec<WebGlRenderbuffer>,
+    colors: usize,
+    samples: i32,
+    width: i32,
+    height: i32,
+    _allocations: Vec<Allocation>,
+}
+
+impl MultisampleTarget {
+    /**
+     * Up to `wanted` samples per pixel, fewer where the formats don't allow
+     * that many
+     */
+    pub fn new(
+        gl: &WebGl2RenderingContext,
+        scene: &SceneTarget,
+        wanted: i32,
+    ) -> Result<Self, String> {
+        let (width, height) = scene.size();
+        let color_format = if scene.is_hdr() {
+            WebGl2RenderingContext::RGBA16F
+        } else {
+            WebGl2RenderingContext::RGBA8
+        };
+        // (format, bytes per sample) of each color attachment, in order
+        let mut colors = vec![(color_format, if scene.is_hdr() { 8 } else { 4 })];
+        if scene.velocity().is_some() {
+            colors.push((WebGl2RenderingContext::RG16F, 4));
+        }
+        let mut samples = wanted;
+        for (format, _) in &colors {
+            samples = pick_samples(&supported_samples(gl, *format), samples).ok_or_else(|| {
+                format!("Multisampling isn't supported for format 0x{:x}", format)
+            })?;
+        }
+
+        let framebuffer = gl
+            .create_framebuffer()
+            .ok_or_else(|| "Unable to create multisample framebuffer".to_string())?;
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(&framebuffer));
+        let mut renderbuffers = Vec::new();
+        let mut allocations = Vec::new();
+        let buffers = js_sys::Array::new();
+        let attachments = colors
+            .iter()
+            .enumerate()
+            .map(|(index, format)| {
+                let attachment = WebGl2RenderingContext::COLOR_ATTACHMENT0 + index as u32;
+                buffers.push(&attachment.into());
+                (attachment, *format)
+            })
+            .chain([(
+                WebGl2RenderingContext::DEPTH_ATTACHMENT,
+                (WebGl2RenderingContext::DEPTH_COMPONENT24, 4),
+            )])
+            .collect::<Vec<_>>();
+        for (attachment, (format, bytes_per_sample)) in attachments {
+            let renderbuffer = gl
+                .create_renderbuffer()
+                .ok_or_else(|| "Unable to create multisample renderbuffer".to_string())?;
+            gl.bind_renderbuffer(WebGl2RenderingContext::RENDERBUFFER, Some(&renderbuffer));
+            gl.renderbuffer_storage_multisample(
+                WebGl2RenderingContext::RENDERBUFFER,
+                samples,
+                format,
+                width,
+                height,
+            );
+            gl.framebuffer_renderbuffer(
+                WebGl2RenderingContext::FRAMEBUFFER,
+                attachment,
+                WebGl2RenderingContext::RENDERBUFFER,
+                Some(&renderbuffer),
+            );
+            renderbuffers.push(renderbuffer);
+            allocations.push(Allocation::new(
+                Kind::Renderbuffer,
+                gpu_memory::texture_bytes(
+                    width as usize,
+                    height as usize,
+                    1,
+                    bytes_per_sample * samples as usize,
+                    false,
+                ),
+            ));
+        }
+        gl.bind_renderbuffer(WebGl2RenderingContext::RENDERBUFFER, None);
+        gl.draw_buffers(&buffers);
+        let status = gl.check_framebuffer_status(WebGl2RenderingContext::FRAMEBUFFER);
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+        if status != WebGl2RenderingContext::FRAMEBUFFER_COMPLETE {
+            return Err(format!("Multisample framebuffer incomplete: {}", status));
+        }
+        Ok(Self {
+            framebuffer,
+            _renderbuffers: renderbuffers,
+            colors: colors.len(),
+            samples,
+            width,
+            height,
+            _allocations: allocations,
+        })
+    }
+
+    pub fn bind(&self, gl: &WebGl2RenderingContext) {
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(&self.framebuffer));
+        gl.viewport(0, 0, self.width, self.height);
+    }
+
+    /**
+     * Clear the bound target like `SceneTarget::clear`
+     */
+    pub fn clear(&self, gl: &WebGl2RenderingContext, color: [f32; 4]) {
+        gl.clear_bufferfv_with_f32_array(WebGl2RenderingContext::COLOR, 0, &color);
+        if self.colors > 1 {
+            gl.clear_bufferfv_with_f32_array(WebGl2RenderingContext::COLOR, 1, &[0.0; 4]);
+        }
+        gl.clear_bufferfv_with_f32_array(WebGl2RenderingContext::DEPTH, 0, &[1.0]);
+    }
+
+    pub fn samples(&self) -> i32 {
+        self.samples
+    }
+
+    /**
+     * Average every attachment's samples into `scene`, which must be the
+     * target this was created for
+     */
+    pub fn resolve(&self, gl: &WebGl2RenderingContext, scene: &SceneTarget) {
+        gl.bind_framebuffer(
+            WebGl2RenderingContext::READ_FRAMEBUFFER,
+            Some(&self.framebuffer),
+        );
+        gl.bind_framebuffer(
+            WebGl2RenderingContext::DRAW_FRAMEBUFFER,
+            Some(&scene.framebuffer),
+        );
+        // A blit reads one color attachment, so copy them one at a time
+        for index in 0..self.colors {
+            let attachment = WebGl2RenderingContext::COLOR_ATTACHMENT0 + index as u32;
+            let buffers = js_sys::Array::new();
+            for other in 0..self.colors {
+                let buffer = if other == index {
+                    attachment
+                } else {
+                    WebGl2RenderingContext::NONE
+                };
+                buffers.push(&buffer.into());
+            }
+            gl.read_buffer(attachment);
+            gl.draw_buffers(&buffers);
+            // Depth goes along with the first
+            let mask = if index == 0 {
+                WebGl2RenderingContext::COLOR_BUFFER_BIT | WebGl2RenderingContext::DEPTH_BUFFER_BIT
+            } else {
+                WebGl2RenderingContext::COLOR_BUFFER_BIT
+            };
+            gl.blit_framebuffer(
+                0,
+                0,
+                self.width,
+                self.height,
+                0,
+                0,
+                self.width,
+                self.height,
+                mask,
+                WebGl2RenderingContext::NEAREST,
+            );
+        }
+        // Draw buffer selection is framebuffer state, so restore the scene's
+        let buffers = js_sys::Array::new();
+        for index in 0..self.colors {
+            buffers.push(&(WebGl2RenderingContext::COLOR_ATTACHMENT0 + index as u32).into());
+        }
+        gl.draw_buffers(&buffers);
+        gl.read_buffer(WebGl2RenderingContext::COLOR_ATTACHMENT0);
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+    }
+}
+
+/**
+ * Sample counts the implementation supports for renderbuffers of `format`
+ */
+fn supported_samples(gl: &WebGl2RenderingContext, format: u32) -> Vec<i32> {
+    gl.get_internalformat_parameter(
+        WebGl2RenderingContext::RENDERBUFFER,
+        format,
+        WebGl2RenderingContext::SAMPLES,
+    )
+    .ok()
+    .and_then(|value| value.dyn_into::<js_sys::Int32Array>().ok())
+    .map(|counts| counts.to_vec())
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_most_samples_up_to_the_request() {
+        assert_eq!(pick_samples(&[8, 4, 2], 4), Some(4));
+        assert_eq!(pick_samples(&[16, 8], 4), None);
+        assert_eq!(pick_samples(&[4, 2], 8), Some(4));
+        assert_eq!(pick_samples(&[], 4), None);
+    }
+}
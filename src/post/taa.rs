@@ -0,0 +1,164 @@
+use web_sys::{WebGl2RenderingContext, WebGlTexture, WebGlUniformLocation};
+
+use super::{ColorTarget, FullscreenPass, SceneTarget};
+use crate::shaders;
+
+// Jitter positions cycled through before repeating
+const JITTER_SAMPLES: u64 = 8;
+// Weight of the newest frame against the history; lower is smoother but slower to settle
+const BLEND: f32 = 0.1;
+
+/**
+ * Element `index` (from 1) of the Halton low-discrepancy sequence in `base`,
+ * in 0..1
+ */
+fn halton(mut index: u64, base: u64) -> f32 {
+    let mut result = 0.0;
+    let mut fraction = 1.0;
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+    result
+}
+
+/**
+ * Subpixel offset of frame `frame` in pixels, within half a pixel of the
+ * center: Halton bases 2 and 3 across and up, so any run of frames covers
+ * the pixel evenly
+ */
+fn jitter_pixels(frame: u64) -> [f32; 2] {
+    let index = frame % JITTER_SAMPLES + 1;
+    [halton(index, 2) - 0.5, halton(index, 3) - 0.5]
+}
+
+/**
+ * Temporal antialiasing: every frame is drawn with the projection nudged by
+ * a different subpixel offset, and blended into a history of the previous
+ * frames. The history is reprojected along the scene's velocities so moving
+ * things don't ghost, and clamped to the colors around each pixel this frame
+ * so what has been uncovered doesn't either. Needs the velocity attachment,
+ * so half-float targets
+ */
+pub struct TemporalAa {
+    pass: FullscreenPass,
+    source_loc: Option<WebGlUniformLocation>,
+    history_loc: Option<WebGlUniformLocation>,
+    velocity_loc: Option<WebGlUniformLocation>,
+    blend_loc: Option<WebGlUniformLocation>,
+    // Written in turns; `latest` holds the last result
+    history: [ColorTarget; 2],
+    latest: usize,
+    // False until there is a frame to blend with
+    valid: bool,
+    frame: u64,
+    size: (i32, i32),
+}
+
+impl TemporalAa {
+    pub fn new(gl: &WebGl2RenderingContext, scene: &SceneTarget) -> Result<Self, String> {
+        if scene.velocity().is_none() {
+            return Err("TAA needs a velocity buffer (EXT_color_buffer_float)".to_string());
+        }
+        let (width, height) = scene.size();
+        let pass = FullscreenPass::new(gl, shaders::POST_TAA_FRAG)?;
+        Ok(Self {
+            source_loc: pass.location(gl, "source"),
+            history_loc: pass.location(gl, "history"),
+            velocity_loc: pass.location(gl, "velocityTexture"),
+            blend_loc: pass.location(gl, "blend"),
+            pass,
+            history: [
+                ColorTarget::new(gl, scene, width, height)?,
+                ColorTarget::new(gl, scene, width, height)?,
+            ],
+            latest: 0,
+            valid: false,
+            frame: 0,
+            size: (width, height),
+        })
+    }
+
+    /**
+     * Offset to add to clip-space x and y (times w) when drawing the next
+     * frame
+     */
+    pub fn jitter(&self) -> [f32; 2] {
+        let [x, y] = jitter_pixels(self.frame);
+        [x * 2.0 / self.size.0 as f32, y * 2.0 / self.size.1 as f32]
+    }
+
+    /**
+     * Start over from the next frame, e.g. after frames were drawn without
+     * jitter
+     */
+    pub fn reset(&mut self) {
+        self.valid = false;
+    }
+
+    /**
+     * Blend `source`, drawn with `jitter()`, into the history and return
+     * the texture holding the result
+     */
+    pub fn apply(
+        &mut self,
+        gl: &WebGl2RenderingContext,
+        source: &WebGlTexture,
+        scene: &SceneTarget,
+    ) -> &WebGlTexture {
+        let Some(velocity) = scene.velocity() else {
+            return self.history[self.latest].texture();
+        };
+        let (previous, next) = (self.latest, 1 - self.latest);
+        self.history[next].bind(gl);
+        self.pass.begin(
+            gl,
+            &[
+                (&self.source_loc, source),
+                (&self.history_loc, self.history[previous].texture()),
+                (&self.velocity_loc, velocity),
+            ],
+        );
+        gl.uniform1f(
+            self.blend_loc.as_ref(),
+            if self.valid { BLEND } else { 1.0 },
+        );
+        self.pass.draw(gl);
+        self.latest = next;
+        self.valid = true;
+        self.frame += 1;
+        self.history[next].texture()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn halton_subdivides_the_unit_interval() {
+        let base_2: Vec<f32> = (1..=4).map(|i| halton(i, 2)).collect();
+        assert_eq!(base_2, [0.5, 0.25, 0.75, 0.125]);
+        let base_3: Vec<f32> = (1..=3).map(|i| halton(i, 3)).collect();
+        for (value, expected) in base_3.iter().zip([1.0 / 3.0, 2.0 / 3.0, 1.0 / 9.0]) {
+            assert!((value - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn jitter_stays_within_the_pixel_and_repeats() {
+        let offsets: Vec<[f32; 2]> = (0..JITTER_SAMPLES).map(jitter_pixels).collect();
+        for [x, y] in &offsets {
+            assert!((-0.5..0.5).contains(x) && (-0.5..0.5).contains(y));
+        }
+        // Every offset in the cycle is different
+        for (i, a) in offsets.iter().enumerate() {
+            assert!(offsets[i + 1..].iter().all(|b| a != b));
+        }
+        assert_eq!(jitter_pixels(JITTER_SAMPLES), offsets[0]);
+        // Averages out to roughly the pixel center
+        let mean_x = offsets.iter().map(|[x, _]| x).sum::<f32>() / offsets.len() as f32;
+        assert!(mean_x.abs() < 0.1);
+    }
+}
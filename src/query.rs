@@ -0,0 +1,104 @@
+use web_sys::Window;
+
+/**
+ * Decoded `key=value` pairs from the page URL's query string, in order
+ */
+pub fn current() -> Vec<(String, String)> {
+    let search = web_sys::window()
+        .and_then(|window| window.location().search().ok())
+        .unwrap_or_default();
+    parse(search.strip_prefix('?').unwrap_or(&search))
+}
+
+/**
+ * Swap the page URL's query string for `pairs` without a navigation or a
+ * new history entry; the path and fragment stay as they are
+ */
+pub fn replace(pairs: &[(&str, String)]) {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let url = with_query(&window, &encode(pairs));
+    if let Ok(history) = window.history() {
+        // Keep the router's state object so back/forward still work
+        let state = history.state().unwrap_or(wasm_bindgen::JsValue::NULL);
+        let _ = history.replace_state_with_url(&state, "", Some(&url));
+    }
+}
+
+fn with_query(window: &Window, query: &str) -> String {
+    let location = window.location();
+    let path = location.pathname().unwrap_or_default();
+    let hash = location.hash().unwrap_or_default();
+    if query.is_empty() {
+        format!("{}{}", path, hash)
+    } else {
+        format!("{}?{}{}", path, query, hash)
+    }
+}
+
+/**
+ * Split `a=1&b=two` into percent-decoded pairs; `+` is a space and a key
+ * without `=` has an empty value
+ */
+pub fn parse(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(key), decode(value))
+        })
+        .collect()
+}
+
+/**
+ * The inverse of `parse`
+ */
+pub fn encode(pairs: &[(&str, String)]) -> String {
+    pairs
+        .iter()
+        .map(|(key, value)| format!("{}={}", encode_component(key), encode_component(value)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn encode_component(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' | b':' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+fn decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' => {
+                // Malformed escapes are kept as they are
+                let escaped = text
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match escaped {
+                    Some(byte) => {
+                        out.push(byte);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
@@ -0,0 +1,184 @@
+use crate::bounds::{Aabb, Sphere};
+use crate::math::{self, Mat4, Vec3};
+use crate::mesh::MeshData;
+use crate::scene::Scene;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    // Always normalized
+    pub direction: Vec3,
+}
+
+/**
+ * Closest intersection of a ray with a scene object
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    pub object: usize,
+    pub distance: f32,
+    pub point: Vec3,
+}
+
+impl Ray {
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Self {
+            origin,
+            direction: math::normalize(direction),
+        }
+    }
+
+    /**
+     * World-space ray through normalized device coordinates (`x`, `y`),
+     * starting on the near plane
+     */
+    pub fn from_ndc(inverse_view_proj: &Mat4, x: f32, y: f32) -> Self {
+        let near = math::transform_point(inverse_view_proj, [x, y, -1.0]);
+        let far = math::transform_point(inverse_view_proj, [x, y, 1.0]);
+        Self::new(near, math::sub(far, near))
+    }
+
+    pub fn at(&self, t: f32) -> Vec3 {
+        math::add(self.origin, math::scale(self.direction, t))
+    }
+
+    /**
+     * Ray expressed in the space described by `m` (e.g. an inverse model matrix).
+     * The direction is deliberately not renormalized, so `t` values stay
+     * comparable with the original ray
+     */
+    fn transformed(&self, m: &Mat4) -> Self {
+        let origin = math::transform_point(m, self.origin);
+        let tip = math::transform_point(m, math::add(self.origin, self.direction));
+        Self {
+            origin,
+            direction: math::sub(tip, origin),
+        }
+    }
+
+    pub fn intersect_plane(&self, point: Vec3, normal: Vec3) -> Option<f32> {
+        let denom = math::dot(self.direction, normal);
+        if denom.abs() < 1e-6 {
+            return None;
+        }
+        let t = math::dot(math::sub(point, self.origin), normal) / denom;
+        (t >= 0.0).then_some(t)
+    }
+
+    /**
+     * Slab test; returns the entry distance (0 when starting inside)
+     */
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let mut t_min = 0.0f32;
+        let mut t_max = f32::INFINITY;
+        for axis in 0..3 {
+            let inv = 1.0 / self.direction[axis];
+            let mut t0 = (aabb.min[axis] - self.origin[axis]) * inv;
+            let mut t1 = (aabb.max[axis] - self.origin[axis]) * inv;
+            if inv < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+            if t_max < t_min {
+                return None;
+            }
+        }
+        Some(t_min)
+    }
+
+    pub fn intersect_sphere(&self, sphere: &Sphere) -> Option<f32> {
+        let oc = math::sub(self.origin, sphere.center);
+        let b = math::dot(oc, self.direction);
+        let c = math::dot(oc, oc) - sphere.radius * sphere.radius;
+        let discriminant = b * b - c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let sqrt_d = discriminant.sqrt();
+        // Nearest non-negative root; a ray starting inside hits the far side
+        [-b - sqrt_d, -b + sqrt_d].into_iter().find(|&t| t >= 0.0)
+    }
+
+    /**
+     * Möller–Trumbore ray/triangle intersection (double-sided)
+     */
+    pub fn intersect_triangle(&self, a: Vec3, b: Vec3, c: Vec3) -> Option<f32> {
+        let edge1 = math::sub(b, a);
+        let edge2 = math::sub(c, a);
+        let p = math::cross(self.direction, edge2);
+        let det = math::dot(edge1, p);
+        if det.abs() < 1e-8 {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+        let s = math::sub(self.origin, a);
+        let u = math::dot(s, p) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = math::cross(s, edge1);
+        let v = math::dot(self.direction, q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = math::dot(edge2, q) * inv_det;
+        (t >= 0.0).then_some(t)
+    }
+}
+
+/**
+ * Closest visible object hit by `ray`, by `cast_mesh`
+ */
+pub fn cast_scene(ray: &Ray, scene: &Scene) -> Option<RayHit> {
+    let mut closest: Option<RayHit> = None;
+
+    for (index, object) in scene.objects.iter().enumerate() {
+        if !object.visible {
+            continue;
+        }
+        let Some(mesh) = scene.meshes.get(object.mesh) else {
+            continue;
+        };
+        let Some(distance) = cast_mesh(ray, mesh, &object.transform.matrix()) else {
+            continue;
+        };
+        if closest.is_none_or(|hit| distance < hit.distance) {
+            closest = Some(RayHit {
+                object: index,
+                distance,
+                point: ray.at(distance),
+            });
+        }
+    }
+
+    closest
+}
+
+/**
+ * Distance along `ray` to the nearest triangle of `mesh` placed by `model`: a
+ * world-space bounding sphere test to reject misses cheaply, then the
+ * triangles in object space for the exact hit
+ */
+pub fn cast_mesh(ray: &Ray, mesh: &MeshData, model: &Mat4) -> Option<f32> {
+    let bounds = mesh.bounds();
+    ray.intersect_sphere(&bounds.transformed(model).bounding_sphere())?;
+    let inverse_model = math::invert(model)?;
+
+    // Object-space ray; for an affine transform its `t` equals the world distance
+    let local = ray.transformed(&inverse_model);
+    local.intersect_aabb(&bounds)?;
+
+    let vertex = |i: u16| {
+        let i = i as usize * 3;
+        [
+            mesh.positions[i],
+            mesh.positions[i + 1],
+            mesh.positions[i + 2],
+        ]
+    };
+    mesh.indices
+        .chunks_exact(3)
+        .filter_map(|tri| local.intersect_triangle(vertex(tri[0]), vertex(tri[1]), vertex(tri[2])))
+        .min_by(f32::total_cmp)
+}
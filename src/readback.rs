@@ -0,0 +1,110 @@
+use web_sys::{WebGl2RenderingContext, WebGlBuffer};
+
+use crate::fence::Fence;
+use crate::gl_util::GlResource;
+
+/**
+ * Pixels on their way from the GPU without stalling it. `readPixels` into a
+ * pixel pack buffer only queues the copy; a fence then says when it has
+ * landed, so the CPU can pick the bytes up a frame or two later instead of
+ * waiting for every queued draw to finish
+ */
+pub struct Readback {
+    buffer: WebGlBuffer,
+    fence: Fence,
+    len: usize,
+}
+
+impl Readback {
+    /**
+     * Queue a copy of a `width` x `height` RGBA8 rectangle at (`x`, `y`),
+     * bottom-left origin, from the bound read framebuffer
+     */
+    pub fn start(
+        gl: &WebGl2RenderingContext,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    ) -> Result<Self, String> {
+        let len = (width.max(0) * height.max(0) * 4) as usize;
+        let buffer = gl
+            .create_buffer()
+            .ok_or_else(|| "Unable to create readback buffer".to_string())?;
+        gl.bind_buffer(WebGl2RenderingContext::PIXEL_PACK_BUFFER, Some(&buffer));
+        gl.buffer_data_with_i32(
+            WebGl2RenderingContext::PIXEL_PACK_BUFFER,
+            len as i32,
+            WebGl2RenderingContext::STREAM_READ,
+        );
+        let copied = gl.read_pixels_with_i32(
+            x,
+            y,
+            width,
+            height,
+            WebGl2RenderingContext::RGBA,
+            WebGl2RenderingContext::UNSIGNED_BYTE,
+            0,
+        );
+        gl.bind_buffer(WebGl2RenderingContext::PIXEL_PACK_BUFFER, None);
+        if let Err(err) = copied {
+            buffer.delete(gl);
+            return Err(format!("readPixels failed: {:?}", err));
+        }
+        let fence = match Fence::insert(gl) {
+            Ok(fence) => fence,
+            Err(err) => {
+                buffer.delete(gl);
+                return Err(err);
+            }
+        };
+        Ok(Self { buffer, fence, len })
+    }
+
+    /**
+     * Whether the copy has finished, without waiting for it
+     */
+    pub fn is_ready(&self, gl: &WebGl2RenderingContext) -> Result<bool, String> {
+        self.fence.is_signaled(gl)
+    }
+
+    /**
+     * The pixels, rows bottom to top. Only stalls if called before `is_ready`
+     * says so
+     */
+    pub fn finish(self, gl: &WebGl2RenderingContext) -> Vec<u8> {
+        let mut pixels = vec![0u8; self.len];
+        gl.bind_buffer(
+            WebGl2RenderingContext::PIXEL_PACK_BUFFER,
+            Some(&self.buffer),
+        );
+        gl.get_buffer_sub_data_with_i32_and_u8_array(
+            WebGl2RenderingContext::PIXEL_PACK_BUFFER,
+            0,
+            &mut pixels,
+        );
+        gl.bind_buffer(WebGl2RenderingContext::PIXEL_PACK_BUFFER, None);
+        self.delete(gl);
+        pixels
+    }
+
+    /**
+     * Resolve with the pixels once the copy has finished
+     */
+    pub async fn wait(self, gl: &WebGl2RenderingContext) -> Result<Vec<u8>, String> {
+        match self.fence.wait(gl).await {
+            Ok(()) => Ok(self.finish(gl)),
+            Err(err) => {
+                self.delete(gl);
+                Err(err)
+            }
+        }
+    }
+}
+
+impl GlResource for Readback {
+    fn delete(&self, gl: &WebGl2RenderingContext) {
+        self.fence.delete(gl);
+        self.buffer.delete(gl);
+    }
+}
@@ -0,0 +1,90 @@
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use crate::material::{BlendMode, MaterialProgram};
+use crate::math::Mat4;
+
+/**
+ * Where a draw's geometry comes from
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrawSource {
+    // Index into `Scene::objects`
+    Object(usize),
+    // Index into the renderer's static batches
+    Batch(usize),
+}
+
+/**
+ * One mesh to draw this frame, with everything needed to sort and submit it
+ */
+#[derive(Clone)]
+pub struct DrawCommand {
+    pub source: DrawSource,
+    // Index into `Scene::materials`
+    pub material: usize,
+    pub program: Rc<MaterialProgram>,
+    pub blend: BlendMode,
+    pub model: Mat4,
+    pub mvp: Mat4,
+}
+
+impl DrawCommand {
+    /**
+     * Clip-space w of the model's origin, i.e. its distance along the view direction
+     */
+    pub fn depth(&self) -> f32 {
+        self.mvp[15]
+    }
+}
+
+/**
+ * Draw calls collected over a frame, then sorted to cut down on GL state changes
+ */
+#[derive(Default)]
+pub struct RenderQueue {
+    commands: Vec<DrawCommand>,
+}
+
+impl RenderQueue {
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+
+    pub fn push(&mut self, command: DrawCommand) {
+        self.commands.push(command);
+    }
+
+    /**
+     * Opaque draws grouped by program, then material, front to back within a
+     * group so early depth testing rejects more; blended draws follow, back to
+     * front, since they have to composite in order
+     */
+    pub fn sort(&mut self) {
+        self.commands.sort_by(|a, b| match (a.blend, b.blend) {
+            (BlendMode::Opaque, BlendMode::Opaque) => a
+                .program
+                .id
+                .cmp(&b.program.id)
+                .then(a.material.cmp(&b.material))
+                .then(a.depth().total_cmp(&b.depth())),
+            (BlendMode::Alpha, BlendMode::Alpha) => b.depth().total_cmp(&a.depth()),
+            (BlendMode::Opaque, BlendMode::Alpha) => Ordering::Less,
+            (BlendMode::Alpha, BlendMode::Opaque) => Ordering::Greater,
+        });
+    }
+
+    pub fn commands(&self) -> &[DrawCommand] {
+        &self.commands
+    }
+}
+
+/**
+ * What submitting a queue cost, for comparing against one state change per object
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    pub draws: usize,
+    pub program_switches: usize,
+    pub material_switches: usize,
+}
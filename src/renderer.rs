@@ -0,0 +1,588 @@
+use std::cell::RefCell;
+
+use web_sys::{
+    WebGl2RenderingContext, WebGlBuffer, WebGlProgram, WebGlUniformLocation, WebGlVertexArrayObject,
+};
+
+use crate::batching;
+use crate::gl_errors;
+use crate::gl_util;
+use crate::material::{Material, ShaderLibrary};
+use crate::math::{self, Mat4, Vec3};
+use crate::mesh::{GpuMesh, MeshData, VertexLayout};
+use crate::picking::Picker;
+use crate::readback::Readback;
+use crate::render_queue::{DrawCommand, DrawSource, RenderQueue, RenderStats};
+use crate::scene::{BodyKind, Scene, SceneObject};
+use crate::shaders;
+use crate::texture::Texture;
+use crate::vertex_format::VertexFormat;
+
+// Slightly blue glass over the reflection
+const MIRROR_TINT: [f32; 4] = [0.55, 0.7, 0.9, 0.25];
+const SHADOW_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.5];
+// Lifts shadows off the ground they're projected onto to avoid z-fighting
+const SHADOW_OFFSET: f32 = 0.005;
+
+// How much larger the back-face hull is than the hovered object
+const OUTLINE_SCALE: f32 = 1.08;
+const OUTLINE_COLOR: [f32; 3] = [1.0, 0.75, 0.1];
+// Towards the single directional light, for lit materials and planar shadows
+const LIGHT_DIRECTION: Vec3 = [0.4, 1.0, 0.3];
+// Collision flash pulses per second
+const FLASH_RATE: f64 = 4.0;
+
+/**
+ * Rectangular mirror in a plane of constant z, reflecting towards +Z
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mirror {
+    pub center: Vec3,
+    pub half_size: [f32; 2],
+}
+
+impl Mirror {
+    /**
+     * Reflection through the mirror plane
+     */
+    fn reflection(&self) -> Mat4 {
+        math::multiply(
+            &math::translation([0.0, 0.0, 2.0 * self.center[2]]),
+            &math::scaling([1.0, 1.0, -1.0]),
+        )
+    }
+
+    // Counter-clockwise seen from the front, so back-face culling hides the back
+    fn corners(&self) -> [f32; 12] {
+        let [x, y, z] = self.center;
+        let [w, h] = self.half_size;
+        #[rustfmt::skip]
+        let corners = [
+            x - w, y - h, z,
+            x + w, y - h, z,
+            x + w, y + h, z,
+            x - w, y + h, z,
+        ];
+        corners
+    }
+}
+
+/**
+ * Draws a `Scene` with depth testing, plus picking and hover outlines
+ */
+pub struct SceneRenderer {
+    gl: WebGl2RenderingContext,
+    shaders: ShaderLibrary,
+    // Reused every frame to keep its allocation
+    queue: RefCell<RenderQueue>,
+    // For objects whose material index is out of range
+    default_material: Material,
+    // Bound for textured materials whose texture hasn't loaded yet
+    placeholder: Texture,
+    outline_program: WebGlProgram,
+    outline_mvp_loc: Option<WebGlUniformLocation>,
+    outline_color_loc: Option<WebGlUniformLocation>,
+    flat_program: WebGlProgram,
+    flat_mvp_loc: Option<WebGlUniformLocation>,
+    flat_color_loc: Option<WebGlUniformLocation>,
+    mirror_vao: WebGlVertexArrayObject,
+    mirror_buffer: WebGlBuffer,
+    meshes: Vec<GpuMesh>,
+    // `Scene::statics` merged per material: (material, mesh in world space)
+    batches: Vec<(usize, GpuMesh)>,
+    picker: Picker,
+    // Clear color behind the scene
+    background: Vec3,
+    // Upload meshes with a solid color per face (`MeshData::flat_shaded`)
+    flat_faces: bool,
+    width: i32,
+    height: i32,
+    // What `render` draws into: the canvas size, or less when rendering scaled
+    render_size: (i32, i32),
+}
+
+impl SceneRenderer {
+    pub fn new(
+        gl: WebGl2RenderingContext,
+        scene: &Scene,
+        width: i32,
+        height: i32,
+    ) -> Result<Self, String> {
+        let _span = tracing::info_span!(target: "renderer", "SceneRenderer::new").entered();
+        let placeholder =
+            Texture::placeholder(&gl).ok_or_else(|| "Unable to create placeholder".to_string())?;
+        let shaders = ShaderLibrary::new(&gl)?;
+        let outline_program = gl_util::create_program(
+            &gl,
+            shaders::RENDERER_OUTLINE_VERT,
+            shaders::RENDERER_OUTLINE_FRAG,
+        )?;
+        let flat_program = gl_util::create_program(
+            &gl,
+            shaders::RENDERER_FLAT_VERT,
+            shaders::RENDERER_FLAT_FRAG,
+        )?;
+        let picker = Picker::new(&gl, width, height)?;
+
+        let mirror_vao = gl
+            .create_vertex_array()
+            .ok_or_else(|| "Unable to create mirror VAO".to_string())?;
+        let mirror_buffer = gl
+            .create_buffer()
+            .ok_or_else(|| "Unable to create mirror buffer".to_string())?;
+        gl.bind_vertex_array(Some(&mirror_vao));
+        gl.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&mirror_buffer));
+        gl.enable_vertex_attrib_array(0);
+        gl.vertex_attrib_pointer_with_i32(0, 3, WebGl2RenderingContext::FLOAT, false, 0, 0);
+        gl.bind_vertex_array(None);
+
+        let mut renderer = Self {
+            outline_mvp_loc: gl.get_uniform_location(&outline_program, "modelViewProjection"),
+            outline_color_loc: gl.get_uniform_location(&outline_program, "outlineColor"),
+            flat_mvp_loc: gl.get_uniform_location(&flat_program, "modelViewProjection"),
+            flat_color_loc: gl.get_uniform_location(&flat_program, "color"),
+            gl,
+            shaders,
+            queue: RefCell::default(),
+            default_material: Material::default(),
+            placeholder,
+            outline_program,
+            flat_program,
+            mirror_vao,
+            mirror_buffer,
+            meshes: Vec::new(),
+            batches: Vec::new(),
+            picker,
+            background: [0.1; 3],
+            flat_faces: false,
+            width,
+            height,
+            render_size: (width, height),
+        };
+        renderer.upload_new_meshes(scene)?;
+        renderer.rebuild_static_batches(scene, VertexFormat::default())?;
+        Ok(renderer)
+    }
+
+    /**
+     * Upload meshes appended to `Scene::meshes` since the last call
+     */
+    pub fn upload_new_meshes(&mut self, scene: &Scene) -> Result<(), String> {
+        // Uploaded in the same order as `Scene::meshes`, so `SceneObject::mesh` indexes both
+        for data in &scene.meshes[self.meshes.len()..] {
+            let mesh = if self.flat_faces {
+                GpuMesh::upload(&self.gl, &data.flat_shaded()?)
+            } else {
+                GpuMesh::upload(&self.gl, data)
+            }
+            .ok_or_else(|| "Unable to upload scene mesh".to_string())?;
+            self.meshes.push(mesh);
+        }
+        Ok(())
+    }
+
+    /**
+     * The material shaders, to register more or swap one's source at runtime
+     */
+    pub fn shaders(&self) -> &ShaderLibrary {
+        &self.shaders
+    }
+
+    pub fn set_background(&mut self, color: Vec3) {
+        self.background = color;
+    }
+
+    /**
+     * Give every face one solid color instead of blending its corners' colors,
+     * for a low-poly look or to see how a mesh is split into faces. Applies
+     * to meshes uploaded from now on, so follow it with `reload_meshes` and
+     * `rebuild_static_batches`
+     */
+    pub fn set_flat_faces(&mut self, flat: bool) {
+        self.flat_faces = flat;
+    }
+
+    /**
+     * Upload every mesh again, after `Scene::meshes` was replaced as a whole
+     */
+    pub fn reload_meshes(&mut self, scene: &Scene) -> Result<(), String> {
+        self.meshes.clear();
+        self.upload_new_meshes(scene)
+    }
+
+    /**
+     * Re-merge `Scene::statics`, storing their vertices in `format`; call after
+     * changing them
+     */
+    pub fn rebuild_static_batches(
+        &mut self,
+        scene: &Scene,
+        format: VertexFormat,
+    ) -> Result<(), String> {
+        self.batches.clear();
+        let flat_meshes;
+        let meshes = if self.flat_faces {
+            flat_meshes = scene
+                .meshes
+                .iter()
+                .map(MeshData::flat_shaded)
+                .collect::<Result<Vec<_>, String>>()?;
+            &flat_meshes
+        } else {
+            &scene.meshes
+        };
+        for batch in batching::build_static_batches(meshes, &scene.statics) {
+            // Batches are the big meshes here, where interleaving pays off most
+            let mesh =
+                GpuMesh::upload_with(&self.gl, &batch.mesh, VertexLayout::Interleaved, format)
+                    .ok_or_else(|| "Unable to upload static batch".to_string())?;
+            self.batches.push((batch.material, mesh));
+        }
+        Ok(())
+    }
+
+    /**
+     * Vertex memory used by the static batches
+     */
+    pub fn static_batch_bytes(&self) -> usize {
+        self.batches
+            .iter()
+            .map(|(_, mesh)| mesh.vertex_bytes())
+            .sum()
+    }
+
+    /**
+     * Draw at `width` x `height` from now on, in the bottom-left corner of
+     * whatever framebuffer is bound. Picking still works in canvas pixels
+     */
+    pub fn set_render_size(&mut self, width: i32, height: i32) {
+        self.render_size = (width, height);
+    }
+
+    pub fn aspect(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+
+    /**
+     * Start finding the scene object under canvas pixel (`x`, `y`); see
+     * `Picker::start_pick`
+     */
+    pub fn start_pick(
+        &self,
+        scene: &Scene,
+        view_proj: &Mat4,
+        x: i32,
+        y: i32,
+    ) -> Option<Result<Readback, String>> {
+        let objects = scene.objects.iter().enumerate().filter_map(|(i, object)| {
+            if !object.visible {
+                return None;
+            }
+            let mesh = self.meshes.get(object.mesh)?;
+            let mvp = math::multiply(view_proj, &object.transform.matrix());
+            Some((i, mesh, mvp))
+        });
+        self.picker.start_pick(&self.gl, objects, x, y)
+    }
+
+    /**
+     * Draw the scene; objects listed in `flashing` pulse red, timed by `time` (seconds).
+     * Returns what the object draws cost, not counting outlines
+     */
+    pub fn render(
+        &self,
+        scene: &Scene,
+        view_proj: &Mat4,
+        hovered: Option<usize>,
+        flashing: &[usize],
+        time: f64,
+    ) -> RenderStats {
+        let gl = &self.gl;
+        let (width, height) = self.render_size;
+        gl.viewport(0, 0, width, height);
+        gl.enable(WebGl2RenderingContext::DEPTH_TEST);
+        gl.enable(WebGl2RenderingContext::CULL_FACE);
+        gl.cull_face(WebGl2RenderingContext::BACK);
+        let [r, g, b] = self.background;
+        gl.clear_color(r, g, b, 1.0);
+        gl.clear(
+            WebGl2RenderingContext::COLOR_BUFFER_BIT | WebGl2RenderingContext::DEPTH_BUFFER_BIT,
+        );
+
+        let pulse = (0.5 + 0.5 * (time * FLASH_RATE * std::f64::consts::TAU).sin()) as f32;
+
+        let stats = self.draw_objects(scene, view_proj, &math::identity(), |index| {
+            if flashing.contains(&index) {
+                pulse * 0.8
+            } else {
+                0.0
+            }
+        });
+
+        for (index, object) in scene.objects.iter().enumerate() {
+            if !object.visible {
+                continue;
+            }
+            let outline = scene
+                .materials
+                .get(object.material)
+                .and_then(|material| material.outline);
+            // The hover outline takes its place
+            if let (Some(outline), false) = (outline, hovered == Some(index)) {
+                self.draw_hull(object, view_proj, 1.0 + outline.width, &outline.color);
+            }
+        }
+        if let Some(object) = hovered.and_then(|i| scene.objects.get(i)) {
+            self.draw_hull(object, view_proj, OUTLINE_SCALE, &OUTLINE_COLOR);
+        }
+
+        gl_errors::check(gl, "renderer: render");
+        stats
+    }
+
+    /**
+     * Inverted hull: an enlarged copy of `object` in `color`, showing only
+     * its back faces, peeks out around the silhouette
+     */
+    fn draw_hull(&self, object: &SceneObject, view_proj: &Mat4, scale: f32, color: &[f32; 3]) {
+        let gl = &self.gl;
+        let Some(mesh) = self.meshes.get(object.mesh) else {
+            return;
+        };
+        let model = math::multiply(&object.transform.matrix(), &math::scaling([scale; 3]));
+        let mvp = math::multiply(view_proj, &model);
+        gl.use_program(Some(&self.outline_program));
+        gl.uniform_matrix4fv_with_f32_array(self.outline_mvp_loc.as_ref(), false, &mvp);
+        gl.uniform3fv_with_f32_array(self.outline_color_loc.as_ref(), color);
+        gl.cull_face(WebGl2RenderingContext::FRONT);
+        mesh.draw(gl);
+        gl.cull_face(WebGl2RenderingContext::BACK);
+    }
+
+    /**
+     * Flatten every movable object and static prop onto the plane y = `ground` along the
+     * scene's directional light and darken the ground there. Fixed bodies
+     * such as the ground itself only receive shadows. Needs a stencil buffer
+     */
+    pub fn render_shadows(&self, scene: &Scene, view_proj: &Mat4, ground: f32) {
+        let gl = &self.gl;
+        let shadow = math::planar_shadow(LIGHT_DIRECTION, ground + SHADOW_OFFSET);
+        let shadow_view_proj = math::multiply(view_proj, &shadow);
+
+        gl.use_program(Some(&self.flat_program));
+        gl.uniform4fv_with_f32_array(self.flat_color_loc.as_ref(), &SHADOW_COLOR);
+        // A flattened mesh covers most pixels twice (top and bottom faces), and
+        // shadows overlap each other; only the first fragment per pixel may blend
+        gl.enable(WebGl2RenderingContext::STENCIL_TEST);
+        gl.clear_stencil(0);
+        gl.clear(WebGl2RenderingContext::STENCIL_BUFFER_BIT);
+        gl.stencil_func(WebGl2RenderingContext::EQUAL, 0, 0xff);
+        gl.stencil_op(
+            WebGl2RenderingContext::KEEP,
+            WebGl2RenderingContext::KEEP,
+            WebGl2RenderingContext::INCR,
+        );
+        gl.enable(WebGl2RenderingContext::BLEND);
+        gl.blend_func(
+            WebGl2RenderingContext::SRC_ALPHA,
+            WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+        );
+        gl.depth_mask(false);
+        // Flattening turns front faces into either winding
+        gl.disable(WebGl2RenderingContext::CULL_FACE);
+        for object in &scene.objects {
+            if object.body == Some(BodyKind::Fixed) || !object.visible {
+                continue;
+            }
+            let Some(mesh) = self.meshes.get(object.mesh) else {
+                continue;
+            };
+            let mvp = math::multiply(&shadow_view_proj, &object.transform.matrix());
+            gl.uniform_matrix4fv_with_f32_array(self.flat_mvp_loc.as_ref(), false, &mvp);
+            mesh.draw(gl);
+        }
+        gl.uniform_matrix4fv_with_f32_array(self.flat_mvp_loc.as_ref(), false, &shadow_view_proj);
+        for (_, mesh) in &self.batches {
+            mesh.draw(gl);
+        }
+        gl.enable(WebGl2RenderingContext::CULL_FACE);
+        gl.depth_mask(true);
+        gl.disable(WebGl2RenderingContext::BLEND);
+        gl.disable(WebGl2RenderingContext::STENCIL_TEST);
+    }
+
+    /**
+     * Draw the scene reflected in `mirror` over the already rendered frame.
+     * Needs a context created with a stencil buffer
+     */
+    pub fn render_mirror(&self, scene: &Scene, view_proj: &Mat4, mirror: &Mirror) {
+        let gl = &self.gl;
+        gl.bind_buffer(
+            WebGl2RenderingContext::ARRAY_BUFFER,
+            Some(&self.mirror_buffer),
+        );
+        unsafe {
+            let array = js_sys::Float32Array::view(&mirror.corners());
+            gl.buffer_data_with_array_buffer_view(
+                WebGl2RenderingContext::ARRAY_BUFFER,
+                &array,
+                WebGl2RenderingContext::DYNAMIC_DRAW,
+            );
+        }
+
+        gl.enable(WebGl2RenderingContext::STENCIL_TEST);
+        gl.clear_stencil(0);
+        gl.clear(WebGl2RenderingContext::STENCIL_BUFFER_BIT);
+
+        // 1. Mark the visible part of the mirror with 1, leaving color and depth alone
+        gl.use_program(Some(&self.flat_program));
+        gl.uniform_matrix4fv_with_f32_array(self.flat_mvp_loc.as_ref(), false, view_proj);
+        gl.bind_vertex_array(Some(&self.mirror_vao));
+        gl.stencil_func(WebGl2RenderingContext::ALWAYS, 1, 0xff);
+        gl.stencil_op(
+            WebGl2RenderingContext::KEEP,
+            WebGl2RenderingContext::KEEP,
+            WebGl2RenderingContext::REPLACE,
+        );
+        gl.color_mask(false, false, false, false);
+        gl.depth_mask(false);
+        gl.draw_arrays(WebGl2RenderingContext::TRIANGLE_FAN, 0, 4);
+
+        // 2. Push the marked depth to the far plane so the reflection, which lies
+        // behind the mirror, isn't hidden by the mirror's own depth
+        gl.stencil_func(WebGl2RenderingContext::EQUAL, 1, 0xff);
+        gl.stencil_op(
+            WebGl2RenderingContext::KEEP,
+            WebGl2RenderingContext::KEEP,
+            WebGl2RenderingContext::KEEP,
+        );
+        gl.depth_mask(true);
+        gl.depth_func(WebGl2RenderingContext::ALWAYS);
+        gl.depth_range(1.0, 1.0);
+        gl.draw_arrays(WebGl2RenderingContext::TRIANGLE_FAN, 0, 4);
+        gl.depth_range(0.0, 1.0);
+        gl.depth_func(WebGl2RenderingContext::LESS);
+        gl.color_mask(true, true, true, true);
+        gl.bind_vertex_array(None);
+
+        // 3. The reflected scene, clipped to the mirror by the stencil test. Mirroring
+        // flips the winding, so the culled side flips too
+        gl.cull_face(WebGl2RenderingContext::FRONT);
+        self.draw_objects(scene, view_proj, &mirror.reflection(), |_| 0.0);
+        gl.cull_face(WebGl2RenderingContext::BACK);
+
+        // 4. Tint the glass, restoring the mirror's depth for anything drawn later
+        gl.use_program(Some(&self.flat_program));
+        gl.uniform_matrix4fv_with_f32_array(self.flat_mvp_loc.as_ref(), false, view_proj);
+        gl.uniform4fv_with_f32_array(self.flat_color_loc.as_ref(), &MIRROR_TINT);
+        gl.enable(WebGl2RenderingContext::BLEND);
+        gl.blend_func(
+            WebGl2RenderingContext::SRC_ALPHA,
+            WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+        );
+        gl.bind_vertex_array(Some(&self.mirror_vao));
+        gl.draw_arrays(WebGl2RenderingContext::TRIANGLE_FAN, 0, 4);
+        gl.bind_vertex_array(None);
+        gl.disable(WebGl2RenderingContext::BLEND);
+        gl.disable(WebGl2RenderingContext::STENCIL_TEST);
+    }
+
+    /**
+     * Queue every object and static batch with its material, sort the queue by
+     * GL state and submit it. `world` is applied on top of each object's own
+     * transform; `flash` gives each object's collision flash amount
+     */
+    fn draw_objects(
+        &self,
+        scene: &Scene,
+        view_proj: &Mat4,
+        world: &Mat4,
+        flash: impl Fn(usize) -> f32,
+    ) -> RenderStats {
+        let gl = &self.gl;
+        let mut queue = self.queue.borrow_mut();
+        queue.clear();
+        let objects = scene
+            .objects
+            .iter()
+            .enumerate()
+            .filter(|(_, object)| object.visible && object.mesh < self.meshes.len())
+            .map(|(index, object)| {
+                let model = math::multiply(world, &object.transform.matrix());
+                (DrawSource::Object(index), object.material, model)
+            });
+        // Batches are already in world space
+        let batches = self
+            .batches
+            .iter()
+            .enumerate()
+            .map(|(index, (material, _))| (DrawSource::Batch(index), *material, *world));
+        for (source, material_index, model) in objects.chain(batches) {
+            let material = scene
+                .materials
+                .get(material_index)
+                .unwrap_or(&self.default_material);
+            let program = match self.shaders.get(gl, &material.shader, material.features) {
+                Ok(program) => program,
+                Err(err) => {
+                    gl_errors::report("renderer: material program", err);
+                    continue;
+                }
+            };
+            queue.push(DrawCommand {
+                source,
+                material: material_index,
+                program,
+                blend: material.blend,
+                mvp: math::multiply(view_proj, &model),
+                model,
+            });
+        }
+        queue.sort();
+
+        let light_direction = math::normalize(LIGHT_DIRECTION);
+        let mut stats = RenderStats::default();
+        let mut current_program = None::<usize>;
+        let mut current_material = None::<usize>;
+        for command in queue.commands() {
+            let program = &command.program;
+            if current_program != Some(program.id) {
+                gl.use_program(Some(&program.program));
+                gl.uniform3fv_with_f32_array(
+                    program.light_direction_loc.as_ref(),
+                    &light_direction,
+                );
+                current_program = Some(program.id);
+                // Uniforms belong to the program, so the material has to be reapplied
+                current_material = None;
+                stats.program_switches += 1;
+            }
+            if current_material != Some(command.material) {
+                let material = scene
+                    .materials
+                    .get(command.material)
+                    .unwrap_or(&self.default_material);
+                material.apply(gl, program, &self.placeholder);
+                current_material = Some(command.material);
+                stats.material_switches += 1;
+            }
+            gl.uniform_matrix4fv_with_f32_array(program.mvp_loc.as_ref(), false, &command.mvp);
+            gl.uniform_matrix4fv_with_f32_array(program.model_loc.as_ref(), false, &command.model);
+            let (mesh, flash, tint) = match command.source {
+                DrawSource::Object(index) => {
+                    let object = &scene.objects[index];
+                    (&self.meshes[object.mesh], flash(index), object.tint)
+                }
+                DrawSource::Batch(index) => (&self.batches[index].1, 0.0, [1.0; 4]),
+            };
+            gl.uniform1f(program.flash_loc.as_ref(), flash);
+            gl.uniform4fv_with_f32_array(program.tint_loc.as_ref(), &tint);
+            mesh.draw(gl);
+            stats.draws += 1;
+        }
+        gl.disable(WebGl2RenderingContext::BLEND);
+        gl.depth_mask(true);
+        gl.enable(WebGl2RenderingContext::CULL_FACE);
+        stats
+    }
+}
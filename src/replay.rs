@@ -0,0 +1,164 @@
+/**
+ * One user input, as far as the simulation is concerned. Pointer positions
+ * are canvas pixels
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputEvent {
+    PointerDown {
+        x: f64,
+        y: f64,
+    },
+    PointerMove {
+        x: f64,
+        y: f64,
+    },
+    PointerUp,
+    PointerLeave,
+    Wheel {
+        delta: f64,
+    },
+    // Keyboard orbiting, as a drag by this many pixels
+    Orbit {
+        dx: f64,
+        dy: f64,
+    },
+    // Keyboard move of an object along the ground, relative to the camera
+    Nudge {
+        object: usize,
+        right: f32,
+        forward: f32,
+    },
+    // Object picked up with the keyboard, held out of the simulation like a drag
+    Hold(Option<usize>),
+    // Object list edits
+    Visible {
+        object: usize,
+        visible: bool,
+    },
+    Tint {
+        object: usize,
+        rgb: [f32; 3],
+    },
+    // UI settings that change what the simulation does
+    TimeScale(f64),
+}
+
+/**
+ * An input stamped with the number of fixed simulation steps that had run
+ * when it was applied
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RecordedEvent {
+    pub step: u64,
+    pub event: InputEvent,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReplayStatus {
+    #[default]
+    Live,
+    Recording {
+        events: usize,
+    },
+    Replaying {
+        applied: usize,
+        total: usize,
+    },
+}
+
+/**
+ * Sits between the DOM event handlers and the render loop. Live input is
+ * queued and handed to the loop once per frame; while recording, each event is
+ * also stamped with the simulation step it was applied before. Replaying
+ * ignores live input and feeds the recording back at exactly the same steps,
+ * so a session started from the same state plays out identically
+ */
+#[derive(Clone, Debug, Default)]
+pub struct InputRecorder {
+    pending: Vec<InputEvent>,
+    recording: Vec<RecordedEvent>,
+    status: ReplayStatus,
+    // Set when recording or replay starts; the loop resets the scene first
+    restart: bool,
+}
+
+impl InputRecorder {
+    /**
+     * Queue a live input for the next frame; dropped during replay
+     */
+    pub fn push(&mut self, event: InputEvent) {
+        if !matches!(self.status, ReplayStatus::Replaying { .. }) {
+            self.pending.push(event);
+        }
+    }
+
+    pub fn status(&self) -> ReplayStatus {
+        self.status
+    }
+
+    pub fn has_recording(&self) -> bool {
+        !self.recording.is_empty()
+    }
+
+    pub fn start_recording(&mut self) {
+        self.recording.clear();
+        self.pending.clear();
+        self.status = ReplayStatus::Recording { events: 0 };
+        self.restart = true;
+    }
+
+    pub fn start_replay(&mut self) {
+        self.pending.clear();
+        self.status = ReplayStatus::Replaying {
+            applied: 0,
+            total: self.recording.len(),
+        };
+        self.restart = true;
+    }
+
+    /**
+     * Back to live input, keeping the recording for replay
+     */
+    pub fn stop(&mut self) {
+        self.status = ReplayStatus::Live;
+    }
+
+    /**
+     * Whether the scene has to be reset to its initial state before this
+     * frame; true once after recording or replay starts
+     */
+    pub fn take_restart(&mut self) -> bool {
+        std::mem::take(&mut self.restart)
+    }
+
+    /**
+     * Inputs to apply before simulation step `step` runs. Call at the start
+     * of each frame and again after every step
+     */
+    pub fn events_for_step(&mut self, step: u64) -> Vec<InputEvent> {
+        match &mut self.status {
+            ReplayStatus::Live => std::mem::take(&mut self.pending),
+            ReplayStatus::Recording { events } => {
+                let pending = std::mem::take(&mut self.pending);
+                self.recording
+                    .extend(pending.iter().map(|&event| RecordedEvent { step, event }));
+                *events = self.recording.len();
+                pending
+            }
+            ReplayStatus::Replaying { applied, total } => {
+                let start = *applied;
+                while *applied < *total && self.recording[*applied].step <= step {
+                    *applied += 1;
+                }
+                let due = self.recording[start..*applied]
+                    .iter()
+                    .map(|recorded| recorded.event)
+                    .collect();
+                if *applied == *total {
+                    self.status = ReplayStatus::Live;
+                }
+                due
+            }
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use crate::material::{BlendMode, Material, Outline, ShaderFeatures};
+use crate::math::{self, Mat4, Vec3};
+use crate::mesh::MeshData;
+
+/**
+ * Translation, Euler rotation (radians, applied X then Y then Z) and scale
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transform {
+    pub translation: Vec3,
+    pub rotation: Vec3,
+    pub scale: Vec3,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            translation: [0.0; 3],
+            rotation: [0.0; 3],
+            scale: [1.0; 3],
+        }
+    }
+}
+
+impl Transform {
+    pub fn matrix(&self) -> Mat4 {
+        math::multiply(
+            &math::translation(self.translation),
+            &math::multiply(&self.rotation_matrix(), &math::scaling(self.scale)),
+        )
+    }
+
+    /**
+     * Just the rotation; its columns are the object's local axes in world space
+     */
+    pub fn rotation_matrix(&self) -> Mat4 {
+        math::multiply(
+            &math::rotation_matrix_z(self.rotation[2]),
+            &math::multiply(
+                &math::rotation_matrix_y(self.rotation[1]),
+                &math::rotation_matrix_x(self.rotation[0]),
+            ),
+        )
+    }
+}
+
+/**
+ * Rigid-body role of an object; fixed bodies can't be moved by the user
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BodyKind {
+    Dynamic,
+    Fixed,
+}
+
+#[derive(Clone, Debug)]
+pub struct SceneObject {
+    pub name: String,
+    // Index into `Scene::meshes`
+    pub mesh: usize,
+    // Index into `Scene::materials`
+    pub material: usize,
+    pub transform: Transform,
+    pub body: Option<BodyKind>,
+    // Multiplied into the material's color, so one object can change color
+    // without a material of its own
+    pub tint: [f32; 4],
+    // Hidden objects aren't drawn, picked or shadowed, but still simulated
+    pub visible: bool,
+}
+
+/**
+ * Decoration that never moves; the renderer merges these into static batches
+ */
+#[derive(Clone, Debug)]
+pub struct StaticProp {
+    // Index into `Scene::meshes`
+    pub mesh: usize,
+    // Index into `Scene::materials`
+    pub material: usize,
+    pub transform: Transform,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Scene {
+    pub meshes: Vec<MeshData>,
+    pub materials: Vec<Material>,
+    pub objects: Vec<SceneObject>,
+    pub statics: Vec<StaticProp>,
+}
+
+impl Scene {
+    /**
+     * Index of the first material called `name`
+     */
+    pub fn material_index(&self, name: &str) -> Option<usize> {
+        self.materials
+            .iter()
+            .position(|material| material.name == name)
+    }
+
+    /**
+     * A handful of cubes spread around the origin, each with its own material
+     */
+    pub fn demo() -> Self {
+        let placements: [(Vec3, Vec3, Vec3); 6] = [
+            ([0.0, 0.0, 0.0], [0.0, 0.4, 0.0], [0.8, 0.8, 0.8]),
+            ([-1.4, 0.0, -0.6], [0.3, 0.0, 0.2], [0.6, 0.6, 0.6]),
+            ([1.3, 0.2, -0.4], [0.0, -0.5, 0.0], [0.5, 1.0, 0.5]),
+            ([-0.6, -0.1, 1.2], [0.0, 0.8, 0.0], [0.5, 0.5, 0.5]),
+            ([0.9, -0.2, 1.1], [0.2, 0.2, 0.2], [0.4, 0.4, 0.4]),
+            ([-1.5, 0.1, 0.6], [0.4, 0.7, 0.0], [0.55, 0.55, 0.55]),
+        ];
+
+        #[allow(unused_mut)]
+        let mut objects: Vec<SceneObject> = placements
+            .iter()
+            .enumerate()
+            .map(|(i, &(translation, rotation, scale))| SceneObject {
+                name: format!("Cube {}", i + 1),
+                mesh: 0,
+                material: i,
+                transform: Transform {
+                    translation,
+                    rotation,
+                    scale,
+                },
+                body: Some(BodyKind::Dynamic),
+                tint: [1.0; 4],
+                visible: true,
+            })
+            .collect();
+
+        // Something for the rigid bodies to land on
+        #[cfg(feature = "physics")]
+        objects.push(SceneObject {
+            name: "Ground".to_string(),
+            mesh: 0,
+            material: 1,
+            transform: Transform {
+                translation: [0.0, -1.05, 0.0],
+                rotation: [0.0; 3],
+                scale: [6.0, 0.1, 6.0],
+            },
+            body: Some(BodyKind::Fixed),
+            tint: [1.0; 4],
+            visible: true,
+        });
+
+        let lit = ShaderFeatures {
+            lighting: true,
+            ..Default::default()
+        };
+        let materials = vec![
+            Material::default(),
+            Material {
+                name: "Matte".to_string(),
+                features: lit,
+                color: [0.85, 0.85, 0.8, 1.0],
+                ..Default::default()
+            },
+            Material {
+                name: "Checker".to_string(),
+                features: ShaderFeatures {
+                    texture: true,
+                    ..lit
+                },
+                ..Default::default()
+            },
+            Material {
+                name: "Glass".to_string(),
+                features: lit,
+                color: [0.5, 0.8, 1.0, 0.45],
+                blend: BlendMode::Alpha,
+                double_sided: true,
+                ..Default::default()
+            },
+            Material {
+                name: "Shaded colors".to_string(),
+                features: ShaderFeatures {
+                    vertex_colors: true,
+                    ..lit
+                },
+                ..Default::default()
+            },
+            Material {
+                name: "Toon".to_string(),
+                features: ShaderFeatures { toon: true, ..lit },
+                color: [1.0, 0.55, 0.35, 1.0],
+                outline: Some(Outline::default()),
+                ..Default::default()
+            },
+        ];
+
+        Self {
+            meshes: vec![MeshData::cube()],
+            materials,
+            objects,
+            statics: Vec::new(),
+        }
+    }
+
+    /**
+     * `count` pebbles scattered in a ring on the ground (top at `ground`),
+     * cycling through the first few materials
+     */
+    pub fn pebbles(&self, count: usize, ground: f32) -> Vec<StaticProp> {
+        let materials = self.materials.len().clamp(1, 3);
+        (0..count)
+            .map(|i| {
+                let seed = i as f32;
+                let angle = hash(seed) * std::f32::consts::TAU;
+                let radius = 2.2 + hash(seed * 1.7) * 2.5;
+                let size = 0.05 + hash(seed * 3.3) * 0.1;
+                StaticProp {
+                    mesh: 0,
+                    material: i % materials,
+                    transform: Transform {
+                        translation: [
+                            angle.cos() * radius,
+                            ground + size * 0.5,
+                            angle.sin() * radius,
+                        ],
+                        rotation: [0.0, hash(seed * 5.1) * 3.0, 0.0],
+                        scale: [size; 3],
+                    },
+                }
+            })
+            .collect()
+    }
+}
+
+// Cheap deterministic 0..1 noise for scattering props
+fn hash(seed: f32) -> f32 {
+    ((seed * 12.9898).sin() * 43758.547).fract().abs()
+}
@@ -0,0 +1,306 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use js_sys::{ArrayBuffer, Uint8Array};
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{BinaryType, CloseEvent, Event, MessageEvent, WebSocket};
+
+use crate::json::{self, Value};
+use crate::math::Vec3;
+use crate::scene::Scene;
+
+/**
+ * Which object an update is for: its index in `Scene::objects` or its name
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum ObjectRef {
+    Index(usize),
+    Name(String),
+}
+
+/**
+ * New values for some of one object's properties; fields left `None` keep
+ * their current value
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct SceneUpdate {
+    pub object: ObjectRef,
+    pub translation: Option<Vec3>,
+    pub rotation: Option<Vec3>,
+    pub scale: Option<Vec3>,
+    // Replaces the color of the object's material
+    pub color: Option<[f32; 4]>,
+}
+
+impl SceneUpdate {
+    /**
+     * Write the update into `scene`; false when the object doesn't exist
+     */
+    pub fn apply(&self, scene: &mut Scene) -> bool {
+        let index = match &self.object {
+            ObjectRef::Index(index) => Some(*index).filter(|&index| index < scene.objects.len()),
+            ObjectRef::Name(name) => scene.objects.iter().position(|object| object.name == *name),
+        };
+        let Some(index) = index else {
+            return false;
+        };
+        let object = &mut scene.objects[index];
+        if let Some(translation) = self.translation {
+            object.transform.translation = translation;
+        }
+        if let Some(rotation) = self.rotation {
+            object.transform.rotation = rotation;
+        }
+        if let Some(scale) = self.scale {
+            object.transform.scale = scale;
+        }
+        if let Some(color) = self.color {
+            let material = object.material;
+            scene.materials[material].color = color;
+        }
+        true
+    }
+
+    /**
+     * The update as a JSON message, the format `parse_json` reads
+     */
+    pub fn to_json(&self) -> String {
+        let object = match &self.object {
+            ObjectRef::Index(index) => index.to_string(),
+            ObjectRef::Name(name) => json_string(name),
+        };
+        let mut members = vec![format!("\"object\":{}", object)];
+        let fields: [(&str, Option<&[f32]>); 4] = [
+            ("translation", self.translation.as_ref().map(|v| &v[..])),
+            ("rotation", self.rotation.as_ref().map(|v| &v[..])),
+            ("scale", self.scale.as_ref().map(|v| &v[..])),
+            ("color", self.color.as_ref().map(|v| &v[..])),
+        ];
+        for (name, values) in fields {
+            if let Some(values) = values {
+                let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+                members.push(format!("\"{}\":[{}]", name, values.join(",")));
+            }
+        }
+        format!("{{{}}}", members.join(","))
+    }
+}
+
+/**
+ * Updates from a JSON text message: one update object or an array of them,
+ * e.g. `{"object": "Red cube", "translation": [0, 1, 0], "color": [1, 0, 0, 1]}`.
+ * `object` is a name or an index
+ */
+pub fn parse_json(text: &str) -> Result<Vec<SceneUpdate>, String> {
+    match json::parse(text)? {
+        Value::Array(items) => items.iter().map(update_from_json).collect(),
+        value => Ok(vec![update_from_json(&value)?]),
+    }
+}
+
+fn update_from_json(value: &Value) -> Result<SceneUpdate, String> {
+    let object = match value.get("object") {
+        Some(Value::String(name)) => ObjectRef::Name(name.clone()),
+        Some(index) => ObjectRef::Index(
+            index
+                .as_usize()
+                .ok_or_else(|| "\"object\" must be a name or an index".to_string())?,
+        ),
+        None => return Err("Update without an \"object\"".to_string()),
+    };
+    Ok(SceneUpdate {
+        object,
+        translation: json_floats(value, "translation")?,
+        rotation: json_floats(value, "rotation")?,
+        scale: json_floats(value, "scale")?,
+        color: json_floats(value, "color")?,
+    })
+}
+
+fn json_floats<const N: usize>(value: &Value, key: &str) -> Result<Option<[f32; N]>, String> {
+    let Some(field) = value.get(key) else {
+        return Ok(None);
+    };
+    let invalid = || format!("\"{}\" must be an array of {} numbers", key, N);
+    let items = field
+        .as_array()
+        .filter(|items| items.len() == N)
+        .ok_or_else(invalid)?;
+    let mut out = [0.0; N];
+    for (out, item) in out.iter_mut().zip(items) {
+        *out = item.as_f64().ok_or_else(invalid)? as f32;
+    }
+    Ok(Some(out))
+}
+
+fn json_string(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// Field bits in a binary update record
+const FIELD_TRANSLATION: u8 = 1;
+const FIELD_ROTATION: u8 = 2;
+const FIELD_SCALE: u8 = 4;
+const FIELD_COLOR: u8 = 8;
+
+/**
+ * Updates from a binary message, a compact format for high-rate streams. Each
+ * record is a little-endian u16 object index and a u8 field mask, followed by
+ * f32 values for the fields present, in order: translation (bit 0, 3 floats),
+ * rotation (bit 1, 3), scale (bit 2, 3) and RGBA color (bit 3, 4)
+ */
+pub fn parse_binary(bytes: &[u8]) -> Result<Vec<SceneUpdate>, String> {
+    let mut reader = Reader { bytes, position: 0 };
+    let mut updates = Vec::new();
+    while reader.position < bytes.len() {
+        let index = u16::from_le_bytes(reader.take()?);
+        let mask = reader.take::<1>()?[0];
+        let mut field = |bit: u8| -> Result<Option<[f32; 4]>, String> {
+            if mask & bit == 0 {
+                return Ok(None);
+            }
+            let count = if bit == FIELD_COLOR { 4 } else { 3 };
+            let mut values = [0.0; 4];
+            for value in &mut values[..count] {
+                *value = f32::from_le_bytes(reader.take()?);
+            }
+            Ok(Some(values))
+        };
+        let vec3 = |values: [f32; 4]| [values[0], values[1], values[2]];
+        updates.push(SceneUpdate {
+            object: ObjectRef::Index(index as usize),
+            translation: field(FIELD_TRANSLATION)?.map(vec3),
+            rotation: field(FIELD_ROTATION)?.map(vec3),
+            scale: field(FIELD_SCALE)?.map(vec3),
+            color: field(FIELD_COLOR)?,
+        });
+    }
+    Ok(updates)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + N)
+            .ok_or_else(|| format!("Update message truncated at byte {}", self.position))?;
+        self.position += N;
+        Ok(bytes.try_into().unwrap())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SyncStatus {
+    Connecting,
+    Open,
+    Closed(String),
+}
+
+/**
+ * WebSocket connection that collects scene updates as they arrive. The render
+ * loop drains them once per frame with `take_updates`; local edits go back
+ * out with `send` so every connected view stays in step. Closes on drop
+ */
+pub struct SceneSocket {
+    socket: WebSocket,
+    received: Rc<RefCell<Vec<SceneUpdate>>>,
+    // Event handlers, kept alive for as long as the socket can call them
+    _on_open: Closure<dyn FnMut(Event)>,
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+    _on_close: Closure<dyn FnMut(CloseEvent)>,
+}
+
+impl SceneSocket {
+    pub fn connect(url: &str, on_status: impl FnMut(SyncStatus) + 'static) -> Result<Self, String> {
+        let socket = WebSocket::new(url)
+            .map_err(|err| err.as_string().unwrap_or_else(|| format!("{:?}", err)))?;
+        socket.set_binary_type(BinaryType::Arraybuffer);
+        let on_status = Rc::new(RefCell::new(on_status));
+        on_status.borrow_mut()(SyncStatus::Connecting);
+
+        let on_open = Closure::<dyn FnMut(Event)>::new({
+            let on_status = on_status.clone();
+            move |_| on_status.borrow_mut()(SyncStatus::Open)
+        });
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let on_message = Closure::<dyn FnMut(MessageEvent)>::new({
+            let received = received.clone();
+            move |evt: MessageEvent| {
+                let data = evt.data();
+                let updates = if let Some(text) = data.as_string() {
+                    parse_json(&text)
+                } else if let Some(buffer) = data.dyn_ref::<ArrayBuffer>() {
+                    parse_binary(&Uint8Array::new(buffer).to_vec())
+                } else {
+                    Err("Unsupported message type".to_string())
+                };
+                match updates {
+                    Ok(updates) => received.borrow_mut().extend(updates),
+                    Err(err) => tracing::warn!(target: "sync", "Ignoring message: {}", err),
+                }
+            }
+        });
+        // An error is always followed by a close, which reports it
+        let on_close = Closure::<dyn FnMut(CloseEvent)>::new(move |evt: CloseEvent| {
+            let reason = match evt.reason() {
+                reason if reason.is_empty() => format!("code {}", evt.code()),
+                reason => reason,
+            };
+            on_status.borrow_mut()(SyncStatus::Closed(reason));
+        });
+        socket.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+        socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+
+        Ok(Self {
+            socket,
+            received,
+            _on_open: on_open,
+            _on_message: on_message,
+            _on_close: on_close,
+        })
+    }
+
+    /**
+     * Updates received since the last call, oldest first
+     */
+    pub fn take_updates(&self) -> Vec<SceneUpdate> {
+        std::mem::take(&mut self.received.borrow_mut())
+    }
+
+    /**
+     * Send a local change to the server; dropped unless the socket is open
+     */
+    pub fn send(&self, update: &SceneUpdate) {
+        if self.socket.ready_state() == WebSocket::OPEN {
+            if let Err(err) = self.socket.send_with_str(&update.to_json()) {
+                tracing::warn!(target: "sync", "Sending an update failed: {:?}", err);
+            }
+        }
+    }
+}
+
+impl Drop for SceneSocket {
+    fn drop(&mut self) {
+        // The handlers are about to be freed, so the socket must not call them
+        self.socket.set_onopen(None);
+        self.socket.set_onmessage(None);
+        self.socket.set_onclose(None);
+        let _ = self.socket.close();
+    }
+}
@@ -0,0 +1,184 @@
+use crate::math::{self, Mat4, Vec3};
+
+/**
+ * The selected object ids, in the order they were selected. The last one is
+ * the primary selection, the one an inspector shows
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Selection {
+    ids: Vec<u64>,
+}
+
+impl Selection {
+    pub fn contains(&self, id: u64) -> bool {
+        self.ids.contains(&id)
+    }
+
+    pub fn ids(&self) -> &[u64] {
+        &self.ids
+    }
+
+    pub fn primary(&self) -> Option<u64> {
+        self.ids.last().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /**
+     * A click that hit `hit`, or empty space. A plain click selects just that
+     * object, or nothing; an additive (Shift) click toggles it and keeps the rest
+     */
+    pub fn click(&mut self, hit: Option<u64>, additive: bool) {
+        match (hit, additive) {
+            (Some(id), true) => {
+                if self.contains(id) {
+                    self.ids.retain(|&selected| selected != id);
+                } else {
+                    self.ids.push(id);
+                }
+            }
+            (Some(id), false) => self.ids = vec![id],
+            (None, true) => {}
+            (None, false) => self.ids.clear(),
+        }
+    }
+
+    /**
+     * Select `ids`, such as everything inside a box, replacing the selection
+     * or adding to it
+     */
+    pub fn select(&mut self, ids: impl IntoIterator<Item = u64>, additive: bool) {
+        if !additive {
+            self.ids.clear();
+        }
+        for id in ids {
+            if !self.contains(id) {
+                self.ids.push(id);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.ids.clear();
+    }
+}
+
+/**
+ * Axis-aligned rectangle in canvas pixels, top-left origin
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScreenRect {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+}
+
+impl ScreenRect {
+    /**
+     * The rectangle spanned by two opposite corners in any order, such as
+     * where a rubber-band drag started and where it is now
+     */
+    pub fn from_corners(a: [f32; 2], b: [f32; 2]) -> Self {
+        Self {
+            min: [a[0].min(b[0]), a[1].min(b[1])],
+            max: [a[0].max(b[0]), a[1].max(b[1])],
+        }
+    }
+
+    pub fn intersects(&self, other: &ScreenRect) -> bool {
+        (0..2).all(|axis| self.min[axis] <= other.max[axis] && other.min[axis] <= self.max[axis])
+    }
+
+    pub fn size(&self) -> [f32; 2] {
+        [self.max[0] - self.min[0], self.max[1] - self.min[1]]
+    }
+}
+
+/**
+ * Screen-space bounds of world-space `points` seen through `view_proj` on a
+ * `width`×`height` canvas. Points behind the camera are left out; `None` if
+ * all of them are
+ */
+pub fn projected_rect(
+    view_proj: &Mat4,
+    points: &[Vec3],
+    width: f32,
+    height: f32,
+) -> Option<ScreenRect> {
+    points
+        .iter()
+        .filter_map(|&point| math::world_to_screen(view_proj, point, width, height))
+        .map(|p| ScreenRect { min: p, max: p })
+        .reduce(|a, b| ScreenRect {
+            min: [a.min[0].min(b.min[0]), a.min[1].min(b.min[1])],
+            max: [a.max[0].max(b.max[0]), a.max[1].max(b.max[1])],
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_clicks_replace_and_shift_clicks_toggle() {
+        let mut selection = Selection::default();
+        selection.click(Some(1), false);
+        selection.click(Some(2), true);
+        selection.click(Some(3), true);
+        assert_eq!(selection.ids(), &[1, 2, 3]);
+        assert_eq!(selection.primary(), Some(3));
+
+        selection.click(Some(2), true);
+        assert_eq!(selection.ids(), &[1, 3]);
+        // Shift-clicking empty space keeps the selection, a plain click clears it
+        selection.click(None, true);
+        assert_eq!(selection.len(), 2);
+        selection.click(Some(4), false);
+        assert_eq!(selection.ids(), &[4]);
+        selection.click(None, false);
+        assert!(selection.is_empty());
+    }
+
+    #[test]
+    fn box_selections_replace_or_add_without_duplicates() {
+        let mut selection = Selection::default();
+        selection.select([1, 2], false);
+        selection.select([2, 3], true);
+        assert_eq!(selection.ids(), &[1, 2, 3]);
+        selection.select([5], false);
+        assert_eq!(selection.ids(), &[5]);
+        selection.clear();
+        assert_eq!(selection.primary(), None);
+    }
+
+    #[test]
+    fn rectangles_from_any_pair_of_corners() {
+        let rect = ScreenRect::from_corners([30.0, 5.0], [10.0, 25.0]);
+        assert_eq!(rect.min, [10.0, 5.0]);
+        assert_eq!(rect.size(), [20.0, 20.0]);
+        let touching = ScreenRect::from_corners([30.0, 25.0], [40.0, 40.0]);
+        let apart = ScreenRect::from_corners([31.0, 0.0], [40.0, 40.0]);
+        assert!(rect.intersects(&touching));
+        assert!(!rect.intersects(&apart));
+    }
+
+    #[test]
+    fn projects_points_to_their_pixel_bounds() {
+        // Identity: NDC -1..1 maps across a 100×100 canvas, y flipped
+        let rect = projected_rect(
+            &math::identity(),
+            &[[-0.5, 0.5, 0.0], [0.5, -0.5, 0.0], [0.0, 0.0, 0.0]],
+            100.0,
+            100.0,
+        )
+        .unwrap();
+        assert_eq!(rect.min, [25.0, 25.0]);
+        assert_eq!(rect.max, [75.0, 75.0]);
+        assert_eq!(projected_rect(&math::identity(), &[], 100.0, 100.0), None);
+    }
+}
@@ -0,0 +1,68 @@
+// Not every file is used by itself, nor every pair as a program
+#![allow(dead_code)]
+
+/**
+ * A vertex and fragment shader from `shaders/` sharing a name, such as
+ * `oit/resolve`, and the `#define`s their `#ifdef`s test for
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShaderProgram {
+    pub name: &'static str,
+    pub vertex: &'static str,
+    pub fragment: &'static str,
+    pub defines: &'static [&'static str],
+}
+
+impl ShaderProgram {
+    /**
+     * Every combination of the program's defines, the empty one first
+     */
+    pub fn permutations(&self) -> Vec<Vec<&'static str>> {
+        (0..1usize << self.defines.len())
+            .map(|mask| {
+                self.defines
+                    .iter()
+                    .enumerate()
+                    .filter(|(bit, _)| mask & (1 << bit) != 0)
+                    .map(|(_, define)| *define)
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/**
+ * The program called `name`, the path under `shaders/` without the stage
+ */
+pub fn program(name: &str) -> Option<&'static ShaderProgram> {
+    PROGRAMS.iter().find(|program| program.name == name)
+}
+
+include!(concat!(env!("OUT_DIR"), "/shaders.rs"));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairs_stages_into_programs() {
+        let resolve = program("oit/resolve").unwrap();
+        assert_eq!(resolve.vertex, OIT_RESOLVE_VERT);
+        assert_eq!(resolve.fragment, OIT_RESOLVE_FRAG);
+        // A fragment shader without its own vertex shader is no program
+        assert!(program("oit/occluder").is_none());
+    }
+
+    #[test]
+    fn finds_material_permutations() {
+        let material = program("material").unwrap();
+        let mut defines = material.defines.to_vec();
+        defines.sort();
+        assert_eq!(defines, ["LIGHTING", "TEXTURE", "TOON", "VERTEX_COLORS"]);
+
+        let permutations = material.permutations();
+        assert_eq!(permutations.len(), 16);
+        assert!(permutations[0].is_empty());
+        assert_eq!(permutations[15].len(), 4);
+    }
+}
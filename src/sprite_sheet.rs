@@ -0,0 +1,50 @@
+use crate::atlas::UvRect;
+
+/**
+ * Flipbook animation laid out as a grid of equally sized frames, read left to
+ * right then top to bottom
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpriteSheet {
+    pub columns: u32,
+    pub rows: u32,
+    // May be less than `columns * rows` when the last row isn't full
+    pub frame_count: u32,
+    pub frames_per_second: f32,
+}
+
+impl SpriteSheet {
+    /**
+     * Frame shown `time` seconds into a looping animation
+     */
+    pub fn frame_at(&self, time: f32) -> u32 {
+        let frame = (time * self.frames_per_second).floor() as i64;
+        frame.rem_euclid(self.frame_count.max(1) as i64) as u32
+    }
+
+    /**
+     * UV rect of `frame` relative to the whole sheet; combine with
+     * `UvRect::sub_rect` when the sheet itself lives in an atlas
+     */
+    pub fn frame_uv(&self, frame: u32) -> UvRect {
+        let (column, row) = (frame % self.columns, frame / self.columns);
+        let (w, h) = (1.0 / self.columns as f32, 1.0 / self.rows as f32);
+        UvRect {
+            min: [column as f32 * w, row as f32 * h],
+            max: [(column + 1) as f32 * w, (row + 1) as f32 * h],
+        }
+    }
+
+    /**
+     * Grid and timing as `vec4(columns, rows, frameCount, framesPerSecond)`,
+     * for shaders that pick frames themselves (e.g. per particle age)
+     */
+    pub fn shader_params(&self) -> [f32; 4] {
+        [
+            self.columns as f32,
+            self.rows as f32,
+            self.frame_count as f32,
+            self.frames_per_second,
+        ]
+    }
+}
@@ -0,0 +1,130 @@
+use web_sys::{WebGl2RenderingContext, WebGlProgram, WebGlUniformLocation, WebGlVertexArrayObject};
+
+use crate::atlas::UvRect;
+use crate::dynamic_buffer::DynamicBuffer;
+use crate::gl_util;
+use crate::math::Mat4;
+use crate::shaders;
+use crate::texture::Texture;
+
+// position.xy + uv + rgba per vertex, six vertices (two triangles) per sprite
+const FLOATS_PER_VERTEX: usize = 8;
+const STRIDE: i32 = (FLOATS_PER_VERTEX * 4) as i32;
+
+/**
+ * Textured quad in the batch's projection space (pixels for a screen-space
+ * orthographic projection). The top edge samples `uv.min[1]`
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprite {
+    pub center: [f32; 2],
+    pub size: [f32; 2],
+    // Radians
+    pub rotation: f32,
+    pub uv: UvRect,
+    // Multiplied with the texture, including alpha
+    pub color: [f32; 4],
+}
+
+/**
+ * Collects sprites sharing one texture and draws them with a single call
+ */
+pub struct SpriteBatch {
+    program: WebGlProgram,
+    projection_loc: Option<WebGlUniformLocation>,
+    image_loc: Option<WebGlUniformLocation>,
+    vao: WebGlVertexArrayObject,
+    buffer: DynamicBuffer,
+    vertices: Vec<f32>,
+}
+
+impl SpriteBatch {
+    pub fn new(gl: &WebGl2RenderingContext) -> Result<Self, String> {
+        let program = gl_util::create_program(gl, shaders::SPRITES_VERT, shaders::SPRITES_FRAG)?;
+        let vao = gl
+            .create_vertex_array()
+            .ok_or_else(|| "Unable to create sprite VAO".to_string())?;
+        let buffer = DynamicBuffer::new(gl, WebGl2RenderingContext::ARRAY_BUFFER)?;
+
+        gl.bind_vertex_array(Some(&vao));
+        buffer.bind(gl);
+        gl.enable_vertex_attrib_array(0);
+        gl.vertex_attrib_pointer_with_i32(0, 2, WebGl2RenderingContext::FLOAT, false, STRIDE, 0);
+        gl.enable_vertex_attrib_array(1);
+        gl.vertex_attrib_pointer_with_i32(1, 2, WebGl2RenderingContext::FLOAT, false, STRIDE, 8);
+        gl.enable_vertex_attrib_array(2);
+        gl.vertex_attrib_pointer_with_i32(2, 4, WebGl2RenderingContext::FLOAT, false, STRIDE, 16);
+        gl.bind_vertex_array(None);
+
+        Ok(Self {
+            projection_loc: gl.get_uniform_location(&program, "projection"),
+            image_loc: gl.get_uniform_location(&program, "image"),
+            program,
+            vao,
+            buffer,
+            vertices: Vec::new(),
+        })
+    }
+
+    pub fn push(&mut self, sprite: &Sprite) {
+        let (s, c) = sprite.rotation.sin_cos();
+        let [hw, hh] = [sprite.size[0] * 0.5, sprite.size[1] * 0.5];
+        let corner = |x: f32, y: f32, u: f32, v: f32| {
+            let (lx, ly) = (x * hw, y * hh);
+            [
+                sprite.center[0] + c * lx - s * ly,
+                sprite.center[1] + s * lx + c * ly,
+                u,
+                v,
+            ]
+        };
+        let UvRect { min, max } = sprite.uv;
+        // Local -y is the top edge, which matches a y-down pixel projection
+        let top_left = corner(-1.0, -1.0, min[0], min[1]);
+        let top_right = corner(1.0, -1.0, max[0], min[1]);
+        let bottom_left = corner(-1.0, 1.0, min[0], max[1]);
+        let bottom_right = corner(1.0, 1.0, max[0], max[1]);
+        for vertex in [
+            top_left,
+            bottom_left,
+            top_right,
+            top_right,
+            bottom_left,
+            bottom_right,
+        ] {
+            self.vertices.extend(vertex);
+            self.vertices.extend(sprite.color);
+        }
+    }
+
+    /**
+     * Draw everything pushed since the last flush with alpha blending, then clear the batch
+     */
+    pub fn flush(&mut self, gl: &WebGl2RenderingContext, projection: &Mat4, texture: &Texture) {
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        self.buffer.write(gl, &self.vertices);
+
+        gl.enable(WebGl2RenderingContext::BLEND);
+        gl.blend_func(
+            WebGl2RenderingContext::SRC_ALPHA,
+            WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+        );
+        gl.use_program(Some(&self.program));
+        gl.uniform_matrix4fv_with_f32_array(self.projection_loc.as_ref(), false, projection);
+        gl.uniform1i(self.image_loc.as_ref(), 0);
+        texture.bind(gl, 0);
+        gl.bind_vertex_array(Some(&self.vao));
+        gl.draw_arrays(
+            WebGl2RenderingContext::TRIANGLES,
+            0,
+            (self.vertices.len() / FLOATS_PER_VERTEX) as i32,
+        );
+        gl.bind_vertex_array(None);
+        gl.disable(WebGl2RenderingContext::BLEND);
+
+        self.vertices.clear();
+    }
+}
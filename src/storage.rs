@@ -0,0 +1,24 @@
+use web_sys::Storage;
+
+/**
+ * The page's localStorage; `None` where it's unavailable, e.g. disabled by
+ * privacy settings
+ */
+fn local_storage() -> Option<Storage> {
+    web_sys::window()?.local_storage().ok().flatten()
+}
+
+pub fn load(key: &str) -> Option<String> {
+    local_storage()?.get_item(key).ok().flatten()
+}
+
+/**
+ * Best effort: quota and privacy errors are logged, not returned
+ */
+pub fn save(key: &str, value: &str) {
+    if let Some(storage) = local_storage() {
+        if let Err(err) = storage.set_item(key, value) {
+            tracing::warn!(target: "storage", "Unable to save {}: {:?}", key, err);
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use web_sys::{CanvasRenderingContext2d, WebGl2RenderingContext};
+
+use crate::canvas_texture::offscreen_canvas;
+use crate::texture::Texture;
+
+/**
+ * How `TextTexture` draws its text. `font_family` is a CSS font list; the
+ * default ends in the common color emoji fonts so emoji render in color
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextStyle {
+    pub font_family: String,
+    pub size: f64,
+    pub bold: bool,
+    // Any CSS color
+    pub color: String,
+    pub background: Option<String>,
+    // Empty space around the text, in pixels
+    pub padding: f64,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            font_family:
+                "sans-serif, \"Apple Color Emoji\", \"Segoe UI Emoji\", \"Noto Color Emoji\""
+                    .to_string(),
+            size: 32.0,
+            bold: false,
+            color: "#ffffff".to_string(),
+            background: None,
+            padding: 4.0,
+        }
+    }
+}
+
+impl TextStyle {
+    /**
+     * The CSS `font` shorthand for this style
+     */
+    pub fn font(&self) -> String {
+        let weight = if self.bold { "bold " } else { "" };
+        format!("{}{}px {}", weight, self.size, self.font_family)
+    }
+
+    /**
+     * Distance between the baselines of consecutive lines
+     */
+    fn line_height(&self) -> f64 {
+        (self.size * 1.25).ceil()
+    }
+}
+
+/**
+ * Canvas size for lines of the given measured widths, and the vertical
+ * middle of each line
+ */
+fn layout(line_widths: &[f64], style: &TextStyle) -> (u32, u32, Vec<f64>) {
+    let widest = line_widths.iter().copied().fold(0.0, f64::max);
+    let line_height = style.line_height();
+    let width = (widest + style.padding * 2.0).ceil().max(1.0) as u32;
+    let height = (line_height * line_widths.len().max(1) as f64 + style.padding * 2.0)
+        .ceil()
+        .max(1.0) as u32;
+    let middles = (0..line_widths.len())
+        .map(|line| style.padding + line_height * (line as f64 + 0.5))
+        .collect();
+    (width, height, middles)
+}
+
+/**
+ * A string rendered once by the browser's 2D canvas and uploaded as a texture,
+ * sized to fit it. Lines are split at `\n` and left aligned. Rows run top to
+ * bottom, so v = 0 is the top of the text
+ */
+pub struct TextTexture {
+    texture: Texture,
+    width: u32,
+    height: u32,
+}
+
+impl TextTexture {
+    pub fn new(gl: &WebGl2RenderingContext, text: &str, style: &TextStyle) -> Result<Self, String> {
+        let lines: Vec<&str> = text.split('\n').collect();
+        let (canvas, context) = offscreen_canvas(1, 1)?;
+        context.set_font(&style.font());
+        let widths = lines
+            .iter()
+            .map(|line| {
+                context
+                    .measure_text(line)
+                    .map(|metrics| metrics.width())
+                    .map_err(|_| "Unable to measure text".to_string())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (width, height, middles) = layout(&widths, style);
+        // Resizing resets the context, font included
+        canvas.set_width(width);
+        canvas.set_height(height);
+        draw(&context, &lines, &middles, style, width, height);
+
+        let texture =
+            Texture::empty(gl).ok_or_else(|| "Unable to create text texture".to_string())?;
+        texture
+            .upload_canvas(gl, &canvas)
+            .ok_or_else(|| "Unable to upload text texture".to_string())?;
+        Ok(Self {
+            texture,
+            width,
+            height,
+        })
+    }
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /**
+     * Width over height, for sizing the quad the text is drawn on
+     */
+    pub fn aspect(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+
+    pub fn delete(&self, gl: &WebGl2RenderingContext) {
+        self.texture.delete(gl);
+    }
+}
+
+fn draw(
+    context: &CanvasRenderingContext2d,
+    lines: &[&str],
+    middles: &[f64],
+    style: &TextStyle,
+    width: u32,
+    height: u32,
+) {
+    if let Some(background) = &style.background {
+        context.set_fill_style_str(background);
+        context.fill_rect(0.0, 0.0, width as f64, height as f64);
+    }
+    context.set_font(&style.font());
+    context.set_fill_style_str(&style.color);
+    context.set_text_align("left");
+    context.set_text_baseline("middle");
+    for (line, &middle) in lines.iter().zip(middles) {
+        let _ = context.fill_text(line, style.padding, middle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn font_shorthand_includes_weight_and_size() {
+        let style = TextStyle {
+            font_family: "monospace".to_string(),
+            size: 20.0,
+            bold: true,
+            ..TextStyle::default()
+        };
+        assert_eq!(style.font(), "bold 20px monospace");
+        assert!(TextStyle::default().font().starts_with("32px sans-serif"));
+    }
+
+    #[test]
+    fn layout_fits_the_widest_line_and_every_line() {
+        let style = TextStyle {
+            size: 16.0,
+            padding: 2.0,
+            ..TextStyle::default()
+        };
+        let (width, height, middles) = layout(&[10.2, 40.5, 7.0], &style);
+        assert_eq!(width, 45);
+        assert_eq!(height, 20 * 3 + 4);
+        assert_eq!(middles, vec![12.0, 32.0, 52.0]);
+    }
+
+    #[test]
+    fn empty_text_still_gets_a_texture() {
+        let style = TextStyle {
+            padding: 0.0,
+            ..TextStyle::default()
+        };
+        let (width, height, _) = layout(&[0.0], &style);
+        assert_eq!((width, height), (1, 40));
+    }
+}
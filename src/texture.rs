@@ -0,0 +1,552 @@
+use std::rc::Rc;
+
+use web_sys::{
+    HtmlCanvasElement, HtmlVideoElement, ImageBitmap, WebGl2RenderingContext, WebGlTexture,
+};
+
+use crate::gpu_memory::{self, Allocation, Kind};
+
+/**
+ * 2D RGBA texture on the GPU
+ */
+#[derive(Clone, Debug)]
+pub struct Texture {
+    texture: WebGlTexture,
+    // Shared by clones, like the texture itself
+    allocation: Rc<Allocation>,
+}
+
+impl Texture {
+    /**
+     * Upload a decoded image with mipmaps and trilinear filtering
+     */
+    pub fn from_image(gl: &WebGl2RenderingContext, image: &ImageBitmap) -> Option<Self> {
+        let texture = gl.create_texture()?;
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&texture));
+        gl.tex_image_2d_with_u32_and_u32_and_image_bitmap(
+            WebGl2RenderingContext::TEXTURE_2D,
+            0,
+            WebGl2RenderingContext::RGBA as i32,
+            WebGl2RenderingContext::RGBA,
+            WebGl2RenderingContext::UNSIGNED_BYTE,
+            image,
+        )
+        .ok()?;
+        gl.generate_mipmap(WebGl2RenderingContext::TEXTURE_2D);
+        set_filtering(
+            gl,
+            WebGl2RenderingContext::TEXTURE_2D,
+            WebGl2RenderingContext::LINEAR_MIPMAP_LINEAR,
+            WebGl2RenderingContext::LINEAR,
+        );
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+        let bytes =
+            gpu_memory::texture_bytes(image.width() as usize, image.height() as usize, 1, 4, true);
+        Some(Self::tracked(texture, bytes))
+    }
+
+    /**
+     * Upload raw RGBA8 pixels (`width * height * 4` bytes) with nearest filtering
+     */
+    pub fn from_rgba(
+        gl: &WebGl2RenderingContext,
+        width: i32,
+        height: i32,
+        pixels: &[u8],
+    ) -> Option<Self> {
+        Self::from_rgba_filtered(gl, width, height, pixels, WebGl2RenderingContext::NEAREST)
+    }
+
+    /**
+     * Upload raw RGBA8 pixels with linear filtering and no mipmaps, for data
+     * meant to be interpolated between texels such as distance fields
+     */
+    pub fn from_rgba_linear(
+        gl: &WebGl2RenderingContext,
+        width: i32,
+        height: i32,
+        pixels: &[u8],
+    ) -> Option<Self> {
+        Self::from_rgba_filtered(gl, width, height, pixels, WebGl2RenderingContext::LINEAR)
+    }
+
+    fn from_rgba_filtered(
+        gl: &WebGl2RenderingContext,
+        width: i32,
+        height: i32,
+        pixels: &[u8],
+        filter: u32,
+    ) -> Option<Self> {
+        let texture = gl.create_texture()?;
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&texture));
+        gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            WebGl2RenderingContext::TEXTURE_2D,
+            0,
+            WebGl2RenderingContext::RGBA8 as i32,
+            width,
+            height,
+            0,
+            WebGl2RenderingContext::RGBA,
+            WebGl2RenderingContext::UNSIGNED_BYTE,
+            Some(pixels),
+        )
+        .ok()?;
+        set_filtering(gl, WebGl2RenderingContext::TEXTURE_2D, filter, filter);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+        let bytes = gpu_memory::texture_bytes(width as usize, height as usize, 1, 4, false);
+        Some(Self::tracked(texture, bytes))
+    }
+
+    /**
+     * Texture with linear filtering and no contents yet, for sources that are
+     * re-uploaded whole (and may change size) such as canvases
+     */
+    pub fn empty(gl: &WebGl2RenderingContext) -> Option<Self> {
+        let texture = gl.create_texture()?;
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&texture));
+        set_filtering(
+            gl,
+            WebGl2RenderingContext::TEXTURE_2D,
+            WebGl2RenderingContext::LINEAR,
+            WebGl2RenderingContext::LINEAR,
+        );
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+        Some(Self::tracked(texture, 0))
+    }
+
+    /**
+     * Replace the contents with the current pixels of a 2D canvas
+     */
+    pub fn upload_canvas(
+        &self,
+        gl: &WebGl2RenderingContext,
+        canvas: &HtmlCanvasElement,
+    ) -> Option<()> {
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&self.texture));
+        let result = gl.tex_image_2d_with_u32_and_u32_and_html_canvas_element(
+            WebGl2RenderingContext::TEXTURE_2D,
+            0,
+            WebGl2RenderingContext::RGBA as i32,
+            WebGl2RenderingContext::RGBA,
+            WebGl2RenderingContext::UNSIGNED_BYTE,
+            canvas,
+        );
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+        self.allocation.resize(gpu_memory::texture_bytes(
+            canvas.width() as usize,
+            canvas.height() as usize,
+            1,
+            4,
+            false,
+        ));
+        result.ok()
+    }
+
+    /**
+     * Replace the contents with the video's current frame
+     */
+    pub fn upload_video(
+        &self,
+        gl: &WebGl2RenderingContext,
+        video: &HtmlVideoElement,
+    ) -> Option<()> {
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&self.texture));
+        let result = gl.tex_image_2d_with_u32_and_u32_and_html_video_element(
+            WebGl2RenderingContext::TEXTURE_2D,
+            0,
+            WebGl2RenderingContext::RGBA as i32,
+            WebGl2RenderingContext::RGBA,
+            WebGl2RenderingContext::UNSIGNED_BYTE,
+            video,
+        );
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+        self.allocation.resize(gpu_memory::texture_bytes(
+            video.video_width() as usize,
+            video.video_height() as usize,
+            1,
+            4,
+            false,
+        ));
+        result.ok()
+    }
+
+    /**
+     * Allocate an uninitialized RGBA8 texture with linear filtering, to be
+     * filled region by region
+     */
+    pub fn with_size(gl: &WebGl2RenderingContext, width: i32, height: i32) -> Option<Self> {
+        let texture = gl.create_texture()?;
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&texture));
+        gl.tex_storage_2d(
+            WebGl2RenderingContext::TEXTURE_2D,
+            1,
+            WebGl2RenderingContext::RGBA8,
+            width,
+            height,
+        );
+        set_filtering(
+            gl,
+            WebGl2RenderingContext::TEXTURE_2D,
+            WebGl2RenderingContext::LINEAR,
+            WebGl2RenderingContext::LINEAR,
+        );
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+        let bytes = gpu_memory::texture_bytes(width as usize, height as usize, 1, 4, false);
+        Some(Self::tracked(texture, bytes))
+    }
+
+    /**
+     * Copy `image` into the texture with its top-left corner at (`x`, `y`)
+     */
+    pub fn upload_region(
+        &self,
+        gl: &WebGl2RenderingContext,
+        x: i32,
+        y: i32,
+        image: &ImageBitmap,
+    ) -> Option<()> {
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&self.texture));
+        let result = gl.tex_sub_image_2d_with_u32_and_u32_and_image_bitmap(
+            WebGl2RenderingContext::TEXTURE_2D,
+            0,
+            x,
+            y,
+            WebGl2RenderingContext::RGBA,
+            WebGl2RenderingContext::UNSIGNED_BYTE,
+            image,
+        );
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+        result.ok()
+    }
+
+    /**
+     * Copy RGBA8 pixels starting `offset` bytes into the bound
+     * `PIXEL_UNPACK_BUFFER` into the rectangle with its top-left corner at
+     * (`x`, `y`)
+     */
+    pub fn upload_from_unpack_buffer(
+        &self,
+        gl: &WebGl2RenderingContext,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        offset: i32,
+    ) -> Result<(), String> {
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&self.texture));
+        let result = gl.tex_sub_image_2d_with_i32_and_i32_and_u32_and_type_and_i32(
+            WebGl2RenderingContext::TEXTURE_2D,
+            0,
+            x,
+            y,
+            width,
+            height,
+            WebGl2RenderingContext::RGBA,
+            WebGl2RenderingContext::UNSIGNED_BYTE,
+            offset,
+        );
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+        result.map_err(|err| format!("texSubImage2D from unpack buffer failed: {:?}", err))
+    }
+
+    /**
+     * Stand-in for textures that haven't loaded yet: a 2x2 gray/magenta checker
+     */
+    pub fn placeholder(gl: &WebGl2RenderingContext) -> Option<Self> {
+        #[rustfmt::skip]
+        let pixels = [
+            128, 128, 128, 255, 255, 0, 255, 255,
+            255, 0, 255, 255, 128, 128, 128, 255,
+        ];
+        Self::from_rgba(gl, 2, 2, &pixels)
+    }
+
+    fn tracked(texture: WebGlTexture, bytes: usize) -> Self {
+        Self {
+            texture,
+            allocation: Rc::new(Allocation::new(Kind::Texture, bytes)),
+        }
+    }
+
+    pub fn bind(&self, gl: &WebGl2RenderingContext, unit: u32) {
+        gl.active_texture(WebGl2RenderingContext::TEXTURE0 + unit);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&self.texture));
+    }
+
+    /**
+     * Free the GPU memory now rather than whenever the context goes away;
+     * clones share the texture and must not be used afterwards
+     */
+    pub fn delete(&self, gl: &WebGl2RenderingContext) {
+        gl.delete_texture(Some(&self.texture));
+    }
+}
+
+/**
+ * 3D texture (`TEXTURE_3D`), such as a volume of densities or a color lookup
+ * table
+ */
+pub struct Texture3D {
+    texture: WebGlTexture,
+    _allocation: Allocation,
+}
+
+impl Texture3D {
+    /**
+     * Upload `width * height * depth` bytes, x varying fastest, with trilinear filtering
+     */
+    pub fn from_r8(
+        gl: &WebGl2RenderingContext,
+        width: i32,
+        height: i32,
+        depth: i32,
+        voxels: &[u8],
+    ) -> Option<Self> {
+        Self::upload(gl, (width, height, depth), voxels, 1)
+    }
+
+    /**
+     * Like `from_r8`, with four bytes (RGBA) per voxel
+     */
+    pub fn from_rgba8(
+        gl: &WebGl2RenderingContext,
+        width: i32,
+        height: i32,
+        depth: i32,
+        voxels: &[u8],
+    ) -> Option<Self> {
+        Self::upload(gl, (width, height, depth), voxels, 4)
+    }
+
+    fn upload(
+        gl: &WebGl2RenderingContext,
+        (width, height, depth): (i32, i32, i32),
+        voxels: &[u8],
+        bytes_per_voxel: usize,
+    ) -> Option<Self> {
+        let (internal_format, format) = if bytes_per_voxel == 4 {
+            (WebGl2RenderingContext::RGBA8, WebGl2RenderingContext::RGBA)
+        } else {
+            (WebGl2RenderingContext::R8, WebGl2RenderingContext::RED)
+        };
+        let texture = gl.create_texture()?;
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_3D, Some(&texture));
+        // Rows of single bytes needn't be 4-byte aligned
+        gl.pixel_storei(WebGl2RenderingContext::UNPACK_ALIGNMENT, 1);
+        let result = gl.tex_image_3d_with_opt_u8_array(
+            WebGl2RenderingContext::TEXTURE_3D,
+            0,
+            internal_format as i32,
+            width,
+            height,
+            depth,
+            0,
+            format,
+            WebGl2RenderingContext::UNSIGNED_BYTE,
+            Some(voxels),
+        );
+        gl.pixel_storei(WebGl2RenderingContext::UNPACK_ALIGNMENT, 4);
+        result.ok()?;
+        set_filtering(
+            gl,
+            WebGl2RenderingContext::TEXTURE_3D,
+            WebGl2RenderingContext::LINEAR,
+            WebGl2RenderingContext::LINEAR,
+        );
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_3D, None);
+        let bytes = gpu_memory::texture_bytes(
+            width as usize,
+            height as usize,
+            depth as usize,
+            bytes_per_voxel,
+            false,
+        );
+        Some(Self {
+            texture,
+            _allocation: Allocation::new(Kind::Texture, bytes),
+        })
+    }
+
+    pub fn bind(&self, gl: &WebGl2RenderingContext, unit: u32) {
+        gl.active_texture(WebGl2RenderingContext::TEXTURE0 + unit);
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_3D, Some(&self.texture));
+    }
+}
+
+/**
+ * Stack of same-sized RGBA layers (`TEXTURE_2D_ARRAY`); shaders pick a layer
+ * with the third texture coordinate, so one bind serves many materials
+ */
+pub struct TextureArray {
+    texture: WebGlTexture,
+    _allocation: Allocation,
+}
+
+impl TextureArray {
+    /**
+     * Upload one layer per image with mipmaps and trilinear filtering.
+     * Every image must match the first one's size
+     */
+    pub fn from_images(gl: &WebGl2RenderingContext, images: &[ImageBitmap]) -> Option<Self> {
+        let first = images.first()?;
+        let (width, height) = (first.width() as i32, first.height() as i32);
+        let levels = 32 - (width.max(height) as u32).leading_zeros() as i32;
+        let texture = gl.create_texture()?;
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D_ARRAY, Some(&texture));
+        gl.tex_storage_3d(
+            WebGl2RenderingContext::TEXTURE_2D_ARRAY,
+            levels,
+            WebGl2RenderingContext::RGBA8,
+            width,
+            height,
+            images.len() as i32,
+        );
+        let uploaded = images.iter().enumerate().all(|(layer, image)| {
+            image.width() as i32 == width
+                && image.height() as i32 == height
+                && gl
+                    .tex_sub_image_3d_with_image_bitmap(
+                        WebGl2RenderingContext::TEXTURE_2D_ARRAY,
+                        0,
+                        0,
+                        0,
+                        layer as i32,
+                        width,
+                        height,
+                        1,
+                        WebGl2RenderingContext::RGBA,
+                        WebGl2RenderingContext::UNSIGNED_BYTE,
+                        image,
+                    )
+                    .is_ok()
+        });
+        if !uploaded {
+            gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D_ARRAY, None);
+            gl.delete_texture(Some(&texture));
+            return None;
+        }
+        gl.generate_mipmap(WebGl2RenderingContext::TEXTURE_2D_ARRAY);
+        set_filtering(
+            gl,
+            WebGl2RenderingContext::TEXTURE_2D_ARRAY,
+            WebGl2RenderingContext::LINEAR_MIPMAP_LINEAR,
+            WebGl2RenderingContext::LINEAR,
+        );
+        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D_ARRAY, None);
+        let bytes =
+            gpu_memory::texture_bytes(width as usize, height as usize, images.len(), 4, true);
+        Some(Self {
+            texture,
+            _allocation: Allocation::new(Kind::Texture, bytes),
+        })
+    }
+
+    pub fn bind(&self, gl: &WebGl2RenderingContext, unit: u32) {
+        gl.active_texture(WebGl2RenderingContext::TEXTURE0 + unit);
+        gl.bind_texture(
+            WebGl2RenderingContext::TEXTURE_2D_ARRAY,
+            Some(&self.texture),
+        );
+    }
+}
+
+fn set_filtering(gl: &WebGl2RenderingContext, target: u32, min: u32, mag: u32) {
+    gl.tex_parameteri(
+        target,
+        WebGl2RenderingContext::TEXTURE_MIN_FILTER,
+        min as i32,
+    );
+    gl.tex_parameteri(
+        target,
+        WebGl2RenderingContext::TEXTURE_MAG_FILTER,
+        mag as i32,
+    );
+    gl.tex_parameteri(
+        target,
+        WebGl2RenderingContext::TEXTURE_WRAP_S,
+        WebGl2RenderingContext::CLAMP_TO_EDGE as i32,
+    );
+    gl.tex_parameteri(
+        target,
+        WebGl2RenderingContext::TEXTURE_WRAP_T,
+        WebGl2RenderingContext::CLAMP_TO_EDGE as i32,
+    );
+    gl.tex_parameteri(
+        target,
+        WebGl2RenderingContext::TEXTURE_WRAP_R,
+        WebGl2RenderingContext::CLAMP_TO_EDGE as i32,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::assets;
+    use crate::gl_util::{self, GlResource};
+
+    const CHECKER: &[u8] = include_bytes!("../assets/textures/checker.png");
+
+    /**
+     * Level 0 of `texture` read back through a framebuffer, rows bottom to top
+     */
+    fn read_back(
+        gl: &WebGl2RenderingContext,
+        texture: &Texture,
+        width: i32,
+        height: i32,
+    ) -> Vec<u8> {
+        let framebuffer = gl.create_framebuffer().unwrap();
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(&framebuffer));
+        gl.framebuffer_texture_2d(
+            WebGl2RenderingContext::FRAMEBUFFER,
+            WebGl2RenderingContext::COLOR_ATTACHMENT0,
+            WebGl2RenderingContext::TEXTURE_2D,
+            Some(&texture.texture),
+            0,
+        );
+        let mut pixels = vec![0; (width * height * 4) as usize];
+        gl.read_pixels_with_opt_u8_array(
+            0,
+            0,
+            width,
+            height,
+            WebGl2RenderingContext::RGBA,
+            WebGl2RenderingContext::UNSIGNED_BYTE,
+            Some(&mut pixels),
+        )
+        .unwrap();
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+        framebuffer.delete(gl);
+        pixels
+    }
+
+    #[wasm_bindgen_test]
+    fn uploads_rgba_pixels() {
+        let gl = gl_util::test_context();
+        // Red, green, blue and half-transparent white, bottom row first
+        let pixels = [
+            255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255, 255, 255, 128,
+        ];
+        let texture = Texture::from_rgba(&gl, 2, 2, &pixels).unwrap();
+        assert_eq!(read_back(&gl, &texture, 2, 2), pixels);
+        assert_eq!(gl.get_error(), WebGl2RenderingContext::NO_ERROR);
+        texture.delete(&gl);
+    }
+
+    #[wasm_bindgen_test]
+    async fn uploads_decoded_images() {
+        let gl = gl_util::test_context();
+        let bytes = js_sys::Uint8Array::from(CHECKER).buffer();
+        let image = assets::decode_image(&bytes).await.unwrap();
+        let (width, height, expected) = assets::image_pixels(&image).unwrap();
+        let texture = Texture::from_image(&gl, &image).unwrap();
+
+        // The image's top row is uploaded first, so it is also read back first
+        let pixels = read_back(&gl, &texture, width as i32, height as i32);
+        assert_eq!(pixels, expected);
+        assert_eq!(gl.get_error(), WebGl2RenderingContext::NO_ERROR);
+        texture.delete(&gl);
+    }
+}
@@ -0,0 +1,124 @@
+/**
+ * Accumulates real frame time and reports how many fixed-size simulation
+ * steps are due, so simulation results don't depend on the display rate
+ */
+#[derive(Clone, Debug)]
+pub struct FixedTimestep {
+    step: f64,
+    accumulator: f64,
+    last_timestamp: Option<f64>,
+    // Simulated seconds per real second: 0 pauses, below 1 is slow motion
+    time_scale: f64,
+    // Simulated seconds so far, scaled but not quantized to steps
+    elapsed: f64,
+    // Steps handed out so far
+    steps: u64,
+}
+
+// Longest frame we try to catch up on; anything beyond is dropped to avoid a
+// spiral of ever-longer frames after a stall (e.g. a background tab)
+const MAX_FRAME_TIME: f64 = 0.25;
+
+impl FixedTimestep {
+    pub fn new(step: f64) -> Self {
+        Self {
+            step,
+            accumulator: 0.0,
+            last_timestamp: None,
+            time_scale: 1.0,
+            elapsed: 0.0,
+            steps: 0,
+        }
+    }
+
+    /**
+     * Total steps handed out by `advance`, for stamping inputs
+     */
+    pub fn step_count(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn set_time_scale(&mut self, time_scale: f64) {
+        self.time_scale = time_scale.max(0.0);
+    }
+
+    /**
+     * Scaled time in seconds, for animations that should speed up, slow down
+     * and pause along with the simulation
+     */
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    /**
+     * Feed a requestAnimationFrame timestamp (milliseconds) and get the number
+     * of steps to simulate this frame
+     */
+    pub fn advance(&mut self, timestamp: f64) -> u32 {
+        let frame_time = self
+            .last_timestamp
+            .replace(timestamp)
+            .map(|last| ((timestamp - last) / 1000.0).clamp(0.0, MAX_FRAME_TIME))
+            .unwrap_or(0.0)
+            * self.time_scale;
+
+        self.elapsed += frame_time;
+        self.accumulator += frame_time;
+        let steps = (self.accumulator / self.step).floor();
+        self.accumulator -= steps * self.step;
+        self.steps += steps as u64;
+        steps as u32
+    }
+}
+
+// RAF timestamps jitter around the display's refresh interval; this much slack
+// keeps a cap equal to the refresh rate from dropping every other frame
+const FRAME_SLACK_MS: f64 = 1.5;
+
+/**
+ * Caps how often a requestAnimationFrame loop renders by skipping callbacks
+ * until enough time has accumulated. Skipped time isn't lost: timestamp-based
+ * timing such as `FixedTimestep` sees it in the next rendered frame
+ */
+#[derive(Clone, Debug, Default)]
+pub struct FrameLimiter {
+    // Frames per second to render at most; `None` renders every callback
+    target_fps: Option<u32>,
+    last_frame: Option<f64>,
+}
+
+impl FrameLimiter {
+    pub fn set_target_fps(&mut self, target_fps: Option<u32>) {
+        if self.target_fps != target_fps {
+            self.target_fps = target_fps;
+            self.last_frame = None;
+        }
+    }
+
+    /**
+     * Whether to render the frame with this RAF timestamp (milliseconds)
+     */
+    pub fn ready(&mut self, timestamp: f64) -> bool {
+        let Some(fps) = self.target_fps else {
+            return true;
+        };
+        let interval = 1000.0 / fps.max(1) as f64;
+        let Some(last) = self.last_frame else {
+            self.last_frame = Some(timestamp);
+            return true;
+        };
+        let elapsed = timestamp - last;
+        if elapsed < interval - FRAME_SLACK_MS {
+            return false;
+        }
+        // Advance by whole intervals to hold the cadence, but start over after
+        // a stall instead of rendering a burst of catch-up frames
+        let next = last + interval * (elapsed / interval).floor().max(1.0);
+        self.last_frame = Some(if timestamp - next > interval {
+            timestamp
+        } else {
+            next
+        });
+        true
+    }
+}
@@ -0,0 +1,508 @@
+use std::f32::consts::TAU;
+
+use crate::math::{self, Vec3};
+use crate::raycast::Ray;
+use crate::scene::Transform;
+
+// Gizmo length as a fraction of its distance from the eye, so it stays about
+// the same size on screen
+const SCREEN_SIZE: f32 = 0.18;
+// How close, as a fraction of the gizmo length, the cursor has to come to a
+// line or ring to grab it
+const TOLERANCE: f32 = 0.08;
+// Where the translate gizmo's plane squares start and end along their axes
+const PLANE_MIN: f32 = 0.25;
+const PLANE_MAX: f32 = 0.45;
+// Line segments per rotate ring
+const RING_SEGMENTS: usize = 48;
+// Half the size of the cubes on the scale gizmo's tips
+const TIP_SIZE: f32 = 0.05;
+// Smallest scale a drag can shrink an axis to
+const MIN_SCALE: f32 = 0.05;
+
+const AXIS_COLORS: [[f32; 3]; 3] = [[0.9, 0.25, 0.2], [0.3, 0.8, 0.3], [0.2, 0.4, 0.95]];
+const HIGHLIGHT_COLOR: [f32; 3] = [1.0, 0.85, 0.2];
+
+/**
+ * Which part of the transform the gizmo edits
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GizmoMode {
+    #[default]
+    Translate,
+    Rotate,
+    Scale,
+}
+
+impl GizmoMode {
+    pub const ALL: [GizmoMode; 3] = [GizmoMode::Translate, GizmoMode::Rotate, GizmoMode::Scale];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GizmoMode::Translate => "Move",
+            GizmoMode::Rotate => "Rotate",
+            GizmoMode::Scale => "Scale",
+        }
+    }
+}
+
+/**
+ * Increments transforms snap to: a world grid spacing for positions, an
+ * angle in radians for turns and a step for scale factors. A zero step
+ * leaves that part free
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Snap {
+    pub grid: f32,
+    pub angle: f32,
+    pub scale: f32,
+}
+
+impl Default for Snap {
+    fn default() -> Self {
+        Self {
+            grid: 0.25,
+            angle: 15f32.to_radians(),
+            scale: 0.1,
+        }
+    }
+}
+
+/**
+ * `value` rounded to the nearest multiple of `step`, or as is for a zero step
+ */
+pub fn round_to(value: f32, step: f32) -> f32 {
+    if step > 0.0 {
+        (value / step).round() * step
+    } else {
+        value
+    }
+}
+
+/**
+ * A grabbable part of the gizmo, by axis index (0 = X, 1 = Y, 2 = Z)
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Handle {
+    // Moves or scales along the axis
+    Axis(usize),
+    // Moves in the plane the axis is the normal of
+    Plane(usize),
+    // Turns about the axis
+    Ring(usize),
+}
+
+/**
+ * A manipulator placed on an object: arrows and plane squares to move it,
+ * rings to turn it about the world axes, or axes with cube tips to scale it
+ * along its own. It is sized by its distance from `eye` so it looks the
+ * same at any zoom
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Gizmo {
+    pub mode: GizmoMode,
+    pub origin: Vec3,
+    // World axes, or the object's own when scaling
+    pub axes: [Vec3; 3],
+    pub size: f32,
+}
+
+impl Gizmo {
+    pub fn new(mode: GizmoMode, transform: &Transform, eye: Vec3) -> Self {
+        let axes = match mode {
+            GizmoMode::Scale => {
+                let m = transform.rotation_matrix();
+                [0, 1, 2].map(|axis| [m[axis * 4], m[axis * 4 + 1], m[axis * 4 + 2]])
+            }
+            _ => [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+        };
+        let distance = math::length(math::sub(transform.translation, eye));
+        Self {
+            mode,
+            origin: transform.translation,
+            axes,
+            size: distance * SCREEN_SIZE,
+        }
+    }
+
+    fn handles(&self) -> Vec<Handle> {
+        let axes = 0..3;
+        match self.mode {
+            GizmoMode::Translate => axes
+                .clone()
+                .map(Handle::Axis)
+                .chain(axes.map(Handle::Plane))
+                .collect(),
+            GizmoMode::Rotate => axes.map(Handle::Ring).collect(),
+            GizmoMode::Scale => axes.map(Handle::Axis).collect(),
+        }
+    }
+
+    /**
+     * The handle `ray` passes nearest the eye, within grabbing distance
+     */
+    pub fn pick(&self, ray: &Ray) -> Option<Handle> {
+        self.handles()
+            .into_iter()
+            .filter_map(|handle| Some((handle, self.hit(handle, ray)?)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(handle, _)| handle)
+    }
+
+    // Distance along `ray` to where it grabs `handle`
+    fn hit(&self, handle: Handle, ray: &Ray) -> Option<f32> {
+        let tolerance = self.size * TOLERANCE;
+        match handle {
+            Handle::Axis(axis) => {
+                let along = self.axis_param(axis, ray)?.clamp(0.0, self.size);
+                let point = math::add(self.origin, math::scale(self.axes[axis], along));
+                let t = math::dot(math::sub(point, ray.origin), ray.direction);
+                let miss = math::length(math::sub(ray.at(t), point));
+                (t >= 0.0 && miss <= tolerance).then_some(t)
+            }
+            Handle::Plane(axis) => {
+                let t = ray.intersect_plane(self.origin, self.axes[axis])?;
+                let offset = math::sub(ray.at(t), self.origin);
+                let inside = [1, 2].iter().all(|turn| {
+                    let along = math::dot(offset, self.axes[(axis + turn) % 3]);
+                    (PLANE_MIN * self.size..=PLANE_MAX * self.size).contains(&along)
+                });
+                inside.then_some(t)
+            }
+            Handle::Ring(axis) => {
+                let t = ray.intersect_plane(self.origin, self.axes[axis])?;
+                let radius = math::length(math::sub(ray.at(t), self.origin));
+                ((radius - self.size).abs() <= tolerance).then_some(t)
+            }
+        }
+    }
+
+    /**
+     * Where along axis `axis` through the origin `ray` passes closest, or
+     * `None` when they are parallel
+     */
+    fn axis_param(&self, axis: usize, ray: &Ray) -> Option<f32> {
+        let direction = self.axes[axis];
+        let w = math::sub(self.origin, ray.origin);
+        let b = math::dot(direction, ray.direction);
+        let denom = 1.0 - b * b;
+        if denom < 1e-6 {
+            return None;
+        }
+        Some((b * math::dot(ray.direction, w) - math::dot(direction, w)) / denom)
+    }
+
+    /**
+     * Where `ray` meets the plane a plane or ring handle moves in
+     */
+    fn plane_point(&self, axis: usize, ray: &Ray) -> Option<Vec3> {
+        Some(ray.at(ray.intersect_plane(self.origin, self.axes[axis])?))
+    }
+
+    /**
+     * Line segments drawing the gizmo, with `highlighted` in a brighter color
+     */
+    pub fn lines(&self, highlighted: Option<Handle>) -> Vec<(Vec3, Vec3, [f32; 3])> {
+        let point = |pairs: &[(usize, f32)]| {
+            pairs.iter().fold(self.origin, |p, &(axis, amount)| {
+                math::add(p, math::scale(self.axes[axis], amount * self.size))
+            })
+        };
+        let mut lines = Vec::new();
+        for handle in self.handles() {
+            let color = if highlighted == Some(handle) {
+                HIGHLIGHT_COLOR
+            } else {
+                match handle {
+                    Handle::Axis(axis) | Handle::Plane(axis) | Handle::Ring(axis) => {
+                        AXIS_COLORS[axis]
+                    }
+                }
+            };
+            match handle {
+                Handle::Axis(axis) => {
+                    lines.push((self.origin, point(&[(axis, 1.0)]), color));
+                    if self.mode == GizmoMode::Scale {
+                        // A cube on the tip, edges along the gizmo's axes
+                        let corner = |bits: usize| {
+                            let mut pairs = vec![(axis, 1.0)];
+                            for corner_axis in 0..3 {
+                                let outward = bits & (1 << corner_axis) != 0;
+                                let sign = if outward { 1.0 } else { -1.0 };
+                                pairs.push((corner_axis, sign * TIP_SIZE));
+                            }
+                            point(&pairs)
+                        };
+                        for bit in [1, 2, 4] {
+                            for i in (0..8).filter(|i| i & bit == 0) {
+                                lines.push((corner(i), corner(i | bit), color));
+                            }
+                        }
+                    }
+                }
+                Handle::Plane(axis) => {
+                    let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+                    let corners = [
+                        point(&[(u, PLANE_MIN), (v, PLANE_MIN)]),
+                        point(&[(u, PLANE_MAX), (v, PLANE_MIN)]),
+                        point(&[(u, PLANE_MAX), (v, PLANE_MAX)]),
+                        point(&[(u, PLANE_MIN), (v, PLANE_MAX)]),
+                    ];
+                    for i in 0..4 {
+                        lines.push((corners[i], corners[(i + 1) % 4], color));
+                    }
+                }
+                Handle::Ring(axis) => {
+                    let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+                    let ring_point = |i: usize| {
+                        let (s, c) = (i as f32 * TAU / RING_SEGMENTS as f32).sin_cos();
+                        point(&[(u, c), (v, s)])
+                    };
+                    for i in 0..RING_SEGMENTS {
+                        lines.push((ring_point(i), ring_point(i + 1), color));
+                    }
+                }
+            }
+        }
+        lines
+    }
+
+    /**
+     * Start dragging `handle` of this gizmo, placed on `transform`, from
+     * where `ray` grabbed it. `None` if the ray runs parallel to what the
+     * handle moves along
+     */
+    pub fn begin_drag(
+        &self,
+        handle: Handle,
+        transform: &Transform,
+        ray: &Ray,
+    ) -> Option<GizmoDrag> {
+        let grab = self.grab(handle, ray)?;
+        Some(GizmoDrag {
+            gizmo: *self,
+            handle,
+            start: transform.clone(),
+            grab,
+        })
+    }
+
+    // What a drag measures from: a distance along an axis, or a point on a plane
+    fn grab(&self, handle: Handle, ray: &Ray) -> Option<Grab> {
+        match handle {
+            Handle::Axis(axis) => self.axis_param(axis, ray).map(Grab::Along),
+            Handle::Plane(axis) | Handle::Ring(axis) => {
+                self.plane_point(axis, ray).map(Grab::Point)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Grab {
+    Along(f32),
+    Point(Vec3),
+}
+
+/**
+ * A handle being dragged: turns the cursor's ray into a new transform,
+ * always measured from where the drag started so errors don't accumulate
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct GizmoDrag {
+    gizmo: Gizmo,
+    handle: Handle,
+    start: Transform,
+    grab: Grab,
+}
+
+impl GizmoDrag {
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
+    /**
+     * The transform with the handle dragged to `ray`, or `None` while the
+     * ray runs parallel to what the handle moves along. With `snap`, moved
+     * coordinates land on the grid, turns go in whole angle steps from the
+     * start, and a stretched axis's scale lands on a whole scale step
+     */
+    pub fn update(&self, ray: &Ray, snap: Option<&Snap>) -> Option<Transform> {
+        let round = |value: f32, step: fn(&Snap) -> f32| match snap {
+            Some(snap) => round_to(value, step(snap)),
+            None => value,
+        };
+        let gizmo = &self.gizmo;
+        let mut transform = self.start.clone();
+        match (gizmo.grab(self.handle, ray)?, self.grab, self.handle) {
+            (Grab::Along(now), Grab::Along(start), Handle::Axis(axis)) => match gizmo.mode {
+                GizmoMode::Scale => {
+                    if start.abs() < 1e-6 {
+                        return None;
+                    }
+                    let scale = self.start.scale[axis] * now / start;
+                    transform.scale[axis] = round(scale, |snap| snap.scale).max(MIN_SCALE);
+                }
+                _ => {
+                    let moved = math::scale(gizmo.axes[axis], now - start);
+                    transform.translation = math::add(self.start.translation, moved);
+                    // Translate axes are the world's, so this is one coordinate
+                    transform.translation[axis] =
+                        round(transform.translation[axis], |snap| snap.grid);
+                }
+            },
+            (Grab::Point(now), Grab::Point(start), Handle::Plane(axis)) => {
+                let moved = math::sub(now, start);
+                transform.translation = math::add(self.start.translation, moved);
+                for turn in [1, 2] {
+                    let coordinate = &mut transform.translation[(axis + turn) % 3];
+                    *coordinate = round(*coordinate, |snap| snap.grid);
+                }
+            }
+            (Grab::Point(now), Grab::Point(start), Handle::Ring(axis)) => {
+                let from = math::sub(start, gizmo.origin);
+                let to = math::sub(now, gizmo.origin);
+                let normal = gizmo.axes[axis];
+                let angle = math::dot(math::cross(from, to), normal).atan2(math::dot(from, to));
+                let angle = round(angle, |snap| snap.angle);
+                // The rotation matrices turn clockwise for positive angles
+                let turn = match axis {
+                    0 => math::rotation_matrix_x(-angle),
+                    1 => math::rotation_matrix_y(-angle),
+                    _ => math::rotation_matrix_z(-angle),
+                };
+                let rotation = math::multiply(&turn, &self.start.rotation_matrix());
+                transform.rotation = math::euler_angles(&rotation);
+            }
+            _ => return None,
+        }
+        Some(transform)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: Vec3, expected: Vec3) {
+        let error = math::length(math::sub(actual, expected));
+        assert!(error < 1e-3, "{:?} != {:?}", actual, expected);
+    }
+
+    // Looking down -Z from 10 units away, so the gizmo is 1.8 long
+    fn placed(mode: GizmoMode) -> (Gizmo, Transform) {
+        let transform = Transform::default();
+        (Gizmo::new(mode, &transform, [0.0, 0.0, 10.0]), transform)
+    }
+
+    fn ray_through(x: f32, y: f32) -> Ray {
+        Ray::new([x, y, 10.0], [0.0, 0.0, -1.0])
+    }
+
+    #[test]
+    fn picks_the_handle_under_the_ray() {
+        let (gizmo, _) = placed(GizmoMode::Translate);
+        assert!((gizmo.size - 1.8).abs() < 1e-5);
+        assert_eq!(gizmo.pick(&ray_through(1.0, 0.05)), Some(Handle::Axis(0)));
+        assert_eq!(gizmo.pick(&ray_through(0.02, 1.5)), Some(Handle::Axis(1)));
+        // The XY square, whose normal is Z
+        assert_eq!(gizmo.pick(&ray_through(0.6, 0.6)), Some(Handle::Plane(2)));
+        assert_eq!(gizmo.pick(&ray_through(1.2, 1.2)), None);
+        // Past the arrow's tip
+        assert_eq!(gizmo.pick(&ray_through(2.5, 0.0)), None);
+
+        let (rings, _) = placed(GizmoMode::Rotate);
+        assert_eq!(rings.pick(&ray_through(0.0, 1.8)), Some(Handle::Ring(2)));
+        assert_eq!(rings.pick(&ray_through(0.0, 1.0)), None);
+    }
+
+    #[test]
+    fn axis_drags_move_only_along_the_axis() {
+        let (gizmo, transform) = placed(GizmoMode::Translate);
+        let drag = gizmo
+            .begin_drag(Handle::Axis(0), &transform, &ray_through(1.0, 0.0))
+            .unwrap();
+        let moved = drag.update(&ray_through(1.5, 0.7), None).unwrap();
+        assert_near(moved.translation, [0.5, 0.0, 0.0]);
+
+        let drag = gizmo
+            .begin_drag(Handle::Plane(2), &transform, &ray_through(0.3, 0.3))
+            .unwrap();
+        let moved = drag.update(&ray_through(1.0, -0.2), None).unwrap();
+        assert_near(moved.translation, [0.7, -0.5, 0.0]);
+    }
+
+    #[test]
+    fn ring_drags_turn_about_the_axis() {
+        let (gizmo, transform) = placed(GizmoMode::Rotate);
+        let drag = gizmo
+            .begin_drag(Handle::Ring(2), &transform, &ray_through(1.8, 0.0))
+            .unwrap();
+        let turned = drag.update(&ray_through(0.0, 1.8), None).unwrap();
+        // A quarter turn counterclockwise seen from +Z takes +X to +Y
+        let x_axis = math::transform_point(&turned.matrix(), [1.0, 0.0, 0.0]);
+        assert_near(x_axis, [0.0, 1.0, 0.0]);
+        assert_near(turned.translation, [0.0; 3]);
+    }
+
+    #[test]
+    fn scale_drags_stretch_along_the_objects_own_axes() {
+        let transform = Transform {
+            rotation: [0.0, 0.0, -std::f32::consts::FRAC_PI_2],
+            scale: [0.5; 3],
+            ..Transform::default()
+        };
+        let gizmo = Gizmo::new(GizmoMode::Scale, &transform, [0.0, 0.0, 10.0]);
+        // The object's X axis points along world +Y once turned
+        assert_near(gizmo.axes[0], [0.0, 1.0, 0.0]);
+        let drag = gizmo
+            .begin_drag(Handle::Axis(0), &transform, &ray_through(0.0, 1.0))
+            .unwrap();
+        let scaled = drag.update(&ray_through(0.0, 2.0), None).unwrap();
+        assert!((scaled.scale[0] - 1.0).abs() < 1e-4);
+        assert_eq!(&scaled.scale[1..], &[0.5, 0.5]);
+        // Dragging through the origin doesn't turn the object inside out
+        let flipped = drag.update(&ray_through(0.0, -1.0), None).unwrap();
+        assert_eq!(flipped.scale[0], MIN_SCALE);
+    }
+
+    #[test]
+    fn snapping_rounds_what_the_drag_changes() {
+        let snap = Snap::default();
+        let (gizmo, mut transform) = placed(GizmoMode::Translate);
+        transform.translation = [0.1, 0.1, 0.0];
+        let drag = gizmo
+            .begin_drag(Handle::Axis(0), &transform, &ray_through(1.0, 0.0))
+            .unwrap();
+        let moved = drag.update(&ray_through(1.37, 0.0), Some(&snap)).unwrap();
+        // Only the dragged coordinate snaps
+        assert_near(moved.translation, [0.5, 0.1, 0.0]);
+
+        let (rings, transform) = placed(GizmoMode::Rotate);
+        let drag = rings
+            .begin_drag(Handle::Ring(2), &transform, &ray_through(1.8, 0.0))
+            .unwrap();
+        // 40 degrees rounds to 45
+        let (s, c) = 40f32.to_radians().sin_cos();
+        let turned = drag
+            .update(&ray_through(1.8 * c, 1.8 * s), Some(&snap))
+            .unwrap();
+        let x_axis = math::transform_point(&turned.matrix(), [1.0, 0.0, 0.0]);
+        let (s, c) = 45f32.to_radians().sin_cos();
+        assert_near(x_axis, [c, s, 0.0]);
+
+        let (scaler, transform) = placed(GizmoMode::Scale);
+        let drag = scaler
+            .begin_drag(Handle::Axis(1), &transform, &ray_through(0.0, 1.0))
+            .unwrap();
+        let scaled = drag.update(&ray_through(0.0, 1.33), Some(&snap)).unwrap();
+        assert!((scaled.scale[1] - 1.3).abs() < 1e-4);
+    }
+
+    #[test]
+    fn zero_steps_leave_values_alone() {
+        assert_eq!(round_to(0.37, 0.25), 0.25);
+        assert_eq!(round_to(-0.4, 0.25), -0.5);
+        assert_eq!(round_to(0.37, 0.0), 0.37);
+    }
+}
@@ -0,0 +1,100 @@
+use web_sys::{WebGl2RenderingContext, WebGlBuffer};
+
+use crate::fence::FenceRing;
+use crate::gl_util::GlResource;
+use crate::gpu_memory::{Allocation, Kind};
+use crate::texture::Texture;
+
+// Buffers used in turn; by the time one comes round again the GPU has almost
+// always copied out of it
+const RING: usize = 3;
+
+/**
+ * Texture uploads staged through a `PIXEL_UNPACK_BUFFER`. Writing the pixels
+ * into a buffer only queues a copy; the driver moves them into the texture
+ * when it gets to the `texSubImage2D`, instead of the call blocking until a
+ * texture still in use by queued draws is free. Each buffer only grows, and
+ * is fenced after its copy: a buffer the GPU is done with is overwritten in
+ * place, one it is still copying from is orphaned first
+ */
+pub struct PixelUnpackBuffer {
+    buffers: [WebGlBuffer; RING],
+    fences: FenceRing,
+    // Allocated size of each buffer, in bytes
+    sizes: [usize; RING],
+    allocation: Allocation,
+}
+
+impl PixelUnpackBuffer {
+    pub fn new(gl: &WebGl2RenderingContext) -> Result<Self, String> {
+        let create = || {
+            gl.create_buffer()
+                .ok_or_else(|| "Unable to create pixel unpack buffer".to_string())
+        };
+        Ok(Self {
+            buffers: [create()?, create()?, create()?],
+            fences: FenceRing::new(RING),
+            sizes: [0; RING],
+            allocation: Allocation::new(Kind::Buffer, 0),
+        })
+    }
+
+    /**
+     * Replace the `(width, height)` rectangle of `texture` with its top-left
+     * corner at `(x, y)` by RGBA8 `pixels`, rows top to bottom
+     */
+    pub fn upload(
+        &mut self,
+        gl: &WebGl2RenderingContext,
+        texture: &Texture,
+        (x, y): (i32, i32),
+        (width, height): (i32, i32),
+        pixels: &[u8],
+    ) -> Result<(), String> {
+        let len = (width.max(0) * height.max(0) * 4) as usize;
+        if pixels.len() < len {
+            return Err(format!(
+                "Expected {} bytes of pixels, got {}",
+                len,
+                pixels.len()
+            ));
+        }
+
+        let (slot, idle) = self.fences.acquire(gl)?;
+        gl.bind_buffer(
+            WebGl2RenderingContext::PIXEL_UNPACK_BUFFER,
+            Some(&self.buffers[slot]),
+        );
+        if len > self.sizes[slot] || !idle {
+            if !idle {
+                tracing::trace!(target: "renderer", "Unpack buffer {} still in use, orphaning it", slot);
+            }
+            self.sizes[slot] = self.sizes[slot].max(len);
+            self.allocation.resize(self.sizes.iter().sum());
+            gl.buffer_data_with_i32(
+                WebGl2RenderingContext::PIXEL_UNPACK_BUFFER,
+                self.sizes[slot] as i32,
+                WebGl2RenderingContext::STREAM_DRAW,
+            );
+        }
+        gl.buffer_sub_data_with_i32_and_u8_array(
+            WebGl2RenderingContext::PIXEL_UNPACK_BUFFER,
+            0,
+            &pixels[..len],
+        );
+        let result = texture.upload_from_unpack_buffer(gl, x, y, width, height, 0);
+        // Left bound, every other upload would read from this buffer
+        gl.bind_buffer(WebGl2RenderingContext::PIXEL_UNPACK_BUFFER, None);
+        result?;
+        self.fences.release(gl, slot)
+    }
+}
+
+impl GlResource for PixelUnpackBuffer {
+    fn delete(&self, gl: &WebGl2RenderingContext) {
+        for buffer in &self.buffers {
+            buffer.delete(gl);
+        }
+        self.fences.delete(gl);
+    }
+}
@@ -0,0 +1,125 @@
+use web_sys::WebGl2RenderingContext;
+
+/**
+ * Component types a mesh's vertex attributes are stored as on the GPU
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VertexFormat {
+    // 32-bit floats throughout: 24 bytes per vertex
+    #[default]
+    Float,
+    // Half-float positions and normalized 8-bit colors: 12 bytes per vertex.
+    // Halves keep ~3 significant digits, plenty for props a few units across
+    Compact,
+}
+
+/**
+ * How one attribute is stored, for `vertexAttribPointer`
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AttributeFormat {
+    // GL component type
+    pub kind: u32,
+    // Integers are mapped to 0..1 (unsigned) or -1..1 (signed) in the shader
+    pub normalized: bool,
+    // Size of one attribute value, padded to a multiple of 4 bytes
+    pub bytes: i32,
+}
+
+impl VertexFormat {
+    pub fn position(self) -> AttributeFormat {
+        match self {
+            Self::Float => AttributeFormat {
+                kind: WebGl2RenderingContext::FLOAT,
+                normalized: false,
+                bytes: 12,
+            },
+            Self::Compact => AttributeFormat {
+                kind: WebGl2RenderingContext::HALF_FLOAT,
+                normalized: false,
+                bytes: 8,
+            },
+        }
+    }
+
+    pub fn color(self) -> AttributeFormat {
+        match self {
+            Self::Float => AttributeFormat {
+                kind: WebGl2RenderingContext::FLOAT,
+                normalized: false,
+                bytes: 12,
+            },
+            Self::Compact => AttributeFormat {
+                kind: WebGl2RenderingContext::UNSIGNED_BYTE,
+                normalized: true,
+                bytes: 4,
+            },
+        }
+    }
+
+    /**
+     * Append one xyz position in this format
+     */
+    pub fn encode_position(self, position: &[f32], out: &mut Vec<u8>) {
+        match self {
+            Self::Float => position.iter().for_each(|v| out.extend(v.to_le_bytes())),
+            Self::Compact => {
+                position
+                    .iter()
+                    .for_each(|&v| out.extend(f32_to_f16(v).to_le_bytes()));
+                // Padding keeps the next attribute 4-byte aligned
+                out.extend([0, 0]);
+            }
+        }
+    }
+
+    /**
+     * Append one rgb color in this format
+     */
+    pub fn encode_color(self, color: &[f32], out: &mut Vec<u8>) {
+        match self {
+            Self::Float => color.iter().for_each(|v| out.extend(v.to_le_bytes())),
+            Self::Compact => {
+                out.extend(color.iter().map(|&v| unorm8(v)));
+                out.push(u8::MAX);
+            }
+        }
+    }
+}
+
+/**
+ * Nearest IEEE 754 half-precision float, as its bit pattern. Out of range
+ * values become infinity, tiny ones subnormals or zero
+ */
+pub fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let mantissa = bits & 0x7f_ffff;
+    if (bits >> 23) & 0xff == 0xff {
+        // Infinity stays infinity, NaN stays NaN
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+    if exponent >= 31 {
+        sign | 0x7c00
+    } else if exponent <= 0 {
+        // Subnormal: shift the implicit leading 1 into the mantissa
+        let shift = 14 - exponent;
+        if shift > 24 {
+            return sign;
+        }
+        let full = mantissa | 0x80_0000;
+        sign | ((full + (1 << (shift - 1))) >> shift) as u16
+    } else {
+        let half = sign | ((exponent as u16) << 10) | (mantissa >> 13) as u16;
+        // Round to nearest; a carry correctly bumps the exponent
+        half + ((mantissa >> 12) & 1) as u16
+    }
+}
+
+/**
+ * 0..1 to a normalized unsigned byte
+ */
+pub fn unorm8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
@@ -0,0 +1,150 @@
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    HtmlMediaElement, HtmlVideoElement, MediaStream, MediaStreamConstraints, MediaStreamTrack,
+    WebGl2RenderingContext,
+};
+
+use crate::texture::Texture;
+
+// HTMLMediaElement.HAVE_CURRENT_DATA: a frame is available to draw
+const HAVE_CURRENT_DATA: u16 = 2;
+
+/**
+ * Offscreen, muted, looping `<video>` whose current frame is copied into a
+ * GL texture whenever playback has moved on
+ */
+pub struct VideoTexture {
+    video: HtmlVideoElement,
+    texture: Texture,
+    // Playback position of the frame last uploaded
+    uploaded_time: Option<f64>,
+}
+
+impl VideoTexture {
+    pub fn new(gl: &WebGl2RenderingContext) -> Result<Self, String> {
+        let document = web_sys::window()
+            .and_then(|window| window.document())
+            .ok_or_else(|| "No document".to_string())?;
+        let video = document
+            .create_element("video")
+            .map_err(|_| "Unable to create video element".to_string())?
+            .dyn_into::<HtmlVideoElement>()
+            .map_err(|_| "Unable to create video element".to_string())?;
+        // Muted inline playback is allowed to start without a user gesture
+        video.set_muted(true);
+        video.set_loop(true);
+        video.set_autoplay(true);
+        let _ = video.set_attribute("playsinline", "");
+        // Remote videos must be CORS-enabled to be usable as textures
+        video.set_cross_origin(Some("anonymous"));
+        let texture =
+            Texture::empty(gl).ok_or_else(|| "Unable to create video texture".to_string())?;
+
+        Ok(Self {
+            video,
+            texture,
+            uploaded_time: None,
+        })
+    }
+
+    /**
+     * Start playing `url` (a remote URL or a blob URL from a local file)
+     */
+    pub fn play_url(&mut self, url: &str) {
+        self.media().set_src(url);
+        self.start();
+    }
+
+    /**
+     * Start playing a live stream, e.g. from `getUserMedia`
+     */
+    pub fn play_stream(&mut self, stream: &MediaStream) {
+        let media = self.media();
+        let _ = media.remove_attribute("src");
+        media.set_src_object(Some(stream));
+        self.start();
+    }
+
+    /**
+     * Pause and release the current source
+     */
+    pub fn stop(&mut self) {
+        let media = self.media();
+        let _ = media.pause();
+        media.set_src_object(None);
+        let _ = media.remove_attribute("src");
+        media.load();
+        self.uploaded_time = None;
+    }
+
+    /**
+     * The texture, refreshed with the latest frame. `None` until a frame is available
+     */
+    pub fn texture(&mut self, gl: &WebGl2RenderingContext) -> Option<&Texture> {
+        let media = self.media();
+        if media.ready_state() < HAVE_CURRENT_DATA {
+            return self.uploaded_time.map(|_| &self.texture);
+        }
+        let time = media.current_time();
+        if self.uploaded_time != Some(time) && self.texture.upload_video(gl, &self.video).is_some()
+        {
+            self.uploaded_time = Some(time);
+        }
+        self.uploaded_time.map(|_| &self.texture)
+    }
+
+    fn start(&mut self) {
+        self.uploaded_time = None;
+        // If playback is refused the video simply never becomes ready
+        let _ = self.media().play();
+    }
+
+    fn media(&self) -> &HtmlMediaElement {
+        &self.video
+    }
+}
+
+/**
+ * Ask for camera access and resolve to its stream; errors are readable messages
+ */
+pub async fn open_webcam() -> Result<MediaStream, String> {
+    let window = web_sys::window().ok_or_else(|| "No window".to_string())?;
+    // `mediaDevices` only exists in secure contexts (https or localhost)
+    let devices = window
+        .navigator()
+        .media_devices()
+        .map_err(|_| "Camera access requires https or localhost".to_string())?;
+    let constraints = MediaStreamConstraints::new();
+    constraints.set_video(&JsValue::TRUE);
+    constraints.set_audio(&JsValue::FALSE);
+    let promise = devices
+        .get_user_media_with_constraints(&constraints)
+        .map_err(|_| "Camera access requires https or localhost".to_string())?;
+    let stream = JsFuture::from(promise).await.map_err(|err| {
+        let name = js_sys::Reflect::get(&err, &"name".into())
+            .ok()
+            .and_then(|name| name.as_string())
+            .unwrap_or_default();
+        match name.as_str() {
+            "NotAllowedError" => "Camera permission was denied".to_string(),
+            "NotFoundError" => "No camera was found".to_string(),
+            "NotReadableError" => "The camera is in use by another application".to_string(),
+            _ => format!("Camera unavailable ({})", name),
+        }
+    })?;
+    stream
+        .dyn_into::<MediaStream>()
+        .map_err(|_| "Camera returned no stream".to_string())
+}
+
+/**
+ * Stop every track so the browser releases the device (and its indicator light)
+ */
+pub fn stop_tracks(stream: &MediaStream) {
+    for track in stream.get_tracks().iter() {
+        if let Ok(track) = track.dyn_into::<MediaStreamTrack>() {
+            track.stop();
+        }
+    }
+}
@@ -0,0 +1,313 @@
+use naga::{Binding, ScalarKind, TypeInner};
+
+use crate::glsl::{self, Interface, ShaderStage};
+
+// The WGSL types uniforms and vertex attributes can have
+const TYPES: [&str; 15] = [
+    "f32",
+    "i32",
+    "u32",
+    "vec2<f32>",
+    "vec3<f32>",
+    "vec4<f32>",
+    "vec2<i32>",
+    "vec3<i32>",
+    "vec4<i32>",
+    "vec2<u32>",
+    "vec3<u32>",
+    "vec4<u32>",
+    "mat2x2<f32>",
+    "mat3x3<f32>",
+    "mat4x4<f32>",
+];
+
+/**
+ * A vertex shader input, fed from a vertex buffer
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VertexAttribute {
+    pub name: String,
+    pub location: u32,
+    // WGSL type, such as `vec3<f32>`
+    pub wgsl_type: &'static str,
+}
+
+/**
+ * Where a GLSL uniform ended up in the uniform buffer at binding 0
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UniformField {
+    pub name: String,
+    pub offset: usize,
+    pub size: usize,
+    // WGSL type, such as `mat4x4<f32>`
+    pub wgsl_type: &'static str,
+}
+
+/**
+ * A vertex and fragment shader pair translated into a WGSL module per stage,
+ * with the entry points `vs_main` and `fs_main`, and the layout a WebGPU
+ * pipeline needs to feed them. Every non-sampler uniform is packed into one
+ * uniform buffer at group 0, binding 0; each sampler becomes a texture and a
+ * sampler at the next two bindings, in declaration order
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WgslProgram {
+    pub vertex: String,
+    pub fragment: String,
+    pub attributes: Vec<VertexAttribute>,
+    pub uniforms: Vec<UniformField>,
+    // Bytes of uniform buffer, padded to 16
+    pub uniform_size: usize,
+    // Sampler uniform names, in binding order
+    pub textures: Vec<String>,
+}
+
+/**
+ * Translate a GLSL ES 3.00 program into WGSL, with `defines` set for its
+ * `#ifdef`s the way `ShaderLibrary` prepends them. Each stage goes through
+ * `glsl::to_vulkan` and naga, the same as the build script's validation, so
+ * anything that passes the build translates. The vertex stage also maps
+ * clip-space depth from GL's −w..w to WebGPU's 0..w. WebGPU only draws
+ * one-pixel points, so WGSL has no point size or point coordinates:
+ * `gl_PointSize` lines are dropped and `gl_PointCoord` is the pixel's centre
+ */
+pub fn translate(vertex: &str, fragment: &str, defines: &[&str]) -> Result<WgslProgram, String> {
+    let interface = Interface::new(&[
+        (ShaderStage::Vertex, vertex),
+        (ShaderStage::Fragment, fragment),
+    ]);
+    // Blanked rather than removed, so error lines still match
+    let vertex: String = vertex
+        .lines()
+        .map(|line| {
+            if line.trim_start().starts_with("gl_PointSize") {
+                "\n".to_string()
+            } else {
+                format!("{}\n", line)
+            }
+        })
+        .collect();
+    let vertex = glsl::wrap_main(
+        &vertex,
+        "gl_Position.z = (gl_Position.z + gl_Position.w) * 0.5;",
+    );
+    let fragment = glsl::replace_identifier(fragment, "gl_PointCoord", "vec2(0.5)");
+    let [vertex, fragment] = [
+        (vertex.as_str(), ShaderStage::Vertex, "vs_main"),
+        (fragment.as_str(), ShaderStage::Fragment, "fs_main"),
+    ]
+    .map(|(source, stage, entry_point)| {
+        let name = match stage {
+            ShaderStage::Vertex => "vertex",
+            _ => "fragment",
+        };
+        let (mut module, info) = glsl::parse(source, stage, &interface, defines)
+            .map_err(|errors| format!("{} shader: {}", name, errors.join("; ")))?;
+        for entry in module.entry_points.iter_mut() {
+            entry.name = entry_point.to_string();
+        }
+        let source =
+            naga::back::wgsl::write_string(&module, &info, naga::back::wgsl::WriterFlags::empty())
+                .map_err(|err| format!("{} shader: {}", name, err))?;
+        Ok::<_, String>((module, source))
+    });
+    let ((vertex_module, vertex), (_, fragment)) = (vertex?, fragment?);
+
+    let attributes = vertex_module
+        .entry_points
+        .iter()
+        .flat_map(|entry| &entry.function.arguments)
+        .filter_map(|argument| {
+            let Some(Binding::Location { location, .. }) = argument.binding else {
+                return None;
+            };
+            Some(VertexAttribute {
+                name: argument.name.clone()?,
+                location,
+                wgsl_type: type_name(&vertex_module.types[argument.ty].inner)?,
+            })
+        })
+        .collect();
+    let (uniforms, uniform_size) = uniform_layout(&vertex_module, &interface)?;
+    Ok(WgslProgram {
+        vertex,
+        fragment,
+        attributes,
+        uniforms,
+        uniform_size,
+        textures: interface
+            .samplers
+            .iter()
+            .map(|sampler| sampler.name.clone())
+            .collect(),
+    })
+}
+
+/**
+ * Fields of the uniform block, named as the GLSL declares them, at the
+ * offsets WGSL's layout rules give them. naga's WGSL output drops the
+ * block's std140 offsets, so these are what the shader actually reads
+ */
+fn uniform_layout(
+    module: &naga::Module,
+    interface: &Interface,
+) -> Result<(Vec<UniformField>, usize), String> {
+    let block = module
+        .global_variables
+        .iter()
+        .find(|(_, global)| global.space == naga::AddressSpace::Uniform);
+    let Some((_, block)) = block else {
+        return Ok((Vec::new(), 0));
+    };
+    let TypeInner::Struct { ref members, .. } = module.types[block.ty].inner else {
+        return Err("The uniform block isn't a struct".to_string());
+    };
+    let mut layouter = naga::proc::Layouter::default();
+    layouter
+        .update(module.to_ctx())
+        .map_err(|err| format!("Unable to lay out uniforms: {}", err))?;
+
+    let mut fields = Vec::new();
+    let mut offset = 0;
+    for (member, declaration) in members.iter().zip(&interface.uniforms) {
+        let layout = layouter[member.ty];
+        offset = layout.alignment.round_up(offset);
+        let wgsl_type = type_name(&module.types[member.ty].inner)
+            .ok_or_else(|| format!("Uniform {} has an unsupported type", declaration.name))?;
+        fields.push(UniformField {
+            name: declaration.name.clone(),
+            offset: offset as usize,
+            size: layout.size as usize,
+            wgsl_type,
+        });
+        offset += layout.size;
+    }
+    Ok((fields, (offset as usize).next_multiple_of(16)))
+}
+
+fn type_name(inner: &TypeInner) -> Option<&'static str> {
+    let scalar = |scalar: naga::Scalar| match scalar.kind {
+        ScalarKind::Float => "f32",
+        ScalarKind::Sint => "i32",
+        ScalarKind::Uint => "u32",
+        _ => "",
+    };
+    let name = match *inner {
+        TypeInner::Scalar(s) => scalar(s).to_string(),
+        TypeInner::Vector { size, scalar: s } => format!("vec{}<{}>", size as u8, scalar(s)),
+        TypeInner::Matrix {
+            columns,
+            rows,
+            scalar: s,
+        } => format!("mat{}x{}<{}>", columns as u8, rows as u8, scalar(s)),
+        _ => return None,
+    };
+    TYPES.into_iter().find(|ty| *ty == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VERT: &str = "#version 300 es
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec3 color;
+uniform mat4 modelViewProjection;
+uniform float time;
+out vec3 vColor;
+void main() {
+    float scale = 1.0 + 0.1 * sin(time);
+    gl_Position = modelViewProjection * vec4(position * scale, 1.0);
+    vColor = color;
+}
+";
+
+    const FRAG: &str = "#version 300 es
+precision mediump float;
+in vec3 vColor;
+uniform vec3 tint;
+uniform sampler2D image;
+out vec4 fragColor;
+float luma(vec3 c) {
+    return dot(c, vec3(0.299, 0.587, 0.114));
+}
+void main() {
+    vec3 n = normalize(cross(dFdx(vColor), dFdy(vColor)));
+    n.xy *= n.z > 0.0 ? 0.5 : 2.0;
+    fragColor = vec4(texture(image, n.xy).rgb * tint * luma(vColor), 1.0);
+}
+";
+
+    #[test]
+    fn translates_a_program() {
+        let program = translate(VERT, FRAG, &[]).unwrap();
+        assert!(program.vertex.contains("@vertex \nfn vs_main("));
+        assert!(program.fragment.contains("@fragment \nfn fs_main("));
+        assert!(program
+            .fragment
+            .contains("textureSample(image_texture, image_sampler"));
+        assert!(program.fragment.contains("dpdx("));
+        assert_eq!(
+            program.attributes,
+            vec![
+                VertexAttribute {
+                    name: "position".to_string(),
+                    location: 0,
+                    wgsl_type: "vec3<f32>",
+                },
+                VertexAttribute {
+                    name: "color".to_string(),
+                    location: 1,
+                    wgsl_type: "vec3<f32>",
+                },
+            ]
+        );
+        assert_eq!(program.textures, vec!["image".to_string()]);
+    }
+
+    #[test]
+    fn lays_out_uniforms_like_wgsl() {
+        let program = translate(VERT, FRAG, &[]).unwrap();
+        let layout: Vec<(&str, usize, usize)> = program
+            .uniforms
+            .iter()
+            .map(|field| (field.name.as_str(), field.offset, field.size))
+            .collect();
+        // The vec3 is aligned to 16 bytes, after the float at 64
+        assert_eq!(
+            layout,
+            vec![
+                ("modelViewProjection", 0, 64),
+                ("time", 64, 4),
+                ("tint", 80, 12)
+            ]
+        );
+        assert_eq!(program.uniform_size, 96);
+    }
+
+    #[test]
+    fn reports_errors_with_the_stage_and_line() {
+        let error = translate(VERT, &FRAG.replace("* tint", "* tnit"), &[]).unwrap_err();
+        assert_eq!(error, "fragment shader: 13: Unknown variable: tnit");
+        let error = translate(&VERT.replace("sin(time)", "sin()"), FRAG, &[]).unwrap_err();
+        assert!(error.starts_with("vertex shader: 8: "), "{}", error);
+    }
+
+    #[test]
+    fn translates_every_program_in_each_permutation() {
+        for program in crate::shaders::PROGRAMS {
+            for defines in program.permutations() {
+                if let Err(err) = translate(program.vertex, program.fragment, &defines) {
+                    panic!("{} with {:?}: {}", program.name, defines, err);
+                }
+            }
+        }
+        let material = crate::shaders::program("material").unwrap();
+        for defines in material.permutations() {
+            let program = translate(material.vertex, material.fragment, &defines).unwrap();
+            let sampled = program.fragment.contains("textureSample(baseTexture");
+            assert_eq!(sampled, defines.contains(&"TEXTURE"), "{:?}", defines);
+        }
+    }
+}